@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use std::any::Any;
 use std::net::TcpStream;
+use std::path::PathBuf;
 use std::thread;
 use std::{env, time::Duration};
 use styx_emulator::core::executor::Delta;
@@ -8,8 +9,7 @@ use styx_emulator::core::util::logging::init_logging;
 use styx_emulator::cpu::arch::arm::ArmRegister;
 use styx_emulator::loader::RawLoader;
 use styx_emulator::peripheral_clients::uart::UartClient;
-use styx_emulator::plugins::fuzzer::{FuzzerExecutor, StyxFuzzerConfig};
-use styx_emulator::plugins::styx_trace::StyxTracePlugin;
+use styx_emulator::plugins::fuzzer::{FuzzerExecutor, StyxFuzzerConfig, TriageConfig};
 use styx_emulator::prelude::*;
 use styx_emulator::processors::arm::kinetis21::Kinetis21Builder;
 use styx_emulator::sync::Arc;
@@ -95,10 +95,7 @@ fn pre_fuzzing_setup(proc: &mut ProcessorCore) {
             .tick(
                 proc.cpu.as_mut(),
                 &mut proc.mmu,
-                &Delta {
-                    time: Duration::from_nanos(1000),
-                    count: 1000,
-                },
+                &Delta::from_instructions(1000),
             )
             .unwrap();
         proc.event_controller
@@ -157,6 +154,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Starting emulator");
 
+    // `cargo run -- <crash>` replays and minimizes a crash instead of fuzzing
+    let replay = env::args().nth(1).map(PathBuf::from);
+
     let mut proc = ProcessorBuilder::default()
         .with_builder(Kinetis21Builder::default())
         .with_backend(Backend::Unicorn)
@@ -171,11 +171,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 setup: Box::new(pre_fuzzing_setup),
                 context_restore: Box::new(context_restore),
                 context_save: Box::new(context_save),
+                triage: Some(TriageConfig::default()),
+                replay,
+                replay_report: Box::new(|report| println!("{report}")),
                 ..Default::default()
             },
         ))
         .with_ipc_port(16000)
-        .add_plugin(StyxTracePlugin::new(false, false, false, true))
         .with_loader(RawLoader)
         .with_target_program(get_firmware_path())
         .build()?;
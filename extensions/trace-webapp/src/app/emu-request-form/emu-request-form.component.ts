@@ -88,6 +88,8 @@ export class TargetChoiceModel {
         return "CYCLONE V (" + this.variant + ")";
       case Target.BLACKFIN512:
         return "BLACKFIN (" + this.variant + ")";
+      case Target.MIPS32:
+        return "MIPS32 (" + this.variant + ")";
     }
   }
 
@@ -170,11 +172,13 @@ export class EmuRequestFormComponent implements OnInit, AfterViewInit {
     const TCM_STM32F107 = new TargetChoiceModel(Target.STM32F107);
     const TCM_CYCLONEV = new TargetChoiceModel(Target.CYCLONEV);
     const TCM_BLACKFIN512 = new TargetChoiceModel(Target.BLACKFIN512);
+    const TCM_MIPS32 = new TargetChoiceModel(Target.MIPS32);
     this.targetChoiceModels.push(TCM_KINETIS21);
     this.targetChoiceModels.push(TCM_POWERQUICC);
     this.targetChoiceModels.push(TCM_STM32F107);
     this.targetChoiceModels.push(TCM_CYCLONEV);
     this.targetChoiceModels.push(TCM_BLACKFIN512);
+    this.targetChoiceModels.push(TCM_MIPS32);
   }
 
   // Get a list of ghidra program from typhunix
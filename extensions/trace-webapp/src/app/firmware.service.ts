@@ -26,6 +26,9 @@ export class Variants {
   public static get Bf512(): string {
     return "Bf512";
   }
+  public static get Mips3224kf(): string {
+    return "Mips3224kf";
+  }
 }
 
 @Injectable({
@@ -46,6 +49,8 @@ export class FirmwareService {
         return ArchEnum.ARM;
       case Target.BLACKFIN512:
         return ArchEnum.BLACKFIN;
+      case Target.MIPS32:
+        return ArchEnum.MIPS;
     }
   }
 
@@ -65,6 +70,9 @@ export class FirmwareService {
 
       case Target.BLACKFIN512:
         return Variants.Bf512;
+
+      case Target.MIPS32:
+        return Variants.Mips3224kf;
     }
   }
 
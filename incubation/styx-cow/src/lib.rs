@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: BSD-2-Clause
 #![cfg(target_os = "linux")]
 
-use libc::{c_void, close, dup, ftruncate, memfd_create, mmap, mremap, munmap};
-use libc::{MAP_FAILED, MAP_PRIVATE, MAP_SHARED, MREMAP_MAYMOVE, PROT_READ, PROT_WRITE};
+use libc::{c_void, close, dup, ftruncate, madvise, memfd_create, mmap, mremap, munmap};
+use libc::{
+    MADV_DONTNEED, MAP_FAILED, MAP_PRIVATE, MAP_SHARED, MREMAP_MAYMOVE, PROT_READ, PROT_WRITE,
+};
 use std::io::Error;
 use std::slice;
 use thiserror::Error;
@@ -19,6 +21,8 @@ const NULL: *mut c_void = std::ptr::null_mut();
 pub enum StyxCowError {
     #[error("Creating a copy of a copy is not allowed.")]
     CopyOfCopyError,
+    #[error("Resetting an original is not allowed, only copies can be reset.")]
+    ResetOriginalError,
     #[error("OS error: {0}")]
     OSError(String),
 }
@@ -140,6 +144,26 @@ impl Cow {
         })
     }
 
+    /// Discards every write made to a copy so it matches the original mapping again.
+    ///
+    /// Uses 'madvise' with 'MADV_DONTNEED' to drop the private pages of the copy, the next
+    /// access repopulates them from the original. Only pages written since the copy was made (or
+    /// last reset) were ever copied, so this is cheap when few pages are dirty. The copy keeps
+    /// its address so existing pointers to the data stay valid.
+    ///
+    /// returns an error if called on an original.
+    pub fn reset(&mut self) -> Result<(), StyxCowError> {
+        if !self.is_copy {
+            return Err(StyxCowError::ResetOriginalError);
+        }
+
+        if unsafe { madvise(self.ptr, self.size, MADV_DONTNEED) } == -1 {
+            return Err(Error::last_os_error())?;
+        }
+
+        Ok(())
+    }
+
     pub fn len(&self) -> usize {
         self.size
     }
@@ -148,6 +172,11 @@ impl Cow {
     }
 }
 
+// SAFETY: the mapping is owned by the Cow and only handed out through borrows of it, so it can be
+// moved to and shared between threads like a `Vec<u8>`.
+unsafe impl Send for Cow {}
+unsafe impl Sync for Cow {}
+
 impl Drop for Cow {
     /// unmaps and closes the file
     fn drop(&mut self) {
@@ -231,6 +260,39 @@ mod tests {
         assert_eq!(r2.get_data()[4 * 1024 + 1], 2);
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn reset() {
+        // a reset copy should match the original again, including changes to
+        // the original made after the copy was written to
+        let mut r1 = Cow::new(8 * 1024).unwrap();
+        r1.get_data_mut()[0] = 1;
+
+        let mut r2 = r1.try_clone().unwrap();
+        let ptr = r2.get_data().as_ptr();
+        r2.get_data_mut()[0] = 2;
+        r2.get_data_mut()[4 * 1024] = 2;
+        r1.get_data_mut()[1] = 1;
+
+        assert_eq!(r2.get_data()[1], 0);
+
+        r2.reset().unwrap();
+
+        assert_eq!(r2.get_data(), r1.get_data());
+        assert_eq!(r2.get_data()[0], 1);
+        assert_eq!(r2.get_data()[1], 1);
+        assert_eq!(r2.get_data()[4 * 1024], 0);
+        // the copy is still mapped at the same address
+        assert_eq!(r2.get_data().as_ptr(), ptr);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn reset_original() {
+        let mut r = Cow::new(16).unwrap();
+        assert!(matches!(r.reset(), Err(StyxCowError::ResetOriginalError)));
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     #[should_panic]
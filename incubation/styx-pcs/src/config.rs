@@ -70,9 +70,9 @@ impl AsRef<str> for ProcessorId {
 
 /// A gRPC client that is to be spawned.
 ///
-/// Not used yet :/
+/// Currently a transparent Component Reference to a [`DeviceService`](crate::DeviceService).
 #[derive(Deserialize, Clone, Debug)]
+#[serde(transparent)]
 pub struct SpawnDevice {
-    #[allow(unused)]
-    component_ref: crate::components::SerdeComponentReference,
+    pub component_ref: crate::components::SerdeComponentReference,
 }
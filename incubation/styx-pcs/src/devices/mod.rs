@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use styx_core::errors::UnknownError;
+use tokio::runtime::Handle;
+
+use crate::{
+    components::{Component, ComponentStore, DuplicateId},
+    processor::Processors,
+};
+
+/// SPI device models from styx-devices.
+mod spi;
+
+/// A device service implementation.
+///
+/// Strictly speaking this is a function that *spawns* a client modeling a device on the
+/// provided async runtime. Device services should error on an invalid config schema, invalid
+/// config values, or if the processor is not in the device list.
+///
+/// Use [`ProcessorId`](crate::config::ProcessorId) and [`Processors`] to find and connect to processors.
+pub type DeviceService = fn(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<DeviceHandle, UnknownError>;
+
+inventory::collect!(Component<DeviceService>);
+
+/// Handle to introspect a running device client.
+///
+/// does nothing at the moment but planning to use in the future to start/stop devices
+pub struct DeviceHandle {}
+
+/// Get a populated [`ComponentStore`] of [`DeviceService`]s register via inventory.
+///
+/// Can error if multiple device services have the same id.
+pub fn registered_devices() -> Result<ComponentStore<DeviceService>, DuplicateId> {
+    ComponentStore::populated()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify the styx-devices models are available in the device service list.
+    #[test]
+    fn test_devices_available() {
+        let devices = registered_devices().unwrap();
+        for id in ["AT25HP512", "ADS7866", "RHRDAC121"] {
+            assert!(devices.list().any(|i| i == id), "{id} missing");
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use serde::{de::DeserializeOwned, Deserialize};
+use styx_core::{
+    errors::UnknownError,
+    peripheral_clients::spi::{spawn_spi_device, SPIDevice},
+    prelude::{anyhow, log::debug, Context},
+};
+use styx_devices::{adc::ADS7866, dac::RHRDAC121, eeprom::AT25HP512};
+use tokio::runtime::Handle;
+
+use crate::{
+    config::ProcessorId,
+    devices::{DeviceHandle, DeviceService},
+    processor::Processors,
+};
+
+/// Where a SPI device is connected.
+#[derive(Deserialize)]
+struct SpiDeviceConfig {
+    processor: ProcessorId,
+    port: u32,
+    #[serde(default)]
+    chip_select_id: u32,
+}
+
+#[derive(Deserialize)]
+struct DacConfig {
+    #[serde(flatten)]
+    bus: SpiDeviceConfig,
+    /// Reference voltage.
+    vcc: Option<f64>,
+}
+
+fn parse_config<C: DeserializeOwned>(
+    config: Option<&serde_yaml::Value>,
+) -> Result<C, UnknownError> {
+    serde_yaml::from_value::<C>(config.ok_or(anyhow!("missing config"))?.clone())
+        .with_context(|| "bad config")
+}
+
+/// Connect `device` to the SPI port in `config`.
+fn spawn_device(
+    device: impl SPIDevice + Send + 'static,
+    config: &SpiDeviceConfig,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<DeviceHandle, UnknownError> {
+    let proc = processors
+        .get_processor(&config.processor)
+        .ok_or(anyhow!("processor {} not found", config.processor))?;
+    debug!(
+        "connecting {} to spi port {} of {}",
+        device.get_name(),
+        config.port,
+        config.processor
+    );
+    spawn_spi_device(
+        runtime,
+        proc.addr().clone(),
+        config.port,
+        config.chip_select_id,
+        device,
+    );
+
+    Ok(DeviceHandle {})
+}
+
+const AT25HP512_BUILDER: DeviceService = build_at25hp512;
+const ADS7866_BUILDER: DeviceService = build_ads7866;
+const RHRDAC121_BUILDER: DeviceService = build_rhrdac121;
+
+inventory::submit! {
+    crate::component!("AT25HP512", AT25HP512_BUILDER)
+}
+inventory::submit! {
+    crate::component!("ADS7866", ADS7866_BUILDER)
+}
+inventory::submit! {
+    crate::component!("RHRDAC121", RHRDAC121_BUILDER)
+}
+
+fn build_at25hp512(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<DeviceHandle, UnknownError> {
+    let config: SpiDeviceConfig = parse_config(config)?;
+    spawn_device(AT25HP512::new(), &config, processors, runtime)
+}
+
+fn build_ads7866(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<DeviceHandle, UnknownError> {
+    let config: SpiDeviceConfig = parse_config(config)?;
+    spawn_device(ADS7866::new(), &config, processors, runtime)
+}
+
+fn build_rhrdac121(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<DeviceHandle, UnknownError> {
+    let config: DacConfig = parse_config(config)?;
+    spawn_device(RHRDAC121::new(config.vcc), &config.bus, processors, runtime)
+}
@@ -21,14 +21,18 @@
 //! A Proxy service proxies traffic between several processors (gRPC servers) over a
 //! peripheral protocol (e.g. uart, spit, i2c, etc.).
 //!
-//! This crate currently defines a `uart` proxy service.
+//! This crate currently defines `uart`, `spi`, `i2c` and `ethernet` proxy services.
 //! Users can define their own by defining a function with the [`ProxyService`] signature and calling
 //! [`inventory::submit!()`] on the component instantiated using [`component!()`].
 //!
-//! ## Unimplemented
+//! ## Device Service
 //!
-//! - Spawn clients from styx-devices
-//!   - This is present in the config but not implemented yet
+//! styx-pcs also defines the [`DeviceService`] component.
+//! A device service spawns a client that models a device (e.g. an EEPROM or ADC) on a processor's
+//! bus, these are the `Spawn` entries in the device list.
+//!
+//! This crate currently defines device services for the `styx-devices` SPI models: `AT25HP512`,
+//! `ADS7866` and `RHRDAC121`.
 //!
 
 /// Component logic for register peripheral implementations.
@@ -43,6 +47,10 @@ pub use config::*;
 mod peripherals;
 pub use peripherals::*;
 
+/// Included device client implementations.
+mod devices;
+pub use devices::*;
+
 /// Processors that can be configured to connect to.
 mod processor;
 pub use processor::*;
@@ -52,12 +60,25 @@ use tokio::runtime::Handle;
 
 /// Spawns the Peripheral Component Service on the given runtime
 pub fn start_pcs(config: &PcsConfig, runtime: &Handle) -> Result<(), UnknownError> {
-    // TODO we should spawn local devices below
     // create a list of remote devices (processors/gRPC servers) and a list of local devices
-    let (remote_devices, _spawn_devices) = config.devices.separate();
+    let (remote_devices, spawn_devices) = config.devices.separate();
     let processors = Processors::from_config(remote_devices)?;
     let peripherals =
         peripherals::registered_peripherals().context("could not collect peripherals")?;
+    let devices = devices::registered_devices().context("could not collect devices")?;
+
+    // spawn local devices
+    for device in spawn_devices {
+        let component_ref = &device.component_ref;
+        let device_generator = devices.get(component_ref.id())?;
+        log::info!("spawning device {}", component_ref.id());
+        let _handle = device_generator(
+            component_ref.config().map(|c| &c.config),
+            &processors,
+            runtime,
+        )
+        .with_context(|| format!("could not spawn device `{}`", component_ref.id()))?;
+    }
 
     // spawn proxies
     for proxy in config.connections.iter() {
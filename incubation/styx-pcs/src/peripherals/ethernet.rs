@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use serde::Deserialize;
+use styx_core::{
+    errors::UnknownError,
+    grpc::io::ethernet::{ethernet_port_client::EthernetPortClient, SubscribeRequest},
+    prelude::{
+        anyhow,
+        log::{debug, error, trace},
+        Context,
+    },
+};
+use tokio::runtime::Handle;
+use tokio_stream::StreamExt;
+use tonic::transport::Channel;
+
+use crate::{
+    config::ProcessorId,
+    peripherals::{peripheral_service_handle, ProxyHandle, ProxyService},
+    processor::Processors,
+};
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+enum EthernetDirection {
+    Tx,
+    Rx,
+    Both,
+}
+
+/// Forwards ethernet frames between two processors, acting as a cable between their interfaces.
+#[derive(Deserialize)]
+struct EthernetConfig {
+    from: ProcessorId,
+    to: ProcessorId,
+    direction: EthernetDirection,
+}
+
+/// Ethernet specific processor with an id and Ethernet Client.
+#[derive(Clone, Debug)]
+struct ResolvedProcessor {
+    id: String,
+    client: EthernetPortClient<Channel>,
+}
+
+async fn resolve_connection(
+    id: &ProcessorId,
+    processors: &Processors,
+) -> Result<ResolvedProcessor, UnknownError> {
+    let proc = processors
+        .get_processor(id)
+        .ok_or(anyhow!("processor not found"))?;
+    let client = EthernetPortClient::connect(proc.addr().clone())
+        .await
+        .with_context(|| "could not connect")?;
+    Ok(ResolvedProcessor {
+        id: id.as_ref().to_owned(),
+        client,
+    })
+}
+
+const ETHERNET_BUILDER: ProxyService = build_ethernet;
+
+inventory::submit! {
+    crate::component!("ethernet", ETHERNET_BUILDER)
+}
+
+fn build_ethernet(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<ProxyHandle, UnknownError> {
+    let processors = processors.clone();
+    let ethernet_config =
+        serde_yaml::from_value::<EthernetConfig>(config.ok_or(anyhow!("missing config"))?.clone())
+            .with_context(|| "bad config")?;
+    runtime.spawn(async move { spawn_ethernet(&ethernet_config, &processors).await });
+
+    let (_, handle) = peripheral_service_handle();
+    Ok(handle)
+}
+
+async fn spawn_ethernet(config: &EthernetConfig, processors: &Processors) {
+    let from = resolve_connection(&config.from, processors).await.unwrap();
+    let to = resolve_connection(&config.to, processors).await.unwrap();
+    debug!("from: {from:?}");
+    debug!("to: {to:?}");
+    if matches!(
+        config.direction,
+        EthernetDirection::Tx | EthernetDirection::Both
+    ) {
+        tokio::spawn(build_from(from.clone(), to.clone()));
+    }
+    if matches!(
+        config.direction,
+        EthernetDirection::Rx | EthernetDirection::Both
+    ) {
+        tokio::spawn(build_from(to.clone(), from.clone()));
+    }
+}
+
+async fn build_from(from: ResolvedProcessor, to: ResolvedProcessor) {
+    // for now, panic on error.
+    // shouldn't affect anything as a soft thread
+    single_proxy(from, to).await.unwrap();
+}
+
+/// Spawn a thread that receives frames sent by From and delivers them to To
+async fn single_proxy(from: ResolvedProcessor, to: ResolvedProcessor) -> Result<(), UnknownError> {
+    let mut from_client = from.client.clone();
+    let mut to_client = to.client.clone();
+    debug!("subscribing to the frames of {from:?}");
+    let mut resp = from_client
+        .subscribe(SubscribeRequest {})
+        .await
+        .with_context(|| format!("could not subscribe to ethernet {from:?}"))?
+        .into_inner();
+
+    while let Some(recv) = resp.next().await {
+        let packet = match recv {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+        };
+
+        trace!(
+            "got a {} byte frame from {} to {}",
+            packet.frame.len(),
+            from.id,
+            to.id
+        );
+        to_client.receive(packet).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use serde::Deserialize;
+use styx_core::{
+    errors::UnknownError,
+    grpc::io::i2c::{
+        i2c_packet::Contents, i2c_port_client::I2cPortClient, signal::Sig, I2cPacket,
+        I2cRegistration, Signal, Start,
+    },
+    prelude::{
+        anyhow,
+        log::{debug, error, trace},
+        Context,
+    },
+};
+use tokio::runtime::Handle;
+use tokio_stream::StreamExt;
+use tonic::{transport::Channel, Streaming};
+
+use crate::{
+    config::ProcessorId,
+    peripherals::{peripheral_service_handle, ProxyHandle, ProxyService},
+    processor::Processors,
+};
+
+/// Bridges the I2C bus of the `master` processor to the I2C bus of the `target` processor.
+///
+/// The proxy registers on both buses with `address`. Transactions the master starts with
+/// `address` are forwarded to the target's bus and the data and acks put on the target's bus
+/// during them are returned to the master. Bus implementations must not send a client's own
+/// broadcasts back to it, otherwise packets would bounce between the buses.
+#[derive(Deserialize)]
+struct I2cConfig {
+    master: ProcessorConnection,
+    target: ProcessorConnection,
+    /// Address of the target on the master's bus.
+    address: u32,
+}
+
+#[derive(Deserialize)]
+struct ProcessorConnection {
+    id: ProcessorId,
+    #[serde(default)]
+    bus: u32,
+}
+
+/// I2C specific processor with an id, i2c bus, and I2C Client.
+#[derive(Clone, Debug)]
+struct ResolvedProcessor {
+    id: String,
+    bus: u32,
+    client: I2cPortClient<Channel>,
+}
+
+async fn resolve_connection(
+    connection: &ProcessorConnection,
+    processors: &Processors,
+) -> Result<ResolvedProcessor, UnknownError> {
+    let proc = processors
+        .get_processor(&connection.id)
+        .ok_or(anyhow!("processor not found"))?;
+    let client = I2cPortClient::connect(proc.addr().clone())
+        .await
+        .with_context(|| "could not connect")?;
+    Ok(ResolvedProcessor {
+        id: connection.id.as_ref().to_owned(),
+        bus: connection.bus,
+        client,
+    })
+}
+
+const I2C_BUILDER: ProxyService = build_i2c;
+
+inventory::submit! {
+    crate::component!("i2c", I2C_BUILDER)
+}
+
+fn build_i2c(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<ProxyHandle, UnknownError> {
+    let processors = processors.clone();
+    let i2c_config =
+        serde_yaml::from_value::<I2cConfig>(config.ok_or(anyhow!("missing config"))?.clone())
+            .with_context(|| "bad config")?;
+    runtime.spawn(async move { spawn_i2c(&i2c_config, &processors).await });
+
+    let (_, handle) = peripheral_service_handle();
+    Ok(handle)
+}
+
+async fn spawn_i2c(config: &I2cConfig, processors: &Processors) {
+    let master = resolve_connection(&config.master, processors)
+        .await
+        .unwrap();
+    let target = resolve_connection(&config.target, processors)
+        .await
+        .unwrap();
+    debug!("master: {master:?}");
+    debug!("target: {target:?}");
+    let active = Arc::new(AtomicBool::new(false));
+    tokio::spawn(proxy_task(master_proxy(
+        master.clone(),
+        target.clone(),
+        config.address,
+        active.clone(),
+    )));
+    tokio::spawn(proxy_task(target_proxy(
+        target,
+        master,
+        config.address,
+        active,
+    )));
+}
+
+async fn proxy_task(proxy: impl std::future::Future<Output = Result<(), UnknownError>>) {
+    // for now, panic on error.
+    // shouldn't affect anything as a soft thread
+    proxy.await.unwrap();
+}
+
+/// Where a [TransactionFilter] is in the traffic of the master's bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransactionState {
+    /// Between transactions or in a transaction with another address.
+    Idle,
+    /// Got a start, the next data byte is the address.
+    Started,
+    /// In a transaction with the proxied address.
+    Active,
+}
+
+/// Picks the transactions with `address` out of the traffic of the master's bus.
+///
+/// A start is held back until the address byte after it, both are forwarded when the address
+/// matches and so is everything up to and including the stop.
+#[derive(Debug)]
+struct TransactionFilter {
+    address: u32,
+    state: TransactionState,
+}
+
+impl TransactionFilter {
+    fn new(address: u32) -> Self {
+        Self {
+            address,
+            state: TransactionState::Idle,
+        }
+    }
+
+    /// Is the master in a transaction with the proxied address?
+    fn active(&self) -> bool {
+        self.state == TransactionState::Active
+    }
+
+    /// Packet contents to forward to the target for `contents` seen on the master's bus.
+    fn filter(&mut self, contents: Contents) -> Vec<Contents> {
+        match (&contents, self.state) {
+            (
+                Contents::Sig(Signal {
+                    sig: Some(Sig::Start(_)),
+                }),
+                _,
+            ) => {
+                self.state = TransactionState::Started;
+                Vec::new()
+            }
+            (
+                Contents::Sig(Signal {
+                    sig: Some(Sig::Stop(_)),
+                }),
+                state,
+            ) => {
+                self.state = TransactionState::Idle;
+                if state == TransactionState::Active {
+                    vec![contents]
+                } else {
+                    Vec::new()
+                }
+            }
+            (Contents::Data(data), TransactionState::Started) => {
+                // 7 bit address followed by the read/write bit
+                if data.data >> 1 == self.address {
+                    self.state = TransactionState::Active;
+                    vec![
+                        Contents::Sig(Signal {
+                            sig: Some(Sig::Start(Start {})),
+                        }),
+                        contents,
+                    ]
+                } else {
+                    self.state = TransactionState::Idle;
+                    Vec::new()
+                }
+            }
+            (_, TransactionState::Active) => vec![contents],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Register on the bus of `from` as `address` on behalf of `to`.
+async fn register(
+    from: &ResolvedProcessor,
+    to: &ResolvedProcessor,
+    address: u32,
+) -> Result<Streaming<I2cPacket>, UnknownError> {
+    let mut from_client = from.client.clone();
+    debug!("registering on the bus of {from:?}");
+    Ok(from_client
+        .register_client(I2cRegistration {
+            bus: from.bus,
+            dev_address: address,
+            device_name: format!("styx-pcs {}", to.id),
+        })
+        .await
+        .with_context(|| format!("could not register on i2c bus {from:?}"))?
+        .into_inner())
+}
+
+/// Forward the transactions of the master with `address` to the bus of the target.
+///
+/// `active` is set while the master is in such a transaction.
+async fn master_proxy(
+    master: ResolvedProcessor,
+    target: ResolvedProcessor,
+    address: u32,
+    active: Arc<AtomicBool>,
+) -> Result<(), UnknownError> {
+    let mut target_client = target.client.clone();
+    let mut resp = register(&master, &target, address).await?;
+    let mut filter = TransactionFilter::new(address);
+
+    while let Some(recv) = resp.next().await {
+        let packet = match recv {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+        };
+        let Some(contents) = packet.contents else {
+            continue;
+        };
+
+        let forwarded = filter.filter(contents);
+        active.store(filter.active(), Ordering::Release);
+        for contents in forwarded {
+            trace!("i2c {contents:?} from {} to {}", master.id, target.id);
+            target_client
+                .broadcast(I2cPacket {
+                    bus: target.bus,
+                    contents: Some(contents),
+                })
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Forward the data and acks of the target to the bus of the master while `active` is set.
+async fn target_proxy(
+    target: ResolvedProcessor,
+    master: ResolvedProcessor,
+    address: u32,
+    active: Arc<AtomicBool>,
+) -> Result<(), UnknownError> {
+    let mut master_client = master.client.clone();
+    let mut resp = register(&target, &master, address).await?;
+
+    while let Some(recv) = resp.next().await {
+        let packet = match recv {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+        };
+        // the master proxy forwards the starts and stops of the master
+        let Some(
+            contents @ (Contents::Data(_)
+            | Contents::Sig(Signal {
+                sig: Some(Sig::Ack(_)),
+            })),
+        ) = packet.contents
+        else {
+            continue;
+        };
+        if !active.load(Ordering::Acquire) {
+            continue;
+        }
+
+        trace!("i2c {contents:?} from {} to {}", target.id, master.id);
+        master_client
+            .broadcast(I2cPacket {
+                bus: master.bus,
+                contents: Some(contents),
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use styx_core::grpc::io::i2c::{Ack, Data, Stop};
+
+    use super::*;
+
+    fn data(data: u32) -> Contents {
+        Contents::Data(Data { data })
+    }
+
+    fn signal(sig: Sig) -> Contents {
+        Contents::Sig(Signal { sig: Some(sig) })
+    }
+
+    /// Only the transactions with the proxied address are forwarded.
+    #[test]
+    fn test_transaction_filter() {
+        let mut filter = TransactionFilter::new(0x48);
+
+        // write to another device
+        assert!(filter.filter(signal(Sig::Start(Start {}))).is_empty());
+        assert!(filter.filter(data(0x4d << 1)).is_empty());
+        assert!(filter.filter(data(0x01)).is_empty());
+        assert!(!filter.active());
+        assert!(filter.filter(signal(Sig::Stop(Stop {}))).is_empty());
+
+        // read from the proxied device
+        assert!(filter.filter(signal(Sig::Start(Start {}))).is_empty());
+        assert_eq!(
+            filter.filter(data((0x48 << 1) | 1)),
+            vec![signal(Sig::Start(Start {})), data((0x48 << 1) | 1)]
+        );
+        assert!(filter.active());
+        assert_eq!(
+            filter.filter(signal(Sig::Ack(Ack {}))),
+            vec![signal(Sig::Ack(Ack {}))]
+        );
+        assert_eq!(
+            filter.filter(signal(Sig::Stop(Stop {}))),
+            vec![signal(Sig::Stop(Stop {}))]
+        );
+        assert!(!filter.active());
+
+        // traffic outside of a transaction
+        assert!(filter.filter(data(0x48 << 1)).is_empty());
+    }
+}
@@ -7,6 +7,12 @@ use crate::{
     processor::Processors,
 };
 
+/// Ethernet peripheral service.
+mod ethernet;
+/// I2C peripheral service.
+mod i2c;
+/// SPI peripheral service.
+mod spi;
 /// Uart peripheral service.
 mod uart;
 
@@ -60,4 +66,13 @@ mod tests {
         let peripherals = registered_peripherals().unwrap();
         assert!(peripherals.list().any(|i| i == "uart"));
     }
+
+    /// Verify the bus proxies are available in the peripheral service list.
+    #[test]
+    fn test_bus_proxies_available() {
+        let peripherals = registered_peripherals().unwrap();
+        for id in ["spi", "i2c", "ethernet"] {
+            assert!(peripherals.list().any(|i| i == id), "{id} missing");
+        }
+    }
 }
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use serde::Deserialize;
+use styx_core::{
+    errors::UnknownError,
+    grpc::io::spi::{
+        spi_port_client::SpiPortClient, MasterPacket, PortRequest, SlaveChipSelectPacket,
+        SlavePacket,
+    },
+    prelude::{
+        anyhow,
+        log::{debug, error, trace},
+        Context,
+    },
+};
+use tokio::runtime::Handle;
+use tokio_stream::StreamExt;
+use tonic::transport::Channel;
+
+use crate::{
+    config::ProcessorId,
+    peripherals::{peripheral_service_handle, ProxyHandle, ProxyService},
+    processor::Processors,
+};
+
+/// Connects the `master` SPI port of one processor to a `slave` SPI port of another.
+///
+/// MOSI data and chip select changes for `chip_select_id` are forwarded to the slave and MISO
+/// data from the slave is returned to the master with `chip_select_id`.
+#[derive(Deserialize)]
+struct SpiConfig {
+    master: ProcessorConnection,
+    slave: ProcessorConnection,
+    #[serde(default)]
+    chip_select_id: u32,
+}
+
+#[derive(Deserialize)]
+struct ProcessorConnection {
+    id: ProcessorId,
+    port: u32,
+}
+
+/// SPI specific processor with an id, spi port, and SPI Client.
+#[derive(Clone, Debug)]
+struct ResolvedProcessor {
+    id: String,
+    port: u32,
+    client: SpiPortClient<Channel>,
+}
+
+async fn resolve_connection(
+    connection: &ProcessorConnection,
+    processors: &Processors,
+) -> Result<ResolvedProcessor, UnknownError> {
+    let proc = processors
+        .get_processor(&connection.id)
+        .ok_or(anyhow!("processor not found"))?;
+    let client = SpiPortClient::connect(proc.addr().clone())
+        .await
+        .with_context(|| "could not connect")?;
+    Ok(ResolvedProcessor {
+        id: connection.id.as_ref().to_owned(),
+        port: connection.port,
+        client,
+    })
+}
+
+const SPI_BUILDER: ProxyService = build_spi;
+
+inventory::submit! {
+    crate::component!("spi", SPI_BUILDER)
+}
+
+fn build_spi(
+    config: Option<&serde_yaml::Value>,
+    processors: &Processors,
+    runtime: &Handle,
+) -> Result<ProxyHandle, UnknownError> {
+    let processors = processors.clone();
+    let spi_config =
+        serde_yaml::from_value::<SpiConfig>(config.ok_or(anyhow!("missing config"))?.clone())
+            .with_context(|| "bad config")?;
+    runtime.spawn(async move { spawn_spi(&spi_config, &processors).await });
+
+    let (_, handle) = peripheral_service_handle();
+    Ok(handle)
+}
+
+async fn spawn_spi(config: &SpiConfig, processors: &Processors) {
+    let master = resolve_connection(&config.master, processors)
+        .await
+        .unwrap();
+    let slave = resolve_connection(&config.slave, processors).await.unwrap();
+    debug!("master: {master:?}");
+    debug!("slave: {slave:?}");
+    let chip_select_id = config.chip_select_id;
+
+    tokio::spawn(proxy_task(mosi_proxy(
+        master.clone(),
+        slave.clone(),
+        chip_select_id,
+    )));
+    tokio::spawn(proxy_task(chip_select_proxy(
+        master.clone(),
+        slave.clone(),
+        chip_select_id,
+    )));
+    tokio::spawn(proxy_task(miso_proxy(master, slave, chip_select_id)));
+}
+
+async fn proxy_task(proxy: impl std::future::Future<Output = Result<(), UnknownError>>) {
+    // for now, panic on error.
+    // shouldn't affect anything as a soft thread
+    proxy.await.unwrap();
+}
+
+fn port_request(port: u32) -> PortRequest {
+    PortRequest {
+        port,
+        device_name: "styx-pcs".to_owned(),
+    }
+}
+
+/// Forward master MOSI data to the slave.
+async fn mosi_proxy(
+    master: ResolvedProcessor,
+    slave: ResolvedProcessor,
+    chip_select_id: u32,
+) -> Result<(), UnknownError> {
+    let mut master_client = master.client.clone();
+    let mut slave_client = slave.client.clone();
+    let mut resp = master_client
+        .master_subscribe(port_request(master.port))
+        .await
+        .with_context(|| format!("could not subscribe to spi mosi of {master:?}"))?
+        .into_inner();
+
+    while let Some(recv) = resp.next().await {
+        let packet = match recv {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+        };
+        if packet.chip_select_id != chip_select_id {
+            continue;
+        }
+
+        trace!("mosi {:?} from {} to {}", packet.data, master.id, slave.id);
+        slave_client
+            .slave_receive(SlavePacket {
+                port: slave.port,
+                data: packet.data,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Forward master chip select changes to the slave.
+async fn chip_select_proxy(
+    master: ResolvedProcessor,
+    slave: ResolvedProcessor,
+    chip_select_id: u32,
+) -> Result<(), UnknownError> {
+    let mut master_client = master.client.clone();
+    let mut slave_client = slave.client.clone();
+    let mut resp = master_client
+        .master_chip_select_subscribe(port_request(master.port))
+        .await
+        .with_context(|| format!("could not subscribe to spi chip select of {master:?}"))?
+        .into_inner();
+
+    while let Some(recv) = resp.next().await {
+        let packet = match recv {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+        };
+        if packet.chip_select_id != chip_select_id {
+            continue;
+        }
+
+        trace!(
+            "chip select {} from {} to {}",
+            packet.chip_select,
+            master.id,
+            slave.id
+        );
+        slave_client
+            .slave_chip_select_receive(SlaveChipSelectPacket {
+                port: slave.port,
+                chip_select: packet.chip_select,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Forward slave MISO data to the master.
+async fn miso_proxy(
+    master: ResolvedProcessor,
+    slave: ResolvedProcessor,
+    chip_select_id: u32,
+) -> Result<(), UnknownError> {
+    let mut master_client = master.client.clone();
+    let mut slave_client = slave.client.clone();
+    let mut resp = slave_client
+        .slave_subscribe(port_request(slave.port))
+        .await
+        .with_context(|| format!("could not subscribe to spi miso of {slave:?}"))?
+        .into_inner();
+
+    while let Some(recv) = resp.next().await {
+        let packet = match recv {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+        };
+
+        trace!("miso {:?} from {} to {}", packet.data, slave.id, master.id);
+        master_client
+            .master_receive(MasterPacket {
+                port: master.port,
+                chip_select_id,
+                data: packet.data,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::{pin::Pin, time::Duration};
+
+use styx_core::{
+    grpc::io::spi::{
+        spi_port_server::{SpiPort, SpiPortServer},
+        Empty, MasterChipSelectPacket, MasterPacket, PortRequest, SlaveChipSelectPacket,
+        SlavePacket,
+    },
+    prelude::*,
+    util::logging::init_logging,
+};
+use styx_pcs::{start_pcs, PcsConfig};
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{async_trait, service::RoutesBuilder, transport::Server};
+
+type SubscribeStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send + 'static>>;
+
+/// Test SPI master that selects chip select 0 and forwards the MISO packets it receives to a
+/// channel.
+struct SpiMaster {
+    incoming_packet_send: mpsc::Sender<MasterPacket>,
+}
+
+fn pending<T: Send + 'static>() -> SubscribeStream<T> {
+    Box::pin(tokio_stream::pending::<Result<T, tonic::Status>>())
+}
+
+#[async_trait]
+impl SpiPort for SpiMaster {
+    type MasterChipSelectSubscribeStream = SubscribeStream<MasterChipSelectPacket>;
+    type MasterSubscribeStream = SubscribeStream<MasterPacket>;
+    type SlaveSubscribeStream = SubscribeStream<SlavePacket>;
+
+    async fn slave_chip_select_receive(
+        &self,
+        _request: tonic::Request<SlaveChipSelectPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn master_chip_select_subscribe(
+        &self,
+        request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::MasterChipSelectSubscribeStream>, tonic::Status> {
+        let packet = MasterChipSelectPacket {
+            port: request.into_inner().port,
+            chip_select_id: 0,
+            chip_select: true,
+        };
+        // the device stays selected
+        let packets = tokio_stream::iter([Ok(packet)]).chain(pending());
+        Ok(tonic::Response::new(Box::pin(packets)))
+    }
+
+    async fn slave_receive(
+        &self,
+        _request: tonic::Request<SlavePacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn master_subscribe(
+        &self,
+        _request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::MasterSubscribeStream>, tonic::Status> {
+        Ok(tonic::Response::new(pending()))
+    }
+
+    async fn slave_subscribe(
+        &self,
+        _request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::SlaveSubscribeStream>, tonic::Status> {
+        Ok(tonic::Response::new(pending()))
+    }
+
+    // copies packets into a channel for inspection later
+    async fn master_receive(
+        &self,
+        request: tonic::Request<MasterPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let packet = request.into_inner();
+        log::info!(
+            "<gRPC> SPI port {} received data: {:?}",
+            packet.port,
+            packet.data
+        );
+        // the device keeps sending while selected, later packets are not needed
+        let _ = self.incoming_packet_send.try_send(packet);
+        Ok(tonic::Response::new(Empty {}))
+    }
+}
+
+/// A `Spawn` device connects to the SPI port of its processor and answers while selected.
+#[test]
+fn test_spawn_device() {
+    init_logging();
+    let (send, mut spi_recv) = mpsc::channel(100);
+    let mut routes = RoutesBuilder::default();
+    routes.add_service(SpiPortServer::new(SpiMaster {
+        incoming_packet_send: send,
+    }));
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let tcp = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port = tcp.local_addr().unwrap().port();
+
+    let yaml = format!(
+        r#"
+        devices:
+            - !Remote
+              id: mcu
+              endpoint: http://127.0.0.1:{port}
+            - !Spawn
+              id: ADS7866
+              config:
+                  processor: mcu
+                  port: 2
+        connections: []
+    "#
+    );
+
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp))
+            .await
+            .unwrap()
+    });
+
+    let config = serde_yaml::from_str::<PcsConfig>(&yaml).unwrap();
+    start_pcs(&config, runtime.handle()).unwrap();
+
+    runtime.block_on(async {
+        tokio::select! {
+            packet = spi_recv.recv() => {
+                let packet = packet.unwrap();
+                assert_eq!(2, packet.port);
+                assert_eq!(0, packet.chip_select_id);
+                // the 12 bit sample starts with 4 zero bits
+                assert_eq!(0, packet.data[0] & 0xf0);
+            }
+            _ = tokio::time::sleep(Duration::from_millis(10000)) => {
+                panic!("timed out")
+            }
+        }
+    });
+}
+
+/// A `Spawn` device on a processor missing from the device list is an error.
+#[test]
+fn test_spawn_device_unknown_processor() {
+    let yaml = r#"
+        devices:
+            - !Spawn
+              id: ADS7866
+              config:
+                  processor: mcu
+                  port: 0
+        connections: []
+    "#;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap();
+    let config = serde_yaml::from_str::<PcsConfig>(yaml).unwrap();
+    assert!(start_pcs(&config, runtime.handle()).is_err());
+}
@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::{pin::Pin, time::Duration};
+
+use styx_core::{
+    grpc::io::ethernet::{
+        ethernet_port_server::{EthernetPort, EthernetPortServer},
+        Empty, EthernetPacket, SubscribeRequest,
+    },
+    prelude::*,
+    util::logging::init_logging,
+};
+use styx_pcs::{start_pcs, PcsConfig};
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{async_trait, service::RoutesBuilder, transport::Server};
+
+type SubscribeStream =
+    Pin<Box<dyn Stream<Item = Result<EthernetPacket, tonic::Status>> + Send + 'static>>;
+
+/// Test ethernet interface that sends one frame and forwards the frames it receives to a channel.
+struct EthernetInterface {
+    frame: Vec<u8>,
+    incoming_frame_send: mpsc::Sender<Vec<u8>>,
+}
+
+impl EthernetInterface {
+    fn new(frame: &[u8]) -> (Self, mpsc::Receiver<Vec<u8>>) {
+        let (send, recv) = mpsc::channel(100);
+        (
+            EthernetInterface {
+                frame: frame.to_vec(),
+                incoming_frame_send: send,
+            },
+            recv,
+        )
+    }
+}
+
+#[async_trait]
+impl EthernetPort for EthernetInterface {
+    type SubscribeStream = SubscribeStream;
+
+    // copies frames into a channel for inspection later
+    async fn receive(
+        &self,
+        request: tonic::Request<EthernetPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let packet = request.into_inner();
+        log::info!(
+            "<gRPC> ethernet received a {} byte frame",
+            packet.frame.len()
+        );
+        self.incoming_frame_send.send(packet.frame).await.unwrap();
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn subscribe(
+        &self,
+        _request: tonic::Request<SubscribeRequest>,
+    ) -> Result<tonic::Response<Self::SubscribeStream>, tonic::Status> {
+        let packet = EthernetPacket {
+            frame: self.frame.clone(),
+            crc: 0,
+        };
+        // keep the stream open after the frame like a real interface
+        let frames =
+            tokio_stream::iter([Ok::<_, tonic::Status>(packet)]).chain(tokio_stream::pending());
+        Ok(tonic::Response::new(
+            Box::pin(frames) as Self::SubscribeStream
+        ))
+    }
+}
+
+/// Frames sent by each interface reach the other one.
+#[test]
+fn test_ethernet() {
+    init_logging();
+    let frame_a = [[0xff; 6].as_slice(), &[0xa; 6], &[0x08, 0x00], &[0xaa; 46]].concat();
+    let frame_b = [[0xff; 6].as_slice(), &[0xb; 6], &[0x08, 0x00], &[0xbb; 46]].concat();
+    let (interface_a, mut recv_a) = EthernetInterface::new(&frame_a);
+    let (interface_b, mut recv_b) = EthernetInterface::new(&frame_b);
+    let mut routes_a = RoutesBuilder::default();
+    routes_a.add_service(EthernetPortServer::new(interface_a));
+    let mut routes_b = RoutesBuilder::default();
+    routes_b.add_service(EthernetPortServer::new(interface_b));
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let tcp_a = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port_a = tcp_a.local_addr().unwrap().port();
+    let tcp_b = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port_b = tcp_b.local_addr().unwrap().port();
+
+    let yaml = format!(
+        r#"
+        devices:
+            - !Remote
+              id: a
+              endpoint: http://127.0.0.1:{port_a}
+            - !Remote
+              id: b
+              endpoint: http://127.0.0.1:{port_b}
+        connections:
+            - id: ethernet
+              config:
+                  direction: Both
+                  from: a
+                  to: b
+    "#
+    );
+
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes_a.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp_a))
+            .await
+            .unwrap()
+    });
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes_b.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp_b))
+            .await
+            .unwrap()
+    });
+
+    let config = serde_yaml::from_str::<PcsConfig>(&yaml).unwrap();
+    start_pcs(&config, runtime.handle()).unwrap();
+
+    let run = async { (recv_b.recv().await.unwrap(), recv_a.recv().await.unwrap()) };
+
+    runtime.block_on(async {
+        tokio::select! {
+            (received_b, received_a) = run => {
+                assert_eq!(frame_a, received_b);
+                assert_eq!(frame_b, received_a);
+            }
+            _ = tokio::time::sleep(Duration::from_millis(10000)) => {
+                panic!("timed out")
+            }
+        }
+    });
+}
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::{pin::Pin, time::Duration};
+
+use styx_core::{
+    grpc::io::i2c::{
+        i2c_packet::Contents,
+        i2c_port_server::{I2cPort, I2cPortServer},
+        signal::Sig,
+        Ack, Data, Empty, I2cPacket, I2cRegistration, Signal, Start, Stop,
+    },
+    prelude::*,
+    util::logging::init_logging,
+};
+use styx_pcs::{start_pcs, PcsConfig};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::Stream;
+use tonic::{async_trait, service::RoutesBuilder, transport::Server};
+
+type RegisterClientStream =
+    Pin<Box<dyn Stream<Item = Result<I2cPacket, tonic::Status>> + Send + 'static>>;
+
+/// Address of the proxied device.
+const ADDRESS: u32 = 0x48;
+/// Address of another device on the master's bus.
+const OTHER_ADDRESS: u32 = 0x4d;
+
+/// Test I2C bus that puts the packets of a channel on the bus and forwards the packets
+/// broadcast by clients to another channel.
+///
+/// With `ack_data` set the bus acks every data byte broadcast on it, like an addressed device.
+struct I2cBus {
+    bus_send: broadcast::Sender<Contents>,
+    incoming_send: mpsc::Sender<Contents>,
+    ack_data: bool,
+}
+
+fn data(data: u32) -> Contents {
+    Contents::Data(Data { data })
+}
+
+fn signal(sig: Sig) -> Contents {
+    Contents::Sig(Signal { sig: Some(sig) })
+}
+
+#[async_trait]
+impl I2cPort for I2cBus {
+    type RegisterClientStream = RegisterClientStream;
+
+    // copies packets into a channel for inspection later
+    async fn broadcast(
+        &self,
+        request: tonic::Request<I2cPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let packet = request.into_inner();
+        log::info!("<gRPC> I2C{} got {:?}", packet.bus, packet.contents);
+        let contents = packet.contents.unwrap();
+        if self.ack_data && matches!(contents, Contents::Data(_)) {
+            self.bus_send.send(signal(Sig::Ack(Ack {}))).unwrap();
+        }
+        self.incoming_send.send(contents).await.unwrap();
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn register_client(
+        &self,
+        request: tonic::Request<I2cRegistration>,
+    ) -> Result<tonic::Response<Self::RegisterClientStream>, tonic::Status> {
+        let registration = request.into_inner();
+        log::info!(
+            "<gRPC> {} registered at {:#x} on I2C{}",
+            registration.device_name,
+            registration.dev_address,
+            registration.bus
+        );
+        let bus = registration.bus;
+        let stream = tokio_stream::wrappers::BroadcastStream::new(self.bus_send.subscribe());
+        let packets = async_stream::stream! {
+            for await contents in stream {
+                yield Ok(I2cPacket { bus, contents: Some(contents.unwrap()) });
+            }
+        };
+        Ok(tonic::Response::new(
+            Box::pin(packets) as Self::RegisterClientStream
+        ))
+    }
+}
+
+/// Only the transaction with the proxied address crosses the proxy, the target's acks come back.
+#[test]
+fn test_i2c() {
+    init_logging();
+    let (master_bus, _) = broadcast::channel(100);
+    let (master_send, mut master_recv) = mpsc::channel(100);
+    let (target_bus, _) = broadcast::channel(100);
+    let (target_send, mut target_recv) = mpsc::channel(100);
+    let mut routes_master = RoutesBuilder::default();
+    routes_master.add_service(I2cPortServer::new(I2cBus {
+        bus_send: master_bus.clone(),
+        incoming_send: master_send,
+        ack_data: false,
+    }));
+    let mut routes_target = RoutesBuilder::default();
+    routes_target.add_service(I2cPortServer::new(I2cBus {
+        bus_send: target_bus.clone(),
+        incoming_send: target_send,
+        ack_data: true,
+    }));
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let tcp_master = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port_master = tcp_master.local_addr().unwrap().port();
+    let tcp_target = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port_target = tcp_target.local_addr().unwrap().port();
+
+    let yaml = format!(
+        r#"
+        devices:
+            - !Remote
+              id: mcu
+              endpoint: http://127.0.0.1:{port_master}
+            - !Remote
+              id: sensor
+              endpoint: http://127.0.0.1:{port_target}
+        connections:
+            - id: i2c
+              config:
+                  address: {ADDRESS}
+                  master:
+                      id: mcu
+                  target:
+                      id: sensor
+    "#
+    );
+
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes_master.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp_master))
+            .await
+            .unwrap()
+    });
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes_target.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp_target))
+            .await
+            .unwrap()
+    });
+
+    let config = serde_yaml::from_str::<PcsConfig>(&yaml).unwrap();
+    start_pcs(&config, runtime.handle()).unwrap();
+
+    let run = async {
+        // wait for the proxy to register on both buses
+        while master_bus.receiver_count() == 0 || target_bus.receiver_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // a write to another device, then a write to the proxied one
+        for contents in [
+            signal(Sig::Start(Start {})),
+            data(OTHER_ADDRESS << 1),
+            data(0x01),
+            signal(Sig::Stop(Stop {})),
+            signal(Sig::Start(Start {})),
+            data(ADDRESS << 1),
+            data(0x42),
+        ] {
+            master_bus.send(contents).unwrap();
+        }
+
+        let mut target_packets = Vec::new();
+        for _ in 0..3 {
+            target_packets.push(target_recv.recv().await.unwrap());
+        }
+        assert_eq!(
+            vec![signal(Sig::Start(Start {})), data(ADDRESS << 1), data(0x42)],
+            target_packets
+        );
+
+        // the target acked the address and the data byte
+        for _ in 0..2 {
+            assert_eq!(signal(Sig::Ack(Ack {})), master_recv.recv().await.unwrap());
+        }
+
+        master_bus.send(signal(Sig::Stop(Stop {}))).unwrap();
+        assert_eq!(
+            signal(Sig::Stop(Stop {})),
+            target_recv.recv().await.unwrap()
+        );
+    };
+
+    runtime.block_on(async {
+        tokio::select! {
+            _ = run => {}
+            _ = tokio::time::sleep(Duration::from_millis(10000)) => {
+                panic!("timed out")
+            }
+        }
+    });
+}
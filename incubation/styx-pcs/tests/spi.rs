@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::{pin::Pin, time::Duration};
+
+use styx_core::{
+    grpc::io::spi::{
+        spi_port_server::{SpiPort, SpiPortServer},
+        Empty, MasterChipSelectPacket, MasterPacket, PortRequest, SlaveChipSelectPacket,
+        SlavePacket,
+    },
+    prelude::*,
+    util::logging::init_logging,
+};
+use styx_pcs::{start_pcs, PcsConfig};
+use tokio::{runtime::Runtime, sync::mpsc};
+use tokio_stream::Stream;
+use tonic::{async_trait, service::RoutesBuilder, transport::Server};
+
+type SubscribeStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send + 'static>>;
+
+const MESSAGE: &str = "TURN IT UP THROW IT DOWN";
+const REPLY: &str = "PUMP IT UP";
+
+/// Test SPI master that sends [`MESSAGE`] on MOSI, split over two chip selects, selects and
+/// deselects both chip selects and forwards the MISO packets it receives to a channel.
+struct SpiMaster {
+    incoming_packet_send: mpsc::Sender<MasterPacket>,
+}
+
+/// Test SPI slave that sends [`REPLY`] on MISO and forwards the MOSI data and chip select changes
+/// it receives to channels.
+struct SpiSlave {
+    incoming_data_send: mpsc::Sender<u8>,
+    chip_select_send: mpsc::Sender<bool>,
+}
+
+fn pending<T: Send + 'static>() -> SubscribeStream<T> {
+    Box::pin(tokio_stream::pending::<Result<T, tonic::Status>>())
+}
+
+#[async_trait]
+impl SpiPort for SpiMaster {
+    type MasterChipSelectSubscribeStream = SubscribeStream<MasterChipSelectPacket>;
+    type MasterSubscribeStream = SubscribeStream<MasterPacket>;
+    type SlaveSubscribeStream = SubscribeStream<SlavePacket>;
+
+    async fn slave_chip_select_receive(
+        &self,
+        _request: tonic::Request<SlaveChipSelectPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn master_chip_select_subscribe(
+        &self,
+        request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::MasterChipSelectSubscribeStream>, tonic::Status> {
+        let port = request.into_inner().port;
+        // changes of chip select 1 are not for the proxied slave
+        let packets = [(1, true), (0, true), (1, false), (0, false)].map(
+            move |(chip_select_id, chip_select)| {
+                Ok::<_, tonic::Status>(MasterChipSelectPacket {
+                    port,
+                    chip_select_id,
+                    chip_select,
+                })
+            },
+        );
+        Ok(tonic::Response::new(Box::pin(tokio_stream::iter(packets))))
+    }
+
+    async fn slave_receive(
+        &self,
+        _request: tonic::Request<SlavePacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn master_subscribe(
+        &self,
+        request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::MasterSubscribeStream>, tonic::Status> {
+        let port = request.into_inner().port;
+        // bytes for chip select 1 are not for the proxied slave
+        let packets = MESSAGE.bytes().flat_map(move |b| {
+            [1, 0].map(|chip_select_id| {
+                Ok::<_, tonic::Status>(MasterPacket {
+                    port,
+                    chip_select_id,
+                    data: vec![if chip_select_id == 0 { b } else { b'!' }],
+                })
+            })
+        });
+        Ok(tonic::Response::new(Box::pin(tokio_stream::iter(packets))))
+    }
+
+    async fn slave_subscribe(
+        &self,
+        _request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::SlaveSubscribeStream>, tonic::Status> {
+        Ok(tonic::Response::new(pending()))
+    }
+
+    // copies packets into a channel for inspection later
+    async fn master_receive(
+        &self,
+        request: tonic::Request<MasterPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let packet = request.into_inner();
+        log::info!(
+            "<gRPC> SPI port {} received data: {:?}",
+            packet.port,
+            packet.data
+        );
+        self.incoming_packet_send.send(packet).await.unwrap();
+        Ok(tonic::Response::new(Empty {}))
+    }
+}
+
+#[async_trait]
+impl SpiPort for SpiSlave {
+    type MasterChipSelectSubscribeStream = SubscribeStream<MasterChipSelectPacket>;
+    type MasterSubscribeStream = SubscribeStream<MasterPacket>;
+    type SlaveSubscribeStream = SubscribeStream<SlavePacket>;
+
+    // copies chip select changes into a channel for inspection later
+    async fn slave_chip_select_receive(
+        &self,
+        request: tonic::Request<SlaveChipSelectPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let packet = request.into_inner();
+        log::info!(
+            "<gRPC> SPI port {} chip select: {}",
+            packet.port,
+            packet.chip_select
+        );
+        self.chip_select_send
+            .send(packet.chip_select)
+            .await
+            .unwrap();
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn master_chip_select_subscribe(
+        &self,
+        _request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::MasterChipSelectSubscribeStream>, tonic::Status> {
+        Ok(tonic::Response::new(pending()))
+    }
+
+    // copies data into a channel for inspection later
+    async fn slave_receive(
+        &self,
+        request: tonic::Request<SlavePacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let packet = request.into_inner();
+        log::info!(
+            "<gRPC> SPI port {} received data: {:?}",
+            packet.port,
+            packet.data
+        );
+        for byte in packet.data {
+            self.incoming_data_send.send(byte).await.unwrap();
+        }
+        Ok(tonic::Response::new(Empty {}))
+    }
+
+    async fn master_subscribe(
+        &self,
+        _request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::MasterSubscribeStream>, tonic::Status> {
+        Ok(tonic::Response::new(pending()))
+    }
+
+    async fn slave_subscribe(
+        &self,
+        request: tonic::Request<PortRequest>,
+    ) -> Result<tonic::Response<Self::SlaveSubscribeStream>, tonic::Status> {
+        let port = request.into_inner().port;
+        let packets = REPLY.bytes().map(move |b| {
+            Ok::<_, tonic::Status>(SlavePacket {
+                port,
+                data: vec![b],
+            })
+        });
+        Ok(tonic::Response::new(Box::pin(tokio_stream::iter(packets))))
+    }
+
+    async fn master_receive(
+        &self,
+        _request: tonic::Request<MasterPacket>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        Ok(tonic::Response::new(Empty {}))
+    }
+}
+
+/// Receiving ends of the channels of the test master and slave.
+struct Received {
+    master_packets: mpsc::Receiver<MasterPacket>,
+    slave_data: mpsc::Receiver<u8>,
+    slave_chip_selects: mpsc::Receiver<bool>,
+}
+
+/// Serve a test master and slave and proxy chip select 0 of master port 1 to slave port 0.
+fn start() -> (Runtime, Received) {
+    init_logging();
+    let (master_send, master_packets) = mpsc::channel(100);
+    let (data_send, slave_data) = mpsc::channel(100);
+    let (chip_select_send, slave_chip_selects) = mpsc::channel(100);
+    let mut routes_master = RoutesBuilder::default();
+    routes_master.add_service(SpiPortServer::new(SpiMaster {
+        incoming_packet_send: master_send,
+    }));
+    let mut routes_slave = RoutesBuilder::default();
+    routes_slave.add_service(SpiPortServer::new(SpiSlave {
+        incoming_data_send: data_send,
+        chip_select_send,
+    }));
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let tcp_master = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port_master = tcp_master.local_addr().unwrap().port();
+    let tcp_slave = runtime
+        .block_on(tokio::net::TcpListener::bind("0.0.0.0:0"))
+        .unwrap();
+    let port_slave = tcp_slave.local_addr().unwrap().port();
+
+    let yaml = format!(
+        r#"
+        devices:
+            - !Remote
+              id: mcu
+              endpoint: http://127.0.0.1:{port_master}
+            - !Remote
+              id: sensor
+              endpoint: http://127.0.0.1:{port_slave}
+        connections:
+            - id: spi
+              config:
+                  chip_select_id: 0
+                  master:
+                      id: mcu
+                      port: 1
+                  slave:
+                      id: sensor
+                      port: 0
+    "#
+    );
+
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes_master.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp_master))
+            .await
+            .unwrap()
+    });
+    runtime.spawn(async {
+        Server::builder()
+            .add_routes(routes_slave.routes())
+            .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(tcp_slave))
+            .await
+            .unwrap()
+    });
+
+    let config = serde_yaml::from_str::<PcsConfig>(&yaml).unwrap();
+    start_pcs(&config, runtime.handle()).unwrap();
+
+    (
+        runtime,
+        Received {
+            master_packets,
+            slave_data,
+            slave_chip_selects,
+        },
+    )
+}
+
+/// Run `future` on `runtime`, panicking if it takes too long.
+fn run_with_timeout<T>(runtime: &Runtime, future: impl std::future::Future<Output = T>) -> T {
+    runtime.block_on(async {
+        tokio::select! {
+            value = future => value,
+            _ = tokio::time::sleep(Duration::from_millis(10000)) => {
+                panic!("timed out")
+            }
+        }
+    })
+}
+
+/// MOSI data for the proxied chip select reaches the slave.
+#[test]
+fn test_spi() {
+    let (runtime, mut received) = start();
+
+    let buf = run_with_timeout(&runtime, async {
+        let mut buf = String::new();
+        while let Some(recv_byte) = received.slave_data.recv().await {
+            buf.push(recv_byte.into());
+            if buf.len() == MESSAGE.len() {
+                break;
+            }
+        }
+        buf
+    });
+    assert_eq!(MESSAGE, buf);
+}
+
+/// MISO data from the slave reaches the master tagged with the proxied chip select.
+#[test]
+fn test_spi_miso() {
+    let (runtime, mut received) = start();
+
+    let buf = run_with_timeout(&runtime, async {
+        let mut buf = String::new();
+        while let Some(packet) = received.master_packets.recv().await {
+            assert_eq!(1, packet.port);
+            assert_eq!(0, packet.chip_select_id);
+            buf.extend(packet.data.into_iter().map(char::from));
+            if buf.len() == REPLY.len() {
+                break;
+            }
+        }
+        buf
+    });
+    assert_eq!(REPLY, buf);
+}
+
+/// Only the changes of the proxied chip select reach the slave.
+#[test]
+fn test_spi_chip_select() {
+    let (runtime, mut received) = start();
+
+    let chip_selects = run_with_timeout(&runtime, async {
+        let mut chip_selects = Vec::new();
+        while let Some(chip_select) = received.slave_chip_selects.recv().await {
+            chip_selects.push(chip_select);
+            if chip_selects.len() == 2 {
+                break;
+            }
+        }
+        chip_selects
+    });
+    assert_eq!(vec![true, false], chip_selects);
+
+    // nothing from chip select 1 follows
+    let extra = runtime.block_on(tokio::time::timeout(
+        Duration::from_millis(100),
+        received.slave_chip_selects.recv(),
+    ));
+    assert!(extra.is_err(), "unexpected chip select {extra:?}");
+}
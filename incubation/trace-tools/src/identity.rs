@@ -2,7 +2,9 @@
 
 //! Service that provides interop for tonic gRPC entitiies and styx enumerations
 use styx_core::cpu::{
-    arch::{arm::ArmVariants, blackfin::BlackfinVariants, ppc32::Ppc32Variants},
+    arch::{
+        arm::ArmVariants, blackfin::BlackfinVariants, mips32::Mips32Variants, ppc32::Ppc32Variants,
+    },
     ArchEndian, {Arch, Backend},
 };
 use styx_core::grpc::{
@@ -379,6 +381,15 @@ pub mod supported_config {
                 Loader::BlackfinLDR,
                 Backend::Pcode
             ),
+            conf!(
+                Target::Mips32,
+                "Mips32",
+                Arch::Mips32,
+                Mips32Variants::Mips3224kf,
+                ArchEndian::BigEndian,
+                Loader::Raw,
+                Backend::Pcode
+            ),
         ]
     }
 }
@@ -401,6 +412,7 @@ mod tests {
             Target::Stm32f107,
             Target::CycloneV,
             Target::Blackfin512,
+            Target::Mips32,
         ]
         .iter()
         {
@@ -3,6 +3,9 @@
 //!
 //! Provide a firmware path and their execution times will be compared and reported.
 //!
+//! The pcode backend caches translated instructions, most of the time spent on hot loops should be
+//! in pcode execution rather than sleigh translation.
+//!
 //! - Run all benchmarks
 //!   - `cargo bench --package styx-emulator --bench pcode_vs_unicorn_ppc`
 //! - Run single benchmark (replace `pcode` with filter)
@@ -103,6 +103,15 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| run(&mut proc, TestType::RestoreOnly))
     });
 
+    group.bench_function("full snapshot only", |b| {
+        b.iter(|| proc.snapshot().unwrap())
+    });
+
+    let snapshot = proc.snapshot().unwrap();
+    group.bench_function("full restore only", |b| {
+        b.iter(|| proc.restore_snapshot(&snapshot).unwrap())
+    });
+
     group.finish();
 }
 
@@ -1140,8 +1140,14 @@ StyxFFIErrorPtr StyxLoader_BlackfinLdrloader_new(StyxLoader *out);
 
 StyxFFIErrorPtr StyxLoader_ElfLoader_new(StyxLoader *out);
 
+StyxFFIErrorPtr StyxLoader_IntelHexLoader_new(StyxLoader *out);
+
 StyxFFIErrorPtr StyxLoader_RawLoader_new(StyxLoader *out);
 
+StyxFFIErrorPtr StyxLoader_SrecordLoader_new(StyxLoader *out);
+
+StyxFFIErrorPtr StyxLoader_Uf2Loader_new(StyxLoader *out);
+
 void StyxPlugin_free(StyxPlugin *ptr);
 
 StyxFFIErrorPtr StyxPlugin_StyxTracePlugin_default(StyxPlugin *out);
@@ -30,6 +30,15 @@ styx_loader_impl! {
 styx_loader_impl! {
     ElfLoader(styx_emulator::core::loader::ElfLoader)
 }
+styx_loader_impl! {
+    IntelHexLoader(styx_emulator::core::loader::IntelHexLoader)
+}
 styx_loader_impl! {
     RawLoader(styx_emulator::core::loader::RawLoader)
 }
+styx_loader_impl! {
+    SRecordLoader(styx_emulator::core::loader::SRecordLoader)
+}
+styx_loader_impl! {
+    Uf2Loader(styx_emulator::core::loader::Uf2Loader)
+}
@@ -69,6 +69,9 @@ pub enum TargetExitReason {
     UnmappedMemoryRead,
     /// Target performed an unmapped memory read
     UnmappedMemoryWrite,
+    /// Target is idle until the next interrupt, e.g. after `wfi`. The
+    /// waiting instruction completed and `pc` points after it
+    WaitForInterrupt,
 }
 
 impl From<styx::TargetExitReason> for TargetExitReason {
@@ -109,6 +112,7 @@ impl From<styx::TargetExitReason> for TargetExitReason {
             styx::TargetExitReason::UnmappedMemoryFetch => TargetExitReason::UnmappedMemoryFetch,
             styx::TargetExitReason::UnmappedMemoryRead => TargetExitReason::UnmappedMemoryRead,
             styx::TargetExitReason::UnmappedMemoryWrite => TargetExitReason::UnmappedMemoryWrite,
+            styx::TargetExitReason::WaitForInterrupt => TargetExitReason::WaitForInterrupt,
         }
     }
 }
@@ -8,8 +8,17 @@ class BlackfinLDRLoader(Loader):
 class ElfLoader(Loader):
     def __new__(cls) -> tuple[ElfLoader, Loader]: ...
 
+class IntelHexLoader(Loader):
+    def __new__(cls) -> tuple[IntelHexLoader, Loader]: ...
+
 class Loader:
     ...
 
 class RawLoader(Loader):
     def __new__(cls) -> tuple[RawLoader, Loader]: ...
+
+class SRecordLoader(Loader):
+    def __new__(cls) -> tuple[SRecordLoader, Loader]: ...
+
+class Uf2Loader(Loader):
+    def __new__(cls) -> tuple[Uf2Loader, Loader]: ...
@@ -270,3 +270,8 @@ class TargetExitReason(Enum):
     r"""
     Target performed an unmapped memory read
     """
+    WaitForInterrupt = ...
+    r"""
+    Target is idle until the next interrupt, e.g. after `wfi`. The
+    waiting instruction completed and `pc` points after it
+    """
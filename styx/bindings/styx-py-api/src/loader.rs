@@ -46,6 +46,20 @@ impl ElfLoader {
     }
 }
 
+#[gen_stub_pyclass]
+#[pyclass(extends=Loader, module="loader")]
+pub struct IntelHexLoader;
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl IntelHexLoader {
+    #[new]
+    pub fn new() -> (IntelHexLoader, Loader) {
+        let inner = styx_emulator::prelude::IntelHexLoader;
+        (Self, Loader::new(inner))
+    }
+}
+
 #[gen_stub_pyclass]
 #[pyclass(extends=Loader, module="loader")]
 pub struct RawLoader;
@@ -60,12 +74,43 @@ impl RawLoader {
     }
 }
 
+#[gen_stub_pyclass]
+#[pyclass(extends=Loader, module="loader")]
+pub struct SRecordLoader;
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl SRecordLoader {
+    #[new]
+    pub fn new() -> (SRecordLoader, Loader) {
+        let inner = styx_emulator::prelude::SRecordLoader;
+        (Self, Loader::new(inner))
+    }
+}
+
+#[gen_stub_pyclass]
+#[pyclass(extends=Loader, module="loader")]
+pub struct Uf2Loader;
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Uf2Loader {
+    #[new]
+    pub fn new() -> (Uf2Loader, Loader) {
+        let inner = styx_emulator::prelude::Uf2Loader::default();
+        (Self, Loader::new(inner))
+    }
+}
+
 pub(crate) fn register(m: &mut ModuleSystem) -> PyResult<()> {
     m.register("loader", |m| {
         m.add_class::<Loader>()?;
         m.add_class::<BlackfinLDRLoader>()?;
         m.add_class::<ElfLoader>()?;
+        m.add_class::<IntelHexLoader>()?;
         m.add_class::<RawLoader>()?;
+        m.add_class::<SRecordLoader>()?;
+        m.add_class::<Uf2Loader>()?;
         Ok(())
     })?;
 
@@ -17,6 +17,7 @@ pub use styx_grpc as grpc;
 pub use styx_loader as loader;
 pub use styx_macros as macros;
 pub use styx_peripheral_clients as peripheral_clients;
+pub use styx_processor::calls;
 pub use styx_processor::core;
 pub use styx_processor::event_controller;
 pub use styx_processor::executor;
@@ -25,6 +26,7 @@ pub use styx_processor::memory;
 pub use styx_processor::plugins;
 pub use styx_processor::processor;
 pub use styx_processor::runtime;
+pub use styx_processor::snapshot;
 pub use styx_sync as sync;
 pub use styx_tracebus as tracebus;
 pub use styx_util as util;
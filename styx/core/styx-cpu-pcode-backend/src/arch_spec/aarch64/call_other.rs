@@ -1,13 +1,22 @@
 // SPDX-License-Identifier: BSD-2-Clause
+use std::collections::HashMap;
+
 use half::f16;
-use styx_processor::cpu::CpuBackend;
+use log::trace;
+use styx_pcode::pcode::VarnodeData;
+use styx_processor::{cpu::CpuBackend, event_controller::EventController, memory::Mmu};
+use styx_sync::sync::{Arc, Mutex};
 
 use crate::{
-    call_other::{CallOtherCallback, CallOtherCpu},
+    call_other::{CallOtherCallback, CallOtherCpu, CallOtherHandleError},
     memory::sized_value::SizedValue,
     PCodeStateChange,
 };
 
+use super::register::{
+    read_pstate, read_system_register, write_pstate, ELR_EL1_OFFSET, SPSR_EL1_OFFSET,
+};
+
 #[derive(Debug, Default)]
 /// Floating-point minimum number (vector):
 /// This instruction compares corresponding vector elements in the two source
@@ -1194,3 +1203,237 @@ impl<T: CpuBackend> CallOtherCallback<T> for NeonCntCallother {
         Ok(PCodeStateChange::Fallthrough)
     }
 }
+
+/// Interrupt number of a supervisor call, matches QEMU's `EXCP_SWI` so the pcode and unicorn
+/// backends report the same number to interrupt hooks.
+const SVC_INTNO: i32 = 2;
+
+#[derive(Debug, Default)]
+/// Supervisor call (`svc #imm16`).
+///
+/// Sleigh Usage:
+///
+/// `CallSupervisor(imm16)`
+///
+/// Implementation:
+///
+/// Triggers the interrupt hooks with [`SVC_INTNO`] after the instruction, the event controller
+/// takes the exception from its interrupt hook with the preferred return address in pc.
+pub struct CallSupervisorCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for CallSupervisorCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        inputs: &[VarnodeData],
+        _output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        let imm16 = cpu.read(&inputs[0]).unwrap().to_u64().unwrap();
+        trace!("svc #{imm16:#x}");
+
+        Ok(PCodeStateChange::DelayedInterrupt(SVC_INTNO))
+    }
+}
+
+#[derive(Debug, Default)]
+/// Exception return (`eret`) from EL1.
+///
+/// Sleigh Usage:
+///
+/// `pc = ExceptionReturn()`
+///
+/// Implementation:
+///
+/// Restores PSTATE from `SPSR_EL1` and returns `ELR_EL1` as the new pc.
+pub struct ExceptionReturnCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for ExceptionReturnCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        _inputs: &[VarnodeData],
+        output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        debug_assert!(output.is_some());
+
+        let spaces = cpu.space_manager();
+        let spsr = read_system_register(spaces, SPSR_EL1_OFFSET);
+        let elr = read_system_register(spaces, ELR_EL1_OFFSET);
+        trace!(
+            "eret to {elr:#x}, pstate {:#x} -> {spsr:#x}",
+            read_pstate(spaces)
+        );
+        write_pstate(spaces, spsr);
+
+        let output = output.unwrap();
+        cpu.write(output, SizedValue::from_u64(elr, output.size as u8))
+            .unwrap();
+
+        Ok(PCodeStateChange::Fallthrough)
+    }
+}
+
+#[derive(Debug, Default)]
+/// Exclusive monitor check of a store exclusive (`stxr` and friends).
+///
+/// Sleigh Usage:
+///
+/// `check:1 = ExclusiveMonitorPass(addr, size)`
+///
+/// Implementation:
+///
+/// There is a single processor and no other bus masters, so the monitor always passes.
+pub struct ExclusiveMonitorPassCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for ExclusiveMonitorPassCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        _inputs: &[VarnodeData],
+        output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        let output = output.unwrap();
+        cpu.write(output, SizedValue::from_u64(1, output.size as u8))
+            .unwrap();
+
+        Ok(PCodeStateChange::Fallthrough)
+    }
+}
+
+#[derive(Debug, Default)]
+/// Status of a store exclusive (`stxr` and friends).
+///
+/// Sleigh Usage:
+///
+/// `status = ExclusiveMonitorsStatus()`
+///
+/// Implementation:
+///
+/// Always 0, the store succeeded.
+pub struct ExclusiveMonitorsStatusCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for ExclusiveMonitorsStatusCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        _inputs: &[VarnodeData],
+        output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        let output = output.unwrap();
+        cpu.write(output, SizedValue::from_u64(0, output.size as u8))
+            .unwrap();
+
+        Ok(PCodeStateChange::Fallthrough)
+    }
+}
+
+/// System registers without a register in the sleigh spec, keyed by their
+/// `(op0, op1, CRn, CRm, op2)` encoding.
+///
+/// Shared by [`UnknownSystemRegisterReadCallother`] and [`UnknownSystemRegisterWriteCallother`].
+/// Registers read as 0 until written, e.g. `ICC_SRE_EL1` reads as 0 so software uses the memory
+/// mapped GIC CPU interface.
+#[derive(Debug, Default)]
+pub struct UnknownSystemRegisters {
+    values: Mutex<HashMap<[u8; 5], u64>>,
+}
+
+impl UnknownSystemRegisters {
+    fn encoding<T: CpuBackend>(cpu: &mut dyn CallOtherCpu<T>, inputs: &[VarnodeData]) -> [u8; 5] {
+        let mut encoding = [0; 5];
+        for (field, input) in encoding.iter_mut().zip(inputs) {
+            *field = cpu.read(input).unwrap().to_u64().unwrap() as u8;
+        }
+        encoding
+    }
+}
+
+#[derive(Debug)]
+/// Read of a system register not in the sleigh spec.
+///
+/// Sleigh Usage:
+///
+/// `tmp:8 = UnkSytemRegRead(op0, op1, CRn, CRm, op2)`
+pub struct UnknownSystemRegisterReadCallother {
+    registers: Arc<UnknownSystemRegisters>,
+}
+
+impl UnknownSystemRegisterReadCallother {
+    pub fn new(registers: Arc<UnknownSystemRegisters>) -> Self {
+        Self { registers }
+    }
+}
+
+impl<T: CpuBackend> CallOtherCallback<T> for UnknownSystemRegisterReadCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        inputs: &[VarnodeData],
+        output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        debug_assert_eq!(inputs.len(), 5);
+
+        let encoding = UnknownSystemRegisters::encoding(cpu, inputs);
+        let value = self
+            .registers
+            .values
+            .lock()
+            .unwrap()
+            .get(&encoding)
+            .copied()
+            .unwrap_or_default();
+        trace!("read of unknown system register {encoding:?}: {value:#x}");
+
+        let output = output.unwrap();
+        cpu.write(output, SizedValue::from_u64(value, output.size as u8))
+            .unwrap();
+
+        Ok(PCodeStateChange::Fallthrough)
+    }
+}
+
+#[derive(Debug)]
+/// Write of a system register not in the sleigh spec.
+///
+/// Sleigh Usage:
+///
+/// `tmp:8 = UnkSytemRegWrite(op0, op1, CRn, CRm, op2, Rt)`
+pub struct UnknownSystemRegisterWriteCallother {
+    registers: Arc<UnknownSystemRegisters>,
+}
+
+impl UnknownSystemRegisterWriteCallother {
+    pub fn new(registers: Arc<UnknownSystemRegisters>) -> Self {
+        Self { registers }
+    }
+}
+
+impl<T: CpuBackend> CallOtherCallback<T> for UnknownSystemRegisterWriteCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        inputs: &[VarnodeData],
+        _output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        debug_assert_eq!(inputs.len(), 6);
+
+        let encoding = UnknownSystemRegisters::encoding(cpu, &inputs[..5]);
+        let value = cpu.read(&inputs[5]).unwrap().to_u64().unwrap();
+        trace!("write of unknown system register {encoding:?}: {value:#x}");
+        self.registers
+            .values
+            .lock()
+            .unwrap()
+            .insert(encoding, value);
+
+        Ok(PCodeStateChange::Fallthrough)
+    }
+}
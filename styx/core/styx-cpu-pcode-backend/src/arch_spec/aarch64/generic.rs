@@ -1,16 +1,25 @@
 // SPDX-License-Identifier: BSD-2-Clause
+use styx_cpu_type::arch::aarch64::Aarch64Register;
 use styx_pcode_translator::sla::{Aarch64, Aarch64UserOps};
+use styx_sync::sync::Arc;
 
 use crate::{
     arch_spec::{
-        aarch64::call_other::{
-            NeonAddvCallother, NeonBifCallother, NeonBitCallother, NeonBslCallother,
-            NeonCmtestCallother, NeonCntCallother, NeonFcmeqCallother, NeonFcmgeCallother,
-            NeonFcmgtCallother, NeonFcmleCallother, NeonFcmltCallother, NeonFminnmCallother,
-            NeonRev64Callother,
+        aarch64::{
+            call_other::{
+                CallSupervisorCallother, ExceptionReturnCallother, ExclusiveMonitorPassCallother,
+                ExclusiveMonitorsStatusCallother, NeonAddvCallother, NeonBifCallother,
+                NeonBitCallother, NeonBslCallother, NeonCmtestCallother, NeonCntCallother,
+                NeonFcmeqCallother, NeonFcmgeCallother, NeonFcmgtCallother, NeonFcmleCallother,
+                NeonFcmltCallother, NeonFminnmCallother, NeonRev64Callother,
+                UnknownSystemRegisterReadCallother, UnknownSystemRegisterWriteCallother,
+                UnknownSystemRegisters,
+            },
+            register::CpsrHandler,
         },
         ArchSpecBuilder,
     },
+    call_other::handlers::{EmptyCallback, TraceCallOther, WaitForInterruptCallOther},
     PcodeBackend,
 };
 
@@ -61,5 +70,93 @@ pub fn build() -> ArchSpecBuilder<Aarch64, PcodeBackend> {
         .add_handler(Aarch64UserOps::NeonFminnm, NeonFminnmCallother)
         .unwrap();
 
+    spec.register_manager
+        .add_handler(Aarch64Register::Cpsr, CpsrHandler)
+        .unwrap();
+
+    // exceptions
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::CallSupervisor, CallSupervisorCallother)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::ExceptionReturn, ExceptionReturnCallother)
+        .unwrap();
+
+    // system registers without a sleigh register
+    let system_registers = Arc::new(UnknownSystemRegisters::default());
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::UnkSytemRegRead,
+            UnknownSystemRegisterReadCallother::new(system_registers.clone()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::UnkSytemRegWrite,
+            UnknownSystemRegisterWriteCallother::new(system_registers),
+        )
+        .unwrap();
+
+    // single processor, exclusive accesses always succeed
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::ExclusiveMonitorPass,
+            ExclusiveMonitorPassCallother,
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::ExclusiveMonitorsStatus,
+            ExclusiveMonitorsStatusCallother,
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::ClearExclusiveLocal, EmptyCallback)
+        .unwrap();
+
+    // barriers and hints
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::DataMemoryBarrier,
+            TraceCallOther::new("DataMemoryBarrier".into()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::DataSynchronizationBarrier,
+            TraceCallOther::new("DataSynchronizationBarrier".into()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::InstructionSynchronizationBarrier,
+            TraceCallOther::new("InstructionSynchronizationBarrier".into()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::SpeculationBarrier, EmptyCallback)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::WaitForInterrupt,
+            WaitForInterruptCallOther::new("WaitForInterrupt".into()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(
+            Aarch64UserOps::WaitForEvent,
+            WaitForInterruptCallOther::new("WaitForEvent".into()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::SendEvent, EmptyCallback)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::SendEventLocally, EmptyCallback)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler(Aarch64UserOps::Yield, EmptyCallback)
+        .unwrap();
+
     spec
 }
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use crate::{pcode_gen::GeneratePcodeError, DEFAULT_REG_ALLOCATION};
+use serde::{Deserialize, Serialize};
 use styx_pcode::pcode::VarnodeData;
 use styx_pcode_translator::ContextOption;
 
@@ -13,9 +14,10 @@ use super::{
 
 mod call_other;
 pub mod generic;
+mod register;
 
 /// [GeneratorHelp] for SuperH processors. Does nothing at the moment.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardGeneratorHelper;
 impl GeneratorHelp for StandardGeneratorHelper {
     fn pre_fetch(
@@ -27,7 +29,7 @@ impl GeneratorHelp for StandardGeneratorHelper {
 }
 
 /// Program Counter manager for Blackfin processors.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! PSTATE for AArch64.
+//!
+//! The sleigh spec keeps the PSTATE fields in separate registers (the `NG`/`ZR`/`CY`/`OV` flags,
+//! `daif`, `currentel` and `spsel`) and has a single `sp`. [`CpsrHandler`] presents them as one
+//! value in the `SPSR_ELx` format and banks `sp` into `sp_el0`/`sp_el1` when the selected stack
+//! pointer changes, so exception entry and return are a write of the saved PSTATE.
+use crate::register_manager::RegisterCallbackCpu;
+use crate::{
+    memory::{sized_value::SizedValue, space_manager::SpaceManager},
+    register_manager::{RegisterCallback, RegisterHandleError},
+};
+use log::trace;
+use styx_cpu_type::arch::{aarch64::Aarch64Register, backends::ArchRegister};
+use styx_pcode::pcode::{SpaceName, VarnodeData};
+use styx_processor::cpu::CpuBackend;
+
+const NEGATIVE_FLAG_OFFSET: u64 = 0x100;
+const ZERO_FLAG_OFFSET: u64 = 0x101;
+const CARRY_FLAG_OFFSET: u64 = 0x102;
+const OVERFLOW_FLAG_OFFSET: u64 = 0x103;
+
+const SP_OFFSET: u64 = 0x8;
+pub(super) const SPSR_EL1_OFFSET: u64 = 0x1000;
+pub(super) const ELR_EL1_OFFSET: u64 = 0x1008;
+const SP_EL0_OFFSET: u64 = 0x1010;
+const SPSEL_OFFSET: u64 = 0x1018;
+const DAIF_OFFSET: u64 = 0x1020;
+const CURRENTEL_OFFSET: u64 = 0x1028;
+const SP_EL1_OFFSET: u64 = 0x1068;
+
+/// Bit offset of the `N` flag, `Z`, `C` and `V` follow below it.
+const PSTATE_NZCV_SHIFT: u32 = 28;
+/// `D`, `A`, `I` and `F`, stored in the same position by `daif`.
+const PSTATE_DAIF_MASK: u64 = 0xF << 6;
+/// `M[3:2]`, the exception level, stored in the same position by `currentel`.
+const PSTATE_EL_MASK: u64 = 0b11 << 2;
+/// `M[0]`, the selected stack pointer.
+const PSTATE_SP: u64 = 1;
+
+fn register(offset: u64, size: u32) -> VarnodeData {
+    VarnodeData {
+        space: SpaceName::Register,
+        offset,
+        size,
+    }
+}
+
+fn read(spaces: &mut SpaceManager, offset: u64, size: u32) -> u64 {
+    spaces
+        .read(&register(offset, size))
+        .unwrap()
+        .to_u64()
+        .unwrap()
+}
+
+/// Read the 64 bit system register at `offset` in the register space.
+pub(super) fn read_system_register(spaces: &mut SpaceManager, offset: u64) -> u64 {
+    read(spaces, offset, 8)
+}
+
+fn write(spaces: &mut SpaceManager, offset: u64, size: u32, value: u64) {
+    spaces
+        .write(
+            &register(offset, size),
+            SizedValue::from_u64(value, size as u8),
+        )
+        .unwrap();
+}
+
+/// Offset of the banked stack pointer used at `el` with `spsel`, EL0 always uses `SP_EL0`.
+fn stack_pointer_bank(el: u64, spsel: u64) -> u64 {
+    if el == 0 || spsel == 0 {
+        SP_EL0_OFFSET
+    } else {
+        SP_EL1_OFFSET
+    }
+}
+
+/// PSTATE in the `SPSR_ELx` format.
+pub(super) fn read_pstate(spaces: &mut SpaceManager) -> u64 {
+    let flags = [
+        OVERFLOW_FLAG_OFFSET,
+        CARRY_FLAG_OFFSET,
+        ZERO_FLAG_OFFSET,
+        NEGATIVE_FLAG_OFFSET,
+    ];
+    let nzcv = flags.iter().enumerate().fold(0, |nzcv, (bit, offset)| {
+        nzcv | ((read(spaces, *offset, 1) & 1) << bit)
+    });
+
+    (nzcv << PSTATE_NZCV_SHIFT)
+        | (read(spaces, DAIF_OFFSET, 8) & PSTATE_DAIF_MASK)
+        | (read(spaces, CURRENTEL_OFFSET, 8) & PSTATE_EL_MASK)
+        | (read(spaces, SPSEL_OFFSET, 8) & PSTATE_SP)
+}
+
+/// Set PSTATE from `value` in the `SPSR_ELx` format, switching `sp` to the newly selected stack
+/// pointer.
+pub(super) fn write_pstate(spaces: &mut SpaceManager, value: u64) {
+    let old_bank = stack_pointer_bank(
+        read(spaces, CURRENTEL_OFFSET, 8) >> 2,
+        read(spaces, SPSEL_OFFSET, 8),
+    );
+
+    let flags = [
+        NEGATIVE_FLAG_OFFSET,
+        ZERO_FLAG_OFFSET,
+        CARRY_FLAG_OFFSET,
+        OVERFLOW_FLAG_OFFSET,
+    ];
+    for (bit, offset) in flags.iter().enumerate() {
+        write(spaces, *offset, 1, (value >> (31 - bit)) & 1);
+    }
+    write(spaces, DAIF_OFFSET, 8, value & PSTATE_DAIF_MASK);
+    let el = value & PSTATE_EL_MASK;
+    write(spaces, CURRENTEL_OFFSET, 8, el);
+    let spsel = value & PSTATE_SP;
+    write(spaces, SPSEL_OFFSET, 8, spsel);
+
+    let new_bank = stack_pointer_bank(el >> 2, spsel);
+    if new_bank != old_bank {
+        trace!("switching stack pointer {old_bank:#x} -> {new_bank:#x}");
+        let sp = read(spaces, SP_OFFSET, 8);
+        write(spaces, old_bank, 8, sp);
+        let sp = read(spaces, new_bank, 8);
+        write(spaces, SP_OFFSET, 8, sp);
+    }
+}
+
+/// The [Aarch64Register::Cpsr] register, PSTATE in the `SPSR_ELx` format.
+///
+/// Only `NZCV`, `DAIF`, the exception level and `SPSel` are kept, writes to the other bits are
+/// ignored.
+#[derive(Debug, Default)]
+pub struct CpsrHandler;
+impl<T: CpuBackend> RegisterCallback<T> for CpsrHandler {
+    fn read(
+        &mut self,
+        register: ArchRegister,
+        cpu: &mut dyn RegisterCallbackCpu<T>,
+    ) -> Result<SizedValue, RegisterHandleError> {
+        if register != Aarch64Register::Cpsr.into() {
+            return Err(RegisterHandleError::CannotHandleRegister(register));
+        }
+
+        let pstate = read_pstate(cpu.space_manager());
+        Ok(SizedValue::from_u64(pstate, 4))
+    }
+
+    fn write(
+        &mut self,
+        register: ArchRegister,
+        value: SizedValue,
+        cpu: &mut dyn RegisterCallbackCpu<T>,
+    ) -> Result<(), RegisterHandleError> {
+        if register != Aarch64Register::Cpsr.into() {
+            return Err(RegisterHandleError::CannotHandleRegister(register));
+        }
+
+        write_pstate(cpu.space_manager(), value.to_u64().unwrap());
+        Ok(())
+    }
+}
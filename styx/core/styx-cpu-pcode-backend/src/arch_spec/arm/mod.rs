@@ -13,13 +13,16 @@ use super::{
     ArchPcManager, ArchSpecBuilder, GeneratorHelp,
 };
 use crate::{
-    arch_spec::ArchSpec, call_other::handlers::TraceCallOther, pcode_gen::GeneratePcodeError,
+    arch_spec::ArchSpec,
+    call_other::handlers::{TraceCallOther, WaitForInterruptCallOther},
+    pcode_gen::GeneratePcodeError,
     PcodeBackend, DEFAULT_REG_ALLOCATION,
 };
 use call_other::*;
 use helpers::StackPointerManager;
 use log::debug;
 use register::*;
+use serde::{Deserialize, Serialize};
 use smallvec::{smallvec, SmallVec};
 use std::str::FromStr;
 use styx_cpu_type::{
@@ -142,6 +145,18 @@ fn armv7_common<T: SlaUserOps<UserOps: FromStr>>(spec: &mut ArchSpecBuilder<T, P
     call_other_manager
         .add_handler_other_sla(Arm7LeUserOps::SoftwareInterrupt, SoftwareInterruptCallOther)
         .unwrap();
+    call_other_manager
+        .add_handler_other_sla(
+            Arm7LeUserOps::WaitForInterrupt,
+            WaitForInterruptCallOther::new("WaitForInterrupt called.".into()),
+        )
+        .unwrap();
+    call_other_manager
+        .add_handler_other_sla(
+            Arm7LeUserOps::WaitForEvent,
+            WaitForInterruptCallOther::new("WaitForEvent called.".into()),
+        )
+        .unwrap();
 
     register_manager
         .add_handler(ArmRegister::Apsr, ApsrHandler)
@@ -191,7 +206,7 @@ fn armv7a_common<S>(spec: &mut ArchSpecBuilder<S, PcodeBackend>) {
 }
 
 /// Program Counter manager for the thumb-only ARM processors.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThumbPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -252,8 +267,9 @@ impl Default for ThumbPcManager {
 
 /// Program Counter manager for Armv7-A processors.
 ///
-/// Controls thumb mode base on bit 5 of the CPSR.
-#[derive(Debug, Clone)]
+/// Controls thumb mode base on bit 5 of the CPSR and switches the banked registers when an
+/// instruction changed the processor mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StandardPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -290,7 +306,12 @@ impl ArchPcManager for StandardPcManager {
         self.internal_pc
     }
 
-    fn set_internal_pc(&mut self, value: u64, _backend: &mut PcodeBackend, _from_branch: bool) {
+    fn set_internal_pc(&mut self, value: u64, backend: &mut PcodeBackend, from_branch: bool) {
+        if from_branch {
+            // exception returns write cpsr directly and branch
+            arm7a_sync_mode(&mut backend.space_manager);
+        }
+
         // i128 here is used so we don't overflow on cast
         let difference = (value as i128 - self.internal_pc as i128) & (!1);
 
@@ -321,6 +342,9 @@ impl ArchPcManager for StandardPcManager {
             .checked_add(bytes_consumed)
             .ok_or(PcOverflow)?;
 
+        // msr and cps write cpsr directly
+        arm7a_sync_mode(&mut backend.space_manager);
+
         // realign to thumb mode
         self.isa_pc = self
             .internal_pc
@@ -346,7 +370,7 @@ impl Default for StandardPcManager {
 }
 
 /// Simple Arm instruction set/Thumb instruction set enum
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 enum ArmCpuMode {
     Arm,
     Thumb,
@@ -370,7 +394,7 @@ impl ArmCpuMode {
 /// Thumb mode switching for Armv7-A processors,
 ///
 /// Thumb mode bit is bit 5 of the CPSR register.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardGeneratorHelper {
     /// Stores the previous cpu mode, [None] at start of running when no mode is stored.
     previous_mode: Option<ArmCpuMode>,
@@ -406,7 +430,7 @@ impl GeneratorHelp for StandardGeneratorHelper {
 /// Thumb mode enabling for the Armv7-M processors.
 ///
 /// Sets the thumb mode context variable on first execution.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ThumbOnlyGeneratorHelper {
     /// `false` on creation, changed to true after setting the thumb mode context on first call.
     thumb_already_set: bool,
@@ -381,6 +381,9 @@ const CPSR_BIT_OFFSET_THUMB_BIT: u32 = 5;
 const CPSR_MASK_THUMB_BIT: u32 = 1 << CPSR_BIT_OFFSET_THUMB_BIT;
 
 /// Current Program Status Register (CPSR)
+///
+/// Writes that change the mode swap in the mode's banked `sp`, `lr` and `spsr`, see
+/// [arm7a_switch_mode()].
 #[derive(Debug, Default)]
 pub struct CpsrHandler;
 impl<T: CpuBackend> RegisterCallback<T> for CpsrHandler {
@@ -428,6 +431,9 @@ impl<T: CpuBackend> RegisterCallback<T> for CpsrHandler {
             return Err(RegisterHandleError::CannotHandleRegister(register));
         }
 
+        // switch banks first, the exception entry writes the new mode's spsr and lr after cpsr
+        arm7a_switch_mode(cpu.space_manager(), value.to_u64().unwrap() as u32);
+
         // FIXME: We need to make sure all of this is writable.
         arm7a_set_cpsr(cpu, value);
         Ok(())
@@ -532,6 +538,21 @@ const GREATER_THAN_OR_EQUAL4_OFFSET: u64 = 0x6E;
 const CPSR_OFFSET: u64 = 0x70;
 /// Varnode name "TB"
 const THUMB_BIT_OFFSET: u64 = 0x69;
+/// Varnode name "sp"
+const SP_OFFSET: u64 = 0x54;
+/// Varnode name "lr"
+const LR_OFFSET: u64 = 0x58;
+/// Varnode name "spsr"
+const SPSR_OFFSET: u64 = 0x74;
+
+// The slaspec has a single `sp`, `lr` and `spsr`, the copies of the inactive modes are kept past
+// the last register of the slaspec.
+/// Start of the banked `sp`, `lr` and `spsr` of each mode, 0x10 bytes per mode.
+const BANKED_REGISTERS_OFFSET: u64 = 0x1000;
+/// Bank of the mode whose registers are in `sp`, `lr` and `spsr`, one byte.
+const ACTIVE_BANK_OFFSET: u64 = 0x1100;
+/// Registers with a copy per mode, in their order in a bank.
+const BANKED_REGISTERS: [u64; 3] = [SP_OFFSET, LR_OFFSET, SPSR_OFFSET];
 
 /// Puts the condition flags (N, Z, C, V and Q) at the specified offset.
 fn armv7_get_condition_flags(backend: &mut SpaceManager, bit_offset: u32) -> u32 {
@@ -592,6 +613,68 @@ fn arm7a_set_cpsr<C: CpuBackend>(backend: &mut dyn RegisterCallbackCpu<C>, value
     backend.write(&varnode, value).unwrap();
 }
 
+/// Bank of a processor mode, User and System mode share bank 0.
+///
+/// Only `sp`, `lr` and `spsr` are banked, the FIQ mode `r8`-`r12` are shared with the other modes.
+fn mode_bank(cpsr: u32) -> u64 {
+    match cpsr & 0x1F {
+        0b10001 => 1, // FIQ
+        0b10010 => 2, // IRQ
+        0b10011 => 3, // Supervisor
+        0b10110 => 4, // Monitor
+        0b10111 => 5, // Abort
+        0b11010 => 6, // Hyp
+        0b11011 => 7, // Undefined
+        _ => 0,       // User, System
+    }
+}
+
+fn register_varnode(offset: u64) -> VarnodeData {
+    VarnodeData {
+        space: SpaceName::Register,
+        offset,
+        size: 4,
+    }
+}
+
+/// Swap in the banked `sp`, `lr` and `spsr` of the mode in `cpsr` if it is not the active mode.
+///
+/// Called before the host writes cpsr and after every instruction, the latter catches mode changes
+/// from exception returns, `msr` and `cps`.
+pub(super) fn arm7a_switch_mode(spaces: &mut SpaceManager, cpsr: u32) {
+    let active_bank = VarnodeData {
+        space: SpaceName::Register,
+        offset: ACTIVE_BANK_OFFSET,
+        size: 1,
+    };
+    let old = spaces.read(&active_bank).unwrap().to_u64().unwrap();
+    let new = mode_bank(cpsr);
+    if old == new {
+        return;
+    }
+
+    trace!("switching register bank {old} -> {new}");
+    for (idx, offset) in BANKED_REGISTERS.iter().enumerate() {
+        let live = register_varnode(*offset);
+        let old_copy = register_varnode(BANKED_REGISTERS_OFFSET + old * 0x10 + idx as u64 * 4);
+        let new_copy = register_varnode(BANKED_REGISTERS_OFFSET + new * 0x10 + idx as u64 * 4);
+
+        let value = spaces.read(&live).unwrap();
+        spaces.write(&old_copy, value).unwrap();
+        let value = spaces.read(&new_copy).unwrap();
+        spaces.write(&live, value).unwrap();
+    }
+    spaces
+        .write(&active_bank, SizedValue::from_u64(new, 1))
+        .unwrap();
+}
+
+/// [arm7a_switch_mode()] to the mode of the current cpsr.
+pub(super) fn arm7a_sync_mode(spaces: &mut SpaceManager) {
+    let cpsr = spaces.read(&register_varnode(CPSR_OFFSET)).unwrap();
+    arm7a_switch_mode(spaces, cpsr.to_u64().unwrap() as u32);
+}
+
 #[derive(Debug, Default)]
 pub struct FloatingPointExtensionHandler {
     value: AtomicU64,
@@ -5,12 +5,15 @@ mod call_other;
 mod register;
 
 use register::AnalogRegister;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use styx_cpu_type::arch::blackfin::BlackfinRegister;
 use styx_pcode::pcode::VarnodeData;
 use styx_pcode_translator::sla::{self, BlackfinUserOps};
 
-use crate::{PcodeBackend, DEFAULT_REG_ALLOCATION};
+use crate::{
+    call_other::handlers::WaitForInterruptCallOther, PcodeBackend, DEFAULT_REG_ALLOCATION,
+};
 
 use super::{
     pc_manager::{apply_difference, PcOverflow},
@@ -18,7 +21,7 @@ use super::{
 };
 
 /// Program Counter manager for Blackfin processors.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -129,6 +132,13 @@ fn blackfin_common(spec: &mut ArchSpecBuilder<sla::Blackfin, PcodeBackend>) {
         .add_handler(BlackfinUserOps::Ssync, call_other::SSyncHandler)
         .unwrap();
 
+    spec.call_other_manager
+        .add_handler(
+            BlackfinUserOps::Idle,
+            WaitForInterruptCallOther::new("idle".into()),
+        )
+        .unwrap();
+
     spec.call_other_manager
         .add_handler(BlackfinUserOps::Deposit, call_other::DepositHandler)
         .unwrap();
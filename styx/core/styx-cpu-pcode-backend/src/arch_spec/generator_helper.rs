@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use crate::{pcode_gen::GeneratePcodeError, PcodeBackend};
 use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Serialize};
 use smallvec::{smallvec, SmallVec};
 use std::fmt::Debug;
 use styx_pcode_translator::ContextOption;
@@ -34,7 +35,7 @@ pub trait GeneratorHelp: Debug {
 // We derive Clone here because the GeneratorHelper
 // is saved and restored in context_save and
 // context_restore using .clone().
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GeneratorHelper {
     #[cfg(feature = "arch_aarch64")]
     Aarch64(aarch64::StandardGeneratorHelper),
@@ -54,7 +55,7 @@ impl Default for GeneratorHelper {
 }
 
 /// [GeneratorHelp] that does nothing.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct EmptyGeneratorHelper;
 impl GeneratorHelp for EmptyGeneratorHelper {
     fn pre_fetch(
@@ -6,7 +6,7 @@ use execution_helper::DefaultHexagonExecutionHelper;
 use log::trace;
 pub use saved_context_opts::SavedContextOpts;
 use smallvec::{smallvec, SmallVec};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 use styx_cpu_type::{
     arch::{
         backends::{ArchRegister, ArchVariant, BasicArchRegister},
@@ -25,7 +25,9 @@ use styx_pcode_translator::ContextOption;
 use styx_processor::{
     cpu::{CpuBackend, ExecutionReport, ReadRegisterError, WriteRegisterError},
     event_controller::EventController,
-    hooks::{AddHookError, DeleteHookError, HookToken, Hookable, StyxHook},
+    hooks::{
+        AddHookError, DeleteHookError, HookControl, HookControlError, HookToken, Hookable, StyxHook,
+    },
     memory::Mmu,
 };
 use thiserror::Error;
@@ -146,6 +148,7 @@ pub struct HexagonPcodeBackend {
     register_manager: RegisterManager<Self>,
     pcode_generator: GhidraPcodeGenerator<Self>,
     stop_requested: bool,
+    wait_requested: bool,
     last_was_branch: bool,
     call_other_manager: Option<CallOtherManager<Self>>,
 
@@ -167,6 +170,10 @@ impl Hookable for HexagonPcodeBackend {
     fn delete_hook(&mut self, token: HookToken) -> Result<(), DeleteHookError> {
         self.hook_manager.delete_hook(token)
     }
+
+    fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.hook_manager.hook_control(token)
+    }
 }
 
 impl HasSpaceManager for HexagonPcodeBackend {
@@ -251,6 +258,14 @@ impl BackendHelper<HexagonExecuteSingleInfo, Vec<Pcode>> for HexagonPcodeBackend
         self.stop_requested = stop_requested;
     }
 
+    fn wait_requested(&self) -> bool {
+        self.wait_requested
+    }
+
+    fn set_wait_requested(&mut self, wait_requested: bool) {
+        self.wait_requested = wait_requested;
+    }
+
     /// Execute a single packet. Returns number of instructions executed and the ordering of the packet (used for execution report)
     fn execute_single(
         &mut self,
@@ -578,6 +593,7 @@ impl HexagonPcodeBackend {
             pcode_generator,
             pcode_config: config.clone(),
             stop_requested: false,
+            wait_requested: false,
             last_was_branch: false,
             call_other_manager: Some(call_other),
             saved_reg_context: BTreeMap::new(),
@@ -658,6 +674,10 @@ impl HexagonPcodeBackend {
                     // Don't increment PC
                 }
                 PCodeStateChange::Exit(reason) => return Ok(Err(reason)),
+                PCodeStateChange::WaitForInterrupt => {
+                    self.wait_requested = true;
+                    i += 1;
+                }
             }
         }
 
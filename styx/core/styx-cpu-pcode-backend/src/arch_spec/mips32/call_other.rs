@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Coprocessor 0 and exception pcodeops for MIPS32.
+//!
+//! `mfc0` reads the coprocessor 0 registers directly, `mtc0` goes through `setCopReg` so
+//! [`SetCopRegCallother`] can apply the read only fields and side effects of the registers used
+//! by exceptions and the count/compare timer.
+use std::str::FromStr;
+
+use log::{trace, warn};
+use styx_cpu_type::ArchEndian;
+use styx_pcode::{
+    pcode::{SpaceName, VarnodeData},
+    sla::SlaUserOps,
+};
+use styx_pcode_translator::sla::Mips32leUserOps;
+use styx_processor::{cpu::CpuBackend, event_controller::EventController, memory::Mmu};
+
+use crate::{
+    arch_spec::ArchSpecBuilder,
+    call_other::{
+        handlers::{EmptyCallback, TraceCallOther, WaitForInterruptCallOther},
+        CallOtherCallback, CallOtherCpu, CallOtherHandleError,
+    },
+    memory::sized_value::SizedValue,
+    PCodeStateChange, PcodeBackend,
+};
+
+/// Interrupt numbers of the synchronous exceptions, they match QEMU's `EXCP_SYSCALL`,
+/// `EXCP_BREAK` and `EXCP_TRAP` so the pcode and unicorn backends report the same number to
+/// interrupt hooks.
+const SYSCALL_INTNO: i32 = 17;
+const BREAK_INTNO: i32 = 18;
+const TRAP_INTNO: i32 = 22;
+
+const COMPARE_OFFSET: u64 = 0x202C;
+const CAUSE_OFFSET: u64 = 0x2034;
+const PRID_OFFSET: u64 = 0x203C;
+const CONFIG1_OFFSET: u64 = 0x2140;
+const EBASE_OFFSET: u64 = 0x213C;
+
+/// `Cause[TI]`, the count/compare timer interrupt is pending.
+const CAUSE_TI: u64 = 1 << 30;
+/// `Cause` fields written by software: `DC`, `IV`, `WP` and the software interrupts `IP1..0`.
+const CAUSE_WRITE_MASK: u64 = 0x08C0_0300;
+/// `EBase` fields written by software: the exception base in bits 29..12.
+const EBASE_WRITE_MASK: u64 = 0x3FFF_F000;
+
+fn cp0_register(offset: u64) -> VarnodeData {
+    VarnodeData {
+        space: SpaceName::Register,
+        offset,
+        size: 4,
+    }
+}
+
+#[derive(Debug, Default)]
+/// Move to coprocessor register (`mtc0`, `mthc0`, `mtc2`).
+///
+/// Sleigh Usage:
+///
+/// `setCopReg(0:1, RD0, RTsrc, sel:1)` or `setCopReg(2:1, immed:4, RTsrc)`
+///
+/// Implementation:
+///
+/// Coprocessor 0 registers are written except for their read only fields, `PRId` and `Config1`
+/// are not written at all. Writing `Compare` clears the pending timer interrupt in `Cause[TI]`.
+/// There is no coprocessor 2, its writes are dropped.
+pub struct SetCopRegCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for SetCopRegCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        inputs: &[VarnodeData],
+        _output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        let coprocessor = cpu.read(&inputs[0]).unwrap().to_u64().unwrap();
+        let value = cpu.read(&inputs[2]).unwrap().to_u64().unwrap();
+        if coprocessor != 0 || inputs[1].space != SpaceName::Register {
+            warn!("ignored write of {value:#x} to coprocessor {coprocessor}");
+            return Ok(PCodeStateChange::Fallthrough);
+        }
+
+        let register = &inputs[1];
+        let old = cpu.read(register).unwrap().to_u64().unwrap();
+        let new = match register.offset {
+            CAUSE_OFFSET => (old & !CAUSE_WRITE_MASK) | (value & CAUSE_WRITE_MASK),
+            EBASE_OFFSET => (old & !EBASE_WRITE_MASK) | (value & EBASE_WRITE_MASK),
+            PRID_OFFSET | CONFIG1_OFFSET => old,
+            COMPARE_OFFSET => {
+                let cause_register = cp0_register(CAUSE_OFFSET);
+                let cause = cpu.read(&cause_register).unwrap().to_u64().unwrap();
+                cpu.write(&cause_register, SizedValue::from_u64(cause & !CAUSE_TI, 4))
+                    .unwrap();
+                value
+            }
+            _ => value,
+        };
+        trace!("cp0 register {:#x} = {new:#x}", register.offset);
+        cpu.write(register, SizedValue::from_u64(new, register.size as u8))
+            .unwrap();
+
+        Ok(PCodeStateChange::Fallthrough)
+    }
+}
+
+#[derive(Debug, Default)]
+/// System call (`syscall`).
+///
+/// Sleigh Usage:
+///
+/// `syscall(code)`
+///
+/// Implementation:
+///
+/// Triggers the interrupt hooks with [`SYSCALL_INTNO`] before the instruction completes, so the
+/// event controller sees the faulting instruction in pc like `EPC` needs.
+pub struct SyscallCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for SyscallCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        inputs: &[VarnodeData],
+        _output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        let code = cpu.read(&inputs[0]).unwrap().to_u64().unwrap();
+        trace!("syscall {code:#x}");
+
+        Ok(PCodeStateChange::Exception(SYSCALL_INTNO))
+    }
+}
+
+/// `SPECIAL` function field of `break`.
+const BREAK_FUNCTION: u32 = 0x0D;
+
+#[derive(Debug, Default)]
+/// Breakpoint (`break`) and trap (`teq`, `tge`, ...) exceptions.
+///
+/// Sleigh Usage:
+///
+/// `trap(code)` or `dest = trap(code)`
+///
+/// Implementation:
+///
+/// `break` uses the same pcodeop as the traps, the instruction is decoded to trigger the
+/// interrupt hooks with [`BREAK_INTNO`] or [`TRAP_INTNO`] before the instruction completes. A
+/// `dest` output is set to the next instruction.
+pub struct TrapCallother;
+impl<T: CpuBackend> CallOtherCallback<T> for TrapCallother {
+    fn handle(
+        &mut self,
+        cpu: &mut dyn CallOtherCpu<T>,
+        mmu: &mut Mmu,
+        _ev: &mut EventController,
+        _inputs: &[VarnodeData],
+        output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        let pc = cpu.pc().unwrap();
+        if let Some(output) = output {
+            cpu.write(output, SizedValue::from_u64(pc + 4, output.size as u8))
+                .unwrap();
+        }
+
+        let instruction = match cpu.endian() {
+            ArchEndian::LittleEndian => mmu.code().read(pc).le().u32(),
+            ArchEndian::BigEndian => mmu.code().read(pc).be().u32(),
+        }
+        .unwrap();
+        // SPECIAL opcode with the break function
+        let intno = if instruction >> 26 == 0 && instruction & 0x3F == BREAK_FUNCTION {
+            BREAK_INTNO
+        } else {
+            TRAP_INTNO
+        };
+        trace!("trap {intno} from {instruction:#010x}");
+
+        Ok(PCodeStateChange::Exception(intno))
+    }
+}
+
+/// Adds the coprocessor 0, exception and hint pcodeops shared by the little and big endian
+/// specs.
+pub fn add_mips32_callothers<S: SlaUserOps<UserOps: FromStr>>(
+    spec: &mut ArchSpecBuilder<S, PcodeBackend>,
+) {
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::SetCopReg, SetCopRegCallother)
+        .unwrap();
+
+    // exceptions
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::Syscall, SyscallCallother)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::Trap, TrapCallother)
+        .unwrap();
+
+    // hints, caches and barriers
+    spec.call_other_manager
+        .add_handler_other_sla(
+            Mips32leUserOps::Wait,
+            WaitForInterruptCallOther::new("wait".into()),
+        )
+        .unwrap();
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::Synch, EmptyCallback)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::Hazzard, EmptyCallback)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::CacheOp, EmptyCallback)
+        .unwrap();
+    spec.call_other_manager
+        .add_handler_other_sla(Mips32leUserOps::Prefetch, EmptyCallback)
+        .unwrap();
+}
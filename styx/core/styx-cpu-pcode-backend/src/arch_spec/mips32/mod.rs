@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 
+mod call_other;
+
 use crate::{
     arch_spec::{mips_common, ArchSpec},
     PcodeBackend,
@@ -11,11 +13,13 @@ use styx_pcode_translator::sla::{Mips32be, Mips32le};
 pub fn build_mips32le() -> super::ArchSpecBuilder<Mips32le, PcodeBackend> {
     let mut spec = super::ArchSpecBuilder::default();
     mips_common::mips_common(&mut spec);
+    call_other::add_mips32_callothers(&mut spec);
     spec
 }
 pub fn build_mips32be() -> super::ArchSpecBuilder<Mips32be, PcodeBackend> {
     let mut spec = super::ArchSpecBuilder::default();
     mips_common::mips_common(&mut spec);
+    call_other::add_mips32_callothers(&mut spec);
     spec
 }
 
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use crate::{PcodeBackend, DEFAULT_REG_ALLOCATION};
+use serde::{Deserialize, Serialize};
 
 use smallvec::SmallVec;
 use styx_pcode::pcode::VarnodeData;
@@ -16,7 +17,7 @@ pub(crate) fn mips_common<Sla>(spec: &mut super::ArchSpecBuilder<Sla, PcodeBacke
     spec.set_generator(GeneratorHelper::default());
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardMipsPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -31,6 +31,9 @@ mod arm;
 #[cfg(feature = "arch_bfin")]
 mod blackfin;
 
+#[cfg(feature = "arch_msp430")]
+mod msp430;
+
 #[cfg(feature = "arch_ppc")]
 mod ppc;
 
@@ -187,6 +190,9 @@ pub fn build_arch_spec(arch: &ArchVariant, endian: ArchEndian) -> ArchSpec<Pcode
         #[cfg(feature = "arch_mips64")]
         ArchVariant::Mips64(_) => mips64::mips64_arch_spec(arch, endian).unwrap(),
 
+        #[cfg(feature = "arch_msp430")]
+        ArchVariant::Msp430(_) => msp430::build().build(arch),
+
         _ => unimplemented!("architecture {arch:?} not supported by pcode backend"),
     }
 }
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Pcode Arch spec for the MSP430 architecture
+//!
+//! The MSP430 sla does not define any user ops so no call other handlers are needed, `bcd_add`
+//! is only used by the MSP430X extension. Low power modes are entered by writing the status
+//! register and are left to the processor implementation.
+use super::{
+    pc_manager::{apply_difference, PcOverflow},
+    ArchPcManager, ArchSpecBuilder, GeneratorHelper,
+};
+use crate::{PcodeBackend, DEFAULT_REG_ALLOCATION};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use styx_pcode::pcode::VarnodeData;
+use styx_pcode_translator::sla;
+
+/// Program Counter manager for MSP430 processors.
+///
+/// Instructions are word aligned and the pc is read as the address of the next word, which the
+/// sla already provides, so the isa pc and internal pc are the same.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct StandardPcManager {
+    isa_pc: u64,
+    internal_pc: u64,
+}
+
+impl ArchPcManager for StandardPcManager {
+    fn isa_pc(&self) -> u64 {
+        self.isa_pc
+    }
+
+    fn internal_pc(&self) -> u64 {
+        self.internal_pc
+    }
+
+    fn set_internal_pc(&mut self, value: u64, _backend: &mut PcodeBackend, _from_branch: bool) {
+        // i128 here is used so we don't overflow on cast
+        let difference = (value as i128 - self.internal_pc as i128) & (!1);
+
+        apply_difference(&mut self.internal_pc, difference);
+        apply_difference(&mut self.isa_pc, difference);
+    }
+
+    fn set_isa_pc(&mut self, value: u64, _backend: &mut PcodeBackend) {
+        // i128 here is used so we don't overflow on cast
+        let difference = (value as i128 - self.isa_pc as i128) & (!1);
+
+        apply_difference(&mut self.internal_pc, difference);
+        apply_difference(&mut self.isa_pc, difference);
+    }
+
+    fn post_execute(
+        &mut self,
+        bytes_consumed: u64,
+        _backend: &mut PcodeBackend,
+        _regs_written: &mut SmallVec<[VarnodeData; DEFAULT_REG_ALLOCATION]>,
+        _total_pcodes: usize,
+    ) -> Result<(), PcOverflow> {
+        self.internal_pc = self
+            .internal_pc
+            .checked_add(bytes_consumed)
+            .ok_or(PcOverflow)?;
+        self.isa_pc = self.isa_pc.checked_add(bytes_consumed).ok_or(PcOverflow)?;
+        Ok(())
+    }
+}
+
+pub fn build() -> ArchSpecBuilder<sla::TiMsp430, PcodeBackend> {
+    let mut spec = ArchSpecBuilder::default();
+
+    spec.set_pc_manager(StandardPcManager::default().into());
+
+    // Standard "do-nothing" generator helper
+    spec.set_generator(GeneratorHelper::default());
+
+    spec
+}
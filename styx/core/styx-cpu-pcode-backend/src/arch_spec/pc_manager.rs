@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use crate::{PcodeBackend, DEFAULT_REG_ALLOCATION};
 use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::fmt::Debug;
 use styx_pcode::pcode::VarnodeData;
@@ -17,6 +18,9 @@ use super::blackfin;
 #[cfg(feature = "arch_superh")]
 use super::superh;
 
+#[cfg(feature = "arch_msp430")]
+use super::msp430;
+
 #[cfg(feature = "arch_ppc")]
 use super::ppc;
 
@@ -35,7 +39,7 @@ use super::mips_common;
 // We derive Clone here because the PcManager
 // is saved and restored in context_save and
 // context_restore using .clone().
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PcManager {
     #[cfg(feature = "arch_aarch64")]
     Aarch64(aarch64::StandardPcManager),
@@ -45,6 +49,8 @@ pub enum PcManager {
     Arm(arm::StandardPcManager),
     #[cfg(feature = "arch_bfin")]
     Blackfin(blackfin::StandardPcManager),
+    #[cfg(feature = "arch_msp430")]
+    Msp430(msp430::StandardPcManager),
     #[cfg(feature = "arch_ppc")]
     Ppc(ppc::StandardPpcPcManager),
     #[cfg(feature = "arch_superh")]
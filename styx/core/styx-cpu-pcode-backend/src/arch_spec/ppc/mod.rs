@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: BSD-2-Clause
 
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use styx_cpu_type::arch::ppc32::Ppc32Register;
 use styx_pcode::pcode::VarnodeData;
+use styx_processor::cpu::CpuBackendExt;
 
-use crate::{PcodeBackend, DEFAULT_REG_ALLOCATION};
+use crate::{backend_helper::BackendHelper, PcodeBackend, DEFAULT_REG_ALLOCATION};
 
 use super::{
     pc_manager::{apply_difference, PcOverflow},
@@ -13,6 +16,9 @@ use super::{
 pub mod call_other;
 pub mod ppc4xx;
 
+/// Wait state enable, the cpu idles until an interrupt clears it.
+const MSR_WE: u32 = 1 << 18;
+
 fn ppc_common<Sla>(spec: &mut super::ArchSpecBuilder<Sla, PcodeBackend>) {
     spec.set_pc_manager(StandardPpcPcManager::default().into());
 
@@ -26,7 +32,7 @@ fn ppc_common<Sla>(spec: &mut super::ArchSpecBuilder<Sla, PcodeBackend>) {
 ///
 /// I think *technically* this is incorrect however ppc code cannot read from
 /// the pc so not needed.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardPpcPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -60,10 +66,16 @@ impl ArchPcManager for StandardPpcPcManager {
     fn post_execute(
         &mut self,
         bytes_consumed: u64,
-        _backend: &mut PcodeBackend,
+        backend: &mut PcodeBackend,
         _regs_written: &mut SmallVec<[VarnodeData; DEFAULT_REG_ALLOCATION]>,
         _total_pcodes: usize,
     ) -> Result<(), PcOverflow> {
+        // MSR[WE] is cleared by the interrupt entry, until then the cpu waits
+        let msr = backend.read_register::<u32>(Ppc32Register::Msr).unwrap();
+        if msr & MSR_WE != 0 {
+            backend.set_wait_requested(true);
+        }
+
         self.internal_pc = self
             .internal_pc
             .checked_add(bytes_consumed)
@@ -14,6 +14,7 @@ use super::{
     ArchPcManager, GeneratorHelp,
 };
 use crate::{pcode_gen::GeneratePcodeError, PcodeBackend, DEFAULT_REG_ALLOCATION};
+use serde::{Deserialize, Serialize};
 use smallvec::{smallvec, SmallVec};
 use styx_pcode::pcode::VarnodeData;
 use styx_pcode_translator::ContextOption;
@@ -21,7 +22,7 @@ use styx_pcode_translator::ContextOption;
 /// Program Counter manager for SuperH processors.
 ///
 /// Copied from Blackfin
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardPcManager {
     isa_pc: u64,
     internal_pc: u64,
@@ -69,7 +70,7 @@ impl ArchPcManager for StandardPcManager {
 }
 
 /// [GeneratorHelp] for SuperH processors. Does nothing at the moment.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StandardGeneratorHelper;
 impl GeneratorHelp for StandardGeneratorHelper {
     fn pre_fetch(
@@ -11,7 +11,7 @@ use crate::{
 
 /// `Sleep_Standby` instruction
 ///
-/// Used by SH1 SH2 and SH2A
+/// Used by SH1 SH2 and SH2A, the cpu waits for an interrupt.
 #[derive(Debug)]
 pub struct SleepStandby;
 
@@ -26,7 +26,7 @@ impl<T: CpuBackend> CallOtherCallback<T> for SleepStandby {
     ) -> Result<PCodeStateChange, CallOtherHandleError> {
         debug!("Sleep_Standby called");
 
-        Ok(PCodeStateChange::Fallthrough)
+        Ok(PCodeStateChange::WaitForInterrupt)
     }
 }
 
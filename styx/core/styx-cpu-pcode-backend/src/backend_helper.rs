@@ -127,6 +127,13 @@ pub trait BackendHelper<ExecuteSingleData, PcodesContainer>:
         res
     }
 
+    /// Clears wait_requested and returns the previous result.
+    fn wait_request_check_and_reset(&mut self) -> bool {
+        let res = self.wait_requested();
+        self.set_wait_requested(false);
+        res
+    }
+
     fn pre_execute_hooks(
         &mut self,
         mmu: &mut Mmu,
@@ -143,6 +150,16 @@ pub trait BackendHelper<ExecuteSingleData, PcodesContainer>:
     /// This should write that field.
     fn set_stop_requested(&mut self, stop_requested: bool);
 
+    /// The trait requires the implementation struct to contain a bool field called
+    /// `wait_requested`, set when an instruction waits for an interrupt.
+    /// This should read that field.
+    fn wait_requested(&self) -> bool;
+
+    /// The trait requires the implementation struct to contain a bool field called
+    /// `wait_requested`, set when an instruction waits for an interrupt.
+    /// This should write that field.
+    fn set_wait_requested(&mut self, wait_requested: bool);
+
     /// Execute a single instruction. The `pcodes` passed in will be empty.
     /// The implementation must use the backing program counter,
     /// to fetch/decode pcodes for the current instruction,
@@ -200,6 +217,7 @@ pub trait BackendHelper<ExecuteSingleData, PcodesContainer>:
             });
         }
         self.set_stop_requested(false);
+        self.set_wait_requested(false);
         let mut current_stop = state.check_done();
         let mut pcodes = Vec::with_capacity(20);
         let mut last_val = None;
@@ -240,6 +258,13 @@ pub trait BackendHelper<ExecuteSingleData, PcodesContainer>:
             }
 
             current_stop = state.increment_instruction_count();
+            // the cpu idles after a waiting instruction, the executor decides how long
+            if self.wait_request_check_and_reset() {
+                current_stop = Some(ExecutionReport::new(
+                    TargetExitReason::WaitForInterrupt,
+                    state.current_instruction_count,
+                ));
+            }
             let stop_requested = self.stop_request_check_and_reset();
             trace!("current stop bool: {stop_requested}");
             current_stop = current_stop.or({
@@ -78,3 +78,30 @@ impl<T: CpuBackend> CallOtherCallback<T> for EmptyCallback {
         Ok(PCodeStateChange::Fallthrough)
     }
 }
+
+/// [CallOtherCallback] for instructions that idle until an interrupt, like `wfi`.
+///
+/// The instruction completes and execution stops, see [PCodeStateChange::WaitForInterrupt]. The
+/// executor then skips ahead to the next peripheral event.
+#[derive(Debug)]
+pub struct WaitForInterruptCallOther {
+    debug_string: Box<str>,
+}
+impl WaitForInterruptCallOther {
+    pub fn new(debug_string: Box<str>) -> Self {
+        Self { debug_string }
+    }
+}
+impl<T: CpuBackend> CallOtherCallback<T> for WaitForInterruptCallOther {
+    fn handle(
+        &mut self,
+        _cpu: &mut dyn CallOtherCpu<T>,
+        _mmu: &mut Mmu,
+        _ev: &mut EventController,
+        _inputs: &[VarnodeData],
+        _output: Option<&VarnodeData>,
+    ) -> Result<PCodeStateChange, CallOtherHandleError> {
+        trace!("{}", self.debug_string);
+        Ok(PCodeStateChange::WaitForInterrupt)
+    }
+}
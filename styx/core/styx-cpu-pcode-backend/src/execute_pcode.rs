@@ -18,13 +18,15 @@ use crate::{
 };
 use log::trace;
 use smallvec::SmallVec;
-use styx_errors::anyhow::anyhow;
+use styx_cpu_type::TargetExitReason;
+use styx_errors::anyhow::{anyhow, Context};
+use styx_errors::UnknownError;
 use styx_pcode::pcode::{Opcode, Pcode, SpaceId, SpaceName, VarnodeData};
 use styx_processor::{
     core::{Exception, HandleExceptionAction},
     cpu::CpuBackend,
     event_controller::EventController,
-    hooks::MemFaultData,
+    hooks::{CompareKind, MemFaultData},
     memory::{MemoryOperationError, Mmu, MmuOpError},
 };
 
@@ -111,6 +113,10 @@ pub fn execute_pcode<T: BasePcodeExecutor<T>>(
     isa_pc: u64,
     regs_written: &mut SmallVec<[VarnodeData; DEFAULT_REG_ALLOCATION]>,
 ) -> PCodeStateChange {
+    if let Err(err) = trigger_compare_hooks(pcode, cpu, mmu, ev, isa_pc) {
+        return PCodeStateChange::Exit(TargetExitReason::GeneralFault(format!("{err:?}")));
+    }
+
     let s = execute_pcode_inner(pcode, cpu, mmu, ev);
 
     // Allows it to get dropped after this
@@ -152,6 +158,51 @@ pub fn execute_pcode<T: BasePcodeExecutor<T>>(
     }
 }
 
+/// Comparison reported to compare hooks for `opcode`, if any.
+fn compare_kind(opcode: Opcode) -> Option<CompareKind> {
+    match opcode {
+        Opcode::IntEqual => Some(CompareKind::Equal),
+        Opcode::IntNotEqual => Some(CompareKind::NotEqual),
+        Opcode::IntLess => Some(CompareKind::Less),
+        Opcode::IntLessEqual => Some(CompareKind::LessEqual),
+        Opcode::IntSLess => Some(CompareKind::SignedLess),
+        Opcode::IntSLessEqual => Some(CompareKind::SignedLessEqual),
+        _ => None,
+    }
+}
+
+/// Triggers compare hooks if `pcode` is an integer comparison.
+///
+/// The operands are read before the comparison executes, without triggering register or memory
+/// hooks, in case the output overwrites one of them.
+fn trigger_compare_hooks<T: BasePcodeExecutor<T>>(
+    pcode: &Pcode,
+    cpu: &mut T,
+    mmu: &mut Mmu,
+    ev: &mut EventController,
+    isa_pc: u64,
+) -> Result<(), UnknownError> {
+    let Some(kind) = compare_kind(pcode.opcode) else {
+        return Ok(());
+    };
+    if cpu.hook_manager().compare_hook_count()? == 0 {
+        return Ok(());
+    }
+
+    let left = cpu.get_value_mmu(mmu, pcode.get_input(0))?;
+    let right = cpu.get_value_mmu(mmu, pcode.get_input(1))?;
+    HookManager::trigger_compare_hook(
+        cpu,
+        mmu,
+        ev,
+        isa_pc,
+        kind,
+        left.size() as u32,
+        left.to_u128().with_context(|| "bad compare operand")?,
+        right.to_u128().with_context(|| "bad compare operand")?,
+    )
+}
+
 fn execute_pcode_inner<'a, B: BasePcodeExecutor<B>>(
     pcode: &'a Pcode,
     cpu: &mut B,
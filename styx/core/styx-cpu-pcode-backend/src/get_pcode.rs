@@ -10,7 +10,9 @@ use styx_processor::{
     cpu::CpuBackend,
     event_controller::{EventController, ExceptionNumber},
     hooks::{MemFaultData, Resolution},
-    memory::{MemoryOperationError, MemoryPermissions, Mmu, MmuOpError},
+    memory::{
+        MemoryOperation, MemoryOperationError, MemoryPermissions, MemoryType, Mmu, MmuOpError,
+    },
 };
 use tap::TryConv;
 use thiserror::Error;
@@ -73,21 +75,88 @@ impl TryFrom<GetPcodeError> for PcodeFetchException {
     }
 }
 
+/// For use with `PcodeBackend`. Applies the context options requested by the generator helper.
+///
+/// With the translation cache enabled the options are applied to the translator and cache
+/// immediately and the cache is synced with writes recorded by the [Mmu]. Otherwise the options
+/// are returned to be passed to [get_pcode_at_address].
+fn pre_fetch_context(
+    cpu: &mut PcodeBackend,
+    mmu: &mut Mmu,
+) -> SmallVec<[ContextOption; CONTEXT_OPTION_LEN]> {
+    let mut helper = cpu.pcode_generator.helper.take().unwrap();
+    let ctx_opts = helper.pre_fetch(cpu).unwrap();
+    cpu.pcode_generator.helper = Some(helper);
+
+    if cpu.pcode_config.disable_translation_cache {
+        return ctx_opts;
+    }
+
+    if !ctx_opts.is_empty() {
+        cpu.pcode_generator.set_context_options(&ctx_opts);
+        cpu.translation_cache.apply_context(&ctx_opts);
+    }
+    cpu.translation_cache.sync(mmu.code_cache());
+    SmallVec::new()
+}
+
 /// For use with `PcodeBackend`. This function wraps `get_pcode_at_address`, and
-/// extracts the needed context options and current PC, which require
-/// `PcodeBackend`-specific function calls.
+/// extracts the needed context options, which require `PcodeBackend`-specific
+/// function calls, and goes through the translation cache.
 fn get_pcode_for_pcode_backend(
     cpu: &mut PcodeBackend,
+    addr: u64,
     pcodes: &mut Vec<Pcode>,
     mmu: &mut Mmu,
     ev: &mut EventController,
 ) -> Result<u64, GetPcodeError> {
-    let addr = cpu.pc_manager.as_mut().unwrap().internal_pc();
-    let mut helper = cpu.pcode_generator.helper.take().unwrap();
-    let ctx_opts = helper.pre_fetch(cpu).unwrap();
-    cpu.pcode_generator.helper = Some(helper);
+    let ctx_opts = pre_fetch_context(cpu, mmu);
+    if cpu.pcode_config.disable_translation_cache {
+        return get_pcode_at_address(cpu, addr, pcodes, &ctx_opts, mmu, ev);
+    }
 
-    get_pcode_at_address(cpu, addr, pcodes, &ctx_opts, mmu, ev)
+    // let the translator report any translation errors
+    let Ok(phys_addr) = mmu.translate_va(addr, MemoryOperation::Read, MemoryType::Code, cpu) else {
+        return get_pcode_at_address(cpu, addr, pcodes, &ctx_opts, mmu, ev);
+    };
+
+    if let Some(bytes) = cpu.translation_cache.get(addr, phys_addr, pcodes) {
+        trace!("translation cache hit @ 0x{addr:X}");
+        return Ok(bytes);
+    }
+
+    let start = pcodes.len();
+    let bytes = get_pcode_at_address(cpu, addr, pcodes, &ctx_opts, mmu, ev)?;
+    cpu.translation_cache
+        .insert(addr, phys_addr, &pcodes[start..], bytes, mmu.code_cache());
+    Ok(bytes)
+}
+
+/// End address of the cached basic block starting at `addr`, see
+/// [BackendHelper::find_first_basic_block()](crate::backend_helper::BackendHelper::find_first_basic_block).
+pub(crate) fn cached_basic_block(cpu: &mut PcodeBackend, mmu: &mut Mmu, addr: u64) -> Option<u64> {
+    if cpu.pcode_config.disable_translation_cache {
+        return None;
+    }
+    // the context could have changed since the last fetch
+    pre_fetch_context(cpu, mmu);
+
+    let phys_addr = mmu
+        .translate_va(addr, MemoryOperation::Read, MemoryType::Code, cpu)
+        .ok()?;
+    cpu.translation_cache.get_block(addr, phys_addr)
+}
+
+/// Cache the end address of the basic block starting at `addr`.
+pub(crate) fn cache_basic_block(cpu: &mut PcodeBackend, mmu: &mut Mmu, addr: u64, end: u64) {
+    if cpu.pcode_config.disable_translation_cache {
+        return;
+    }
+
+    if let Ok(phys_addr) = mmu.translate_va(addr, MemoryOperation::Read, MemoryType::Code, cpu) {
+        cpu.translation_cache
+            .insert_block(addr, phys_addr, end, mmu.code_cache());
+    }
 }
 
 /// thin wrapper to [pcode_gen::get_pcode].
@@ -203,8 +272,9 @@ pub(crate) fn fetch_pcode(
     mmu: &mut Mmu,
     ev: &mut EventController,
 ) -> Result<u64, FetchPcodeError> {
+    let addr = cpu.pc_manager.as_mut().unwrap().internal_pc();
     // attempt to fetch and translate pcodes
-    let result = get_pcode_for_pcode_backend(cpu, pcodes, mmu, ev);
+    let result = get_pcode_for_pcode_backend(cpu, addr, pcodes, mmu, ev);
     // return early on success, or save the error to see if we can resolve the target error
     let result_err = match result {
         Ok(success) => return Ok(success),
@@ -218,7 +288,8 @@ pub(crate) fn fetch_pcode(
     // if we fixed, try get pcodes again and error if another error occurs.
     trace!("did_fix: {did_fix:?}");
     if did_fix.fixed() {
-        let result = get_pcode_for_pcode_backend(cpu, pcodes, mmu, ev);
+        let addr = cpu.pc_manager.as_mut().unwrap().internal_pc();
+        let result = get_pcode_for_pcode_backend(cpu, addr, pcodes, mmu, ev);
         match result {
             Ok(bytes_consumed) => Ok(bytes_consumed),
             Err(get_pcode_err) => {
@@ -236,17 +307,14 @@ pub(crate) fn fetch_pcode(
 
 /// Generates instruction at address and returns true if branching or if unable to generate (e.g.
 /// unmapped memory).
-pub(crate) fn is_branching_instruction<
-    B: CpuBackend + HasConfig + HasPcodeGenerator<InnerCpuBackend = B> + 'static,
->(
-    cpu: &mut B,
+pub(crate) fn is_branching_instruction(
+    cpu: &mut PcodeBackend,
     pcodes: &mut Vec<Pcode>,
-    context_options: &SmallVec<[ContextOption; CONTEXT_OPTION_LEN]>,
     address: u64,
     mmu: &mut Mmu,
     ev: &mut EventController,
 ) -> (bool, u64) {
-    let bytes = match get_pcode_at_address(cpu, address, pcodes, context_options, mmu, ev) {
+    let bytes = match get_pcode_for_pcode_backend(cpu, address, pcodes, mmu, ev) {
         Ok(success) => success,
         _ => {
             // Failed decompile, end of basic block
@@ -1,13 +1,15 @@
 // SPDX-License-Identifier: BSD-2-Clause
-use styx_processor::hooks::{AddressRange, HookToken};
+use styx_processor::hooks::{AddressRange, HookControl, HookToken};
 
 use std::fmt::Debug;
 use std::ops::RangeBounds;
+use std::sync::Arc;
 
 pub struct AddrHookContainer<H> {
     range: AddressRange,
     pub callback: H,
     pub token: HookToken,
+    pub control: Arc<HookControl>,
 }
 impl<T> Debug for AddrHookContainer<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -48,12 +50,23 @@ impl<H> AddrHookBucket<H> {
     }
 
     /// Add a hook to this bucket that that is activated when a given address is in `address_range`.
-    pub fn add_hook(&mut self, token: HookToken, address_range: AddressRange, callback: H) {
+    pub fn add_hook(
+        &mut self,
+        token: HookToken,
+        control: Arc<HookControl>,
+        address_range: AddressRange,
+        callback: H,
+    ) {
         let new_hook = AddrHookContainer {
             range: address_range,
             callback,
             token,
+            control,
         };
         self.0.push(new_hook);
     }
+
+    pub(crate) fn num_hooks(&self) -> usize {
+        self.0.len()
+    }
 }
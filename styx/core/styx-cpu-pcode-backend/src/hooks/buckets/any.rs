@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: BSD-2-Clause
-use styx_processor::hooks::HookToken;
+use styx_processor::hooks::{HookControl, HookToken};
 
 use std::fmt::Debug;
+use std::sync::Arc;
 
 pub struct HookContainer<H> {
     pub callback: H,
     pub token: HookToken,
+    pub control: Arc<HookControl>,
 }
 #[derive(derive_more::Debug)]
 pub(crate) struct HookBucket<H>(Vec<HookContainer<H>>);
@@ -42,8 +44,12 @@ impl<H> HookBucket<H> {
     }
 
     /// Add a hook to this bucket that that is activated when a given address is in `address_range`.
-    pub fn add_hook(&mut self, token: HookToken, callback: H) {
-        let new_hook = HookContainer { callback, token };
+    pub fn add_hook(&mut self, token: HookToken, control: Arc<HookControl>, callback: H) {
+        let new_hook = HookContainer {
+            callback,
+            token,
+            control,
+        };
         self.0.push(new_hook);
     }
 
@@ -1,13 +1,15 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use styx_cpu_type::arch::backends::ArchRegister;
-use styx_processor::hooks::HookToken;
+use styx_processor::hooks::{HookControl, HookToken};
 
 use std::fmt::Debug;
+use std::sync::Arc;
 
 pub struct RegisterHookContainer<H> {
     register: ArchRegister,
     pub callback: H,
     pub token: HookToken,
+    pub control: Arc<HookControl>,
 }
 impl<T> Debug for RegisterHookContainer<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -48,11 +50,18 @@ impl<H> RegisterHookBucket<H> {
         }
     }
 
-    pub fn add_hook(&mut self, token: HookToken, register: ArchRegister, callback: H) {
+    pub fn add_hook(
+        &mut self,
+        token: HookToken,
+        control: Arc<HookControl>,
+        register: ArchRegister,
+        callback: H,
+    ) {
         let new_hook = RegisterHookContainer {
             register,
             callback,
             token,
+            control,
         };
         self.0.push(new_hook);
     }
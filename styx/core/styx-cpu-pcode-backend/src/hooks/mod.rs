@@ -25,6 +25,9 @@
 //!
 mod buckets;
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use buckets::address::AddrHookBucket;
 use buckets::any::HookBucket;
 use buckets::register::RegisterHookBucket;
@@ -38,9 +41,9 @@ use styx_errors::anyhow::{anyhow, Context};
 use styx_errors::{ErrorBuffer, UnknownError};
 use styx_processor::event_controller::ExceptionNumber;
 use styx_processor::hooks::{
-    BlockHook, DeleteHookError, HookToken, InterruptHook, InvalidInstructionHook, MemFaultData,
-    MemoryReadHook, MemoryWriteHook, ProtectionFaultHook, RegisterReadHook, RegisterWriteHook,
-    Resolution, UnmappedFaultHook,
+    BlockHook, CompareHook, CompareKind, DeleteHookError, HookControl, HookControlError, HookToken,
+    InterruptHook, InvalidInstructionHook, MemFaultData, MemoryReadHook, MemoryWriteHook,
+    ProtectionFaultHook, RegisterReadHook, RegisterWriteHook, Resolution, UnmappedFaultHook,
 };
 use styx_processor::memory::MemoryPermissions;
 use styx_processor::{
@@ -68,6 +71,11 @@ pub struct HookManager {
     unmapped_fault_hooks: OptionalHookBucket<AddrHookBucket<Box<dyn UnmappedFaultHook>>>,
     register_read_hooks: OptionalHookBucket<RegisterHookBucket<Box<dyn RegisterReadHook>>>,
     register_write_hooks: OptionalHookBucket<RegisterHookBucket<Box<dyn RegisterWriteHook>>>,
+    compare_hooks: OptionalHookBucket<AddrHookBucket<Box<dyn CompareHook>>>,
+
+    /// Controls of all hooks, kept outside the buckets so they stay reachable while a bucket is
+    /// taken for triggering.
+    controls: HashMap<HookToken, Arc<HookControl>>,
 }
 
 /// Used to mock the main backend struct in testing.
@@ -88,6 +96,20 @@ impl Hookable for PcodeBackend {
     fn delete_hook(&mut self, token: HookToken) -> Result<(), DeleteHookError> {
         self.hook_manager().delete_hook(token)
     }
+
+    fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.hook_manager.hook_control(token)
+    }
+}
+
+/// Check the [`HookControl`] of an activated hook before calling it.
+///
+/// Errors from evaluating the hook condition are collected and the hook is skipped.
+fn should_run(control: &HookControl, proc: &mut CoreHandle, errors: &mut ErrorBuffer) -> bool {
+    control.should_run(proc).unwrap_or_else(|err| {
+        errors.push(err);
+        false
+    })
 }
 
 /// Hook bucket for hooks that are always activated, no matter the trigger data.
@@ -151,63 +173,88 @@ impl HookManager {
     ) -> Result<HookToken, AddHookError> {
         let token = self.get_unique_token();
         trace!("adding hook: {hook:?}[{token:?}]",);
+        let control = Arc::new(HookControl::default());
 
         match hook {
             StyxHook::Code(range, hook) => {
-                self.code_hooks.available()?.add_hook(token, range, hook);
+                self.code_hooks
+                    .available()?
+                    .add_hook(token, control.clone(), range, hook);
             }
             StyxHook::MemoryRead(range, hook) => {
                 self.memory_read_hooks
                     .available()?
-                    .add_hook(token, range, hook);
+                    .add_hook(token, control.clone(), range, hook);
             }
             StyxHook::MemoryWrite(range, hook) => {
                 self.memory_write_hooks
                     .available()?
-                    .add_hook(token, range, hook);
+                    .add_hook(token, control.clone(), range, hook);
             }
             StyxHook::Interrupt(hook) => {
-                self.interrupt_hooks.available()?.add_hook(token, hook);
+                self.interrupt_hooks
+                    .available()?
+                    .add_hook(token, control.clone(), hook);
             }
             StyxHook::InvalidInstruction(hook) => {
                 self.invalid_instruction_hooks
                     .available()?
-                    .add_hook(token, hook);
+                    .add_hook(token, control.clone(), hook);
             }
             StyxHook::ProtectionFault(range, hook) => {
-                self.protection_fault_hooks
-                    .available()?
-                    .add_hook(token, range, hook);
+                self.protection_fault_hooks.available()?.add_hook(
+                    token,
+                    control.clone(),
+                    range,
+                    hook,
+                );
             }
             StyxHook::UnmappedFault(range, hook) => {
-                self.unmapped_fault_hooks
-                    .available()?
-                    .add_hook(token, range, hook);
+                self.unmapped_fault_hooks.available()?.add_hook(
+                    token,
+                    control.clone(),
+                    range,
+                    hook,
+                );
             }
             StyxHook::Block(hook) => {
-                self.block_hooks.available()?.add_hook(token, hook);
+                self.block_hooks
+                    .available()?
+                    .add_hook(token, control.clone(), hook);
             }
             StyxHook::RegisterRead(register, hook) => {
                 if config.register_read_hooks {
-                    self.register_read_hooks
-                        .available()?
-                        .add_hook(token, register, hook);
+                    self.register_read_hooks.available()?.add_hook(
+                        token,
+                        control.clone(),
+                        register,
+                        hook,
+                    );
                 } else {
                     return Err(AddHookError::HookTypeNotSupported);
                 }
             }
             StyxHook::RegisterWrite(register, hook) => {
                 if config.register_write_hooks {
-                    self.register_write_hooks
-                        .available()?
-                        .add_hook(token, register, hook);
+                    self.register_write_hooks.available()?.add_hook(
+                        token,
+                        control.clone(),
+                        register,
+                        hook,
+                    );
                 } else {
                     return Err(AddHookError::HookTypeNotSupported);
                 }
             }
+            StyxHook::Compare(range, hook) => {
+                self.compare_hooks
+                    .available()?
+                    .add_hook(token, control.clone(), range, hook);
+            }
             _ => return Err(AddHookError::HookTypeNotSupported),
         }
 
+        self.controls.insert(token, control);
         Ok(token)
     }
 
@@ -266,7 +313,23 @@ impl HookManager {
                 .available()
                 .ok()
                 .and_then(|a| a.delete_hook(token)))
-            .ok_or(DeleteHookError::HookDoesNotExist)
+            .or(self
+                .compare_hooks
+                .available()
+                .ok()
+                .and_then(|a| a.delete_hook(token)))
+            .ok_or(DeleteHookError::HookDoesNotExist)?;
+
+        self.controls.remove(&token);
+        Ok(())
+    }
+
+    /// Get the [`HookControl`] of a hook, available even while its bucket is being triggered.
+    pub fn hook_control(&self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.controls
+            .get(&token)
+            .cloned()
+            .ok_or(HookControlError::HookDoesNotExist)
     }
 
     pub fn trigger_code_hook<T: HasHookManager + CpuBackend>(
@@ -280,7 +343,10 @@ impl HookManager {
         trace!("Triggering code hook on 0x{addr:X}.");
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate(addr) {
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler);
             if let Err(err) = hook_callback_res {
                 errors.push(err);
@@ -309,7 +375,10 @@ impl HookManager {
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate(addr) {
             trace!("exec token {:?}.", hook.token);
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, addr, size, data);
             if let Err(err) = hook_callback_res {
                 errors.push(err);
@@ -338,7 +407,10 @@ impl HookManager {
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate(addr) {
             trace!("exec token {:?}.", hook.token);
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, addr, size, data);
             if let Err(err) = hook_callback_res {
                 errors.push(err);
@@ -364,7 +436,10 @@ impl HookManager {
         let mut errors = ErrorBuffer::new();
         let mut fixed = Resolution::default();
         for hook in hook_bucket.activate() {
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler);
             match hook_callback_res {
                 Ok(is_now_fixed) => fixed = fixed & is_now_fixed,
@@ -394,7 +469,10 @@ impl HookManager {
         trace!("Triggering interrupt hook {irqn}");
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate() {
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, irqn);
             match hook_callback_res {
                 Ok(_) => (),
@@ -426,7 +504,10 @@ impl HookManager {
         let mut fixed = Resolution::default();
         for hook in hook_bucket.activate(addr) {
             trace!("exec token {:?}.", hook.token);
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res =
                 hook.callback
                     .call(core_handler, addr, size, permission, fault_data);
@@ -462,7 +543,10 @@ impl HookManager {
         let mut fixed = Resolution::default();
         for hook in hook_bucket.activate(addr) {
             trace!("exec token {:?}.", hook.token);
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, addr, size, fault_data);
             match hook_callback_res {
                 Ok(is_now_fixed) => fixed = fixed & is_now_fixed,
@@ -493,7 +577,10 @@ impl HookManager {
         trace!("Triggering block hook on 0x{addr:X}.");
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate() {
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, addr, size);
             if let Err(err) = hook_callback_res {
                 errors.push(err);
@@ -512,6 +599,45 @@ impl HookManager {
         Ok(self.block_hooks.available()?.num_hooks())
     }
 
+    /// Trigger compare hooks for a comparison executed by the instruction at `pc`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn trigger_compare_hook<T: HasHookManager + CpuBackend>(
+        cpu: &mut T,
+        mmu: &mut Mmu,
+        ev: &mut EventController,
+        pc: u64,
+        kind: CompareKind,
+        size: u32,
+        left: u128,
+        right: u128,
+    ) -> Result<(), UnknownError> {
+        let mut hook_bucket = cpu.hook_manager().compare_hooks.take()?;
+
+        trace!("Triggering compare hook on 0x{pc:X}.");
+        let mut errors = ErrorBuffer::new();
+        for hook in hook_bucket.activate(pc) {
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
+            let hook_callback_res = hook.callback.call(core_handler, kind, size, left, right);
+            if let Err(err) = hook_callback_res {
+                errors.push(err);
+            }
+        }
+
+        // replace hook bucket structure
+        cpu.hook_manager().compare_hooks.put_back(hook_bucket);
+
+        errors
+            .result()
+            .with_context(|| "error(s) from compare hook triggerings")
+    }
+
+    pub(crate) fn compare_hook_count(&mut self) -> Result<usize, UnknownError> {
+        Ok(self.compare_hooks.available()?.num_hooks())
+    }
+
     pub fn trigger_register_read_hook<T: HasHookManager + CpuBackend>(
         cpu: &mut T,
         mmu: &mut Mmu,
@@ -524,7 +650,10 @@ impl HookManager {
         trace!("Triggering register read hook for {register}.");
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate(register) {
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, register, data);
             if let Err(err) = hook_callback_res {
                 errors.push(err);
@@ -552,7 +681,10 @@ impl HookManager {
         trace!("Triggering register write for {register}.");
         let mut errors = ErrorBuffer::new();
         for hook in hook_bucket.activate(register) {
-            let core_handler = CoreHandle::new(cpu, mmu, ev);
+            let mut core_handler = CoreHandle::new(cpu, mmu, ev);
+            if !should_run(&hook.control, &mut core_handler, &mut errors) {
+                continue;
+            }
             let hook_callback_res = hook.callback.call(core_handler, register, data);
             if let Err(err) = hook_callback_res {
                 errors.push(err);
@@ -573,13 +705,14 @@ impl HookManager {
 #[cfg(test)]
 mod tests {
     use std::sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     };
 
     use styx_processor::{
         core::ProcessorCore,
         cpu::{CpuBackend, ExecutionReport},
+        hooks::{Comparison, HookCondition},
     };
 
     use super::*;
@@ -593,6 +726,7 @@ mod tests {
 
         captain.add_hook(
             token,
+            Default::default(),
             (0x1000..0x1100).into(),
             Box::new(read_memory_callback),
         );
@@ -622,6 +756,10 @@ mod tests {
         fn delete_hook(&mut self, token: HookToken) -> Result<(), DeleteHookError> {
             self.hook_manager().delete_hook(token)
         }
+
+        fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+            self.hook_manager().hook_control(token)
+        }
     }
     impl CpuBackend for DummyPcodeBackend {
         fn read_register_raw(
@@ -711,4 +849,51 @@ mod tests {
             .unwrap();
         assert!(is_triggered.load(Ordering::SeqCst));
     }
+
+    #[test]
+    fn test_hook_control() {
+        let mut cpu = DummyPcodeBackend::default();
+        let calls = Arc::new(AtomicU64::new(0));
+        let code_hook = {
+            let calls = calls.clone();
+            move |_: CoreHandle| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        };
+        let token = cpu
+            .add_conditional_hook(
+                StyxHook::code(0x1000, code_hook),
+                HookCondition::hits(Comparison::Greater, 1),
+            )
+            .unwrap();
+
+        let mut core = ProcessorCore::dummy();
+        let mut trigger = |cpu: &mut DummyPcodeBackend| {
+            HookManager::trigger_code_hook(cpu, &mut core.mmu, &mut core.event_controller, 0x1000)
+                .unwrap()
+        };
+
+        // first hit is filtered by the condition
+        trigger(&mut cpu);
+        trigger(&mut cpu);
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert_eq!(2, cpu.hook_hits(token).unwrap());
+
+        // disabled hooks are skipped and not counted
+        cpu.disable_hook(token).unwrap();
+        trigger(&mut cpu);
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert_eq!(2, cpu.hook_hits(token).unwrap());
+
+        cpu.enable_hook(token).unwrap();
+        trigger(&mut cpu);
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+
+        cpu.delete_hook(token).unwrap();
+        assert!(matches!(
+            cpu.hook_control(token),
+            Err(HookControlError::HookDoesNotExist)
+        ));
+    }
 }
@@ -17,7 +17,7 @@ use smallvec::smallvec;
 use self::{
     hooks::HookManager,
     memory::{sized_value::SizedValue, space_manager::SpaceManager},
-    pcode_gen::GhidraPcodeGenerator,
+    pcode_gen::{GhidraPcodeGenerator, TranslationCache},
     register_manager::RegisterManager,
 };
 pub use arch_spec::HexagonPcodeBackend;
@@ -27,6 +27,7 @@ use derivative::Derivative;
 use log::trace;
 use memory::{mmu_store::MmuSpace, space_manager::VarnodeError};
 use pcode_gen::GeneratePcodeError;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use styx_cpu_type::{
     arch::{
@@ -41,12 +42,13 @@ use styx_errors::{
     styx_cpu::StyxCpuBackendError,
     UnknownError,
 };
-use styx_pcode::pcode::{Pcode, VarnodeData};
+use styx_pcode::pcode::{Pcode, SpaceName, VarnodeData};
 use styx_processor::{
     core::{builder::BuildProcessorImplArgs, ExceptionBehavior},
     cpu::{CpuBackend, ExecutionReport, ReadRegisterError, WriteRegisterError},
     event_controller::{EventController, ExceptionNumber},
     memory::Mmu,
+    snapshot::{ComponentState, CpuSnapshot, MemorySpanSnapshot},
 };
 use types::*;
 
@@ -73,6 +75,9 @@ pub(crate) enum PCodeStateChange {
     /// Trigger interrupt as soon as possible. This is used for a TLB exception.
     Exception(i32),
     Exit(TargetExitReason),
+    /// Finish the instruction, then stop execution with [TargetExitReason::WaitForInterrupt].
+    /// Used for `wfi` and similar instructions.
+    WaitForInterrupt,
 }
 
 struct MachineState {
@@ -104,6 +109,16 @@ impl MachineState {
     }
 }
 
+/// [PcodeBackend] state that is not in the architecture registers, saved in
+/// [CpuSnapshot::backend].
+#[derive(Serialize, Deserialize)]
+struct PcodeBackendState {
+    pc_manager: Option<PcManager>,
+    generator_helper: Option<Box<GeneratorHelper>>,
+    /// Whole register space, see [PcodeBackend::saved_register_space].
+    register_space: MemorySpanSnapshot,
+}
+
 /// The Pcode Cpu Backend
 ///
 /// # Behaviors
@@ -130,6 +145,10 @@ pub struct PcodeBackend {
     /// This should be accessed through [Self::stop_request_check_and_reset()] to ensure it is
     /// cleared after handling.
     stop_requested: bool,
+    /// Did the last instruction wait for an interrupt?
+    ///
+    /// Accessed through [Self::wait_request_check_and_reset()].
+    wait_requested: bool,
 
     #[derivative(Debug = "ignore")]
     arch_def: Box<dyn ArchitectureDef>,
@@ -141,11 +160,17 @@ pub struct PcodeBackend {
     /// Was the last instruction a branch instruction?
     last_was_branch: bool,
     pcode_config: PcodeBackendConfiguration,
+    /// Translated instructions and basic blocks, see
+    /// [PcodeBackendConfiguration::disable_translation_cache].
+    translation_cache: TranslationCache,
 
     // holds saved register state
     saved_reg_context: BTreeMap<ArchRegister, RegisterValue>,
     saved_pc_manager: Option<PcManager>,
     saved_generator_helper: Option<Box<GeneratorHelper>>,
+    /// Whole register space, holds backend registers that are not architecture registers, e.g.
+    /// the banked registers of inactive modes.
+    saved_register_space: Vec<u8>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -153,6 +178,13 @@ pub struct PcodeBackendConfiguration {
     pub register_read_hooks: bool,
     pub register_write_hooks: bool,
     pub exception: ExceptionBehavior,
+    /// Translate every instruction with sleigh instead of caching translated instructions and
+    /// basic blocks.
+    ///
+    /// Cached translations are invalidated by writes through the [Mmu] and on changes to its
+    /// physical memory backend. Disable the cache if code is modified without going through the
+    /// [Mmu].
+    pub disable_translation_cache: bool,
 }
 
 impl From<&BuildProcessorImplArgs> for PcodeBackendConfiguration {
@@ -209,6 +241,7 @@ impl PcodeBackend {
             arch_def,
             // the backend does not have stop requested initially
             stop_requested: false,
+            wait_requested: false,
             space_manager,
             pcode_generator,
             endian,
@@ -220,9 +253,11 @@ impl PcodeBackend {
             ),
             last_was_branch: false,
             pcode_config: config.clone(),
+            translation_cache: TranslationCache::default(),
             saved_reg_context: BTreeMap::default(),
             saved_pc_manager: None,
             saved_generator_helper: None,
+            saved_register_space: Vec::new(),
         }
     }
 
@@ -253,6 +288,10 @@ impl BackendHelper<u64, Pcode> for PcodeBackend {
         ev: &mut EventController,
         initial_pc: u64,
     ) -> u64 {
+        if let Some(end) = get_pcode::cached_basic_block(self, mmu, initial_pc) {
+            return end;
+        }
+
         let mut pcodes = Vec::with_capacity(16);
 
         // Maximum amount of bytes to search for the next branch.
@@ -262,29 +301,20 @@ impl BackendHelper<u64, Pcode> for PcodeBackend {
 
         // Does not matter if this is a branch instruction since it could be the beginning of
         // execution.
-
-        let mut helper = self.pcode_generator.helper.take().unwrap();
-        let ctx_opts = helper.pre_fetch(self).unwrap();
-        self.pcode_generator.helper = Some(helper);
-
-        let (_, bytes) =
-            is_branching_instruction(self, &mut pcodes, &ctx_opts, initial_pc, mmu, ev);
+        let (_, bytes) = is_branching_instruction(self, &mut pcodes, initial_pc, mmu, ev);
         instruction_pc += bytes;
 
         let mut stop_search = false;
         while !stop_search {
-            let mut helper = self.pcode_generator.helper.take().unwrap();
-            let ctx_opts = helper.pre_fetch(self).unwrap();
-            self.pcode_generator.helper = Some(helper);
-
             let (is_branch, bytes) =
-                is_branching_instruction(self, &mut pcodes, &ctx_opts, instruction_pc, mmu, ev);
+                is_branching_instruction(self, &mut pcodes, instruction_pc, mmu, ev);
 
             // Stop search if we found a branch OR we've gone over our max search
             stop_search = is_branch || (instruction_pc - initial_pc) > max_search;
             instruction_pc += bytes
         }
 
+        get_pcode::cache_basic_block(self, mmu, initial_pc, instruction_pc);
         instruction_pc
     }
     fn pre_execute_hooks(
@@ -311,6 +341,14 @@ impl BackendHelper<u64, Pcode> for PcodeBackend {
         self.stop_requested = stop_requested;
     }
 
+    fn wait_requested(&self) -> bool {
+        self.wait_requested
+    }
+
+    fn set_wait_requested(&mut self, wait_requested: bool) {
+        self.wait_requested = wait_requested;
+    }
+
     /// Execute a single machine instruction.
     fn execute_single(
         &mut self,
@@ -414,6 +452,10 @@ impl BackendHelper<u64, Pcode> for PcodeBackend {
                     return Ok(Ok(bytes_consumed)); // Don't increment PC
                 }
                 PCodeStateChange::Exit(reason) => return Ok(Err(reason)),
+                PCodeStateChange::WaitForInterrupt => {
+                    self.wait_requested = true;
+                    i += 1;
+                }
             }
         }
         let mut pc_manager = self.pc_manager.take().unwrap();
@@ -442,8 +484,9 @@ impl BackendHelper<u64, Pcode> for PcodeBackend {
 
 impl CpuBackend for PcodeBackend {
     fn read_register_raw(&mut self, reg: ArchRegister) -> Result<RegisterValue, ReadRegisterError> {
-        let data = if reg == self.pc_register().variant() {
-            SizedValue::from_u128(self.pc()? as u128, 4)
+        let pc_register = self.pc_register();
+        let data = if reg == pc_register.variant() {
+            SizedValue::from_u128(self.pc()? as u128, pc_register.byte_size().get() as u8)
         } else {
             RegisterManager::read_register(self, reg)
                 .map_err(|err| StyxCpuBackendError::GenericError(err.into()))
@@ -502,10 +545,14 @@ impl CpuBackend for PcodeBackend {
     fn set_pc(&mut self, value: u64) -> Result<(), UnknownError> {
         let mut pc_manager = self.pc_manager.take().unwrap();
         pc_manager.set_internal_pc(value, self, false);
-        let isa_pc = SizedValue::from_u128(pc_manager.isa_pc() as u128, 4);
+        let pc_register = self.pc_register();
+        // sized to the pc register, it is not 32 bits on every architecture
+        let isa_pc = SizedValue::from_u128(
+            pc_manager.isa_pc() as u128,
+            pc_register.byte_size().get() as u8,
+        );
         self.pc_manager = Some(pc_manager);
-        let pc_reg_variant = self.pc_register().variant();
-        RegisterManager::write_register(self, pc_reg_variant, isa_pc)?;
+        RegisterManager::write_register(self, pc_register.variant(), isa_pc)?;
         Ok(())
     }
 
@@ -523,6 +570,11 @@ impl CpuBackend for PcodeBackend {
             }
         }
 
+        self.saved_register_space.resize(REGISTER_SPACE_SIZE, 0);
+        self.space_manager
+            .read_chunk(&SpaceName::Register, 0, &mut self.saved_register_space)
+            .with_context(|| "could not save register space")?;
+
         self.saved_pc_manager = self.pc_manager.clone();
         self.saved_generator_helper = self.pcode_generator.helper.clone();
 
@@ -534,6 +586,11 @@ impl CpuBackend for PcodeBackend {
             return Err(anyhow!("attempting to restore from nothing"));
         }
 
+        // restore the raw registers first so the register writes below see the saved mode
+        self.space_manager
+            .write_chunk(&SpaceName::Register, 0, &self.saved_register_space)
+            .with_context(|| "could not restore register space")?;
+
         let reg_context = std::mem::take(&mut self.saved_reg_context);
 
         for register in reg_context.keys() {
@@ -547,6 +604,40 @@ impl CpuBackend for PcodeBackend {
 
         Ok(())
     }
+
+    fn snapshot(&mut self) -> Result<CpuSnapshot, UnknownError> {
+        let mut snapshot = CpuSnapshot::capture(self)?;
+
+        let mut register_space = vec![0; REGISTER_SPACE_SIZE];
+        self.space_manager
+            .read_chunk(&SpaceName::Register, 0, &mut register_space)
+            .with_context(|| "could not save register space")?;
+        snapshot.backend = ComponentState::new(&PcodeBackendState {
+            pc_manager: self.pc_manager.clone(),
+            generator_helper: self.pcode_generator.helper.clone(),
+            register_space: MemorySpanSnapshot::capture(0, &register_space),
+        })?;
+
+        Ok(snapshot)
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &CpuSnapshot) -> Result<(), UnknownError> {
+        let state: PcodeBackendState = snapshot.backend.get()?;
+
+        // like context_restore, the raw registers go first
+        let mut register_space = vec![0; REGISTER_SPACE_SIZE];
+        state.register_space.restore_into(0, &mut register_space)?;
+        self.space_manager
+            .write_chunk(&SpaceName::Register, 0, &register_space)
+            .with_context(|| "could not restore register space")?;
+
+        snapshot.restore(self)?;
+
+        self.pc_manager = state.pc_manager;
+        self.pcode_generator.helper = state.generator_helper;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1198,6 +1289,113 @@ mod arm_tests {
         backend.execute(&mut mmu, &mut ev, 1).unwrap();
     }
 
+    /// An IRQ taken in Supervisor mode must not clobber the Supervisor `sp` and `lr`.
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn test_irq_banked_registers() {
+        let mut mmu = Mmu::default();
+        let mut ev = EventController::default();
+        let mut backend = PcodeBackend::new_engine(
+            Arch::Arm,
+            ArmVariants::ArmCortexA9,
+            ArchEndian::LittleEndian,
+        );
+
+        // IRQ vector: use a different stack, then return to the interrupted instruction
+        let ks = Keystone::new(keystone_engine::Arch::ARM, keystone_engine::Mode::ARM)
+            .expect("Could not initialize Keystone engine");
+        let asm = ks
+            .asm("mov sp, #0x3000; subs pc, lr, #4".to_string(), 0x18)
+            .expect("Could not assemble");
+        mmu.code().write(0x18).bytes(&asm.bytes).unwrap();
+
+        // Supervisor mode, interrupts masked
+        let svc_cpsr = 0x1D3u32;
+        backend.write_register(ArmRegister::Cpsr, svc_cpsr).unwrap();
+        backend.write_register(ArmRegister::Sp, 0x2000u32).unwrap();
+        backend.write_register(ArmRegister::Lr, 0x44u32).unwrap();
+
+        // take the IRQ at 0x1000 the way the GIC does
+        backend.write_register(ArmRegister::Cpsr, 0x1D2u32).unwrap();
+        backend.write_register(ArmRegister::Spsr, svc_cpsr).unwrap();
+        backend.write_register(ArmRegister::Lr, 0x1004u32).unwrap();
+        backend.set_pc(0x18).unwrap();
+
+        backend.execute(&mut mmu, &mut ev, 2).unwrap();
+
+        assert_eq!(0x1000, backend.pc().unwrap());
+        assert_eq!(
+            svc_cpsr & 0x1F,
+            backend.read_register::<u32>(ArmRegister::Cpsr).unwrap() & 0x1F
+        );
+        assert_eq!(
+            0x2000,
+            backend.read_register::<u32>(ArmRegister::Sp).unwrap()
+        );
+        assert_eq!(0x44, backend.read_register::<u32>(ArmRegister::Lr).unwrap());
+
+        // the IRQ mode keeps its stack pointer
+        backend.write_register(ArmRegister::Cpsr, 0x1D2u32).unwrap();
+        assert_eq!(
+            0x3000,
+            backend.read_register::<u32>(ArmRegister::Sp).unwrap()
+        );
+        assert_eq!(
+            svc_cpsr,
+            backend.read_register::<u32>(ArmRegister::Spsr).unwrap()
+        );
+    }
+
+    /// Snapshots keep the banked registers of inactive modes and the coprocessor registers.
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn test_snapshot_banked_registers() {
+        use styx_cpu_type::arch::arm::{arm_coproc_registers::VBAR, CoProcessorValue};
+
+        let mut backend = PcodeBackend::new_engine(
+            Arch::Arm,
+            ArmVariants::ArmCortexA9,
+            ArchEndian::LittleEndian,
+        );
+        let svc_cpsr = 0x1D3u32;
+        let irq_cpsr = 0x1D2u32;
+
+        backend.write_register(ArmRegister::Cpsr, svc_cpsr).unwrap();
+        backend.write_register(ArmRegister::Sp, 0x2000u32).unwrap();
+        backend.write_register(ArmRegister::Cpsr, irq_cpsr).unwrap();
+        backend.write_register(ArmRegister::Sp, 0x3000u32).unwrap();
+        backend
+            .write_register_raw(VBAR.into(), VBAR.with_value(0x8000).into())
+            .unwrap();
+        backend.set_pc(0x100).unwrap();
+        let snapshot = backend.snapshot().unwrap();
+
+        backend.write_register(ArmRegister::Sp, 0x4000u32).unwrap();
+        backend.write_register(ArmRegister::Cpsr, svc_cpsr).unwrap();
+        backend.write_register(ArmRegister::Sp, 0x5000u32).unwrap();
+        backend
+            .write_register_raw(VBAR.into(), VBAR.with_value(0).into())
+            .unwrap();
+        backend.set_pc(0x200).unwrap();
+
+        backend.restore_snapshot(&snapshot).unwrap();
+        assert_eq!(0x100, backend.pc().unwrap());
+        assert_eq!(
+            0x3000,
+            backend.read_register::<u32>(ArmRegister::Sp).unwrap()
+        );
+        assert_eq!(
+            VBAR.with_value(0x8000),
+            backend.read_register::<CoProcessorValue>(VBAR).unwrap()
+        );
+
+        backend.write_register(ArmRegister::Cpsr, svc_cpsr).unwrap();
+        assert_eq!(
+            0x2000,
+            backend.read_register::<u32>(ArmRegister::Sp).unwrap()
+        );
+    }
+
     #[cfg_attr(miri, ignore)]
     #[test]
     fn test_store() {
@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Cache of translated instructions and basic blocks.
+//!
+//! Translating with sleigh is the most expensive part of executing an instruction in the
+//! [PcodeBackend](crate::PcodeBackend), so the [TranslationCache] keeps the translated pcodes of
+//! every instruction keyed by its virtual address, physical address, and the translator context
+//! options it was translated with. Keying on the physical address means changes to the TLB simply
+//! miss the cache instead of needing to invalidate it.
+//!
+//! The physical pages entries were translated from are registered with the [CodeCacheTracker] in
+//! the [Mmu](styx_processor::memory::Mmu) and writes to them drop the entries.
+use log::trace;
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
+use styx_pcode::pcode::Pcode;
+use styx_pcode_translator::ContextOption;
+use styx_processor::memory::CodeCacheTracker;
+
+use crate::arch_spec::CONTEXT_OPTION_LEN;
+
+/// Smallest page size of supported TLBs.
+///
+/// Entries crossing this boundary could be mapped to discontiguous physical memory so they are not
+/// cached.
+const MIN_PAGE_SIZE: u64 = 0x400;
+
+type ContextOptions = SmallVec<[ContextOption; CONTEXT_OPTION_LEN]>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct CacheKey {
+    virt: u64,
+    phys: u64,
+    /// Index into [TranslationCache::contexts].
+    context: u32,
+}
+
+#[derive(Debug)]
+struct CachedInstruction {
+    pcodes: Vec<Pcode>,
+    /// Bytes consumed by the instruction.
+    bytes: u64,
+}
+
+/// Translated pcode and basic block cache for the [PcodeBackend](crate::PcodeBackend).
+#[derive(Debug)]
+pub(crate) struct TranslationCache {
+    instructions: FxHashMap<CacheKey, CachedInstruction>,
+    /// End address of basic blocks, see
+    /// [BackendHelper::find_first_basic_block()](crate::backend_helper::BackendHelper::find_first_basic_block).
+    blocks: FxHashMap<CacheKey, u64>,
+    /// Entries translated from each physical page, for invalidation.
+    pages: FxHashMap<u64, Vec<CacheKey>>,
+    /// Every set of context options seen so far, index 0 is the translator's initial context.
+    contexts: Vec<ContextOptions>,
+    /// Index of the current translator context.
+    context: u32,
+}
+
+impl Default for TranslationCache {
+    fn default() -> Self {
+        Self {
+            instructions: FxHashMap::default(),
+            blocks: FxHashMap::default(),
+            pages: FxHashMap::default(),
+            contexts: vec![ContextOptions::new()],
+            context: 0,
+        }
+    }
+}
+
+impl TranslationCache {
+    /// Track context options applied to the translator.
+    ///
+    /// Options are sticky in the translator so they are merged into the current context,
+    /// replacing previous values of the same option.
+    pub(crate) fn apply_context(&mut self, options: &[ContextOption]) {
+        if options.is_empty() {
+            return;
+        }
+
+        let mut context = self.contexts[self.context as usize].clone();
+        for option in options {
+            match context
+                .iter_mut()
+                .find(|o| std::mem::discriminant(*o) == std::mem::discriminant(option))
+            {
+                Some(existing) => *existing = *option,
+                None => context.push(*option),
+            }
+        }
+
+        self.context = match self.contexts.iter().position(|c| *c == context) {
+            Some(idx) => idx as u32,
+            None => {
+                self.contexts.push(context);
+                (self.contexts.len() - 1) as u32
+            }
+        };
+    }
+
+    /// Drop entries invalidated by writes recorded in the `tracker`.
+    pub(crate) fn sync(&mut self, tracker: &mut CodeCacheTracker) {
+        if tracker.take_flush() {
+            trace!("flushing translation cache");
+            self.clear();
+        }
+
+        for page in tracker.take_dirty() {
+            let Some(keys) = self.pages.remove(&page) else {
+                continue;
+            };
+            trace!(
+                "invalidating {} translations in page 0x{page:X}",
+                keys.len()
+            );
+            for key in keys {
+                self.instructions.remove(&key);
+                self.blocks.remove(&key);
+            }
+        }
+    }
+
+    /// Appends the cached pcodes of the instruction at `virt`/`phys` to `pcodes`, returning the
+    /// number of bytes consumed by the instruction.
+    pub(crate) fn get(&self, virt: u64, phys: u64, pcodes: &mut Vec<Pcode>) -> Option<u64> {
+        let cached = self.instructions.get(&self.key(virt, phys))?;
+        pcodes.extend(cached.pcodes.iter().cloned());
+        Some(cached.bytes)
+    }
+
+    /// Cache the translation of the `bytes` long instruction at `virt`/`phys`.
+    pub(crate) fn insert(
+        &mut self,
+        virt: u64,
+        phys: u64,
+        pcodes: &[Pcode],
+        bytes: u64,
+        tracker: &mut CodeCacheTracker,
+    ) {
+        if !Self::cacheable(virt, bytes) {
+            return;
+        }
+
+        let key = self.key(virt, phys);
+        let cached = CachedInstruction {
+            pcodes: pcodes.to_vec(),
+            bytes,
+        };
+        if self.instructions.insert(key, cached).is_none() {
+            self.watch(key, tracker);
+        }
+    }
+
+    /// End address of the cached basic block starting at `virt`/`phys`.
+    pub(crate) fn get_block(&self, virt: u64, phys: u64) -> Option<u64> {
+        self.blocks.get(&self.key(virt, phys)).copied()
+    }
+
+    /// Cache the basic block starting at `virt`/`phys` and ending at `end`.
+    pub(crate) fn insert_block(
+        &mut self,
+        virt: u64,
+        phys: u64,
+        end: u64,
+        tracker: &mut CodeCacheTracker,
+    ) {
+        if !Self::cacheable(virt, end.saturating_sub(virt)) {
+            return;
+        }
+
+        let key = self.key(virt, phys);
+        if self.blocks.insert(key, end).is_none() {
+            self.watch(key, tracker);
+        }
+    }
+
+    /// Drop all entries.
+    pub(crate) fn clear(&mut self) {
+        self.instructions.clear();
+        self.blocks.clear();
+        self.pages.clear();
+    }
+
+    fn key(&self, virt: u64, phys: u64) -> CacheKey {
+        CacheKey {
+            virt,
+            phys,
+            context: self.context,
+        }
+    }
+
+    /// Can `bytes` bytes at `virt` be cached, i.e. they don't cross a page.
+    fn cacheable(virt: u64, bytes: u64) -> bool {
+        bytes > 0 && (virt % MIN_PAGE_SIZE) + bytes <= MIN_PAGE_SIZE
+    }
+
+    fn watch(&mut self, key: CacheKey, tracker: &mut CodeCacheTracker) {
+        tracker.watch(key.phys);
+        self.pages
+            .entry(CodeCacheTracker::page(key.phys))
+            .or_default()
+            .push(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use styx_pcode::pcode::{Opcode, SpaceName, VarnodeData};
+
+    use super::*;
+
+    fn pcode(offset: u64) -> Pcode {
+        Pcode {
+            opcode: Opcode::Copy,
+            inputs: smallvec::smallvec![VarnodeData {
+                space: SpaceName::Constant,
+                offset,
+                size: 4,
+            }],
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_get_insert() {
+        let mut tracker = CodeCacheTracker::default();
+        let mut cache = TranslationCache::default();
+        let mut pcodes = Vec::new();
+        assert_eq!(cache.get(0x100, 0x1100, &mut pcodes), None);
+
+        cache.insert(0x100, 0x1100, &[pcode(1), pcode(2)], 4, &mut tracker);
+        assert_eq!(cache.get(0x100, 0x1100, &mut pcodes), Some(4));
+        assert_eq!(pcodes, vec![pcode(1), pcode(2)]);
+
+        // same virtual address mapped somewhere else
+        assert_eq!(cache.get(0x100, 0x2100, &mut pcodes), None);
+
+        // crosses a page so not cached
+        cache.insert(0x3FE, 0x13FE, &[pcode(1)], 4, &mut tracker);
+        assert_eq!(cache.get(0x3FE, 0x13FE, &mut pcodes), None);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut tracker = CodeCacheTracker::default();
+        let mut cache = TranslationCache::default();
+        let mut pcodes = Vec::new();
+        cache.insert(0x100, 0x1100, &[pcode(1)], 4, &mut tracker);
+        cache.insert(0x100, 0x2100, &[pcode(1)], 4, &mut tracker);
+        cache.insert_block(0x100, 0x1100, 0x120, &mut tracker);
+
+        tracker.note_write(0x1104, 4);
+        cache.sync(&mut tracker);
+        assert_eq!(cache.get(0x100, 0x1100, &mut pcodes), None);
+        assert_eq!(cache.get_block(0x100, 0x1100), None);
+        assert_eq!(cache.get(0x100, 0x2100, &mut pcodes), Some(4));
+
+        tracker.flush();
+        cache.sync(&mut tracker);
+        assert_eq!(cache.get(0x100, 0x2100, &mut pcodes), None);
+    }
+
+    #[test]
+    fn test_context() {
+        let mut tracker = CodeCacheTracker::default();
+        let mut cache = TranslationCache::default();
+        let mut pcodes = Vec::new();
+        cache.insert(0x100, 0x100, &[pcode(1)], 4, &mut tracker);
+
+        cache.apply_context(&[ContextOption::ThumbMode(true)]);
+        assert_eq!(cache.get(0x100, 0x100, &mut pcodes), None);
+        cache.insert(0x100, 0x100, &[pcode(2)], 2, &mut tracker);
+
+        // back to the initial context is a different context than no options
+        cache.apply_context(&[ContextOption::ThumbMode(false)]);
+        assert_eq!(cache.get(0x100, 0x100, &mut pcodes), None);
+
+        cache.apply_context(&[ContextOption::ThumbMode(true)]);
+        assert_eq!(cache.get(0x100, 0x100, &mut pcodes), Some(2));
+        assert_eq!(pcodes, vec![pcode(2)]);
+        assert_eq!(cache.contexts.len(), 3);
+    }
+}
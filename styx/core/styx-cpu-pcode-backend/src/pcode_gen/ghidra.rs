@@ -90,6 +90,15 @@ impl<B: CpuBackend + 'static> GhidraPcodeGenerator<B> {
     pub(crate) fn default_space(&self) -> SpaceName {
         SpaceName::Ram
     }
+
+    /// Apply context options to the translator, they stay applied for future translations.
+    pub(crate) fn set_context_options(&mut self, context_options: &[ContextOption]) {
+        let translator = self.translator.as_mut().expect("no translator :(");
+        for option in context_options.iter() {
+            trace!("Setting context option: {option:?}");
+            translator.set_context_option(option);
+        }
+    }
 }
 
 pub(crate) trait RegisterTranslator {
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: BSD-2-Clause
+mod cache;
 mod ghidra;
 mod pcode_generator;
 
+pub(crate) use cache::TranslationCache;
 pub(crate) use ghidra::{get_pcode, GhidraPcodeGenerator, MmuLoader, RegisterTranslator};
 pub(crate) use pcode_generator::GeneratePcodeError;
 use styx_processor::cpu::CpuBackend;
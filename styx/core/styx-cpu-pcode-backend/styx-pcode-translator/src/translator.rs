@@ -167,7 +167,8 @@ impl<L: Loader + LoaderRequires + 'static> PcodeTranslator<L> {
 
 // Clone is required to allow context options to be
 // copied around in the hexagon pcode helper.
-#[derive(Debug, Copy, Clone)]
+// PartialEq is required to key translated pcode by context.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ContextOption {
     ThumbMode(bool),
 
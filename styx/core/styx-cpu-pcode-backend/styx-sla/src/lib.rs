@@ -65,8 +65,40 @@ pub trait SlaRegisters {
 #[cfg(feature = "arch_aarch64")]
 impl SlaRegisters for Aarch64 {
     fn translate_register(register: &CpuRegister) -> Box<str> {
-        // aarch64 sla has all lowercase register names
-        register.name().to_lowercase().into_boxed_str()
+        use styx_cpu_type::arch::{
+            aarch64::Aarch64Register,
+            backends::{ArchRegister, BasicArchRegister},
+        };
+
+        // system registers have an underscore before the exception level
+        let name = match register.variant() {
+            ArchRegister::Basic(BasicArchRegister::Aarch64(register)) => match register {
+                Aarch64Register::SpEl0 => Some("sp_el0"),
+                Aarch64Register::SpEl1 => Some("sp_el1"),
+                Aarch64Register::SpsrEl1 => Some("spsr_el1"),
+                Aarch64Register::ElrEl1 => Some("elr_el1"),
+                Aarch64Register::EsrEl1 => Some("esr_el1"),
+                Aarch64Register::FarEl1 => Some("far_el1"),
+                Aarch64Register::VbarEl1 => Some("vbar_el1"),
+                Aarch64Register::CntfrqEl0 => Some("cntfrq_el0"),
+                Aarch64Register::CntpctEl0 => Some("cntpct_el0"),
+                Aarch64Register::CntvctEl0 => Some("cntvct_el0"),
+                Aarch64Register::CntpCtlEl0 => Some("cntp_ctl_el0"),
+                Aarch64Register::CntpCvalEl0 => Some("cntp_cval_el0"),
+                Aarch64Register::CntpTvalEl0 => Some("cntp_tval_el0"),
+                Aarch64Register::CntvCtlEl0 => Some("cntv_ctl_el0"),
+                Aarch64Register::CntvCvalEl0 => Some("cntv_cval_el0"),
+                Aarch64Register::CntvTvalEl0 => Some("cntv_tval_el0"),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match name {
+            Some(name) => name.into(),
+            // aarch64 sla has all lowercase register names
+            None => register.name().to_lowercase().into_boxed_str(),
+        }
     }
 }
 
@@ -329,6 +361,28 @@ mod mips_common {
     };
 
     pub(crate) fn translate_register(register: &CpuRegister) -> Box<str> {
+        // cp0 registers keep their mixed case names
+        if let ArchRegister::Basic(BasicArchRegister::Mips32(reg)) = register.variant() {
+            let name = match reg {
+                Mips32Register::BadVAddr => Some("BadVAddr"),
+                Mips32Register::Count => Some("Count"),
+                Mips32Register::Compare => Some("Compare"),
+                Mips32Register::Status => Some("Status"),
+                Mips32Register::Cause => Some("Cause"),
+                Mips32Register::Epc => Some("EPC"),
+                Mips32Register::PRId => Some("PRId"),
+                Mips32Register::Config => Some("Config"),
+                Mips32Register::Config1 => Some("Config1"),
+                Mips32Register::IntCtl => Some("IntCtl"),
+                Mips32Register::EBase => Some("EBase"),
+                Mips32Register::ErrorEpc => Some("ErrorEPC"),
+                _ => None,
+            };
+            if let Some(name) = name {
+                return name.into();
+            }
+        }
+
         match register.variant() {
             // mips.sinc uses names from the O32 abi
             ArchRegister::Basic(BasicArchRegister::Mips32(reg)) => match reg {
@@ -454,7 +508,15 @@ mod msp430 {
 
     /// common register translations in TI430Common.sinc
     fn translate_msp430(register: &CpuRegister) -> Box<str> {
-        register.name().to_uppercase().into_boxed_str()
+        let upper_name = register.name().to_uppercase();
+
+        // the sla names the first registers by their function
+        match upper_name.as_str() {
+            "R0" => "PC".into(),
+            "R1" => "SP".into(),
+            "R2" => "SR".into(),
+            _ => upper_name.into_boxed_str(),
+        }
     }
 
     impl crate::SlaRegisters for crate::TiMsp430x {
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::sync::{Arc, Mutex};
+
+use styx_cpu_pcode_backend::PcodeBackend;
+use styx_cpu_type::{
+    arch::ppc32::{Ppc32Register, Ppc32Variants},
+    Arch, ArchEndian,
+};
+use styx_errors::UnknownError;
+use styx_processor::{
+    cpu::{CpuBackend, CpuBackendExt},
+    event_controller::EventController,
+    hooks::{CompareKind, CoreHandle, Hookable, StyxHook},
+    memory::{helpers::WriteExt, MemoryPermissions, Mmu},
+};
+use styx_util::logging::init_logging;
+
+/// Compare hooks get the operands of comparisons executed in their range.
+#[test]
+fn test_compare_hook() -> Result<(), UnknownError> {
+    init_logging();
+    let objdump = "
+       0:	2c 03 12 34 	cmpwi   r3,4660
+       4:	2c 03 00 10 	cmpwi   r3,16
+   ";
+
+    let mut mmu = Mmu::default_region_store();
+    let mut ev = EventController::default();
+    let mut cpu =
+        PcodeBackend::new_engine(Arch::Ppc32, Ppc32Variants::Ppc405, ArchEndian::BigEndian);
+
+    mmu.memory_map(0, 0x10000, MemoryPermissions::all())?;
+    mmu.code()
+        .write(0)
+        .bytes(&styx_util::parse_objdump(objdump)?)?;
+
+    let comparisons = Arc::new(Mutex::new(Vec::new()));
+    let compare_hook = {
+        let comparisons = comparisons.clone();
+        move |_: CoreHandle, kind: CompareKind, size: u32, left: u128, right: u128| {
+            comparisons.lock().unwrap().push((kind, size, left, right));
+            Ok(())
+        }
+    };
+    // only the first instruction
+    cpu.add_hook(StyxHook::compare(0..4, compare_hook))?;
+
+    cpu.write_register(Ppc32Register::R3, 0x1234u32)?;
+    cpu.set_pc(0)?;
+    cpu.execute(&mut mmu, &mut ev, 2)?;
+
+    let comparisons = comparisons.lock().unwrap();
+    assert!(!comparisons.is_empty());
+    assert!(comparisons.contains(&(CompareKind::Equal, 4, 0x1234, 0x1234)));
+    // second comparison is out of the hook's range
+    assert!(comparisons
+        .iter()
+        .all(|(_, size, left, right)| *size == 4 && *left != 0x10 && *right != 0x10));
+
+    Ok(())
+}
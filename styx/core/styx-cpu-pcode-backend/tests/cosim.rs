@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: BSD-2-Clause
+
+//! Two pcode processors in a [CoSimulation] passing a message through shared memory and ringing a
+//! doorbell interrupt.
+
+use std::sync::{Arc, Mutex};
+
+use styx_cpu_pcode_backend::PcodeBackend;
+use styx_cpu_type::{
+    arch::ppc32::{Ppc32Register, Ppc32Variants},
+    Arch, ArchEndian, TargetExitReason,
+};
+use styx_errors::UnknownError;
+use styx_processor::{
+    core::{builder::BuildProcessorImplArgs, ProcessorBundle},
+    cpu::{CpuBackend, CpuBackendExt},
+    event_controller::{
+        ActivateIRQnError, EventControllerImpl, ExceptionNumber, InterruptExecuted, Peripherals,
+    },
+    hooks::{CoreHandle, Hookable, StyxHook},
+    memory::{memory_region::MemoryRegion, MemoryPermissions, Mmu},
+    processor::{CoSimulation, Processor, ProcessorBuilder},
+};
+
+/// Address of the shared memory in the sender.
+const SENDER_SHARED: u64 = 0x2000_0000;
+/// Address of the shared memory in the receiver.
+const RECEIVER_SHARED: u64 = 0x4000;
+const DOORBELL_IRQ: ExceptionNumber = 3;
+
+/// Event controller recording latched interrupts.
+#[derive(Default, Clone)]
+struct Latched(Arc<Mutex<Vec<ExceptionNumber>>>);
+
+impl EventControllerImpl for Latched {
+    fn next(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        Ok(InterruptExecuted::NotExecuted)
+    }
+
+    fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+        self.0.lock().unwrap().push(event);
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        _irq: ExceptionNumber,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Result<InterruptExecuted, ActivateIRQnError> {
+        Ok(InterruptExecuted::NotExecuted)
+    }
+
+    fn finish_interrupt(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Option<ExceptionNumber> {
+        None
+    }
+
+    fn init(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        Ok(())
+    }
+}
+
+/// PowerPC 405 processor running `objdump` from address 0.
+fn processor(objdump: &str, latched: &Latched) -> Processor {
+    let latched = latched.clone();
+    let mut proc = ProcessorBuilder::default()
+        .with_builder(move |_: &BuildProcessorImplArgs| {
+            let mut mmu = Mmu::default_region_store();
+            mmu.add_memory_region(MemoryRegion::new(0, 0x1000, MemoryPermissions::all())?)?;
+            Ok(ProcessorBundle {
+                cpu: Box::new(PcodeBackend::new_engine(
+                    Arch::Ppc32,
+                    Ppc32Variants::Ppc405,
+                    ArchEndian::BigEndian,
+                )),
+                mmu,
+                event_controller: Box::new(latched.clone()),
+                ..Default::default()
+            })
+        })
+        .build()
+        .unwrap();
+
+    let code = styx_util::parse_objdump(objdump).unwrap();
+    proc.core.mmu.write_code(0, &code).unwrap();
+    proc.core.cpu.set_pc(0).unwrap();
+    proc
+}
+
+#[test]
+fn test_shared_memory_doorbell() {
+    // writes the message, then rings the doorbell word after it
+    let sender = "
+       0:	3c 60 20 00 	lis     r3,8192
+       4:	38 80 00 42 	li      r4,66
+       8:	90 83 00 00 	stw     r4,0(r3)
+       c:	38 80 00 01 	li      r4,1
+      10:	90 83 00 04 	stw     r4,4(r3)
+      14:	48 00 00 00 	b       0x14
+    ";
+    // polls the message
+    let receiver = "
+       0:	38 60 40 00 	li      r3,16384
+       4:	80 a3 00 00 	lwz     r5,0(r3)
+       8:	4b ff ff fc 	b       0x4
+    ";
+
+    let mut cosim = CoSimulation::new(100);
+    let sender_latched = Latched::default();
+    let receiver_latched = Latched::default();
+    let a = cosim.add_processor("sender", processor(sender, &sender_latched));
+    let b = cosim.add_processor("receiver", processor(receiver, &receiver_latched));
+    cosim
+        .share_memory(
+            0x1000,
+            MemoryPermissions::RW,
+            &[(a, SENDER_SHARED), (b, RECEIVER_SHARED)],
+        )
+        .unwrap();
+
+    let doorbell = cosim.interrupt_line(b);
+    cosim
+        .processor(a)
+        .add_hook(StyxHook::memory_write(
+            SENDER_SHARED + 4,
+            move |_proc: CoreHandle, _address: u64, _size: u32, _data: &[u8]| {
+                doorbell.raise(DOORBELL_IRQ);
+                Ok(())
+            },
+        ))
+        .unwrap();
+
+    // the sender runs first, the receiver sees the message in the same quantum
+    let report = cosim.run(100).unwrap();
+    assert_eq!(
+        TargetExitReason::InstructionCountComplete,
+        report.exit_reason
+    );
+    assert_eq!(
+        0x42,
+        cosim
+            .processor(b)
+            .core
+            .cpu
+            .read_register::<u32>(Ppc32Register::R5)
+            .unwrap()
+    );
+    // the doorbell is delivered at the next quantum boundary
+    assert!(receiver_latched.0.lock().unwrap().is_empty());
+
+    cosim.run(100).unwrap();
+    assert_eq!(vec![DOORBELL_IRQ], *receiver_latched.0.lock().unwrap());
+    assert!(sender_latched.0.lock().unwrap().is_empty());
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: BSD-2-Clause
+#![cfg(feature = "arch_msp430")]
+
+use styx_cpu_pcode_backend::PcodeBackend;
+use styx_cpu_type::{
+    arch::msp430::{Msp430Register, Msp430Variants},
+    Arch, ArchEndian, TargetExitReason,
+};
+use styx_processor::{
+    cpu::{CpuBackend, CpuBackendExt, ExecutionReport},
+    event_controller::EventController,
+    memory::{memory_region::MemoryRegion, MemoryPermissions, Mmu},
+};
+
+/// Tests register names, the 16 bit pc and stack usage through a call and return.
+#[cfg_attr(miri, ignore)]
+#[test]
+fn test_call_return() {
+    let code: &[u8] = &[
+        0x34, 0x40, 0x34, 0x12, // 4400: mov #0x1234, r4
+        0x15, 0x43, // 4404: mov #1, r5
+        0x05, 0x54, // 4406: add r4, r5
+        0xb0, 0x12, 0x10, 0x44, // 4408: call #0x4410
+        0xff, 0x3f, // 440c: jmp $
+        0x03, 0x43, // 440e: nop
+        0x06, 0x45, // 4410: mov r5, r6
+        0x30, 0x41, // 4412: ret
+    ];
+
+    let mut mmu = Mmu::default_region_store();
+    let mut ev = EventController::default();
+    let mut cpu = PcodeBackend::new_engine(
+        Arch::Msp430,
+        Msp430Variants::Msp430x31x,
+        ArchEndian::LittleEndian,
+    );
+
+    mmu.add_memory_region(MemoryRegion::new(0, 0x10000, MemoryPermissions::all()).unwrap())
+        .unwrap();
+    mmu.write_code(0x4400, code).unwrap();
+
+    cpu.write_register(Msp430Register::Sp, 0x2400u16).unwrap();
+    cpu.set_pc(0x4400).unwrap();
+    // the pc write must not spill into the stack pointer
+    assert_eq!(
+        cpu.read_register::<u16>(Msp430Register::Sp).unwrap(),
+        0x2400
+    );
+    assert_eq!(
+        cpu.read_register::<u16>(Msp430Register::Pc).unwrap(),
+        0x4400
+    );
+
+    let res = cpu.execute(&mut mmu, &mut ev, 7).unwrap();
+    assert_eq!(
+        res,
+        ExecutionReport::new(TargetExitReason::InstructionCountComplete, 7)
+    );
+
+    assert_eq!(
+        cpu.read_register::<u16>(Msp430Register::R4).unwrap(),
+        0x1234
+    );
+    assert_eq!(
+        cpu.read_register::<u16>(Msp430Register::R5).unwrap(),
+        0x1235
+    );
+    assert_eq!(
+        cpu.read_register::<u16>(Msp430Register::R6).unwrap(),
+        0x1235
+    );
+    assert_eq!(
+        cpu.read_register::<u16>(Msp430Register::Sp).unwrap(),
+        0x2400
+    );
+    assert_eq!(cpu.pc().unwrap(), 0x440C);
+}
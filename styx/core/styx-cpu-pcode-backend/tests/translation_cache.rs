@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use styx_cpu_pcode_backend::PcodeBackend;
+use styx_cpu_type::{
+    arch::ppc32::{Ppc32Register, Ppc32Variants},
+    Arch, ArchEndian,
+};
+use styx_errors::UnknownError;
+use styx_processor::cpu::{CpuBackend, CpuBackendExt};
+use styx_processor::memory::helpers::WriteExt;
+use styx_processor::memory::MemoryPermissions;
+use styx_processor::{event_controller::EventController, memory::Mmu};
+use styx_util::logging::init_logging;
+
+/// `li r3, 1`
+const LI_R3_1: [u8; 4] = [0x38, 0x60, 0x00, 0x01];
+/// `li r3, 2`
+const LI_R3_2: [u8; 4] = [0x38, 0x60, 0x00, 0x02];
+/// `li r3, 3`
+const LI_R3_3: [u8; 4] = [0x38, 0x60, 0x00, 0x03];
+
+fn run_at(
+    cpu: &mut PcodeBackend,
+    mmu: &mut Mmu,
+    ev: &mut EventController,
+    pc: u64,
+) -> Result<u32, UnknownError> {
+    cpu.set_pc(pc)?;
+    cpu.execute(mmu, ev, 1)?;
+    Ok(cpu.read_register::<u32>(Ppc32Register::R3)?)
+}
+
+/// Code modified through the mmu after being translated is retranslated.
+#[test]
+fn test_modified_code_retranslated() -> Result<(), UnknownError> {
+    init_logging();
+    let mut mmu = Mmu::default_region_store();
+    let mut ev = EventController::default();
+    let mut cpu =
+        PcodeBackend::new_engine(Arch::Ppc32, Ppc32Variants::Ppc405, ArchEndian::BigEndian);
+
+    mmu.memory_map(0, 0x10000, MemoryPermissions::all())?;
+    mmu.code().write(0x100).bytes(&LI_R3_1)?;
+    mmu.code().write(0x2100).bytes(&LI_R3_1)?;
+
+    // run twice to hit the cache
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x100)?, 1);
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x2100)?, 1);
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x100)?, 1);
+
+    mmu.write_code(0x100, &LI_R3_2)?;
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x100)?, 2);
+
+    mmu.sudo_write_code(0x100, &LI_R3_3)?;
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x100)?, 3);
+
+    // other page was not modified
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x2100)?, 1);
+
+    Ok(())
+}
+
+/// Code modified by the guest is retranslated.
+#[test]
+fn test_self_modifying_code() -> Result<(), UnknownError> {
+    init_logging();
+    let objdump = "
+     0:	3c 60 00 00 	lis     r3,0
+     4:	60 63 00 20 	ori     r3,r3,32
+     8:	3c 80 38 60 	lis     r4,14432
+     c:	60 84 00 02 	ori     r4,r4,2
+    10:	90 83 00 00 	stw     r4,0(r3)
+   ";
+
+    let mut mmu = Mmu::default_region_store();
+    let mut ev = EventController::default();
+    let mut cpu =
+        PcodeBackend::new_engine(Arch::Ppc32, Ppc32Variants::Ppc405, ArchEndian::BigEndian);
+
+    mmu.memory_map(0, 0x10000, MemoryPermissions::all())?;
+    mmu.code()
+        .write(0)
+        .bytes(&styx_util::parse_objdump(objdump)?)?;
+    mmu.code().write(0x20).bytes(&LI_R3_1)?;
+
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x20)?, 1);
+
+    // overwrite the instruction at 0x20 with `li r3, 2`
+    cpu.set_pc(0)?;
+    cpu.execute(&mut mmu, &mut ev, 5)?;
+
+    assert_eq!(run_at(&mut cpu, &mut mmu, &mut ev, 0x20)?, 2);
+
+    Ok(())
+}
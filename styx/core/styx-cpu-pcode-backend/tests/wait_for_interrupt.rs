@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: BSD-2-Clause
+
+//! Instructions that wait for an interrupt complete and then stop execution with
+//! [TargetExitReason::WaitForInterrupt].
+
+use styx_cpu_pcode_backend::PcodeBackend;
+use styx_cpu_type::{Arch, ArchEndian, TargetExitReason};
+use styx_errors::UnknownError;
+use styx_processor::{
+    cpu::CpuBackend,
+    event_controller::EventController,
+    memory::{helpers::WriteExt, MemoryPermissions, Mmu},
+};
+use styx_util::logging::init_logging;
+
+#[cfg(feature = "arch_ppc")]
+#[test]
+fn test_msr_wait_state_ppc32() -> Result<(), UnknownError> {
+    use styx_cpu_type::arch::ppc32::Ppc32Variants;
+
+    init_logging();
+    let mut mmu = Mmu::default_region_store();
+    let mut ev = EventController::default();
+    let mut cpu =
+        PcodeBackend::new_engine(Arch::Ppc32, Ppc32Variants::Ppc405, ArchEndian::BigEndian);
+
+    cpu.set_pc(0x1000)?;
+    mmu.memory_map(0x1000, 0x1000, MemoryPermissions::all())?;
+    mmu.code().write(0x1000).bytes(&[
+        0x3C, 0x60, 0x00, 0x04, // lis r3, 4 (MSR[WE])
+        0x7C, 0x60, 0x01, 0x24, // mtmsr r3
+        0x60, 0x00, 0x00, 0x00, // nop
+    ])?;
+
+    let report = cpu.execute(&mut mmu, &mut ev, 3)?;
+    assert_eq!(TargetExitReason::WaitForInterrupt, report.exit_reason);
+    assert_eq!(Some(2), report.instructions_executed);
+    assert_eq!(0x1008, cpu.pc()?);
+
+    Ok(())
+}
+
+#[cfg(feature = "arch_arm")]
+#[test]
+fn test_wfi_arm_tmode() -> Result<(), UnknownError> {
+    use styx_cpu_type::arch::arm::ArmVariants;
+
+    init_logging();
+    let mut mmu = Mmu::default_region_store();
+    let mut ev = EventController::default();
+    let mut cpu = PcodeBackend::new_engine(
+        Arch::Arm,
+        ArmVariants::ArmCortexM4,
+        ArchEndian::LittleEndian,
+    );
+
+    cpu.set_pc(0x1000)?;
+    mmu.memory_map(0x1000, 0x1000, MemoryPermissions::all())?;
+    mmu.code().write(0x1000).bytes(&[
+        0xC0, 0x46, // nop
+        0x30, 0xBF, // wfi
+        0xC0, 0x46, // nop
+    ])?;
+
+    let report = cpu.execute(&mut mmu, &mut ev, 3)?;
+    assert_eq!(TargetExitReason::WaitForInterrupt, report.exit_reason);
+    assert_eq!(Some(2), report.instructions_executed);
+    assert_eq!(0x1004, cpu.pc()?);
+
+    Ok(())
+}
@@ -279,6 +279,27 @@ create_basic_register_enums!(
     (LR, 64),
     (SP, 64),
     (Cpsr, 32),
+    (Nzcv, 64),
+    (Daif, 64),
+    (CurrentEl, 64),
+    (Spsel, 64),
+    (SpEl0, 64),
+    (SpEl1, 64),
+    (SpsrEl1, 64),
+    (ElrEl1, 64),
+    (EsrEl1, 64),
+    (FarEl1, 64),
+    (VbarEl1, 64),
+    // Generic timer registers
+    (CntfrqEl0, 64),
+    (CntpctEl0, 64),
+    (CntvctEl0, 64),
+    (CntpCtlEl0, 64),
+    (CntpCvalEl0, 64),
+    (CntpTvalEl0, 64),
+    (CntvCtlEl0, 64),
+    (CntvCvalEl0, 64),
+    (CntvTvalEl0, 64),
 );
 
 create_special_register_enums!(Aarch64);
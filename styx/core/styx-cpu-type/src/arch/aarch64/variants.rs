@@ -7,6 +7,30 @@ use derive_more::Display;
 use super::gdb_targets::{Aarch64CoreDescription, AARCH64_CORE_REGISTER_MAP};
 use super::Aarch64Register;
 
+/// System registers used by exception handling and the generic timer.
+const SYSTEM_REGISTERS: &[Aarch64Register] = &[
+    Aarch64Register::Nzcv,
+    Aarch64Register::Daif,
+    Aarch64Register::CurrentEl,
+    Aarch64Register::Spsel,
+    Aarch64Register::SpEl0,
+    Aarch64Register::SpEl1,
+    Aarch64Register::SpsrEl1,
+    Aarch64Register::ElrEl1,
+    Aarch64Register::EsrEl1,
+    Aarch64Register::FarEl1,
+    Aarch64Register::VbarEl1,
+    Aarch64Register::CntfrqEl0,
+    Aarch64Register::CntpctEl0,
+    Aarch64Register::CntvctEl0,
+    Aarch64Register::CntpCtlEl0,
+    Aarch64Register::CntpCvalEl0,
+    Aarch64Register::CntpTvalEl0,
+    Aarch64Register::CntvCtlEl0,
+    Aarch64Register::CntvCvalEl0,
+    Aarch64Register::CntvTvalEl0,
+];
+
 /// A "sane-default" set of default ARM registers.
 #[derive(Default)]
 pub struct Aarch64CoreRegisters {}
@@ -21,7 +45,11 @@ impl CpuRegisterBank for Aarch64CoreRegisters {
     }
 
     fn registers(&self) -> Vec<crate::arch::CpuRegister> {
-        AARCH64_CORE_REGISTER_MAP.values().cloned().collect()
+        AARCH64_CORE_REGISTER_MAP
+            .values()
+            .cloned()
+            .chain(SYSTEM_REGISTERS.iter().map(|r| r.register()))
+            .collect()
     }
 }
 
@@ -95,6 +95,20 @@ create_basic_register_enums!(
     (Lo2, 64),
     (Lo3, 64),
     (DSPControl, 64),
+    // Coprocessor 0 registers used by exceptions and the count/compare timer.
+    // See Chapter 9 (CP0 Registers) of Volume III of Mips32 ISA (doc: MD00090)
+    (BadVAddr, 32),
+    (Count, 32),
+    (Compare, 32),
+    (Status, 32),
+    (Cause, 32),
+    (Epc, 32),
+    (PRId, 32),
+    (Config, 32),
+    (Config1, 32),
+    (IntCtl, 32),
+    (EBase, 32),
+    (ErrorEpc, 32),
 );
 
 #[allow(unused)]
@@ -5,6 +5,22 @@ use crate::arch::Mips32Register;
 use crate::arch::{Arch, ArchitectureDef, ArchitectureVariant, CpuRegisterBank};
 use derive_more::Display;
 
+/// Coprocessor 0 registers used by exceptions and the count/compare timer.
+const CP0_REGISTERS: &[Mips32Register] = &[
+    Mips32Register::BadVAddr,
+    Mips32Register::Count,
+    Mips32Register::Compare,
+    Mips32Register::Status,
+    Mips32Register::Cause,
+    Mips32Register::Epc,
+    Mips32Register::PRId,
+    Mips32Register::Config,
+    Mips32Register::Config1,
+    Mips32Register::IntCtl,
+    Mips32Register::EBase,
+    Mips32Register::ErrorEpc,
+];
+
 #[derive(Default)]
 pub struct Mips32GeneralRegisters {}
 
@@ -18,7 +34,11 @@ impl CpuRegisterBank for Mips32GeneralRegisters {
     }
 
     fn registers(&self) -> Vec<crate::arch::CpuRegister> {
-        MIPS32_CPU_REGISTER_MAP.values().cloned().collect()
+        MIPS32_CPU_REGISTER_MAP
+            .values()
+            .cloned()
+            .chain(CP0_REGISTERS.iter().map(|r| r.register()))
+            .collect()
     }
 }
 
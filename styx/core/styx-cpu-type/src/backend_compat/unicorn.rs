@@ -37,6 +37,7 @@ impl From<unicorn_const::Arch> for crate::arch::Arch {
     fn from(value: unicorn_const::Arch) -> Self {
         match value {
             unicorn_const::Arch::ARM => crate::arch::Arch::Arm,
+            unicorn_const::Arch::ARM64 => crate::arch::Arch::Aarch64,
             unicorn_const::Arch::M68K => crate::arch::Arch::M68k,
             unicorn_const::Arch::MIPS => crate::arch::Arch::Mips64,
             unicorn_const::Arch::PPC => crate::arch::Arch::Ppc32,
@@ -55,6 +56,7 @@ impl TryInto<unicorn_const::Arch> for crate::arch::Arch {
     fn try_into(self) -> Result<unicorn_const::Arch, Self::Error> {
         match self {
             crate::arch::Arch::Arm => Ok(unicorn_const::Arch::ARM),
+            crate::arch::Arch::Aarch64 => Ok(unicorn_const::Arch::ARM64),
             crate::arch::Arch::M68k => Ok(unicorn_const::Arch::M68K),
             crate::arch::Arch::Mips64 => Ok(unicorn_const::Arch::MIPS),
             crate::arch::Arch::Mips32 => Ok(unicorn_const::Arch::MIPS),
@@ -120,6 +122,78 @@ impl From<crate::arch::arm::ArmMetaVariants> for unicorn_engine::ArmCpuModel {
     }
 }
 
+impl From<crate::arch::aarch64::Aarch64MetaVariants> for unicorn_engine::Arm64CpuModel {
+    fn from(value: crate::arch::aarch64::Aarch64MetaVariants) -> Self {
+        use crate::arch::aarch64::Aarch64MetaVariants as STYX_AARCH64;
+        use unicorn_engine::Arm64CpuModel as UC_ARM64;
+
+        match value {
+            // plain ARMv8-A core
+            STYX_AARCH64::Generic(_) => UC_ARM64::UC_CPU_ARM64_A72,
+        }
+    }
+}
+
+impl From<crate::arch::mips32::Mips32MetaVariants> for unicorn_engine::Mips32CpuModel {
+    fn from(value: crate::arch::mips32::Mips32MetaVariants) -> Self {
+        use crate::arch::mips32::Mips32MetaVariants as STYX_MIPS;
+        use unicorn_engine::Mips32CpuModel as UC_MIPS;
+
+        match value {
+            STYX_MIPS::Mips32r1Generic(_) | STYX_MIPS::Mips324kc(_) | STYX_MIPS::Mips324kp(_) => {
+                UC_MIPS::UC_CPU_MIPS32_4KC
+            }
+            STYX_MIPS::Mips324km(_) | STYX_MIPS::Mips324ksc(_) | STYX_MIPS::Mips324ksd(_) => {
+                UC_MIPS::UC_CPU_MIPS32_4KM
+            }
+            // the M4K is a 4KEm with a fixed mapping translation
+            STYX_MIPS::Mips32m4k(_) | STYX_MIPS::Mips324kem(_) | STYX_MIPS::Mips324kep(_) => {
+                UC_MIPS::UC_CPU_MIPS32_4KEM
+            }
+            STYX_MIPS::Mips324kec(_) => UC_MIPS::UC_CPU_MIPS32_4KEC,
+            STYX_MIPS::Mips32m14k(_) | STYX_MIPS::Mips32m14ke(_) => UC_MIPS::UC_CPU_MIPS32_M14K,
+            STYX_MIPS::Mips32m14kc(_) | STYX_MIPS::Mips32m14kec(_) => {
+                UC_MIPS::UC_CPU_MIPS32_M14KC
+            }
+            STYX_MIPS::Mips3224kc(_) | STYX_MIPS::Mips3224kx(_) => UC_MIPS::UC_CPU_MIPS32_24KC,
+            STYX_MIPS::Mips3224kf2_1(_)
+            | STYX_MIPS::Mips3224kf(_)
+            | STYX_MIPS::Mips3224kf1_1(_)
+            | STYX_MIPS::Mips3224kfx(_) => UC_MIPS::UC_CPU_MIPS32_24KF,
+            STYX_MIPS::Mips3224kec(_)
+            | STYX_MIPS::Mips3224kef2_1(_)
+            | STYX_MIPS::Mips3224kef(_)
+            | STYX_MIPS::Mips3224kef1_1(_)
+            | STYX_MIPS::Mips3224kefx(_)
+            | STYX_MIPS::Mips3224kex(_) => UC_MIPS::UC_CPU_MIPS32_24KEC,
+            STYX_MIPS::Mips3234kc(_)
+            | STYX_MIPS::Mips3234kf2_1(_)
+            | STYX_MIPS::Mips3234kf(_)
+            | STYX_MIPS::Mips3234kf1_1(_)
+            | STYX_MIPS::Mips3234kfx(_)
+            | STYX_MIPS::Mips3234kx(_)
+            | STYX_MIPS::Mips3234kn(_)
+            // no 1004K or interAptiv models, they are multi-core 34Ks
+            | STYX_MIPS::Mips321004kc(_)
+            | STYX_MIPS::Mips321004kf2_1(_)
+            | STYX_MIPS::Mips321004kf(_)
+            | STYX_MIPS::Mips321004kf1_1(_)
+            | STYX_MIPS::Mips32interaptiv(_) => UC_MIPS::UC_CPU_MIPS32_34K,
+            STYX_MIPS::Mips3274kc(_)
+            | STYX_MIPS::Mips3274kf2_1(_)
+            | STYX_MIPS::Mips3274kf(_)
+            | STYX_MIPS::Mips3274kf1_1(_)
+            | STYX_MIPS::Mips3274kfx(_)
+            | STYX_MIPS::Mips3274kx(_)
+            | STYX_MIPS::Mips3274kf3_2(_) => UC_MIPS::UC_CPU_MIPS32_74K,
+            // no M5100 models, guessed the closest release 5 core
+            STYX_MIPS::Mips32p5600(_) | STYX_MIPS::Mips32m5100(_) | STYX_MIPS::Mips32m5101(_) => {
+                UC_MIPS::UC_CPU_MIPS32_P5600
+            }
+        }
+    }
+}
+
 impl From<crate::arch::ppc32::Ppc32MetaVariants> for unicorn_engine::PpcCpuModel {
     fn from(value: crate::arch::ppc32::Ppc32MetaVariants) -> Self {
         use crate::arch::ppc32::Ppc32MetaVariants as STYX_PPC;
@@ -396,3 +470,375 @@ impl From<crate::arch::arm::ArmRegister> for unicorn_engine::RegisterARM {
         }
     }
 }
+
+impl From<crate::arch::aarch64::Aarch64Register> for unicorn_engine::RegisterARM64 {
+    fn from(value: crate::arch::aarch64::Aarch64Register) -> Self {
+        use crate::arch::aarch64::Aarch64Register as STYX_AARCH64;
+        use unicorn_engine::RegisterARM64 as UC_ARM64;
+
+        match value {
+            STYX_AARCH64::W0 => UC_ARM64::W0,
+            STYX_AARCH64::W1 => UC_ARM64::W1,
+            STYX_AARCH64::W2 => UC_ARM64::W2,
+            STYX_AARCH64::W3 => UC_ARM64::W3,
+            STYX_AARCH64::W4 => UC_ARM64::W4,
+            STYX_AARCH64::W5 => UC_ARM64::W5,
+            STYX_AARCH64::W6 => UC_ARM64::W6,
+            STYX_AARCH64::W7 => UC_ARM64::W7,
+            STYX_AARCH64::W8 => UC_ARM64::W8,
+            STYX_AARCH64::W9 => UC_ARM64::W9,
+            STYX_AARCH64::W10 => UC_ARM64::W10,
+            STYX_AARCH64::W11 => UC_ARM64::W11,
+            STYX_AARCH64::W12 => UC_ARM64::W12,
+            STYX_AARCH64::W13 => UC_ARM64::W13,
+            STYX_AARCH64::W14 => UC_ARM64::W14,
+            STYX_AARCH64::W15 => UC_ARM64::W15,
+            STYX_AARCH64::W16 => UC_ARM64::W16,
+            STYX_AARCH64::W17 => UC_ARM64::W17,
+            STYX_AARCH64::W18 => UC_ARM64::W18,
+            STYX_AARCH64::W19 => UC_ARM64::W19,
+            STYX_AARCH64::W20 => UC_ARM64::W20,
+            STYX_AARCH64::W21 => UC_ARM64::W21,
+            STYX_AARCH64::W22 => UC_ARM64::W22,
+            STYX_AARCH64::W23 => UC_ARM64::W23,
+            STYX_AARCH64::W24 => UC_ARM64::W24,
+            STYX_AARCH64::W25 => UC_ARM64::W25,
+            STYX_AARCH64::W26 => UC_ARM64::W26,
+            STYX_AARCH64::W27 => UC_ARM64::W27,
+            STYX_AARCH64::W28 => UC_ARM64::W28,
+            STYX_AARCH64::W29 => UC_ARM64::W29,
+            STYX_AARCH64::W30 => UC_ARM64::W30,
+            STYX_AARCH64::WZR => UC_ARM64::WZR,
+            STYX_AARCH64::X0 => UC_ARM64::X0,
+            STYX_AARCH64::X1 => UC_ARM64::X1,
+            STYX_AARCH64::X2 => UC_ARM64::X2,
+            STYX_AARCH64::X3 => UC_ARM64::X3,
+            STYX_AARCH64::X4 => UC_ARM64::X4,
+            STYX_AARCH64::X5 => UC_ARM64::X5,
+            STYX_AARCH64::X6 => UC_ARM64::X6,
+            STYX_AARCH64::X7 => UC_ARM64::X7,
+            STYX_AARCH64::X8 => UC_ARM64::X8,
+            STYX_AARCH64::X9 => UC_ARM64::X9,
+            STYX_AARCH64::X10 => UC_ARM64::X10,
+            STYX_AARCH64::X11 => UC_ARM64::X11,
+            STYX_AARCH64::X12 => UC_ARM64::X12,
+            STYX_AARCH64::X13 => UC_ARM64::X13,
+            STYX_AARCH64::X14 => UC_ARM64::X14,
+            STYX_AARCH64::X15 => UC_ARM64::X15,
+            STYX_AARCH64::X16 => UC_ARM64::X16,
+            STYX_AARCH64::X17 => UC_ARM64::X17,
+            STYX_AARCH64::X18 => UC_ARM64::X18,
+            STYX_AARCH64::X19 => UC_ARM64::X19,
+            STYX_AARCH64::X20 => UC_ARM64::X20,
+            STYX_AARCH64::X21 => UC_ARM64::X21,
+            STYX_AARCH64::X22 => UC_ARM64::X22,
+            STYX_AARCH64::X23 => UC_ARM64::X23,
+            STYX_AARCH64::X24 => UC_ARM64::X24,
+            STYX_AARCH64::X25 => UC_ARM64::X25,
+            STYX_AARCH64::X26 => UC_ARM64::X26,
+            STYX_AARCH64::X27 => UC_ARM64::X27,
+            STYX_AARCH64::X28 => UC_ARM64::X28,
+            STYX_AARCH64::X29 => UC_ARM64::X29,
+            STYX_AARCH64::X30 => UC_ARM64::X30,
+            STYX_AARCH64::XZR => UC_ARM64::XZR,
+            STYX_AARCH64::B0 => UC_ARM64::B0,
+            STYX_AARCH64::B1 => UC_ARM64::B1,
+            STYX_AARCH64::B2 => UC_ARM64::B2,
+            STYX_AARCH64::B3 => UC_ARM64::B3,
+            STYX_AARCH64::B4 => UC_ARM64::B4,
+            STYX_AARCH64::B5 => UC_ARM64::B5,
+            STYX_AARCH64::B6 => UC_ARM64::B6,
+            STYX_AARCH64::B7 => UC_ARM64::B7,
+            STYX_AARCH64::B8 => UC_ARM64::B8,
+            STYX_AARCH64::B9 => UC_ARM64::B9,
+            STYX_AARCH64::B10 => UC_ARM64::B10,
+            STYX_AARCH64::B11 => UC_ARM64::B11,
+            STYX_AARCH64::B12 => UC_ARM64::B12,
+            STYX_AARCH64::B13 => UC_ARM64::B13,
+            STYX_AARCH64::B14 => UC_ARM64::B14,
+            STYX_AARCH64::B15 => UC_ARM64::B15,
+            STYX_AARCH64::B16 => UC_ARM64::B16,
+            STYX_AARCH64::B17 => UC_ARM64::B17,
+            STYX_AARCH64::B18 => UC_ARM64::B18,
+            STYX_AARCH64::B19 => UC_ARM64::B19,
+            STYX_AARCH64::B20 => UC_ARM64::B20,
+            STYX_AARCH64::B21 => UC_ARM64::B21,
+            STYX_AARCH64::B22 => UC_ARM64::B22,
+            STYX_AARCH64::B23 => UC_ARM64::B23,
+            STYX_AARCH64::B24 => UC_ARM64::B24,
+            STYX_AARCH64::B25 => UC_ARM64::B25,
+            STYX_AARCH64::B26 => UC_ARM64::B26,
+            STYX_AARCH64::B27 => UC_ARM64::B27,
+            STYX_AARCH64::B28 => UC_ARM64::B28,
+            STYX_AARCH64::B29 => UC_ARM64::B29,
+            STYX_AARCH64::B30 => UC_ARM64::B30,
+            STYX_AARCH64::B31 => UC_ARM64::B31,
+            STYX_AARCH64::H0 => UC_ARM64::H0,
+            STYX_AARCH64::H1 => UC_ARM64::H1,
+            STYX_AARCH64::H2 => UC_ARM64::H2,
+            STYX_AARCH64::H3 => UC_ARM64::H3,
+            STYX_AARCH64::H4 => UC_ARM64::H4,
+            STYX_AARCH64::H5 => UC_ARM64::H5,
+            STYX_AARCH64::H6 => UC_ARM64::H6,
+            STYX_AARCH64::H7 => UC_ARM64::H7,
+            STYX_AARCH64::H8 => UC_ARM64::H8,
+            STYX_AARCH64::H9 => UC_ARM64::H9,
+            STYX_AARCH64::H10 => UC_ARM64::H10,
+            STYX_AARCH64::H11 => UC_ARM64::H11,
+            STYX_AARCH64::H12 => UC_ARM64::H12,
+            STYX_AARCH64::H13 => UC_ARM64::H13,
+            STYX_AARCH64::H14 => UC_ARM64::H14,
+            STYX_AARCH64::H15 => UC_ARM64::H15,
+            STYX_AARCH64::H16 => UC_ARM64::H16,
+            STYX_AARCH64::H17 => UC_ARM64::H17,
+            STYX_AARCH64::H18 => UC_ARM64::H18,
+            STYX_AARCH64::H19 => UC_ARM64::H19,
+            STYX_AARCH64::H20 => UC_ARM64::H20,
+            STYX_AARCH64::H21 => UC_ARM64::H21,
+            STYX_AARCH64::H22 => UC_ARM64::H22,
+            STYX_AARCH64::H23 => UC_ARM64::H23,
+            STYX_AARCH64::H24 => UC_ARM64::H24,
+            STYX_AARCH64::H25 => UC_ARM64::H25,
+            STYX_AARCH64::H26 => UC_ARM64::H26,
+            STYX_AARCH64::H27 => UC_ARM64::H27,
+            STYX_AARCH64::H28 => UC_ARM64::H28,
+            STYX_AARCH64::H29 => UC_ARM64::H29,
+            STYX_AARCH64::H30 => UC_ARM64::H30,
+            STYX_AARCH64::H31 => UC_ARM64::H31,
+            STYX_AARCH64::S0 => UC_ARM64::S0,
+            STYX_AARCH64::S1 => UC_ARM64::S1,
+            STYX_AARCH64::S2 => UC_ARM64::S2,
+            STYX_AARCH64::S3 => UC_ARM64::S3,
+            STYX_AARCH64::S4 => UC_ARM64::S4,
+            STYX_AARCH64::S5 => UC_ARM64::S5,
+            STYX_AARCH64::S6 => UC_ARM64::S6,
+            STYX_AARCH64::S7 => UC_ARM64::S7,
+            STYX_AARCH64::S8 => UC_ARM64::S8,
+            STYX_AARCH64::S9 => UC_ARM64::S9,
+            STYX_AARCH64::S10 => UC_ARM64::S10,
+            STYX_AARCH64::S11 => UC_ARM64::S11,
+            STYX_AARCH64::S12 => UC_ARM64::S12,
+            STYX_AARCH64::S13 => UC_ARM64::S13,
+            STYX_AARCH64::S14 => UC_ARM64::S14,
+            STYX_AARCH64::S15 => UC_ARM64::S15,
+            STYX_AARCH64::S16 => UC_ARM64::S16,
+            STYX_AARCH64::S17 => UC_ARM64::S17,
+            STYX_AARCH64::S18 => UC_ARM64::S18,
+            STYX_AARCH64::S19 => UC_ARM64::S19,
+            STYX_AARCH64::S20 => UC_ARM64::S20,
+            STYX_AARCH64::S21 => UC_ARM64::S21,
+            STYX_AARCH64::S22 => UC_ARM64::S22,
+            STYX_AARCH64::S23 => UC_ARM64::S23,
+            STYX_AARCH64::S24 => UC_ARM64::S24,
+            STYX_AARCH64::S25 => UC_ARM64::S25,
+            STYX_AARCH64::S26 => UC_ARM64::S26,
+            STYX_AARCH64::S27 => UC_ARM64::S27,
+            STYX_AARCH64::S28 => UC_ARM64::S28,
+            STYX_AARCH64::S29 => UC_ARM64::S29,
+            STYX_AARCH64::S30 => UC_ARM64::S30,
+            STYX_AARCH64::S31 => UC_ARM64::S31,
+            STYX_AARCH64::D0 => UC_ARM64::D0,
+            STYX_AARCH64::D1 => UC_ARM64::D1,
+            STYX_AARCH64::D2 => UC_ARM64::D2,
+            STYX_AARCH64::D3 => UC_ARM64::D3,
+            STYX_AARCH64::D4 => UC_ARM64::D4,
+            STYX_AARCH64::D5 => UC_ARM64::D5,
+            STYX_AARCH64::D6 => UC_ARM64::D6,
+            STYX_AARCH64::D7 => UC_ARM64::D7,
+            STYX_AARCH64::D8 => UC_ARM64::D8,
+            STYX_AARCH64::D9 => UC_ARM64::D9,
+            STYX_AARCH64::D10 => UC_ARM64::D10,
+            STYX_AARCH64::D11 => UC_ARM64::D11,
+            STYX_AARCH64::D12 => UC_ARM64::D12,
+            STYX_AARCH64::D13 => UC_ARM64::D13,
+            STYX_AARCH64::D14 => UC_ARM64::D14,
+            STYX_AARCH64::D15 => UC_ARM64::D15,
+            STYX_AARCH64::D16 => UC_ARM64::D16,
+            STYX_AARCH64::D17 => UC_ARM64::D17,
+            STYX_AARCH64::D18 => UC_ARM64::D18,
+            STYX_AARCH64::D19 => UC_ARM64::D19,
+            STYX_AARCH64::D20 => UC_ARM64::D20,
+            STYX_AARCH64::D21 => UC_ARM64::D21,
+            STYX_AARCH64::D22 => UC_ARM64::D22,
+            STYX_AARCH64::D23 => UC_ARM64::D23,
+            STYX_AARCH64::D24 => UC_ARM64::D24,
+            STYX_AARCH64::D25 => UC_ARM64::D25,
+            STYX_AARCH64::D26 => UC_ARM64::D26,
+            STYX_AARCH64::D27 => UC_ARM64::D27,
+            STYX_AARCH64::D28 => UC_ARM64::D28,
+            STYX_AARCH64::D29 => UC_ARM64::D29,
+            STYX_AARCH64::D30 => UC_ARM64::D30,
+            STYX_AARCH64::D31 => UC_ARM64::D31,
+            STYX_AARCH64::Q0 => UC_ARM64::Q0,
+            STYX_AARCH64::Q1 => UC_ARM64::Q1,
+            STYX_AARCH64::Q2 => UC_ARM64::Q2,
+            STYX_AARCH64::Q3 => UC_ARM64::Q3,
+            STYX_AARCH64::Q4 => UC_ARM64::Q4,
+            STYX_AARCH64::Q5 => UC_ARM64::Q5,
+            STYX_AARCH64::Q6 => UC_ARM64::Q6,
+            STYX_AARCH64::Q7 => UC_ARM64::Q7,
+            STYX_AARCH64::Q8 => UC_ARM64::Q8,
+            STYX_AARCH64::Q9 => UC_ARM64::Q9,
+            STYX_AARCH64::Q10 => UC_ARM64::Q10,
+            STYX_AARCH64::Q11 => UC_ARM64::Q11,
+            STYX_AARCH64::Q12 => UC_ARM64::Q12,
+            STYX_AARCH64::Q13 => UC_ARM64::Q13,
+            STYX_AARCH64::Q14 => UC_ARM64::Q14,
+            STYX_AARCH64::Q15 => UC_ARM64::Q15,
+            STYX_AARCH64::Q16 => UC_ARM64::Q16,
+            STYX_AARCH64::Q17 => UC_ARM64::Q17,
+            STYX_AARCH64::Q18 => UC_ARM64::Q18,
+            STYX_AARCH64::Q19 => UC_ARM64::Q19,
+            STYX_AARCH64::Q20 => UC_ARM64::Q20,
+            STYX_AARCH64::Q21 => UC_ARM64::Q21,
+            STYX_AARCH64::Q22 => UC_ARM64::Q22,
+            STYX_AARCH64::Q23 => UC_ARM64::Q23,
+            STYX_AARCH64::Q24 => UC_ARM64::Q24,
+            STYX_AARCH64::Q25 => UC_ARM64::Q25,
+            STYX_AARCH64::Q26 => UC_ARM64::Q26,
+            STYX_AARCH64::Q27 => UC_ARM64::Q27,
+            STYX_AARCH64::Q28 => UC_ARM64::Q28,
+            STYX_AARCH64::Q29 => UC_ARM64::Q29,
+            STYX_AARCH64::Q30 => UC_ARM64::Q30,
+            STYX_AARCH64::Q31 => UC_ARM64::Q31,
+            STYX_AARCH64::V0 => UC_ARM64::V0,
+            STYX_AARCH64::V1 => UC_ARM64::V1,
+            STYX_AARCH64::V2 => UC_ARM64::V2,
+            STYX_AARCH64::V3 => UC_ARM64::V3,
+            STYX_AARCH64::V4 => UC_ARM64::V4,
+            STYX_AARCH64::V5 => UC_ARM64::V5,
+            STYX_AARCH64::V6 => UC_ARM64::V6,
+            STYX_AARCH64::V7 => UC_ARM64::V7,
+            STYX_AARCH64::V8 => UC_ARM64::V8,
+            STYX_AARCH64::V9 => UC_ARM64::V9,
+            STYX_AARCH64::V10 => UC_ARM64::V10,
+            STYX_AARCH64::V11 => UC_ARM64::V11,
+            STYX_AARCH64::V12 => UC_ARM64::V12,
+            STYX_AARCH64::V13 => UC_ARM64::V13,
+            STYX_AARCH64::V14 => UC_ARM64::V14,
+            STYX_AARCH64::V15 => UC_ARM64::V15,
+            STYX_AARCH64::V16 => UC_ARM64::V16,
+            STYX_AARCH64::V17 => UC_ARM64::V17,
+            STYX_AARCH64::V18 => UC_ARM64::V18,
+            STYX_AARCH64::V19 => UC_ARM64::V19,
+            STYX_AARCH64::V20 => UC_ARM64::V20,
+            STYX_AARCH64::V21 => UC_ARM64::V21,
+            STYX_AARCH64::V22 => UC_ARM64::V22,
+            STYX_AARCH64::V23 => UC_ARM64::V23,
+            STYX_AARCH64::V24 => UC_ARM64::V24,
+            STYX_AARCH64::V25 => UC_ARM64::V25,
+            STYX_AARCH64::V26 => UC_ARM64::V26,
+            STYX_AARCH64::V27 => UC_ARM64::V27,
+            STYX_AARCH64::V28 => UC_ARM64::V28,
+            STYX_AARCH64::V29 => UC_ARM64::V29,
+            STYX_AARCH64::V30 => UC_ARM64::V30,
+            STYX_AARCH64::V31 => UC_ARM64::V31,
+            STYX_AARCH64::PC => UC_ARM64::PC,
+            STYX_AARCH64::LR => UC_ARM64::X30,
+            STYX_AARCH64::SP => UC_ARM64::SP,
+            STYX_AARCH64::Cpsr => UC_ARM64::PSTATE,
+            STYX_AARCH64::Nzcv => UC_ARM64::NZCV,
+            STYX_AARCH64::SpEl0 => UC_ARM64::SP_EL0,
+            STYX_AARCH64::SpEl1 => UC_ARM64::SP_EL1,
+            STYX_AARCH64::ElrEl1 => UC_ARM64::ELR_EL1,
+            STYX_AARCH64::EsrEl1 => UC_ARM64::ESR_EL1,
+            STYX_AARCH64::FarEl1 => UC_ARM64::FAR_EL1,
+            STYX_AARCH64::VbarEl1 => UC_ARM64::VBAR_EL1,
+            // no named unicorn register, accessed by encoding
+            STYX_AARCH64::Daif
+            | STYX_AARCH64::CurrentEl
+            | STYX_AARCH64::Spsel
+            | STYX_AARCH64::SpsrEl1
+            | STYX_AARCH64::CntfrqEl0
+            | STYX_AARCH64::CntpctEl0
+            | STYX_AARCH64::CntvctEl0
+            | STYX_AARCH64::CntpCtlEl0
+            | STYX_AARCH64::CntpCvalEl0
+            | STYX_AARCH64::CntpTvalEl0
+            | STYX_AARCH64::CntvCtlEl0
+            | STYX_AARCH64::CntvCvalEl0
+            | STYX_AARCH64::CntvTvalEl0 => UC_ARM64::CP_REG,
+        }
+    }
+}
+
+impl From<crate::arch::mips32::Mips32Register> for unicorn_engine::RegisterMIPS {
+    fn from(value: crate::arch::mips32::Mips32Register) -> Self {
+        use crate::arch::mips32::Mips32Register as STYX_MIPS;
+        use unicorn_engine::RegisterMIPS as UC_MIPS;
+
+        match value {
+            STYX_MIPS::R0 => UC_MIPS::R0,
+            STYX_MIPS::R1 => UC_MIPS::R1,
+            STYX_MIPS::R2 => UC_MIPS::R2,
+            STYX_MIPS::R3 => UC_MIPS::R3,
+            STYX_MIPS::R4 => UC_MIPS::R4,
+            STYX_MIPS::R5 => UC_MIPS::R5,
+            STYX_MIPS::R6 => UC_MIPS::R6,
+            STYX_MIPS::R7 => UC_MIPS::R7,
+            STYX_MIPS::R8 => UC_MIPS::R8,
+            STYX_MIPS::R9 => UC_MIPS::R9,
+            STYX_MIPS::R10 => UC_MIPS::R10,
+            STYX_MIPS::R11 => UC_MIPS::R11,
+            STYX_MIPS::R12 => UC_MIPS::R12,
+            STYX_MIPS::R13 => UC_MIPS::R13,
+            STYX_MIPS::R14 => UC_MIPS::R14,
+            STYX_MIPS::R15 => UC_MIPS::R15,
+            STYX_MIPS::R16 => UC_MIPS::R16,
+            STYX_MIPS::R17 => UC_MIPS::R17,
+            STYX_MIPS::R18 => UC_MIPS::R18,
+            STYX_MIPS::R19 => UC_MIPS::R19,
+            STYX_MIPS::R20 => UC_MIPS::R20,
+            STYX_MIPS::R21 => UC_MIPS::R21,
+            STYX_MIPS::R22 => UC_MIPS::R22,
+            STYX_MIPS::R23 => UC_MIPS::R23,
+            STYX_MIPS::R24 => UC_MIPS::R24,
+            STYX_MIPS::R25 => UC_MIPS::R25,
+            STYX_MIPS::R26 => UC_MIPS::R26,
+            STYX_MIPS::R27 => UC_MIPS::R27,
+            STYX_MIPS::R28 => UC_MIPS::R28,
+            STYX_MIPS::R29 => UC_MIPS::R29,
+            STYX_MIPS::R30 => UC_MIPS::R30,
+            STYX_MIPS::R31 => UC_MIPS::R31,
+            STYX_MIPS::Lo => UC_MIPS::LO,
+            STYX_MIPS::Hi => UC_MIPS::HI,
+            STYX_MIPS::Pc => UC_MIPS::PC,
+            STYX_MIPS::F0 => UC_MIPS::F0,
+            STYX_MIPS::F1 => UC_MIPS::F1,
+            STYX_MIPS::F2 => UC_MIPS::F2,
+            STYX_MIPS::F3 => UC_MIPS::F3,
+            STYX_MIPS::F4 => UC_MIPS::F4,
+            STYX_MIPS::F5 => UC_MIPS::F5,
+            STYX_MIPS::F6 => UC_MIPS::F6,
+            STYX_MIPS::F7 => UC_MIPS::F7,
+            STYX_MIPS::F8 => UC_MIPS::F8,
+            STYX_MIPS::F9 => UC_MIPS::F9,
+            STYX_MIPS::F10 => UC_MIPS::F10,
+            STYX_MIPS::F11 => UC_MIPS::F11,
+            STYX_MIPS::F12 => UC_MIPS::F12,
+            STYX_MIPS::F13 => UC_MIPS::F13,
+            STYX_MIPS::F14 => UC_MIPS::F14,
+            STYX_MIPS::F15 => UC_MIPS::F15,
+            STYX_MIPS::F16 => UC_MIPS::F16,
+            STYX_MIPS::F17 => UC_MIPS::F17,
+            STYX_MIPS::F18 => UC_MIPS::F18,
+            STYX_MIPS::F19 => UC_MIPS::F19,
+            STYX_MIPS::F20 => UC_MIPS::F20,
+            STYX_MIPS::F21 => UC_MIPS::F21,
+            STYX_MIPS::F22 => UC_MIPS::F22,
+            STYX_MIPS::F23 => UC_MIPS::F23,
+            STYX_MIPS::F24 => UC_MIPS::F24,
+            STYX_MIPS::F25 => UC_MIPS::F25,
+            STYX_MIPS::F26 => UC_MIPS::F26,
+            STYX_MIPS::F27 => UC_MIPS::F27,
+            STYX_MIPS::F28 => UC_MIPS::F28,
+            STYX_MIPS::F29 => UC_MIPS::F29,
+            STYX_MIPS::F30 => UC_MIPS::F30,
+            STYX_MIPS::F31 => UC_MIPS::F31,
+            STYX_MIPS::Status => UC_MIPS::CP0_STATUS,
+            // unicorn only exposes Status of the cp0 registers used by styx, so the mips32
+            // processor's exceptions and timer are pcode only
+            _ => UC_MIPS::INVALID,
+        }
+    }
+}
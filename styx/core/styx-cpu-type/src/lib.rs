@@ -96,6 +96,9 @@ pub enum TargetExitReason {
     UnmappedMemoryRead,
     /// Target performed an unmapped memory read
     UnmappedMemoryWrite,
+    /// Target is idle until the next interrupt, e.g. after `wfi`. The
+    /// waiting instruction completed and `pc` points after it
+    WaitForInterrupt,
 }
 
 impl TargetExitReason {
@@ -108,6 +111,7 @@ impl TargetExitReason {
             TargetExitReason::InstructionCountComplete
                 | TargetExitReason::ExecutionTimeoutComplete
                 | TargetExitReason::HostStopRequest
+                | TargetExitReason::WaitForInterrupt
         )
     }
 
@@ -126,7 +130,8 @@ impl From<TargetExitReason> for gdbstub::common::Signal {
             Tgt::BusError => Signal::SIGBUS,
             Tgt::ExecutionTimeoutComplete
             | Tgt::InstructionCountComplete
-            | Tgt::HostStopRequest => unreachable!(),
+            | Tgt::HostStopRequest
+            | Tgt::WaitForInterrupt => unreachable!(),
             Tgt::IllegalInstruction | Tgt::InstructionDecodeError => Signal::SIGILL,
             Tgt::UnmappedMemoryFetch
             | Tgt::UnmappedMemoryRead
@@ -25,6 +25,7 @@ pub fn styx_to_unicorn_machine(
         // add arch options, required for some architectures for some reason
         out_mode |= match arch {
             Arch::Ppc32 => unicorn_const::Mode::PPC32,
+            Arch::Mips32 => unicorn_const::Mode::MIPS32,
             _ => unicorn_const::Mode::empty(),
         };
 
@@ -52,6 +53,14 @@ fn styx_to_unicorn_cpu_model(
             let tmp: unicorn_engine::PpcCpuModel = inner.into();
             tmp.into()
         }
+        ArchVariant::Aarch64(inner) => {
+            let tmp: unicorn_engine::Arm64CpuModel = inner.into();
+            tmp.into()
+        }
+        ArchVariant::Mips32(inner) => {
+            let tmp: unicorn_engine::Mips32CpuModel = inner.into();
+            tmp.into()
+        }
         other => {
             return Err(StyxCpuArchError::NotSupportedVariantOnBackend(
                 other,
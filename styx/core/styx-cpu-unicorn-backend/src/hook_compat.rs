@@ -10,6 +10,9 @@
 //! [StyxHookDescriptor] a pointer to the backend's [crate::CorePointers]. These are updated every
 //! time the backend is executed to ensure the pointers are valid if the user moves them.
 //!
+//! Each hook's [`HookControl`] is checked right before its callback, fault hooks still go through
+//! exception handling when their callback is skipped.
+//!
 //! Errors in hooks are stored in the unicorn backend and emulation is stopped.
 use log::trace;
 use std::ops::RangeBounds;
@@ -17,7 +20,7 @@ use styx_errors::{anyhow::anyhow, UnknownError};
 use styx_processor::{
     core::{Exception, HandleExceptionAction},
     event_controller::EventController,
-    hooks::{CoreHandle, MemFaultData, Resolution, StyxHook},
+    hooks::{CoreHandle, HookControl, MemFaultData, Resolution, StyxHook},
     memory::{
         helpers::{ReadExt, WriteExt},
         MemoryRegionSize, Mmu,
@@ -30,23 +33,31 @@ use crate::{hooks::StyxHookDescriptor, UnicornBackend};
 /// Extracts hook and [CoreHandle] references from ptr_state and hook pointers and gives them to a
 /// hook specific logic callback `F`. The hook logic callback is in charge of calling the [StyxHook]
 /// call function but can also sanity check the hook type and address args.
+///
+/// The hook logic is skipped if the hook's [`HookControl`] says so.
 fn hook_proxy<T, F: FnOnce(CoreHandle, &mut StyxHook) -> Result<T, UnknownError>>(
     hook: *mut StyxHookDescriptor,
     hook_logic: F,
 ) -> Option<T> {
-    hook_proxy_separate(hook, |cpu, mmu, ev, hook| {
-        let core = CoreHandle::new(cpu, mmu, ev);
-        hook_logic(core, hook)
+    hook_proxy_separate(hook, |cpu, mmu, ev, control, hook| {
+        let mut core = CoreHandle::new(cpu, mmu, ev);
+        if !control.should_run(&mut core)? {
+            return Ok(None);
+        }
+        hook_logic(core, hook).map(Some)
     })
+    .flatten()
 }
 
-/// Same as [`hook_proxy`] but with the trinity split out.
+/// Same as [`hook_proxy`] but with the trinity split out and without checking the
+/// [`HookControl`].
 fn hook_proxy_separate<
     T,
     F: FnOnce(
         &mut UnicornBackend,
         &mut Mmu,
         &mut EventController,
+        &HookControl,
         &mut StyxHook,
     ) -> Result<T, UnknownError>,
 >(
@@ -66,7 +77,7 @@ fn hook_proxy_separate<
     let ev = unsafe { &mut *ptr_core.event_controller };
 
     log::trace!("hook proxy call hook {:?}", hook.styx_hook);
-    let hook_logic_result = hook_logic(cpu, mmu, ev, &mut hook.styx_hook);
+    let hook_logic_result = hook_logic(cpu, mmu, ev, &hook.control, &mut hook.styx_hook);
 
     // re-sync memory after hook callback
     if !cpu.check_synced(mmu).unwrap() {
@@ -244,7 +255,7 @@ pub fn invalid_insn_hook_proxy(
     hook: *mut StyxHookDescriptor,
 ) -> bool {
     trace!("invalid insn fault");
-    let res = hook_proxy_separate(hook, |cpu, mmu, ev, hook| {
+    let res = hook_proxy_separate(hook, |cpu, mmu, ev, control, hook| {
         let action = cpu
             .exception
             .handle_exception(Exception::InvalidInstruction);
@@ -261,7 +272,10 @@ pub fn invalid_insn_hook_proxy(
                 };
 
                 // call callback + propagate the return code
-                let proc = CoreHandle::new(cpu, mmu, ev);
+                let mut proc = CoreHandle::new(cpu, mmu, ev);
+                if !control.should_run(&mut proc)? {
+                    return Ok(Resolution::NotFixed);
+                }
                 hook.call(proc)
             }
         }
@@ -287,7 +301,7 @@ pub fn protection_fault_hook_proxy(
     value: i64, // always 0 when `mem_type` is a `READ_PROT`
     hook: *mut StyxHookDescriptor,
 ) -> bool {
-    let res = hook_proxy_separate(hook, |cpu, mmu, ev, hook| {
+    let res = hook_proxy_separate(hook, |cpu, mmu, ev, control, hook| {
         let exception = match mem_type {
             unicorn_const::MemType::READ_PROT => Exception::UnmappedMemoryRead,
             unicorn_const::MemType::WRITE_PROT => Exception::UnmappedMemoryWrite,
@@ -318,7 +332,10 @@ pub fn protection_fault_hook_proxy(
                     range.contains(&address),
                     "Trigger address: 0x{address:X} is not in {range:?}",
                 );
-                let proc = CoreHandle::new(cpu, mmu, ev);
+                let mut proc = CoreHandle::new(cpu, mmu, ev);
+                if !control.should_run(&mut proc)? {
+                    return Ok(Resolution::NotFixed);
+                }
                 // get the fault data for the callback
                 let fault_bytes = match proc.endian() {
                     styx_cpu_type::ArchEndian::LittleEndian => value.to_le_bytes(),
@@ -365,7 +382,7 @@ pub fn unmapped_fault_hook_proxy(
     value: i64, // always 0 when `mem_type` is a `READ_UNMAPPED`
     hook: *mut StyxHookDescriptor,
 ) -> bool {
-    let res = hook_proxy_separate(hook, |cpu, mmu, ev, hook| {
+    let res = hook_proxy_separate(hook, |cpu, mmu, ev, control, hook| {
         let exception = match mem_type {
             unicorn_const::MemType::READ_UNMAPPED => Exception::UnmappedMemoryRead,
             unicorn_const::MemType::WRITE_UNMAPPED => Exception::UnmappedMemoryWrite,
@@ -395,7 +412,10 @@ pub fn unmapped_fault_hook_proxy(
                     range.contains(&address),
                     "Trigger address: 0x{address:X} is not in {range:?}",
                 );
-                let proc = CoreHandle::new(cpu, mmu, ev);
+                let mut proc = CoreHandle::new(cpu, mmu, ev);
+                if !control.should_run(&mut proc)? {
+                    return Ok(Resolution::NotFixed);
+                }
                 // get the fault data for the callback
                 let fault_bytes = match proc.endian() {
                     styx_cpu_type::ArchEndian::LittleEndian => value.to_le_bytes(),
@@ -11,8 +11,9 @@
 use derivative::Derivative;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::Arc;
 use styx_errors::{anyhow::anyhow, UnknownError};
-use styx_processor::hooks::{DeleteHookError, HookToken, StyxHook};
+use styx_processor::hooks::{DeleteHookError, HookControl, HookControlError, HookToken, StyxHook};
 
 use crate::CorePointers;
 
@@ -23,6 +24,8 @@ pub struct StyxHookDescriptor {
     #[derivative(Debug = "ignore")]
     pub styx_hook: StyxHook,
     pub core: *mut CorePointers,
+    /// Checked before calling `styx_hook`.
+    pub control: Arc<HookControl>,
 }
 
 /// This is a map that works off of the generated [`HookToken`]'s provided by a cpu engine when
@@ -85,6 +88,14 @@ impl StyxHookMap {
         }
         Ok(())
     }
+
+    /// Given a hook with a [`HookToken`], get its [`HookControl`].
+    pub fn hook_control(&self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.inner
+            .get(&token)
+            .map(|descriptor| descriptor.control.clone())
+            .ok_or(HookControlError::HookDoesNotExist)
+    }
 }
 
 /// Is the hook valid for use in the unicorn backend?
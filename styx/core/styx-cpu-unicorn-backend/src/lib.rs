@@ -1,6 +1,16 @@
 // SPDX-License-Identifier: BSD-2-Clause
 
-use std::{collections::BTreeMap, ffi::c_void, marker::PhantomPinned, mem::size_of, pin::Pin};
+use std::{
+    collections::BTreeMap,
+    ffi::c_void,
+    marker::PhantomPinned,
+    mem::size_of,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use arbitrary_int::{u20, u40, u80};
 use beau_collector::BeauCollector;
@@ -15,7 +25,8 @@ use unicorn_engine::{ffi, unicorn_const, HookType, MemRegion, Unicorn};
 use styx_cpu_type::{
     arch::{
         arm::{SpecialArmRegister, SpecialArmRegisterValues},
-        backends::{ArchRegister, ArchVariant, SpecialArchRegister},
+        backends::{ArchRegister, ArchVariant, BasicArchRegister, SpecialArchRegister},
+        ppc32::Ppc32Register,
         Arch, ArchEndian, ArchitectureDef, RegisterValue,
     },
     TargetExitReason,
@@ -26,9 +37,12 @@ use styx_errors::{
 };
 use styx_processor::{
     core::ExceptionBehavior,
-    cpu::{CpuBackend, ExecutionReport, ReadRegisterError, WriteRegisterError},
+    cpu::{CpuBackend, CpuBackendExt, ExecutionReport, ReadRegisterError, WriteRegisterError},
     event_controller::EventController,
-    hooks::{AddHookError, AddressRange, DeleteHookError, HookToken, Hookable, StyxHook},
+    hooks::{
+        AddHookError, AddressRange, DeleteHookError, HookControl, HookControlError, HookToken,
+        Hookable, StyxHook,
+    },
     memory::{memory_region::MemoryRegion, MemoryPermissions, MemoryRegionSize, Mmu},
 };
 use styx_sync::cell::UnsafeCell;
@@ -43,7 +57,12 @@ mod register_compat;
 
 use arch_compat::styx_to_unicorn_machine;
 use error::UcErr;
-use register_compat::{styx_to_unicorn_register, UcArmCoprocessorRegisterAction};
+use register_compat::{
+    styx_to_unicorn_register, UcArm64SystemRegisterAction, UcArmCoprocessorRegisterAction,
+};
+
+/// PowerPC MSR wait state enable, the cpu idles until an interrupt clears it.
+const PPC_MSR_WE: u32 = 1 << 18;
 
 /// A pretty unsafe struct that is used to proxy calls to unicorn
 /// while remaining [`Send`] + [`Sync`].
@@ -124,6 +143,64 @@ pub struct UnicornBackend {
     saved_context: BTreeMap<ArchRegister, RegisterValue>,
     exception: ExceptionBehavior,
     exception_requested_stop: Option<TargetExitReason>,
+    /// Shared with the code hook counting instructions, see [InstructionCounter].
+    #[derivative(Debug = "ignore")]
+    counter: Arc<InstructionCounter>,
+}
+
+/// Counts executed instructions and stops unicorn once the requested count is met.
+///
+/// This replaces unicorn's own instruction count, which ends emulation the same way as a halting
+/// instruction does and does not tell how many instructions ran. The counting code hook is added
+/// before any other hook so it runs first and later hooks don't see the instruction it stops at.
+#[derive(Debug, Default)]
+struct InstructionCounter {
+    executed: AtomicU64,
+    limit: AtomicU64,
+    /// Address of the last counted instruction.
+    last_address: AtomicU64,
+}
+
+impl InstructionCounter {
+    /// Start counting towards `limit` instructions.
+    fn start(&self, limit: u64) {
+        self.executed.store(0, Ordering::Relaxed);
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Count the instruction at `address` about to execute, false if the limit is met and unicorn
+    /// should stop.
+    fn count(&self, address: u64) -> bool {
+        let executed = self.executed.load(Ordering::Relaxed);
+        if executed >= self.limit.load(Ordering::Relaxed) {
+            return false;
+        }
+        self.executed.store(executed + 1, Ordering::Relaxed);
+        self.last_address.store(address, Ordering::Relaxed);
+        true
+    }
+
+    /// Instructions started since [InstructionCounter::start()].
+    fn executed(&self) -> u64 {
+        self.executed.load(Ordering::Relaxed)
+    }
+
+    /// Instructions completed when emulation stopped early at `pc`.
+    ///
+    /// A hook stopping emulation or a fault leaves `pc` at an instruction that was counted but
+    /// did not complete.
+    fn completed_at(&self, pc: u64) -> u64 {
+        let executed = self.executed();
+        if executed > 0 && self.last_address.load(Ordering::Relaxed) == pc {
+            executed - 1
+        } else {
+            executed
+        }
+    }
+
+    fn limit_met(&self) -> bool {
+        self.executed() >= self.limit.load(Ordering::Relaxed)
+    }
 }
 
 /// Holds the pointers needed to reconstruct a CoreHandle in a unicorn hook proxy.
@@ -228,6 +305,7 @@ fn add_unicorn_hook(
     let mut callback_meta = Box::new(StyxHookDescriptor {
         styx_hook: hook,
         core: state,
+        control: Default::default(),
     });
     // make ptr for output token
     let mut hook_token = HookToken::null_pointer();
@@ -295,6 +373,10 @@ impl Hookable for UnicornBackend {
 
         Ok(())
     }
+
+    fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.hook_map.hook_control(token)
+    }
 }
 impl CpuBackend for UnicornBackend {
     fn read_register_raw(&mut self, reg: ArchRegister) -> Result<RegisterValue, ReadRegisterError> {
@@ -304,6 +386,28 @@ impl CpuBackend for UnicornBackend {
         // the unicorn register to read
         let uc_reg = styx_to_unicorn_register(reg)?;
 
+        // aarch64 system registers without a named unicorn register are read by encoding
+        if let ArchRegister::Basic(BasicArchRegister::Aarch64(r)) = reg {
+            if let Some(mut value) = UcArm64SystemRegisterAction::from_register(r) {
+                let value_bytes: &mut [u8] = unsafe { any_as_u8_slice_mut(&mut value) };
+
+                // call the ffi with the raw byte array
+                let err = unsafe {
+                    ffi::uc_reg_read(
+                        self.inner().get_handle(),
+                        uc_reg,
+                        value_bytes.as_mut_ptr() as _,
+                    )
+                };
+
+                // make sure the backend succeeded
+                UcErr::from_unicorn(err)
+                    .with_context(|| format!("failed to read unicorn register {reg:?}"))?;
+
+                return Ok(value.val.into());
+            }
+        }
+
         let read_u64 = || {
             self.inner()
                 .reg_read(uc_reg)
@@ -442,6 +546,24 @@ impl CpuBackend for UnicornBackend {
                 .with_context(|| format!("failed to write unicorn register {reg:?}"))
         };
 
+        // aarch64 system registers without a named unicorn register are written by encoding
+        if let ArchRegister::Basic(BasicArchRegister::Aarch64(r)) = reg {
+            if let Some(mut uc_reg_val) = UcArm64SystemRegisterAction::from_register(r) {
+                let RegisterValue::u64(value) = value else {
+                    return Err(WriteRegisterError::Other(anyhow!(
+                        "aarch64 system register {reg} takes a u64"
+                    )));
+                };
+                uc_reg_val.val = value;
+
+                // # Safety
+                // The type being converted to a raw pointer is `#[repr(C)]`
+                let uc_type_as_bytes = unsafe { any_as_u8_slice(&uc_reg_val) };
+
+                return Ok(write_reg_long(uc_type_as_bytes)?);
+            }
+        }
+
         // convert into the size for the backend
         // - by default unicorn takes a u64
         // - anything larger than a u64 has its own method in the ffi (reg_write_long)
@@ -526,13 +648,13 @@ impl CpuBackend for UnicornBackend {
         }
         // clamp to u64::MAX microseconds
         let timeout_micros = u64::MAX;
-        let count_usize = count.clamp(0, usize::MAX as u64) as usize;
 
         // we are running now
         self.set_running();
+        self.counter.start(count);
 
-        // start emulation at pc
-        let uc_exit_reason = self.inner().emu_start(pc, 0, timeout_micros, count_usize);
+        // start emulation at pc, the instruction count is met by our counter
+        let uc_exit_reason = self.inner().emu_start(pc, 0, timeout_micros, 0);
 
         // no longer running, set stopped
         self.set_stopped();
@@ -551,23 +673,26 @@ impl CpuBackend for UnicornBackend {
                     Err(wrapped_error.unwrap_err())
                 } else if let Some(exit_reason) = self.exception_requested_stop.clone() {
                     self.exception_requested_stop = None;
-                    Ok(ExecutionReport::unknown_instruction_count(exit_reason))
+                    self.stopped_early(exit_reason)
                 } else if self.stop_request_check_and_reset() {
-                    Ok(ExecutionReport::unknown_instruction_count(
-                        TargetExitReason::HostStopRequest,
-                    ))
+                    self.stopped_early(TargetExitReason::HostStopRequest)
                 } else if self.instruction_count_met() {
                     Ok(ExecutionReport::new(
                         TargetExitReason::InstructionCountComplete,
                         count,
                     ))
+                } else if self.waiting_for_interrupt()? {
+                    Ok(ExecutionReport::new(
+                        TargetExitReason::WaitForInterrupt,
+                        self.counter.executed(),
+                    ))
                 } else {
                     Err(anyhow!("unicorn.emu_start returned okay but did not complete instruction count or timeout"))
                 }
             }
             // an error was tripped by either the target or the host
             Err(err) => match TryInto::<TargetExitReason>::try_into(err) {
-                Ok(exit_reason) => Ok(ExecutionReport::unknown_instruction_count(exit_reason)),
+                Ok(exit_reason) => self.stopped_early(exit_reason),
                 Err(_) => Err(anyhow!("unknown unicorn error {err:?}")),
             },
         }
@@ -781,9 +906,19 @@ impl UnicornBackend {
             styx_to_unicorn_machine(arch, arch_variant, endian).unwrap();
 
         // setup the correct unicorn instance
-        let uc = Unicorn::new(uc_arch, uc_mode).unwrap();
+        let mut uc = Unicorn::new(uc_arch, uc_mode).unwrap();
         uc.ctl_set_cpu_model(uc_model).unwrap();
 
+        let counter = Arc::new(InstructionCounter::default());
+        let hook_counter = counter.clone();
+        uc.add_code_hook(1, 0, move |uc, address, _size| {
+            if !hook_counter.count(address) {
+                // only fails if unicorn is not running
+                let _ = uc.emu_stop();
+            }
+        })
+        .unwrap();
+
         UnicornBackend {
             hook_map: StyxHookMap::default(),
             arch_def,
@@ -798,6 +933,7 @@ impl UnicornBackend {
             saved_context: BTreeMap::default(),
             exception,
             exception_requested_stop: None,
+            counter,
         }
     }
 
@@ -829,11 +965,74 @@ impl UnicornBackend {
     /// |----------------|---------------------|---------|
     /// | false          | false               | X (false) |
     /// | false          | true                | X (false) |
-    /// | true           | false               | true, cpu stopped, and was not requested. implies insn count met|
+    /// | true           | false               | cpu stopped, and was not requested. insn count met if the [InstructionCounter] says so |
     /// | true           | true                | false, cpu stopped, was requested. implies insn count not met |
     #[inline]
     fn instruction_count_met(&self) -> bool {
-        self.stopped && !self.stop_request
+        self.stopped && !self.stop_request && self.counter.limit_met()
+    }
+
+    /// Report for an emulation that ended before the instruction count was met.
+    fn stopped_early(
+        &mut self,
+        exit_reason: TargetExitReason,
+    ) -> Result<ExecutionReport, UnknownError> {
+        let pc = self.pc()?;
+        Ok(ExecutionReport::new(
+            exit_reason,
+            self.counter.completed_at(pc),
+        ))
+    }
+
+    /// Did the cpu halt in a wait instruction?
+    ///
+    /// Only asked once the instruction count was not met. Unicorn ends emulation without an error
+    /// when the cpu halts and handles `EXCP_HLT` internally without calling the interrupt hooks, so
+    /// the instruction before `pc` is checked: `wfi` on Arm and AArch64, `wait` on MIPS, and
+    /// MSR\[WE\] is checked on PowerPC. `wfe` is executed as a hint by unicorn and does not stop
+    /// emulation.
+    fn waiting_for_interrupt(&mut self) -> Result<bool, UnknownError> {
+        let pc = self.pc()?;
+        let waiting = match self.architecture().architecture() {
+            Arch::Arm => {
+                let mode = self
+                    .inner()
+                    .query(unicorn_const::Query::MODE)
+                    .pipe(UcErr::from_unicorn_result)
+                    .with_context(|| "couldn't query unicorn mode")?;
+                if (mode as i32 & unicorn_const::Mode::THUMB.bits()) > 0 {
+                    // `wfi` or `wfi.w`, a 32 bit thumb instruction is two halfwords
+                    self.instruction_before(pc, 2) == Some(0xBF30)
+                        || self
+                            .instruction_before(pc, 4)
+                            .map(|insn| insn.rotate_left(16))
+                            == Some(0xF3AF_8003)
+                } else {
+                    self.instruction_before(pc, 4)
+                        .is_some_and(|insn| insn & 0x0FFF_FFFF == 0x0320_F003)
+                }
+            }
+            Arch::Aarch64 => self.instruction_before(pc, 4) == Some(0xD503_207F),
+            Arch::Mips32 => self
+                .instruction_before(pc, 4)
+                .is_some_and(|insn| insn & 0xFE00_003F == 0x4200_0020),
+            Arch::Ppc32 => self.read_register::<u32>(Ppc32Register::Msr)? & PPC_MSR_WE != 0,
+            _ => false,
+        };
+        Ok(waiting)
+    }
+
+    /// The `size` byte instruction ending at `pc`, [None] if it can't be read.
+    fn instruction_before(&self, pc: u64, size: usize) -> Option<u32> {
+        let address = pc.checked_sub(size as u64)?;
+        let bytes = self.inner().mem_read_as_vec(address, size).ok()?;
+        let insn = match (size, self.endian) {
+            (2, ArchEndian::LittleEndian) => u16::from_le_bytes(bytes.try_into().ok()?) as u32,
+            (2, ArchEndian::BigEndian) => u16::from_be_bytes(bytes.try_into().ok()?) as u32,
+            (_, ArchEndian::LittleEndian) => u32::from_le_bytes(bytes.try_into().ok()?),
+            (_, ArchEndian::BigEndian) => u32::from_be_bytes(bytes.try_into().ok()?),
+        };
+        Some(insn)
     }
 
     #[inline]
@@ -1523,6 +1722,38 @@ mod tests {
         machine.run_and_assert_exit_reason(TargetExitReason::InstructionCountComplete);
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg_attr(asan, ignore)]
+    fn test_wfi_exit_status() {
+        let mut machine = TestMachine::with_code("movs r0, #1; wfi; movs r0, #2");
+
+        let report = machine
+            .proc
+            .execute(&mut machine.mmu, &mut machine.ev, 3)
+            .unwrap();
+        assert_eq!(TargetExitReason::WaitForInterrupt, report.exit_reason);
+        assert_eq!(Some(2), report.instructions_executed);
+        // the waiting instruction completed
+        assert_eq!(0x4004, machine.proc.pc().unwrap());
+        assert_eq!(
+            1,
+            machine.proc.read_register::<u32>(ArmRegister::R0).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg_attr(asan, ignore)]
+    fn test_count_met_after_wfi() {
+        // the count is met right behind a wfi that never ran
+        let mut machine = TestMachine::with_code("b over; wfi; over: movs r0, #2");
+        machine.instruction_count = 1;
+
+        machine.run_and_assert_exit_reason(TargetExitReason::InstructionCountComplete);
+        assert_eq!(0x4004, machine.proc.pc().unwrap());
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     #[cfg_attr(asan, ignore)] // unicorn leaks memory
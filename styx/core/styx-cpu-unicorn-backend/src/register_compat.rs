@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! Styx to unicorn register compatibility layer
+use styx_cpu_type::arch::aarch64::Aarch64Register;
 use styx_cpu_type::arch::arm::{CoProcessor, CoProcessorValue, SpecialArmRegisterValues};
 use styx_cpu_type::arch::backends::{ArchRegister, BasicArchRegister, SpecialArchRegister};
 use styx_cpu_type::arch::RegisterValue;
@@ -92,6 +93,72 @@ impl From<CoProcessor> for UcArmCoprocessorRegisterAction {
     }
 }
 
+/// Rust version of the type representing an AArch64 system register access
+///
+/// Sourced from unicorn, the typedef looks like this:
+///
+/// ```c
+/// typedef struct uc_arm64_cp_reg {
+///     uint32_t crn; // Coprocessor register number
+///     uint32_t crm; // Coprocessor register number
+///     uint32_t op0; // Opcode0
+///     uint32_t op1; // Opcode1
+///     uint32_t op2; // Opcode2
+///     uint64_t val; // The value to read/write
+/// } uc_arm64_cp_reg;
+/// ```
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct UcArm64SystemRegisterAction {
+    /// Coprocessor register number 1
+    crn: u32,
+    /// Coprocessor register number 2
+    crm: u32,
+    /// Opcode0
+    op0: u32,
+    /// Opcode1
+    op1: u32,
+    /// Opcode2
+    op2: u32,
+    /// Value to read to / write from
+    pub val: u64,
+}
+
+impl UcArm64SystemRegisterAction {
+    const fn new(op0: u32, op1: u32, crn: u32, crm: u32, op2: u32) -> Self {
+        Self {
+            crn,
+            crm,
+            op0,
+            op1,
+            op2,
+            val: 0,
+        }
+    }
+
+    /// Access for the AArch64 system registers without a named unicorn register, `None` for
+    /// all other registers.
+    pub fn from_register(reg: Aarch64Register) -> Option<Self> {
+        let access = match reg {
+            Aarch64Register::Daif => Self::new(3, 3, 4, 2, 1),
+            Aarch64Register::CurrentEl => Self::new(3, 0, 4, 2, 2),
+            Aarch64Register::Spsel => Self::new(3, 0, 4, 2, 0),
+            Aarch64Register::SpsrEl1 => Self::new(3, 0, 4, 0, 0),
+            Aarch64Register::CntfrqEl0 => Self::new(3, 3, 14, 0, 0),
+            Aarch64Register::CntpctEl0 => Self::new(3, 3, 14, 0, 1),
+            Aarch64Register::CntvctEl0 => Self::new(3, 3, 14, 0, 2),
+            Aarch64Register::CntpTvalEl0 => Self::new(3, 3, 14, 2, 0),
+            Aarch64Register::CntpCtlEl0 => Self::new(3, 3, 14, 2, 1),
+            Aarch64Register::CntpCvalEl0 => Self::new(3, 3, 14, 2, 2),
+            Aarch64Register::CntvTvalEl0 => Self::new(3, 3, 14, 3, 0),
+            Aarch64Register::CntvCtlEl0 => Self::new(3, 3, 14, 3, 1),
+            Aarch64Register::CntvCvalEl0 => Self::new(3, 3, 14, 3, 2),
+            _ => return None,
+        };
+        Some(access)
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::items_after_test_module)]
 mod unicorn_arm_coproc_access_tests {
@@ -189,6 +256,12 @@ pub fn styx_to_unicorn_register(reg: ArchRegister) -> Result<i32, UnknownError>
         ArchRegister::Basic(BasicArchRegister::Ppc32(inner)) => {
             Ok(Into::<unicorn_engine::RegisterPPC>::into(inner).into())
         }
+        ArchRegister::Basic(BasicArchRegister::Aarch64(inner)) => {
+            Ok(Into::<unicorn_engine::RegisterARM64>::into(inner).into())
+        }
+        ArchRegister::Basic(BasicArchRegister::Mips32(inner)) => {
+            Ok(Into::<unicorn_engine::RegisterMIPS>::into(inner).into())
+        }
         ArchRegister::Special(SpecialArchRegister::Arm(inner)) => {
             Ok(Into::<unicorn_engine::RegisterARM>::into(inner).into())
         }
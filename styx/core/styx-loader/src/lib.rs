@@ -153,6 +153,10 @@ pub struct MemoryLoaderDesc {
     /// been passed to the backing loader. Eg. sometimes it is possible to
     /// get target hints from parsing firmware files, or versions etc.
     env_state: LoaderHints,
+
+    /// Symbols and source lines recovered from the firmware file, empty if
+    /// the format or file does not carry any.
+    symbols: SymbolTable,
 }
 
 impl MemoryLoaderDesc {
@@ -279,6 +283,45 @@ impl MemoryLoaderDesc {
         self.env_state.drain().collect()
     }
 
+    /// Provides read only access to the [`SymbolTable`] of the loaded program
+    ///
+    /// ```rust
+    /// use styx_loader::{MemoryLoaderDesc, Symbol, SymbolKind, SymbolTable};
+    ///
+    /// let main = Symbol::new("main", 0x1000, 0x20, SymbolKind::Function);
+    ///
+    /// let mut desc = MemoryLoaderDesc::default();
+    /// assert!(desc.symbols().is_empty());
+    /// desc.set_symbols(SymbolTable::new(vec![main], vec![]));
+    /// assert_eq!(Some(0x1000), desc.symbols().address_of("main"));
+    /// ```
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Replace the [`SymbolTable`] of the loaded program
+    pub fn set_symbols(&mut self, symbols: SymbolTable) {
+        self.symbols = symbols;
+    }
+
+    /// Take ownership of the [`SymbolTable`], leaving an empty one behind
+    ///
+    /// ```rust
+    /// use styx_loader::{MemoryLoaderDesc, Symbol, SymbolKind, SymbolTable};
+    ///
+    /// let main = Symbol::new("main", 0x1000, 0x20, SymbolKind::Function);
+    ///
+    /// let mut desc = MemoryLoaderDesc::default();
+    /// desc.set_symbols(SymbolTable::new(vec![main], vec![]));
+    ///
+    /// let symbols = desc.take_symbols();
+    /// assert_eq!(Some(0x1000), symbols.address_of("main"));
+    /// assert!(desc.symbols().is_empty());
+    /// ```
+    pub fn take_symbols(&mut self) -> SymbolTable {
+        std::mem::take(&mut self.symbols)
+    }
+
     /// Adds an arbitrary environment state variable to the loader description.
     ///
     /// Before adding the variable to the collection, the name of the variable
@@ -450,6 +493,9 @@ impl<'a> Iterator for LoaderRegionIterMut<'a> {
 mod loaders;
 pub use loaders::*;
 
+mod symbols;
+pub use symbols::*;
+
 #[cfg(test)]
 mod tests {
     use styx_memory::MemoryPermissions;
@@ -2,11 +2,18 @@
 // import all the modules
 mod blackfin;
 mod elf;
+mod ihex;
 mod parameterized;
 mod raw;
+mod sparse;
+mod srec;
+mod uf2;
 
 // re-export under styx-loader::loaders::*;
 pub use blackfin::BlackfinLDRLoader;
 pub use elf::{ElfLoader, ElfLoaderConfig};
+pub use ihex::IntelHexLoader;
 pub use parameterized::*;
 pub use raw::RawLoader;
+pub use srec::SRecordLoader;
+pub use uf2::Uf2Loader;
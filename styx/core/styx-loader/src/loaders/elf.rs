@@ -1,9 +1,16 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! Loads an ELF compatible object into something usable by `styx`
 
-use crate::{Loader, LoaderHints, MemoryLoaderDesc, StyxLoaderError};
+use crate::{
+    LineEntry, Loader, LoaderHints, MemoryLoaderDesc, StyxLoaderError, Symbol, SymbolKind,
+    SymbolTable,
+};
 use goblin::elf::Elf;
 use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use styx_cpu_type::arch::{Arch, ArchEndian};
 use styx_errors::anyhow::Context;
 use styx_memory::{MemoryPermissions, MemoryRegion};
@@ -51,12 +58,16 @@ impl From<ElfMachine> for Arch {
 pub struct ElfLoaderConfig {
     /// Log a warning if the provided ELF has no loadable segments. Defaults to true.
     pub warn_no_loadable_segments: bool,
+    /// Read `.symtab` and the DWARF line tables into the [`SymbolTable`] of the
+    /// [`MemoryLoaderDesc`]. Defaults to true.
+    pub load_debug_info: bool,
 }
 
 impl Default for ElfLoaderConfig {
     fn default() -> Self {
         Self {
             warn_no_loadable_segments: true,
+            load_debug_info: true,
         }
     }
 }
@@ -75,6 +86,12 @@ impl Default for ElfLoaderConfig {
 /// - if provided, a `pc` hint of type [`u64`] can be provided, this *will* override
 ///   the header.
 ///
+/// # Debug Info
+/// Function, object and untyped symbols from `.symtab` and the line tables from
+/// `.debug_line` are collected into the [`SymbolTable`] of the returned
+/// [`MemoryLoaderDesc`], see [`ElfLoaderConfig::load_debug_info`]. Malformed DWARF is
+/// logged and ignored, it never fails the load.
+///
 /// TODO: test all the hints
 /// TODO: add integration tests for this loader
 #[derive(Debug, Default)]
@@ -149,7 +166,7 @@ pub(crate) fn load_elf(
 
     // collect all the regions we need to load
     let mut regions = Vec::new();
-    for ph in elf.program_headers {
+    for ph in elf.program_headers.iter() {
         // Look only for loadable segments, see `man 5 elf` for more
         //
         // Note that `PT_LOAD` segments are described by
@@ -236,5 +253,185 @@ pub(crate) fn load_elf(
             .with_context(|| "failed to set pc to elf header")?;
     }
 
+    if config.load_debug_info {
+        let symbols = load_symbols(&elf, arch);
+        let lines = load_lines(&elf, data).unwrap_or_else(|err| {
+            log::warn!("Could not parse DWARF line info, continuing without it: {err}");
+            Vec::new()
+        });
+        log::trace!(
+            "File has `{}` symbols and `{}` line entries",
+            symbols.len(),
+            lines.len()
+        );
+
+        desc.set_symbols(SymbolTable::new(symbols, lines));
+    }
+
     Ok(desc)
 }
+
+/// Collect the named, defined symbols from `.symtab`.
+fn load_symbols(elf: &Elf, arch: Arch) -> Vec<Symbol> {
+    use goblin::elf::section_header::SHN_UNDEF;
+    use goblin::elf::sym::{STB_GLOBAL, STB_WEAK, STT_FUNC, STT_NOTYPE, STT_OBJECT};
+
+    elf.syms
+        .iter()
+        .filter_map(|sym| {
+            if sym.st_shndx == SHN_UNDEF as usize {
+                return None;
+            }
+
+            let kind = match sym.st_type() {
+                STT_FUNC => SymbolKind::Function,
+                STT_OBJECT => SymbolKind::Object,
+                STT_NOTYPE => SymbolKind::Other,
+                // sections, files, tls etc.
+                _ => return None,
+            };
+
+            let name = elf.strtab.get_at(sym.st_name)?;
+            // arm mapping symbols (`$a`, `$t`, `$d`) mark instruction set changes
+            if name.is_empty() || name.starts_with('$') {
+                return None;
+            }
+
+            let mut address = sym.st_value;
+            // the thumb bit is not part of the address
+            if arch == Arch::Arm && kind == SymbolKind::Function {
+                address &= !1;
+            }
+
+            Some(Symbol {
+                name: name.to_owned(),
+                address,
+                size: sym.st_size,
+                kind,
+                global: matches!(sym.st_bind(), STB_GLOBAL | STB_WEAK),
+            })
+        })
+        .collect()
+}
+
+/// Collect the rows of every DWARF line program.
+fn load_lines<'a>(elf: &Elf<'a>, data: &'a [u8]) -> Result<Vec<LineEntry>, gimli::Error> {
+    let endian = if elf.little_endian {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+
+    // missing sections are empty, gimli will complain if it actually needed them
+    let section = |id: gimli::SectionId| -> Result<_, gimli::Error> {
+        let bytes = elf
+            .section_headers
+            .iter()
+            .find(|sh| elf.shdr_strtab.get_at(sh.sh_name) == Some(id.name()))
+            .and_then(|sh| sh.file_range())
+            .and_then(|range| data.get(range))
+            .unwrap_or(&[]);
+        Ok(gimli::EndianSlice::new(bytes, endian))
+    };
+    let dwarf = gimli::Dwarf::load(section)?;
+
+    let mut lines = Vec::new();
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        let Some(program) = unit.line_program.clone() else {
+            continue;
+        };
+
+        // many rows share a file, only build each path once
+        let mut files: HashMap<u64, Arc<str>> = HashMap::new();
+        let mut rows = program.rows();
+        while let Some((header, row)) = rows.next_row()? {
+            if row.end_sequence() {
+                lines.push(LineEntry::end_sequence(row.address()));
+                continue;
+            }
+            let Some(line) = row.line() else {
+                continue;
+            };
+
+            let file = match files.entry(row.file_index()) {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => entry
+                    .insert(line_file_path(&dwarf, &unit, header, row)?)
+                    .clone(),
+            };
+            lines.push(LineEntry::new(row.address(), file, line.get()));
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Path of the source file of a line program row.
+fn line_file_path<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    header: &gimli::LineProgramHeader<R>,
+    row: &gimli::LineRow,
+) -> Result<Arc<str>, gimli::Error> {
+    let Some(file) = row.file(header) else {
+        return Ok(Arc::from("??"));
+    };
+
+    let mut path = PathBuf::new();
+    if let Some(directory) = file.directory(header) {
+        let directory = dwarf.attr_string(unit, directory)?;
+        path.push(&*directory.to_string_lossy()?);
+    }
+    let name = dwarf.attr_string(unit, file.path_name())?;
+    path.push(&*name.to_string_lossy()?);
+
+    Ok(Arc::from(path.to_string_lossy()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use styx_util::resolve_test_bin;
+
+    const LED_OUTPUT_PATH: &str = "arm/kinetis_21/bin/led_output/led_output_debug.elf";
+
+    /// Checks symbols and lines are read from a thumb elf built with debug info.
+    #[test]
+    fn test_debug_info() {
+        let data = std::fs::read(resolve_test_bin(LED_OUTPUT_PATH)).unwrap();
+        let mut desc = ElfLoader::default()
+            .load_bytes(Cow::Owned(data), LoaderHints::new())
+            .unwrap();
+        let symbols = desc.take_symbols();
+
+        // thumb bit is cleared
+        let main = symbols.get("main").unwrap();
+        assert_eq!(main.address, 0x74C);
+        assert_eq!(main.size, 100);
+        assert_eq!(main.kind, SymbolKind::Function);
+        assert_eq!(Some(0x71C), symbols.address_of("delay"));
+
+        assert_eq!("delay+0x6", symbols.lookup(0x722).unwrap().to_string());
+
+        let line = symbols.line(0x722).unwrap();
+        assert!(line.file.ends_with("gpio_led_output.c"), "{line}");
+        assert_eq!(line.line, 64);
+    }
+
+    /// Debug info is skipped when disabled.
+    #[test]
+    fn test_no_debug_info() {
+        let data = std::fs::read(resolve_test_bin(LED_OUTPUT_PATH)).unwrap();
+        let loader = ElfLoader::new(ElfLoaderConfig {
+            load_debug_info: false,
+            ..Default::default()
+        });
+        let desc = loader
+            .load_bytes(Cow::Owned(data), LoaderHints::new())
+            .unwrap();
+
+        assert!(desc.symbols().is_empty());
+    }
+}
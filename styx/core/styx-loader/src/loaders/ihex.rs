@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::borrow::Cow;
+
+use super::sparse::{decode_hex, SparseImage};
+use crate::{Loader, LoaderHints, MemoryLoaderDesc, StyxLoaderError};
+use log::warn;
+use styx_cpu_type::arch::Arch;
+use styx_memory::MemoryPermissions;
+
+const RECORD_DATA: u8 = 0x00;
+const RECORD_END_OF_FILE: u8 = 0x01;
+const RECORD_EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const RECORD_START_SEGMENT_ADDRESS: u8 = 0x03;
+const RECORD_EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const RECORD_START_LINEAR_ADDRESS: u8 = 0x05;
+
+/// Loader for Intel HEX (`.hex`/`.ihex`) files.
+///
+/// Every run of contiguous data records becomes its own `RWX` memory region, so images with
+/// several sections (eg. flash and an option-byte block) produce a sparse [`MemoryLoaderDesc`].
+/// Segment (`02`) and linear (`04`) extended addresses are supported.
+///
+/// If the file contains a start address record (`03` or `05`) the pc is set to it, this requires
+/// an `arch` hint of type [`styx_cpu_type::Arch`].
+#[derive(Debug, Default)]
+pub struct IntelHexLoader;
+
+impl Loader for IntelHexLoader {
+    /// Returns the name of the [`Loader`]
+    ///
+    /// ```rust
+    /// use styx_loader::{IntelHexLoader, Loader};
+    ///
+    /// assert_eq!("intel hex", IntelHexLoader.name());
+    /// ```
+    fn name(&self) -> &'static str {
+        "intel hex"
+    }
+
+    fn load_bytes(
+        &self,
+        data: Cow<[u8]>,
+        hints: LoaderHints,
+    ) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+        let arch = hints_contain!(hints, "arch", Arch)?;
+        load_ihex(&data, arch)
+    }
+}
+
+/// Load the provided Intel HEX data. Breaking this out into a helper allows us to call it from
+/// other loaders.
+pub(crate) fn load_ihex(
+    data: &[u8],
+    arch: Option<&Arch>,
+) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| StyxLoaderError::MalformedInput("intel hex is not text".to_owned()))?;
+
+    let mut image = SparseImage::default();
+    // added to the address of data records
+    let mut base_address = 0u64;
+    let mut found_end = false;
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = Record::parse(line).map_err(|err| malformed(line_idx, &err))?;
+
+        match record.kind {
+            RECORD_DATA => {
+                image.write(base_address + record.offset as u64, &record.data)?;
+            }
+            RECORD_END_OF_FILE => {
+                found_end = true;
+                break;
+            }
+            RECORD_EXTENDED_SEGMENT_ADDRESS => {
+                base_address = (record.be_value(line_idx, 2)? as u64) << 4;
+            }
+            RECORD_EXTENDED_LINEAR_ADDRESS => {
+                base_address = (record.be_value(line_idx, 2)? as u64) << 16;
+            }
+            RECORD_START_SEGMENT_ADDRESS => {
+                // CS:IP
+                let cs_ip = record.be_value(line_idx, 4)?;
+                image.set_entry(((cs_ip >> 16) << 4) as u64 + (cs_ip & 0xFFFF) as u64);
+            }
+            RECORD_START_LINEAR_ADDRESS => {
+                image.set_entry(record.be_value(line_idx, 4)? as u64);
+            }
+            kind => {
+                return Err(malformed(
+                    line_idx,
+                    &format!("unknown record type {kind:02X}"),
+                ));
+            }
+        }
+    }
+
+    if !found_end {
+        warn!("intel hex has no end of file record");
+    }
+
+    image.into_desc(MemoryPermissions::all(), arch)
+}
+
+/// One line of an Intel HEX file.
+struct Record {
+    kind: u8,
+    /// 16 bit offset from the current base address.
+    offset: u16,
+    data: Vec<u8>,
+}
+
+impl Record {
+    /// Split up a record and verify its checksum.
+    fn parse(line: &str) -> Result<Self, String> {
+        let hex = line
+            .strip_prefix(':')
+            .ok_or("record does not start with `:`")?;
+        let mut bytes = decode_hex(hex).map_err(|_| "record is not hex")?;
+
+        // count, 2 byte offset, type, checksum
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err("record length does not match byte count".to_owned());
+        }
+        let checksum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if checksum != 0 {
+            return Err("record checksum mismatch".to_owned());
+        }
+
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]);
+        let kind = bytes[3];
+        bytes.pop();
+        let data = bytes.split_off(4);
+
+        Ok(Self { kind, offset, data })
+    }
+
+    /// Interpret the data as a big endian value of exactly `size` bytes.
+    fn be_value(&self, line_idx: usize, size: usize) -> Result<u32, StyxLoaderError> {
+        if self.data.len() != size {
+            return Err(malformed(
+                line_idx,
+                &format!("record type {:02X} must have {size} data bytes", self.kind),
+            ));
+        }
+        Ok(self
+            .data
+            .iter()
+            .fold(0u32, |value, byte| (value << 8) | *byte as u32))
+    }
+}
+
+fn malformed(line_idx: usize, reason: &str) -> StyxLoaderError {
+    StyxLoaderError::MalformedInput(format!("intel hex line {}: {reason}", line_idx + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use styx_cpu_type::arch::backends::ArchRegister;
+
+    const IMAGE: &str = "\
+:020000040001F9
+:04000000DEADBEEFC4
+:0400040001020304EE
+:020000040002F8
+:02001000AA55EF
+:0400000500010000F6
+:00000001FF
+";
+
+    #[test]
+    fn test_sparse_regions() {
+        let mut desc = load_ihex(IMAGE.as_bytes(), Some(&Arch::Arm)).unwrap();
+
+        let regions = desc.take_memory_regions();
+        assert_eq!(2, regions.len());
+        let flash = regions.iter().find(|r| r.base() == 0x10000).unwrap();
+        assert_eq!(8, flash.size());
+        assert_eq!(
+            vec![0xDE, 0xAD, 0xBE, 0xEF, 1, 2, 3, 4],
+            flash.read_data(0x10000, 8).unwrap()
+        );
+        let other = regions.iter().find(|r| r.base() == 0x20010).unwrap();
+        assert_eq!(vec![0xAA, 0x55], other.read_data(0x20010, 2).unwrap());
+
+        let registers = desc.take_registers();
+        assert_eq!(1, registers.len());
+        assert_eq!(ArchRegister::Basic(Arch::Arm.pc()), registers[0].0);
+        assert_eq!(0x10000, registers[0].1);
+    }
+
+    #[test]
+    fn test_segment_address() {
+        let image = ":020000021000EC\n:020020001122AB\n:0400000312340010A3\n:00000001FF\n";
+        let mut desc = load_ihex(image.as_bytes(), Some(&Arch::Arm)).unwrap();
+
+        let regions = desc.take_memory_regions();
+        assert_eq!(0x10020, regions[0].base());
+        assert_eq!(0x12350, desc.take_registers()[0].1);
+    }
+
+    #[test]
+    fn test_no_arch_ignores_entry() {
+        let mut desc = load_ihex(IMAGE.as_bytes(), None).unwrap();
+        assert!(desc.take_registers().is_empty());
+    }
+
+    #[test]
+    fn test_bad_checksum() {
+        let image = ":04000000DEADBEEFC5\n:00000001FF\n";
+        assert!(matches!(
+            load_ihex(image.as_bytes(), None),
+            Err(StyxLoaderError::MalformedInput(_))
+        ));
+    }
+}
@@ -4,6 +4,9 @@
 //! Valid actions are:
 //!   - FileElf - load an ELF
 //!   - FileRaw - load a raw file
+//!   - FileIntelHex - load an Intel HEX file
+//!   - FileSRecord - load a Motorola S-record file
+//!   - FileUf2 - load a UF2 file
 //!   - MemoryRegion - map a memory region
 //!   - RegisterImmediate - initialize a register with an immediate value
 //!   - RegisterMemoryAddress - initialize a register with a value read from memory.
@@ -12,7 +15,7 @@
 //!
 //! See `src/styx-loader/example-input/parameterized.yaml` for an example file.
 use crate::loaders::elf::ElfLoaderConfig;
-use crate::{Loader, LoaderHints, MemoryLoaderDesc, RegisterMap};
+use crate::{Loader, LoaderHints, MemoryLoaderDesc, RegisterMap, SymbolTable};
 use log::{info, warn};
 use serde::Deserialize;
 use std::{borrow::Cow, collections::HashMap, fs, path::Path};
@@ -22,7 +25,10 @@ use styx_errors::anyhow::Context;
 use styx_memory::{MemoryPermissions, MemoryRegion};
 
 use super::elf::load_elf;
+use super::ihex::load_ihex;
 use super::raw::load_raw_with_base;
+use super::srec::load_srec;
+use super::uf2::load_uf2;
 
 /// This record structure specifies the parameters for a load of an ELF.
 #[derive(Deserialize, PartialEq, Debug, Clone)]
@@ -74,6 +80,26 @@ pub struct LoadFileRaw {
     pub perms: Option<LoadMemoryPermissions>,
 }
 
+/// This record structure specifies the parameters for a load of a record based image (Intel HEX or
+/// S-record). Memory regions are allocated for the data in the image and the pc is set to the
+/// entry point if the image has one.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+pub struct LoadFileImage {
+    /// Path to the image to be loaded.
+    pub file: String,
+}
+
+/// This record structure specifies the parameters for a load of a UF2 file. Memory regions are
+/// allocated for the data in the file.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+pub struct LoadFileUf2 {
+    /// Path to the UF2 file to be loaded.
+    pub file: String,
+    /// Only load blocks for this family. If not provided, the blocks of the first family in the
+    /// file are loaded.
+    pub family_id: Option<u32>,
+}
+
 /// This record structure specifies the parameters for mapping a memory region. The memory region
 /// is created with the specified permissions.
 #[derive(Deserialize, PartialEq, Debug, Clone)]
@@ -114,7 +140,10 @@ pub struct LoadEnvStateVariable(String, u64);
 pub enum LoadRecordType {
     EnvironmentStateVariable(LoadEnvStateVariable),
     FileElf(LoadFileElf),
+    FileIntelHex(LoadFileImage),
     FileRaw(LoadFileRaw),
+    FileSRecord(LoadFileImage),
+    FileUf2(LoadFileUf2),
     MemoryRegion(LoadMemoryRegion),
     RegisterImmediate(LoadRegisterImmediate),
     RegisterMemoryAddress(LoadRegisterMemoryAddress),
@@ -131,10 +160,30 @@ fn warn_key_overwrite(old_map: &RegisterMap, new_map: &RegisterMap) {
     }
 }
 
+/// Read an image file referenced by a record.
+fn read_image(file: &str, kind: &str) -> Result<Vec<u8>, crate::StyxLoaderError> {
+    let path = Path::new(file);
+    let data =
+        fs::read(path).with_context(|| format!("Failed to read {kind} file {}", path.display()))?;
+    Ok(data)
+}
+
+/// Save the regions and register values generated by an image loader to add to our final
+/// descriptor.
+fn merge_image(
+    mut image_desc: MemoryLoaderDesc,
+    registers: &mut RegisterMap,
+    regions: &mut Vec<MemoryRegion>,
+) {
+    warn_key_overwrite(registers, &image_desc.registers);
+    registers.extend(image_desc.take_registers());
+    regions.extend(image_desc.take_memory_regions());
+}
+
 /// Loader for parameterized data files.
 ///
-/// These YAML files can specify files to be loaded (ELF and raw), memory regions to be mapped and
-/// register initializations.
+/// These YAML files can specify files to be loaded (ELF, raw, Intel HEX, S-record and UF2), memory
+/// regions to be mapped and register initializations.
 #[derive(Debug, Default)]
 pub struct ParameterizedLoader {
     /// Supplemental records for loading.
@@ -173,6 +222,7 @@ impl Loader for ParameterizedLoader {
     /// contain a list of [`LoadRecordType`] structures. These structures describe the action to be
     /// performed. The available actions are:
     /// - Load an ELF file.
+    /// - Load an Intel HEX, S-record or UF2 image.
     /// - Load a raw data file to a specified address with provided permissions.
     /// - Map a memory region to the specified address with provided permissions.
     /// - Initialize a register with an immediate value.
@@ -194,6 +244,7 @@ impl Loader for ParameterizedLoader {
         let mut regions: Vec<MemoryRegion> = Vec::new();
         let mut reg_address_updates: Vec<(String, u64)> = Vec::new();
         let mut env_state_variables: LoaderHints = LoaderHints::new();
+        let mut symbols = SymbolTable::default();
 
         let mut records: LoadRecords =
             serde_yaml::from_slice(&data[..]).with_context(|| "failed to parse loader yaml")?;
@@ -220,6 +271,22 @@ impl Loader for ParameterizedLoader {
                     warn_key_overwrite(&registers, &elf_desc.registers);
                     registers.extend(elf_desc.take_registers());
                     regions.extend(elf_desc.take_memory_regions());
+                    symbols.merge(elf_desc.take_symbols());
+                }
+                LoadRecordType::FileIntelHex(image_record) => {
+                    let data = read_image(&image_record.file, "Intel HEX")?;
+                    let image_desc = load_ihex(&data, Some(arch))?;
+                    merge_image(image_desc, &mut registers, &mut regions);
+                }
+                LoadRecordType::FileSRecord(image_record) => {
+                    let data = read_image(&image_record.file, "S-record")?;
+                    let image_desc = load_srec(&data, Some(arch))?;
+                    merge_image(image_desc, &mut registers, &mut regions);
+                }
+                LoadRecordType::FileUf2(uf2_record) => {
+                    let data = read_image(&uf2_record.file, "UF2")?;
+                    let image_desc = load_uf2(&data, uf2_record.family_id)?;
+                    merge_image(image_desc, &mut registers, &mut regions);
                 }
                 LoadRecordType::FileRaw(raw_record) => {
                     // Load the raw file into memory using the raw file loader.
@@ -317,6 +384,7 @@ impl Loader for ParameterizedLoader {
         let mut desc = MemoryLoaderDesc::with_regions(regions).unwrap();
         desc.registers.extend(registers);
         desc.env_state.extend(env_state_variables);
+        desc.set_symbols(symbols);
         Ok(desc)
     }
 }
@@ -347,6 +415,16 @@ mod tests {
                 file: "hurr.bin".to_string(),
                 perms: None,
             }),
+            LoadRecordType::FileIntelHex(LoadFileImage {
+                file: "firmware.hex".to_string(),
+            }),
+            LoadRecordType::FileSRecord(LoadFileImage {
+                file: "firmware.srec".to_string(),
+            }),
+            LoadRecordType::FileUf2(LoadFileUf2 {
+                file: "update.uf2".to_string(),
+                family_id: Some(0xE48BFF56),
+            }),
             LoadRecordType::MemoryRegion(LoadMemoryRegion {
                 base: 0x100000,
                 size: 0x800000,
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Shared plumbing for record based image formats (Intel HEX, S-record, UF2).
+//!
+//! These formats describe memory as a list of small, possibly out of order chunks. The chunks are
+//! collected into a [`SparseImage`] which coalesces contiguous chunks so the resulting
+//! [`MemoryLoaderDesc`] has one region per contiguous run of data instead of one per record.
+use std::collections::BTreeMap;
+
+use crate::{MemoryLoaderDesc, StyxLoaderError};
+use log::{debug, warn};
+use styx_cpu_type::arch::Arch;
+use styx_errors::anyhow::Context;
+use styx_memory::{MemoryPermissions, MemoryRegion};
+
+/// Non-contiguous data collected from an image file.
+#[derive(Debug, Default)]
+pub(crate) struct SparseImage {
+    /// Contiguous chunks of data keyed by start address, never adjacent or overlapping.
+    chunks: BTreeMap<u64, Vec<u8>>,
+    /// Execution entry point, if the image specified one.
+    entry: Option<u64>,
+}
+
+impl SparseImage {
+    /// Add `data` at `address`, merging with neighboring chunks.
+    ///
+    /// Overlapping data is an error, images are expected to describe every byte once.
+    pub(crate) fn write(&mut self, address: u64, data: &[u8]) -> Result<(), StyxLoaderError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let end = address
+            .checked_add(data.len() as u64)
+            .ok_or_else(|| overlap_error(address, data.len()))?;
+
+        // merge into the chunk before us if it ends where we start
+        let previous = self
+            .chunks
+            .range_mut(..=address)
+            .next_back()
+            .map(|(start, chunk)| (*start, chunk));
+        let start = match previous {
+            Some((start, chunk)) if start + chunk.len() as u64 > address => {
+                return Err(overlap_error(address, data.len()));
+            }
+            Some((start, chunk)) if start + chunk.len() as u64 == address => {
+                chunk.extend_from_slice(data);
+                start
+            }
+            _ => {
+                self.chunks.insert(address, data.to_vec());
+                address
+            }
+        };
+
+        // and pull in the chunk after us if it starts where we end
+        if let Some((&next, _)) = self.chunks.range(address + 1..).next() {
+            if next < end {
+                // undo so a failed write leaves the image untouched
+                let chunk = self.chunks.get_mut(&start).unwrap();
+                chunk.truncate(chunk.len() - data.len());
+                if chunk.is_empty() {
+                    self.chunks.remove(&start);
+                }
+                return Err(overlap_error(address, data.len()));
+            }
+            if next == end {
+                let following = self.chunks.remove(&next).unwrap();
+                self.chunks.get_mut(&start).unwrap().extend(following);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record the execution entry point, the last one wins.
+    pub(crate) fn set_entry(&mut self, entry: u64) {
+        if let Some(previous) = self.entry.replace(entry) {
+            warn!("entry point 0x{previous:X} replaced by 0x{entry:X}");
+        }
+    }
+
+    /// Convert into a [`MemoryLoaderDesc`] with one region per contiguous chunk.
+    ///
+    /// The pc is set to the entry point if there is one and the architecture is known.
+    pub(crate) fn into_desc(
+        self,
+        perms: MemoryPermissions,
+        arch: Option<&Arch>,
+    ) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+        let mut desc = MemoryLoaderDesc::default();
+
+        for (base, data) in self.chunks {
+            debug!("image chunk 0x{base:X} size 0x{:X}", data.len());
+            let region = MemoryRegion::new_with_data(base, data.len() as u64, perms, data)?;
+            desc.add_region(region)
+                .with_context(|| format!("could not add image region at 0x{base:X}"))?;
+        }
+
+        match (self.entry, arch) {
+            (Some(entry), Some(arch)) => {
+                desc.add_register(arch.pc(), entry)
+                    .with_context(|| "failed to set pc to image entry point")?;
+            }
+            (Some(entry), None) => {
+                warn!("no `arch` hint provided, ignoring entry point 0x{entry:X}");
+            }
+            (None, _) => (),
+        }
+
+        Ok(desc)
+    }
+}
+
+fn overlap_error(address: u64, size: usize) -> StyxLoaderError {
+    StyxLoaderError::MalformedInput(format!(
+        "data at 0x{address:X} (size 0x{size:X}) overlaps previous data"
+    ))
+}
+
+/// Decode a string of hex digit pairs.
+pub(crate) fn decode_hex(line: &str) -> Result<Vec<u8>, StyxLoaderError> {
+    if line.len() % 2 != 0 || !line.is_ascii() {
+        return Err(StyxLoaderError::MalformedInput(format!(
+            "invalid hex record `{line}`"
+        )));
+    }
+
+    (0..line.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&line[idx..idx + 2], 16).map_err(|_| {
+                StyxLoaderError::MalformedInput(format!("invalid hex record `{line}`"))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(image: &SparseImage) -> Vec<(u64, usize)> {
+        image
+            .chunks
+            .iter()
+            .map(|(start, data)| (*start, data.len()))
+            .collect()
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let mut image = SparseImage::default();
+        image.write(0x100, &[0; 0x10]).unwrap();
+        image.write(0x120, &[0; 0x10]).unwrap();
+        assert_eq!(vec![(0x100, 0x10), (0x120, 0x10)], chunks(&image));
+
+        // fills the gap
+        image.write(0x110, &[0; 0x10]).unwrap();
+        assert_eq!(vec![(0x100, 0x30)], chunks(&image));
+
+        // out of order
+        image.write(0x80, &[0; 0x80]).unwrap();
+        assert_eq!(vec![(0x80, 0xB0)], chunks(&image));
+    }
+
+    #[test]
+    fn test_overlap() {
+        let mut image = SparseImage::default();
+        image.write(0x100, &[0; 0x10]).unwrap();
+        image.write(0x120, &[0; 0x10]).unwrap();
+
+        assert!(image.write(0x108, &[0; 4]).is_err());
+        assert!(image.write(0x118, &[0; 0x10]).is_err());
+        assert!(image.write(0xF0, &[0; 0x20]).is_err());
+        // failed writes leave the image as is
+        assert_eq!(vec![(0x100, 0x10), (0x120, 0x10)], chunks(&image));
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(vec![0x01, 0xAB, 0xff], decode_hex("01ABff").unwrap());
+        assert!(decode_hex("01A").is_err());
+        assert!(decode_hex("0G").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::borrow::Cow;
+
+use super::sparse::{decode_hex, SparseImage};
+use crate::{Loader, LoaderHints, MemoryLoaderDesc, StyxLoaderError};
+use log::{debug, warn};
+use styx_cpu_type::arch::Arch;
+use styx_memory::MemoryPermissions;
+
+/// Loader for Motorola S-record (`.srec`/`.s19`/`.s28`/`.s37`) files.
+///
+/// Every run of contiguous data records (`S1`/`S2`/`S3`) becomes its own `RWX` memory region,
+/// producing a sparse [`MemoryLoaderDesc`]. The header (`S0`) is logged and the record counts
+/// (`S5`/`S6`) are checked.
+///
+/// The termination record (`S7`/`S8`/`S9`) holds the entry point and the pc is set to it, this
+/// requires an `arch` hint of type [`styx_cpu_type::Arch`].
+#[derive(Debug, Default)]
+pub struct SRecordLoader;
+
+impl Loader for SRecordLoader {
+    /// Returns the name of the [`Loader`]
+    ///
+    /// ```rust
+    /// use styx_loader::{Loader, SRecordLoader};
+    ///
+    /// assert_eq!("s-record", SRecordLoader.name());
+    /// ```
+    fn name(&self) -> &'static str {
+        "s-record"
+    }
+
+    fn load_bytes(
+        &self,
+        data: Cow<[u8]>,
+        hints: LoaderHints,
+    ) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+        let arch = hints_contain!(hints, "arch", Arch)?;
+        load_srec(&data, arch)
+    }
+}
+
+/// Load the provided S-record data. Breaking this out into a helper allows us to call it from
+/// other loaders.
+pub(crate) fn load_srec(
+    data: &[u8],
+    arch: Option<&Arch>,
+) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| StyxLoaderError::MalformedInput("s-record is not text".to_owned()))?;
+
+    let mut image = SparseImage::default();
+    let mut data_records = 0u64;
+    let mut found_end = false;
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = Record::parse(line).map_err(|err| malformed(line_idx, &err))?;
+
+        match record.kind {
+            0 => debug!(
+                "s-record header `{}`",
+                String::from_utf8_lossy(&record.data)
+            ),
+            1..=3 => {
+                image.write(record.address, &record.data)?;
+                data_records += 1;
+            }
+            5 | 6 => {
+                if record.address != data_records {
+                    warn!(
+                        "s-record count record says {} data records, found {data_records}",
+                        record.address
+                    );
+                }
+            }
+            7..=9 => {
+                image.set_entry(record.address);
+                found_end = true;
+                break;
+            }
+            kind => return Err(malformed(line_idx, &format!("unsupported record S{kind}"))),
+        }
+    }
+
+    if !found_end {
+        warn!("s-record has no termination record");
+    }
+
+    image.into_desc(MemoryPermissions::all(), arch)
+}
+
+/// One line of an S-record file.
+struct Record {
+    /// Record type, the digit after the `S`.
+    kind: u8,
+    /// Address, record count or entry point depending on the type.
+    address: u64,
+    data: Vec<u8>,
+}
+
+impl Record {
+    /// Split up a record and verify its checksum.
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut chars = line.chars();
+        if chars.next() != Some('S') {
+            return Err("record does not start with `S`".to_owned());
+        }
+        let kind = chars
+            .next()
+            .and_then(|kind| kind.to_digit(10))
+            .ok_or("record type is not a digit")? as u8;
+        let address_size = match kind {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err(format!("unsupported record S{kind}")),
+        };
+        let mut bytes = decode_hex(chars.as_str()).map_err(|_| "record is not hex")?;
+
+        // count, address, checksum
+        if bytes.len() < address_size + 2 || bytes.len() != bytes[0] as usize + 1 {
+            return Err("record length does not match byte count".to_owned());
+        }
+        let checksum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if checksum != 0xFF {
+            return Err("record checksum mismatch".to_owned());
+        }
+
+        bytes.pop();
+        let data = bytes.split_off(1 + address_size);
+        let address = bytes[1..]
+            .iter()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+
+        Ok(Self {
+            kind,
+            address,
+            data,
+        })
+    }
+}
+
+fn malformed(line_idx: usize, reason: &str) -> StyxLoaderError {
+    StyxLoaderError::MalformedInput(format!("s-record line {}: {reason}", line_idx + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use styx_cpu_type::arch::backends::ArchRegister;
+
+    const IMAGE: &str = "\
+S008000068656C6C6FE3
+S1071000DEADBEEFB0
+S10510040506DB
+S20801100401020304D8
+S30720000010AA55C9
+S5030003F9
+S70520000010CA
+";
+
+    #[test]
+    fn test_sparse_regions() {
+        let mut desc = load_srec(IMAGE.as_bytes(), Some(&Arch::Arm)).unwrap();
+
+        let mut regions = desc.take_memory_regions();
+        regions.sort_by_key(|region| region.base());
+        let layout: Vec<_> = regions.iter().map(|r| (r.base(), r.size())).collect();
+        assert_eq!(vec![(0x1000, 6), (0x11004, 4), (0x2000_0010, 2)], layout);
+        assert_eq!(
+            vec![0xDE, 0xAD, 0xBE, 0xEF, 5, 6],
+            regions[0].read_data(0x1000, 6).unwrap()
+        );
+
+        let registers = desc.take_registers();
+        assert_eq!(1, registers.len());
+        assert_eq!(ArchRegister::Basic(Arch::Arm.pc()), registers[0].0);
+        assert_eq!(0x2000_0010, registers[0].1);
+    }
+
+    #[test]
+    fn test_s19_entry() {
+        let image = "S1071000DEADBEEFB0\nS9031000EC\n";
+        let mut desc = load_srec(image.as_bytes(), Some(&Arch::Arm)).unwrap();
+
+        assert_eq!(0x1000, desc.take_registers()[0].1);
+    }
+
+    #[test]
+    fn test_bad_checksum() {
+        let image = "S1071000DEADBEEFB1\nS9031000EC\n";
+        assert!(matches!(
+            load_srec(image.as_bytes(), None),
+            Err(StyxLoaderError::MalformedInput(_))
+        ));
+    }
+}
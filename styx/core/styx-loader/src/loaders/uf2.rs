@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::borrow::Cow;
+
+use super::sparse::SparseImage;
+use crate::{Loader, LoaderHints, MemoryLoaderDesc, StyxLoaderError};
+use log::{debug, warn};
+use styx_memory::MemoryPermissions;
+
+const BLOCK_SIZE: usize = 512;
+const MAX_PAYLOAD_SIZE: usize = 476;
+const DATA_OFFSET: usize = 32;
+
+const MAGIC_START0: u32 = 0x0A32_4655;
+const MAGIC_START1: u32 = 0x9E5D_5157;
+const MAGIC_END: u32 = 0x0AB1_6F30;
+
+const FLAG_NOT_MAIN_FLASH: u32 = 0x0000_0001;
+const FLAG_FILE_CONTAINER: u32 = 0x0000_1000;
+const FLAG_FAMILY_ID_PRESENT: u32 = 0x0000_2000;
+
+/// Loader for [UF2](https://github.com/microsoft/uf2) firmware update files.
+///
+/// Every run of contiguous blocks becomes its own `RWX` memory region, producing a sparse
+/// [`MemoryLoaderDesc`]. Blocks flagged as not main flash and file container blocks are skipped.
+///
+/// A UF2 file may bundle images for several chips, often at the same addresses, so only the blocks
+/// of one family are loaded: the one given to [`Uf2Loader::with_family_id`], or else the family of
+/// the first block tagged with one. Blocks without a family are skipped only when a family is
+/// given. UF2 has no notion of an entry point so no registers are set,
+/// the processor is expected to start from its reset vector.
+#[derive(Debug, Default)]
+pub struct Uf2Loader {
+    family_id: Option<u32>,
+}
+
+impl Uf2Loader {
+    /// Only load blocks tagged with `family_id`.
+    pub fn with_family_id(family_id: u32) -> Self {
+        Self {
+            family_id: Some(family_id),
+        }
+    }
+}
+
+impl Loader for Uf2Loader {
+    /// Returns the name of the [`Loader`]
+    ///
+    /// ```rust
+    /// use styx_loader::{Loader, Uf2Loader};
+    ///
+    /// assert_eq!("uf2", Uf2Loader::default().name());
+    /// ```
+    fn name(&self) -> &'static str {
+        "uf2"
+    }
+
+    fn load_bytes(
+        &self,
+        data: Cow<[u8]>,
+        _hints: LoaderHints,
+    ) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+        // note: we don't use any hints
+        load_uf2(&data, self.family_id)
+    }
+}
+
+/// Load the provided UF2 data. Breaking this out into a helper allows us to call it from other
+/// loaders.
+pub(crate) fn load_uf2(
+    data: &[u8],
+    family_id: Option<u32>,
+) -> Result<MemoryLoaderDesc, StyxLoaderError> {
+    if data.len() % BLOCK_SIZE != 0 {
+        return Err(StyxLoaderError::MalformedInput(format!(
+            "uf2 size 0x{:X} is not a multiple of the block size",
+            data.len()
+        )));
+    }
+
+    // without a requested family, the first family seen
+    let explicit_family = family_id.is_some();
+    let mut family_id = family_id;
+    let mut skipped_families = Vec::new();
+    let mut image = SparseImage::default();
+    for (block_idx, block) in data.chunks_exact(BLOCK_SIZE).enumerate() {
+        let word =
+            |offset: usize| u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap());
+        let malformed = |reason: &str| {
+            StyxLoaderError::MalformedInput(format!("uf2 block {block_idx}: {reason}"))
+        };
+
+        if word(0) != MAGIC_START0 || word(4) != MAGIC_START1 || word(BLOCK_SIZE - 4) != MAGIC_END {
+            return Err(malformed("bad magic"));
+        }
+
+        let flags = word(8);
+        let target_address = word(12);
+        let payload_size = word(16) as usize;
+        if flags & (FLAG_NOT_MAIN_FLASH | FLAG_FILE_CONTAINER) != 0 {
+            debug!("skipping uf2 block {block_idx} with flags 0x{flags:X}");
+            continue;
+        }
+        if flags & FLAG_FAMILY_ID_PRESENT != 0 {
+            let block_family_id = word(28);
+            match family_id {
+                Some(family_id) if family_id != block_family_id => {
+                    if !skipped_families.contains(&block_family_id) {
+                        skipped_families.push(block_family_id);
+                    }
+                    continue;
+                }
+                Some(_) => (),
+                None => {
+                    debug!("loading uf2 family 0x{block_family_id:X}");
+                    family_id = Some(block_family_id);
+                }
+            }
+        } else if explicit_family {
+            continue;
+        }
+        if payload_size > MAX_PAYLOAD_SIZE {
+            return Err(malformed("payload too large"));
+        }
+
+        image.write(
+            target_address as u64,
+            &block[DATA_OFFSET..DATA_OFFSET + payload_size],
+        )?;
+    }
+
+    if !explicit_family && !skipped_families.is_empty() {
+        let skipped: Vec<_> = skipped_families
+            .iter()
+            .map(|family_id| format!("0x{family_id:X}"))
+            .collect();
+        warn!(
+            "loaded uf2 family 0x{:X}, skipped families {}, use `Uf2Loader::with_family_id` to \
+            load another one",
+            family_id.unwrap_or_default(),
+            skipped.join(", ")
+        );
+    }
+
+    image.into_desc(MemoryPermissions::all(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(flags: u32, address: u32, payload: &[u8], family_id: u32) -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        let words = [
+            MAGIC_START0,
+            MAGIC_START1,
+            flags,
+            address,
+            payload.len() as u32,
+            0,
+            1,
+            family_id,
+        ];
+        for (idx, word) in words.iter().enumerate() {
+            block[idx * 4..idx * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        block[DATA_OFFSET..DATA_OFFSET + payload.len()].copy_from_slice(payload);
+        block[BLOCK_SIZE - 4..].copy_from_slice(&MAGIC_END.to_le_bytes());
+        block
+    }
+
+    fn image() -> Vec<u8> {
+        [
+            block(FLAG_FAMILY_ID_PRESENT, 0x1000_0000, &[1; 256], 0xE48B_FF56),
+            block(FLAG_FAMILY_ID_PRESENT, 0x1000_0100, &[2; 256], 0xE48B_FF56),
+            block(FLAG_FAMILY_ID_PRESENT, 0x0800_0000, &[3; 256], 0x5775_5A57),
+            block(FLAG_NOT_MAIN_FLASH, 0x2000_0000, &[4; 256], 0),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_sparse_regions() {
+        let data = [
+            block(0, 0x1000_0000, &[1; 256], 0),
+            block(0, 0x1000_0100, &[2; 256], 0),
+            block(0, 0x0800_0000, &[3; 256], 0),
+        ]
+        .concat();
+        let mut desc = load_uf2(&data, None).unwrap();
+
+        let mut regions = desc.take_memory_regions();
+        regions.sort_by_key(|region| region.base());
+        let layout: Vec<_> = regions.iter().map(|r| (r.base(), r.size())).collect();
+        assert_eq!(vec![(0x0800_0000, 0x100), (0x1000_0000, 0x200)], layout);
+        assert_eq!(vec![1, 2], regions[1].read_data(0x1000_00FF, 2).unwrap());
+        assert!(desc.take_registers().is_empty());
+    }
+
+    #[test]
+    fn test_first_family() {
+        // the second family overlaps the first one
+        let mut data = image();
+        data.extend(block(
+            FLAG_FAMILY_ID_PRESENT,
+            0x1000_0000,
+            &[5; 256],
+            0x5775_5A57,
+        ));
+        let mut desc = load_uf2(&data, None).unwrap();
+
+        let regions = desc.take_memory_regions();
+        assert_eq!(1, regions.len());
+        assert_eq!(0x1000_0000, regions[0].base());
+        assert_eq!(0x200, regions[0].size());
+        assert_eq!(vec![1], regions[0].read_data(0x1000_0000, 1).unwrap());
+    }
+
+    #[test]
+    fn test_family_filter() {
+        let mut desc = load_uf2(&image(), Some(0x5775_5A57)).unwrap();
+
+        let regions = desc.take_memory_regions();
+        assert_eq!(1, regions.len());
+        assert_eq!(0x0800_0000, regions[0].base());
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let mut data = image();
+        data[BLOCK_SIZE - 1] = 0;
+        assert!(matches!(
+            load_uf2(&data, None),
+            Err(StyxLoaderError::MalformedInput(_))
+        ));
+        assert!(load_uf2(&data[..100], None).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Symbol and source line information recovered from a target program.
+//!
+//! Loaders that understand a debug format fill a [`SymbolTable`] and attach it to the
+//! [`MemoryLoaderDesc`](crate::MemoryLoaderDesc), the processor keeps it around after loading so
+//! hooks, traces and debuggers can refer to code by name.
+//!
+//! ```rust
+//! use styx_loader::{Symbol, SymbolKind, SymbolTable};
+//!
+//! let table = SymbolTable::new(
+//!     vec![
+//!         Symbol::new("main", 0x1000, 0x40, SymbolKind::Function),
+//!         Symbol::new("delay", 0x1040, 0x20, SymbolKind::Function),
+//!     ],
+//!     vec![],
+//! );
+//!
+//! assert_eq!(Some(0x1040), table.address_of("delay"));
+//! assert_eq!("main+0x10", table.lookup(0x1010).unwrap().to_string());
+//! assert!(table.lookup(0x1060).is_none());
+//! ```
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// What a [`Symbol`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// Executable code.
+    Function,
+    /// Data, e.g. a global variable.
+    Object,
+    /// Anything else with an address, e.g. untyped assembly labels.
+    Other,
+}
+
+/// A named address in the target program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u64,
+    /// Size in bytes, zero if unknown.
+    pub size: u64,
+    pub kind: SymbolKind,
+    /// Visible outside of its compilation unit.
+    pub global: bool,
+}
+
+impl Symbol {
+    /// Create a global symbol.
+    pub fn new(name: impl Into<String>, address: u64, size: u64, kind: SymbolKind) -> Self {
+        Self {
+            name: name.into(),
+            address,
+            size,
+            kind,
+            global: true,
+        }
+    }
+
+    /// True if `address` lies inside of this symbol.
+    ///
+    /// Symbols of unknown size only contain their own address.
+    pub fn contains(&self, address: u64) -> bool {
+        address == self.address || (address > self.address && address - self.address < self.size)
+    }
+}
+
+/// An address resolved to a [`Symbol`] and the offset into it.
+///
+/// Displays as `name` or `name+0xoffset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolLocation<'a> {
+    pub symbol: &'a Symbol,
+    pub offset: u64,
+}
+
+impl Display for SymbolLocation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.offset == 0 {
+            write!(f, "{}", self.symbol.name)
+        } else {
+            write!(f, "{}+{:#x}", self.symbol.name, self.offset)
+        }
+    }
+}
+
+/// Source location of the instruction at `address`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineEntry {
+    pub address: u64,
+    pub file: Arc<str>,
+    pub line: u64,
+}
+
+impl LineEntry {
+    pub fn new(address: u64, file: Arc<str>, line: u64) -> Self {
+        Self {
+            address,
+            file,
+            line,
+        }
+    }
+
+    /// Marks the end of a contiguous run of line entries, addresses past it have no source line
+    /// until the next entry.
+    pub fn end_sequence(address: u64) -> Self {
+        Self::new(address, Arc::from(""), 0)
+    }
+
+    fn is_end_sequence(&self) -> bool {
+        self.line == 0
+    }
+}
+
+impl Display for LineEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// Queryable symbol and line table of a target program.
+///
+/// Lookups by address use binary search, lookups by name use a hash map. When several symbols
+/// share a name the first global one is returned.
+#[derive(Debug, Default, Clone)]
+pub struct SymbolTable {
+    /// Sorted by address, functions sort after other symbols at the same address so they win
+    /// address lookups.
+    symbols: Vec<Symbol>,
+    /// Index into `symbols`.
+    by_name: HashMap<String, usize>,
+    /// Sorted by address, sequence ends sort before entries at the same address.
+    lines: Vec<LineEntry>,
+}
+
+impl SymbolTable {
+    pub fn new(mut symbols: Vec<Symbol>, mut lines: Vec<LineEntry>) -> Self {
+        symbols.sort_by_key(|symbol| (symbol.address, symbol.kind == SymbolKind::Function));
+        // a sequence can start where another ends, the start must win
+        lines.sort_by_key(|line| (line.address, !line.is_end_sequence()));
+
+        let mut by_name: HashMap<String, usize> = HashMap::with_capacity(symbols.len());
+        for (idx, symbol) in symbols.iter().enumerate() {
+            by_name
+                .entry(symbol.name.clone())
+                .and_modify(|existing| {
+                    if !symbols[*existing].global && symbol.global {
+                        *existing = idx;
+                    }
+                })
+                .or_insert(idx);
+        }
+
+        Self {
+            symbols,
+            by_name,
+            lines,
+        }
+    }
+
+    /// Add all symbols and lines from `other`.
+    pub fn merge(&mut self, other: SymbolTable) {
+        let mut symbols = std::mem::take(&mut self.symbols);
+        symbols.extend(other.symbols);
+        let mut lines = std::mem::take(&mut self.lines);
+        lines.extend(other.lines);
+
+        *self = Self::new(symbols, lines);
+    }
+
+    /// True if there are no symbols and no line information.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty() && self.lines.is_empty()
+    }
+
+    /// All symbols ordered by address.
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    /// Get a symbol by name.
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|idx| &self.symbols[*idx])
+    }
+
+    /// Address of the symbol `name`.
+    pub fn address_of(&self, name: &str) -> Option<u64> {
+        self.get(name).map(|symbol| symbol.address)
+    }
+
+    /// Resolve `address` to the symbol containing it.
+    pub fn lookup(&self, address: u64) -> Option<SymbolLocation<'_>> {
+        let idx = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= address);
+
+        // skip over labels inside of the symbol we are looking for, stop at the first symbol with
+        // a known size
+        for symbol in self.symbols[..idx].iter().rev() {
+            if symbol.contains(address) {
+                return Some(SymbolLocation {
+                    symbol,
+                    offset: address - symbol.address,
+                });
+            }
+            if symbol.size > 0 {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Source line of the instruction at `address`.
+    pub fn line(&self, address: u64) -> Option<&LineEntry> {
+        let idx = self.lines.partition_point(|line| line.address <= address);
+        let entry = self.lines[..idx].last()?;
+
+        (!entry.is_end_sequence()).then_some(entry)
+    }
+
+    /// Human readable location of `address`, e.g. `main+0x4 (main.c:12)`.
+    ///
+    /// Falls back to the hex address if nothing is known about it.
+    pub fn describe(&self, address: u64) -> String {
+        let mut description = match self.lookup(address) {
+            Some(location) => location.to_string(),
+            None => format!("{address:#x}"),
+        };
+
+        if let Some(line) = self.line(address) {
+            description.push_str(&format!(" ({line})"));
+        }
+        description
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> SymbolTable {
+        let file: Arc<str> = Arc::from("main.c");
+        SymbolTable::new(
+            vec![
+                Symbol::new("delay", 0x1040, 0x20, SymbolKind::Function),
+                Symbol::new("main", 0x1000, 0x40, SymbolKind::Function),
+                Symbol::new("label", 0x1000, 0, SymbolKind::Other),
+                Symbol::new("loop", 0x1010, 0, SymbolKind::Other),
+                Symbol::new("counter", 0x2000, 4, SymbolKind::Object),
+                Symbol {
+                    global: false,
+                    ..Symbol::new("counter", 0x3000, 4, SymbolKind::Object)
+                },
+            ],
+            vec![
+                LineEntry::new(0x1000, file.clone(), 10),
+                LineEntry::new(0x1008, file.clone(), 11),
+                LineEntry::end_sequence(0x1010),
+                LineEntry::new(0x1040, file, 20),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_lookup_address() {
+        let table = table();
+
+        // function wins over the label at the same address
+        assert_eq!("main", table.lookup(0x1000).unwrap().to_string());
+        assert_eq!("main+0x3f", table.lookup(0x103F).unwrap().to_string());
+        // labels inside of a function
+        assert_eq!("loop", table.lookup(0x1010).unwrap().to_string());
+        assert_eq!("main+0x14", table.lookup(0x1014).unwrap().to_string());
+        assert_eq!("delay+0x4", table.lookup(0x1044).unwrap().to_string());
+        assert!(table.lookup(0x1060).is_none());
+        assert!(table.lookup(0xFFF).is_none());
+    }
+
+    #[test]
+    fn test_lookup_name() {
+        let table = table();
+
+        assert_eq!(Some(0x1000), table.address_of("main"));
+        // global symbol preferred over the local one
+        assert_eq!(Some(0x2000), table.address_of("counter"));
+        assert_eq!(None, table.address_of("missing"));
+    }
+
+    #[test]
+    fn test_lines() {
+        let table = table();
+
+        assert_eq!(10, table.line(0x1004).unwrap().line);
+        assert_eq!(11, table.line(0x1008).unwrap().line);
+        // past the end of the sequence
+        assert!(table.line(0x1020).is_none());
+        assert_eq!("delay+0x2 (main.c:20)", table.describe(0x1042));
+        assert_eq!("0x5000", table.describe(0x5000));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut table = table();
+        table.merge(SymbolTable::new(
+            vec![Symbol::new("other", 0x500, 0x10, SymbolKind::Function)],
+            vec![],
+        ));
+
+        assert_eq!(Some(0x500), table.address_of("other"));
+        assert_eq!(Some(0x1000), table.address_of("main"));
+        assert_eq!("other+0x8", table.lookup(0x508).unwrap().to_string());
+    }
+}
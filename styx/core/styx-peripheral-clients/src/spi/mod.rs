@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use std::borrow::Cow;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use log::{debug, error};
 use styx_grpc::io::spi::spi_port_client::SpiPortClient;
@@ -8,6 +9,7 @@ use styx_grpc::io::spi::{self, MasterChipSelectPacket, MasterPacket};
 use styx_sync::sync::atomic::AtomicBool;
 use styx_sync::sync::Arc;
 use tokio::net::ToSocketAddrs;
+use tokio::runtime::Handle;
 use tokio_stream::StreamExt;
 use tonic::{codegen::StdError, transport::Channel};
 
@@ -33,6 +35,9 @@ pub struct SPISimpleClient<T> {
     _inner: SpiPortClient<Channel>,
 }
 
+/// How long a selected device with nothing to send waits for the master before it is read again.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 enum DeviceComs {
     Data(u8),
     ChipSelect(bool),
@@ -68,143 +73,188 @@ impl<T> SPISimpleClient<T> {
         }
     }
 
-    pub fn connect_device<D: SPIDevice + Send + 'static>(&self, mut device: D)
+    pub fn connect_device<D: SPIDevice + Send + 'static>(&self, device: D)
     where
         T: ToSocketAddrs,
         T: TryInto<tonic::transport::Endpoint>,
         T::Error: Into<StdError>,
         T: Clone + Send + 'static + std::fmt::Display,
     {
-        let (to_cpu_tx, mut to_cpu_rx) = tokio::sync::mpsc::channel(32);
-        let (to_dev_tx, mut to_dev_rx) = tokio::sync::mpsc::channel(32);
-
-        let port = self.spi_port;
-        let address = self.address.clone();
-        let address_ = address.clone();
-        let device_name = device.get_name().to_string();
-        let device_name_ = device_name.clone();
-
-        let chip_select: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
-        let chip_select_ = chip_select.clone();
-        let good_id = self.chip_select_id;
-
-        #[derive(Debug)]
-        enum Packet {
-            Data(MasterPacket),
-            CSel(MasterChipSelectPacket),
-        }
+        spawn_spi_device(
+            self.runtime.handle(),
+            self.address.clone(),
+            self.spi_port,
+            self.chip_select_id,
+            device,
+        );
+    }
+}
 
-        // Subscribe to port mosi and csel and process.
-        self.runtime.spawn(async move {
-            let mut inner = SpiPortClient::connect(address).await.unwrap();
-            let mosi_resp = inner
-                .master_subscribe(spi::PortRequest {
-                    port,
-                    device_name: device_name.clone(),
-                })
-                .await
-                .unwrap()
-                .into_inner();
-            let csel_resp = inner
-                .master_chip_select_subscribe(spi::PortRequest {
-                    port,
-                    device_name: device_name.clone(),
-                })
-                .await
-                .unwrap()
-                .into_inner();
+/// Connect `device` as the slave with `chip_select_id` on SPI `port` of the processor at
+/// `address`.
+///
+/// The client tasks are spawned on `runtime` and run until the processor disconnects.
+pub fn spawn_spi_device<T, D>(
+    runtime: &Handle,
+    address: T,
+    port: u32,
+    chip_select_id: u32,
+    mut device: D,
+) where
+    T: TryInto<tonic::transport::Endpoint>,
+    T::Error: Into<StdError>,
+    T: Clone + Send + 'static,
+    D: SPIDevice + Send + 'static,
+{
+    let (to_cpu_tx, mut to_cpu_rx) = tokio::sync::mpsc::channel(32);
+    let (to_dev_tx, mut to_dev_rx) = tokio::sync::mpsc::channel(32);
 
-            let mut merged = mosi_resp
-                .map(|res| res.map(Packet::Data))
-                .merge(csel_resp.map(|res| res.map(Packet::CSel)));
+    let address_ = address.clone();
+    let device_name = device.get_name().to_string();
+    let device_name_ = device_name.clone();
 
-            while let Some(recv) = merged.next().await {
-                if let Err(e) = recv {
-                    error!("Server disconnected or other error occured: {e:?}");
-                    break;
-                }
+    let chip_select: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let chip_select_ = chip_select.clone();
+    let good_id = chip_select_id;
 
-                // packet is not an error, so we can unwrap
-                let packet = recv.unwrap();
-                debug!("[{device_name}] received: {packet:?}");
+    #[derive(Debug)]
+    enum Packet {
+        Data(MasterPacket),
+        CSel(MasterChipSelectPacket),
+    }
 
-                match packet {
-                    Packet::CSel(c) => {
-                        if c.chip_select_id != good_id {
-                            continue;
-                        }
-                        debug!("received chip select {}", c.chip_select);
-                        chip_select.store(c.chip_select, std::sync::atomic::Ordering::Release);
-                        to_dev_tx
-                            .send(DeviceComs::ChipSelect(c.chip_select))
-                            .await
-                            .unwrap();
+    // Subscribe to port mosi and csel and process.
+    runtime.spawn(async move {
+        let mut inner = SpiPortClient::connect(address).await.unwrap();
+        let mosi_resp = inner
+            .master_subscribe(spi::PortRequest {
+                port,
+                device_name: device_name.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let csel_resp = inner
+            .master_chip_select_subscribe(spi::PortRequest {
+                port,
+                device_name: device_name.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        let mut merged = mosi_resp
+            .map(|res| res.map(Packet::Data))
+            .merge(csel_resp.map(|res| res.map(Packet::CSel)));
+
+        while let Some(recv) = merged.next().await {
+            if let Err(e) = recv {
+                error!("Server disconnected or other error occured: {e:?}");
+                break;
+            }
+
+            // packet is not an error, so we can unwrap
+            let packet = recv.unwrap();
+            debug!("[{device_name}] received: {packet:?}");
+
+            match packet {
+                Packet::CSel(c) => {
+                    if c.chip_select_id != good_id {
+                        continue;
+                    }
+                    debug!("received chip select {}", c.chip_select);
+                    chip_select.store(c.chip_select, std::sync::atomic::Ordering::Release);
+                    to_dev_tx
+                        .send(DeviceComs::ChipSelect(c.chip_select))
+                        .await
+                        .unwrap();
+                }
+                Packet::Data(d) => {
+                    if d.chip_select_id != good_id {
+                        continue;
                     }
-                    Packet::Data(d) => {
-                        if d.chip_select_id != good_id {
-                            continue;
-                        }
-
-                        for byte in d.data {
-                            // will wait for space to send
-                            to_dev_tx.send(DeviceComs::Data(byte)).await.unwrap();
-                        }
+
+                    for byte in d.data {
+                        // will wait for space to send
+                        to_dev_tx.send(DeviceComs::Data(byte)).await.unwrap();
                     }
                 }
             }
-            error!("stream disconnected");
-        });
+        }
+        error!("stream disconnected");
+    });
 
-        // This thread receives data from a device and sends it to the processor
-        self.runtime.spawn(async move {
-            let mut inner = SpiPortClient::connect(address_).await.unwrap();
-            loop {
-                // recv call blocks thread
-                if let Some(data) = to_cpu_rx.recv().await {
-                    let packet = spi::MasterPacket {
-                        port,
-                        chip_select_id: good_id,
-                        data: vec![data],
-                    };
-                    debug!("[{device_name_}] sending: {packet:?}");
-                    if let Err(e) = inner.master_receive(packet).await {
-                        error!("to master receive socket disconnected: {e}");
-                        break;
-                    }
-                } else {
-                    error!("[to_cpu_rx] channel disconnected");
+    // This thread receives data from a device and sends it to the processor
+    runtime.spawn(async move {
+        let mut inner = SpiPortClient::connect(address_).await.unwrap();
+        loop {
+            // recv call blocks thread
+            if let Some(data) = to_cpu_rx.recv().await {
+                let packet = spi::MasterPacket {
+                    port,
+                    chip_select_id: good_id,
+                    data: vec![data],
+                };
+                debug!("[{device_name_}] sending: {packet:?}");
+                if let Err(e) = inner.master_receive(packet).await {
+                    error!("to master receive socket disconnected: {e}");
                     break;
                 }
+            } else {
+                error!("[to_cpu_rx] channel disconnected");
+                break;
             }
-        });
+        }
+    });
 
-        // this thread handles reading/writing data from/to the device
-        self.runtime.spawn(async move {
-            loop {
-                // get data from the server to process, if any is available
-                if let Ok(packet) = to_dev_rx.try_recv() {
-                    match packet {
-                        DeviceComs::Data(byte) => device.write_data(byte),
-                        DeviceComs::ChipSelect(cs) => device.set_cs(cs),
-                    }
+    // this thread handles reading/writing data from/to the device
+    runtime.spawn(async move {
+        // did the device have nothing to send last time it was read?
+        let mut device_idle = false;
+        loop {
+            let selected = chip_select_.load(std::sync::atomic::Ordering::Acquire);
+            // get data from the server to process, if we aren't selected there is nothing else to
+            // do so wait for it
+            let packet = if !selected {
+                match to_dev_rx.recv().await {
+                    Some(packet) => Some(packet),
+                    None => break,
                 }
-
-                // If we aren't selected, then keep processing data that we've received but don't send anything new
-                if !chip_select_.load(std::sync::atomic::Ordering::Acquire) {
-                    continue;
+            } else if device_idle {
+                // wait for the master instead of spinning, reading the device again shortly
+                match tokio::time::timeout(DEVICE_POLL_INTERVAL, to_dev_rx.recv()).await {
+                    Ok(Some(packet)) => Some(packet),
+                    Ok(None) => break,
+                    Err(_) => None,
                 }
+            } else {
+                to_dev_rx.try_recv().ok()
+            };
+            if let Some(packet) = packet {
+                match packet {
+                    DeviceComs::Data(byte) => device.write_data(byte),
+                    DeviceComs::ChipSelect(cs) => device.set_cs(cs),
+                }
+            }
 
-                // send
-                if let Some(d) = device.read_data() {
-                    // will wait for space to send
-                    if let Err(e) = to_cpu_tx.send(d).await {
-                        error!("[to_cpu_tx] channel disconnected: {e}");
-                        break;
-                    }
+            // If we aren't selected, then keep processing data that we've received but don't send anything new
+            if !chip_select_.load(std::sync::atomic::Ordering::Acquire) {
+                continue;
+            }
+
+            // send
+            if let Some(d) = device.read_data() {
+                device_idle = false;
+                // will wait for space to send
+                if let Err(e) = to_cpu_tx.send(d).await {
+                    error!("[to_cpu_tx] channel disconnected: {e}");
+                    break;
                 }
+            } else {
+                device_idle = true;
             }
-        });
-    }
+        }
+    });
 }
 
 impl<T: SPIDevice> SPIDevice for Arc<Mutex<T>> {
@@ -0,0 +1,401 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Per architecture instruction decoders that recognize calls and returns.
+//!
+//! Detection works from the raw instruction bytes at the pc so it is independent of the cpu
+//! backend. Only the instruction forms compilers emit for calls and returns are recognized, a
+//! hand written `mov pc, r3` is just a jump.
+use styx_cpu_type::{
+    arch::{arm::ArmRegister, Arch},
+    ArchEndian,
+};
+use styx_errors::UnknownError;
+
+use crate::{cpu::CpuBackendExt, hooks::CoreHandle, memory::helpers::ReadExt};
+
+/// What a call or return instruction does to the call stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum BranchKind {
+    /// Calls a function, returning to the fallthrough address.
+    Call,
+    /// Returns from a function.
+    Return,
+    /// Returns from an interrupt or exception handler.
+    InterruptReturn,
+}
+
+/// A call or return instruction found at the pc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Branch {
+    pub kind: BranchKind,
+    /// Address of the next sequential instruction, after any delay slot.
+    ///
+    /// For calls this is the return address. Execution arriving here means a conditional branch
+    /// was not taken.
+    pub fallthrough: u64,
+}
+
+impl Branch {
+    const fn new(kind: BranchKind, fallthrough: u64) -> Self {
+        Self { kind, fallthrough }
+    }
+}
+
+/// Classifies the instruction at the pc as a call, return or neither.
+pub trait CallDetector: Send {
+    /// Decode the instruction at `pc`, `None` if it is neither a call nor a return.
+    fn classify(&self, proc: &mut CoreHandle, pc: u64) -> Result<Option<Branch>, UnknownError>;
+}
+
+/// Get the [`CallDetector`] for an architecture, `None` if calls can't be detected on it.
+///
+/// `variant` is the [`ArchitectureDef::architecture_variant()`](styx_cpu_type::arch::ArchitectureDef::architecture_variant())
+/// and is used to tell thumb only cores apart.
+pub fn detector(arch: Arch, variant: &str) -> Option<Box<dyn CallDetector>> {
+    let detector: Box<dyn CallDetector> = match arch {
+        Arch::Arm => Box::new(ArmDetector {
+            thumb_only: variant.contains("CortexM"),
+        }),
+        Arch::Ppc32 => Box::new(PpcDetector),
+        Arch::Blackfin => Box::new(BlackfinDetector),
+        Arch::SuperH => Box::new(SuperHDetector),
+        _ => return None,
+    };
+    Some(detector)
+}
+
+/// Read an instruction half word, `None` if the pc is not in readable code.
+fn read_u16(proc: &mut CoreHandle, addr: u64) -> Option<u16> {
+    let endian = proc.cpu.endian();
+    let read = proc.mmu.virt_code(proc.cpu).read(addr);
+    let value = match endian {
+        ArchEndian::LittleEndian => read.le().u16(),
+        ArchEndian::BigEndian => read.be().u16(),
+    };
+    value.ok()
+}
+
+/// Read an instruction word, `None` if the pc is not in readable code.
+fn read_u32(proc: &mut CoreHandle, addr: u64) -> Option<u32> {
+    let endian = proc.cpu.endian();
+    let read = proc.mmu.virt_code(proc.cpu).read(addr);
+    let value = match endian {
+        ArchEndian::LittleEndian => read.le().u32(),
+        ArchEndian::BigEndian => read.be().u32(),
+    };
+    value.ok()
+}
+
+struct ArmDetector {
+    /// M profile cores only execute thumb.
+    thumb_only: bool,
+}
+
+impl CallDetector for ArmDetector {
+    fn classify(&self, proc: &mut CoreHandle, pc: u64) -> Result<Option<Branch>, UnknownError> {
+        const CPSR_THUMB: u32 = 1 << 5;
+
+        let thumb =
+            self.thumb_only || proc.cpu.read_register::<u32>(ArmRegister::Cpsr)? & CPSR_THUMB != 0;
+        if thumb {
+            let Some(first) = read_u16(proc, pc) else {
+                return Ok(None);
+            };
+            // the second half word is only needed for 32 bit encodings
+            let second = read_u16(proc, pc + 2).unwrap_or_default();
+            Ok(classify_thumb(pc, first, second))
+        } else {
+            Ok(read_u32(proc, pc).and_then(|insn| classify_a32(pc, insn)))
+        }
+    }
+}
+
+/// Classify an A32 instruction.
+pub(crate) fn classify_a32(pc: u64, insn: u32) -> Option<Branch> {
+    let next = pc + 4;
+    let condition = insn >> 28;
+
+    // blx <imm> is in the unconditional space
+    if insn & 0xFE00_0000 == 0xFA00_0000 {
+        return Some(Branch::new(BranchKind::Call, next));
+    }
+    if condition == 0xF {
+        return None;
+    }
+
+    let kind = match insn & 0x0FFF_FFFF {
+        // bl <imm>
+        _ if insn & 0x0F00_0000 == 0x0B00_0000 => BranchKind::Call,
+        // blx <reg>
+        masked if masked & 0x0FFF_FFF0 == 0x012F_FF30 => BranchKind::Call,
+        // bx lr
+        0x012F_FF1E => BranchKind::Return,
+        // mov pc, lr
+        0x01A0_F00E => BranchKind::Return,
+        // ldr pc, [sp], #4
+        0x049D_F004 => BranchKind::Return,
+        // ldm sp!, {..., pc} (pop)
+        masked if masked & 0x0FFF_8000 == 0x08BD_8000 => BranchKind::Return,
+        // movs pc, lr
+        0x01B0_F00E => BranchKind::InterruptReturn,
+        // subs pc, lr, #imm
+        masked if masked & 0x0FFF_F000 == 0x025E_F000 => BranchKind::InterruptReturn,
+        _ => return None,
+    };
+    Some(Branch::new(kind, next))
+}
+
+/// Classify a T32 instruction, `second` is only used for 32 bit encodings.
+pub(crate) fn classify_thumb(pc: u64, first: u16, second: u16) -> Option<Branch> {
+    // 32 bit encodings start with 0b11101, 0b11110 or 0b11111
+    if first >> 11 >= 0b11101 {
+        let next = pc + 4;
+        let kind = match (first, second) {
+            // bl <imm>
+            (first, second) if first & 0xF800 == 0xF000 && second & 0xD000 == 0xD000 => {
+                BranchKind::Call
+            }
+            // blx <imm>
+            (first, second) if first & 0xF800 == 0xF000 && second & 0xD001 == 0xC000 => {
+                BranchKind::Call
+            }
+            // pop.w {..., pc}
+            (0xE8BD, second) if second & 0x8000 != 0 => BranchKind::Return,
+            // ldr.w pc, [sp], #4
+            (0xF85D, 0xFB04) => BranchKind::Return,
+            _ => return None,
+        };
+        return Some(Branch::new(kind, next));
+    }
+
+    let next = pc + 2;
+    let kind = match first {
+        // blx <reg>
+        first if first & 0xFF87 == 0x4780 => BranchKind::Call,
+        // bx lr, this is also the M profile exception return
+        0x4770 => BranchKind::Return,
+        // mov pc, lr
+        0x46F7 => BranchKind::Return,
+        // pop {..., pc}
+        first if first & 0xFF00 == 0xBD00 => BranchKind::Return,
+        _ => return None,
+    };
+    Some(Branch::new(kind, next))
+}
+
+struct PpcDetector;
+
+impl CallDetector for PpcDetector {
+    fn classify(&self, proc: &mut CoreHandle, pc: u64) -> Result<Option<Branch>, UnknownError> {
+        Ok(read_u32(proc, pc).and_then(|insn| classify_ppc(pc, insn)))
+    }
+}
+
+/// Classify a PowerPC instruction.
+pub(crate) fn classify_ppc(pc: u64, insn: u32) -> Option<Branch> {
+    const OPCODE_BC: u32 = 16;
+    const OPCODE_B: u32 = 18;
+    const OPCODE_XL: u32 = 19;
+    const XO_BCLR: u32 = 16;
+    const XO_BCCTR: u32 = 528;
+
+    let opcode = insn >> 26;
+    let extended = (insn >> 1) & 0x3FF;
+    let link = insn & 1 != 0;
+
+    let kind = match (opcode, extended) {
+        // bl, bcl
+        (OPCODE_B | OPCODE_BC, _) if link => BranchKind::Call,
+        // bctrl, bclrl
+        (OPCODE_XL, XO_BCCTR | XO_BCLR) if link => BranchKind::Call,
+        // blr and conditional variants
+        (OPCODE_XL, XO_BCLR) => BranchKind::Return,
+        // rfi, rfci, rfmci, rfdi
+        (OPCODE_XL, 50 | 51 | 38 | 39) => BranchKind::InterruptReturn,
+        _ => return None,
+    };
+    Some(Branch::new(kind, pc + 4))
+}
+
+struct BlackfinDetector;
+
+impl CallDetector for BlackfinDetector {
+    fn classify(&self, proc: &mut CoreHandle, pc: u64) -> Result<Option<Branch>, UnknownError> {
+        Ok(read_u16(proc, pc).and_then(|insn| classify_blackfin(pc, insn)))
+    }
+}
+
+/// Classify the first half word of a Blackfin instruction.
+pub(crate) fn classify_blackfin(pc: u64, insn: u16) -> Option<Branch> {
+    let (kind, size) = match insn {
+        // call pcrel
+        insn if insn & 0xFF00 == 0xE300 => (BranchKind::Call, 4),
+        // call (preg), call (pc + preg)
+        0x0060..=0x0067 | 0x0070..=0x0077 => (BranchKind::Call, 2),
+        // rts
+        0x0010 => (BranchKind::Return, 2),
+        // rti, rtx, rtn, rte
+        0x0011..=0x0014 => (BranchKind::InterruptReturn, 2),
+        _ => return None,
+    };
+    Some(Branch::new(kind, pc + size))
+}
+
+struct SuperHDetector;
+
+impl CallDetector for SuperHDetector {
+    fn classify(&self, proc: &mut CoreHandle, pc: u64) -> Result<Option<Branch>, UnknownError> {
+        Ok(read_u16(proc, pc).and_then(|insn| classify_superh(pc, insn)))
+    }
+}
+
+/// Classify a SuperH instruction.
+///
+/// Most SuperH branches have a delay slot so the fallthrough skips it, the `/N` variants don't.
+pub(crate) fn classify_superh(pc: u64, insn: u16) -> Option<Branch> {
+    let (kind, size) = match insn {
+        // bsr
+        insn if insn & 0xF000 == 0xB000 => (BranchKind::Call, 4),
+        // bsrf rm, jsr @rm
+        insn if insn & 0xF0FF == 0x0003 || insn & 0xF0FF == 0x400B => (BranchKind::Call, 4),
+        // jsr/n @rm
+        insn if insn & 0xF0FF == 0x404B => (BranchKind::Call, 2),
+        // rts
+        0x000B => (BranchKind::Return, 4),
+        // rts/n, rtv/n rm
+        insn if insn == 0x006B || insn & 0xF0FF == 0x007B => (BranchKind::Return, 2),
+        // rte
+        0x002B => (BranchKind::InterruptReturn, 4),
+        _ => return None,
+    };
+    Some(Branch::new(kind, pc + size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(branch: Option<Branch>) -> Option<BranchKind> {
+        branch.map(|branch| branch.kind)
+    }
+
+    #[test]
+    fn test_a32() {
+        // bl, blx imm, blx r3
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x1004)),
+            classify_a32(0x1000, 0xEB00_0010)
+        );
+        assert_eq!(Some(BranchKind::Call), kind(classify_a32(0, 0xFA00_0010)));
+        assert_eq!(Some(BranchKind::Call), kind(classify_a32(0, 0xE12F_FF33)));
+        // bx lr, conditional bxeq lr, pop {r4, pc}, ldr pc, [sp], #4
+        assert_eq!(Some(BranchKind::Return), kind(classify_a32(0, 0xE12F_FF1E)));
+        assert_eq!(Some(BranchKind::Return), kind(classify_a32(0, 0x012F_FF1E)));
+        assert_eq!(Some(BranchKind::Return), kind(classify_a32(0, 0xE8BD_8010)));
+        assert_eq!(Some(BranchKind::Return), kind(classify_a32(0, 0xE49D_F004)));
+        // subs pc, lr, #4
+        assert_eq!(
+            Some(BranchKind::InterruptReturn),
+            kind(classify_a32(0, 0xE25E_F004))
+        );
+        // b, bx r3, add r0, r0, r1
+        assert_eq!(None, classify_a32(0, 0xEA00_0010));
+        assert_eq!(None, classify_a32(0, 0xE12F_FF13));
+        assert_eq!(None, classify_a32(0, 0xE080_0001));
+    }
+
+    #[test]
+    fn test_thumb() {
+        // bl
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x1004)),
+            classify_thumb(0x1000, 0xF000, 0xF802)
+        );
+        // blx r3
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x1002)),
+            classify_thumb(0x1000, 0x4798, 0)
+        );
+        // bx lr, pop {r4, pc}, pop.w {r4-r11, pc}
+        assert_eq!(Some(BranchKind::Return), kind(classify_thumb(0, 0x4770, 0)));
+        assert_eq!(Some(BranchKind::Return), kind(classify_thumb(0, 0xBD10, 0)));
+        assert_eq!(
+            Some(BranchKind::Return),
+            kind(classify_thumb(0, 0xE8BD, 0x8FF0))
+        );
+        // push {r4, lr}, bx r3, b.w
+        assert_eq!(None, classify_thumb(0, 0xB510, 0));
+        assert_eq!(None, classify_thumb(0, 0x4718, 0));
+        assert_eq!(None, classify_thumb(0, 0xF000, 0xB802));
+    }
+
+    #[test]
+    fn test_ppc() {
+        // bl, bctrl
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x104)),
+            classify_ppc(0x100, 0x4800_0011)
+        );
+        assert_eq!(Some(BranchKind::Call), kind(classify_ppc(0, 0x4E80_0421)));
+        // blr, beqlr
+        assert_eq!(Some(BranchKind::Return), kind(classify_ppc(0, 0x4E80_0020)));
+        assert_eq!(Some(BranchKind::Return), kind(classify_ppc(0, 0x4D82_0020)));
+        // rfi
+        assert_eq!(
+            Some(BranchKind::InterruptReturn),
+            kind(classify_ppc(0, 0x4C00_0064))
+        );
+        // b, bctr
+        assert_eq!(None, classify_ppc(0, 0x4800_0010));
+        assert_eq!(None, classify_ppc(0, 0x4E80_0420));
+    }
+
+    #[test]
+    fn test_blackfin() {
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x104)),
+            classify_blackfin(0x100, 0xE300)
+        );
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x102)),
+            classify_blackfin(0x100, 0x0062)
+        );
+        assert_eq!(Some(BranchKind::Return), kind(classify_blackfin(0, 0x0010)));
+        assert_eq!(
+            Some(BranchKind::InterruptReturn),
+            kind(classify_blackfin(0, 0x0011))
+        );
+        // jump.s
+        assert_eq!(None, classify_blackfin(0, 0x2000));
+    }
+
+    #[test]
+    fn test_superh() {
+        // bsr has a delay slot
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x104)),
+            classify_superh(0x100, 0xB010)
+        );
+        // jsr @r3, jsr/n @r3
+        assert_eq!(Some(BranchKind::Call), kind(classify_superh(0, 0x430B)));
+        assert_eq!(
+            Some(Branch::new(BranchKind::Call, 0x102)),
+            classify_superh(0x100, 0x434B)
+        );
+        // rts, rts/n
+        assert_eq!(
+            Some(Branch::new(BranchKind::Return, 0x104)),
+            classify_superh(0x100, 0x000B)
+        );
+        assert_eq!(
+            Some(Branch::new(BranchKind::Return, 0x102)),
+            classify_superh(0x100, 0x006B)
+        );
+        assert_eq!(
+            Some(BranchKind::InterruptReturn),
+            kind(classify_superh(0, 0x002B))
+        );
+        // bra, jmp @r3
+        assert_eq!(None, classify_superh(0, 0xA010));
+        assert_eq!(None, classify_superh(0, 0x432B));
+    }
+}
@@ -0,0 +1,403 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Call and return tracking with a shadow call stack.
+//!
+//! Once enabled with [`ProcessorCore::track_calls()`](crate::core::ProcessorCore::track_calls())
+//! a code hook decodes every instruction with the [`CallDetector`] of the architecture and
+//! maintains a [`CallStack`] in the [`EventController`](crate::event_controller::EventController).
+//! Interrupt entry pushes a frame too, so the stack reflects the real nesting of the target. The
+//! stack is available from hooks through [`CoreHandle::call_stack()`].
+//!
+//! Because the stack is built by watching execution it needs no frame pointers or debug info,
+//! which makes it useful for backtraces of stripped firmware. Returns that land somewhere other
+//! than where the matching call would return to are recorded as [`ReturnMismatch`]es, these are
+//! usually a smashed stack.
+//!
+//! Calls and returns are also emitted on the trace bus as [`BranchEvent`]s.
+mod detect;
+
+pub use detect::{detector, Branch, BranchKind, CallDetector};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use styx_cpu_type::arch::backends::ArchRegister;
+use styx_errors::{anyhow::anyhow, UnknownError};
+use styx_tracebus::{branchevt, BranchEvent, BranchInfo, TraceEventType, TraceProvider};
+
+use crate::{
+    cpu::{CpuBackend, CpuBackendExt},
+    hooks::{CodeHook, CoreHandle},
+};
+
+/// Frames past this depth drop the oldest frame, runaway recursion shouldn't eat all memory.
+const MAX_DEPTH: usize = 4096;
+/// Only the first mismatches are kept, after a smash everything that follows is noise.
+const MAX_MISMATCHES: usize = 64;
+
+/// How a [`CallFrame`] was entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameKind {
+    /// Entered by a call instruction.
+    Call,
+    /// Entered by taking an interrupt or exception.
+    Interrupt,
+}
+
+/// One frame of the shadow call stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallFrame {
+    pub kind: FrameKind,
+    /// Address of the call instruction, or the interrupted instruction.
+    pub call_site: u64,
+    /// Address of the called function or interrupt handler.
+    pub function: u64,
+    /// Address execution continues at when the frame returns.
+    pub return_address: u64,
+    /// Stack pointer on entry to the function.
+    pub stack_pointer: u64,
+}
+
+/// A return that did not go back to the return address of the innermost frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReturnMismatch {
+    /// Address of the return instruction.
+    pub return_site: u64,
+    /// Where the innermost frame should have returned to.
+    pub expected: u64,
+    /// Where execution actually went.
+    pub actual: u64,
+}
+
+/// A call or return that executed but whose destination is not known yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PendingBranch {
+    pc: u64,
+    branch: Branch,
+}
+
+/// Shadow call stack built from the calls and returns executed by the target.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallStack {
+    /// Follows the processor, not the snapshot.
+    #[serde(skip)]
+    enabled: bool,
+    /// Outermost frame first.
+    frames: Vec<CallFrame>,
+    pending: Option<PendingBranch>,
+    mismatches: Vec<ReturnMismatch>,
+}
+
+impl CallStack {
+    /// Is call tracking enabled on this processor?
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Active frames, outermost first.
+    pub fn frames(&self) -> &[CallFrame] {
+        &self.frames
+    }
+
+    /// Number of active frames.
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The innermost frame, `None` if execution is in the outermost function.
+    pub fn current(&self) -> Option<&CallFrame> {
+        self.frames.last()
+    }
+
+    /// Returns that did not match the call stack, oldest first.
+    pub fn mismatches(&self) -> &[ReturnMismatch] {
+        &self.mismatches
+    }
+
+    /// Addresses of the backtrace starting at `pc`, innermost first.
+    ///
+    /// The first address is `pc` followed by the call site of each frame.
+    pub fn backtrace(&self, pc: u64) -> Vec<u64> {
+        std::iter::once(pc)
+            .chain(self.frames.iter().rev().map(|frame| frame.call_site))
+            .collect()
+    }
+
+    /// Forget all frames and mismatches, eg. when the target is reset.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.pending = None;
+        self.mismatches.clear();
+    }
+
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Replace the stack with a saved one, keeping whether tracking is enabled.
+    pub(crate) fn restore(&mut self, saved: &CallStack) {
+        *self = Self {
+            enabled: self.enabled,
+            ..saved.clone()
+        };
+    }
+
+    /// Is a branch waiting for the next pc to be resolved?
+    fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Record a call or return executed at `pc`, it is resolved at the next instruction.
+    fn branch(&mut self, pc: u64, branch: Branch) {
+        self.pending = Some(PendingBranch { pc, branch });
+    }
+
+    /// Resolve the pending branch now that execution arrived at `pc`.
+    fn resolve(&mut self, pc: u64, stack_pointer: u64) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        // still in the delay slot
+        if pc > pending.pc && pc < pending.branch.fallthrough {
+            self.pending = Some(pending);
+            return;
+        }
+        // conditional branch not taken
+        if pc == pending.branch.fallthrough {
+            return;
+        }
+
+        match pending.branch.kind {
+            BranchKind::Call => {
+                self.push(CallFrame {
+                    kind: FrameKind::Call,
+                    call_site: pending.pc,
+                    function: pc,
+                    return_address: pending.branch.fallthrough,
+                    stack_pointer,
+                });
+                branchevt!(pending.pc as u32, pc as u32, BranchInfo::CallU);
+            }
+            BranchKind::Return => {
+                self.pop_return(pending.pc, pc, false);
+                branchevt!(pending.pc as u32, pc as u32, BranchInfo::RetU);
+            }
+            BranchKind::InterruptReturn => {
+                self.pop_return(pending.pc, pc, true);
+                branchevt!(pending.pc as u32, pc as u32, BranchInfo::ReturnInterruptU);
+            }
+        }
+    }
+
+    /// An interrupt handler at `handler` was entered, interrupting `interrupted_pc`.
+    pub(crate) fn interrupt(&mut self, interrupted_pc: u64, handler: u64, stack_pointer: u64) {
+        // a call or return right before the interrupt landed on the interrupted pc
+        self.resolve(interrupted_pc, stack_pointer);
+
+        self.push(CallFrame {
+            kind: FrameKind::Interrupt,
+            call_site: interrupted_pc,
+            function: handler,
+            return_address: interrupted_pc,
+            stack_pointer,
+        });
+        branchevt!(
+            interrupted_pc as u32,
+            handler as u32,
+            BranchInfo::InterruptU
+        );
+    }
+
+    fn push(&mut self, frame: CallFrame) {
+        if self.frames.len() >= MAX_DEPTH {
+            self.frames.remove(0);
+        }
+        self.frames.push(frame);
+    }
+
+    /// Pop frames for a return from `return_site` that went to `target`.
+    fn pop_return(&mut self, return_site: u64, target: u64, interrupt: bool) {
+        // usually the innermost frame, deeper frames are unwound by longjmp and friends
+        if let Some(idx) = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.return_address == target)
+        {
+            self.frames.truncate(idx);
+            return;
+        }
+
+        // exception returns on some architectures go through a magic address, pop the handler
+        let innermost_interrupt = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.kind == FrameKind::Interrupt);
+        match (self.frames.last(), innermost_interrupt) {
+            (Some(frame), Some(idx)) if interrupt || frame.kind == FrameKind::Interrupt => {
+                self.frames.truncate(idx);
+            }
+            (Some(frame), _) => {
+                warn!(
+                    "return at 0x{return_site:X} went to 0x{target:X} instead of 0x{:X}, possible stack corruption",
+                    frame.return_address
+                );
+                if self.mismatches.len() < MAX_MISMATCHES {
+                    self.mismatches.push(ReturnMismatch {
+                        return_site,
+                        expected: frame.return_address,
+                        actual: target,
+                    });
+                }
+                self.frames.pop();
+            }
+            // returning out of the outermost function we know about
+            (None, _) => (),
+        }
+    }
+}
+
+/// Code hook that feeds every instruction through a [`CallDetector`].
+pub(crate) struct CallTracker {
+    detector: Box<dyn CallDetector>,
+    stack_pointer: ArchRegister,
+}
+
+impl CallTracker {
+    /// Errors if calls can't be detected on the architecture of `cpu`.
+    pub(crate) fn new(cpu: &dyn CpuBackend) -> Result<Self, UnknownError> {
+        let arch = cpu.architecture();
+        let detector = detector(arch.architecture(), &arch.architecture_variant())
+            .ok_or_else(|| anyhow!("call tracking is not supported on {}", arch.architecture()))?;
+
+        Ok(Self {
+            detector,
+            stack_pointer: arch.registers().sp().variant(),
+        })
+    }
+}
+
+impl CodeHook for CallTracker {
+    fn call(&mut self, mut proc: CoreHandle) -> Result<(), UnknownError> {
+        let pc = proc.pc()?;
+
+        if proc.event_controller.call_stack.is_pending() {
+            let stack_pointer = proc.cpu.read_register::<u32>(self.stack_pointer)? as u64;
+            proc.event_controller.call_stack.resolve(pc, stack_pointer);
+        }
+        if let Some(branch) = self.detector.classify(&mut proc, pc)? {
+            proc.event_controller.call_stack.branch(pc, branch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(fallthrough: u64) -> Branch {
+        Branch {
+            kind: BranchKind::Call,
+            fallthrough,
+        }
+    }
+
+    fn ret(fallthrough: u64) -> Branch {
+        Branch {
+            kind: BranchKind::Return,
+            fallthrough,
+        }
+    }
+
+    /// Execute `branch` at `pc` and land on `target`.
+    fn step(stack: &mut CallStack, pc: u64, branch: Branch, target: u64) {
+        stack.branch(pc, branch);
+        stack.resolve(target, 0x2000_0000);
+    }
+
+    #[test]
+    fn test_call_return() {
+        let mut stack = CallStack::default();
+        step(&mut stack, 0x100, call(0x104), 0x400);
+        step(&mut stack, 0x410, call(0x414), 0x800);
+        assert_eq!(2, stack.depth());
+        assert_eq!(vec![0x810, 0x410, 0x100], stack.backtrace(0x810));
+
+        step(&mut stack, 0x820, ret(0x824), 0x414);
+        assert_eq!(1, stack.depth());
+        assert_eq!(0x400, stack.current().unwrap().function);
+        step(&mut stack, 0x420, ret(0x424), 0x104);
+        assert_eq!(0, stack.depth());
+        assert!(stack.mismatches().is_empty());
+    }
+
+    #[test]
+    fn test_not_taken_and_delay_slot() {
+        let mut stack = CallStack::default();
+        // conditional call not taken
+        step(&mut stack, 0x100, call(0x104), 0x104);
+        assert_eq!(0, stack.depth());
+
+        // delay slot executes before the target
+        stack.branch(0x100, call(0x104));
+        stack.resolve(0x102, 0);
+        assert_eq!(0, stack.depth());
+        stack.resolve(0x400, 0);
+        assert_eq!(1, stack.depth());
+        assert_eq!(0x104, stack.current().unwrap().return_address);
+    }
+
+    #[test]
+    fn test_unwind_to_outer_frame() {
+        let mut stack = CallStack::default();
+        step(&mut stack, 0x100, call(0x104), 0x400);
+        step(&mut stack, 0x410, call(0x414), 0x800);
+        step(&mut stack, 0x810, call(0x814), 0xC00);
+
+        // longjmp back to the outermost function
+        step(&mut stack, 0xC10, ret(0xC14), 0x104);
+        assert_eq!(0, stack.depth());
+        assert!(stack.mismatches().is_empty());
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let mut stack = CallStack::default();
+        step(&mut stack, 0x100, call(0x104), 0x400);
+        step(&mut stack, 0x420, ret(0x424), 0x4141_4140);
+
+        assert_eq!(0, stack.depth());
+        assert_eq!(
+            &[ReturnMismatch {
+                return_site: 0x420,
+                expected: 0x104,
+                actual: 0x4141_4140,
+            }],
+            stack.mismatches()
+        );
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut stack = CallStack::default();
+        step(&mut stack, 0x100, call(0x104), 0x400);
+        // call right before the interrupt
+        stack.branch(0x404, call(0x408));
+        stack.interrupt(0x800, 0x1000, 0);
+        assert_eq!(3, stack.depth());
+        assert_eq!(FrameKind::Interrupt, stack.current().unwrap().kind);
+
+        // exception return through a magic address pops only the handler
+        step(
+            &mut stack,
+            0x1010,
+            Branch {
+                kind: BranchKind::InterruptReturn,
+                fallthrough: 0x1014,
+            },
+            0xFFFF_FFF9,
+        );
+        assert_eq!(2, stack.depth());
+        assert_eq!(0x800, stack.current().unwrap().function);
+    }
+}
@@ -7,18 +7,23 @@
 //! references to the mmu, and event controller. The same is true to most calls to the mmu and event
 //! controller taking the other two as mutable references.
 //!
+use std::sync::Arc;
+
 use delegate::delegate;
 use styx_cpu_type::{
     arch::{backends::ArchRegister, ArchitectureDef, RegisterValue},
     ArchEndian,
 };
-use styx_errors::UnknownError;
+use styx_errors::{anyhow::anyhow, UnknownError};
+use styx_loader::SymbolTable;
 
 use crate::{
+    calls::{CallStack, CallTracker},
     cpu::{CpuBackend, DummyBackend, ExecutionReport, ReadRegisterError, WriteRegisterError},
     event_controller::{ActivateIRQnError, DummyEventController, EventController, ExceptionNumber},
-    hooks::CoreHandle,
+    hooks::{CodeHook, CoreHandle, HookToken, Hookable, StyxHook},
     memory::{DummyTlb, MemoryOperationError, Mmu},
+    snapshot::Snapshot,
 };
 
 pub mod builder;
@@ -35,6 +40,9 @@ pub struct ProcessorCore {
     pub cpu: Box<dyn CpuBackend>,
     pub mmu: Mmu,
     pub event_controller: EventController,
+    /// Symbols and source lines of the loaded target program, empty if the loader did not find
+    /// any.
+    pub symbols: Arc<SymbolTable>,
 }
 
 impl ProcessorCore {
@@ -83,9 +91,42 @@ impl ProcessorCore {
             cpu: Box::new(DummyBackend),
             mmu: Mmu::from_impl(Box::new(DummyTlb)),
             event_controller: EventController::new(Box::new(DummyEventController::default())),
+            symbols: Default::default(),
         }
     }
 
+    /// Add a code hook on the first instruction of the symbol `name`.
+    ///
+    /// Errors if the loaded target program has no symbol called `name`.
+    pub fn add_symbol_hook(
+        &mut self,
+        name: &str,
+        hook: impl CodeHook + 'static,
+    ) -> Result<HookToken, UnknownError> {
+        let address = self
+            .symbols
+            .address_of(name)
+            .ok_or_else(|| anyhow!("no symbol named `{name}` in the target program"))?;
+
+        Ok(self.cpu.add_hook(StyxHook::code(address, hook))?)
+    }
+
+    /// Start maintaining the shadow [`CallStack`], see [`calls`](crate::calls).
+    ///
+    /// Installs a code hook on every instruction, delete the returned token to stop tracking.
+    /// Errors if calls can't be detected on the architecture of this processor.
+    pub fn track_calls(&mut self) -> Result<HookToken, UnknownError> {
+        let tracker = CallTracker::new(self.cpu.as_ref())?;
+        let token = self.cpu.add_hook(StyxHook::code(.., tracker))?;
+        self.event_controller.call_stack.enable();
+        Ok(token)
+    }
+
+    /// The shadow call stack, empty unless [`ProcessorCore::track_calls()`] was called.
+    pub fn call_stack(&self) -> &CallStack {
+        &self.event_controller.call_stack
+    }
+
     /// Repackage the [`ProcessorCore`] as a [`CoreHandle`] struct for use within hooks.
     pub fn core_handle(&mut self) -> CoreHandle {
         CoreHandle {
@@ -94,4 +135,14 @@ impl ProcessorCore {
             event_controller: &mut self.event_controller,
         }
     }
+
+    /// Capture the complete state of the core into a [`Snapshot`].
+    pub fn snapshot(&mut self) -> Result<Snapshot, UnknownError> {
+        Snapshot::capture(self)
+    }
+
+    /// Restore the complete state of the core from a [`Snapshot`].
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), UnknownError> {
+        snapshot.restore(self)
+    }
 }
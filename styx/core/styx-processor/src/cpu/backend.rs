@@ -10,7 +10,9 @@ use styx_cpu_type::{
 use styx_errors::UnknownError;
 use thiserror::Error;
 
-use crate::{event_controller::EventController, hooks::Hookable, memory::Mmu};
+use crate::{
+    event_controller::EventController, hooks::Hookable, memory::Mmu, snapshot::CpuSnapshot,
+};
 
 #[derive(Debug, Error)]
 pub enum ReadRegisterError {
@@ -141,6 +143,27 @@ pub trait CpuBackend: Debug + Hookable + Send {
     /// Should return an error if attempting to restore context without saving first
     fn context_restore(&mut self) -> Result<(), UnknownError>;
 
+    /// Capture the current cpu state into a [`CpuSnapshot`].
+    ///
+    /// Unlike [`CpuBackend::context_save()`] this does not keep any state in the backend, so any
+    /// number of snapshots can be taken. The default implementation saves every architecture
+    /// register the backend can read, backends with state outside of the architecture registers
+    /// should add it to [`CpuSnapshot::backend`].
+    ///
+    /// ### NOTE
+    /// Requires the CPU to be stopped.
+    fn snapshot(&mut self) -> Result<CpuSnapshot, UnknownError> {
+        CpuSnapshot::capture(self)
+    }
+
+    /// Restore the cpu state from a [`CpuSnapshot`] taken with [`CpuBackend::snapshot()`].
+    ///
+    /// ### NOTE
+    /// Requires the CPU to be stopped.
+    fn restore_snapshot(&mut self, snapshot: &CpuSnapshot) -> Result<(), UnknownError> {
+        snapshot.restore(self)
+    }
+
     /// Get the current value of the current `pc` register.
     ///
     /// ### NOTE
@@ -8,14 +8,16 @@ use crate::{
     event_controller::EventController,
     hooks::{AddHookError, DeleteHookError, Hookable, StyxHook},
     memory::Mmu,
+    snapshot::CpuSnapshot,
 };
 
 use super::CpuBackend;
 
 /// [CpuBackend] and [Hookable] for testing purposes.
 ///
-/// The only implemented functionality here is [CpuBackend::execute()]. All
-/// other functions will panic unimplemented.
+/// The only implemented functionality here is [CpuBackend::execute()] and
+/// snapshots, which are always empty. All other functions will panic
+/// unimplemented.
 #[derive(Default, Debug)]
 pub struct DummyBackend;
 
@@ -73,6 +75,14 @@ impl CpuBackend for DummyBackend {
     fn context_restore(&mut self) -> Result<(), UnknownError> {
         unimplemented!()
     }
+
+    fn snapshot(&mut self) -> Result<CpuSnapshot, UnknownError> {
+        Ok(CpuSnapshot::default())
+    }
+
+    fn restore_snapshot(&mut self, _snapshot: &CpuSnapshot) -> Result<(), UnknownError> {
+        Ok(())
+    }
 }
 
 impl Hookable for DummyBackend {
@@ -51,6 +51,10 @@ impl EventControllerImpl for DummyEventController {
         None
     }
 
+    fn has_pending_events(&self) -> bool {
+        false
+    }
+
     fn init(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
         Ok(())
     }
@@ -19,7 +19,13 @@ use styx_errors::{
 };
 use thiserror::Error;
 
-use crate::{cpu::CpuBackend, executor::Delta, memory::Mmu};
+use crate::{
+    calls::CallStack,
+    cpu::{CpuBackend, CpuBackendExt},
+    executor::{Delta, VirtualClock},
+    memory::Mmu,
+    snapshot::ComponentState,
+};
 
 pub type ExceptionNumber = i32;
 
@@ -85,6 +91,14 @@ pub trait EventControllerImpl: AsAny + Send {
         mmu: &mut Mmu,
     ) -> Option<ExceptionNumber>;
 
+    /// Whether a latched event waits to be delivered by [`EventControllerImpl::next()`].
+    ///
+    /// A cpu waiting for an interrupt only skips ahead in time while this is false. The default
+    /// `true` is safe for controllers that don't track it, the cpu keeps running stride by stride.
+    fn has_pending_events(&self) -> bool {
+        true
+    }
+
     fn init(&mut self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError>;
 
     fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
@@ -103,6 +117,23 @@ pub trait EventControllerImpl: AsAny + Send {
     fn available_exceptions(&mut self) -> Result<Cow<'_, [Exception]>, OptionalFeatureError> {
         Err(OptionalFeatureError::Unsupported)
     }
+
+    /// Serialize the pending/active exception state for a
+    /// [`Snapshot`](crate::snapshot::Snapshot).
+    ///
+    /// The default saves nothing, event controllers with interrupt state must implement this to
+    /// be restored correctly.
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::default())
+    }
+
+    /// Restore state saved by [`EventControllerImpl::save_state()`].
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        if !state.is_empty() {
+            return Err(anyhow!("event controller does not support restoring state"));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -131,6 +162,12 @@ pub struct EventController {
     /// Processor specific event controller implementation
     pub inner: Box<dyn EventControllerImpl>,
     pub peripherals: Peripherals,
+    /// Shadow call stack, only maintained once call tracking is enabled.
+    pub call_stack: CallStack,
+    /// Virtual time, advanced on every [`EventController::tick()`].
+    pub clock: VirtualClock,
+    /// Interrupts taken by [`EventController::next()`] and [`EventController::execute()`].
+    interrupts_taken: u64,
 }
 
 impl Default for EventController {
@@ -144,6 +181,9 @@ impl EventController {
         Self {
             inner: event_controller,
             peripherals: Peripherals::default(),
+            call_stack: CallStack::default(),
+            clock: VirtualClock::default(),
+            interrupts_taken: 0,
         }
     }
     pub fn next(
@@ -152,7 +192,10 @@ impl EventController {
         mmu: &mut Mmu,
     ) -> Result<InterruptExecuted, UnknownError> {
         trace!("event controller next");
-        self.inner.next(cpu, mmu, &mut self.peripherals)
+        let interrupted_pc = self.interrupted_pc(cpu)?;
+        let executed = self.inner.next(cpu, mmu, &mut self.peripherals)?;
+        self.track_interrupt(cpu, interrupted_pc, &executed)?;
+        Ok(executed)
     }
 
     pub fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
@@ -165,7 +208,38 @@ impl EventController {
         cpu: &mut dyn CpuBackend,
         mmu: &mut Mmu,
     ) -> Result<InterruptExecuted, ActivateIRQnError> {
-        self.inner.execute(irq, cpu, mmu)
+        let interrupted_pc = self.interrupted_pc(cpu)?;
+        let executed = self.inner.execute(irq, cpu, mmu)?;
+        self.track_interrupt(cpu, interrupted_pc, &executed)?;
+        Ok(executed)
+    }
+
+    /// The pc before an interrupt is taken, if calls are tracked.
+    fn interrupted_pc(&self, cpu: &mut dyn CpuBackend) -> Result<Option<u64>, UnknownError> {
+        if self.call_stack.is_enabled() {
+            Ok(Some(cpu.pc()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Count a taken interrupt and push an interrupt frame on the call stack.
+    fn track_interrupt(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        interrupted_pc: Option<u64>,
+        executed: &InterruptExecuted,
+    ) -> Result<(), UnknownError> {
+        if let InterruptExecuted::Executed = executed {
+            self.interrupts_taken += 1;
+        }
+        if let (Some(interrupted_pc), InterruptExecuted::Executed) = (interrupted_pc, executed) {
+            let stack_pointer = cpu.architecture().registers().sp().variant();
+            let stack_pointer = cpu.read_register::<u32>(stack_pointer)? as u64;
+            self.call_stack
+                .interrupt(interrupted_pc, cpu.pc()?, stack_pointer);
+        }
+        Ok(())
     }
 
     /// Called to indicate the currently executing interrupt is finished, typically called by
@@ -183,6 +257,8 @@ impl EventController {
 
     pub fn reset(&mut self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
         self.inner.reset(cpu, mmu)?;
+        self.call_stack.clear();
+        self.clock = VirtualClock::default();
         for peripheral in self.peripherals.peripherals.iter_mut() {
             peripheral.reset(cpu, mmu)?;
         }
@@ -261,6 +337,7 @@ impl EventController {
         delta: &Delta,
     ) -> Result<(), UnknownError> {
         trace!("ticking event controller");
+        self.clock.advance(delta.count);
         // error buffer
         let mut errors = Vec::new();
 
@@ -285,6 +362,25 @@ impl EventController {
         }
     }
 
+    /// Number of interrupts taken so far, used to tell if a waiting cpu was woken up.
+    pub(crate) fn interrupts_taken(&self) -> u64 {
+        self.interrupts_taken
+    }
+
+    /// See [`EventControllerImpl::has_pending_events()`].
+    pub fn has_pending_events(&self) -> bool {
+        self.inner.has_pending_events()
+    }
+
+    /// Earliest [`Peripheral::next_deadline()`] of all peripherals.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.peripherals
+            .peripherals
+            .iter()
+            .filter_map(|peripheral| peripheral.next_deadline())
+            .min()
+    }
+
     pub fn add_peripheral(&mut self, peripheral: Box<dyn Peripheral>) -> Result<(), UnknownError> {
         self.peripherals.insert_peripheral(peripheral)?;
         Ok(())
@@ -2,9 +2,9 @@
 use as_any::AsAny;
 use log::debug;
 use static_assertions::assert_obj_safe;
-use styx_errors::UnknownError;
+use styx_errors::{anyhow::anyhow, UnknownError};
 
-use crate::{cpu::CpuBackend, memory::Mmu, processor::BuildingProcessor};
+use crate::{cpu::CpuBackend, memory::Mmu, processor::BuildingProcessor, snapshot::ComponentState};
 
 use super::{Delta, EventControllerImpl, ExceptionNumber};
 
@@ -83,6 +83,34 @@ pub trait Peripheral: AsAny + Send {
     ) -> Result<(), UnknownError> {
         Ok(())
     }
+
+    /// Instructions until the peripheral must be ticked next, `None` if nothing is scheduled.
+    ///
+    /// Polled before every stride, the [`Executor`](crate::executor::Executor) ends the stride
+    /// at the earliest deadline so timers fire at the exact instruction they are due. Peripherals
+    /// are ticked at least every stride regardless.
+    fn next_deadline(&self) -> Option<u64> {
+        None
+    }
+
+    /// Serialize the peripheral's state for a [`Snapshot`](crate::snapshot::Snapshot).
+    ///
+    /// The default saves nothing, peripherals holding register or queue state must implement this
+    /// to be restored correctly.
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::default())
+    }
+
+    /// Restore state saved by [`Peripheral::save_state()`].
+    ///
+    /// The default only accepts the empty state saved by the default
+    /// [`Peripheral::save_state()`].
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        if !state.is_empty() {
+            return Err(anyhow!("{} does not support restoring state", self.name()));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Default, Debug)]
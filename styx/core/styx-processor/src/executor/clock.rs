@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Deterministic virtual time.
+//!
+//! Time in a processor is counted in retired instructions, never in host wall time, so identical
+//! inputs always raise peripheral events at the same instruction. Each instruction takes
+//! [`NANOS_PER_INSTRUCTION`] of virtual time.
+//!
+//! Peripherals schedule their next event by returning a deadline from
+//! [`Peripheral::next_deadline()`](crate::event_controller::Peripheral::next_deadline). The
+//! [`Executor`](super::Executor) ends each stride at the earliest deadline so the peripheral is
+//! ticked exactly when it is due.
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Virtual time taken by one instruction.
+pub const NANOS_PER_INSTRUCTION: u64 = 1;
+
+/// Virtual time taken by `count` instructions.
+pub fn instructions_to_duration(count: u64) -> Duration {
+    Duration::from_nanos(count.saturating_mul(NANOS_PER_INSTRUCTION))
+}
+
+/// Instructions needed for at least `duration` of virtual time to pass.
+pub fn duration_to_instructions(duration: Duration) -> u64 {
+    let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+    nanos.div_ceil(NANOS_PER_INSTRUCTION)
+}
+
+/// Virtual time of a processor, see the [module documentation](self).
+///
+/// Owned by the [`EventController`](crate::event_controller::EventController) and advanced every
+/// time the peripherals are ticked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VirtualClock {
+    instructions: u64,
+}
+
+impl VirtualClock {
+    /// Instructions retired since the processor was built or reset.
+    pub fn now(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Virtual time elapsed since the processor was built or reset.
+    pub fn elapsed(&self) -> Duration {
+        instructions_to_duration(self.instructions)
+    }
+
+    /// Move time forward by `count` instructions.
+    pub fn advance(&mut self, count: u64) {
+        self.instructions = self.instructions.wrapping_add(count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_conversions() {
+        let mut clock = VirtualClock::default();
+        clock.advance(1500);
+        assert_eq!(1500, clock.now());
+        assert_eq!(instructions_to_duration(1500), clock.elapsed());
+        assert_eq!(1500, duration_to_instructions(clock.elapsed()));
+        // partial instructions round up
+        assert_eq!(1, duration_to_instructions(Duration::from_nanos(1)));
+        assert_eq!(0, duration_to_instructions(Duration::ZERO));
+    }
+}
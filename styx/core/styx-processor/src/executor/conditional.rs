@@ -54,10 +54,10 @@ impl ExecutorImpl for ConditionalExecutor {
         let cpu = &mut proc.cpu;
         let mmu = &mut proc.mmu;
 
-        event_controller.next(cpu.as_mut(), mmu)?;
-
         event_controller.tick(cpu.as_mut(), mmu, delta)?;
 
+        event_controller.next(cpu.as_mut(), mmu)?;
+
         plugins.tick(proc)?;
 
         Ok(())
@@ -17,7 +17,7 @@ use crate::{
 /// when to handle events, when to update the state of peripherals, and when to stop emulation.
 ///
 /// Both `halt_emulation` and `post_stride_processing` take a [`Delta`] representing the number of
-/// instructions and time elapsed during execution. It's recommended to forward this to the `tick`
+/// instructions and [virtual time](crate::executor::clock) elapsed during execution. It's recommended to forward this to the `tick`
 /// events in the event controller, peripherals, and plugins, but an [`ExecutorImpl`] can choose to
 /// modify this to change the speed of time.
 ///
@@ -28,6 +28,7 @@ use crate::{
 /// - [`DefaultExecutor`](crate::executor::DefaultExecutor)
 /// - [`ConditionalExecutor:lExecutorutor`](crate::executor::ConditionalExecutor)
 /// - [`SingleStepExecutor`](crate::executor::SingleStepExecutor)
+/// - [`IdleLoopExecutor`](crate::executor::IdleLoopExecutor)
 ///
 /// ## Execution Behavior
 ///
@@ -43,11 +44,13 @@ use crate::{
 ///    event controller, peripherals, and plugins.
 /// 4. The emulation loop is entered. First in the emulation loop,
 ///    [`ExecutorImpl::valid_emulation_conditions()`] is checked.
-/// 5. [`ExecutorImpl::emulate()`] is called. This should call `proc.cpu.execute()`.
+/// 5. [`ExecutorImpl::emulate()`] is called. This should call `proc.cpu.execute()`. The
+///    instruction count is the stride length, cut short at the earliest
+///    [peripheral deadline](crate::event_controller::Peripheral::next_deadline).
 /// 6. [`ExecutorImpl::halt_emulation()`] is called with the cpu's [`TargetExitReason`] and the
 ///    amount of [`Delta`] time spent emulating.
-/// 7. [`ExecutorImpl::post_stride_processing()`] is called. This should call `tick()` and `next()`
-///    methods.
+/// 7. [`ExecutorImpl::post_stride_processing()`] is called. This should call the `tick()` and
+///    then the `next()` methods.
 /// 8. Finally, the execution constraints are checked and emulation exits if either are met.
 /// 9. This loop (starting at `4.`) continues until something indicates the processor should stop.
 ///    After which, [`ExecutorImpl::emulation_teardown()`] is called. There is `processor_stop()`
@@ -118,8 +121,8 @@ pub trait ExecutorImpl: Send {
 
     /// Do any post-stride processing, called at the end of each stride.
     ///
-    /// This should call `proc.event_controller().next()` to process any pending events and
-    /// `proc.event_controller.tick()` to tick peripherals.
+    /// This should call `proc.event_controller.tick()` to tick peripherals and then
+    /// `proc.event_controller().next()` to process any pending events.
     #[inline]
     fn post_stride_processing(
         &mut self,
@@ -131,8 +134,9 @@ pub trait ExecutorImpl: Send {
         let cpu = &mut proc.cpu;
         let mmu = &mut proc.mmu;
 
-        event_controller.next(cpu.as_mut(), mmu)?;
+        // tick first so events due at the end of this stride are handled right away
         event_controller.tick(cpu.as_mut(), mmu, delta)?;
+        event_controller.next(cpu.as_mut(), mmu)?;
         plugins.tick(proc)?;
 
         Ok(())
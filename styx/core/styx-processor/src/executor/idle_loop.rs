@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Executor that detects idle polling loops.
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use log::{debug, trace};
+use styx_cpu_type::TargetExitReason;
+use styx_errors::UnknownError;
+
+use crate::{
+    core::ProcessorCore,
+    cpu::ExecutionReport,
+    executor::{DefaultExecutor, Delta},
+    hooks::{CoreHandle, HookToken},
+    plugins::collection::Plugins,
+    snapshot::CpuSnapshot,
+};
+
+use super::ExecutorImpl;
+
+/// Instructions between two visits of a loop head for the loop to count as tight.
+const MAX_LOOP_LENGTH: usize = 16;
+/// Identical loop iterations before the loop is considered idle.
+const IDLE_ITERATIONS: u32 = 4;
+
+/// Executor that treats tight polling loops on unchanged memory as waiting for an interrupt.
+///
+/// Firmware without a wait instruction usually idles by polling a status register or a flag set
+/// by an interrupt handler. This executor wraps another [ExecutorImpl] and watches execution with
+/// code and memory hooks. A loop is idle when [`IDLE_ITERATIONS`] consecutive iterations of at
+/// most [`MAX_LOOP_LENGTH`] instructions write no memory, read the same data from the same
+/// addresses and leave all registers unchanged.
+///
+/// Once an idle loop is found the cpu is stopped and the stride reports
+/// [TargetExitReason::WaitForInterrupt], so the [Executor](super::Executor) skips ahead to the
+/// next peripheral deadline.
+///
+/// Reads are recorded after earlier memory read hooks ran, so registers modeled by peripheral
+/// hooks are compared with the values the guest saw. Loops that wait on a free running counter
+/// are never idle.
+///
+/// The hooks slow down emulation, use this for firmware that spends most of its time idle.
+///
+/// Notably implements [ExecutorImpl].
+#[derive(Debug)]
+pub struct IdleLoopExecutor<E = DefaultExecutor> {
+    inner: E,
+    tracker: Arc<Mutex<LoopTracker>>,
+    hooks: Vec<HookToken>,
+}
+
+impl Default for IdleLoopExecutor<DefaultExecutor> {
+    fn default() -> Self {
+        Self::new(DefaultExecutor)
+    }
+}
+
+impl<E: ExecutorImpl> IdleLoopExecutor<E> {
+    /// Detect idle loops while running `inner`.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            tracker: Default::default(),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl<E: ExecutorImpl> ExecutorImpl for IdleLoopExecutor<E> {
+    fn valid_emulation_conditions(&mut self, proc: &mut ProcessorCore) -> bool {
+        self.inner.valid_emulation_conditions(proc)
+    }
+
+    fn emulate(
+        &mut self,
+        proc: &mut ProcessorCore,
+        insns: u64,
+    ) -> Result<ExecutionReport, UnknownError> {
+        self.tracker.lock().unwrap().reset();
+        let mut report = self.inner.emulate(proc, insns)?;
+
+        // other stop requests, e.g. from user hooks, are left alone
+        let stopped = self.tracker.lock().unwrap().stopped;
+        if stopped && report.exit_reason == TargetExitReason::HostStopRequest {
+            debug!("idle loop detected");
+            report.exit_reason = TargetExitReason::WaitForInterrupt;
+        }
+        Ok(report)
+    }
+
+    fn halt_emulation(&mut self, reason: &TargetExitReason, delta: &Delta) -> bool {
+        self.inner.halt_emulation(reason, delta)
+    }
+
+    fn emulation_setup(
+        &mut self,
+        proc: &mut ProcessorCore,
+        plugins: &mut Plugins,
+    ) -> Result<(), UnknownError> {
+        self.inner.emulation_setup(proc, plugins)?;
+
+        let tracker = self.tracker.clone();
+        let code = proc.cpu.code_hook(
+            0,
+            u64::MAX,
+            Box::new(move |mut proc: CoreHandle| {
+                let pc = proc.cpu.pc()?;
+                let mut tracker = tracker.lock().unwrap();
+                if tracker.instruction(pc, || CpuSnapshot::capture(proc.cpu))? {
+                    proc.cpu.stop();
+                    tracker.stopped = true;
+                }
+                Ok(())
+            }),
+        )?;
+        self.hooks.push(code);
+
+        let tracker = self.tracker.clone();
+        let read = proc.cpu.mem_read_hook(
+            0,
+            u64::MAX,
+            Box::new(
+                move |_proc: CoreHandle, address: u64, size: u32, data: &mut [u8]| {
+                    tracker
+                        .lock()
+                        .unwrap()
+                        .read(address, &data[..size as usize]);
+                    Ok(())
+                },
+            ),
+        )?;
+        self.hooks.push(read);
+
+        let tracker = self.tracker.clone();
+        let write = proc.cpu.mem_write_hook(
+            0,
+            u64::MAX,
+            Box::new(
+                move |_proc: CoreHandle, _address: u64, _size: u32, _data: &[u8]| {
+                    tracker.lock().unwrap().write();
+                    Ok(())
+                },
+            ),
+        )?;
+        self.hooks.push(write);
+
+        Ok(())
+    }
+
+    fn emulation_teardown(
+        &mut self,
+        proc: &mut ProcessorCore,
+        plugins: &mut Plugins,
+    ) -> Result<(), UnknownError> {
+        for token in self.hooks.drain(..) {
+            proc.cpu.delete_hook(token)?;
+        }
+        self.inner.emulation_teardown(proc, plugins)
+    }
+
+    fn post_stride_processing(
+        &mut self,
+        proc: &mut ProcessorCore,
+        plugins: &mut Plugins,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        self.inner.post_stride_processing(proc, plugins, delta)
+    }
+
+    fn get_stride_length(&self) -> u64 {
+        self.inner.get_stride_length()
+    }
+}
+
+/// Memory accesses of one loop iteration.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Iteration {
+    /// Address and data of every read, in order.
+    reads: Vec<(u64, Vec<u8>)>,
+    wrote: bool,
+    instructions: usize,
+}
+
+/// Finds idle loops from the executed instructions and their memory accesses.
+#[derive(Debug, Default)]
+struct LoopTracker {
+    /// Most recently executed pcs.
+    recent: VecDeque<u64>,
+    /// Start of the loop being watched.
+    head: Option<u64>,
+    current: Iteration,
+    /// Previous iteration and the registers at its end.
+    previous: Option<(Iteration, CpuSnapshot)>,
+    /// Consecutive identical iterations.
+    matches: u32,
+    idle: bool,
+    /// Did the code hook stop the cpu because of an idle loop?
+    stopped: bool,
+}
+
+impl LoopTracker {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn read(&mut self, address: u64, data: &[u8]) {
+        self.current.reads.push((address, data.to_vec()));
+    }
+
+    fn write(&mut self) {
+        self.current.wrote = true;
+    }
+
+    /// Record the instruction at `pc`, `registers` is only called at a loop head.
+    ///
+    /// Returns true when the loop became idle.
+    fn instruction(
+        &mut self,
+        pc: u64,
+        registers: impl FnOnce() -> Result<CpuSnapshot, UnknownError>,
+    ) -> Result<bool, UnknownError> {
+        if self.head == Some(pc) {
+            self.end_iteration(registers()?);
+        } else if self.current.instructions >= MAX_LOOP_LENGTH {
+            // not a tight loop, start looking for a new head
+            self.head = None;
+        }
+
+        if self.head.is_none() && self.recent.contains(&pc) {
+            trace!("watching loop at {pc:#X}");
+            self.head = Some(pc);
+            self.current = Iteration::default();
+            self.previous = None;
+            self.matches = 0;
+        }
+        if self.head.is_none() {
+            // only iterations of a watched loop are recorded
+            self.current = Iteration::default();
+        }
+
+        if self.recent.len() == MAX_LOOP_LENGTH {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(pc);
+        self.current.instructions += 1;
+
+        if !self.idle && self.matches >= IDLE_ITERATIONS {
+            self.idle = true;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn end_iteration(&mut self, registers: CpuSnapshot) {
+        let iteration = std::mem::take(&mut self.current);
+        let polling = !iteration.wrote && !iteration.reads.is_empty();
+        let repeated = self
+            .previous
+            .as_ref()
+            .is_some_and(|(previous, previous_registers)| {
+                *previous == iteration && *previous_registers == registers
+            });
+
+        if polling && repeated {
+            self.matches += 1;
+        } else {
+            self.matches = 0;
+        }
+        self.previous = Some((iteration, registers));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registers(pc: u64) -> impl FnOnce() -> Result<CpuSnapshot, UnknownError> {
+        move || {
+            Ok(CpuSnapshot {
+                pc,
+                ..Default::default()
+            })
+        }
+    }
+
+    /// Run `iterations` of a 3 instruction loop at 0x100 reading `data` from 0x4000.
+    fn poll(tracker: &mut LoopTracker, iterations: usize, data: u8) -> bool {
+        let mut idle = false;
+        for _ in 0..iterations {
+            for pc in [0x100, 0x104, 0x108] {
+                idle |= tracker.instruction(pc, registers(pc)).unwrap();
+                if pc == 0x100 {
+                    tracker.read(0x4000, &[data]);
+                }
+            }
+        }
+        idle
+    }
+
+    #[test]
+    fn test_polling_loop_is_idle() {
+        let mut tracker = LoopTracker::default();
+        // the first pass finds the head and the second is compared against
+        assert!(!poll(&mut tracker, 2 + IDLE_ITERATIONS as usize, 0));
+        assert!(poll(&mut tracker, 1, 0));
+        assert!(tracker.idle);
+    }
+
+    #[test]
+    fn test_changing_loop_is_not_idle() {
+        let mut tracker = LoopTracker::default();
+        for data in 0..16 {
+            assert!(!poll(&mut tracker, 1, data));
+        }
+
+        // writing memory is progress
+        let mut tracker = LoopTracker::default();
+        for _ in 0..16 {
+            for pc in [0x100, 0x104] {
+                assert!(!tracker.instruction(pc, registers(pc)).unwrap());
+                tracker.read(0x4000, &[0]);
+                tracker.write();
+            }
+        }
+    }
+}
@@ -13,19 +13,35 @@
 //!   ticked after every instruction.
 //! - [ConditionalExecutor] evaluates a custom function each stride to determine if the processor
 //!   should halt.
+//! - [IdleLoopExecutor] wraps another executor and treats tight polling loops on unchanged memory
+//!   as waiting for an interrupt.
 //!
+//! Time seen by peripherals is [virtual](clock), it is counted in executed instructions so
+//! emulation is deterministic. Strides are cut short at the earliest
+//! [peripheral deadline](crate::event_controller::Peripheral::next_deadline).
+//!
+//! When the cpu waits for an interrupt, reported as [TargetExitReason::WaitForInterrupt], time
+//! skips ahead to the next peripheral deadline without executing instructions, the skipped time
+//! is a second round of post stride processing. Without a deadline the cpu idles for the rest of
+//! the stride. Time is not skipped if the cpu took an interrupt at the end of the stride or the
+//! event controller has a [pending event](crate::event_controller::EventControllerImpl::has_pending_events).
+//!
+pub mod clock;
 mod conditional;
 mod default;
 mod execution_constraint;
 mod executor_impl;
+mod idle_loop;
 mod single_step;
 #[cfg(test)]
 mod test;
 
+pub use clock::VirtualClock;
 pub use conditional::ConditionalExecutor;
 pub use default::DefaultExecutor;
 pub use execution_constraint::{ExecutionConstraint, ExecutionConstraintConcrete, Forever};
 pub use executor_impl::ExecutorImpl;
+pub use idle_loop::IdleLoopExecutor;
 pub use single_step::SingleStepExecutor;
 
 use log::{debug, trace};
@@ -70,6 +86,32 @@ impl Executor {
         proc: &mut ProcessorCore,
         plugins: &mut Plugins,
         conditions: impl ExecutionConstraint,
+    ) -> Result<EmulationReport, UnknownError> {
+        self.setup(proc, plugins)?;
+        let report = self.step(proc, plugins, conditions)?;
+        self.teardown(proc, plugins)?;
+        Ok(report)
+    }
+
+    /// Start emulation, see [`ExecutorImpl::emulation_setup()`].
+    pub(crate) fn setup(
+        &mut self,
+        proc: &mut ProcessorCore,
+        plugins: &mut Plugins,
+    ) -> Result<(), UnknownError> {
+        self.inner.emulation_setup(proc, plugins)
+    }
+
+    /// Emulate until `conditions` are met, between [`Executor::setup()`] and
+    /// [`Executor::teardown()`].
+    ///
+    /// Unlike [`Executor::begin()`] this can be called many times per setup, e.g. to run a
+    /// processor in quanta.
+    pub(crate) fn step(
+        &mut self,
+        proc: &mut ProcessorCore,
+        plugins: &mut Plugins,
+        conditions: impl ExecutionConstraint,
     ) -> Result<EmulationReport, UnknownError> {
         let conditions = conditions.concrete();
         debug!("executor emulating with conditions: {conditions:?}");
@@ -79,8 +121,6 @@ impl Executor {
             self.inner.get_stride_length()
         };
 
-        self.inner.emulation_setup(proc, plugins)?;
-
         let target_time = conditions
             .timeout
             .map(|timeout_duration| Instant::now() + timeout_duration);
@@ -92,20 +132,23 @@ impl Executor {
                 break TargetExitReason::HostStopRequest;
             }
 
-            trace!("executor start emulating");
+            // end the stride when the next peripheral event is due
+            let stride = match proc.event_controller.next_deadline() {
+                Some(deadline) => stride_constraint.min(deadline.max(1)),
+                None => stride_constraint,
+            };
+
+            trace!("executor start emulating {stride} instructions");
             let emulate_start = Instant::now();
-            let report = self.inner.emulate(proc, stride_constraint)?;
-            let emulate_time = Instant::now() - emulate_start;
+            let report = self.inner.emulate(proc, stride)?;
+            total_wall_time += Instant::now() - emulate_start;
 
             // update bookeeping for emulation statistics
-            let instruction_report =
-                InstructionReport::from_execution_report(&report, stride_constraint);
+            let instruction_report = InstructionReport::from_execution_report(&report, stride);
             total_instructions += instruction_report;
-            let delta = Delta {
-                time: emulate_time,
-                count: instruction_report.instructions(),
-            };
-            total_wall_time += delta.time;
+            let executed = instruction_report.instructions();
+
+            let delta = Delta::from_instructions(executed);
 
             // check if inner wants to halt
             if self.inner.halt_emulation(&report.exit_reason, &delta) {
@@ -114,8 +157,36 @@ impl Executor {
             }
 
             // post stride processing
+            let interrupts_taken = proc.event_controller.interrupts_taken();
             self.inner.post_stride_processing(proc, plugins, &delta)?;
 
+            // a waiting cpu idles until the next deadline or the end of the stride, unless it was
+            // woken up or an event is pending. The peripherals were just ticked so their deadlines
+            // include the executed instructions
+            let mut elapsed = executed;
+            if report.exit_reason == TargetExitReason::WaitForInterrupt
+                && proc.event_controller.interrupts_taken() == interrupts_taken
+                && !proc.event_controller.has_pending_events()
+            {
+                let budget = remaining_instructions
+                    .unwrap_or(u64::MAX)
+                    .saturating_sub(executed);
+                let idle = proc
+                    .event_controller
+                    .next_deadline()
+                    .unwrap_or(stride.saturating_sub(executed))
+                    .min(budget);
+                if idle > 0 {
+                    trace!("cpu waiting for interrupt, skipping {idle} instructions");
+                    self.inner.post_stride_processing(
+                        proc,
+                        plugins,
+                        &Delta::from_instructions(idle),
+                    )?;
+                    elapsed += idle;
+                }
+            }
+
             // timeout check processing
             if target_time
                 .map(|timeout| Instant::now() > timeout)
@@ -126,7 +197,7 @@ impl Executor {
             }
 
             if let Some(remaining_instr) = &mut remaining_instructions {
-                *remaining_instr = remaining_instr.saturating_sub(stride_constraint);
+                *remaining_instr = remaining_instr.saturating_sub(stride.max(elapsed));
                 if *remaining_instr == 0 {
                     trace!("executor instruction count hit");
                     break TargetExitReason::InstructionCountComplete;
@@ -137,13 +208,21 @@ impl Executor {
             }
         };
 
-        self.inner.emulation_teardown(proc, plugins)?;
         Ok(EmulationReport {
             exit_reason,
             instructions: total_instructions,
             wall_time: total_wall_time,
         })
     }
+
+    /// Stop emulation, see [`ExecutorImpl::emulation_teardown()`].
+    pub(crate) fn teardown(
+        &mut self,
+        proc: &mut ProcessorCore,
+        plugins: &mut Plugins,
+    ) -> Result<(), UnknownError> {
+        self.inner.emulation_teardown(proc, plugins)
+    }
 }
 
 impl Default for Executor {
@@ -157,8 +236,19 @@ impl Default for Executor {
 #[derive(Debug, Clone)]
 /// Represents a length of emulation.
 pub struct Delta {
-    /// Elapsed time.
+    /// Elapsed [virtual time](clock).
     pub time: std::time::Duration,
-    /// Number of instructions executed.
+    /// Number of instructions executed, including the instructions skipped while the cpu was
+    /// waiting for an interrupt.
     pub count: u64,
 }
+
+impl Delta {
+    /// Delta of `count` executed instructions and their [virtual time](clock).
+    pub fn from_instructions(count: u64) -> Self {
+        Self {
+            time: clock::instructions_to_duration(count),
+            count,
+        }
+    }
+}
@@ -1,12 +1,16 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use std::sync::{Arc, Mutex};
 
+use styx_cpu_type::TargetExitReason;
 use styx_errors::UnknownError;
 
 use crate::{
     core::ProcessorCore,
-    cpu::{CpuBackend, DummyBackend},
-    event_controller::{EventController, EventControllerImpl, Peripheral},
+    cpu::{CpuBackend, DummyBackend, ExecutionReport},
+    event_controller::{
+        ActivateIRQnError, EventController, EventControllerImpl, ExceptionNumber,
+        InterruptExecuted, Peripheral, Peripherals,
+    },
     executor::{ConditionalExecutor, DefaultExecutor, Executor, ExecutorImpl, SingleStepExecutor},
     memory::Mmu,
     plugins::{Plugin, Plugins},
@@ -208,6 +212,7 @@ pub fn test_executor_events(
         cpu: Box::new(cpu),
         mmu,
         event_controller: ev,
+        symbols: Default::default(),
     };
     let mut plugins = Plugins {
         plugins: vec![Box::new(ticker.plugin.clone())],
@@ -253,3 +258,216 @@ fn test_single_step() {
     // each instruction is a tick, hence single step
     test_executor_events(Box::new(executor), normal_begin_executor, 1000, 1).unwrap();
 }
+
+/// Peripheral that records the instruction count it was ticked at every `period` instructions.
+struct DeadlinePeripheral {
+    period: u64,
+    /// Instructions executed in total.
+    now: u64,
+    /// Instructions executed since the last period.
+    elapsed: u64,
+    fired: Arc<Mutex<Vec<u64>>>,
+}
+
+impl Peripheral for DeadlinePeripheral {
+    fn name(&self) -> &str {
+        "deadline"
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _event_controller: &mut dyn EventControllerImpl,
+        delta: &crate::executor::Delta,
+    ) -> Result<(), UnknownError> {
+        self.now += delta.count;
+        self.elapsed += delta.count;
+        while self.elapsed >= self.period {
+            self.elapsed -= self.period;
+            self.fired.lock().unwrap().push(self.now);
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<u64> {
+        Some(self.period - self.elapsed)
+    }
+}
+
+/// Strides end exactly at peripheral deadlines.
+#[test]
+fn test_deadline_cuts_stride() {
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let mut ev = EventController::default();
+    ev.add_peripheral(Box::new(DeadlinePeripheral {
+        period: 300,
+        now: 0,
+        elapsed: 0,
+        fired: fired.clone(),
+    }))
+    .unwrap();
+    let mut proc = ProcessorCore {
+        cpu: Box::new(DummyBackend),
+        mmu: Mmu::default(),
+        event_controller: ev,
+        symbols: Default::default(),
+    };
+
+    let mut executor = Executor::new(Box::new(DefaultExecutor));
+    let report = executor
+        .begin(&mut proc, &mut Plugins::default(), 1000)
+        .unwrap();
+
+    assert_eq!(vec![300, 600, 900], *fired.lock().unwrap());
+    assert_eq!(1000, report.instructions.instructions());
+    assert_eq!(1000, proc.event_controller.clock.now());
+}
+
+/// Executes `active` instructions per stride, then waits for an interrupt.
+struct WaitingExecutor {
+    active: u64,
+}
+
+impl ExecutorImpl for WaitingExecutor {
+    fn emulate(
+        &mut self,
+        _proc: &mut ProcessorCore,
+        insns: u64,
+    ) -> Result<ExecutionReport, UnknownError> {
+        Ok(ExecutionReport::new(
+            TargetExitReason::WaitForInterrupt,
+            self.active.min(insns),
+        ))
+    }
+}
+
+/// A waiting cpu skips ahead to the next peripheral deadline.
+#[test]
+fn test_wait_for_interrupt_skips_to_deadline() {
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let mut ev = EventController::default();
+    ev.add_peripheral(Box::new(DeadlinePeripheral {
+        period: 300,
+        now: 0,
+        elapsed: 0,
+        fired: fired.clone(),
+    }))
+    .unwrap();
+    let mut proc = ProcessorCore {
+        cpu: Box::new(DummyBackend),
+        mmu: Mmu::default(),
+        event_controller: ev,
+        symbols: Default::default(),
+    };
+
+    let mut executor = Executor::new(Box::new(WaitingExecutor { active: 10 }));
+    let report = executor
+        .begin(&mut proc, &mut Plugins::default(), 1000)
+        .unwrap();
+
+    assert_eq!(vec![300, 600, 900], *fired.lock().unwrap());
+    // strides of 300, 300, 300 and 100 instructions
+    assert_eq!(40, report.instructions.instructions());
+    assert_eq!(
+        TargetExitReason::InstructionCountComplete,
+        report.exit_reason
+    );
+    assert_eq!(1000, proc.event_controller.clock.now());
+}
+
+/// Without a deadline a waiting cpu idles for the rest of each stride.
+#[test]
+fn test_wait_for_interrupt_without_deadline() {
+    let mut proc = ProcessorCore {
+        cpu: Box::new(DummyBackend),
+        mmu: Mmu::default(),
+        event_controller: EventController::default(),
+        symbols: Default::default(),
+    };
+
+    let mut executor = Executor::new(Box::new(WaitingExecutor { active: 10 }));
+    let report = executor
+        .begin(&mut proc, &mut Plugins::default(), 5000)
+        .unwrap();
+
+    // 5 strides of 1000 instructions, 10 of them executed
+    assert_eq!(50, report.instructions.instructions());
+    assert_eq!(
+        TargetExitReason::InstructionCountComplete,
+        report.exit_reason
+    );
+    assert_eq!(5000, proc.event_controller.clock.now());
+}
+
+/// Event controller with an event that is never delivered.
+struct PendingEventController;
+
+impl EventControllerImpl for PendingEventController {
+    fn next(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        Ok(InterruptExecuted::NotExecuted)
+    }
+
+    fn latch(&mut self, _event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        _irq: ExceptionNumber,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Result<InterruptExecuted, ActivateIRQnError> {
+        Ok(InterruptExecuted::NotExecuted)
+    }
+
+    fn finish_interrupt(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Option<ExceptionNumber> {
+        None
+    }
+
+    fn has_pending_events(&self) -> bool {
+        true
+    }
+
+    fn init(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        Ok(())
+    }
+}
+
+/// A waiting cpu does not skip time while an event is pending.
+#[test]
+fn test_wait_for_interrupt_with_pending_event() {
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let mut ev = EventController::new(Box::new(PendingEventController));
+    ev.add_peripheral(Box::new(DeadlinePeripheral {
+        period: 300,
+        now: 0,
+        elapsed: 0,
+        fired: fired.clone(),
+    }))
+    .unwrap();
+    let mut proc = ProcessorCore {
+        cpu: Box::new(DummyBackend),
+        mmu: Mmu::default(),
+        event_controller: ev,
+        symbols: Default::default(),
+    };
+
+    let mut executor = Executor::new(Box::new(WaitingExecutor { active: 10 }));
+    executor
+        .begin(&mut proc, &mut Plugins::default(), 100)
+        .unwrap();
+
+    // only the executed instructions count
+    assert!(fired.lock().unwrap().is_empty());
+    assert_eq!(10, proc.event_controller.clock.now());
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use styx_errors::UnknownError;
+
+use crate::hooks::CoreHandle;
+
+/// Integer comparison performed by the guest.
+///
+/// See [StyxHook::compare()](crate::hooks::StyxHook::compare()).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareKind {
+    /// `left == right`
+    Equal,
+    /// `left != right`
+    NotEqual,
+    /// Unsigned `left < right`
+    Less,
+    /// Unsigned `left <= right`
+    LessEqual,
+    /// Signed `left < right`
+    SignedLess,
+    /// Signed `left <= right`
+    SignedLessEqual,
+}
+
+/// Callback for a compare hook.
+///
+/// See [StyxHook::compare()](crate::hooks::StyxHook::compare()) for more information on
+/// constructing compare hooks.
+pub trait CompareHook: Send {
+    fn call(
+        &mut self,
+        proc: CoreHandle,
+        kind: CompareKind,
+        size: u32,
+        left: u128,
+        right: u128,
+    ) -> Result<(), UnknownError>;
+}
+
+impl<T: FnMut(CoreHandle, CompareKind, u32, u128, u128) -> Result<(), UnknownError> + Send>
+    CompareHook for T
+{
+    fn call(
+        &mut self,
+        proc: CoreHandle,
+        kind: CompareKind,
+        size: u32,
+        left: u128,
+        right: u128,
+    ) -> Result<(), UnknownError> {
+        self(proc, kind, size, left, right)
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 mod block;
 mod code;
+mod compare;
 mod interrupt;
 mod invalid_instruction;
 mod memory_protection;
@@ -13,6 +14,7 @@ use std::ops::BitAnd;
 
 pub use block::*;
 pub use code::*;
+pub use compare::*;
 pub use interrupt::*;
 pub use invalid_instruction::*;
 pub use memory_protection::*;
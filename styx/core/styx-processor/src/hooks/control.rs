@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Enabling, disabling and filtering added hooks without removing them.
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Mutex,
+};
+
+use styx_cpu_type::{arch::backends::ArchRegister, ArchEndian};
+use styx_errors::{anyhow::anyhow, UnknownError};
+
+use super::CoreHandle;
+use crate::memory::helpers::ReadExt;
+
+/// How a value is compared in a [`HookCondition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// Every bit set in the expected value is set.
+    BitsSet,
+}
+
+impl Comparison {
+    fn compare(self, actual: u64, expected: u64) -> bool {
+        match self {
+            Comparison::Equal => actual == expected,
+            Comparison::NotEqual => actual != expected,
+            Comparison::Less => actual < expected,
+            Comparison::LessEqual => actual <= expected,
+            Comparison::Greater => actual > expected,
+            Comparison::GreaterEqual => actual >= expected,
+            Comparison::BitsSet => actual & expected == expected,
+        }
+    }
+}
+
+/// Cheap predicate checked before a hook's callback runs, the callback is skipped if it is
+/// false.
+///
+/// ```
+/// use styx_processor::hooks::{Comparison, HookCondition};
+/// use styx_cpu_type::arch::arm::ArmRegister;
+///
+/// // every 16th hit with r0 == 3
+/// let condition = HookCondition::register(ArmRegister::R0, Comparison::Equal, 3)
+///     .and(HookCondition::every(16));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookCondition {
+    /// Compare the value of a register.
+    Register {
+        register: ArchRegister,
+        comparison: Comparison,
+        value: u64,
+    },
+    /// Compare a value in (virtual) data memory, read in the endianness of the target.
+    Memory {
+        address: u64,
+        /// 1, 2, 4 or 8 bytes.
+        size: u8,
+        comparison: Comparison,
+        value: u64,
+    },
+    /// Compare the number of times the hook was hit, including this hit.
+    Hits { comparison: Comparison, value: u64 },
+    /// Every `n`th hit.
+    Every(u64),
+    /// Every condition holds.
+    All(Vec<HookCondition>),
+    /// At least one condition holds.
+    Any(Vec<HookCondition>),
+}
+
+impl HookCondition {
+    pub fn register(register: impl Into<ArchRegister>, comparison: Comparison, value: u64) -> Self {
+        Self::Register {
+            register: register.into(),
+            comparison,
+            value,
+        }
+    }
+
+    pub fn memory(address: u64, size: u8, comparison: Comparison, value: u64) -> Self {
+        Self::Memory {
+            address,
+            size,
+            comparison,
+            value,
+        }
+    }
+
+    pub fn hits(comparison: Comparison, value: u64) -> Self {
+        Self::Hits { comparison, value }
+    }
+
+    pub fn every(n: u64) -> Self {
+        Self::Every(n)
+    }
+
+    /// Both `self` and `other` hold.
+    pub fn and(self, other: HookCondition) -> Self {
+        match self {
+            Self::All(mut conditions) => {
+                conditions.push(other);
+                Self::All(conditions)
+            }
+            condition => Self::All(vec![condition, other]),
+        }
+    }
+
+    /// Either `self` or `other` holds.
+    pub fn or(self, other: HookCondition) -> Self {
+        match self {
+            Self::Any(mut conditions) => {
+                conditions.push(other);
+                Self::Any(conditions)
+            }
+            condition => Self::Any(vec![condition, other]),
+        }
+    }
+
+    /// Check the condition for the `hits`th hit of a hook.
+    pub fn evaluate(&self, proc: &mut CoreHandle, hits: u64) -> Result<bool, UnknownError> {
+        Ok(match self {
+            HookCondition::Register {
+                register,
+                comparison,
+                value,
+            } => {
+                let actual = proc
+                    .read_register_raw(*register)?
+                    .to_u64()
+                    .ok_or_else(|| anyhow!("register {register} is wider than 64 bits"))?;
+                comparison.compare(actual, *value)
+            }
+            HookCondition::Memory {
+                address,
+                size,
+                comparison,
+                value,
+            } => {
+                let mut bytes = [0u8; 8];
+                let bytes = bytes
+                    .get_mut(..*size as usize)
+                    .filter(|_| matches!(size, 1 | 2 | 4 | 8))
+                    .ok_or_else(|| anyhow!("invalid memory condition size {size}"))?;
+                let endian = proc.endian();
+                proc.mmu.virt_data(proc.cpu).read(*address).bytes(bytes)?;
+                let actual = bytes.iter().enumerate().fold(0u64, |actual, (idx, byte)| {
+                    let shift = match endian {
+                        ArchEndian::LittleEndian => idx,
+                        ArchEndian::BigEndian => bytes.len() - 1 - idx,
+                    };
+                    actual | ((*byte as u64) << (shift * 8))
+                });
+                comparison.compare(actual, *value)
+            }
+            HookCondition::Hits { comparison, value } => comparison.compare(hits, *value),
+            HookCondition::Every(n) => *n != 0 && hits % n == 0,
+            HookCondition::All(conditions) => {
+                for condition in conditions {
+                    if !condition.evaluate(proc, hits)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            HookCondition::Any(conditions) => {
+                for condition in conditions {
+                    if condition.evaluate(proc, hits)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+        })
+    }
+}
+
+/// Enabled state, hit count and condition of an added hook.
+///
+/// [`Hookable`](super::Hookable) implementors keep one per hook and call
+/// [`HookControl::should_run()`] before the hook callback. Users get to it by token through
+/// [`Hookable::hook_control()`](super::Hookable::hook_control()), changes take effect on the next
+/// trigger, even from inside a hook callback.
+#[derive(Debug)]
+pub struct HookControl {
+    enabled: AtomicBool,
+    hits: AtomicU64,
+    /// Set while `condition` is `Some`, so hooks without a condition don't take the lock.
+    has_condition: AtomicBool,
+    condition: Mutex<Option<HookCondition>>,
+}
+
+impl Default for HookControl {
+    fn default() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            hits: AtomicU64::new(0),
+            has_condition: AtomicBool::new(false),
+            condition: Mutex::new(None),
+        }
+    }
+}
+
+impl HookControl {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Disabled hooks are skipped and don't count hits.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Number of times the hook triggered while enabled, whether or not its condition held.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_hits(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+    }
+
+    /// Set or clear the condition checked before the callback runs.
+    pub fn set_condition(&self, condition: Option<HookCondition>) {
+        let mut current = self.condition.lock().unwrap();
+        self.has_condition
+            .store(condition.is_some(), Ordering::Release);
+        *current = condition;
+    }
+
+    pub fn condition(&self) -> Option<HookCondition> {
+        self.condition.lock().unwrap().clone()
+    }
+
+    /// Record a hit and check if the hook callback should run.
+    pub fn should_run(&self, proc: &mut CoreHandle) -> Result<bool, UnknownError> {
+        if !self.is_enabled() {
+            return Ok(false);
+        }
+        let hits = self.hits.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.has_condition.load(Ordering::Acquire) {
+            return Ok(true);
+        }
+
+        match self.condition.lock().unwrap().as_ref() {
+            Some(condition) => condition.evaluate(proc, hits),
+            None => Ok(true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ProcessorCore;
+
+    #[test]
+    fn test_comparison() {
+        assert!(Comparison::Equal.compare(3, 3));
+        assert!(Comparison::Less.compare(2, 3));
+        assert!(!Comparison::GreaterEqual.compare(2, 3));
+        assert!(Comparison::BitsSet.compare(0b1110, 0b0110));
+        assert!(!Comparison::BitsSet.compare(0b1010, 0b0110));
+    }
+
+    #[test]
+    fn test_should_run() {
+        let mut core = ProcessorCore::dummy();
+        let control = HookControl::default();
+        control.set_condition(Some(
+            HookCondition::every(2).or(HookCondition::hits(Comparison::Equal, 5)),
+        ));
+
+        let mut proc =
+            CoreHandle::new(core.cpu.as_mut(), &mut core.mmu, &mut core.event_controller);
+        let runs: Vec<_> = (0..6)
+            .map(|_| control.should_run(&mut proc).unwrap())
+            .collect();
+        assert_eq!(vec![false, true, false, true, true, true], runs);
+        assert_eq!(6, control.hits());
+
+        control.set_enabled(false);
+        assert!(!control.should_run(&mut proc).unwrap());
+        assert_eq!(6, control.hits());
+
+        control.set_enabled(true);
+        control.set_condition(None);
+        assert!(control.should_run(&mut proc).unwrap());
+        assert_eq!(7, control.hits());
+    }
+}
@@ -1,12 +1,17 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use crate::{
+    calls::CallStack,
     cpu::{CpuBackend, ExecutionReport, ReadRegisterError, WriteRegisterError},
     event_controller::{ActivateIRQnError, EventController, ExceptionNumber},
-    hooks::{AddHookError, DeleteHookError, HookToken, StyxHook},
+    hooks::{
+        AddHookError, DeleteHookError, HookCondition, HookControl, HookControlError, HookToken,
+        StyxHook,
+    },
     memory::{MemoryOperationError, Mmu},
 };
 
 use delegate::delegate;
+use std::sync::Arc;
 use styx_cpu_type::{
     arch::{backends::ArchRegister, ArchitectureDef, RegisterValue},
     ArchEndian,
@@ -35,6 +40,13 @@ impl<'a> CoreHandle<'a> {
         }
     }
 
+    /// The shadow call stack, empty unless call tracking is enabled.
+    ///
+    /// See [`ProcessorCore::track_calls()`](crate::core::ProcessorCore::track_calls()).
+    pub fn call_stack(&self) -> &CallStack {
+        &self.event_controller.call_stack
+    }
+
     // delegate the common ops to the [`CoreHandle`]
     delegate! {
         to self.cpu {
@@ -67,6 +79,20 @@ impl<'a> CoreHandle<'a> {
             pub fn add_hook(&mut self, hook: StyxHook) -> Result<HookToken, AddHookError>;
             /// See [`Hookable::delete_hook()`](crate::hooks::Hookable::delete_hook())
             pub fn delete_hook(&mut self, token: HookToken) -> Result<(), DeleteHookError>;
+            /// See [`Hookable::hook_control()`](crate::hooks::Hookable::hook_control())
+            pub fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError>;
+            /// See [`Hookable::enable_hook()`](crate::hooks::Hookable::enable_hook())
+            pub fn enable_hook(&mut self, token: HookToken) -> Result<(), HookControlError>;
+            /// See [`Hookable::disable_hook()`](crate::hooks::Hookable::disable_hook())
+            pub fn disable_hook(&mut self, token: HookToken) -> Result<(), HookControlError>;
+            /// See [`Hookable::hook_hits()`](crate::hooks::Hookable::hook_hits())
+            pub fn hook_hits(&mut self, token: HookToken) -> Result<u64, HookControlError>;
+            /// See [`Hookable::set_hook_condition()`](crate::hooks::Hookable::set_hook_condition())
+            pub fn set_hook_condition(
+                &mut self,
+                token: HookToken,
+                condition: Option<HookCondition>,
+            ) -> Result<(), HookControlError>;
         }
 
         to self.mmu {
@@ -7,8 +7,8 @@ use thiserror::Error;
 
 use super::{
     callbacks::{CodeHook, ProtectionFaultHook},
-    BlockHook, HookToken, InterruptHook, InvalidInstructionHook, MemoryReadHook,
-    MemoryReadHookData, MemoryReadHookDataFn, MemoryWriteHook, MemoryWriteHookData,
+    BlockHook, HookCondition, HookControl, HookToken, InterruptHook, InvalidInstructionHook,
+    MemoryReadHook, MemoryReadHookData, MemoryReadHookDataFn, MemoryWriteHook, MemoryWriteHookData,
     MemoryWriteHookDataFn, StyxHook, UnmappedFaultHook,
 };
 
@@ -32,6 +32,17 @@ pub enum DeleteHookError {
     Other(#[from] UnknownError),
 }
 
+/// Error while getting the [`HookControl`] of a hook.
+#[derive(Error, Debug)]
+pub enum HookControlError {
+    #[error("hook with token does not exist")]
+    HookDoesNotExist,
+    #[error("hookable does not support hook control")]
+    NotSupported,
+    #[error(transparent)]
+    Other(#[from] UnknownError),
+}
+
 pub type HookUserData = Arc<dyn Any + Send + Sync>;
 
 assert_obj_safe!(Hookable);
@@ -81,6 +92,76 @@ pub trait Hookable {
     // TODO would it be useful to return the StyxHook?
     fn delete_hook(&mut self, token: HookToken) -> Result<(), DeleteHookError>;
 
+    /// Get the [`HookControl`] of a hook using a token given by [`Hookable::add_hook()`].
+    ///
+    /// The control enables/disables the hook, counts its hits and holds an optional
+    /// [`HookCondition`], all without deleting and re-adding the hook. The default implementation
+    /// returns [`HookControlError::NotSupported`].
+    fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        let _ = token;
+        Err(HookControlError::NotSupported)
+    }
+
+    /// Let a hook run again after [`Hookable::disable_hook()`].
+    fn enable_hook(&mut self, token: HookToken) -> Result<(), HookControlError> {
+        self.hook_control(token)?.set_enabled(true);
+        Ok(())
+    }
+
+    /// Skip a hook until it is enabled again with [`Hookable::enable_hook()`].
+    fn disable_hook(&mut self, token: HookToken) -> Result<(), HookControlError> {
+        self.hook_control(token)?.set_enabled(false);
+        Ok(())
+    }
+
+    /// Number of times a hook triggered while enabled.
+    fn hook_hits(&mut self, token: HookToken) -> Result<u64, HookControlError> {
+        Ok(self.hook_control(token)?.hits())
+    }
+
+    /// Set or clear the [`HookCondition`] checked before the hook callback runs.
+    fn set_hook_condition(
+        &mut self,
+        token: HookToken,
+        condition: Option<HookCondition>,
+    ) -> Result<(), HookControlError> {
+        self.hook_control(token)?.set_condition(condition);
+        Ok(())
+    }
+
+    /// Add a hook that only runs its callback when `condition` holds.
+    ///
+    /// ```
+    /// use styx_processor::hooks::{Comparison, CoreHandle, HookCondition, Hookable, StyxHook};
+    /// use styx_processor::processor::ProcessorBuilder;
+    /// use styx_processor::core::builder::DummyProcessorBuilder;
+    ///
+    /// let mut proc = ProcessorBuilder::default()
+    ///     .with_builder(DummyProcessorBuilder)
+    ///     .build()?;
+    ///
+    /// // the dummy cpu does not support hook control
+    /// let condition = HookCondition::hits(Comparison::Greater, 100);
+    /// let hook = StyxHook::code(0x1000, |_proc: CoreHandle| Ok(()));
+    /// assert!(proc.add_conditional_hook(hook, condition).is_err());
+    /// # Ok::<(), styx_errors::UnknownError>(())
+    /// ```
+    ///
+    /// The hook is removed again if the condition can't be set.
+    fn add_conditional_hook(
+        &mut self,
+        hook: StyxHook,
+        condition: HookCondition,
+    ) -> Result<HookToken, AddHookError> {
+        let token = self.add_hook(hook)?;
+        if let Err(err) = self.set_hook_condition(token, Some(condition)) {
+            // best effort, the hook is unusable either way
+            let _ = self.delete_hook(token);
+            return Err(AddHookError::Other(err.into()));
+        }
+        Ok(token)
+    }
+
     /// Add a block hook.
     ///
     /// See [StyxHook::block()] for information on block hooks.
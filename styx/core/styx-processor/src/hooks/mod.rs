@@ -29,6 +29,10 @@
 //! allowing users to pass a closure or function with appropriate signature as a hook without having
 //! to manually impl the trait.
 //!
+//! Added hooks can be disabled, counted and filtered without removing them, see
+//! [`Hookable::hook_control()`] and [`HookCondition`]. This is much cheaper than deleting and
+//! re-adding a hook to toggle it.
+//!
 //! # Example
 //!
 //! ```
@@ -75,12 +79,14 @@
 
 mod address_range;
 mod callbacks;
+mod control;
 mod core_handle;
 mod hookable;
 mod token;
 
 pub use address_range::AddressRange;
 pub use callbacks::*;
+pub use control::*;
 pub use core_handle::*;
 pub use hookable::*;
 use styx_cpu_type::arch::backends::ArchRegister;
@@ -262,6 +268,18 @@ pub enum StyxHook {
     ///
     /// This is currently **allowed** behavior but may change in the future.
     RegisterWrite(ArchRegister, Box<dyn RegisterWriteHook>),
+
+    /// Hook on an integer comparison executed by an instruction with a
+    /// program counter in the [AddressRange].
+    ///
+    /// The callback receives the kind of comparison, the size of the operands
+    /// in bytes and the operand values. Comparisons are reported at the
+    /// granularity of the backend, byte-wise comparison loops like `memcmp`
+    /// trigger the hook once per compared byte.
+    ///
+    /// Only supported by cpu backends that have the comparison operands
+    /// available, others return [`AddHookError::HookTypeNotSupported`].
+    Compare(AddressRange, Box<dyn CompareHook>),
 }
 impl Debug for StyxHook {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -292,6 +310,9 @@ impl Debug for StyxHook {
             StyxHook::RegisterWrite(register, _hook) => {
                 write!(f, "RegisterWrite({register})")
             }
+            StyxHook::Compare(range, _hook) => {
+                write!(f, "Compare({range:X?})")
+            }
         }
     }
 }
@@ -639,4 +660,36 @@ impl StyxHook {
     ) -> Self {
         Self::RegisterWrite(register.into(), Box::new(hook))
     }
+
+    /// Construct a hook on integer comparisons executed by instructions with a
+    /// program counter in `range`.
+    ///
+    /// `size` is the size of the operands in bytes, `left` and `right` hold
+    /// the zero extended operand values. Useful for comparison tracing in
+    /// fuzzers, e.g. to find the magic values an input is checked against.
+    ///
+    /// Not supported by all cpu backends.
+    ///
+    /// ```
+    /// use styx_processor::hooks::{CompareKind, CoreHandle, StyxHook};
+    /// use styx_errors::UnknownError;
+    ///
+    /// fn my_compare_hook(
+    ///     mut proc: CoreHandle,
+    ///     kind: CompareKind,
+    ///     size: u32,
+    ///     left: u128,
+    ///     right: u128,
+    /// ) -> Result<(), UnknownError> {
+    ///     // do hook things
+    ///     let pc = proc.pc()?;
+    ///     println!("pc: 0x{pc:X} {left:X} {kind:?} {right:X}");
+    ///     Ok(())
+    /// }
+    ///
+    /// let compare_hook = StyxHook::compare(.., my_compare_hook);
+    /// ```
+    pub fn compare(range: impl Into<AddressRange>, hook: impl CompareHook + 'static) -> Self {
+        Self::Compare(range.into(), Box::new(hook))
+    }
 }
@@ -27,12 +27,12 @@
 //! 3. `running` - [`executor::Executor::begin()`]
 //!   1. `emulate` - [`cpu::CpuBackend::execute()`]
 //!   2. `post stride processing` - [`executor::ExecutorImpl::post_stride_processing()`]
-//!      - 1. `interrupt` - [`event_controller::EventControllerImpl::next()`]
-//!      - 2. `tick`
-//!      - [`event_controller::Peripheral::tick()`]
-//!      - [`plugins::Plugin::tick()`]
+//!      - 1. `tick` - [`event_controller::Peripheral::tick()`]
+//!      - 2. `interrupt` - [`event_controller::EventControllerImpl::next()`]
+//!      - 3. `tick` - [`plugins::Plugin::tick()`]
 //!   3. `valid_emulation_conditions` - [`executor::ExecutorImpl::valid_emulation_conditions()`]
 //!
+pub mod calls;
 pub mod core;
 pub mod cpu;
 pub mod event_controller;
@@ -42,3 +42,4 @@ pub mod memory;
 pub mod plugins;
 pub mod processor;
 pub mod runtime;
+pub mod snapshot;
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Tracking of writes to memory that cpu backends have translated code from.
+use rustc_hash::FxHashSet;
+
+/// Granularity of [`CodeCacheTracker`] pages.
+pub const CODE_PAGE_SIZE: u64 = 0x1000;
+
+/// Lets cpu backends that cache translated code find out when that code changes.
+///
+/// Backends [`watch`](CodeCacheTracker::watch) the physical pages they translated code from.
+/// Writes through the [`Mmu`](super::Mmu) to a watched page mark it dirty, and the backend drops
+/// its translations from dirty pages after [`take_dirty`](CodeCacheTracker::take_dirty). Changes
+/// the tracker can't see page by page, like restoring a memory context, request a full
+/// [`flush`](CodeCacheTracker::flush).
+///
+/// Writes that bypass the [`Mmu`](super::Mmu), e.g. through the raw data of a
+/// [`MemoryRegion`](super::memory_region::MemoryRegion), are not tracked.
+#[derive(Debug, Default)]
+pub struct CodeCacheTracker {
+    /// Watched page numbers, see [`CODE_PAGE_SIZE`].
+    watched: FxHashSet<u64>,
+    /// Watched pages written since the last [`CodeCacheTracker::take_dirty()`].
+    dirty: Vec<u64>,
+    /// Was a flush requested since the last [`CodeCacheTracker::take_flush()`].
+    flush: bool,
+}
+
+impl CodeCacheTracker {
+    /// Page number containing `phys_addr`.
+    pub fn page(phys_addr: u64) -> u64 {
+        phys_addr / CODE_PAGE_SIZE
+    }
+
+    /// Start watching the page containing `phys_addr` for writes.
+    pub fn watch(&mut self, phys_addr: u64) {
+        self.watched.insert(Self::page(phys_addr));
+    }
+
+    /// Record a write of `len` bytes starting at `phys_addr`.
+    pub fn note_write(&mut self, phys_addr: u64, len: usize) {
+        if self.watched.is_empty() || len == 0 {
+            return;
+        }
+
+        let first = Self::page(phys_addr);
+        let last = Self::page(phys_addr.saturating_add(len as u64 - 1));
+        for page in first..=last {
+            // stop watching, the backend has to watch again after retranslating
+            if self.watched.remove(&page) {
+                self.dirty.push(page);
+            }
+        }
+    }
+
+    /// Invalidate all translated code.
+    pub fn flush(&mut self) {
+        self.watched.clear();
+        self.dirty.clear();
+        self.flush = true;
+    }
+
+    /// Returns true if a flush was requested since the last call.
+    pub fn take_flush(&mut self) -> bool {
+        std::mem::take(&mut self.flush)
+    }
+
+    /// Returns the page numbers written since the last call.
+    pub fn take_dirty(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_write() {
+        let mut tracker = CodeCacheTracker::default();
+        tracker.watch(0x1004);
+        tracker.watch(0x3000);
+
+        // unwatched page
+        tracker.note_write(0x2000, 4);
+        assert!(tracker.take_dirty().is_empty());
+
+        // spans the first watched page
+        tracker.note_write(0xFFE, 4);
+        assert_eq!(tracker.take_dirty(), vec![1]);
+
+        // page is no longer watched
+        tracker.note_write(0x1000, 4);
+        assert!(tracker.take_dirty().is_empty());
+
+        tracker.flush();
+        assert!(tracker.take_flush());
+        assert!(!tracker.take_flush());
+        tracker.note_write(0x3000, 4);
+        assert!(tracker.take_dirty().is_empty());
+    }
+}
@@ -13,6 +13,7 @@ use thiserror::Error;
 use zstd::{decode_all, encode_all};
 
 use super::UnmappedMemoryError;
+use crate::snapshot::{MemorySpanSnapshot, SnapshotError};
 
 #[derive(Debug, Error)]
 #[error("region not 0x{expected_alignment:X} aligned (base: 0x{base:X}, size: 0x{size:X})")]
@@ -564,6 +565,29 @@ impl MemoryRegion {
             Ok(())
         }
     }
+
+    /// Capture the contents of the [`MemoryRegion`] for a
+    /// [`MemorySnapshot`](crate::snapshot::MemorySnapshot).
+    ///
+    /// The CPU must be paused so the contents don't change while reading.
+    pub fn snapshot(&self) -> MemorySpanSnapshot {
+        // # Safety
+        // We only read the data inside of the [`UnsafeCell`], writers are paused.
+        let data: &Vec<u8> = unsafe { &*self.data.with(|ptr| ptr) };
+        MemorySpanSnapshot::capture(self.base, data)
+    }
+
+    /// Overwrite the contents of the [`MemoryRegion`] with a snapshot taken by
+    /// [`MemoryRegion::snapshot()`].
+    ///
+    /// The CPU must be paused before calling.
+    pub fn restore_snapshot(&self, snapshot: &MemorySpanSnapshot) -> Result<(), SnapshotError> {
+        // # Safety
+        // The unsafe part about this is that we are accessing
+        // data inside of an [`UnsafeCell`], which is intentional.
+        let data: &mut Vec<u8> = unsafe { &mut *self.data.with_mut(|ptr| ptr) };
+        snapshot.restore_into(self.base, data)
+    }
 }
 
 #[cfg(test)]
@@ -855,4 +879,19 @@ mod tests {
             panic!("Read size {size} @  {base:#08X} failed!");
         }
     }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let region = MemoryRegion::new(0x1000, 0x3000, Perms::all()).unwrap();
+        region.write_data(0x1010, &[1, 2, 3, 4]).unwrap();
+
+        let snapshot = region.snapshot();
+
+        region.write_data(0x1010, &[0xff; 4]).unwrap();
+        region.write_data(0x3000, &[0xff; 4]).unwrap();
+        region.restore_snapshot(&snapshot).unwrap();
+
+        assert_eq!(region.read_data(0x1010, 4).unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(region.read_data(0x3000, 4).unwrap(), vec![0; 4]);
+    }
 }
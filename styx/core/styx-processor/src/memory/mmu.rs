@@ -10,7 +10,11 @@ use super::{
 use crate::{
     cpu::CpuBackend,
     event_controller::ExceptionNumber,
-    memory::{physical::address_space::MemoryImpl, tlb::TlbProcessor, TlbTranslateResult},
+    memory::{
+        code_cache::CodeCacheTracker, physical::address_space::MemoryImpl, tlb::TlbProcessor,
+        TlbTranslateResult,
+    },
+    snapshot::MemorySnapshot,
 };
 use std::ops::Range;
 use styx_errors::UnknownError;
@@ -49,10 +53,14 @@ pub enum MemoryType {
 ///
 /// For a processor ready default use [`Mmu::default_region_store()`].
 ///
-/// Access physical memory using [`Mmu::memory()`].
+/// Inspect physical memory using [`Mmu::memory()`], all writes go through the [`Mmu`].
+///
+/// Writes through the [`Mmu`] are reported to its [`CodeCacheTracker`] so cpu backends caching
+/// translated code can invalidate the written ranges, see [`Mmu::code_cache()`].
 pub struct Mmu {
     pub tlb: Box<dyn TlbImpl>,
-    pub memory: MemoryBackend,
+    memory: MemoryBackend,
+    code_cache: CodeCacheTracker,
 }
 
 impl Default for Mmu {
@@ -61,6 +69,7 @@ impl Default for Mmu {
         Mmu {
             tlb: Box::new(DummyTlb),
             memory: MemoryBackend::new(PhysicalMemoryVariant::FlatMemory),
+            code_cache: CodeCacheTracker::default(),
         }
     }
 }
@@ -76,6 +85,7 @@ impl Mmu {
         Ok(Self {
             tlb,
             memory: MemoryBackend::new(memory),
+            code_cache: CodeCacheTracker::default(),
         })
     }
 
@@ -84,6 +94,7 @@ impl Mmu {
         Self {
             tlb,
             memory: MemoryBackend::default(),
+            code_cache: CodeCacheTracker::default(),
         }
     }
 
@@ -93,13 +104,21 @@ impl Mmu {
         Mmu {
             tlb: Box::new(DummyTlb),
             memory: MemoryBackend::new(PhysicalMemoryVariant::RegionStore),
+            code_cache: CodeCacheTracker::default(),
         }
     }
 
-    /// Returns a mutable reference to the physical memory backend.  Useful
-    /// if you want to read/write memory without involving the Tlb.
-    pub fn memory(&mut self) -> &mut MemoryBackend {
-        &mut self.memory
+    /// Returns a reference to the physical memory backend.  Useful
+    /// if you want to inspect memory without involving the Tlb.
+    ///
+    /// Use the physical write methods to write without involving the Tlb.
+    pub fn memory(&self) -> &MemoryBackend {
+        &self.memory
+    }
+
+    /// Tracker of writes to memory containing translated code.
+    pub fn code_cache(&mut self) -> &mut CodeCacheTracker {
+        &mut self.code_cache
     }
 
     /// Returns the range made up of the min and max addresses supported
@@ -122,15 +141,40 @@ impl Mmu {
 
     /// Adds a pre-populated MemoryRegion to emulator memory map.
     pub fn add_memory_region(&mut self, region: MemoryRegion) -> Result<(), AddRegionError> {
+        self.code_cache.flush();
         self.memory.add_region(region)
     }
 
+    /// Save the physical memory state, overwriting any previously saved state.
+    ///
+    /// Notes: Not all backends support saving memory.
+    pub fn context_save(&mut self) -> Result<(), UnknownError> {
+        self.memory.context_save()
+    }
+
+    /// Restore the physical memory state saved by [`Mmu::context_save()`].
+    pub fn context_restore(&mut self) -> Result<(), UnknownError> {
+        self.code_cache.flush();
+        self.memory.context_restore()
+    }
+
+    /// Overwrite the physical memory contents from a [`MemorySnapshot`].
+    ///
+    /// Notes: Not all backends support snapshots.
+    pub fn restore_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), UnknownError> {
+        self.code_cache.flush();
+        self.memory.restore_snapshot(snapshot)
+    }
+
     /// Returns an iterator over the regions contained in the underlying physical memory backend.
     ///
     /// Notes: The return type is an `Option` because iterating over memory regions is not always
     /// a definable operation.
+    ///
+    /// Writes through the regions can't be tracked so this flushes the [`Mmu::code_cache()`].
     pub fn regions(&mut self) -> Option<impl Iterator<Item = MemoryRegionView>> {
-        let rtn: Option<Box<dyn Iterator<Item = MemoryRegionView>>> = match self.memory() {
+        self.code_cache.flush();
+        let rtn: Option<Box<dyn Iterator<Item = MemoryRegionView>>> = match &mut self.memory {
             MemoryBackend::HarvardFlatMemory(_) => None,
             MemoryBackend::FlatMemory(flat_memory) => Some(Box::new(
                 [flat_memory].into_iter().map(MemoryRegionView::from),
@@ -138,6 +182,8 @@ impl Mmu {
             MemoryBackend::RegionStore(region_store) => Some(Box::new(
                 region_store.regions.iter_mut().map(MemoryRegionView::from),
             )),
+            #[cfg(target_os = "linux")]
+            MemoryBackend::CowRegionStore(cow_store) => Some(Box::new(cow_store.regions_mut())),
         };
         rtn
     }
@@ -158,6 +204,7 @@ impl Mmu {
 
     /// Write an array of bytes to data memory, the address will be interpreted as a physical address.
     pub fn write_data(&mut self, phys_addr: u64, bytes: &[u8]) -> Result<(), MemoryOperationError> {
+        self.code_cache.note_write(phys_addr, bytes.len());
         self.memory.write_data(phys_addr, bytes)
     }
 
@@ -172,6 +219,7 @@ impl Mmu {
 
     /// Write an array of bytes to code memory, the address will be interpreted as a physical address.
     pub fn write_code(&mut self, phys_addr: u64, bytes: &[u8]) -> Result<(), MemoryOperationError> {
+        self.code_cache.note_write(phys_addr, bytes.len());
         self.memory.write_code(phys_addr, bytes)
     }
 
@@ -194,6 +242,7 @@ impl Mmu {
         cpu: &mut dyn CpuBackend,
     ) -> Result<(), MmuOpError> {
         let phys_addr = self.translate_va(addr, MemoryOperation::Write, MemoryType::Data, cpu)?;
+        self.code_cache.note_write(phys_addr, bytes.len());
         self.memory.write_data(phys_addr, bytes).map_err(Into::into)
     }
 
@@ -217,6 +266,7 @@ impl Mmu {
         cpu: &mut dyn CpuBackend,
     ) -> Result<(), MmuOpError> {
         let phys_addr = self.translate_va(addr, MemoryOperation::Write, MemoryType::Code, cpu)?;
+        self.code_cache.note_write(phys_addr, bytes.len());
         self.memory.write_code(phys_addr, bytes).map_err(Into::into)
     }
 
@@ -239,6 +289,7 @@ impl Mmu {
         phys_addr: u64,
         bytes: &[u8],
     ) -> Result<(), MemoryOperationError> {
+        self.code_cache.note_write(phys_addr, bytes.len());
         self.memory.unchecked_write_data(phys_addr, bytes)
     }
 
@@ -257,6 +308,7 @@ impl Mmu {
         phys_addr: u64,
         bytes: &[u8],
     ) -> Result<(), MemoryOperationError> {
+        self.code_cache.note_write(phys_addr, bytes.len());
         self.memory.unchecked_write_code(phys_addr, bytes)
     }
 
@@ -511,6 +563,7 @@ impl Writable for DataMemoryOp<'_> {
     type Error = MmuOpError;
 
     fn write_raw(&mut self, phys_addr: u64, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.code_cache.note_write(phys_addr, bytes.len());
         self.0
             .memory
             .write_data(phys_addr, bytes)
@@ -533,6 +586,7 @@ impl Writable for CodeMemoryOp<'_> {
     type Error = MmuOpError;
 
     fn write_raw(&mut self, phys_addr: u64, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.code_cache.note_write(phys_addr, bytes.len());
         self.0
             .memory
             .write_code(phys_addr, bytes)
@@ -572,6 +626,7 @@ impl Writable for VirtualCodeMemoryOp<'_> {
             self.cpu,
         )?;
 
+        self.mmu.code_cache.note_write(phys_addr, bytes.len());
         self.mmu
             .memory
             .write_code(phys_addr, bytes)
@@ -611,6 +666,7 @@ impl Writable for VirtualDataMemoryOp<'_> {
             self.cpu,
         )?;
 
+        self.mmu.code_cache.note_write(phys_addr, bytes.len());
         self.mmu
             .memory
             .write_data(phys_addr, bytes)
@@ -633,6 +689,7 @@ impl Writable for SudoDataMemoryOp<'_> {
     type Error = MmuOpError;
 
     fn write_raw(&mut self, phys_addr: u64, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.code_cache.note_write(phys_addr, bytes.len());
         self.0
             .memory
             .unchecked_write_data(phys_addr, bytes)
@@ -655,6 +712,7 @@ impl Writable for SudoCodeMemoryOp<'_> {
     type Error = MmuOpError;
 
     fn write_raw(&mut self, phys_addr: u64, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.code_cache.note_write(phys_addr, bytes.len());
         self.0
             .memory
             .unchecked_write_code(phys_addr, bytes)
@@ -683,5 +741,21 @@ mod tests {
         assert_eq!(data, 0xdeadbeef)
     }
 
+    /// Writes only dirty the written code pages, reading memory invalidates nothing.
+    #[test]
+    fn test_code_cache_writes() {
+        let mut mmu = Mmu::default();
+        mmu.code_cache().watch(0x1000);
+        mmu.code_cache().watch(0x3000);
+
+        let _ = mmu.memory().min_address(None);
+        mmu.data().write(0x1FFE).le().u32(0).unwrap();
+        assert!(!mmu.code_cache().take_flush());
+        assert_eq!(mmu.code_cache().take_dirty(), vec![1]);
+
+        mmu.sudo_write_code(0x3000, &[0]).unwrap();
+        assert_eq!(mmu.code_cache().take_dirty(), vec![3]);
+    }
+
     // todo add tests for virtual addressing once we make a TLB
 }
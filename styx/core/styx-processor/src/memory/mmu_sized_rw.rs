@@ -86,13 +86,13 @@ macro_rules! mmu_sized_rw {
 
             pub fn [<write_ $type _le_phys_data>](&mut self, addr: u64, val: $type) -> Result<(), UnknownError> {
                 let data = $type::to_le_bytes(val);
-                self.memory.write_data(addr, &data)?;
+                self.write_data(addr, &data)?;
                 Ok(())
             }
 
             pub fn [<write_ $type _be_phys_data>](&mut self, addr: u64, val: $type) -> Result<(), UnknownError> {
                 let data = $type::to_be_bytes(val);
-                self.memory.write_data(addr, &data)?;
+                self.write_data(addr, &data)?;
                 Ok(())
             }
 
@@ -100,7 +100,7 @@ macro_rules! mmu_sized_rw {
                 let mut proc = TlbProcessor::new(&mut self.memory, cpu);
                 let data = $type::to_le_bytes(val);
                 let phys_addr = self.tlb.translate_va(addr, MemoryOperation::Write, MemoryType::Data, &mut proc)?;
-                self.memory.write_data(phys_addr, &data)?;
+                self.write_data(phys_addr, &data)?;
                 Ok(())
             }
 
@@ -108,19 +108,19 @@ macro_rules! mmu_sized_rw {
                 let mut proc = TlbProcessor::new(&mut self.memory, cpu);
                 let data = $type::to_be_bytes(val);
                 let phys_addr = self.tlb.translate_va(addr, MemoryOperation::Write, MemoryType::Data, &mut proc)?;
-                self.memory.write_data(phys_addr, &data)?;
+                self.write_data(phys_addr, &data)?;
                 Ok(())
             }
 
             pub fn [<write_ $type _le_phys_code>](&mut self, addr: u64, val: $type) -> Result<(), UnknownError> {
                 let data = $type::to_le_bytes(val);
-                self.memory.write_code(addr, &data)?;
+                self.write_code(addr, &data)?;
                 Ok(())
             }
 
             pub fn [<write_ $type _be_phys_code>](&mut self, addr: u64, val: $type) -> Result<(), UnknownError> {
                 let data = $type::to_be_bytes(val);
-                self.memory.write_code(addr, &data)?;
+                self.write_code(addr, &data)?;
                 Ok(())
             }
 
@@ -128,7 +128,7 @@ macro_rules! mmu_sized_rw {
                 let mut proc = TlbProcessor::new(&mut self.memory, cpu);
                 let data = $type::to_le_bytes(val);
                 let phys_addr = self.tlb.translate_va(addr, MemoryOperation::Write, MemoryType::Code, &mut proc)?;
-                self.memory.write_code(phys_addr, &data)?;
+                self.write_code(phys_addr, &data)?;
                 Ok(())
             }
 
@@ -136,7 +136,7 @@ macro_rules! mmu_sized_rw {
                 let mut proc = TlbProcessor::new(&mut self.memory, cpu);
                 let data = $type::to_be_bytes(val);
                 let phys_addr = self.tlb.translate_va(addr, MemoryOperation::Write, MemoryType::Code, &mut proc)?;
-                self.memory.write_code(phys_addr, &data)?;
+                self.write_code(phys_addr, &data)?;
                 Ok(())
             }
         }
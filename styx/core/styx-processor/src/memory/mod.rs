@@ -7,6 +7,7 @@
 use bitflags::bitflags;
 use derive_more::Display;
 
+mod code_cache;
 pub mod helpers;
 pub mod memory_region;
 mod mmu;
@@ -14,6 +15,7 @@ mod mmu_sized_rw;
 pub mod physical;
 mod tlb;
 
+pub use code_cache::{CodeCacheTracker, CODE_PAGE_SIZE};
 pub use memory_region::{MemoryRegionData, MemoryRegionSize};
 pub use mmu::{
     CodeMemoryOp, DataMemoryOp, MemoryType, Mmu, MmuOpError, SudoCodeMemoryOp, SudoDataMemoryOp,
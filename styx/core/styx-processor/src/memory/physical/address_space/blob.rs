@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! TODO: add some kind of dirty page tracking thing so we can reasonably support saving/restoring memory.
+use styx_errors::UnknownError;
+
 use crate::memory::{
     memory_region::MemoryRegionView, physical::UnmappedMemoryError, FromConfigError,
     MemoryOperationError, MemoryPermissions, MemoryRegionData, MemoryRegionSize,
 };
+use crate::snapshot::{MemorySnapshot, MemorySpanSnapshot};
 
 use super::{FromYaml, MemoryImpl};
 
@@ -134,4 +137,16 @@ impl MemoryImpl for FlatMemory {
         // no distinction between code and data here
         self.unchecked_write_code(addr, bytes)
     }
+
+    fn snapshot(&self) -> Result<MemorySnapshot, UnknownError> {
+        Ok(MemorySnapshot {
+            spans: vec![MemorySpanSnapshot::capture(self.base, &self.bytes)],
+        })
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), UnknownError> {
+        snapshot.expect_spans(1)?;
+        snapshot.spans[0].restore_into(self.base, &mut self.bytes)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,442 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Region based memory backed by copy-on-write mappings.
+use std::cmp::{max, min};
+
+use log::debug;
+use smallvec::SmallVec;
+use styx_cow::{Cow, StyxCowError};
+use styx_errors::UnknownError;
+
+use crate::memory::{
+    memory_region::{MemoryRegion, MemoryRegionView},
+    AddRegionError, MemoryOperationError, MemoryPermissions, MemoryRegionData, MemoryRegionSize,
+    UnmappedMemoryError,
+};
+use crate::snapshot::{MemorySnapshot, MemorySpanSnapshot, SNAPSHOT_PAGE_SIZE};
+
+use super::{FromYaml, MemoryImpl};
+
+/// A single region of a [`CowRegionStore`].
+struct CowRegion {
+    base: u64,
+    perms: MemoryPermissions,
+    /// Shared mapping holding the contents as of the last [`MemoryImpl::context_save()`].
+    saved: Cow,
+    /// Private copy-on-write mapping of `saved`, all memory operations go here.
+    working: Cow,
+}
+
+impl MemoryRegionSize for CowRegion {
+    fn base(&self) -> u64 {
+        self.base
+    }
+
+    fn size(&self) -> u64 {
+        self.working.len() as u64
+    }
+}
+
+impl<'a> From<&'a mut CowRegion> for MemoryRegionView<'a> {
+    fn from(value: &'a mut CowRegion) -> Self {
+        MemoryRegionView {
+            base: value.base,
+            perms: value.perms,
+            data: value.working.get_data_mut(),
+        }
+    }
+}
+
+impl CowRegion {
+    fn new(region: &MemoryRegion) -> Result<Self, StyxCowError> {
+        let mut saved = Cow::new(region.size() as usize)?;
+        // regions without permissions have no backing data
+        let data = region.data();
+        if !data.is_empty() {
+            saved.get_data_mut().copy_from_slice(data);
+        }
+        let working = saved.try_clone()?;
+
+        Ok(Self {
+            base: region.base(),
+            perms: region.perms(),
+            saved,
+            working,
+        })
+    }
+
+    /// Last address in the region, inclusive.
+    fn last(&self) -> u64 {
+        self.base + (self.size() - 1)
+    }
+
+    /// Commit the working copy to the saved mapping, then drop the private pages.
+    fn save(&mut self) -> Result<(), StyxCowError> {
+        let saved_pages = self.saved.get_data_mut().chunks_mut(SNAPSHOT_PAGE_SIZE);
+        let working_pages = self.working.get_data().chunks(SNAPSHOT_PAGE_SIZE);
+        for (saved, working) in saved_pages.zip(working_pages) {
+            // untouched working pages are still backed by the saved mapping
+            if saved != working {
+                saved.copy_from_slice(working);
+            }
+        }
+
+        self.working.reset()
+    }
+}
+
+/// Portion of a memory operation that falls into a single region.
+struct Chunk {
+    /// Index into [`CowRegionStore::regions`].
+    region: usize,
+    /// Offset of the chunk into the region.
+    offset: usize,
+    /// Offset of the chunk into the operation's buffer.
+    buf_offset: usize,
+    len: usize,
+}
+
+/// A region based memory implementation with cheap save/restore, memory is represented by zero or
+/// more unique, non-overlapping memory regions like the
+/// [`RegionStore`](super::RegionStore).
+///
+/// Each region is a private copy-on-write mapping of a memory file holding the saved contents.
+/// The kernel only copies a page on its first write, so [`MemoryImpl::context_restore()`] just has
+/// to throw away the dirty pages instead of copying the whole region. This makes it a good fit for
+/// fuzzing, where the same state is restored after every run and most of memory is never touched.
+/// Dirty pages are tracked for writes made through the raw region data as well, e.g. by the
+/// unicorn backend.
+///
+/// [`MemoryImpl::context_save()`] is more expensive, every page is compared with the saved
+/// contents. Aliased regions are not supported, each region gets its own backing memory.
+///
+/// Only available on linux.
+#[derive(Default)]
+pub struct CowRegionStore {
+    regions: Vec<CowRegion>,
+}
+
+impl FromYaml for CowRegionStore {
+    fn from_config(
+        config: Vec<crate::memory::physical::MemoryRegionDescriptor>,
+    ) -> Result<Self, crate::memory::FromConfigError>
+    where
+        Self: Sized,
+    {
+        let mut mem = CowRegionStore::default();
+
+        for region in config {
+            mem.add_region(MemoryRegion::new(
+                region.base,
+                region.size,
+                region.perms.into(),
+            )?)?;
+        }
+
+        Ok(mem)
+    }
+}
+
+impl CowRegionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Iterate over views of each memory region.
+    pub(in crate::memory) fn regions_mut(&mut self) -> impl Iterator<Item = MemoryRegionView> {
+        self.regions.iter_mut().map(MemoryRegionView::from)
+    }
+
+    /// Splits the range `addr..addr + len` into the regions it touches.
+    ///
+    /// Errors if any of the range is unmapped.
+    fn chunks(&self, addr: u64, len: usize) -> Result<SmallVec<[Chunk; 2]>, MemoryOperationError> {
+        let mut chunks = SmallVec::new();
+        // first region that could contain `addr`, regions are sorted
+        let mut index = self.regions.partition_point(|r| r.last() < addr);
+        let mut current = addr;
+        let mut done = 0;
+
+        while done < len {
+            let region = match self.regions.get(index) {
+                Some(region) if region.base <= current => region,
+                _ if done == 0 => {
+                    return Err(UnmappedMemoryError::UnmappedStart(addr).into());
+                }
+                _ => return Err(UnmappedMemoryError::GoesUnmapped(done as u64).into()),
+            };
+
+            let offset = (current - region.base) as usize;
+            let chunk_len = min(len - done, region.working.len() - offset);
+            chunks.push(Chunk {
+                region: index,
+                offset,
+                buf_offset: done,
+                len: chunk_len,
+            });
+
+            done += chunk_len;
+            index += 1;
+            current = match current.checked_add(chunk_len as u64) {
+                Some(next) => next,
+                // ran off the end of the address space
+                None if done < len => {
+                    return Err(UnmappedMemoryError::GoesUnmapped(done as u64).into());
+                }
+                None => break,
+            };
+        }
+
+        Ok(chunks)
+    }
+
+    /// Checks that each region of the operation has permissions `need`.
+    fn check_perms(
+        &self,
+        chunks: &[Chunk],
+        need: MemoryPermissions,
+    ) -> Result<(), MemoryOperationError> {
+        for chunk in chunks {
+            let have = self.regions[chunk.region].perms;
+            if !have.contains(need) {
+                return Err(MemoryOperationError::InvalidRegionPermissions { have, need });
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads memory into `bytes`, each region must have permissions `need`.
+    fn read_memory(
+        &self,
+        addr: u64,
+        bytes: &mut [u8],
+        need: MemoryPermissions,
+    ) -> Result<(), MemoryOperationError> {
+        let chunks = self.chunks(addr, bytes.len())?;
+        self.check_perms(&chunks, need)?;
+
+        for chunk in chunks {
+            let data = self.regions[chunk.region].working.get_data();
+            bytes[chunk.buf_offset..chunk.buf_offset + chunk.len]
+                .copy_from_slice(&data[chunk.offset..chunk.offset + chunk.len]);
+        }
+        Ok(())
+    }
+
+    /// Writes `bytes` into memory, each region must have permissions `need`.
+    fn write_memory(
+        &mut self,
+        addr: u64,
+        bytes: &[u8],
+        need: MemoryPermissions,
+    ) -> Result<(), MemoryOperationError> {
+        let chunks = self.chunks(addr, bytes.len())?;
+        self.check_perms(&chunks, need)?;
+
+        for chunk in chunks {
+            let data = self.regions[chunk.region].working.get_data_mut();
+            data[chunk.offset..chunk.offset + chunk.len]
+                .copy_from_slice(&bytes[chunk.buf_offset..chunk.buf_offset + chunk.len]);
+        }
+        Ok(())
+    }
+}
+
+impl MemoryImpl for CowRegionStore {
+    fn context_save(&mut self) -> Result<(), UnknownError> {
+        for region in self.regions.iter_mut() {
+            region.save()?;
+        }
+        Ok(())
+    }
+
+    fn context_restore(&mut self) -> Result<(), UnknownError> {
+        for region in self.regions.iter_mut() {
+            region.working.reset()?;
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Result<MemorySnapshot, UnknownError> {
+        Ok(MemorySnapshot {
+            spans: self
+                .regions
+                .iter()
+                .map(|r| MemorySpanSnapshot::capture(r.base, r.working.get_data()))
+                .collect(),
+        })
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), UnknownError> {
+        snapshot.expect_spans(self.regions.len())?;
+        for (region, span) in self.regions.iter_mut().zip(snapshot.spans.iter()) {
+            span.restore_into(region.base, region.working.get_data_mut())?;
+        }
+        Ok(())
+    }
+
+    fn min_address(&self, _space: Option<crate::memory::physical::Space>) -> u64 {
+        self.regions.first().map(|r| r.base).unwrap_or(u64::MAX)
+    }
+
+    fn max_address(&self, _space: Option<crate::memory::physical::Space>) -> u64 {
+        self.regions.last().map(|r| r.last()).unwrap_or(u64::MIN)
+    }
+
+    fn add_region(&mut self, region: MemoryRegion) -> Result<(), AddRegionError> {
+        debug!(
+            "adding cow region with base: 0x{:X} size: 0x{:X}",
+            region.base(),
+            region.size()
+        );
+        let base = region.base();
+        let size = region.size();
+
+        // check for overlap, ignoring anything that would overflow like the region store
+        if let Some(end) = base.checked_add(size) {
+            let overlaps = self.regions.iter().any(|r| {
+                !(max(base, r.base)..min(end, r.base.saturating_add(r.size()))).is_empty()
+            });
+            if overlaps {
+                return Err(AddRegionError::OverlappingRegion(base, size));
+            }
+        }
+
+        let region = CowRegion::new(&region).map_err(|_| AddRegionError::SizeTooLarge(size))?;
+        self.regions.push(region);
+        self.regions.sort_by_key(|r| r.base);
+
+        Ok(())
+    }
+
+    fn read_code(&self, addr: u64, bytes: &mut [u8]) -> Result<(), MemoryOperationError> {
+        self.read_memory(addr, bytes, MemoryPermissions::READ)
+    }
+
+    fn read_data(&self, addr: u64, bytes: &mut [u8]) -> Result<(), MemoryOperationError> {
+        self.read_memory(addr, bytes, MemoryPermissions::READ)
+    }
+
+    fn write_code(&mut self, addr: u64, bytes: &[u8]) -> Result<(), MemoryOperationError> {
+        self.write_memory(addr, bytes, MemoryPermissions::WRITE)
+    }
+
+    fn write_data(&mut self, addr: u64, bytes: &[u8]) -> Result<(), MemoryOperationError> {
+        self.write_memory(addr, bytes, MemoryPermissions::WRITE)
+    }
+
+    fn unchecked_read_code(&self, addr: u64, bytes: &mut [u8]) -> Result<(), MemoryOperationError> {
+        self.read_memory(addr, bytes, MemoryPermissions::empty())
+    }
+
+    fn unchecked_read_data(&self, addr: u64, bytes: &mut [u8]) -> Result<(), MemoryOperationError> {
+        self.read_memory(addr, bytes, MemoryPermissions::empty())
+    }
+
+    fn unchecked_write_code(
+        &mut self,
+        addr: u64,
+        bytes: &[u8],
+    ) -> Result<(), MemoryOperationError> {
+        self.write_memory(addr, bytes, MemoryPermissions::empty())
+    }
+
+    fn unchecked_write_data(
+        &mut self,
+        addr: u64,
+        bytes: &[u8],
+    ) -> Result<(), MemoryOperationError> {
+        self.write_memory(addr, bytes, MemoryPermissions::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> CowRegionStore {
+        let mut store = CowRegionStore::new();
+        store
+            .add_region(MemoryRegion::new(0x0, 0x2000, MemoryPermissions::all()).unwrap())
+            .unwrap();
+        store
+            .add_region(MemoryRegion::new(0x2000, 0x1000, MemoryPermissions::READ).unwrap())
+            .unwrap();
+        store
+            .add_region(MemoryRegion::new(0x8000, 0x1000, MemoryPermissions::all()).unwrap())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_read_write_across_regions() {
+        let mut store = store();
+
+        store.unchecked_write_data(0x1ffe, &[1, 2, 3, 4]).unwrap();
+        let mut buf = [0u8; 4];
+        store.read_data(0x1ffe, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+
+        // second region is read only
+        assert!(matches!(
+            store.write_data(0x1ffe, &[0; 4]),
+            Err(MemoryOperationError::InvalidRegionPermissions { .. })
+        ));
+        // failed write is not partially applied
+        store.read_data(0x1ffe, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_unmapped() {
+        let mut store = store();
+
+        assert!(matches!(
+            store.write_data(0x4000, &[0; 4]),
+            Err(MemoryOperationError::UnmappedMemory(
+                UnmappedMemoryError::UnmappedStart(0x4000)
+            ))
+        ));
+        assert!(matches!(
+            store.unchecked_write_data(0x2ff0, &[0; 0x20]),
+            Err(MemoryOperationError::UnmappedMemory(
+                UnmappedMemoryError::GoesUnmapped(0x10)
+            ))
+        ));
+        assert!(matches!(
+            store.add_region(MemoryRegion::new(0x8800, 0x1000, MemoryPermissions::all()).unwrap()),
+            Err(AddRegionError::OverlappingRegion(0x8800, 0x1000))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_save_restore() {
+        let mut store = store();
+        store.write_data(0x10, &[0xAA; 4]).unwrap();
+        store.context_save().unwrap();
+
+        store.write_data(0x10, &[0xBB; 4]).unwrap();
+        store.write_data(0x8000, &[0xCC; 4]).unwrap();
+        // writes through the raw region data are tracked too
+        for region in store.regions_mut() {
+            region.data[0x100] = 0xDD;
+        }
+        store.context_restore().unwrap();
+
+        let mut buf = [0u8; 4];
+        store.read_data(0x10, &mut buf).unwrap();
+        assert_eq!(buf, [0xAA; 4]);
+        store.read_data(0x8000, &mut buf).unwrap();
+        assert_eq!(buf, [0; 4]);
+        store.read_data(0x100, &mut buf).unwrap();
+        assert_eq!(buf, [0; 4]);
+
+        // restoring again goes back to the same saved state
+        store.write_data(0x10, &[0xEE; 4]).unwrap();
+        store.context_restore().unwrap();
+        store.read_data(0x10, &mut buf).unwrap();
+        assert_eq!(buf, [0xAA; 4]);
+    }
+}
@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! TODO: add some kind of dirty page tracking thing so we can reasonably support saving/restoring memory.
+use styx_errors::UnknownError;
+
 use crate::memory::{
     physical::{MemoryRegionDescriptor, Space, UnmappedMemoryError},
     FromConfigError, MemoryOperationError,
 };
+use crate::snapshot::{MemorySnapshot, MemorySpanSnapshot};
 
 use super::{FromYaml, MemoryImpl};
 
@@ -164,4 +167,20 @@ impl MemoryImpl for HarvardStore {
         self_slice.copy_from_slice(bytes);
         Ok(())
     }
+
+    fn snapshot(&self) -> Result<MemorySnapshot, UnknownError> {
+        Ok(MemorySnapshot {
+            spans: vec![
+                MemorySpanSnapshot::capture(self.code_base, &self.code_mem),
+                MemorySpanSnapshot::capture(self.data_base, &self.data_mem),
+            ],
+        })
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), UnknownError> {
+        snapshot.expect_spans(2)?;
+        snapshot.spans[0].restore_into(self.code_base, &mut self.code_mem)?;
+        snapshot.spans[1].restore_into(self.data_base, &mut self.data_mem)?;
+        Ok(())
+    }
 }
@@ -1,15 +1,20 @@
 // SPDX-License-Identifier: BSD-2-Clause
 mod blob;
+#[cfg(target_os = "linux")]
+mod cow;
 mod harvard;
 mod region;
 
 pub use blob::FlatMemory;
+#[cfg(target_os = "linux")]
+pub use cow::CowRegionStore;
 use enum_dispatch::enum_dispatch;
 pub use harvard::HarvardStore;
 pub use region::RegionStore;
 use styx_errors::{anyhow::anyhow, UnknownError};
 
 use crate::memory::memory_region::MemoryRegion;
+use crate::snapshot::MemorySnapshot;
 
 use super::{AddRegionError, FromConfigError, MemoryOperationError, MemoryRegionDescriptor, Space};
 
@@ -104,4 +109,19 @@ pub trait MemoryImpl {
             "memory implementation doesn't support save/restore"
         ))
     }
+
+    /// Capture the full memory contents into a [`MemorySnapshot`].
+    ///
+    /// Unlike [`MemoryImpl::context_save()`], this does not hold any state in the memory
+    /// implementation.
+    fn snapshot(&self) -> Result<MemorySnapshot, UnknownError> {
+        Err(anyhow!("memory implementation doesn't support snapshots"))
+    }
+
+    /// Overwrite the full memory contents from a [`MemorySnapshot`].
+    ///
+    /// This will error if the snapshot was taken from a memory with a different layout.
+    fn restore_snapshot(&mut self, _snapshot: &MemorySnapshot) -> Result<(), UnknownError> {
+        Err(anyhow!("memory implementation doesn't support snapshots"))
+    }
 }
@@ -4,6 +4,7 @@ use std::cmp::{max, min};
 use crate::memory::{
     memory_region::MemoryRegion, AddRegionError, MemoryOperationError, UnmappedMemoryError,
 };
+use crate::snapshot::MemorySnapshot;
 
 use super::{FromYaml, MemoryImpl};
 
@@ -295,6 +296,20 @@ impl MemoryImpl for RegionStore {
         Ok(())
     }
 
+    fn snapshot(&self) -> Result<MemorySnapshot, UnknownError> {
+        Ok(MemorySnapshot {
+            spans: self.regions.iter().map(|r| r.snapshot()).collect(),
+        })
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &MemorySnapshot) -> Result<(), UnknownError> {
+        snapshot.expect_spans(self.regions.len())?;
+        for (region, span) in self.regions.iter().zip(snapshot.spans.iter()) {
+            region.restore_snapshot(span)?;
+        }
+        Ok(())
+    }
+
     fn min_address(&self, _space: Option<crate::memory::physical::Space>) -> u64 {
         self.min_address
     }
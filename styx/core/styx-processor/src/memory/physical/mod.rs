@@ -3,6 +3,8 @@ pub(crate) mod address_space;
 
 use std::borrow::Cow;
 
+#[cfg(target_os = "linux")]
+use address_space::CowRegionStore;
 use address_space::{FlatMemory, HarvardStore, MemoryImpl, RegionStore};
 use enum_dispatch::enum_dispatch;
 use serde::Deserialize;
@@ -118,6 +120,10 @@ pub enum PhysicalMemoryVariant {
     HarvardFlatMemory,
     /// Separate Code + Data, flat array based memory, RW for data, RX for code
     RegionStore,
+    /// Same as [`PhysicalMemoryVariant::RegionStore`] but backed by copy-on-write mappings, only
+    /// pages dirtied since the last context save are reset on a context restore
+    #[cfg(target_os = "linux")]
+    CowRegionStore,
 }
 
 /// Physical memory storage.
@@ -128,6 +134,8 @@ pub enum MemoryBackend {
     FlatMemory(FlatMemory),
     HarvardFlatMemory(HarvardStore),
     RegionStore(RegionStore),
+    #[cfg(target_os = "linux")]
+    CowRegionStore(CowRegionStore),
 }
 
 impl Default for MemoryBackend {
@@ -144,6 +152,8 @@ impl MemoryBackend {
             }
             PhysicalMemoryVariant::FlatMemory => Self::FlatMemory(FlatMemory::default()),
             PhysicalMemoryVariant::RegionStore => Self::RegionStore(RegionStore::new()),
+            #[cfg(target_os = "linux")]
+            PhysicalMemoryVariant::CowRegionStore => Self::CowRegionStore(CowRegionStore::new()),
         }
     }
 
@@ -161,6 +171,10 @@ impl MemoryBackend {
             PhysicalMemoryVariant::RegionStore => {
                 Self::RegionStore(RegionStore::from_config(config).unwrap())
             }
+            #[cfg(target_os = "linux")]
+            PhysicalMemoryVariant::CowRegionStore => {
+                Self::CowRegionStore(CowRegionStore::from_config(config).unwrap())
+            }
         }
     }
 
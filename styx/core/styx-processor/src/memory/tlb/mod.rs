@@ -6,12 +6,13 @@ pub use dummy::DummyTlb;
 mod closure;
 pub use closure::FnTlb;
 
-use styx_errors::UnknownError;
+use styx_errors::{anyhow::anyhow, UnknownError};
 use thiserror::Error;
 
 use crate::memory::mmu::MemoryType;
 use crate::memory::physical::MemoryBackend;
 use crate::memory::MemoryOperation;
+use crate::snapshot::ComponentState;
 use crate::{cpu::CpuBackend, event_controller::ExceptionNumber};
 
 #[derive(Error, Debug)]
@@ -97,4 +98,19 @@ pub trait TlbImpl: Send {
     ///
     /// The implementation decides how to interpret the idx value.
     fn invalidate(&mut self, idx: usize) -> Result<(), UnknownError>;
+
+    /// Serialize the TLB entries and translation state for a [`Snapshot`](crate::snapshot::Snapshot).
+    ///
+    /// Stateless implementations can use the default, which saves nothing.
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::default())
+    }
+
+    /// Restore state saved by [`TlbImpl::save_state()`].
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        if !state.is_empty() {
+            return Err(anyhow!("tlb does not support restoring state"));
+        }
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! `ProcessorBuilder` logic and utilities
 use std::borrow::Cow;
+use std::sync::Arc;
 
 use log::{debug, info};
 use styx_cpu_type::Backend;
@@ -279,6 +280,7 @@ impl<'a> ProcessorBuilder<'a> {
             cpu,
             mmu,
             event_controller,
+            symbols: Default::default(),
         };
 
         autobots_load_up(
@@ -384,6 +386,10 @@ fn autobots_load_up(
         core.mmu.write_code(region.base(), &region_data)?;
     }
 
+    let symbols = memory_desc.take_symbols();
+    debug!("got {} symbols from loader", symbols.symbols().count());
+    core.symbols = Arc::new(symbols);
+
     for (register, value) in memory_desc.take_registers().into_iter() {
         // mildly sketchy but should mostly work out ok wrt converting
         match TryInto::<u32>::try_into(value) {
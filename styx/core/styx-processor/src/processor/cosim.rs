@@ -0,0 +1,640 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Run several [`Processor`]s as one system.
+//!
+//! Check out [`CoSimulation`] for detailed docs.
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use log::{debug, trace};
+use styx_cpu_type::TargetExitReason;
+use styx_errors::{anyhow::anyhow, UnknownError};
+
+use super::{EmulationReport, InstructionReport, Processor};
+use crate::{
+    event_controller::ExceptionNumber,
+    executor::{ExecutionConstraint, ExecutionConstraintConcrete, VirtualClock},
+    memory::{memory_region::MemoryRegion, AddRegionError, MemoryPermissions},
+};
+
+/// Handle to a processor added to a [`CoSimulation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CoreId(usize);
+
+impl CoreId {
+    /// Position of the processor in the [`CoSimulation`], in the order they were added.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// Interrupts raised through [`InterruptLine`]s, waiting for the next quantum boundary.
+type PendingInterrupts = Arc<Mutex<Vec<(CoreId, ExceptionNumber)>>>;
+
+/// Raises interrupts on one processor of a [`CoSimulation`] from anywhere else.
+///
+/// Get one with [`CoSimulation::interrupt_line()`]. Lines are [`Clone`] and [`Send`] so they can
+/// be moved into hooks and peripherals of the other processors, e.g. a memory write hook on a
+/// mailbox doorbell register.
+///
+/// Raised interrupts are latched on the target's event controller at the start of the next
+/// quantum, regardless of the order processors are stepped in.
+#[derive(Clone)]
+pub struct InterruptLine {
+    target: CoreId,
+    pending: PendingInterrupts,
+}
+
+impl InterruptLine {
+    /// Processor this line interrupts.
+    pub fn target(&self) -> CoreId {
+        self.target
+    }
+
+    /// Queue `irq` to be latched on the target processor.
+    pub fn raise(&self, irq: ExceptionNumber) {
+        trace!("raising interrupt {irq} on {:?}", self.target);
+        self.pending.lock().unwrap().push((self.target, irq));
+    }
+}
+
+impl Debug for InterruptLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterruptLine")
+            .field("target", &self.target)
+            .finish()
+    }
+}
+
+struct CoSimCore {
+    name: String,
+    processor: Processor,
+    /// Instructions executed by this processor every full quantum.
+    instructions_per_quantum: u64,
+    /// Totals over the current [`CoSimulation::run()`].
+    instructions: InstructionReport,
+    wall_time: std::time::Duration,
+    exit_reason: TargetExitReason,
+}
+
+/// Results of a [`CoSimulation::run()`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoSimulationReport {
+    /// Why the co-simulation stopped.
+    ///
+    /// This is the exit reason of [`CoSimulationReport::halted_core`] if a processor halted,
+    /// otherwise the met execution constraint.
+    pub exit_reason: TargetExitReason,
+    /// First processor that halted with a fatal exit reason or a stop request.
+    pub halted_core: Option<CoreId>,
+    /// Per processor reports, indexed by [`CoreId::index()`].
+    pub cores: Vec<EmulationReport>,
+    /// Reference instructions elapsed on the shared timeline.
+    pub elapsed: u64,
+}
+
+/// Steps multiple [`Processor`]s in lockstep with shared memory and cross-core interrupts.
+///
+/// Emulation advances in quanta on a shared timeline counted in reference instructions. Every
+/// quantum, each processor is run in turn (in the order they were added) for its share of the
+/// quantum, so no processor is ever more than one quantum ahead of another. Smaller quanta couple
+/// the processors more tightly at the cost of speed.
+///
+/// Processors can run at different rates to model asymmetric parts, see
+/// [`CoSimulation::add_processor_with_rate()`]. Each processor keeps its own
+/// [virtual clock](crate::executor::clock), executor, plugins and peripherals.
+///
+/// # Shared Memory
+///
+/// [`CoSimulation::share_memory()`] maps the same physical storage into several processors, at
+/// any base address in each, to model dual-port RAMs and mailboxes. Writes are visible to the
+/// other processors as soon as they run. Shared memory is meant for data: instructions cached by
+/// one processor are not invalidated by writes from another, and snapshots or context restores
+/// of one processor also restore the shared contents.
+///
+/// # Interrupts
+///
+/// [`InterruptLine`]s raise interrupts on another processor's event controller. Interrupts are
+/// delivered at the start of the next quantum which keeps delivery deterministic.
+///
+/// ```
+/// # use styx_processor::processor::{CoSimulation, ProcessorBuilder};
+/// # use styx_processor::core::builder::DummyProcessorBuilder;
+/// # use styx_cpu_type::TargetExitReason;
+/// let mut cosim = CoSimulation::new(1000);
+/// let hps = cosim.add_processor(
+///     "hps",
+///     ProcessorBuilder::default()
+///         .with_builder(DummyProcessorBuilder)
+///         .build()
+///         .unwrap(),
+/// );
+/// // companion core at half the speed
+/// let companion = cosim.add_processor_with_rate(
+///     "companion",
+///     ProcessorBuilder::default()
+///         .with_builder(DummyProcessorBuilder)
+///         .build()
+///         .unwrap(),
+///     500,
+/// );
+///
+/// // interrupt the companion from hooks on the hps
+/// let _doorbell = cosim.interrupt_line(companion);
+///
+/// let report = cosim.run(10_000).unwrap();
+/// assert_eq!(TargetExitReason::InstructionCountComplete, report.exit_reason);
+/// assert_eq!(10_000, report.cores[hps.index()].instructions());
+/// assert_eq!(5_000, report.cores[companion.index()].instructions());
+/// ```
+pub struct CoSimulation {
+    cores: Vec<CoSimCore>,
+    /// Reference instructions per quantum.
+    quantum: u64,
+    pending_interrupts: PendingInterrupts,
+    clock: VirtualClock,
+}
+
+impl CoSimulation {
+    /// Create an empty co-simulation stepping `quantum` reference instructions at a time.
+    pub fn new(quantum: u64) -> Self {
+        Self {
+            cores: Vec::new(),
+            quantum: quantum.max(1),
+            pending_interrupts: Default::default(),
+            clock: VirtualClock::default(),
+        }
+    }
+
+    /// Add a processor running at the reference rate, i.e. one quantum worth of instructions per
+    /// quantum.
+    pub fn add_processor(&mut self, name: impl Into<String>, processor: Processor) -> CoreId {
+        let quantum = self.quantum;
+        self.add_processor_with_rate(name, processor, quantum)
+    }
+
+    /// Add a processor executing `instructions_per_quantum` instructions every quantum.
+    ///
+    /// E.g. with a quantum of 1000, a rate of 500 models a core clocked at half the reference
+    /// frequency.
+    pub fn add_processor_with_rate(
+        &mut self,
+        name: impl Into<String>,
+        processor: Processor,
+        instructions_per_quantum: u64,
+    ) -> CoreId {
+        let id = CoreId(self.cores.len());
+        let name = name.into();
+        debug!("co-simulation added {name} as {id:?} at {instructions_per_quantum} per quantum");
+        self.cores.push(CoSimCore {
+            name,
+            processor,
+            instructions_per_quantum,
+            instructions: InstructionReport::default(),
+            wall_time: std::time::Duration::ZERO,
+            exit_reason: TargetExitReason::InstructionCountComplete,
+        });
+        id
+    }
+
+    /// Find a processor by the name it was added with.
+    pub fn core_id(&self, name: &str) -> Option<CoreId> {
+        self.cores
+            .iter()
+            .position(|core| core.name == name)
+            .map(CoreId)
+    }
+
+    /// Access a processor, e.g. to add hooks or inspect its state between runs.
+    ///
+    /// # Panics
+    /// Panics if `id` is not from this co-simulation.
+    pub fn processor(&mut self, id: CoreId) -> &mut Processor {
+        &mut self.cores[id.0].processor
+    }
+
+    /// Iterate over the processors and their names.
+    pub fn processors(&mut self) -> impl Iterator<Item = (CoreId, &str, &mut Processor)> {
+        self.cores
+            .iter_mut()
+            .enumerate()
+            .map(|(i, core)| (CoreId(i), core.name.as_str(), &mut core.processor))
+    }
+
+    /// Reference instructions elapsed on the shared timeline.
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Map `size` bytes of memory shared by several processors.
+    ///
+    /// `mappings` lists the processors and the physical base address the memory is mapped at in
+    /// each of them. The memory is initialized to 0.
+    ///
+    /// Returns the shared region based at address 0 so the host can access the contents by
+    /// offset.
+    pub fn share_memory(
+        &mut self,
+        size: u64,
+        perms: MemoryPermissions,
+        mappings: &[(CoreId, u64)],
+    ) -> Result<MemoryRegion, AddRegionError> {
+        let region = MemoryRegion::new(0, size, perms)?;
+        for (id, base) in mappings {
+            let core = &mut self.cores[id.0];
+            debug!("sharing {size:#x} bytes with {} at {base:#x}", core.name);
+            core.processor
+                .core
+                .mmu
+                .add_memory_region(region.new_alias(*base))?;
+        }
+        Ok(region)
+    }
+
+    /// Get an [`InterruptLine`] raising interrupts on `target`.
+    pub fn interrupt_line(&self, target: CoreId) -> InterruptLine {
+        InterruptLine {
+            target,
+            pending: self.pending_interrupts.clone(),
+        }
+    }
+
+    /// Run all processors in lockstep.
+    ///
+    /// The instruction count of `bounds` is in reference instructions on the shared timeline and
+    /// its timeout is host wall time.
+    ///
+    /// Emulation stops at the end of the quantum in which any processor exits with a fatal exit
+    /// reason or a stop request. The other processors still finish that quantum so they stay in
+    /// step.
+    ///
+    /// Each processor is started once per call, plugins, event controllers and peripherals see
+    /// one `on_processor_start()` before the first quantum and one `on_processor_stop()` after the
+    /// last.
+    pub fn run(
+        &mut self,
+        bounds: impl ExecutionConstraint,
+    ) -> Result<CoSimulationReport, UnknownError> {
+        let conditions = bounds.concrete();
+        debug!("co-simulation running with conditions: {conditions:?}");
+        if self.cores.is_empty() {
+            return Err(anyhow!("co-simulation has no processors"));
+        }
+
+        for core in self.cores.iter_mut() {
+            core.instructions = InstructionReport::default();
+            core.wall_time = std::time::Duration::ZERO;
+            core.exit_reason = TargetExitReason::InstructionCountComplete;
+            core.processor.emulation_setup()?;
+        }
+
+        let report = self.run_quanta(conditions);
+        let teardown: Result<Vec<()>, UnknownError> = self
+            .cores
+            .iter_mut()
+            .map(|core| core.processor.emulation_teardown())
+            .collect();
+        let report = report?;
+        teardown?;
+        Ok(report)
+    }
+
+    /// The lockstep loop of [`CoSimulation::run()`], all processors are started.
+    fn run_quanta(
+        &mut self,
+        conditions: ExecutionConstraintConcrete,
+    ) -> Result<CoSimulationReport, UnknownError> {
+        let target_time = conditions
+            .timeout
+            .map(|timeout_duration| Instant::now() + timeout_duration);
+        let mut remaining_instructions = conditions.inst_count;
+        let mut elapsed = 0;
+        let mut halted_core = None;
+        let exit_reason = loop {
+            let step = match remaining_instructions {
+                Some(remaining) => self.quantum.min(remaining),
+                None => self.quantum,
+            };
+
+            self.deliver_interrupts()?;
+
+            trace!("co-simulation stepping {step} reference instructions");
+            for (i, core) in self.cores.iter_mut().enumerate() {
+                // partial quanta are scaled down to each processor's rate
+                let instructions = (core.instructions_per_quantum as u128 * step as u128
+                    / self.quantum as u128) as u64;
+                if instructions == 0 {
+                    continue;
+                }
+
+                let report = core.processor.run_quantum(instructions)?;
+                core.instructions += report.instructions;
+                core.wall_time += report.wall_time;
+                if halted_core.is_none() && (report.is_fatal() || report.is_stop_request()) {
+                    debug!("{} halted: {:?}", core.name, report.exit_reason);
+                    halted_core = Some(CoreId(i));
+                }
+                core.exit_reason = report.exit_reason;
+            }
+            self.clock.advance(step);
+            elapsed += step;
+
+            if let Some(id) = halted_core {
+                break self.cores[id.0].exit_reason.clone();
+            }
+
+            if target_time
+                .map(|timeout| Instant::now() > timeout)
+                .unwrap_or(false)
+            {
+                trace!("co-simulation timeout hit");
+                break TargetExitReason::ExecutionTimeoutComplete;
+            }
+
+            if let Some(remaining) = &mut remaining_instructions {
+                *remaining -= step;
+                if *remaining == 0 {
+                    trace!("co-simulation instruction count hit");
+                    break TargetExitReason::InstructionCountComplete;
+                }
+            }
+        };
+
+        Ok(CoSimulationReport {
+            exit_reason,
+            halted_core,
+            cores: self
+                .cores
+                .iter()
+                .map(|core| {
+                    EmulationReport::new(
+                        core.exit_reason.clone(),
+                        core.instructions,
+                        core.wall_time,
+                    )
+                })
+                .collect(),
+            elapsed,
+        })
+    }
+
+    /// Latch interrupts raised since the last quantum and let the event controllers take them.
+    fn deliver_interrupts(&mut self) -> Result<(), UnknownError> {
+        let pending = std::mem::take(&mut *self.pending_interrupts.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut interrupted = vec![false; self.cores.len()];
+        for (id, irq) in pending {
+            let core = self
+                .cores
+                .get_mut(id.0)
+                .ok_or_else(|| anyhow!("interrupt raised on unknown {id:?}"))?;
+            trace!("delivering interrupt {irq} to {}", core.name);
+            core.processor.core.event_controller.latch(irq)?;
+            interrupted[id.0] = true;
+        }
+
+        for (core, _) in self
+            .cores
+            .iter_mut()
+            .zip(interrupted)
+            .filter(|(_, interrupted)| *interrupted)
+        {
+            let proc = &mut core.processor.core;
+            proc.event_controller
+                .next(proc.cpu.as_mut(), &mut proc.mmu)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for CoSimulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CoSimulation")
+            .field(
+                "processors",
+                &self.cores.iter().map(|core| &core.name).collect::<Vec<_>>(),
+            )
+            .field("quantum", &self.quantum)
+            .field("clock", &self.clock)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{
+            builder::{BuildProcessorImplArgs, DummyProcessorBuilder},
+            ProcessorBundle, ProcessorCore,
+        },
+        cpu::CpuBackend,
+        event_controller::{
+            ActivateIRQnError, EventControllerImpl, InterruptExecuted, Peripherals,
+        },
+        memory::Mmu,
+        plugins::{Plugin, UninitPlugin},
+        processor::{BuildingProcessor, ProcessorBuilder},
+    };
+
+    /// Event controller recording latched interrupts and calls to `next`.
+    #[derive(Default, Clone)]
+    struct Recorder {
+        latched: Arc<Mutex<Vec<ExceptionNumber>>>,
+        next_calls: Arc<Mutex<u32>>,
+    }
+
+    impl EventControllerImpl for Recorder {
+        fn next(
+            &mut self,
+            _cpu: &mut dyn CpuBackend,
+            _mmu: &mut Mmu,
+            _peripherals: &mut Peripherals,
+        ) -> Result<InterruptExecuted, UnknownError> {
+            *self.next_calls.lock().unwrap() += 1;
+            Ok(InterruptExecuted::NotExecuted)
+        }
+
+        fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+            self.latched.lock().unwrap().push(event);
+            Ok(())
+        }
+
+        fn execute(
+            &mut self,
+            _irq: ExceptionNumber,
+            _cpu: &mut dyn CpuBackend,
+            _mmu: &mut Mmu,
+        ) -> Result<InterruptExecuted, ActivateIRQnError> {
+            Ok(InterruptExecuted::NotExecuted)
+        }
+
+        fn finish_interrupt(
+            &mut self,
+            _cpu: &mut dyn CpuBackend,
+            _mmu: &mut Mmu,
+        ) -> Option<ExceptionNumber> {
+            None
+        }
+
+        fn init(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+            Ok(())
+        }
+    }
+
+    /// Plugin counting processor starts and stops.
+    #[derive(Default, Clone)]
+    struct StartStopCounter {
+        starts: Arc<Mutex<u32>>,
+        stops: Arc<Mutex<u32>>,
+    }
+
+    impl Plugin for StartStopCounter {
+        fn name(&self) -> &str {
+            "start stop counter"
+        }
+
+        fn on_processor_start(&mut self, _core: &mut ProcessorCore) -> Result<(), UnknownError> {
+            *self.starts.lock().unwrap() += 1;
+            Ok(())
+        }
+
+        fn on_processor_stop(&mut self, _core: &mut ProcessorCore) -> Result<(), UnknownError> {
+            *self.stops.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    impl UninitPlugin for StartStopCounter {
+        fn init(
+            self: Box<Self>,
+            _proc: &mut BuildingProcessor,
+        ) -> Result<Box<dyn Plugin>, UnknownError> {
+            Ok(self)
+        }
+    }
+
+    fn processor(recorder: &Recorder) -> Processor {
+        let recorder = recorder.clone();
+        ProcessorBuilder::default()
+            .with_builder(move |_: &BuildProcessorImplArgs| {
+                Ok(ProcessorBundle {
+                    mmu: Mmu::default_region_store(),
+                    event_controller: Box::new(recorder.clone()),
+                    ..Default::default()
+                })
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lockstep_rates() {
+        let mut cosim = CoSimulation::new(1000);
+        let fast = cosim.add_processor("fast", processor(&Recorder::default()));
+        let slow = cosim.add_processor_with_rate("slow", processor(&Recorder::default()), 250);
+        assert_eq!(Some(slow), cosim.core_id("slow"));
+
+        // the last quantum is partial
+        let report = cosim.run(2500).unwrap();
+        assert_eq!(
+            TargetExitReason::InstructionCountComplete,
+            report.exit_reason
+        );
+        assert_eq!(None, report.halted_core);
+        assert_eq!(2500, report.elapsed);
+        assert_eq!(2500, cosim.clock().now());
+        assert_eq!(2500, report.cores[fast.index()].instructions());
+        assert_eq!(625, report.cores[slow.index()].instructions());
+        assert_eq!(
+            2500,
+            cosim.processor(fast).core.event_controller.clock.now()
+        );
+        assert_eq!(625, cosim.processor(slow).core.event_controller.clock.now());
+    }
+
+    #[test]
+    fn test_shared_memory() {
+        let mut cosim = CoSimulation::new(1000);
+        let a = cosim.add_processor("a", processor(&Recorder::default()));
+        let b = cosim.add_processor("b", processor(&Recorder::default()));
+        let shared = cosim
+            .share_memory(
+                0x1000,
+                MemoryPermissions::RW,
+                &[(a, 0x2000_0000), (b, 0x4000)],
+            )
+            .unwrap();
+
+        cosim
+            .processor(a)
+            .core
+            .write_data(0x2000_0010, &[0xDE, 0xAD])
+            .unwrap();
+        let mut buf = [0u8; 2];
+        cosim.processor(b).core.read_data(0x4010, &mut buf).unwrap();
+        assert_eq!([0xDE, 0xAD], buf);
+        assert_eq!(vec![0xDE, 0xAD], shared.read_data(0x10, 2).unwrap());
+
+        // only the shared window is mapped
+        assert!(cosim.processor(b).core.read_data(0x5000, &mut buf).is_err());
+    }
+
+    #[test]
+    fn test_cross_core_interrupts() {
+        let mut cosim = CoSimulation::new(1000);
+        let recorder_a = Recorder::default();
+        let recorder_b = Recorder::default();
+        let _a = cosim.add_processor("a", processor(&recorder_a));
+        let b = cosim.add_processor("b", processor(&recorder_b));
+
+        let line = cosim.interrupt_line(b);
+        assert_eq!(b, line.target());
+        line.raise(5);
+        line.raise(7);
+        // nothing is delivered until the co-simulation runs
+        assert!(recorder_b.latched.lock().unwrap().is_empty());
+
+        *recorder_b.next_calls.lock().unwrap() = 0;
+        // a single stride per processor
+        cosim.run(1000).unwrap();
+        assert_eq!(vec![5, 7], *recorder_b.latched.lock().unwrap());
+        assert!(recorder_a.latched.lock().unwrap().is_empty());
+        // once at the quantum boundary and once after the stride
+        assert_eq!(2, *recorder_b.next_calls.lock().unwrap());
+
+        // raised interrupts are delivered once
+        cosim.run(1000).unwrap();
+        assert_eq!(2, recorder_b.latched.lock().unwrap().len());
+    }
+
+    #[test]
+    fn test_started_once_per_run() {
+        let counter = StartStopCounter::default();
+        let mut cosim = CoSimulation::new(100);
+        cosim.add_processor(
+            "a",
+            ProcessorBuilder::default()
+                .with_builder(DummyProcessorBuilder)
+                .add_plugin(counter.clone())
+                .build()
+                .unwrap(),
+        );
+
+        // ten quanta
+        cosim.run(1000).unwrap();
+        assert_eq!(1, *counter.starts.lock().unwrap());
+        assert_eq!(1, *counter.stops.lock().unwrap());
+
+        cosim.run(1000).unwrap();
+        assert_eq!(2, *counter.starts.lock().unwrap());
+        assert_eq!(2, *counter.stops.lock().unwrap());
+    }
+}
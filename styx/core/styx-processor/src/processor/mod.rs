@@ -8,8 +8,11 @@
 //! [`SyncProcessor`] is provided for asynchronous applications and allows multiple threads to
 //! interact with the processor while it is running.
 //!
+//! The [`CoSimulation`] runs several [`Processor`]s in lockstep with shared memory and cross-core
+//! interrupts, e.g. for asymmetric multi-core parts.
+//!
 mod builder;
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 pub use builder::*;
 
@@ -19,16 +22,23 @@ pub use sync::*;
 mod emulation_report;
 pub use emulation_report::*;
 
+mod cosim;
+pub use cosim::*;
+
 use static_assertions::assert_impl_all;
 use styx_errors::UnknownError;
 
 use crate::{
     core::{ProcMeta, ProcessorCore},
     executor::{ExecutionConstraint, Executor},
-    hooks::{AddHookError, DeleteHookError, HookToken, Hookable, StyxHook},
+    hooks::{
+        AddHookError, CodeHook, DeleteHookError, HookControl, HookControlError, HookToken,
+        Hookable, StyxHook,
+    },
     memory::physical::address_space::MemoryImpl,
     plugins::{collection::PluginsContainer, Plugin},
     runtime::ProcessorRuntime,
+    snapshot::Snapshot,
 };
 
 // Processor impls Send
@@ -106,6 +116,29 @@ impl Processor {
             .begin(&mut self.core, &mut self.plugins, bounds)
     }
 
+    /// Start emulation for repeated [`Processor::run_quantum()`] calls.
+    ///
+    /// Plugins, the event controller and peripherals get their `on_processor_start()` here
+    /// instead of on every quantum.
+    pub(crate) fn emulation_setup(&mut self) -> Result<(), UnknownError> {
+        self.executor.setup(&mut self.core, &mut self.plugins)
+    }
+
+    /// Run like [`Processor::run()`] without starting and stopping emulation, between
+    /// [`Processor::emulation_setup()`] and [`Processor::emulation_teardown()`].
+    pub(crate) fn run_quantum(
+        &mut self,
+        bounds: impl ExecutionConstraint,
+    ) -> Result<EmulationReport, UnknownError> {
+        self.executor
+            .step(&mut self.core, &mut self.plugins, bounds)
+    }
+
+    /// Stop emulation started with [`Processor::emulation_setup()`].
+    pub(crate) fn emulation_teardown(&mut self) -> Result<(), UnknownError> {
+        self.executor.teardown(&mut self.core, &mut self.plugins)
+    }
+
     /// Get resolved ipc port the [`Processor`] will use for I/O
     /// and Peripherals.
     pub fn ipc_port(&self) -> u16 {
@@ -113,9 +146,12 @@ impl Processor {
     }
 
     /// Save the [`Processor`]'s context to be restored in the future.
+    ///
+    /// Only the cpu registers and memory are saved and only a single context is held. Use
+    /// [`Processor::snapshot()`] to save the whole machine.
     pub fn context_save(&mut self) -> Result<(), UnknownError> {
         self.core.cpu.context_save()?;
-        self.core.mmu.memory.context_save()?;
+        self.core.mmu.context_save()?;
 
         Ok(())
     }
@@ -123,10 +159,51 @@ impl Processor {
     /// Restore the [`Processor`]'s context from a saved one.
     pub fn context_restore(&mut self) -> Result<(), UnknownError> {
         self.core.cpu.context_restore()?;
-        self.core.mmu.memory.context_restore()?;
+        self.core.mmu.context_restore()?;
 
         Ok(())
     }
+
+    /// Take a [`Snapshot`] of the complete [`Processor`] state.
+    ///
+    /// The snapshot includes the cpu, memory, TLB, event controller and all peripherals. Snapshots
+    /// are independent values, any number of them can be held and restored in any order.
+    pub fn snapshot(&mut self) -> Result<Snapshot, UnknownError> {
+        self.core.snapshot()
+    }
+
+    /// Restore the [`Processor`] to the state held in a [`Snapshot`].
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), UnknownError> {
+        self.core.restore_snapshot(snapshot)
+    }
+
+    /// Add a code hook on the first instruction of the symbol `name`.
+    ///
+    /// ```
+    /// # use styx_processor::processor::{ProcessorBuilder, Processor};
+    /// # use styx_processor::core::builder::DummyProcessorBuilder;
+    /// # use styx_processor::hooks::CoreHandle;
+    /// # use styx_errors::UnknownError;
+    /// fn hook(_proc: CoreHandle) -> Result<(), UnknownError> {
+    ///     Ok(())
+    /// }
+    ///
+    /// let mut proc: Processor = ProcessorBuilder::default()
+    ///     .with_builder(DummyProcessorBuilder)
+    ///     .build().unwrap();
+    ///
+    /// // no target program so no symbols
+    /// assert!(proc.add_symbol_hook("main", hook).is_err());
+    /// ```
+    ///
+    /// See [`ProcessorCore::add_symbol_hook()`].
+    pub fn add_symbol_hook(
+        &mut self,
+        name: &str,
+        hook: impl CodeHook + 'static,
+    ) -> Result<HookToken, UnknownError> {
+        self.core.add_symbol_hook(name, hook)
+    }
 }
 
 impl Hookable for Processor {
@@ -139,6 +216,11 @@ impl Hookable for Processor {
     fn delete_hook(&mut self, token: HookToken) -> Result<(), DeleteHookError> {
         self.core.cpu.delete_hook(token)
     }
+
+    /// Gets the [`HookControl`] of a [`StyxHook`] on the [`Processor`].
+    fn hook_control(&mut self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.core.cpu.hook_control(token)
+    }
 }
 
 impl Debug for Processor {
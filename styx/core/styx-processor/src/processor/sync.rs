@@ -21,7 +21,7 @@ use crate::{
     core::ProcessorCore,
     cpu::{ReadRegisterError, WriteRegisterError},
     executor::ExecutionConstraint,
-    hooks::{AddHookError, DeleteHookError, HookToken, StyxHook},
+    hooks::{AddHookError, DeleteHookError, HookControl, HookControlError, HookToken, StyxHook},
     memory::{
         helpers::{ReadExt, Readable, Writable},
         MmuOpError,
@@ -296,6 +296,12 @@ impl SyncProcessor {
         self.access(move |proc| proc.cpu.delete_hook(token))
     }
 
+    /// Gets the [`HookControl`] of a [`StyxHook`], usable from any thread while the processor
+    /// runs.
+    pub fn hook_control(&self, token: HookToken) -> Result<Arc<HookControl>, HookControlError> {
+        self.access(move |proc| proc.cpu.hook_control(token))
+    }
+
     pub fn ipc_port(&self) -> u16 {
         self.port
     }
@@ -0,0 +1,578 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Full machine snapshots.
+//!
+//! A [`Snapshot`] is an owned copy of the complete state of a [`ProcessorCore`]: the
+//! [`CpuBackend`] registers, the physical memory and TLB held by the [`Mmu`](crate::memory::Mmu),
+//! the event controller (pending and active exceptions), every attached
+//! [`Peripheral`](crate::event_controller::Peripheral), the [`CallStack`] and the
+//! [`VirtualClock`].
+//!
+//! Unlike [`Processor::context_save()`](crate::processor::Processor::context_save), which keeps a
+//! single saved slot inside each component, snapshots are plain values. Any number of them can be
+//! held at once, cloned, written to disk with [`Snapshot::save()`] and loaded back with
+//! [`Snapshot::load()`].
+//!
+//! ```no_run
+//! # use styx_processor::processor::ProcessorBuilder;
+//! # use styx_processor::core::builder::DummyProcessorBuilder;
+//! let mut proc = ProcessorBuilder::default()
+//!     .with_builder(DummyProcessorBuilder)
+//!     .build()
+//!     .unwrap();
+//!
+//! let snapshot = proc.snapshot().unwrap();
+//! // ... run the processor, poke memory, etc.
+//! proc.restore_snapshot(&snapshot).unwrap();
+//! ```
+//!
+//! # Component State
+//!
+//! Event controllers, TLBs and peripherals store their state as an opaque [`ComponentState`].
+//! Implementors serialize whatever they need to resume exactly where they were, usually by
+//! deriving [`Serialize`]/[`Deserialize`] on a small state struct and using
+//! [`ComponentState::new()`] and [`ComponentState::get()`]. Stateless components can rely on the
+//! default trait methods, which save an empty state and ignore it on restore.
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use styx_cpu_type::{
+    arch::{
+        arm::{arm_coproc_registers, CoProcessor, SpecialArmRegister, SpecialArmRegisterValues},
+        backends::{ArchRegister, SpecialArchRegister},
+        ppc32::{SpecialPpc32Register, SpecialPpc32RegisterValues, SprRegister},
+        u20, u40, u80, RegisterValue, RegisterValueCompatible,
+    },
+    Arch,
+};
+use styx_errors::UnknownError;
+use thiserror::Error;
+use zstd::{decode_all, encode_all};
+
+use crate::{
+    calls::CallStack, core::ProcessorCore, cpu::CpuBackend, executor::VirtualClock,
+    memory::physical::address_space::MemoryImpl,
+};
+
+/// Granularity used when storing memory contents in a [`MemorySpanSnapshot`].
+///
+/// Pages that are entirely zero are not stored.
+pub const SNAPSHOT_PAGE_SIZE: usize = 0x1000;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("could not serialize snapshot: {0}")]
+    Serialization(#[from] bincode::Error),
+    #[error("snapshot i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("snapshot memory span {{base: 0x{found_base:x}, size: 0x{found_size:x}}} does not match memory {{base: 0x{base:x}, size: 0x{size:x}}}")]
+    MemoryLayout {
+        base: u64,
+        size: u64,
+        found_base: u64,
+        found_size: u64,
+    },
+    #[error("snapshot holds {found} memory spans, memory has {expected}")]
+    MemorySpanCount { expected: usize, found: usize },
+    #[error("snapshot holds {found} peripherals, processor has {expected}")]
+    PeripheralCount { expected: usize, found: usize },
+    #[error("snapshot peripheral `{found}` does not match processor peripheral `{expected}`")]
+    PeripheralMismatch { expected: String, found: String },
+}
+
+/// Serialized state of a single emulated component.
+///
+/// An empty state is used by components that have nothing to save.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentState(Vec<u8>);
+
+impl ComponentState {
+    /// Serialize `value` into a new [`ComponentState`].
+    pub fn new<T: Serialize>(value: &T) -> Result<Self, SnapshotError> {
+        Ok(Self(bincode::serialize(value)?))
+    }
+
+    /// Deserialize the state back into `T`.
+    pub fn get<T: DeserializeOwned>(&self) -> Result<T, SnapshotError> {
+        Ok(bincode::deserialize(&self.0)?)
+    }
+
+    /// True if no state was saved.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Saved value of a single cpu register.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterSnapshot {
+    /// Name of the register, see [`CpuRegister::name()`](styx_cpu_type::arch::CpuRegister::name).
+    pub name: String,
+    /// Size of the register in bits.
+    pub bits: usize,
+    /// Register value, zero extended.
+    pub value: u128,
+    /// The register was read as an architecture specific [`RegisterValue`], e.g. an Arm
+    /// coprocessor register, and is written back as one.
+    pub special: bool,
+}
+
+impl RegisterSnapshot {
+    fn to_register_value(&self, register: ArchRegister) -> Option<RegisterValue> {
+        let value = self.value;
+        if self.special {
+            return match register {
+                ArchRegister::Special(SpecialArchRegister::Arm(
+                    SpecialArmRegister::CoProcessor(coproc),
+                )) => Some(coproc.with_value(value as u64).into()),
+                ArchRegister::Special(SpecialArchRegister::Ppc32(
+                    SpecialPpc32Register::SprRegister(_),
+                )) => SprRegister::new(value as u16).map(Into::into),
+                _ => None,
+            };
+        }
+
+        Some(match self.bits {
+            8 => RegisterValue::u8(value as u8),
+            16 => RegisterValue::u16(value as u16),
+            20 => RegisterValue::u20(u20::new(value as u32 & 0xF_FFFF)),
+            32 => RegisterValue::u32(value as u32),
+            40 => RegisterValue::u40(u40::new(value as u64 & 0xFF_FFFF_FFFF)),
+            64 => RegisterValue::u64(value as u64),
+            80 => RegisterValue::u80(u80::new(value & ((1 << 80) - 1))),
+            128 => RegisterValue::u128(value),
+            _ => return None,
+        })
+    }
+}
+
+/// Coprocessor registers saved on Arm cpus, these are not in the architecture register bank.
+const ARM_COPROCESSOR_REGISTERS: [(&str, CoProcessor); 3] = [
+    ("cbar", arm_coproc_registers::CBAR),
+    ("vbar", arm_coproc_registers::VBAR),
+    ("sctlr", arm_coproc_registers::SCTLR),
+];
+
+/// Name and register of everything saved in a [`CpuSnapshot`] of `cpu`.
+fn snapshot_registers<C: CpuBackend + ?Sized>(cpu: &C) -> Vec<(&'static str, ArchRegister)> {
+    let mut registers: Vec<_> = cpu
+        .architecture()
+        .registers()
+        .registers()
+        .iter()
+        .map(|register| (register.name(), register.variant()))
+        .collect();
+    if cpu.architecture().architecture() == Arch::Arm {
+        registers.extend(
+            ARM_COPROCESSOR_REGISTERS
+                .iter()
+                .map(|(name, register)| (*name, (*register).into())),
+        );
+    }
+    registers
+}
+
+/// Converts a [`RegisterValue`] into its bit size, integer value and whether it is an
+/// architecture specific "special" value.
+///
+/// Arm coprocessor values are saved as their 64 bit value, PowerPC SPR values as the 16 bit SPR.
+fn register_value_bits(value: RegisterValue) -> Option<(usize, u128, bool)> {
+    match value {
+        RegisterValue::ArmSpecial(SpecialArmRegisterValues::CoProcessor(coproc)) => {
+            return Some((64, coproc.value as u128, true))
+        }
+        RegisterValue::Ppc32Special(SpecialPpc32RegisterValues::SprRegister(_)) => {
+            let spr = SprRegister::as_inner_value(value).ok()?;
+            return Some((16, spr as u128, true));
+        }
+        _ => (),
+    }
+
+    let bits = value.to_bit_size();
+    let value = match value {
+        RegisterValue::u8(v) => v as u128,
+        RegisterValue::u16(v) => v as u128,
+        RegisterValue::u20(v) => v.value() as u128,
+        RegisterValue::u32(v) => v as u128,
+        RegisterValue::u40(v) => v.value() as u128,
+        RegisterValue::u64(v) => v as u128,
+        RegisterValue::u80(v) => v.value(),
+        RegisterValue::u128(v) => v,
+        RegisterValue::ArmSpecial(_) | RegisterValue::Ppc32Special(_) => return None,
+    };
+
+    Some((bits, value, false))
+}
+
+/// Saved state of a [`CpuBackend`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuSnapshot {
+    /// Program counter at the time of the snapshot.
+    pub pc: u64,
+    /// All architecture registers readable on this cpu.
+    pub registers: Vec<RegisterSnapshot>,
+    /// Backend specific state that is not visible through the architecture registers.
+    pub backend: ComponentState,
+}
+
+impl CpuSnapshot {
+    /// Capture every register defined by the cpu's architecture that the backend can read, plus
+    /// the Arm coprocessor registers.
+    ///
+    /// This is the default behavior of [`CpuBackend::snapshot()`].
+    pub fn capture<C: CpuBackend + ?Sized>(cpu: &mut C) -> Result<Self, UnknownError> {
+        let mut registers = Vec::new();
+        for (name, register) in snapshot_registers(cpu) {
+            // not every processor supports all of the registers defined by the architecture
+            let Ok(value) = cpu.read_register_raw(register) else {
+                continue;
+            };
+            if let Some((bits, value, special)) = register_value_bits(value) {
+                registers.push(RegisterSnapshot {
+                    name: name.to_owned(),
+                    bits,
+                    value,
+                    special,
+                });
+            }
+        }
+
+        Ok(Self {
+            pc: cpu.pc()?,
+            registers,
+            backend: ComponentState::default(),
+        })
+    }
+
+    /// Write every saved register back to the cpu, followed by the pc.
+    ///
+    /// This is the default behavior of [`CpuBackend::restore_snapshot()`].
+    pub fn restore<C: CpuBackend + ?Sized>(&self, cpu: &mut C) -> Result<(), UnknownError> {
+        let registers = snapshot_registers(cpu);
+        for saved in self.registers.iter() {
+            let Some((_, register)) = registers.iter().find(|(name, _)| *name == saved.name) else {
+                continue;
+            };
+            let Some(value) = saved.to_register_value(*register) else {
+                continue;
+            };
+            cpu.write_register_raw(*register, value)?;
+        }
+
+        cpu.set_pc(self.pc)
+    }
+}
+
+/// A contiguous span of memory stored as sparse, non-zero pages.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemorySpanSnapshot {
+    pub base: u64,
+    pub size: u64,
+    /// Non-zero pages as `(page index, contents)`, sorted by page index.
+    pub pages: Vec<(u64, Vec<u8>)>,
+}
+
+impl MemorySpanSnapshot {
+    /// Capture the memory in `data`, starting at address `base`.
+    pub fn capture(base: u64, data: &[u8]) -> Self {
+        let pages = data
+            .chunks(SNAPSHOT_PAGE_SIZE)
+            .enumerate()
+            .filter(|(_, page)| page.iter().any(|b| *b != 0))
+            .map(|(idx, page)| (idx as u64, page.to_vec()))
+            .collect();
+
+        Self {
+            base,
+            size: data.len() as u64,
+            pages,
+        }
+    }
+
+    /// Overwrite `data`, starting at address `base`, with the saved contents.
+    ///
+    /// Pages not present in the snapshot are zeroed.
+    pub fn restore_into(&self, base: u64, data: &mut [u8]) -> Result<(), SnapshotError> {
+        if base != self.base || data.len() as u64 != self.size {
+            return Err(SnapshotError::MemoryLayout {
+                base,
+                size: data.len() as u64,
+                found_base: self.base,
+                found_size: self.size,
+            });
+        }
+
+        let mut saved = self.pages.iter().peekable();
+        for (idx, page) in data.chunks_mut(SNAPSHOT_PAGE_SIZE).enumerate() {
+            match saved.next_if(|(saved_idx, _)| *saved_idx == idx as u64) {
+                Some((_, contents)) => page.copy_from_slice(contents),
+                None => {
+                    if page.iter().any(|b| *b != 0) {
+                        page.fill(0);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Saved contents of physical memory.
+///
+/// Each memory implementation decides how it splits its storage into spans, restoring is only
+/// valid on a memory with the same layout.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    pub spans: Vec<MemorySpanSnapshot>,
+}
+
+impl MemorySnapshot {
+    /// Check that the snapshot holds `expected` spans.
+    pub fn expect_spans(&self, expected: usize) -> Result<(), SnapshotError> {
+        if self.spans.len() != expected {
+            return Err(SnapshotError::MemorySpanCount {
+                expected,
+                found: self.spans.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Saved state of a single peripheral.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeripheralSnapshot {
+    /// [`Peripheral::name()`](crate::event_controller::Peripheral::name) of the peripheral.
+    pub name: String,
+    pub state: ComponentState,
+}
+
+/// Complete saved state of a [`ProcessorCore`].
+///
+/// See the [module level documentation](self).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub cpu: CpuSnapshot,
+    pub memory: MemorySnapshot,
+    pub tlb: ComponentState,
+    pub event_controller: ComponentState,
+    pub peripherals: Vec<PeripheralSnapshot>,
+    /// Shadow call stack, empty unless call tracking is enabled.
+    pub call_stack: CallStack,
+    /// Virtual time, restored so peripheral deadlines keep their place after a restore.
+    pub clock: VirtualClock,
+}
+
+impl Snapshot {
+    /// Capture the complete state of `core`.
+    ///
+    /// The cpu must be stopped.
+    pub fn capture(core: &mut ProcessorCore) -> Result<Self, UnknownError> {
+        let cpu = core.cpu.snapshot()?;
+        let memory = core.mmu.memory().snapshot()?;
+        let tlb = core.mmu.tlb.save_state()?;
+        let event_controller = core.event_controller.inner.save_state()?;
+        let peripherals = core
+            .event_controller
+            .peripherals
+            .peripherals
+            .iter()
+            .map(|p| {
+                Ok(PeripheralSnapshot {
+                    name: p.name().to_owned(),
+                    state: p.save_state()?,
+                })
+            })
+            .collect::<Result<Vec<_>, UnknownError>>()?;
+        let call_stack = core.event_controller.call_stack.clone();
+        let clock = core.event_controller.clock;
+
+        Ok(Self {
+            cpu,
+            memory,
+            tlb,
+            event_controller,
+            peripherals,
+            call_stack,
+            clock,
+        })
+    }
+
+    /// Restore `core` to the state held in this snapshot.
+    ///
+    /// The cpu must be stopped. The snapshot must have been captured from a processor built the
+    /// same way (same memory layout and peripherals).
+    pub fn restore(&self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        let peripherals = &mut core.event_controller.peripherals.peripherals;
+        if peripherals.len() != self.peripherals.len() {
+            return Err(SnapshotError::PeripheralCount {
+                expected: peripherals.len(),
+                found: self.peripherals.len(),
+            }
+            .into());
+        }
+        for (peripheral, saved) in peripherals.iter().zip(self.peripherals.iter()) {
+            if peripheral.name() != saved.name {
+                return Err(SnapshotError::PeripheralMismatch {
+                    expected: peripheral.name().to_owned(),
+                    found: saved.name.clone(),
+                }
+                .into());
+            }
+        }
+
+        core.mmu.restore_snapshot(&self.memory)?;
+        core.mmu.tlb.restore_state(&self.tlb)?;
+        core.cpu.restore_snapshot(&self.cpu)?;
+        core.event_controller
+            .inner
+            .restore_state(&self.event_controller)?;
+        for (peripheral, saved) in core
+            .event_controller
+            .peripherals
+            .peripherals
+            .iter_mut()
+            .zip(self.peripherals.iter())
+        {
+            peripheral.restore_state(&saved.state)?;
+        }
+        core.event_controller.call_stack.restore(&self.call_stack);
+        core.event_controller.clock = self.clock;
+
+        Ok(())
+    }
+
+    /// Serialize and compress the snapshot.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let raw = bincode::serialize(self)?;
+        Ok(encode_all(raw.as_slice(), 0)?)
+    }
+
+    /// Decompress and deserialize a snapshot created by [`Snapshot::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let raw = decode_all(bytes)?;
+        Ok(bincode::deserialize(&raw)?)
+    }
+
+    /// Write the snapshot to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read a snapshot previously written with [`Snapshot::save()`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use styx_cpu_type::arch::arm::ArmRegister;
+    use test_case::test_case;
+
+    #[test_case(RegisterValue::u8(0xAB))]
+    #[test_case(RegisterValue::u20(u20::new(0xF_1234)))]
+    #[test_case(RegisterValue::u40(u40::new(0xAB_1234_5678)))]
+    #[test_case(RegisterValue::u80(u80::new(0xABCD_1234_5678_9ABC_DEF0)))]
+    #[test_case(RegisterValue::u128(u128::MAX))]
+    fn test_register_roundtrip(value: RegisterValue) {
+        let (bits, raw, special) = register_value_bits(value).unwrap();
+        let saved = RegisterSnapshot {
+            name: "reg".to_owned(),
+            bits,
+            value: raw,
+            special,
+        };
+
+        let restored = saved.to_register_value(ArmRegister::R0.into()).unwrap();
+        assert_eq!(register_value_bits(restored), Some((bits, raw, false)));
+    }
+
+    #[test]
+    fn test_special_register_roundtrip() {
+        let value: RegisterValue = arm_coproc_registers::VBAR.with_value(0x8000).into();
+        let (bits, raw, special) = register_value_bits(value).unwrap();
+        assert!(special);
+        let saved = RegisterSnapshot {
+            name: "vbar".to_owned(),
+            bits,
+            value: raw,
+            special,
+        };
+
+        let restored = saved
+            .to_register_value(arm_coproc_registers::VBAR.into())
+            .unwrap();
+        assert_eq!(value, restored);
+    }
+
+    #[test]
+    fn test_memory_span_sparse() {
+        let mut data = vec![0u8; SNAPSHOT_PAGE_SIZE * 4];
+        data[SNAPSHOT_PAGE_SIZE * 2 + 7] = 0x41;
+
+        let span = MemorySpanSnapshot::capture(0x1000, &data);
+        assert_eq!(span.pages.len(), 1);
+        assert_eq!(span.pages[0].0, 2);
+
+        // dirty a page that was zero and the saved page
+        let mut current = data.clone();
+        current[0] = 0xFF;
+        current[SNAPSHOT_PAGE_SIZE * 2 + 7] = 0x00;
+
+        span.restore_into(0x1000, &mut current).unwrap();
+        assert_eq!(current, data);
+    }
+
+    #[test]
+    fn test_memory_span_layout_mismatch() {
+        let data = vec![0u8; SNAPSHOT_PAGE_SIZE];
+        let span = MemorySpanSnapshot::capture(0, &data);
+
+        let mut other = vec![0u8; SNAPSHOT_PAGE_SIZE * 2];
+        assert!(span.restore_into(0, &mut other).is_err());
+        assert!(span
+            .restore_into(0x1000, &mut other[..SNAPSHOT_PAGE_SIZE])
+            .is_err());
+    }
+
+    #[test]
+    fn test_snapshot_bytes_roundtrip() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct State {
+            pending: Vec<i32>,
+            active: Option<i32>,
+        }
+        let state = State {
+            pending: vec![1, 5, 16],
+            active: Some(3),
+        };
+
+        let snapshot = Snapshot {
+            cpu: CpuSnapshot {
+                pc: 0x4000,
+                registers: vec![RegisterSnapshot {
+                    name: "r0".to_owned(),
+                    bits: 32,
+                    value: 0xdead_beef,
+                }],
+                backend: ComponentState::default(),
+            },
+            memory: MemorySnapshot {
+                spans: vec![MemorySpanSnapshot::capture(0, &[1, 2, 3, 4])],
+            },
+            tlb: ComponentState::default(),
+            event_controller: ComponentState::new(&state).unwrap(),
+            peripherals: vec![],
+            call_stack: CallStack::default(),
+            clock: VirtualClock::default(),
+        };
+
+        let restored = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored, snapshot);
+        assert_eq!(restored.event_controller.get::<State>().unwrap(), state);
+    }
+}
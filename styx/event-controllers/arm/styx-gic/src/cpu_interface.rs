@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! GIC CPU interface (ICC) register model.
+//!
+//! The CPU interface signals the highest priority interrupt forwarded by the [`Distributor`] to
+//! the core if it is above the priority mask and can preempt the interrupts already active.
+//! Reading `ICCIAR` acknowledges it (pending -> active), writing its ID to `ICCEOIR` completes it
+//! (active -> inactive).
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+use crate::distributor::{Distributor, CPU_ID, NUM_INTERRUPTS, NUM_SGIS, PRIORITY_MASK};
+
+/// Returned by `ICCIAR` and `ICCHPIR` when no interrupt is pending.
+pub(crate) const SPURIOUS_INTERRUPT: u32 = 1023;
+
+/// CPU Interface Identification Register value of the Cortex-A9 GIC.
+const ICCIIDR_VALUE: u32 = 0x3901_243B;
+
+/// CPU Interface Control Register
+const ICCICR: u32 = 0x00;
+const ICCICR_ENABLE_GROUP0: u32 = 1 << 0;
+const ICCICR_ENABLE_GROUP1: u32 = 1 << 1;
+const ICCICR_FIQ_ENABLE: u32 = 1 << 3;
+const ICCICR_MASK: u32 = 0x1F;
+/// Interrupt Priority Mask Register
+const ICCPMR: u32 = 0x04;
+/// Binary Point Register
+const ICCBPR: u32 = 0x08;
+/// Interrupt Acknowledge Register
+const ICCIAR: u32 = 0x0C;
+/// End of Interrupt Register
+const ICCEOIR: u32 = 0x10;
+/// Running Priority Register
+const ICCRPR: u32 = 0x14;
+/// Highest Pending Interrupt Register
+const ICCHPIR: u32 = 0x18;
+/// Aliased Binary Point Register
+const ICCABPR: u32 = 0x1C;
+/// CPU Interface Identification Register
+const ICCIIDR: u32 = 0xFC;
+
+/// With five priority bits the binary point can not go below 2.
+const MIN_BINARY_POINT: u32 = 2;
+
+/// Priority of an idle CPU interface.
+const IDLE_PRIORITY: u8 = 0xFF;
+
+/// An interrupt the CPU interface signals to the core.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Signal {
+    pub id: usize,
+    /// Signaled as FIQ instead of IRQ.
+    pub fiq: bool,
+}
+
+/// State of the CPU interface registers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CpuInterface {
+    control: u32,
+    priority_mask: u8,
+    binary_point: u32,
+    aliased_binary_point: u32,
+    /// Acknowledged and not yet completed interrupts, oldest first.
+    active: Vec<usize>,
+}
+
+impl Default for CpuInterface {
+    fn default() -> Self {
+        Self {
+            control: 0,
+            priority_mask: 0,
+            binary_point: MIN_BINARY_POINT,
+            aliased_binary_point: MIN_BINARY_POINT + 1,
+            active: Vec::new(),
+        }
+    }
+}
+
+impl CpuInterface {
+    /// Most recently acknowledged interrupt that is not completed yet.
+    pub(crate) fn current(&self) -> Option<usize> {
+        self.active.last().copied()
+    }
+
+    /// Highest priority interrupt forwarded by the distributor, lowest ID wins a tie.
+    fn highest_pending(&self, distributor: &Distributor) -> Option<(usize, u8)> {
+        (0..NUM_INTERRUPTS)
+            .filter(|id| distributor.forwards(*id))
+            .filter_map(|id| Some((id, distributor.interrupt(id)?.priority)))
+            .min_by_key(|(id, priority)| (*priority, *id))
+    }
+
+    fn running_priority(&self, distributor: &Distributor) -> u8 {
+        self.active
+            .iter()
+            .filter_map(|id| distributor.interrupt(*id))
+            .map(|interrupt| interrupt.priority)
+            .min()
+            .unwrap_or(IDLE_PRIORITY)
+    }
+
+    /// Only the group priority bits above the binary point take part in preemption.
+    fn group_priority(&self, priority: u8) -> u8 {
+        priority & ((0xFFu32 << (self.binary_point + 1)) & 0xFF) as u8
+    }
+
+    /// Interrupt to signal to the core, if any.
+    pub(crate) fn signal(&self, distributor: &Distributor) -> Option<Signal> {
+        let (id, priority) = self.highest_pending(distributor)?;
+        let group1 = distributor.interrupt(id)?.group1;
+        let group_enable = if group1 {
+            ICCICR_ENABLE_GROUP1
+        } else {
+            ICCICR_ENABLE_GROUP0
+        };
+        if self.control & group_enable == 0 || priority >= self.priority_mask {
+            return None;
+        }
+        if !self.active.is_empty() {
+            let running = self.running_priority(distributor);
+            if self.group_priority(priority) >= self.group_priority(running) {
+                return None;
+            }
+        }
+
+        Some(Signal {
+            id,
+            fiq: !group1 && self.control & ICCICR_FIQ_ENABLE != 0,
+        })
+    }
+
+    /// Acknowledge the signaled interrupt, returns the `ICCIAR` value.
+    fn acknowledge(&mut self, distributor: &mut Distributor) -> u32 {
+        let Some(Signal { id, .. }) = self.signal(distributor) else {
+            return SPURIOUS_INTERRUPT;
+        };
+        if let Some(interrupt) = distributor.interrupt_mut(id) {
+            interrupt.pending = false;
+            interrupt.active = true;
+        }
+        self.active.push(id);
+        trace!("acknowledged interrupt {id}");
+
+        // SGIs report the requesting CPU
+        let source = if id < NUM_SGIS { CPU_ID as u32 } else { 0 };
+        (source << 10) | id as u32
+    }
+
+    /// Complete the interrupt in `value`, returns its ID if it was active.
+    fn end_of_interrupt(&mut self, distributor: &mut Distributor, value: u32) -> Option<usize> {
+        let id = (value & 0x3FF) as usize;
+        let Some(position) = self.active.iter().position(|active| *active == id) else {
+            debug!("end of interrupt for inactive interrupt {id}");
+            return None;
+        };
+        self.active.remove(position);
+        if let Some(interrupt) = distributor.interrupt_mut(id) {
+            interrupt.active = false;
+        }
+        trace!("completed interrupt {id}");
+        Some(id)
+    }
+
+    /// Read the word register at `offset` from the CPU interface base, reading `ICCIAR`
+    /// acknowledges the signaled interrupt.
+    pub(crate) fn read(&mut self, distributor: &mut Distributor, offset: u32) -> u32 {
+        match offset {
+            ICCICR => self.control,
+            ICCPMR => self.priority_mask as u32,
+            ICCBPR => self.binary_point,
+            ICCIAR => self.acknowledge(distributor),
+            ICCRPR => self.running_priority(distributor) as u32,
+            ICCHPIR => self
+                .highest_pending(distributor)
+                .map(|(id, _)| id as u32)
+                .unwrap_or(SPURIOUS_INTERRUPT),
+            ICCABPR => self.aliased_binary_point,
+            ICCIIDR => ICCIIDR_VALUE,
+            _ => {
+                trace!("read of unimplemented cpu interface register {offset:#x}");
+                0
+            }
+        }
+    }
+
+    /// Write the bytes of `value` selected by `mask` to the word register at `offset` from the CPU
+    /// interface base.
+    ///
+    /// Returns the ID of the interrupt completed by an `ICCEOIR` write.
+    pub(crate) fn write(
+        &mut self,
+        distributor: &mut Distributor,
+        offset: u32,
+        value: u32,
+        mask: u32,
+    ) -> Option<usize> {
+        let merge = |old: u32| (old & !mask) | (value & mask);
+        match offset {
+            ICCICR => self.control = merge(self.control) & ICCICR_MASK,
+            ICCPMR => self.priority_mask = merge(self.priority_mask as u32) as u8 & PRIORITY_MASK,
+            ICCBPR => self.binary_point = (merge(self.binary_point) & 0x7).max(MIN_BINARY_POINT),
+            ICCEOIR => return self.end_of_interrupt(distributor, value & mask),
+            ICCABPR => self.aliased_binary_point = merge(self.aliased_binary_point) & 0x7,
+            _ => debug!("write to read only cpu interface register {offset:#x}: {value:#x}"),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: u32 = 0xFFFF_FFFF;
+
+    /// Distributor forwarding the SPIs in `ids` with `priority`, everything enabled.
+    fn setup(ids: &[(usize, u8)]) -> (Distributor, CpuInterface) {
+        let mut distributor = Distributor::default();
+        distributor.write(0x000, 0x3, ALL);
+        for (id, priority) in ids {
+            let interrupt = distributor.interrupt_mut(*id).unwrap();
+            interrupt.enabled = true;
+            interrupt.targets = 1;
+            interrupt.priority = *priority;
+            distributor.set_pending(*id);
+        }
+
+        let mut cpu_interface = CpuInterface::default();
+        cpu_interface.write(&mut distributor, ICCICR, 0x3, ALL);
+        cpu_interface.write(&mut distributor, ICCPMR, 0xFF, ALL);
+        (distributor, cpu_interface)
+    }
+
+    #[test]
+    fn test_acknowledge_and_complete() {
+        let (mut distributor, mut cpu_interface) = setup(&[(40, 0xA0), (72, 0x80)]);
+        assert_eq!(72, cpu_interface.read(&mut distributor, ICCHPIR));
+        assert_eq!(
+            Some(Signal { id: 72, fiq: false }),
+            cpu_interface.signal(&distributor)
+        );
+
+        assert_eq!(72, cpu_interface.read(&mut distributor, ICCIAR));
+        assert_eq!(0x80, cpu_interface.read(&mut distributor, ICCRPR));
+        assert_eq!(Some(72), cpu_interface.current());
+        // 40 has a lower priority than the running 72
+        assert_eq!(None, cpu_interface.signal(&distributor));
+        assert_eq!(40, cpu_interface.read(&mut distributor, ICCHPIR));
+
+        assert_eq!(
+            None,
+            cpu_interface.write(&mut distributor, ICCEOIR, 40, ALL)
+        );
+        assert_eq!(
+            Some(72),
+            cpu_interface.write(&mut distributor, ICCEOIR, 72, ALL)
+        );
+        assert_eq!(0xFF, cpu_interface.read(&mut distributor, ICCRPR));
+        assert_eq!(40, cpu_interface.read(&mut distributor, ICCIAR));
+        assert_eq!(
+            SPURIOUS_INTERRUPT,
+            cpu_interface.read(&mut distributor, ICCIAR)
+        );
+    }
+
+    #[test]
+    fn test_priority_mask_and_preemption() {
+        let (mut distributor, mut cpu_interface) = setup(&[(40, 0x88)]);
+        cpu_interface.write(&mut distributor, ICCPMR, 0x88, ALL);
+        assert_eq!(None, cpu_interface.signal(&distributor));
+        cpu_interface.write(&mut distributor, ICCPMR, 0x90, ALL);
+        assert_eq!(40, cpu_interface.read(&mut distributor, ICCIAR));
+
+        // with a binary point of 3 only the upper 4 bits preempt
+        let interrupt = distributor.interrupt_mut(50).unwrap();
+        interrupt.enabled = true;
+        interrupt.targets = 1;
+        interrupt.priority = 0x80;
+        distributor.set_pending(50);
+        cpu_interface.write(&mut distributor, ICCBPR, 3, ALL);
+        assert_eq!(None, cpu_interface.signal(&distributor));
+        cpu_interface.write(&mut distributor, ICCBPR, 2, ALL);
+        assert_eq!(
+            Some(Signal { id: 50, fiq: false }),
+            cpu_interface.signal(&distributor)
+        );
+    }
+
+    #[test]
+    fn test_group0_fiq() {
+        let (mut distributor, mut cpu_interface) = setup(&[(40, 0x80)]);
+        cpu_interface.write(&mut distributor, ICCICR, ICCICR_FIQ_ENABLE | 0x3, ALL);
+        assert_eq!(
+            Some(Signal { id: 40, fiq: true }),
+            cpu_interface.signal(&distributor)
+        );
+
+        // group 1 interrupts are always IRQs
+        distributor.interrupt_mut(40).unwrap().group1 = true;
+        assert_eq!(
+            Some(Signal { id: 40, fiq: false }),
+            cpu_interface.signal(&distributor)
+        );
+        cpu_interface.write(&mut distributor, ICCICR, ICCICR_ENABLE_GROUP0, ALL);
+        assert_eq!(None, cpu_interface.signal(&distributor));
+    }
+}
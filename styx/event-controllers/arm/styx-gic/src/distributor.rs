@@ -0,0 +1,410 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! GIC distributor (ICD) register model.
+//!
+//! The distributor holds the state of every interrupt (enabled, pending, active, priority,
+//! targets, configuration and group) and decides which interrupts are forwarded to the CPU
+//! interface.
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// Number of interrupt IDs, 16 SGIs, 16 PPIs and 224 SPIs.
+pub(crate) const NUM_INTERRUPTS: usize = 256;
+
+/// Interrupts below this ID are banked per CPU (SGIs and PPIs).
+const NUM_PRIVATE_INTERRUPTS: usize = 32;
+
+/// The IRQn for a Software Generated Interrupt (SGI) is in the range of 0-15.
+pub(crate) const NUM_SGIS: usize = 16;
+
+/// Number of the CPU interface of the emulated core.
+pub(crate) const CPU_ID: u8 = 0;
+const CPU_MASK: u8 = 1 << CPU_ID;
+
+/// Only the upper five priority bits are implemented, giving 32 priority levels.
+pub(crate) const PRIORITY_MASK: u8 = 0xF8;
+
+/// Distributor Implementer Identification Register value of the Cortex-A9 GIC.
+const ICDIIDR_VALUE: u32 = 0x0102_043B;
+
+/// Distributor Control Register
+const ICDDCR: u32 = 0x000;
+const ICDDCR_MASK: u32 = 0x3;
+/// Interrupt Controller Type Register
+const ICDICTR: u32 = 0x004;
+/// Distributor Implementer Identification Register
+const ICDIIDR: u32 = 0x008;
+/// Interrupt Security Registers (group)
+const ICDISR: u32 = 0x080;
+/// Interrupt Set-Enable Registers
+const ICDISER: u32 = 0x100;
+/// Interrupt Clear-Enable Registers
+const ICDICER: u32 = 0x180;
+/// Interrupt Set-Pending Registers
+const ICDISPR: u32 = 0x200;
+/// Interrupt Clear-Pending Registers
+const ICDICPR: u32 = 0x280;
+/// Active Bit Registers (set)
+const ICDABR: u32 = 0x300;
+/// Active Bit Registers (clear)
+const ICDACR: u32 = 0x380;
+/// Interrupt Priority Registers
+const ICDIPR: u32 = 0x400;
+/// Interrupt Processor Targets Registers
+const ICDIPTR: u32 = 0x800;
+/// Interrupt Configuration Registers
+const ICDICFR: u32 = 0xC00;
+/// Software Generated Interrupt Register
+const ICDSGIR: u32 = 0xF00;
+
+/// Bitmaps, one bit per interrupt.
+const BITMAP_LEN: u32 = (NUM_INTERRUPTS / 8) as u32;
+/// Byte maps, one byte per interrupt.
+const BYTEMAP_LEN: u32 = NUM_INTERRUPTS as u32;
+/// Configuration, two bits per interrupt.
+const ICDICFR_LEN: u32 = (NUM_INTERRUPTS / 4) as u32;
+
+/// State of a single interrupt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct InterruptState {
+    pub enabled: bool,
+    pub pending: bool,
+    pub active: bool,
+    /// Edge triggered instead of level sensitive, only stored.
+    pub edge_triggered: bool,
+    /// Group 1 (non-secure) instead of group 0 (secure).
+    pub group1: bool,
+    /// Lower is more important.
+    pub priority: u8,
+    /// CPU interfaces the interrupt is forwarded to, one bit per CPU.
+    pub targets: u8,
+}
+
+/// State of the distributor registers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Distributor {
+    /// `ICDDCR`, bit 0 enables group 0 and bit 1 group 1 forwarding.
+    control: u32,
+    interrupts: Vec<InterruptState>,
+}
+
+impl Default for Distributor {
+    fn default() -> Self {
+        let interrupts = (0..NUM_INTERRUPTS)
+            .map(|id| InterruptState {
+                // SGIs are always enabled and edge triggered
+                enabled: id < NUM_SGIS,
+                edge_triggered: id < NUM_SGIS,
+                // private interrupts always target the reading CPU
+                targets: if id < NUM_PRIVATE_INTERRUPTS {
+                    CPU_MASK
+                } else {
+                    0
+                },
+                ..Default::default()
+            })
+            .collect();
+
+        Self {
+            control: 0,
+            interrupts,
+        }
+    }
+}
+
+impl Distributor {
+    pub(crate) fn interrupt(&self, id: usize) -> Option<&InterruptState> {
+        self.interrupts.get(id)
+    }
+
+    pub(crate) fn interrupt_mut(&mut self, id: usize) -> Option<&mut InterruptState> {
+        self.interrupts.get_mut(id)
+    }
+
+    /// Mark an interrupt pending, false if the ID does not exist.
+    pub(crate) fn set_pending(&mut self, id: usize) -> bool {
+        match self.interrupts.get_mut(id) {
+            Some(interrupt) => {
+                interrupt.pending = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The distributor forwards the interrupt to the CPU interface of the emulated core.
+    pub(crate) fn forwards(&self, id: usize) -> bool {
+        let Some(interrupt) = self.interrupts.get(id) else {
+            return false;
+        };
+        let group_enabled = self.control & (1 << interrupt.group1 as u32) != 0;
+        group_enabled
+            && interrupt.enabled
+            && interrupt.pending
+            && !interrupt.active
+            && interrupt.targets & CPU_MASK != 0
+    }
+
+    /// Read the word register at `offset` from the distributor base.
+    pub(crate) fn read(&self, offset: u32) -> u32 {
+        match offset {
+            ICDDCR => self.control,
+            ICDICTR => (NUM_INTERRUPTS / 32 - 1) as u32,
+            ICDIIDR => ICDIIDR_VALUE,
+            _ if in_map(ICDISR, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDISR, |interrupt| interrupt.group1)
+            }
+            _ if in_map(ICDISER, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDISER, |interrupt| interrupt.enabled)
+            }
+            _ if in_map(ICDICER, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDICER, |interrupt| interrupt.enabled)
+            }
+            _ if in_map(ICDISPR, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDISPR, |interrupt| interrupt.pending)
+            }
+            _ if in_map(ICDICPR, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDICPR, |interrupt| interrupt.pending)
+            }
+            _ if in_map(ICDABR, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDABR, |interrupt| interrupt.active)
+            }
+            _ if in_map(ICDACR, BITMAP_LEN, offset) => {
+                self.read_bitmap(offset - ICDACR, |interrupt| interrupt.active)
+            }
+            _ if in_map(ICDIPR, BYTEMAP_LEN, offset) => {
+                self.read_bytemap(offset - ICDIPR, |interrupt| interrupt.priority)
+            }
+            _ if in_map(ICDIPTR, BYTEMAP_LEN, offset) => {
+                self.read_bytemap(offset - ICDIPTR, |interrupt| interrupt.targets)
+            }
+            _ if in_map(ICDICFR, ICDICFR_LEN, offset) => {
+                let first = ((offset - ICDICFR) * 4) as usize;
+                (0..16).fold(0, |value, idx| {
+                    let edge = self.interrupts[first + idx].edge_triggered as u32;
+                    value | (edge << (idx * 2 + 1))
+                })
+            }
+            _ => {
+                trace!("read of unimplemented distributor register {offset:#x}");
+                0
+            }
+        }
+    }
+
+    /// Write the bytes of `value` selected by `mask` to the word register at `offset` from the
+    /// distributor base.
+    ///
+    /// Returns the SGI to raise on the emulated core, if any.
+    pub(crate) fn write(&mut self, offset: u32, value: u32, mask: u32) -> Option<usize> {
+        let set = value & mask;
+        match offset {
+            ICDDCR => self.control = (self.control & !mask) | (set & ICDDCR_MASK),
+            _ if in_map(ICDISR, BITMAP_LEN, offset) => {
+                let first = ((offset - ICDISR) * 8) as usize;
+                for idx in (0..32).filter(|idx| mask & (1 << idx) != 0) {
+                    self.interrupts[first + idx].group1 = value & (1 << idx) != 0;
+                }
+            }
+            _ if in_map(ICDISER, BITMAP_LEN, offset) => {
+                self.write_bitmap(offset - ICDISER, set, |interrupt, id| {
+                    // SGIs can not be disabled
+                    if id >= NUM_SGIS {
+                        interrupt.enabled = true
+                    }
+                })
+            }
+            _ if in_map(ICDICER, BITMAP_LEN, offset) => {
+                self.write_bitmap(offset - ICDICER, set, |interrupt, id| {
+                    if id >= NUM_SGIS {
+                        interrupt.enabled = false
+                    }
+                })
+            }
+            _ if in_map(ICDISPR, BITMAP_LEN, offset) => {
+                self.write_bitmap(offset - ICDISPR, set, |interrupt, id| {
+                    // SGI pending state is only changed through ICDSGIR
+                    if id >= NUM_SGIS {
+                        interrupt.pending = true
+                    }
+                })
+            }
+            _ if in_map(ICDICPR, BITMAP_LEN, offset) => {
+                self.write_bitmap(offset - ICDICPR, set, |interrupt, id| {
+                    if id >= NUM_SGIS {
+                        interrupt.pending = false
+                    }
+                })
+            }
+            _ if in_map(ICDABR, BITMAP_LEN, offset) => {
+                self.write_bitmap(offset - ICDABR, set, |interrupt, _| interrupt.active = true)
+            }
+            _ if in_map(ICDACR, BITMAP_LEN, offset) => {
+                self.write_bitmap(offset - ICDACR, set, |interrupt, _| {
+                    interrupt.active = false
+                })
+            }
+            _ if in_map(ICDIPR, BYTEMAP_LEN, offset) => {
+                self.write_bytemap(offset - ICDIPR, value, mask, |interrupt, _, byte| {
+                    interrupt.priority = byte & PRIORITY_MASK
+                })
+            }
+            _ if in_map(ICDIPTR, BYTEMAP_LEN, offset) => {
+                self.write_bytemap(offset - ICDIPTR, value, mask, |interrupt, id, byte| {
+                    if id >= NUM_PRIVATE_INTERRUPTS {
+                        interrupt.targets = byte
+                    }
+                })
+            }
+            _ if in_map(ICDICFR, ICDICFR_LEN, offset) => {
+                let first = ((offset - ICDICFR) * 4) as usize;
+                for idx in (0..16).filter(|idx| mask & (0x2 << (idx * 2)) != 0) {
+                    if first + idx >= NUM_SGIS {
+                        self.interrupts[first + idx].edge_triggered =
+                            value & (0x2 << (idx * 2)) != 0;
+                    }
+                }
+            }
+            ICDSGIR => return self.software_interrupt(set),
+            _ => debug!("write to unimplemented distributor register {offset:#x}: {value:#x}"),
+        }
+        None
+    }
+
+    /// Handle a write to `ICDSGIR`.
+    fn software_interrupt(&mut self, value: u32) -> Option<usize> {
+        let id = (value & 0xF) as usize;
+        let target_list = ((value >> 16) & 0xFF) as u8;
+        let targets = match (value >> 24) & 0x3 {
+            0 => target_list,
+            // every other CPU
+            1 => !CPU_MASK,
+            // the requesting CPU
+            2 => CPU_MASK,
+            _ => 0,
+        };
+        trace!("SGI{id} for CPUs {targets:#x}");
+
+        (targets & CPU_MASK != 0).then_some(id)
+    }
+
+    fn read_bitmap(&self, offset: u32, bit: impl Fn(&InterruptState) -> bool) -> u32 {
+        let first = (offset * 8) as usize;
+        self.interrupts[first..first + 32]
+            .iter()
+            .enumerate()
+            .fold(0, |value, (idx, interrupt)| {
+                value | ((bit(interrupt) as u32) << idx)
+            })
+    }
+
+    /// Calls `update` with each interrupt of the register whose bit is set in `bits`.
+    fn write_bitmap(
+        &mut self,
+        offset: u32,
+        bits: u32,
+        mut update: impl FnMut(&mut InterruptState, usize),
+    ) {
+        let first = (offset * 8) as usize;
+        for (idx, interrupt) in self.interrupts[first..first + 32].iter_mut().enumerate() {
+            if bits & (1 << idx) != 0 {
+                update(interrupt, first + idx);
+            }
+        }
+    }
+
+    fn read_bytemap(&self, offset: u32, byte: impl Fn(&InterruptState) -> u8) -> u32 {
+        let first = offset as usize;
+        self.interrupts[first..first + 4]
+            .iter()
+            .enumerate()
+            .fold(0, |value, (idx, interrupt)| {
+                value | ((byte(interrupt) as u32) << (idx * 8))
+            })
+    }
+
+    /// Calls `update` with each interrupt of the register whose byte is selected by `mask`.
+    fn write_bytemap(
+        &mut self,
+        offset: u32,
+        value: u32,
+        mask: u32,
+        mut update: impl FnMut(&mut InterruptState, usize, u8),
+    ) {
+        let first = offset as usize;
+        for (idx, interrupt) in self.interrupts[first..first + 4].iter_mut().enumerate() {
+            if mask & (0xFF << (idx * 8)) != 0 {
+                update(interrupt, first + idx, (value >> (idx * 8)) as u8);
+            }
+        }
+    }
+}
+
+/// `offset` is a word register inside the map of `len` bytes at `base`.
+fn in_map(base: u32, len: u32, offset: u32) -> bool {
+    (base..base + len).contains(&offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enable_and_pending() {
+        let mut distributor = Distributor::default();
+        distributor.write(ICDDCR, 0x1, 0xFFFF_FFFF);
+        distributor.write(ICDIPTR + 72, 0x1, 0xFF);
+        assert!(distributor.set_pending(72));
+        assert!(!distributor.forwards(72));
+
+        // ICDISER2 bit 8 is interrupt 72
+        distributor.write(ICDISER + 8, 1 << 8, 0xFFFF_FFFF);
+        assert!(distributor.forwards(72));
+        assert_eq!(1 << 8, distributor.read(ICDISPR + 8));
+
+        distributor.write(ICDICER + 8, 1 << 8, 0xFFFF_FFFF);
+        assert!(!distributor.forwards(72));
+        assert_eq!(0, distributor.read(ICDISER + 8));
+
+        distributor.write(ICDICPR + 8, 1 << 8, 0xFFFF_FFFF);
+        assert_eq!(0, distributor.read(ICDISPR + 8));
+        assert!(!distributor.set_pending(NUM_INTERRUPTS));
+    }
+
+    #[test]
+    fn test_byte_registers() {
+        let mut distributor = Distributor::default();
+        // only the second byte is written
+        distributor.write(ICDIPR + 32, 0xFFFF_FFFF, 0xFF00);
+        assert_eq!(0xF800, distributor.read(ICDIPR + 32));
+
+        // private interrupts always target the emulated core
+        distributor.write(ICDIPTR, 0x0202_0202, 0xFFFF_FFFF);
+        assert_eq!(0x0101_0101, distributor.read(ICDIPTR));
+        distributor.write(ICDIPTR + 32, 0x0202_0202, 0xFFFF_FFFF);
+        assert_eq!(0x0202_0202, distributor.read(ICDIPTR + 32));
+        assert!(!distributor.forwards(33));
+    }
+
+    #[test]
+    fn test_software_interrupt() {
+        let mut distributor = Distributor::default();
+        // target list with CPU 0
+        assert_eq!(
+            Some(3),
+            distributor.write(ICDSGIR, 0x0001_0003, 0xFFFF_FFFF)
+        );
+        // target list without CPU 0
+        assert_eq!(None, distributor.write(ICDSGIR, 0x0002_0003, 0xFFFF_FFFF));
+        // every other CPU
+        assert_eq!(None, distributor.write(ICDSGIR, 0x0100_0005, 0xFFFF_FFFF));
+        // the requesting CPU
+        assert_eq!(
+            Some(5),
+            distributor.write(ICDSGIR, 0x0200_0005, 0xFFFF_FFFF)
+        );
+
+        // SGIs can not be disabled
+        distributor.write(ICDICER, 0xFFFF_FFFF, 0xFFFF_FFFF);
+        assert_eq!(0xFFFF, distributor.read(ICDISER));
+    }
+}
@@ -15,13 +15,13 @@
 //! | IRQ interrupt                 | 0x0018 | Interrupt Request                             |
 //! | FIQ interrupt                 | 0x001C | Fast Interrupt Request                        |
 //!
-//!   - Any interrupt can be defined as secure or non-secure.
-//!   - Any non-secure IRQ goes to the IRQ interrupt vector.
-//!   - For any secure IRQ:
-//!     - If FIQs are enabled (FIQen bit in the ICPICR Register), go to the FIQ interrupt
+//!   - Any interrupt can be defined as secure (group 0) or non-secure (group 1).
+//!   - Any non-secure interrupt goes to the IRQ interrupt vector.
+//!   - For any secure interrupt:
+//!     - If FIQs are enabled (FIQEn bit in the ICCICR Register), go to the FIQ interrupt
 //!       vector.
 //!     - If FIQs are not enabled, they go to the IRQ interrupt vector (same as a non-secure IRQ).
-//!   - the other 6 dispatches are for specific non-IRQ functions. Not sure if we do anything with these yet
+//!   - The other exceptions are raised by the cpu backend and taken immediately.
 //!
 //! GIC registers:
 //!     Note:
@@ -29,44 +29,29 @@
 //!         ICD = Interrupt Controller Distributor
 //!
 //! Implemented:
-//!      - Interrupt Acknowledge Register (ICCIAR)
-//!          - The processor reads this register to obtain the interrupt ID.
-//!      - Software Generated Interrupt Register (ICDSGIR) - issue SGI
-//!
-//! TODO:
-//!    not needed:
-//!      - End of Interrupt Register (ICCEOIR) (we already do this by intercepting execution)
-//!
-//!   higher priority:
-//!      - Interrupt Controller Type Register (ICDICTR)
-//!      - Highest Pending Interrupt Register (ICCHPIR)
-//!      - CPU Interface Control Register (ICCICR)
-//!          - Enables the signaling of interrupts to the target processors.
-//!
-//!    lower priority:
-//!      - Interrupt Priority Mask Register (ICCPMR)
-//!      - Binary Point Register (ICCBPR)
-//!      - CPU Interface Identification Register (ICCIIDR).
 //!      - Distributor Control Register (ICDDCR)
+//!      - Interrupt Controller Type Register (ICDICTR)
 //!      - Distributor Implementer Identification Register (ICDIIDR)
-//!      - Interrupt Set-Enable Registers (ICDISERn)
-//!      - Interrupt Clear-Enable Registers (ICDICERn)
-//!      - Interrupt Set-Pending Registers (ICDISPRn)
-//!      - Interrupt Clear-Pending Registers (ICDICPRn)
-//!      - Active Bit Registers (ICDABRn)
-//!      - Interrupt Priority Registers (ICDIPRn)
-//!      - Running Priority Register (ICCRPR)
-//!
-//!    we'll see:
-//!      - Interrupt Configuration Registers (ICDICFRn):
-//!          - edge triggered vs. level sensitive
-//!          - 1-N or N-N handling
 //!      - Interrupt Security Registers (ICDISRn)
+//!      - Interrupt Set-Enable / Clear-Enable Registers (ICDISERn / ICDICERn)
+//!      - Interrupt Set-Pending / Clear-Pending Registers (ICDISPRn / ICDICPRn)
+//!      - Active Bit Registers (ICDABRn / ICDACRn)
+//!      - Interrupt Priority Registers (ICDIPRn), five priority bits
 //!      - Interrupt Processor Targets Registers (ICDIPTRn)
-//!      - Aliased Binary Point Register (ICCABPR)
+//!      - Interrupt Configuration Registers (ICDICFRn), stored only, every latch is an edge
+//!      - Software Generated Interrupt Register (ICDSGIR)
+//!      - CPU Interface Control Register (ICCICR)
+//!      - Interrupt Priority Mask Register (ICCPMR)
+//!      - Binary Point Register (ICCBPR) and Aliased Binary Point Register (ICCABPR)
+//!      - Interrupt Acknowledge Register (ICCIAR)
+//!      - End of Interrupt Register (ICCEOIR)
+//!      - Running Priority Register (ICCRPR)
+//!      - Highest Pending Interrupt Register (ICCHPIR)
+//!      - CPU Interface Identification Register (ICCIIDR)
 //!
 //! Functionally, the GIC is divided into two parts: the distributor and one or more CPU
-//! interfaces.
+//! interfaces. Only the CPU interface of the emulated core (CPU 0) exists, interrupts targeting
+//! other CPUs stay pending in the distributor.
 //!
 //! GIC startup execution:
 //!   - Get exception vector base address (based on the System Control Register's Vectors bit and
@@ -74,137 +59,93 @@
 //!   - Get base address for distributor and CPU interface registers (in Configuration Base Address
 //!     Register).
 //!
-//! GIC interrupt handling execution (stuff in parenthesis we're going to just hand wave for now):
-//!   1. peripheral interrupt received by GIC
-//!   2. (the interrupt state is marked as "pending")
-//!   3. (GIC determines if the interrupt is enabled)
-//!   4. (for enabled interrupts, the distributor determines the target processor(s) for the
-//!      interrupt)
-//!   5. (distributor determines highest priority pending interrupt and forwards to the CPU
-//!      interface)
-//!   6. (CPU interface compares the interrupt priority with the current interrupt priority for the
-//!      processor. If the interrupt priority is high enough, the processor will be notified)
-//!   7. The preferred return address and cpsr are saved.
-//!   8. The CPU interface signals the processor about the interrupt with a ICCIAR write.
-//!   9. The processor's program counter is redirected to the proper exception vector.
-//!        - If the interrupt is an IRQ, the "secure" state for it is checked:
-//!            - If the interrupt is "secure" _and_ if FIQs are enabled (FIQen bit in the ICPICR
-//!              Register), the FIQ interrupt vector is invoked.
-//!            - Otherwise, the IRQ interrupt vector is invoked.
-//!  10. (CPU receives/acknowledges with ICCIAR read - we are just assuming this read for now to
-//!      avoid an extra hook)
-//!  11. (GIC changes the interrupt state from "pending" to either "active" or "active and pending")
-//!  12. (Upon interrupt handling completion, the processor signals completion with a write to the
-//!      ICCEOIR register.)
-//!  13. (The GIC changes the interrupt state: "active" -> "inactive"; "active and pending' ->
-//!      "pending")
-//!  14. If there is a pending interrupt of sufficient priority, we return to step 8.
-//!  15. If there is no pending interrupt of sufficient priority, the interrupt exception request
-//!      ICCIAR is cleared.
-//!  16. The execution context is popped off of the stack and normal execution continues.
+//! GIC interrupt handling execution:
+//!   1. peripheral interrupt received by GIC ([`EventControllerImpl::latch`])
+//!   2. the interrupt state is marked as "pending"
+//!   3. the distributor forwards it to the CPU interface if the distributor and the interrupt are
+//!      enabled and the interrupt targets CPU 0
+//!   4. the CPU interface picks the highest priority forwarded interrupt, it is signaled to the
+//!      processor if its priority is above the priority mask and its group priority preempts the
+//!      running priority
+//!   5. if the `I` (or `F` for FIQs) bit in `cpsr` is clear, the processor takes the exception:
+//!      `cpsr` is saved to the `spsr` of the new mode, the return address to its `lr` and the
+//!      program counter is redirected to the exception vector
+//!   6. the handler reads ICCIAR, the interrupt changes from "pending" to "active"
+//!   7. the handler writes the interrupt ID to ICCEOIR, the interrupt changes from "active" to
+//!      "inactive" and the post event hook of the peripheral is called
+//!   8. the handler returns with the usual exception return, restoring `cpsr` from `spsr`
 //!
 //! NOTE:
-//! * Only interrupts marked "pending" can be signaled to a processor (so "active and pending" will
-//!   remain in the queue).
-//! * We are not currently handling preemption.
+//! * Only interrupts marked "pending" and not "active" can be signaled to a processor, an interrupt
+//!   latched while active is signaled again after its EOI.
+//! * Level sensitive interrupts are not re-sampled, peripherals latch them again from their post
+//!   event hook while the line is still asserted.
+//!
+//! AArch64 processors use the [Gicv3], which adds the redistributor and takes exceptions through
+//! the AArch64 vector table.
+mod cpu_interface;
+mod distributor;
+mod redistributor;
+mod v3;
+
+pub use v3::Gicv3;
+
+use std::borrow::Cow;
 
-use binary_heap_plus::{BinaryHeap, MinComparator};
+use cpu_interface::CpuInterface;
+use distributor::{Distributor, NUM_INTERRUPTS};
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
 use styx_core::{
     arch::arm::ArmRegister,
-    event_controller::{ActivateIRQnError, InterruptExecuted},
+    event_controller::{
+        ActivateIRQnError, Exception, InterruptExecuted, OptionalFeatureError, Peripherals,
+    },
     prelude::*,
+    snapshot::ComponentState,
 };
 use thiserror::Error;
-use tracing::{debug, error, trace};
-
-/// Every time PC points to this address, we intercept execution and use it as a trampoline to
-/// redirect to either the correct ISR or return back to the previously executing code
-const TODO_ISR_RECOVERY_LR_VALUE: u32 = 0x99999999;
-
-/// We need a custom IRQn for exceptions so we can treat everything the same way. The "peripheral"
-/// that handles this IRQn is the GIC itself.
-const GIC_EXCEPTION_IRQN: ExceptionNumber = -42;
-
-/// The IRQn for a Software Generated Interrupt (SGI) is in the range of 0-15.
-const MAX_SGI_IRQN: ExceptionNumber = 15;
+use tracing::{debug, trace};
 
 /// Interrupt controller CPU interfaces.
 const INT_CTRL_CPU_OFFSET: u32 = 0x0100;
+const INT_CTRL_CPU_SIZE: u32 = 0x0100;
 /// Interrupt controller distributor.
 const INT_CTRL_DIST_OFFSET: u32 = 0x1000;
+const INT_CTRL_DIST_SIZE: u32 = 0x1000;
 
-/// Interrupt Acknowledge Register
-/// Read here -> processor acknowledges the interrupt.
-const ICCIAR_OFFSET: u32 = 0x00C;
-const ICCIAR_RESET: u32 = 0x000003FF;
+/// We need a custom IRQn for exceptions so we can treat everything the same way. The "peripheral"
+/// that handles this IRQn is the GIC itself.
+const GIC_EXCEPTION_IRQN: ExceptionNumber = -42;
 
-/// End of Interrupt Register
-/// Write here -> processor is done with interrupt handling.
-#[allow(dead_code)]
-const ICCEOIR_OFFSET: u32 = 0x010;
+const CPSR_MODE_MASK: u32 = 0x1F;
+const CPSR_T: u32 = 1 << 5;
+const CPSR_F: u32 = 1 << 6;
+const CPSR_I: u32 = 1 << 7;
+const CPSR_A: u32 = 1 << 8;
 
-/// Software Generated Interrupt Register
-/// Guest write here -> initiate Software Generated Interrupt (SGI)
-const ICDSGIR_OFFSET: u32 = 0xF00;
+const MODE_FIQ: u32 = 0x11;
+const MODE_IRQ: u32 = 0x12;
+const MODE_SVC: u32 = 0x13;
+const MODE_ABT: u32 = 0x17;
+const MODE_HYP: u32 = 0x1A;
+const MODE_UND: u32 = 0x1B;
 
-// XXX: Should we just keep a weak cpu pointer in the structure, since the data is closely tied to
-// the cpu, and we need to use it in every method?
 #[derive(Debug)]
-/// Container for interrupt-related system registers.
+/// Base addresses of the memory mapped GIC registers.
 struct GicRegisters {
-    icciar_addr: u32,
-    icdsgir_addr: u32,
+    cpu_interface_base: u32,
+    distributor_base: u32,
 }
 
 impl GicRegisters {
     fn new(cba: u32) -> Self {
-        let cpu_interface_base = cba + INT_CTRL_CPU_OFFSET;
-        let distributor_base = cba + INT_CTRL_DIST_OFFSET;
-        let icciar_addr = cpu_interface_base + ICCIAR_OFFSET;
-        let icdsgir_addr = distributor_base + ICDSGIR_OFFSET;
-
         GicRegisters {
-            icciar_addr,
-            icdsgir_addr,
+            cpu_interface_base: cba + INT_CTRL_CPU_OFFSET,
+            distributor_base: cba + INT_CTRL_DIST_OFFSET,
         }
     }
-
-    fn read_reg_val(&self, mmu: &mut Mmu, addr: u32) -> Result<u32, UnknownError> {
-        Ok(mmu.data().read(addr).le().u32()?)
-    }
-
-    fn set_icciar_interrupt(&self, mmu: &mut Mmu, interrupt_id: u32) {
-        // We need to preserve the initial value.
-        let initial_icciar = self.read_reg_val(mmu, self.icciar_addr).unwrap();
-
-        // TODO: Check if we should be setting the CPU ID field.
-        // Clear the Interrupt ID bits and set to the new value.
-        let new_icciar = (initial_icciar & !ICCIAR_RESET) | (interrupt_id & ICCIAR_RESET);
-
-        // Perform a write to the icciar register.
-        mmu.data()
-            .write(self.icciar_addr)
-            .le()
-            .u32(new_icciar)
-            .unwrap();
-    }
-
-    fn reset_icciar(&self, mmu: &mut Mmu) {
-        // We need to preserve the initial value.
-        let initial_icciar = self.read_reg_val(mmu, self.icciar_addr).unwrap();
-
-        // Clear the Interrupt ID bits.
-        let new_icciar = initial_icciar & !ICCIAR_RESET;
-
-        // Perform a reset to the icciar register.
-        mmu.data()
-            .write(self.icciar_addr)
-            .le()
-            .u32(new_icciar)
-            .unwrap();
-    }
 }
 
 // The exception vector table for ARMv7-A.
@@ -247,87 +188,40 @@ enum ExceptionVector {
     Fiq = 7,
 }
 
-//#[derive(PartialEq, Eq, Debug)]
-#[derive(Debug)]
-enum EventType {
-    Exception(ExceptionVector),
-    Interrupt(ExceptionNumber),
-}
-
-/// This is the data container used by the [`GicIsrRecovery`] and holds the register state +
-/// interrupt number as well as holds the routines to perform the automatic stack maintinence.
-/// According to the reference, `pc` and `cpsr` are saved by the processor, but we also save `lr`
-/// since we use it to intercept returns from ISRs.
-/// TODO: move the maintinence routines into [`GicIsrRecovery`]
-#[derive(Debug)]
-struct IsrContext {
-    lr: u32,
-    pc: u32,
-    cpsr: u32,
-    evt_num: ExceptionNumber,
-}
-
-impl IsrContext {
-    fn new(cpu: &mut dyn CpuBackend, evt_num: ExceptionNumber) -> Self {
-        IsrContext {
-            lr: cpu.read_register::<u32>(ArmRegister::Lr).unwrap(),
-            pc: cpu.read_register::<u32>(ArmRegister::Pc).unwrap(),
-            cpsr: cpu.read_register::<u32>(ArmRegister::Cpsr).unwrap(),
-            evt_num,
+impl ExceptionVector {
+    /// Processor mode the exception is taken to.
+    fn mode(self) -> u32 {
+        match self {
+            Self::Reset | Self::SupervisorCall => MODE_SVC,
+            Self::UndefinedInstruction => MODE_UND,
+            Self::PrefetchAbort | Self::DataAbort => MODE_ABT,
+            Self::HypervisorTrap => MODE_HYP,
+            Self::Irq => MODE_IRQ,
+            Self::Fiq => MODE_FIQ,
         }
     }
 
-    #[allow(dead_code)]
-    fn to_bytes(&self) -> Vec<u8> {
-        let mut out: Vec<u8> = Vec::new();
-
-        out.extend_from_slice(&self.cpsr.to_le_bytes()[0..]);
-        out.extend_from_slice(&self.pc.to_le_bytes()[0..]);
-        out.extend_from_slice(&self.lr.to_le_bytes()[0..]);
-
-        out
-    }
-}
-
-/// This is used to manage the ISR state machine for entering/ exiting interrupts. In hardware,
-/// this seems to be handled by the processor instead of the GIC, but we do it here since it keeps
-/// the architecture simple.
-/// Overview of exception entry: Arm7-A and Arm7-R TRM section B1.8.3.
-/// Saves `pc`, `cpsr` (per the spec) and `lr` (because we abuse it to intercept and redirect
-/// execution).
-#[derive(Debug, Default)]
-struct GicIsrRecovery {
-    contexts: Arc<Mutex<Vec<IsrContext>>>,
-}
-
-impl GicIsrRecovery {
-    /// Save the necessary execution context elements prior to handling an exception.
-    fn push_context(&self, cpu: &mut dyn CpuBackend, evt_num: ExceptionNumber) {
-        // get the current context
-        let context = IsrContext::new(cpu, evt_num);
-
-        // save context
-        self.contexts.lock().unwrap().push(context);
-
-        // Save the cpsr to the spsr... not sure if anything uses it, but it is spelled out in the
-        // ARM7-A / ARM7-R TRM.
-        let cpsr = cpu.read_register::<u32>(ArmRegister::Cpsr).unwrap();
-        cpu.write_register(ArmRegister::Spsr, cpsr).unwrap();
-    }
-
-    /// Restore the execution context after handling an exception.
-    fn pop_context(&self, cpu: &mut dyn CpuBackend) -> ExceptionNumber {
-        let context = self.contexts.lock().unwrap().pop().unwrap();
-
-        cpu.write_register(ArmRegister::Lr, context.lr).unwrap();
-        cpu.write_register(ArmRegister::Pc, context.pc).unwrap();
-        cpu.write_register(ArmRegister::Cpsr, context.cpsr).unwrap();
-
-        context.evt_num
+    /// `cpsr` mask bits set on exception entry, see table B1-6 of the ARMv7-A and ARMv7-R TRM.
+    fn masks(self) -> u32 {
+        match self {
+            Self::Reset | Self::Fiq => CPSR_I | CPSR_F | CPSR_A,
+            Self::PrefetchAbort | Self::DataAbort | Self::Irq => CPSR_I | CPSR_A,
+            Self::UndefinedInstruction | Self::SupervisorCall | Self::HypervisorTrap => CPSR_I,
+        }
     }
 
-    fn is_recovery_stack_empty(&self) -> bool {
-        self.contexts.lock().unwrap().is_empty()
+    /// Offset added to `pc` for the `lr` of the exception mode.
+    ///
+    /// The backends report the faulting instruction for undefined instructions and aborts, the
+    /// next instruction for supervisor calls and interrupts are taken between instructions, see
+    /// table B1-7 of the ARMv7-A and ARMv7-R TRM.
+    fn return_offset(self, thumb: bool) -> u32 {
+        match self {
+            Self::UndefinedInstruction if thumb => 2,
+            Self::UndefinedInstruction | Self::PrefetchAbort | Self::Irq | Self::Fiq => 4,
+            Self::DataAbort => 8,
+            Self::Reset | Self::SupervisorCall | Self::HypervisorTrap => 0,
+        }
     }
 }
 
@@ -337,6 +231,8 @@ pub enum GicError {
     EmptyIrq(ExceptionNumber),
     #[error("Failed to initialize with given configuration")]
     InitializationFailure,
+    #[error("Unhandled exception {0} from the backend")]
+    UnhandledException(i32),
 }
 
 #[derive(Debug)]
@@ -345,93 +241,71 @@ struct GicConfig {
     vba: u32,
 }
 
+/// Serialized [`Gic`] state.
+#[derive(Serialize, Deserialize)]
+struct GicState {
+    distributor: Distributor,
+    cpu_interface: CpuInterface,
+}
+
+#[derive(Default)]
 pub struct Gic {
-    /// XXX: In no universe is this correct, for now it does
-    /// the job. `Gic` needs to be reworked to take into
-    /// account the MMR's that change the priority of interrupts
-    /// at runtime.
-    latched_events: BinaryHeap<ExceptionNumber, MinComparator>,
+    distributor: Distributor,
 
-    /// is the [`Gic`] currently executing an interrupt
-    executing_interrupt: Mutex<bool>,
+    /// CPU interface of the emulated core.
+    cpu_interface: CpuInterface,
 
-    /// handle to isr recovery insertion routines
-    isr_recovery: Arc<GicIsrRecovery>,
+    /// Interrupt completed by the last ICCEOIR write, handed to
+    /// [`EventControllerImpl::finish_interrupt`].
+    completed: Option<ExceptionNumber>,
 
     // We make the registers and config substructures [`LateInit`] since they depend on
     // configuration information that is not available at the time of instantiation.
-    /// Registers for interfacing with the guest during interrupts.
+    /// Base addresses of the memory mapped registers.
     registers: LateInit<GicRegisters>,
 
     /// Hold configuration information.
     config: LateInit<GicConfig>,
 }
 
-/// Max number of events, currently only used to pre-allocate some things.
-///
-/// NOTE: If this is used for more than emulating ARM Cortex-A9 GIC's then this should be re-worked
-/// to become an implementation detail.
-const TODO_GIC_CEC_MAX_EVENTS: usize = 1244;
-
-impl Default for Gic {
-    fn default() -> Self {
-        let mut latched = BinaryHeap::new_min();
-        latched.reserve(TODO_GIC_CEC_MAX_EVENTS);
-
-        Self {
-            latched_events: latched,
-            executing_interrupt: Mutex::new(false),
-            isr_recovery: Arc::new(GicIsrRecovery::default()),
-            registers: Default::default(),
-            config: Default::default(),
-        }
-    }
-}
-
 impl EventControllerImpl for Gic {
-    /// For the moment we can get away with only a simple "get the smallest interrupt number and
-    /// execute it"
+    /// Takes the interrupt signaled by the CPU interface, if the processor does not mask it.
     fn next(
         &mut self,
         cpu: &mut dyn CpuBackend,
-        mmu: &mut Mmu,
-        _peripherals: &mut styx_core::event_controller::Peripherals,
-    ) -> Result<styx_core::event_controller::InterruptExecuted, UnknownError> {
-        // peek to see if there are events to execute
-        let event = self.latched_events.peek();
-
-        // Get the current minimum value in the interrupt min-heap if it exists, else we're done
-        // and don't need to do anything. By default, the lowest value is the highest priority.
-        if event.is_some() {
-            // event is present -- now we need to attempt to grab
-            // the interrupt execution lock
-            if !self.interrupt_begin() {
-                return Ok(InterruptExecuted::NotExecuted);
-            }
-
-            // we now own the interrupt executing boolean, so we
-            // can now pop the event off the queue
-            let evt_num = self.latched_events.pop().unwrap();
-            trace!(
-                target: "interrupts",
-                "{{\"type\": \"interrupts\", \"action\": \"execute\", \"event\": {}}}",
-                evt_num
-            );
-
-            self.handle_event(cpu, mmu, EventType::Interrupt(evt_num));
+        _mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        let Some(signal) = self.cpu_interface.signal(&self.distributor) else {
+            return Ok(InterruptExecuted::NotExecuted);
+        };
 
-            // we inserted an ISR
-            Ok(InterruptExecuted::Executed)
+        let (vector, mask) = if signal.fiq {
+            (ExceptionVector::Fiq, CPSR_F)
         } else {
-            // no ISR was inserted
-            Ok(InterruptExecuted::NotExecuted)
+            (ExceptionVector::Irq, CPSR_I)
+        };
+        if cpu.read_register::<u32>(ArmRegister::Cpsr)? & mask != 0 {
+            return Ok(InterruptExecuted::NotExecuted);
         }
+
+        let evt_num = signal.id as ExceptionNumber;
+        trace!(
+            target: "interrupts",
+            "{{\"type\": \"interrupts\", \"action\": \"execute\", \"event\": {}}}",
+            evt_num
+        );
+        self.enter_exception(cpu, vector, evt_num)?;
+        Ok(InterruptExecuted::Executed)
     }
 
     fn latch(&mut self, evt: ExceptionNumber) -> Result<(), ActivateIRQnError> {
         trace!("Latching EVT: {}", evt);
 
-        self.latched_events.push(evt);
+        let id = usize::try_from(evt).map_err(|_| ActivateIRQnError::InvalidIRQn(evt))?;
+        if !self.distributor.set_pending(id) {
+            return Err(ActivateIRQnError::InvalidIRQn(evt));
+        }
         Ok(())
     }
 
@@ -449,10 +323,53 @@ impl EventControllerImpl for Gic {
         _cpu: &mut dyn CpuBackend,
         _mmu: &mut Mmu,
     ) -> Option<ExceptionNumber> {
-        None
+        self.completed.take()
+    }
+
+    /// `WFI` wakes up on a signaled interrupt even if the processor masks it.
+    fn has_pending_events(&self) -> bool {
+        self.cpu_interface.signal(&self.distributor).is_some()
+    }
+
+    fn init(&mut self, cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.register_hooks(cpu)
     }
 
-    fn init(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.distributor = Default::default();
+        self.cpu_interface = Default::default();
+        self.completed = None;
+        Ok(())
+    }
+
+    fn current_exception(&mut self) -> Result<Option<Exception>, OptionalFeatureError> {
+        Ok(self.cpu_interface.current().map(|id| Exception {
+            name: format!("IRQ{id}").into(),
+            number: id as ExceptionNumber,
+        }))
+    }
+
+    fn available_exceptions(&mut self) -> Result<Cow<'_, [Exception]>, OptionalFeatureError> {
+        Ok((0..NUM_INTERRUPTS)
+            .map(|id| Exception {
+                name: format!("IRQ{id}").into(),
+                number: id as ExceptionNumber,
+            })
+            .collect())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&GicState {
+            distributor: self.distributor.clone(),
+            cpu_interface: self.cpu_interface.clone(),
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: GicState = state.get()?;
+        self.distributor = state.distributor;
+        self.cpu_interface = state.cpu_interface;
+        self.completed = None;
         Ok(())
     }
 }
@@ -487,7 +404,7 @@ enum UnicornException {
     */
 }
 
-/// Convert a Unicorn exception into a guest exception and add the event to the event queue.
+/// Convert a Unicorn exception into a guest exception and take it.
 fn handle_interrupts(proc: CoreHandle, intno: i32) -> Result<(), UnknownError> {
     let gic = proc.event_controller.get_impl::<Gic>()?;
 
@@ -518,96 +435,165 @@ fn handle_interrupts(proc: CoreHandle, intno: i32) -> Result<(), UnknownError> {
         }
     };
 
-    // should this be execute?
     // Rather than latch them, we handle these events immediately since Unicorn is blocked.
-    gic.handle_event(proc.cpu, proc.mmu, EventType::Exception(native_exception));
-    Ok(())
+    gic.enter_exception(proc.cpu, native_exception, GIC_EXCEPTION_IRQN)
 }
 
-/// Code hook that will restore the top of the ISR stack to the current cpu state
-fn pop_isr_context(proc: CoreHandle) -> Result<(), UnknownError> {
+fn cpu_interface_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
     let gic = proc.event_controller.get_impl::<Gic>()?;
+    let offset = (address - gic.registers.cpu_interface_base as u64) as u32;
+    let Gic {
+        distributor,
+        cpu_interface,
+        ..
+    } = gic;
+    read_words(offset, &mut data[..size as usize], |word| {
+        cpu_interface.read(distributor, word)
+    });
+    Ok(())
+}
 
-    // store the current pc
-    let old_pc = proc.cpu.pc().unwrap() as u32;
+fn cpu_interface_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let mut completed = None;
+    {
+        let gic = proc.event_controller.get_impl::<Gic>()?;
+        let offset = (address - gic.registers.cpu_interface_base as u64) as u32;
+        for (word, value, mask) in write_words(offset, &data[..size as usize]) {
+            if let Some(id) = gic
+                .cpu_interface
+                .write(&mut gic.distributor, word, value, mask)
+            {
+                completed = Some(id as ExceptionNumber);
+            }
+        }
+        gic.completed = completed;
+    }
 
-    // this restores the old-old pc (destination/new pc)
-    let evt_num = gic.isr_recovery.pop_context(proc.cpu);
+    if let Some(evt_num) = completed {
+        let pc = proc.cpu.pc()? as u32;
+        strace!(InterruptEvent {
+            etype: TraceEventType::INTERRUPT,
+            old_pc: pc,
+            new_pc: pc,
+            interrupt_num: evt_num,
+            interrupt_type: InterruptType::IsrExit,
+            ..Default::default()
+        });
+        trace!(target: "interrupts", "{{\"type\": \"interrupts\", \"action\": \"complete\", \"event\": {}}}", evt_num);
 
-    // Clear the Interrupt ID from the ICCIAR.
-    gic.registers.reset_icciar(proc.mmu);
+        // route the post event hook to the peripheral of the completed interrupt
+        proc.event_controller.finish_interrupt(proc.cpu, proc.mmu);
+    }
+    Ok(())
+}
 
-    // debug the interrupt IsrExit event
-    strace!(InterruptEvent {
-        etype: TraceEventType::INTERRUPT,
-        old_pc,
-        new_pc: proc.cpu.pc().unwrap() as u32,
-        interrupt_num: evt_num,
-        interrupt_type: InterruptType::IsrExit,
-        ..Default::default()
+fn distributor_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let gic = proc.event_controller.get_impl::<Gic>()?;
+    let offset = (address - gic.registers.distributor_base as u64) as u32;
+    read_words(offset, &mut data[..size as usize], |word| {
+        gic.distributor.read(word)
     });
-
-    // popped execution context, now route cleanup hook
-    // and relinquish the execution lock
-    gic.post_irq_route_hook(evt_num);
-    if gic.isr_recovery.is_recovery_stack_empty() {
-        gic.interrupt_complete();
-    }
     Ok(())
 }
 
-fn gic_icdsgir_write_callback(
+fn distributor_write_hook(
     proc: CoreHandle,
-    _address: u64,
-    _size: u32,
+    address: u64,
+    size: u32,
     data: &[u8],
 ) -> Result<(), UnknownError> {
-    // Example of setting the icdsgir in the Altera SDK:
-    //   armv7a/hwlib/src/hwmgr/alt_interrupt.c!alt_int_sgi_trigger line 854.
-    // XXX: We are ignoring the CPU target list for now.
-    let icdsgir = u32::from_le_bytes(data[0..4].try_into().unwrap());
-    let sgi_int_id: ExceptionNumber = 0x000F & icdsgir as ExceptionNumber;
-
-    // SGIs are Interrupt IDs 0-15.
-    if sgi_int_id > MAX_SGI_IRQN {
-        panic!("Received an SGI with an invalid interrupt ID greater than 15.");
-    }
-
-    trace!("Latching SGI: interrupt ID {}", sgi_int_id);
-    proc.event_controller.latch(sgi_int_id).unwrap();
     let gic = proc.event_controller.get_impl::<Gic>()?;
-
-    // We've handled it behind the scenes, so we clear the register.
-    proc.mmu
-        .data()
-        .write(gic.registers.icdsgir_addr)
-        .bytes(&[0u8; 4])
-        .unwrap();
+    let offset = (address - gic.registers.distributor_base as u64) as u32;
+    for (word, value, mask) in write_words(offset, &data[..size as usize]) {
+        if let Some(sgi) = gic.distributor.write(word, value, mask) {
+            // Example of setting the icdsgir in the Altera SDK:
+            //   armv7a/hwlib/src/hwmgr/alt_interrupt.c!alt_int_sgi_trigger line 854.
+            trace!("Latching SGI: interrupt ID {}", sgi);
+            gic.distributor.set_pending(sgi);
+        }
+    }
     Ok(())
 }
 
-impl Peripheral for Gic {
-    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
-        self.register_hooks(proc.core.cpu.as_mut())
+/// Fill the little endian `data` read at `offset` from the word registers returned by `read`,
+/// each word register is read once.
+fn read_words(offset: u32, data: &mut [u8], mut read: impl FnMut(u32) -> u32) {
+    let mut current: Option<(u32, u32)> = None;
+    for (offset, byte) in (offset..).zip(data.iter_mut()) {
+        let word = offset & !0x3;
+        let value = match current {
+            Some((read_word, value)) if read_word == word => value,
+            _ => {
+                let value = read(word);
+                current = Some((word, value));
+                value
+            }
+        };
+        *byte = (value >> ((offset - word) * 8)) as u8;
     }
+}
 
-    fn name(&self) -> &str {
-        "Gic Peripheral"
+/// Split the little endian `data` written at `offset` into `(word offset, value, byte mask)`
+/// writes to word registers.
+fn write_words(offset: u32, data: &[u8]) -> Vec<(u32, u32, u32)> {
+    let mut writes: Vec<(u32, u32, u32)> = Vec::new();
+    for (offset, byte) in (offset..).zip(data.iter()) {
+        let word = offset & !0x3;
+        let shift = (offset - word) * 8;
+        let (value, mask) = ((*byte as u32) << shift, 0xFF << shift);
+        match writes.last_mut() {
+            Some((written, written_value, written_mask)) if *written == word => {
+                *written_value |= value;
+                *written_mask |= mask;
+            }
+            _ => writes.push((word, value, mask)),
+        }
     }
+    writes
 }
 
 impl Gic {
-    /// this should setup the runtime memory hooks needed by the Gic
+    /// Setup the runtime memory hooks of the memory mapped registers and the exception hook.
     fn register_hooks(&self, cpu: &mut dyn CpuBackend) -> Result<(), UnknownError> {
-        // When pc hits 0x99999999 (impossible value)
-        // we catch it and restore the stack like hardware does
-        let isr_address = (TODO_ISR_RECOVERY_LR_VALUE - 1) as u64;
-        cpu.code_hook(isr_address, isr_address, Box::new(pop_isr_context))?;
+        let cpu_interface_start = self.registers.cpu_interface_base as u64;
+        let cpu_interface_end = cpu_interface_start + INT_CTRL_CPU_SIZE as u64 - 1;
+        cpu.mem_read_hook(
+            cpu_interface_start,
+            cpu_interface_end,
+            Box::new(cpu_interface_read_hook),
+        )?;
+        cpu.mem_write_hook(
+            cpu_interface_start,
+            cpu_interface_end,
+            Box::new(cpu_interface_write_hook),
+        )?;
 
+        let distributor_start = self.registers.distributor_base as u64;
+        let distributor_end = distributor_start + INT_CTRL_DIST_SIZE as u64 - 1;
+        cpu.mem_read_hook(
+            distributor_start,
+            distributor_end,
+            Box::new(distributor_read_hook),
+        )?;
         cpu.mem_write_hook(
-            self.registers.icdsgir_addr as u64,
-            self.registers.icdsgir_addr as u64,
-            Box::new(gic_icdsgir_write_callback),
+            distributor_start,
+            distributor_end,
+            Box::new(distributor_write_hook),
         )?;
 
         cpu.intr_hook(Box::new(handle_interrupts))?;
@@ -615,68 +601,34 @@ impl Gic {
         Ok(())
     }
 
-    /// returns if it is okay to continue executing new interrupt
-    fn interrupt_begin(&self) -> bool {
-        // if we're already executing an interrupt don't do anything
-        let interrupt_execution = self.executing_interrupt.try_lock();
-
-        // Check for the two error cases:
-        // - failed to get the lock -- bail
-        // - got the lock but we are already executing interrupt -- bail
-        if let Ok(mut interrupt_executing) = interrupt_execution {
-            if *interrupt_executing {
-                return false;
-            }
-
-            // there is no current holder of the lock, and
-            // there it no current interrupt executing, change that
-            // and return true :D
-            *interrupt_executing = true;
-            true
-        } else {
-            // error
-            false
-        }
-    }
-
-    fn interrupt_complete(&self) {
-        let mut executing = self.executing_interrupt.lock().unwrap();
-        *executing = false;
-    }
-
-    #[inline]
-    fn handle_event(&self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu, event_type: EventType) {
-        let evt_num: ExceptionNumber;
-        match event_type {
-            EventType::Exception(_) => {
-                // We use a special IRQn for these, since they are exceptions and are handled
-                // directly by the GIC.
-                evt_num = GIC_EXCEPTION_IRQN;
-            }
-            EventType::Interrupt(event_irqn) => {
-                evt_num = event_irqn;
-
-                // Put the interrupt ID into the ICCIAR register for the guest. This is only
-                // necessary for IRQs and FIQs.
-                self.registers.set_icciar_interrupt(mmu, evt_num as u32);
-            }
-        };
-
-        // Save execution context - to be restored upon ISR exit.
-        self.isr_recovery.push_context(cpu, evt_num);
-
-        // Move our magic into LR so that our ISR hw cleanup happens.
-        cpu.write_register(ArmRegister::Lr, TODO_ISR_RECOVERY_LR_VALUE)
-            .unwrap();
+    /// Take an exception like the processor does, see section B1.8.3 of the ARMv7-A and ARMv7-R
+    /// TRM.
+    ///
+    /// `cpsr` is written first so the backend switches to the banked `spsr` and `lr` of the
+    /// exception mode.
+    fn enter_exception(
+        &self,
+        cpu: &mut dyn CpuBackend,
+        vector: ExceptionVector,
+        evt_num: ExceptionNumber,
+    ) -> Result<(), UnknownError> {
+        let cpsr = cpu.read_register::<u32>(ArmRegister::Cpsr)?;
+        let old_pc = cpu.pc()? as u32;
+        let return_address = old_pc.wrapping_add(vector.return_offset(cpsr & CPSR_T != 0));
+
+        let new_cpsr = (cpsr & !(CPSR_MODE_MASK | CPSR_T)) | vector.mode() | vector.masks();
+        cpu.write_register(ArmRegister::Cpsr, new_cpsr)?;
+        cpu.write_register(ArmRegister::Spsr, cpsr)?;
+        cpu.write_register(ArmRegister::Lr, return_address)?;
 
         // Find the address of the appropriate exception vector.
-        let new_pc = self.vector_address(event_type);
+        let new_pc = self.vector_address(vector);
         trace!("Setting PC to {:#08X}, (EVT{})", new_pc, evt_num);
 
         // emit `styx_trace` interrupt ISR entry event
         strace!(InterruptEvent {
             etype: TraceEventType::INTERRUPT,
-            old_pc: cpu.pc().unwrap() as u32,
+            old_pc,
             new_pc,
             interrupt_num: evt_num,
             interrupt_type: InterruptType::IsrEntry,
@@ -684,39 +636,90 @@ impl Gic {
         });
 
         // Move pc to vector.
-        cpu.write_register(ArmRegister::Pc, new_pc).unwrap();
-    }
-
-    /// This is called by [`GicIsrRecovery`] to route the "post evt hook"
-    /// callbacks to the proper peripheral
-    #[inline]
-    fn post_irq_route_hook(&self, evt: ExceptionNumber) {
-        trace!(target: "interrupts", "{{\"type\": \"interrupts\", \"action\": \"complete\", \"event\": {}}}", evt);
+        cpu.write_register(ArmRegister::Pc, new_pc)?;
+        Ok(())
     }
 
     /// Calculates the address of the desired interrupt vector
-    fn vector_address(&self, event_type: EventType) -> u32 {
-        let vector_index = match event_type {
-            EventType::Exception(exception_id) => exception_id as u32,
-            EventType::Interrupt(_) => {
-                // TODO: check if this should be an FIQ.
-                // If FIQs are enabled (FIQen bit in the ICPICR Register), go to the FIQ interrupt
-                // vector.
-                ExceptionVector::Irq as u32
-            }
-        };
-
+    fn vector_address(&self, vector: ExceptionVector) -> u32 {
         // Index into the exception vector table for the appropriate vector.
-        self.config.vba + (vector_index * 4)
+        self.config.vba + (vector as u32 * 4)
     }
 
     pub fn initialize(&self, vba: u32, cba: u32) -> Result<(), UnknownError> {
+        let registers = GicRegisters::new(cba);
+        self.initialize_at(
+            vba,
+            registers.distributor_base,
+            registers.cpu_interface_base,
+        )
+    }
+
+    /// Like [`Gic::initialize()`] with the distributor and the CPU interface at any address
+    /// instead of their Cortex-A9 offsets from the configuration base address, e.g. a GIC-400.
+    pub fn initialize_at(
+        &self,
+        vba: u32,
+        distributor_base: u32,
+        cpu_interface_base: u32,
+    ) -> Result<(), UnknownError> {
         if self.config.init(GicConfig { vba }).is_err() {
             return Err(GicError::InitializationFailure.into());
         }
-        if self.registers.init(GicRegisters::new(cba)).is_err() {
+        let registers = GicRegisters {
+            cpu_interface_base,
+            distributor_base,
+        };
+        if self.registers.init(registers).is_err() {
             return Err(GicError::InitializationFailure.into());
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_words() {
+        let mut data = [0u8; 6];
+        let mut reads = Vec::new();
+        read_words(0x102, &mut data, |word| {
+            reads.push(word);
+            0x4433_2211 + word
+        });
+        assert_eq!(vec![0x100, 0x104], reads);
+        assert_eq!([0x33, 0x44, 0x15, 0x23, 0x33, 0x44], data);
+    }
+
+    #[test]
+    fn test_write_words() {
+        assert_eq!(
+            vec![(0x10, 0x0201_0000, 0xFFFF_0000), (0x14, 0x0403, 0xFFFF)],
+            write_words(0x12, &[0x01, 0x02, 0x03, 0x04])
+        );
+        assert_eq!(vec![(0x0, 0xAA00, 0xFF00)], write_words(0x1, &[0xAA]));
+    }
+
+    #[test]
+    fn test_exception_entry_state() {
+        assert_eq!(MODE_IRQ, ExceptionVector::Irq.mode());
+        assert_eq!(CPSR_I | CPSR_F | CPSR_A, ExceptionVector::Fiq.masks());
+        assert_eq!(CPSR_I, ExceptionVector::SupervisorCall.masks());
+        assert_eq!(2, ExceptionVector::UndefinedInstruction.return_offset(true));
+        assert_eq!(8, ExceptionVector::DataAbort.return_offset(false));
+    }
+
+    #[test]
+    fn test_latch() {
+        let mut gic = Gic::default();
+        assert!(gic.latch(72).is_ok());
+        assert!(gic.distributor.interrupt(72).unwrap().pending);
+        assert!(matches!(
+            gic.latch(NUM_INTERRUPTS as ExceptionNumber),
+            Err(ActivateIRQnError::InvalidIRQn(_))
+        ));
+        assert!(gic.latch(-1).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! GICv3 redistributor (GICR) register model.
+//!
+//! The redistributor of the emulated core has two 64KiB frames. The `RD_base` frame holds the
+//! control, identification and power management registers, the `SGI_base` frame holds the
+//! configuration of the SGIs and PPIs. Their state lives in the [Distributor] banked registers
+//! for CPU 0, so `SGI_base` registers are forwarded to the distributor at the same offset.
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+use crate::distributor::Distributor;
+
+/// Size of each redistributor frame.
+pub(crate) const FRAME_SIZE: u32 = 0x1_0000;
+/// Both frames of the redistributor of the emulated core.
+pub(crate) const REDISTRIBUTOR_SIZE: u32 = 2 * FRAME_SIZE;
+
+/// Peripheral ID2 register, `ArchRev` in bits 7-4, same offset in every GICv3 frame.
+pub(crate) const PIDR2: u32 = 0xFFE8;
+/// GICv3 with the ARM implementer code in the low bits.
+pub(crate) const PIDR2_VALUE: u32 = 0x3B;
+
+/// Redistributor Control Register
+const GICR_CTLR: u32 = 0x0000;
+/// Redistributor Implementer Identification Register
+const GICR_IIDR: u32 = 0x0004;
+const GICR_IIDR_VALUE: u32 = 0x0100_043B;
+/// Redistributor Type Register, 64 bits.
+const GICR_TYPER: u32 = 0x0008;
+/// The redistributor is the last one of the GIC.
+const GICR_TYPER_LAST: u32 = 1 << 4;
+/// Redistributor Wake Register
+const GICR_WAKER: u32 = 0x0014;
+const GICR_WAKER_PROCESSOR_SLEEP: u32 = 1 << 1;
+const GICR_WAKER_CHILDREN_ASLEEP: u32 = 1 << 2;
+
+/// `SGI_base` registers for interrupts 0-31 forwarded to the distributor, `(offset, length)`.
+const SGI_FRAME_REGISTERS: [(u32, u32); 9] = [
+    // IGROUPR0
+    (0x0080, 4),
+    // ISENABLER0
+    (0x0100, 4),
+    // ICENABLER0
+    (0x0180, 4),
+    // ISPENDR0
+    (0x0200, 4),
+    // ICPENDR0
+    (0x0280, 4),
+    // ISACTIVER0
+    (0x0300, 4),
+    // ICACTIVER0
+    (0x0380, 4),
+    // IPRIORITYR0-7
+    (0x0400, 32),
+    // ICFGR0-1
+    (0x0C00, 8),
+];
+
+/// State of the redistributor registers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Redistributor {
+    /// `GICR_WAKER[ProcessorSleep]`, `ChildrenAsleep` follows it immediately.
+    processor_sleep: bool,
+}
+
+impl Default for Redistributor {
+    fn default() -> Self {
+        Self {
+            processor_sleep: true,
+        }
+    }
+}
+
+/// `offset` in the `SGI_base` frame is a register forwarded to the distributor.
+fn forwarded(offset: u32) -> bool {
+    SGI_FRAME_REGISTERS
+        .iter()
+        .any(|(base, len)| (*base..base + len).contains(&offset))
+}
+
+impl Redistributor {
+    /// Read the word register at `offset` from the redistributor base.
+    pub(crate) fn read(&self, distributor: &Distributor, offset: u32) -> u32 {
+        if offset >= FRAME_SIZE {
+            let offset = offset - FRAME_SIZE;
+            return match offset {
+                _ if forwarded(offset) => distributor.read(offset),
+                PIDR2 => PIDR2_VALUE,
+                _ => {
+                    trace!("read of unimplemented redistributor SGI register {offset:#x}");
+                    0
+                }
+            };
+        }
+
+        match offset {
+            GICR_CTLR => 0,
+            GICR_IIDR => GICR_IIDR_VALUE,
+            // processor number and affinity are 0
+            GICR_TYPER => GICR_TYPER_LAST,
+            GICR_WAKER if self.processor_sleep => {
+                GICR_WAKER_PROCESSOR_SLEEP | GICR_WAKER_CHILDREN_ASLEEP
+            }
+            GICR_WAKER => 0,
+            PIDR2 => PIDR2_VALUE,
+            _ => {
+                trace!("read of unimplemented redistributor register {offset:#x}");
+                0
+            }
+        }
+    }
+
+    /// Write the bytes of `value` selected by `mask` to the word register at `offset` from the
+    /// redistributor base.
+    pub(crate) fn write(
+        &mut self,
+        distributor: &mut Distributor,
+        offset: u32,
+        value: u32,
+        mask: u32,
+    ) {
+        if offset >= FRAME_SIZE {
+            let offset = offset - FRAME_SIZE;
+            if forwarded(offset) {
+                distributor.write(offset, value, mask);
+            } else {
+                debug!("write to unimplemented redistributor SGI register {offset:#x}: {value:#x}");
+            }
+            return;
+        }
+
+        match offset {
+            GICR_WAKER if mask & GICR_WAKER_PROCESSOR_SLEEP != 0 => {
+                self.processor_sleep = value & GICR_WAKER_PROCESSOR_SLEEP != 0;
+                trace!("redistributor processor sleep: {}", self.processor_sleep);
+            }
+            _ => debug!("write to unimplemented redistributor register {offset:#x}: {value:#x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: u32 = 0xFFFF_FFFF;
+
+    #[test]
+    fn test_wake() {
+        let mut redistributor = Redistributor::default();
+        let mut distributor = Distributor::default();
+        assert_eq!(
+            GICR_WAKER_PROCESSOR_SLEEP | GICR_WAKER_CHILDREN_ASLEEP,
+            redistributor.read(&distributor, GICR_WAKER)
+        );
+        redistributor.write(&mut distributor, GICR_WAKER, 0, ALL);
+        assert_eq!(0, redistributor.read(&distributor, GICR_WAKER));
+        assert_eq!(
+            GICR_TYPER_LAST,
+            redistributor.read(&distributor, GICR_TYPER)
+        );
+        assert_eq!(PIDR2_VALUE, redistributor.read(&distributor, PIDR2));
+    }
+
+    #[test]
+    fn test_sgi_frame() {
+        let mut redistributor = Redistributor::default();
+        let mut distributor = Distributor::default();
+
+        // ISENABLER0 bit 27 is the virtual timer PPI
+        redistributor.write(&mut distributor, FRAME_SIZE + 0x100, 1 << 27, ALL);
+        assert!(distributor.interrupt(27).unwrap().enabled);
+        assert_eq!(
+            (1 << 27) | 0xFFFF,
+            redistributor.read(&distributor, FRAME_SIZE + 0x100)
+        );
+
+        // IPRIORITYR6 byte 3
+        redistributor.write(&mut distributor, FRAME_SIZE + 0x418, 0xA000_0000, ALL);
+        assert_eq!(0xA0, distributor.interrupt(27).unwrap().priority);
+
+        // SPI registers are not part of the redistributor
+        redistributor.write(&mut distributor, FRAME_SIZE + 0x104, 1, ALL);
+        assert!(!distributor.interrupt(32).unwrap().enabled);
+        assert_eq!(0, redistributor.read(&distributor, FRAME_SIZE + 0x104));
+    }
+}
@@ -0,0 +1,594 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! GICv3 for AArch64 processors.
+//!
+//! [Gicv3] reuses the [distributor](crate::distributor) and
+//! [CPU interface](crate::cpu_interface) models of the [Gic](crate::Gic) and adds the
+//! [redistributor](crate::redistributor) of the emulated core:
+//!
+//!  Frame | Size   | Description
+//! ----------------------------------------------------------
+//!  GICD  | 64KiB  | distributor, SPIs are routed to CPU 0 unless `GICD_IROUTERn` says otherwise
+//!  GICR  | 128KiB | `RD_base` and `SGI_base` frames of the redistributor of CPU 0
+//!  GICC  | 8KiB   | memory mapped CPU interface
+//!
+//! The CPU interface is only memory mapped, the `ICC_*` system registers are not implemented so
+//! `ICC_SRE_EL1.SRE` reads as 0 and SGIs can only be raised through `GICD_SGIR`.
+//!
+//! Exceptions are taken to EL1 with the AArch64 vector table at `VBAR_EL1`:
+//!
+//!  Offset | Taken from
+//! ----------------------------------------------------------
+//!  0x000  | EL1 using `SP_EL0`
+//!  0x200  | EL1 using `SP_EL1`
+//!  0x400  | EL0
+//!
+//! with the synchronous vector at `+0x0`, IRQ at `+0x80` and FIQ at `+0x100`. On entry PSTATE is
+//! saved to `SPSR_EL1` and the preferred return address to `ELR_EL1`, the core switches to EL1h
+//! with `D`, `A`, `I` and `F` set.
+//!
+//! Supervisor calls are the only synchronous exception taken from the interrupt hook,
+//! `ESR_EL1` reports them with the immediate of the `svc` instruction.
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use styx_core::{
+    arch::aarch64::Aarch64Register,
+    event_controller::{
+        ActivateIRQnError, Exception, InterruptExecuted, OptionalFeatureError, Peripherals,
+    },
+    prelude::*,
+    snapshot::ComponentState,
+};
+use tracing::{debug, trace};
+
+use crate::cpu_interface::CpuInterface;
+use crate::distributor::{Distributor, CPU_ID, NUM_INTERRUPTS};
+use crate::redistributor::{Redistributor, PIDR2, PIDR2_VALUE, REDISTRIBUTOR_SIZE};
+use crate::{read_words, write_words, GicError, GIC_EXCEPTION_IRQN};
+
+/// Size of the distributor frame.
+const DISTRIBUTOR_SIZE: u32 = 0x1_0000;
+/// Size of the memory mapped CPU interface.
+const CPU_INTERFACE_SIZE: u32 = 0x2000;
+
+/// Distributor Control Register
+const GICD_CTLR: u32 = 0x0000;
+/// Affinity routing enable, stored so software reads back what it wrote.
+const GICD_CTLR_ARE: u32 = 1 << 4;
+/// Interrupt Routing Registers, 64 bits per SPI.
+const GICD_IROUTER: u32 = 0x6100;
+/// Upper word of the last `GICD_IROUTERn`.
+const GICD_IROUTER_LAST: u32 = 0x6000 + 8 * NUM_INTERRUPTS as u32 - 4;
+/// `Interrupt_Routing_Mode`, route to any participating core.
+const GICD_IROUTER_IRM: u32 = 1 << 31;
+/// `Aff2`, `Aff1` and `Aff0`, `Aff3` is in the upper word and ignored.
+const GICD_IROUTER_AFFINITY: u32 = 0xFF_FFFF;
+
+/// Interrupts below this ID are private to the core and not routed.
+const NUM_PRIVATE_INTERRUPTS: usize = 32;
+
+/// Interrupt number of a supervisor call, QEMU's `EXCP_SWI`, raised by both backends.
+const SVC_INTNO: i32 = 2;
+
+const PSTATE_SP: u32 = 1;
+const PSTATE_EL_SHIFT: u32 = 2;
+const PSTATE_EL_MASK: u32 = 0b11;
+const PSTATE_F: u32 = 1 << 6;
+const PSTATE_I: u32 = 1 << 7;
+const PSTATE_A: u32 = 1 << 8;
+const PSTATE_D: u32 = 1 << 9;
+/// EL1 using `SP_EL1`.
+const PSTATE_EL1H: u32 = (1 << PSTATE_EL_SHIFT) | PSTATE_SP;
+
+/// `ESR_ELx.EC` of a supervisor call from AArch64.
+const ESR_EC_SVC64: u64 = 0x15;
+const ESR_EC_SHIFT: u32 = 26;
+/// `ESR_ELx.IL`, 32 bit instruction.
+const ESR_IL: u64 = 1 << 25;
+
+/// Kind of exception, the offset in each group of the vector table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExceptionKind {
+    Synchronous = 0x000,
+    Irq = 0x080,
+    Fiq = 0x100,
+}
+
+/// Offset into the vector table of `kind` taken from `pstate`.
+fn vector_offset(kind: ExceptionKind, pstate: u32) -> u64 {
+    let el = (pstate >> PSTATE_EL_SHIFT) & PSTATE_EL_MASK;
+    let group = if el == 0 {
+        // lower exception level using AArch64
+        0x400
+    } else if pstate & PSTATE_SP != 0 {
+        0x200
+    } else {
+        0x000
+    };
+    group + kind as u64
+}
+
+/// Base addresses of the memory mapped GICv3 frames.
+#[derive(Debug, Clone, Copy)]
+struct Gicv3Bases {
+    distributor: u64,
+    redistributor: u64,
+    cpu_interface: u64,
+}
+
+/// Serialized [`Gicv3`] state.
+#[derive(Serialize, Deserialize)]
+struct Gicv3State {
+    distributor: Distributor,
+    redistributor: Redistributor,
+    cpu_interface: CpuInterface,
+    affinity_routing: bool,
+    routes: Vec<u32>,
+}
+
+/// GICv3 event controller for AArch64, see the [module documentation](self).
+pub struct Gicv3 {
+    distributor: Distributor,
+    redistributor: Redistributor,
+    /// CPU interface of the emulated core.
+    cpu_interface: CpuInterface,
+    /// `GICD_CTLR.ARE`
+    affinity_routing: bool,
+    /// Lower words of `GICD_IROUTERn` for every SPI.
+    routes: Vec<u32>,
+    /// Interrupt completed by the last `GICC_EOIR` write, handed to
+    /// [`EventControllerImpl::finish_interrupt`].
+    completed: Option<ExceptionNumber>,
+    bases: Gicv3Bases,
+}
+
+impl Gicv3 {
+    /// GICv3 with the distributor, redistributor and CPU interface frames at the given
+    /// addresses.
+    pub fn new(distributor_base: u64, redistributor_base: u64, cpu_interface_base: u64) -> Self {
+        Self {
+            distributor: Self::reset_distributor(),
+            redistributor: Default::default(),
+            cpu_interface: Default::default(),
+            affinity_routing: false,
+            routes: vec![0; NUM_INTERRUPTS - NUM_PRIVATE_INTERRUPTS],
+            completed: None,
+            bases: Gicv3Bases {
+                distributor: distributor_base,
+                redistributor: redistributor_base,
+                cpu_interface: cpu_interface_base,
+            },
+        }
+    }
+
+    /// Distributor with every SPI routed to the emulated core, like a zeroed `GICD_IROUTERn`.
+    fn reset_distributor() -> Distributor {
+        let mut distributor = Distributor::default();
+        for id in NUM_PRIVATE_INTERRUPTS..NUM_INTERRUPTS {
+            if let Some(interrupt) = distributor.interrupt_mut(id) {
+                interrupt.targets = 1 << CPU_ID;
+            }
+        }
+        distributor
+    }
+
+    /// Read the distributor word register at `offset`, adding the GICv3 registers.
+    fn distributor_read(&self, offset: u32) -> u32 {
+        match offset {
+            GICD_CTLR if self.affinity_routing => self.distributor.read(offset) | GICD_CTLR_ARE,
+            GICD_IROUTER..=GICD_IROUTER_LAST if offset % 8 == 0 => {
+                self.routes[((offset - GICD_IROUTER) / 8) as usize]
+            }
+            // Aff3
+            GICD_IROUTER..=GICD_IROUTER_LAST => 0,
+            PIDR2 => PIDR2_VALUE,
+            _ => self.distributor.read(offset),
+        }
+    }
+
+    /// Write the distributor word register at `offset`, adding the GICv3 registers.
+    fn distributor_write(&mut self, offset: u32, value: u32, mask: u32) {
+        match offset {
+            GICD_CTLR => {
+                if mask & GICD_CTLR_ARE != 0 {
+                    self.affinity_routing = value & GICD_CTLR_ARE != 0;
+                }
+                self.distributor.write(offset, value, mask);
+            }
+            GICD_IROUTER..=GICD_IROUTER_LAST if offset % 8 == 0 => {
+                let index = ((offset - GICD_IROUTER) / 8) as usize;
+                let route = &mut self.routes[index];
+                *route = ((*route & !mask) | (value & mask))
+                    & (GICD_IROUTER_IRM | GICD_IROUTER_AFFINITY);
+
+                // the emulated core has affinity 0
+                let targets_core = *route & GICD_IROUTER_IRM != 0 || *route == 0;
+                let id = index + NUM_PRIVATE_INTERRUPTS;
+                trace!("SPI {id} routed to core: {targets_core}");
+                if let Some(interrupt) = self.distributor.interrupt_mut(id) {
+                    interrupt.targets = if targets_core { 1 << CPU_ID } else { 0 };
+                }
+            }
+            GICD_IROUTER..=GICD_IROUTER_LAST => trace!("ignored write to Aff3 of {offset:#x}"),
+            _ => {
+                if let Some(sgi) = self.distributor.write(offset, value, mask) {
+                    trace!("Latching SGI: interrupt ID {}", sgi);
+                    self.distributor.set_pending(sgi);
+                }
+            }
+        }
+    }
+
+    /// Setup the runtime memory hooks of the memory mapped registers and the exception hook.
+    fn register_hooks(&self, cpu: &mut dyn CpuBackend) -> Result<(), UnknownError> {
+        let frames: [(u64, u32, Box<dyn MemoryReadHook>, Box<dyn MemoryWriteHook>); 3] = [
+            (
+                self.bases.distributor,
+                DISTRIBUTOR_SIZE,
+                Box::new(distributor_read_hook),
+                Box::new(distributor_write_hook),
+            ),
+            (
+                self.bases.redistributor,
+                REDISTRIBUTOR_SIZE,
+                Box::new(redistributor_read_hook),
+                Box::new(redistributor_write_hook),
+            ),
+            (
+                self.bases.cpu_interface,
+                CPU_INTERFACE_SIZE,
+                Box::new(cpu_interface_read_hook),
+                Box::new(cpu_interface_write_hook),
+            ),
+        ];
+        for (start, size, read_hook, write_hook) in frames {
+            let end = start + size as u64 - 1;
+            cpu.mem_read_hook(start, end, read_hook)?;
+            cpu.mem_write_hook(start, end, write_hook)?;
+        }
+
+        cpu.intr_hook(Box::new(handle_interrupts))?;
+
+        Ok(())
+    }
+
+    /// Take an exception to EL1 like the processor does.
+    ///
+    /// `return_address` is saved to `ELR_EL1` and `syndrome` to `ESR_EL1` for synchronous
+    /// exceptions. PSTATE is written last so the backend switches to `SP_EL1` with the saved
+    /// state already in place.
+    fn enter_exception(
+        &self,
+        cpu: &mut dyn CpuBackend,
+        kind: ExceptionKind,
+        return_address: u64,
+        syndrome: Option<u64>,
+        evt_num: ExceptionNumber,
+    ) -> Result<(), UnknownError> {
+        let pstate = cpu.read_register::<u32>(Aarch64Register::Cpsr)?;
+        let vbar = cpu.read_register::<u64>(Aarch64Register::VbarEl1)?;
+        let old_pc = cpu.pc()?;
+
+        cpu.write_register(Aarch64Register::SpsrEl1, pstate as u64)?;
+        cpu.write_register(Aarch64Register::ElrEl1, return_address)?;
+        if let Some(syndrome) = syndrome {
+            cpu.write_register(Aarch64Register::EsrEl1, syndrome)?;
+        }
+        let masks = PSTATE_D | PSTATE_A | PSTATE_I | PSTATE_F;
+        cpu.write_register(Aarch64Register::Cpsr, PSTATE_EL1H | masks)?;
+
+        let new_pc = vbar + vector_offset(kind, pstate);
+        trace!("Setting PC to {:#08X}, ({kind:?} EVT{})", new_pc, evt_num);
+
+        // emit `styx_trace` interrupt ISR entry event
+        strace!(InterruptEvent {
+            etype: TraceEventType::INTERRUPT,
+            old_pc: old_pc as u32,
+            new_pc: new_pc as u32,
+            interrupt_num: evt_num,
+            interrupt_type: InterruptType::IsrEntry,
+            ..Default::default()
+        });
+
+        cpu.set_pc(new_pc)?;
+        Ok(())
+    }
+}
+
+impl EventControllerImpl for Gicv3 {
+    /// Takes the interrupt signaled by the CPU interface, if PSTATE does not mask it.
+    fn next(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        let Some(signal) = self.cpu_interface.signal(&self.distributor) else {
+            return Ok(InterruptExecuted::NotExecuted);
+        };
+
+        let (kind, mask) = if signal.fiq {
+            (ExceptionKind::Fiq, PSTATE_F)
+        } else {
+            (ExceptionKind::Irq, PSTATE_I)
+        };
+        if cpu.read_register::<u32>(Aarch64Register::Cpsr)? & mask != 0 {
+            return Ok(InterruptExecuted::NotExecuted);
+        }
+
+        let evt_num = signal.id as ExceptionNumber;
+        trace!(
+            target: "interrupts",
+            "{{\"type\": \"interrupts\", \"action\": \"execute\", \"event\": {}}}",
+            evt_num
+        );
+        // interrupts are taken between instructions, pc is the preferred return address
+        let return_address = cpu.pc()?;
+        self.enter_exception(cpu, kind, return_address, None, evt_num)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    fn latch(&mut self, evt: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+        trace!("Latching EVT: {}", evt);
+
+        let id = usize::try_from(evt).map_err(|_| ActivateIRQnError::InvalidIRQn(evt))?;
+        if !self.distributor.set_pending(id) {
+            return Err(ActivateIRQnError::InvalidIRQn(evt));
+        }
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        _irq: ExceptionNumber,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Result<InterruptExecuted, ActivateIRQnError> {
+        Ok(InterruptExecuted::NotExecuted)
+    }
+
+    fn finish_interrupt(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Option<ExceptionNumber> {
+        self.completed.take()
+    }
+
+    /// `WFI` wakes up on a signaled interrupt even if the processor masks it.
+    fn has_pending_events(&self) -> bool {
+        self.cpu_interface.signal(&self.distributor).is_some()
+    }
+
+    fn init(&mut self, cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.register_hooks(cpu)
+    }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.distributor = Self::reset_distributor();
+        self.redistributor = Default::default();
+        self.cpu_interface = Default::default();
+        self.affinity_routing = false;
+        self.routes.fill(0);
+        self.completed = None;
+        Ok(())
+    }
+
+    fn current_exception(&mut self) -> Result<Option<Exception>, OptionalFeatureError> {
+        Ok(self.cpu_interface.current().map(|id| Exception {
+            name: format!("IRQ{id}").into(),
+            number: id as ExceptionNumber,
+        }))
+    }
+
+    fn available_exceptions(&mut self) -> Result<Cow<'_, [Exception]>, OptionalFeatureError> {
+        Ok((0..NUM_INTERRUPTS)
+            .map(|id| Exception {
+                name: format!("IRQ{id}").into(),
+                number: id as ExceptionNumber,
+            })
+            .collect())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&Gicv3State {
+            distributor: self.distributor.clone(),
+            redistributor: self.redistributor.clone(),
+            cpu_interface: self.cpu_interface.clone(),
+            affinity_routing: self.affinity_routing,
+            routes: self.routes.clone(),
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: Gicv3State = state.get()?;
+        self.distributor = state.distributor;
+        self.redistributor = state.redistributor;
+        self.cpu_interface = state.cpu_interface;
+        self.affinity_routing = state.affinity_routing;
+        self.routes = state.routes;
+        self.completed = None;
+        Ok(())
+    }
+}
+
+/// Take the synchronous exception of a backend interrupt, only supervisor calls are supported.
+///
+/// The backends report the instruction after the `svc`, which is the preferred return address.
+fn handle_interrupts(proc: CoreHandle, intno: i32) -> Result<(), UnknownError> {
+    if intno != SVC_INTNO {
+        return Err(GicError::UnhandledException(intno).into());
+    }
+
+    let return_address = proc.cpu.pc()?;
+    let instruction = proc
+        .mmu
+        .code()
+        .read(return_address.wrapping_sub(4))
+        .le()
+        .u32()?;
+    let imm16 = ((instruction >> 5) & 0xFFFF) as u64;
+    debug!("supervisor call #{imm16:#x}");
+    let syndrome = (ESR_EC_SVC64 << ESR_EC_SHIFT) | ESR_IL | imm16;
+
+    let gic = proc.event_controller.get_impl::<Gicv3>()?;
+    gic.enter_exception(
+        proc.cpu,
+        ExceptionKind::Synchronous,
+        return_address,
+        Some(syndrome),
+        GIC_EXCEPTION_IRQN,
+    )
+}
+
+fn distributor_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let gic = proc.event_controller.get_impl::<Gicv3>()?;
+    let offset = (address - gic.bases.distributor) as u32;
+    read_words(offset, &mut data[..size as usize], |word| {
+        gic.distributor_read(word)
+    });
+    Ok(())
+}
+
+fn distributor_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let gic = proc.event_controller.get_impl::<Gicv3>()?;
+    let offset = (address - gic.bases.distributor) as u32;
+    for (word, value, mask) in write_words(offset, &data[..size as usize]) {
+        gic.distributor_write(word, value, mask);
+    }
+    Ok(())
+}
+
+fn redistributor_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let gic = proc.event_controller.get_impl::<Gicv3>()?;
+    let offset = (address - gic.bases.redistributor) as u32;
+    read_words(offset, &mut data[..size as usize], |word| {
+        gic.redistributor.read(&gic.distributor, word)
+    });
+    Ok(())
+}
+
+fn redistributor_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let gic = proc.event_controller.get_impl::<Gicv3>()?;
+    let offset = (address - gic.bases.redistributor) as u32;
+    for (word, value, mask) in write_words(offset, &data[..size as usize]) {
+        gic.redistributor
+            .write(&mut gic.distributor, word, value, mask);
+    }
+    Ok(())
+}
+
+fn cpu_interface_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let gic = proc.event_controller.get_impl::<Gicv3>()?;
+    let offset = (address - gic.bases.cpu_interface) as u32;
+    let Gicv3 {
+        distributor,
+        cpu_interface,
+        ..
+    } = gic;
+    read_words(offset, &mut data[..size as usize], |word| {
+        cpu_interface.read(distributor, word)
+    });
+    Ok(())
+}
+
+fn cpu_interface_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let mut completed = None;
+    {
+        let gic = proc.event_controller.get_impl::<Gicv3>()?;
+        let offset = (address - gic.bases.cpu_interface) as u32;
+        for (word, value, mask) in write_words(offset, &data[..size as usize]) {
+            if let Some(id) = gic
+                .cpu_interface
+                .write(&mut gic.distributor, word, value, mask)
+            {
+                completed = Some(id as ExceptionNumber);
+            }
+        }
+        gic.completed = completed;
+    }
+
+    if let Some(evt_num) = completed {
+        let pc = proc.cpu.pc()? as u32;
+        strace!(InterruptEvent {
+            etype: TraceEventType::INTERRUPT,
+            old_pc: pc,
+            new_pc: pc,
+            interrupt_num: evt_num,
+            interrupt_type: InterruptType::IsrExit,
+            ..Default::default()
+        });
+        trace!(target: "interrupts", "{{\"type\": \"interrupts\", \"action\": \"complete\", \"event\": {}}}", evt_num);
+
+        // route the post event hook to the peripheral of the completed interrupt
+        proc.event_controller.finish_interrupt(proc.cpu, proc.mmu);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: u32 = 0xFFFF_FFFF;
+
+    #[test]
+    fn test_vector_offset() {
+        // EL1h
+        assert_eq!(0x280, vector_offset(ExceptionKind::Irq, 0x3C5));
+        // EL1t
+        assert_eq!(0x100, vector_offset(ExceptionKind::Fiq, 0x3C4));
+        // EL0
+        assert_eq!(0x400, vector_offset(ExceptionKind::Synchronous, 0x0));
+    }
+
+    #[test]
+    fn test_routing() {
+        let mut gic = Gicv3::new(0x0800_0000, 0x080A_0000, 0x0801_0000);
+        // SPIs start out routed to the emulated core
+        assert_eq!(0, gic.distributor_read(GICD_IROUTER + 8));
+        assert_eq!(1, gic.distributor.interrupt(33).unwrap().targets);
+
+        // affinity 1 is not the emulated core
+        gic.distributor_write(GICD_IROUTER + 8, 0x1, ALL);
+        assert_eq!(0, gic.distributor.interrupt(33).unwrap().targets);
+        assert_eq!(0x1, gic.distributor_read(GICD_IROUTER + 8));
+        gic.distributor_write(GICD_IROUTER + 8, GICD_IROUTER_IRM, ALL);
+        assert_eq!(1, gic.distributor.interrupt(33).unwrap().targets);
+        assert_eq!(GICD_IROUTER_IRM, gic.distributor_read(GICD_IROUTER + 8));
+
+        gic.distributor_write(GICD_CTLR, GICD_CTLR_ARE | 0x2, ALL);
+        assert_eq!(GICD_CTLR_ARE | 0x2, gic.distributor_read(GICD_CTLR));
+        assert_eq!(PIDR2_VALUE, gic.distributor_read(PIDR2));
+    }
+}
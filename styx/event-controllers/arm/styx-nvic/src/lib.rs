@@ -102,18 +102,25 @@
 //! 0xFFFFFFFD | Thread mode  | PSP   | Basic
 //!
 //! The NVIC maps a chunk of memory at 0xffff_f000 with size 0x1000 to deal with these return values.
+//!
+//! The [SysTickTimer] raises the SysTick exception.
 use binary_heap_plus::{BinaryHeap, MinComparator};
 use consts::*;
+use serde::{Deserialize, Serialize};
 use styx_core::cpu::arch::arm::ArmRegister;
 use styx_core::event_controller::*;
 use styx_core::memory::MemoryPermissions;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_core::sync::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU8, Ordering};
 use styx_core::sync::sync::{Arc, Mutex, RwLock};
 use thiserror::Error;
 use tracing::{debug, error, trace, warn};
 
 mod hooks;
+mod systick;
+
+pub use systick::SysTickTimer;
 
 type LatchedEvents<T> = Arc<Mutex<BinaryHeap<T, MinComparator>>>;
 
@@ -185,13 +192,13 @@ pub enum NvicError {
     EmptyIrq(ExceptionNumber),
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 enum CPUMode {
     Handler,
     Thread,
 }
 
-#[derive(PartialEq, Eq, Default, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Default, Debug, Clone, Copy, Serialize, Deserialize)]
 struct Exception {
     pub irqn: ExceptionNumber,
     enabled: bool,
@@ -243,7 +250,7 @@ impl Exception {
 }
 
 /// A struct to keep track of the misc flags used to modify the NVIC's behavior
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NVICFlags {
     // is the floating-point (fp) extension implemented for this device
     has_fp_ext: bool,
@@ -275,6 +282,21 @@ impl Default for NVICFlags {
     }
 }
 
+/// Serialized [`Nvic`] state, see [`EventControllerImpl::save_state()`].
+#[derive(Serialize, Deserialize)]
+struct NvicState {
+    cpu_mode: CPUMode,
+    latched_events: Vec<Exception>,
+    executing_interrupt: bool,
+    exceptions: Vec<Exception>,
+    vector_table_offset: u32,
+    current_priority: Vec<i16>,
+    priority_grouping: u8,
+    max_irq: i32,
+    flags: NVICFlags,
+    current_irqn: Option<ExceptionNumber>,
+}
+
 /// emulation of a Nested Vectored Interrupt Controller
 pub struct Nvic {
     /// The current operating mode of the processor
@@ -505,12 +527,63 @@ impl EventControllerImpl for Nvic {
         self.current_irqn
     }
 
+    fn has_pending_events(&self) -> bool {
+        !self.latched_events.lock().unwrap().is_empty()
+    }
+
     fn init(&mut self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
         self.register_hooks(cpu, mmu)?;
         self.reset_state(cpu, mmu)?;
 
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&NvicState {
+            cpu_mode: *self.cpu_mode.read().unwrap(),
+            latched_events: self
+                .latched_events
+                .lock()
+                .unwrap()
+                .clone()
+                .into_sorted_vec(),
+            executing_interrupt: self.executing_interrupt.load(Ordering::Acquire),
+            exceptions: self.exceptions.read().unwrap().to_vec(),
+            vector_table_offset: self.vector_table_offset.load(Ordering::Acquire),
+            current_priority: self.current_priority.lock().unwrap().clone(),
+            priority_grouping: self.priority_grouping.load(Ordering::Acquire),
+            max_irq: self.max_irq.load(Ordering::Acquire),
+            flags: self.flags.lock().unwrap().clone(),
+            current_irqn: self.current_irqn,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: NvicState = state.get()?;
+
+        *self.cpu_mode.write().unwrap() = state.cpu_mode;
+        {
+            let mut latched = self.latched_events.lock().unwrap();
+            latched.clear();
+            latched.extend(state.latched_events);
+        }
+        self.executing_interrupt
+            .store(state.executing_interrupt, Ordering::Release);
+        *self.exceptions.write().unwrap() = state
+            .exceptions
+            .try_into()
+            .map_err(|_| anyhow!("saved nvic state has the wrong number of exceptions"))?;
+        self.vector_table_offset
+            .store(state.vector_table_offset, Ordering::Release);
+        *self.current_priority.lock().unwrap() = state.current_priority;
+        self.priority_grouping
+            .store(state.priority_grouping, Ordering::Release);
+        self.max_irq.store(state.max_irq, Ordering::Release);
+        *self.flags.lock().unwrap() = state.flags;
+        self.current_irqn = state.current_irqn;
+
+        Ok(())
+    }
 }
 
 impl Nvic {
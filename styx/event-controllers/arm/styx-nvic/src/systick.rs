@@ -1,10 +1,16 @@
 // SPDX-License-Identifier: BSD-2-Clause
+//! The Armv6-M/Armv7-M/Armv8-M SysTick timer.
+use serde::{Deserialize, Serialize};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use tracing::debug;
 
 const SYSTICK_IRQN: ExceptionNumber = -1;
 const SYSTICK_PERIOD: u64 = 10000;
 
+/// SysTick timer, raises the SysTick exception every 10000 instructions once the guest enables
+/// the counter and its interrupt in `SYST_CSR`.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SysTickTimer {
     guest_enabled: bool,
     interrupt_enabled: bool,
@@ -24,10 +30,10 @@ impl Peripheral for SysTickTimer {
         delta: &styx_core::executor::Delta,
     ) -> Result<(), UnknownError> {
         if self.guest_enabled {
-            let _ = self.internal_counter.saturating_add(delta.count);
+            self.internal_counter = self.internal_counter.saturating_add(delta.count);
 
             if self.internal_counter >= SYSTICK_PERIOD {
-                self.internal_counter = 0;
+                self.internal_counter %= SYSTICK_PERIOD;
 
                 // TODO: set COUNTFLAG bit in SYST_CSR
 
@@ -41,6 +47,11 @@ impl Peripheral for SysTickTimer {
         Ok(())
     }
 
+    fn next_deadline(&self) -> Option<u64> {
+        (self.guest_enabled && self.interrupt_enabled)
+            .then(|| SYSTICK_PERIOD - self.internal_counter)
+    }
+
     fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
         proc.core
             .cpu
@@ -59,6 +70,15 @@ impl Peripheral for SysTickTimer {
 
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(self)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        *self = state.get()?;
+        Ok(())
+    }
 }
 
 impl SysTickTimer {
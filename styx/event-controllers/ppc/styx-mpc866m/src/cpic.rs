@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! CPM interrupt controller (CPIC), `CICR`/`CIPR`/`CIMR`/`CISR`/`CIVR`.
+//!
+//! The CPIC prioritizes the interrupt sources of the communications processor and forwards a
+//! single level sensitive request to the SIU, on the level selected by `CICR[IRL]`.
+//!
+//! - A source's bit in `CIPR`, `CIMR` and `CISR` is `1 << vector`.
+//! - Higher vectors have higher priority, except that the source in `CICR[HP]` is raised above all
+//!   the others.
+//! - Writing `CIVR[IACK]` latches the vector of the highest priority pending and unmasked source
+//!   into `CIVR[VN]` and marks it in service in `CISR`, the error vector (0) is latched if none is
+//!   pending.
+//! - `CIPR` and `CISR` bits are cleared by writing a 1.
+//! - The request to the SIU is only raised if `CICR[IEN]` is set and a pending source outranks
+//!   every source in service, which allows handlers to nest.
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use styx_core::prelude::ExceptionNumber;
+
+/// First [`ExceptionNumber`] of the CPM sources, see [`CpmInterrupt::exception_number()`].
+pub const CPM_EXCEPTION_BASE: ExceptionNumber = 0x200;
+
+const CICR_IRL_SHIFT: u32 = 13;
+const CICR_IRL_MASK: u32 = 0x7;
+const CICR_HP_SHIFT: u32 = 8;
+const CICR_HP_MASK: u32 = 0x1F;
+const CICR_IEN: u32 = 0x80;
+
+const CIVR_IACK: u16 = 0x1;
+const CIVR_VN_SHIFT: u16 = 11;
+
+/// Interrupt sources of the CPM interrupt controller, the value is the interrupt vector.
+#[repr(u8)]
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CpmInterrupt {
+    Error = 0x00,
+    PortC4 = 0x01,
+    PortC5 = 0x02,
+    Smc2Pip = 0x03,
+    Smc1 = 0x04,
+    Spi = 0x05,
+    PortC6 = 0x06,
+    Timer4 = 0x07,
+    PortC7 = 0x09,
+    PortC8 = 0x0A,
+    PortC9 = 0x0B,
+    Timer3 = 0x0C,
+    PortC10 = 0x0E,
+    PortC11 = 0x0F,
+    I2c = 0x10,
+    RiscTimerTable = 0x11,
+    Timer2 = 0x12,
+    Idma2 = 0x14,
+    Idma1 = 0x15,
+    Sdma = 0x16,
+    PortC12 = 0x17,
+    PortC13 = 0x18,
+    Timer1 = 0x19,
+    PortC14 = 0x1A,
+    Scc4 = 0x1B,
+    Scc3 = 0x1C,
+    Scc2 = 0x1D,
+    Scc1 = 0x1E,
+    PortC15 = 0x1F,
+}
+
+impl CpmInterrupt {
+    const ALL: [CpmInterrupt; 29] = [
+        Self::Error,
+        Self::PortC4,
+        Self::PortC5,
+        Self::Smc2Pip,
+        Self::Smc1,
+        Self::Spi,
+        Self::PortC6,
+        Self::Timer4,
+        Self::PortC7,
+        Self::PortC8,
+        Self::PortC9,
+        Self::Timer3,
+        Self::PortC10,
+        Self::PortC11,
+        Self::I2c,
+        Self::RiscTimerTable,
+        Self::Timer2,
+        Self::Idma2,
+        Self::Idma1,
+        Self::Sdma,
+        Self::PortC12,
+        Self::PortC13,
+        Self::Timer1,
+        Self::PortC14,
+        Self::Scc4,
+        Self::Scc3,
+        Self::Scc2,
+        Self::Scc1,
+        Self::PortC15,
+    ];
+
+    pub fn from_vector(vector: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| *source as u8 == vector)
+    }
+
+    pub fn from_exception_number(irqn: ExceptionNumber) -> Option<Self> {
+        let vector = irqn.checked_sub(CPM_EXCEPTION_BASE)?;
+        Self::from_vector(u8::try_from(vector).ok()?)
+    }
+
+    pub const fn vector(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn exception_number(self) -> ExceptionNumber {
+        CPM_EXCEPTION_BASE + self as ExceptionNumber
+    }
+}
+
+/// State of the CPM interrupt controller registers.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct CpmInterruptController {
+    cicr: u32,
+    cipr: u32,
+    cimr: u32,
+    cisr: u32,
+    civr: u16,
+}
+
+impl CpmInterruptController {
+    pub(crate) fn latch(&mut self, source: CpmInterrupt) {
+        self.cipr |= 1 << source.vector();
+    }
+
+    fn highest_priority_vector(&self) -> u8 {
+        ((self.cicr >> CICR_HP_SHIFT) & CICR_HP_MASK) as u8
+    }
+
+    /// Priority of a vector, larger is more important.
+    fn priority(&self, vector: u8) -> u8 {
+        if vector == self.highest_priority_vector() {
+            u8::MAX
+        } else {
+            vector
+        }
+    }
+
+    /// Most important vector with its bit set in `bits`.
+    fn highest(&self, bits: u32) -> Option<u8> {
+        (0..32u8)
+            .filter(|vector| bits & (1 << vector) != 0)
+            .max_by_key(|vector| self.priority(*vector))
+    }
+
+    /// SIU level the CPIC is requesting an interrupt on, if any.
+    pub(crate) fn request_level(&self) -> Option<u8> {
+        if self.cicr & CICR_IEN == 0 {
+            return None;
+        }
+        let pending = self.highest(self.cipr & self.cimr)?;
+        if let Some(in_service) = self.highest(self.cisr) {
+            if self.priority(in_service) >= self.priority(pending) {
+                return None;
+            }
+        }
+        Some(((self.cicr >> CICR_IRL_SHIFT) & CICR_IRL_MASK) as u8)
+    }
+
+    pub(crate) fn cicr(&self) -> u32 {
+        self.cicr
+    }
+
+    pub(crate) fn write_cicr(&mut self, value: u32) {
+        self.cicr = value;
+    }
+
+    pub(crate) fn cipr(&self) -> u32 {
+        self.cipr
+    }
+
+    pub(crate) fn write_cipr(&mut self, value: u32) {
+        self.cipr &= !value;
+    }
+
+    pub(crate) fn cimr(&self) -> u32 {
+        self.cimr
+    }
+
+    pub(crate) fn write_cimr(&mut self, value: u32) {
+        self.cimr = value;
+    }
+
+    pub(crate) fn cisr(&self) -> u32 {
+        self.cisr
+    }
+
+    pub(crate) fn write_cisr(&mut self, value: u32) {
+        self.cisr &= !value;
+    }
+
+    pub(crate) fn civr(&self) -> u16 {
+        self.civr
+    }
+
+    /// Only `IACK` is writable, setting it acknowledges the highest priority source.
+    pub(crate) fn write_civr(&mut self, value: u16) {
+        if value & CIVR_IACK == 0 {
+            return;
+        }
+        let vector = self.highest(self.cipr & self.cimr).unwrap_or(0);
+        if vector != CpmInterrupt::Error.vector() {
+            self.cisr |= 1 << vector;
+        }
+        self.civr = (vector as u16) << CIVR_VN_SHIFT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `IRL` = 4, `IEN` set
+    const CICR_LEVEL_4: u32 = (4 << CICR_IRL_SHIFT) | CICR_IEN;
+
+    #[test]
+    fn test_sources() {
+        assert_eq!(Some(CpmInterrupt::Scc1), CpmInterrupt::from_vector(0x1E));
+        assert_eq!(None, CpmInterrupt::from_vector(0x13));
+        assert_eq!(0x21E, CpmInterrupt::Scc1.exception_number());
+        assert_eq!(
+            Some(CpmInterrupt::Timer1),
+            CpmInterrupt::from_exception_number(0x219)
+        );
+    }
+
+    #[test]
+    fn test_request_and_acknowledge() {
+        let mut cpic = CpmInterruptController::default();
+        cpic.latch(CpmInterrupt::Smc1);
+        cpic.latch(CpmInterrupt::Timer1);
+        cpic.write_cimr(u32::MAX);
+        assert_eq!(None, cpic.request_level());
+
+        cpic.write_cicr(CICR_LEVEL_4);
+        assert_eq!(Some(4), cpic.request_level());
+
+        cpic.write_civr(CIVR_IACK);
+        assert_eq!(0x19 << CIVR_VN_SHIFT, cpic.civr());
+        assert_eq!(1 << 0x19, cpic.cisr());
+        // Smc1 does not outrank the timer in service
+        assert_eq!(None, cpic.request_level());
+
+        cpic.write_cipr(1 << 0x19);
+        cpic.write_cisr(1 << 0x19);
+        assert_eq!(Some(4), cpic.request_level());
+        cpic.write_civr(CIVR_IACK);
+        assert_eq!(0x04 << CIVR_VN_SHIFT, cpic.civr());
+    }
+
+    #[test]
+    fn test_highest_priority_override() {
+        let mut cpic = CpmInterruptController::default();
+        cpic.write_cicr(CICR_LEVEL_4 | ((CpmInterrupt::Smc1.vector() as u32) << CICR_HP_SHIFT));
+        cpic.write_cimr(u32::MAX);
+        cpic.latch(CpmInterrupt::Smc1);
+        cpic.latch(CpmInterrupt::Scc1);
+
+        cpic.write_civr(CIVR_IACK);
+        assert_eq!(0x04 << CIVR_VN_SHIFT, cpic.civr());
+
+        cpic.write_cipr(u32::MAX);
+        cpic.write_civr(CIVR_IACK);
+        assert_eq!(0, cpic.civr());
+    }
+}
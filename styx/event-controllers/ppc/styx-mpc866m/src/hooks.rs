@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Hooks feeding backend events into the [`Mpc866mController`](crate::Mpc866mController).
+use styx_core::errors::UnknownError;
+use styx_core::prelude::*;
+use tracing::{trace, warn};
+
+use crate::Mpc866mIRQn;
+
+/// Interrupt number the backends raise for `sc`.
+const SYSTEM_CALL_INTNO: ExceptionNumber = 8;
+
+/// `rfi`
+const RFI_INSN: u32 = 0x4C00_0064;
+
+/// Catches system calls from the cpu backend and services them.
+pub(crate) fn interrupt_hook(proc: CoreHandle, intno: ExceptionNumber) -> Result<(), UnknownError> {
+    if intno != SYSTEM_CALL_INTNO {
+        warn!("unhandled backend interrupt {intno}");
+        return Ok(());
+    }
+
+    trace!("system call at {:#x}", proc.cpu.pc()?);
+    proc.event_controller.execute(
+        Mpc866mIRQn::SystemCall as ExceptionNumber,
+        proc.cpu,
+        proc.mmu,
+    )?;
+    Ok(())
+}
+
+/// Called on every instruction, finishes the current interrupt when an `rfi` is about to execute.
+pub(crate) fn return_from_interrupt_hook(proc: CoreHandle) -> Result<(), UnknownError> {
+    let pc = proc.cpu.pc()?;
+    if proc
+        .mmu
+        .read_u32_be_phys_code(pc)
+        .is_ok_and(|insn| insn == RFI_INSN)
+    {
+        trace!("rfi at {pc:#x}");
+        proc.event_controller.finish_interrupt(proc.cpu, proc.mmu);
+    }
+    Ok(())
+}
@@ -21,13 +21,59 @@
 //! - change the IMMR bank memory hook as needed, and route the hooks to
 //!   the respective rust object
 //!
-#![allow(dead_code, unused_variables)]
+//! ## Interrupts
+//!
+//! [`Mpc866mController`] implements the exception model of the core as well as the two
+//! interrupt controllers in front of it:
+//!
+//! - the SIU interrupt controller (`SIPEND`/`SIMASK`/`SIEL`/`SIVEC`), which raises
+//!   [`Mpc866mIRQn::External`], see [`SiuInterrupt`]
+//! - the CPM interrupt controller (`CICR`/`CIPR`/`CIMR`/`CISR`/`CIVR`), which requests one of
+//!   the SIU levels, see [`CpmInterrupt`]
+//!
+//! Each source has its own [`ExceptionNumber`] to [`latch`](EventControllerImpl::latch):
+//!
+//! | Range | Source |
+//! |-------|--------|
+//! | `0x01..=0x1F` | Core exception, [`Mpc866mIRQn`] |
+//! | `0x100..=0x10F` | SIU source, [`SiuInterrupt::exception_number()`] |
+//! | `0x200..=0x21F` | CPM source, [`CpmInterrupt::exception_number()`] |
+//!
+//! The interrupt controller registers live in the relocatable IMMR, the `SystemInterfaceUnit`
+//! forwards accesses to them through [`Mpc866mController::read_immr()`] and
+//! [`Mpc866mController::write_immr()`].
+//!
+mod cpic;
+mod hooks;
+mod siu;
+
+pub use cpic::{CpmInterrupt, CPM_EXCEPTION_BASE};
+pub use siu::{SiuInterrupt, SIU_EXCEPTION_BASE};
+
+use std::borrow::Cow;
+
+use cpic::CpmInterruptController;
 use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use siu::SiuInterruptController;
 use styx_core::cpu::arch::ppc32::variants::Mpc8xxVariants;
-use styx_core::errors::StyxMachineError;
+use styx_core::cpu::arch::ppc32::Ppc32Register;
 use styx_core::errors::UnknownError;
-use styx_core::event_controller::{ActivateIRQnError, InterruptExecuted, Peripherals};
+use styx_core::event_controller::{
+    ActivateIRQnError, Exception, InterruptExecuted, OptionalFeatureError, Peripherals,
+};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::{debug, trace};
+
+const MSR_ILE: u32 = 0x0001_0000;
+const MSR_EE: u32 = 0x8000;
+const MSR_ME: u32 = 0x1000;
+const MSR_IP: u32 = 0x40;
+const MSR_LE: u32 = 0x1;
+
+/// Exception vectors are relocated here when `MSR[IP]` is set.
+const HIGH_VECTOR_BASE: u64 = 0xFFF0_0000;
 
 /// IRQs for the MPC866m event controller
 ///
@@ -92,13 +138,127 @@ pub enum Mpc866mIRQn {
 }
 
 impl Mpc866mIRQn {
+    /// All exceptions, in the order they are taken when several are pending.
+    const PRIORITY: [Mpc866mIRQn; 21] = [
+        Self::NonmaskableDevelopmentPort,
+        Self::SystemReset,
+        Self::Trace,
+        Self::InstructionTlbMiss,
+        Self::InstructionTlbError,
+        Self::MachineCheck,
+        Self::InstructionBreakpoint,
+        Self::SoftwareEmulation,
+        Self::Program,
+        Self::Alignment,
+        Self::SystemCall,
+        Self::FloatingPointUnavailable,
+        Self::FloatingPointAssist,
+        Self::ISI,
+        Self::DataTlbMiss,
+        Self::DataTlbError,
+        Self::DSI,
+        Self::DataBreakpoint,
+        Self::PeripheralBreakpoint,
+        Self::External,
+        Self::Decrementer,
+    ];
+
+    pub fn from_exception_number(irqn: ExceptionNumber) -> Option<Self> {
+        Self::PRIORITY
+            .into_iter()
+            .find(|exception| *exception as ExceptionNumber == irqn)
+    }
+
     /// Translates the IRQ into the address of the ISR handler routine offset
-    /// for the target, note that this is still going to be rebased off of any
-    /// location written to the memory rebasing immr (TODO: write down which
-    /// register that is)
+    /// for the target, this is rebased to `0xFFF0_0000` when `MSR[IP]` is set.
     const fn isr_address(&self) -> u64 {
         (*self as u64) * 0x100
     }
+
+    /// Bit of the exception in the pending set.
+    const fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    /// Only taken while `MSR[EE]` is set.
+    const fn is_maskable(self) -> bool {
+        matches!(self, Self::External | Self::Decrementer)
+    }
+
+    /// `SRR0` holds the next instruction instead of the one that caused the exception.
+    const fn saves_next_pc(self) -> bool {
+        matches!(
+            self,
+            Self::SystemReset
+                | Self::External
+                | Self::Decrementer
+                | Self::SystemCall
+                | Self::Trace
+                | Self::PeripheralBreakpoint
+                | Self::NonmaskableDevelopmentPort
+        )
+    }
+}
+
+/// Interrupt controller registers in the IMMR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InterruptRegister {
+    Sipend,
+    Simask,
+    Siel,
+    Sivec,
+    Civr,
+    Cicr,
+    Cipr,
+    Cimr,
+    Cisr,
+}
+
+impl InterruptRegister {
+    const ALL: [InterruptRegister; 9] = [
+        Self::Sipend,
+        Self::Simask,
+        Self::Siel,
+        Self::Sivec,
+        Self::Civr,
+        Self::Cicr,
+        Self::Cipr,
+        Self::Cimr,
+        Self::Cisr,
+    ];
+
+    /// Offset from the IMMR base.
+    const fn offset(self) -> u32 {
+        match self {
+            Self::Sipend => 0x10,
+            Self::Simask => 0x14,
+            Self::Siel => 0x18,
+            Self::Sivec => 0x1C,
+            Self::Civr => 0x930,
+            Self::Cicr => 0x940,
+            Self::Cipr => 0x944,
+            Self::Cimr => 0x948,
+            Self::Cisr => 0x94C,
+        }
+    }
+
+    const fn size(self) -> u32 {
+        match self {
+            Self::Civr => 2,
+            _ => 4,
+        }
+    }
+
+    fn containing(offset: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|register| {
+            (register.offset()..register.offset() + register.size()).contains(&offset)
+        })
+    }
+
+    /// Shift of the (big endian) byte at `offset` in the register value.
+    fn byte_shift(self, offset: u32) -> u32 {
+        8 * (self.offset() + self.size() - 1 - offset)
+    }
 }
 
 /// Event Controller + Peripheral Orchestrator for the Mpc8xx
@@ -112,20 +272,125 @@ impl Mpc866mIRQn {
 pub struct Mpc866mController {
     #[allow(dead_code)]
     family_variant: Mpc8xxVariants,
+    /// Latched core exceptions, see [`Mpc866mIRQn::bit()`].
+    pending: u32,
+    siu: SiuInterruptController,
+    cpic: CpmInterruptController,
+    interrupt_stack: Vec<ExceptionNumber>,
+}
+
+/// Serialized [`Mpc866mController`] state.
+#[derive(Serialize, Deserialize)]
+struct Mpc866mControllerState {
+    pending: u32,
+    siu: SiuInterruptController,
+    cpic: CpmInterruptController,
+    interrupt_stack: Vec<ExceptionNumber>,
 }
 
 impl Mpc866mController {
     pub fn new(variant: Mpc8xxVariants) -> Self {
         Self {
             family_variant: variant,
+            pending: 0,
+            siu: Default::default(),
+            cpic: Default::default(),
+            interrupt_stack: Vec::new(),
         }
     }
+
+    /// Read interrupt controller registers at `offset` from the IMMR base into `data`, returns
+    /// false if no byte belongs to an interrupt controller register.
+    pub fn read_immr(&self, offset: u32, data: &mut [u8]) -> bool {
+        let mut handled = false;
+        for (offset, byte) in (offset..).zip(data.iter_mut()) {
+            if let Some(register) = InterruptRegister::containing(offset) {
+                *byte = (self.read_register(register) >> register.byte_shift(offset)) as u8;
+                handled = true;
+            }
+        }
+        handled
+    }
+
+    /// Write `data` to interrupt controller registers at `offset` from the IMMR base, returns
+    /// false if no byte belongs to an interrupt controller register.
+    ///
+    /// Partial writes only touch the written bytes.
+    pub fn write_immr(&mut self, offset: u32, data: &[u8]) -> bool {
+        // (register, value, byte mask)
+        let mut writes: Vec<(InterruptRegister, u32, u32)> = Vec::new();
+        for (offset, byte) in (offset..).zip(data.iter()) {
+            let Some(register) = InterruptRegister::containing(offset) else {
+                continue;
+            };
+            let shift = register.byte_shift(offset);
+            let (value, mask) = ((*byte as u32) << shift, 0xFF << shift);
+            match writes.iter_mut().find(|(written, ..)| *written == register) {
+                Some((_, written_value, written_mask)) => {
+                    *written_value |= value;
+                    *written_mask |= mask;
+                }
+                None => writes.push((register, value, mask)),
+            }
+        }
+
+        for (register, value, mask) in writes.iter() {
+            self.write_register(*register, *value, *mask);
+        }
+        !writes.is_empty()
+    }
+
+    fn read_register(&self, register: InterruptRegister) -> u32 {
+        let cpm_level = self.cpic.request_level();
+        match register {
+            InterruptRegister::Sipend => self.siu.sipend(cpm_level),
+            InterruptRegister::Simask => self.siu.simask(),
+            InterruptRegister::Siel => self.siu.siel(),
+            InterruptRegister::Sivec => self.siu.sivec(cpm_level),
+            InterruptRegister::Civr => self.cpic.civr() as u32,
+            InterruptRegister::Cicr => self.cpic.cicr(),
+            InterruptRegister::Cipr => self.cpic.cipr(),
+            InterruptRegister::Cimr => self.cpic.cimr(),
+            InterruptRegister::Cisr => self.cpic.cisr(),
+        }
+    }
+
+    fn write_register(&mut self, register: InterruptRegister, value: u32, mask: u32) {
+        trace!("{register:?} <- {value:#x} (mask {mask:#x})");
+        let merge = |old: u32| (old & !mask) | (value & mask);
+        match register {
+            InterruptRegister::Sipend => self.siu.write_sipend(value & mask),
+            InterruptRegister::Simask => self.siu.write_simask(merge(self.siu.simask())),
+            InterruptRegister::Siel => self.siu.write_siel(merge(self.siu.siel())),
+            InterruptRegister::Sivec => debug!("ignoring write to read only SIVEC"),
+            InterruptRegister::Civr => self.cpic.write_civr((value & mask) as u16),
+            InterruptRegister::Cicr => self.cpic.write_cicr(merge(self.cpic.cicr())),
+            InterruptRegister::Cipr => self.cpic.write_cipr(value & mask),
+            InterruptRegister::Cimr => self.cpic.write_cimr(merge(self.cpic.cimr())),
+            InterruptRegister::Cisr => self.cpic.write_cisr(value & mask),
+        }
+    }
+
+    fn is_pending(&self, irqn: Mpc866mIRQn) -> bool {
+        let external =
+            irqn == Mpc866mIRQn::External && self.siu.external_request(self.cpic.request_level());
+        self.pending & irqn.bit() != 0 || external
+    }
+
+    /// Highest priority exception that can be taken with the current `msr`.
+    fn next_exception(&self, msr: u32) -> Option<Mpc866mIRQn> {
+        Mpc866mIRQn::PRIORITY
+            .into_iter()
+            .find(|irqn| (!irqn.is_maskable() || msr & MSR_EE != 0) && self.is_pending(*irqn))
+    }
+
     /// Top level method of actual exception insertion into the CPU execution flow
     ///
     /// In general, this target uses registers `SRR0` and `SRR1` to hold the previous
-    /// state during exception execution. In the common case the `MSR` bits `IP` do not
-    /// change, and the `ME` bits are set to zero, and the `LE` bits are copied from the
-    /// `ILE` setting of the interruped execution (if applicable), and other bits are 0.
+    /// state during exception execution. In the common case the `MSR` bits `IP`, `ME`
+    /// and `ILE` do not change (`ME` is cleared by a machine check), the `LE` bit is
+    /// copied from the `ILE` setting of the interruped execution, and other bits are 0.
+    /// `SRR1` holds bits 16-31 of the interrupted `MSR`.
     /// For specific information see section `6.1.2` and `6.1.3` in the MPC866M family
     /// reference manual
     ///
@@ -164,8 +429,46 @@ impl Mpc866mController {
     /// | [`Mpc866mIRQn::InstructionBreakpoint`] | Address of the instruction that caused the exception |
     /// | [`Mpc866mIRQn::PeripheralBreakpoint`] | Address of the next instruction |
     /// | [`Mpc866mIRQn::NonmaskableDevelopmentPort`] | Address of the next instruction |
-    fn insert_exception(&self, irqn: Mpc866mIRQn) -> Result<(), StyxMachineError> {
-        todo!("Mpc866mController::insert_exception");
+    fn insert_exception(
+        &mut self,
+        irqn: Mpc866mIRQn,
+        cpu: &mut dyn CpuBackend,
+    ) -> Result<(), UnknownError> {
+        let pc = cpu.pc()? as u32;
+        let msr = cpu.read_register::<u32>(Ppc32Register::Msr)?;
+
+        // like the other PowerPC event controllers, the pc has already moved past the
+        // instruction that caused a synchronous exception
+        let saved_pc = if irqn.saves_next_pc() {
+            pc
+        } else {
+            pc.wrapping_sub(4)
+        };
+        cpu.write_register(Ppc32Register::SRR0, saved_pc)?;
+        cpu.write_register(Ppc32Register::SRR1, msr & 0xFFFF)?;
+
+        let mut new_msr = msr & (MSR_ILE | MSR_ME | MSR_IP);
+        if irqn == Mpc866mIRQn::MachineCheck {
+            new_msr &= !MSR_ME;
+        }
+        if msr & MSR_ILE != 0 {
+            new_msr |= MSR_LE;
+        }
+        cpu.write_register(Ppc32Register::Msr, new_msr)?;
+
+        let base = if msr & MSR_IP != 0 {
+            HIGH_VECTOR_BASE
+        } else {
+            0
+        };
+        let handler = base + irqn.isr_address();
+        cpu.set_pc(handler)?;
+
+        self.pending &= !irqn.bit();
+        self.interrupt_stack.push(irqn as ExceptionNumber);
+        debug!("exception {irqn} executing at {handler:#x} (saved pc {saved_pc:#x})");
+
+        Ok(())
     }
 }
 
@@ -173,38 +476,193 @@ impl EventControllerImpl for Mpc866mController {
     fn next(
         &mut self,
         cpu: &mut dyn CpuBackend,
-        mmu: &mut Mmu,
-        peripherals: &mut Peripherals,
+        _mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
     ) -> Result<InterruptExecuted, UnknownError> {
-        Ok(InterruptExecuted::NotExecuted)
+        let msr = cpu.read_register::<u32>(Ppc32Register::Msr)?;
+        match self.next_exception(msr) {
+            Some(irqn) => {
+                self.insert_exception(irqn, cpu)?;
+                Ok(InterruptExecuted::Executed)
+            }
+            None => Ok(InterruptExecuted::NotExecuted),
+        }
     }
 
     fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
-        todo!("Mpc866mController::latch_event({})", event);
+        if let Some(irqn) = Mpc866mIRQn::from_exception_number(event) {
+            self.pending |= irqn.bit();
+        } else if let Some(source) = SiuInterrupt::from_exception_number(event) {
+            self.siu.latch(source);
+            if source == SiuInterrupt::Irq0 {
+                self.pending |= Mpc866mIRQn::SystemReset.bit();
+            }
+        } else if let Some(source) = CpmInterrupt::from_exception_number(event) {
+            self.cpic.latch(source);
+        } else {
+            return Err(ActivateIRQnError::InvalidIRQn(event));
+        }
+        trace!("latched event {event:#x}");
+        Ok(())
     }
 
     fn execute(
         &mut self,
         irq: ExceptionNumber,
         cpu: &mut dyn CpuBackend,
-        mmu: &mut Mmu,
+        _mmu: &mut Mmu,
     ) -> Result<InterruptExecuted, ActivateIRQnError> {
-        todo!()
-    }
-
-    fn tick(&mut self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
-        todo!()
+        let irqn =
+            Mpc866mIRQn::from_exception_number(irq).ok_or(ActivateIRQnError::InvalidIRQn(irq))?;
+        self.insert_exception(irqn, cpu)?;
+        Ok(InterruptExecuted::Executed)
     }
 
     fn finish_interrupt(
         &mut self,
-        cpu: &mut dyn CpuBackend,
-        mmu: &mut Mmu,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
     ) -> Option<ExceptionNumber> {
-        None
+        self.interrupt_stack.pop()
+    }
+
+    fn has_pending_events(&self) -> bool {
+        Mpc866mIRQn::PRIORITY
+            .into_iter()
+            .any(|irqn| self.is_pending(irqn))
     }
 
     fn init(&mut self, cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        cpu.intr_hook(Box::new(hooks::interrupt_hook))?;
+        cpu.add_hook(StyxHook::code(.., hooks::return_from_interrupt_hook))?;
         Ok(())
     }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.pending = 0;
+        self.siu = Default::default();
+        self.cpic = Default::default();
+        self.interrupt_stack.clear();
+        Ok(())
+    }
+
+    fn current_exception(&mut self) -> Result<Option<Exception>, OptionalFeatureError> {
+        Ok(self.interrupt_stack.last().map(|number| Exception {
+            name: Mpc866mIRQn::from_exception_number(*number)
+                .map(|irqn| irqn.to_string().into())
+                .unwrap_or_default(),
+            number: *number,
+        }))
+    }
+
+    fn available_exceptions(&mut self) -> Result<Cow<'_, [Exception]>, OptionalFeatureError> {
+        Ok(Mpc866mIRQn::PRIORITY
+            .into_iter()
+            .map(|irqn| Exception {
+                name: irqn.to_string().into(),
+                number: irqn as ExceptionNumber,
+            })
+            .collect())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&Mpc866mControllerState {
+            pending: self.pending,
+            siu: self.siu.clone(),
+            cpic: self.cpic.clone(),
+            interrupt_stack: self.interrupt_stack.clone(),
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: Mpc866mControllerState = state.get()?;
+        self.pending = state.pending;
+        self.siu = state.siu;
+        self.cpic = state.cpic;
+        self.interrupt_stack = state.interrupt_stack;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> Mpc866mController {
+        Mpc866mController::new(Mpc8xxVariants::Mpc866)
+    }
+
+    #[test]
+    fn test_exception_priority() {
+        let mut cec = controller();
+        cec.latch(Mpc866mIRQn::Decrementer as ExceptionNumber)
+            .unwrap();
+        cec.latch(SiuInterrupt::Level2.exception_number()).unwrap();
+        cec.write_immr(0x14, &[0xFF, 0xFF, 0, 0]);
+
+        assert_eq!(None, cec.next_exception(0));
+        assert_eq!(Some(Mpc866mIRQn::External), cec.next_exception(MSR_EE));
+
+        cec.latch(Mpc866mIRQn::Program as ExceptionNumber).unwrap();
+        assert_eq!(Some(Mpc866mIRQn::Program), cec.next_exception(0));
+
+        cec.latch(SiuInterrupt::Irq0.exception_number()).unwrap();
+        assert_eq!(Some(Mpc866mIRQn::SystemReset), cec.next_exception(0));
+
+        assert!(matches!(
+            cec.latch(0x300),
+            Err(ActivateIRQnError::InvalidIRQn(0x300))
+        ));
+    }
+
+    #[test]
+    fn test_immr_registers() {
+        let mut cec = controller();
+        cec.latch(SiuInterrupt::Irq1.exception_number()).unwrap();
+
+        let mut sipend = [0u8; 4];
+        assert!(cec.read_immr(0x10, &mut sipend));
+        assert_eq!([0x20, 0, 0, 0], sipend);
+
+        // byte writes only touch their byte
+        cec.write_immr(0x14, &[0xFF, 0xFF, 0xFF, 0xFF]);
+        cec.write_immr(0x15, &[0x00]);
+        let mut simask = [0u8; 4];
+        cec.read_immr(0x14, &mut simask);
+        assert_eq!([0xFF, 0x00, 0xFF, 0xFF], simask);
+
+        // sipend is write one to clear
+        cec.write_immr(0x10, &[0x20, 0, 0, 0]);
+        cec.read_immr(0x10, &mut sipend);
+        assert_eq!([0; 4], sipend);
+
+        let mut other = [0xAAu8; 2];
+        assert!(!cec.read_immr(0x0, &mut other));
+        assert_eq!([0xAA; 2], other);
+    }
+
+    #[test]
+    fn test_cpm_routing() {
+        let mut cec = controller();
+        // CICR: IRL = 3, IEN
+        cec.write_immr(0x940, &[0x00, 0x00, 0x60, 0x80]);
+        cec.write_immr(0x948, &[0xFF, 0xFF, 0xFF, 0xFF]);
+        cec.write_immr(0x14, &[0xFF, 0xFF, 0, 0]);
+        cec.latch(CpmInterrupt::Scc2.exception_number()).unwrap();
+
+        assert_eq!(Some(Mpc866mIRQn::External), cec.next_exception(MSR_EE));
+        let mut sivec = [0u8; 4];
+        cec.read_immr(0x1C, &mut sivec);
+        // LVL3
+        assert_eq!(0x1C, sivec[0]);
+
+        // IACK
+        cec.write_immr(0x930, &[0x00, 0x01]);
+        let mut civr = [0u8; 2];
+        cec.read_immr(0x930, &mut civr);
+        assert_eq!(0x1D << 11, u16::from_be_bytes(civr));
+
+        // the source stays pending but is in service
+        assert_eq!(None, cec.next_exception(MSR_EE));
+    }
 }
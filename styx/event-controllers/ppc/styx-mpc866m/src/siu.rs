@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! SIU interrupt controller, `SIPEND`/`SIMASK`/`SIEL`/`SIVEC`.
+//!
+//! The SIU collects the eight external `IRQ` pins and eight internal levels (`LVL0`-`LVL7`) into
+//! a single request to the core, the [`Mpc866mIRQn::External`](crate::Mpc866mIRQn::External)
+//! exception. Sources are numbered in priority order, interleaving pins and levels:
+//!
+//! | Index | Source | `SIPEND`/`SIMASK` bit | `SIVEC` code |
+//! |-------|--------|-----------------------|--------------|
+//! | 0  | `IRQ0` | 0  | `0x00` |
+//! | 1  | `LVL0` | 1  | `0x04` |
+//! | 2  | `IRQ1` | 2  | `0x08` |
+//! | .. | ..     | .. | ..     |
+//! | 15 | `LVL7` | 15 | `0x3C` |
+//!
+//! `IRQ0` is non-maskable and raises a [`Mpc866mIRQn::SystemReset`](crate::Mpc866mIRQn::SystemReset)
+//! instead of an external interrupt.
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use styx_core::prelude::ExceptionNumber;
+
+/// First [`ExceptionNumber`] of the SIU sources, see [`SiuInterrupt::exception_number()`].
+pub const SIU_EXCEPTION_BASE: ExceptionNumber = 0x100;
+
+/// Interrupt sources of the SIU interrupt controller.
+///
+/// [`latch`](styx_core::prelude::EventControllerImpl::latch)ing
+/// [`SiuInterrupt::exception_number()`] on the [`Mpc866mController`](crate::Mpc866mController)
+/// sets the source's bit in `SIPEND`, it stays set until software writes a 1 to it.
+#[repr(u8)]
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SiuInterrupt {
+    Irq0 = 0,
+    Level0,
+    Irq1,
+    Level1,
+    Irq2,
+    Level2,
+    Irq3,
+    Level3,
+    Irq4,
+    Level4,
+    Irq5,
+    Level5,
+    Irq6,
+    Level6,
+    Irq7,
+    Level7,
+}
+
+impl SiuInterrupt {
+    const ALL: [SiuInterrupt; 16] = [
+        Self::Irq0,
+        Self::Level0,
+        Self::Irq1,
+        Self::Level1,
+        Self::Irq2,
+        Self::Level2,
+        Self::Irq3,
+        Self::Level3,
+        Self::Irq4,
+        Self::Level4,
+        Self::Irq5,
+        Self::Level5,
+        Self::Irq6,
+        Self::Level6,
+        Self::Irq7,
+        Self::Level7,
+    ];
+
+    /// Source for the internal interrupt level `level`, 0-7.
+    pub fn level(level: u8) -> Option<Self> {
+        Self::from_index(level.checked_mul(2)?.checked_add(1)?)
+    }
+
+    /// Source for the external `IRQ` pin `pin`, 0-7.
+    pub fn irq(pin: u8) -> Option<Self> {
+        Self::from_index(pin.checked_mul(2)?)
+    }
+
+    fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+
+    pub fn from_exception_number(irqn: ExceptionNumber) -> Option<Self> {
+        let index = irqn.checked_sub(SIU_EXCEPTION_BASE)?;
+        Self::from_index(u8::try_from(index).ok()?)
+    }
+
+    pub const fn exception_number(self) -> ExceptionNumber {
+        SIU_EXCEPTION_BASE + self as ExceptionNumber
+    }
+
+    /// Bit of the source in `SIPEND` and `SIMASK`, bit 0 is the MSB.
+    const fn mask(self) -> u32 {
+        0x8000_0000 >> self as u32
+    }
+}
+
+/// State of the SIU interrupt controller registers.
+///
+/// The CPM interrupt controller is level sensitive, its request is passed in as `cpm_level` and
+/// is visible in `SIPEND` without being latched.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct SiuInterruptController {
+    /// Latched `SIPEND` bits.
+    pending: u32,
+    simask: u32,
+    /// Only stored, every latch is treated as an edge.
+    siel: u32,
+}
+
+impl SiuInterruptController {
+    pub(crate) fn latch(&mut self, source: SiuInterrupt) {
+        self.pending |= source.mask();
+    }
+
+    pub(crate) fn sipend(&self, cpm_level: Option<u8>) -> u32 {
+        let cpm = cpm_level
+            .and_then(SiuInterrupt::level)
+            .map(SiuInterrupt::mask)
+            .unwrap_or_default();
+        self.pending | cpm
+    }
+
+    /// Writing a 1 clears the latched bit, the CPM level is cleared at its source.
+    pub(crate) fn write_sipend(&mut self, value: u32) {
+        self.pending &= !value;
+    }
+
+    pub(crate) fn simask(&self) -> u32 {
+        self.simask
+    }
+
+    pub(crate) fn write_simask(&mut self, value: u32) {
+        self.simask = value;
+    }
+
+    pub(crate) fn siel(&self) -> u32 {
+        self.siel
+    }
+
+    pub(crate) fn write_siel(&mut self, value: u32) {
+        self.siel = value;
+    }
+
+    /// Highest priority pending and unmasked source.
+    fn highest(&self, cpm_level: Option<u8>) -> Option<SiuInterrupt> {
+        let active = self.sipend(cpm_level) & self.simask;
+        SiuInterrupt::from_index(active.leading_zeros() as u8)
+    }
+
+    /// Interrupt code of the highest priority source in the upper byte, the lowest priority code
+    /// (`LVL7`) if nothing is pending.
+    pub(crate) fn sivec(&self, cpm_level: Option<u8>) -> u32 {
+        let source = self.highest(cpm_level).unwrap_or(SiuInterrupt::Level7);
+        ((source as u32) * 4) << 24
+    }
+
+    /// The SIU requests an external interrupt from the core.
+    pub(crate) fn external_request(&self, cpm_level: Option<u8>) -> bool {
+        self.sipend(cpm_level) & self.simask & !SiuInterrupt::Irq0.mask() != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources() {
+        assert_eq!(Some(SiuInterrupt::Level3), SiuInterrupt::level(3));
+        assert_eq!(Some(SiuInterrupt::Irq7), SiuInterrupt::irq(7));
+        assert_eq!(None, SiuInterrupt::level(8));
+        assert_eq!(0x107, SiuInterrupt::Level3.exception_number());
+        assert_eq!(
+            Some(SiuInterrupt::Level3),
+            SiuInterrupt::from_exception_number(0x107)
+        );
+        assert_eq!(None, SiuInterrupt::from_exception_number(0x110));
+    }
+
+    #[test]
+    fn test_pending_and_vector() {
+        let mut siu = SiuInterruptController::default();
+        siu.latch(SiuInterrupt::Irq2);
+        assert!(!siu.external_request(None));
+
+        siu.write_simask(0xFFFF_0000);
+        assert!(siu.external_request(None));
+        assert_eq!(0x0800_0000, siu.sipend(None));
+        assert_eq!(0x10 << 24, siu.sivec(None));
+
+        // the CPM on level 1 outranks IRQ2
+        assert_eq!(0x1800_0000, siu.sipend(Some(1)));
+        assert_eq!(0x0C << 24, siu.sivec(Some(1)));
+
+        siu.write_sipend(0x0800_0000);
+        assert!(!siu.external_request(None));
+        assert_eq!(0x3C << 24, siu.sivec(None));
+    }
+
+    #[test]
+    fn test_irq0_is_not_external() {
+        let mut siu = SiuInterruptController::default();
+        siu.write_simask(0xFFFF_0000);
+        siu.latch(SiuInterrupt::Irq0);
+        assert!(!siu.external_request(None));
+        assert_eq!(0, siu.sivec(None));
+    }
+}
@@ -1,7 +1,16 @@
 // SPDX-License-Identifier: BSD-2-Clause
+//! Clock sources driven by the processor's virtual time.
+//!
+//! A [TickSource] turns the [`Delta`]s passed to peripheral ticks into clock ticks, so outputs are
+//! ticked at the same instructions on every run. Peripherals owning a [TickSource] should
+//! forward [`TickSource::next_deadline()`] from
+//! [`Peripheral::next_deadline()`](styx_core::prelude::Peripheral::next_deadline).
 use std::time::Duration;
-use styx_core::sync::{cell::RefCell, sync::atomic::AtomicBool};
-use tokio::time;
+
+use styx_core::executor::clock::duration_to_instructions;
+use styx_core::prelude::Delta;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
 
 /// All types wanting to consume a clock need to implement this
 /// trait
@@ -12,57 +21,61 @@ pub trait Tick {
 }
 
 pub struct TickSource {
-    enabled: AtomicBool,
-    #[allow(dead_code)]
-    period: u64,
-    #[allow(dead_code)]
-    multiplier: u64,
-    #[allow(dead_code)]
-    divisor: u64,
-    tick_count: RefCell<u64>,
-    duration: std::time::Duration,
+    enabled: bool,
+    /// Clock frequency in hertz.
+    hz: u64,
+    tick_count: u64,
+    /// Virtual time since the clock was enabled.
+    elapsed: Duration,
     outputs: Vec<Box<dyn Tick>>,
 }
 
 impl TickSource {
-    /// Constructs a new sysclk from the provided
-    /// period in Mhz
+    /// Constructs a new, disabled, clock of `hz` hertz.
     pub fn from_hz(hz: u64) -> Self {
-        let duration = TickSource::duration(hz, 1, 1);
         TickSource {
-            enabled: false.into(),
-            duration,
-            tick_count: RefCell::new(0),
-            period: hz,
-            multiplier: 1,
-            divisor: 1,
+            enabled: false,
+            hz,
+            tick_count: 0,
+            elapsed: Duration::ZERO,
             outputs: Vec::new(),
         }
     }
 
-    /// static method to resolve the parameters into a [`Duration`](`std::time::Duration`)
-    fn duration(period: u64, multiplier: u32, divisor: u32) -> std::time::Duration {
-        let secs = multiplier as f64 / (period * divisor as u64) as f64;
-        let duration = Duration::from_secs_f64(secs);
+    /// Start ticking, time is counted from this call.
+    pub fn enable(&mut self) {
+        self.enabled = true;
+        self.tick_count = 0;
+        self.elapsed = Duration::ZERO;
+    }
 
-        println!("Constructing a duration of: {duration:?}");
-        duration
+    pub fn disable(&mut self) {
+        self.enabled = false;
     }
 
-    /// while enabled, continuously ticks sysclk
-    pub async fn run(&mut self) {
-        self.enabled = true.into();
-        let mut interval = time::interval(self.duration);
+    /// Ticks since the clock was enabled.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
 
-        while self
-            .enabled
-            .load(styx_core::sync::sync::atomic::Ordering::Relaxed)
-        {
-            // wait
-            interval.tick().await;
+    /// Virtual time of tick number `tick`.
+    fn tick_time(&self, tick: u64) -> Duration {
+        let nanos = (tick as u128 * NANOS_PER_SECOND).div_ceil(self.hz as u128);
+        Duration::from_nanos(nanos.try_into().unwrap_or(u64::MAX))
+    }
 
-            // increase self.tick_count
-            *self.tick_count.borrow_mut() += 1;
+    /// Advance the clock by `delta`, ticking the outputs once for every clock period that
+    /// elapsed.
+    pub fn advance(&mut self, delta: &Delta) {
+        if !self.enabled || self.hz == 0 {
+            return;
+        }
+        self.elapsed += delta.time;
+
+        let ticks = self.elapsed.as_nanos() * self.hz as u128 / NANOS_PER_SECOND;
+        let ticks = u64::try_from(ticks).unwrap_or(u64::MAX);
+        while self.tick_count < ticks {
+            self.tick_count += 1;
 
             // tick all output functions
             for destination in self.outputs.iter() {
@@ -71,7 +84,59 @@ impl TickSource {
         }
     }
 
+    /// Instructions until the next tick, `None` while disabled.
+    pub fn next_deadline(&self) -> Option<u64> {
+        if !self.enabled || self.hz == 0 {
+            return None;
+        }
+        let next = self.tick_time(self.tick_count + 1);
+        Some(duration_to_instructions(next.saturating_sub(self.elapsed)))
+    }
+
     pub fn add_output(&mut self, child: Box<dyn Tick>) {
         self.outputs.push(child)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    struct Counter(Arc<AtomicU64>);
+
+    impl Tick for Counter {
+        fn tick(&self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_virtual_ticks() {
+        let ticks = Arc::new(AtomicU64::new(0));
+        let mut clock = TickSource::from_hz(1_000_000);
+        clock.add_output(Box::new(Counter(ticks.clone())));
+
+        // nothing happens until enabled
+        clock.advance(&Delta::from_instructions(5000));
+        assert_eq!(None, clock.next_deadline());
+        assert_eq!(0, ticks.load(Ordering::Relaxed));
+
+        clock.enable();
+        let period = clock.next_deadline().unwrap();
+        clock.advance(&Delta::from_instructions(period - 1));
+        assert_eq!(0, ticks.load(Ordering::Relaxed));
+        assert_eq!(Some(1), clock.next_deadline());
+        clock.advance(&Delta::from_instructions(1));
+        assert_eq!(1, ticks.load(Ordering::Relaxed));
+
+        clock.advance(&Delta::from_instructions(period * 3));
+        assert_eq!(4, clock.tick_count());
+        assert_eq!(4, ticks.load(Ordering::Relaxed));
+        assert_eq!(Some(period), clock.next_deadline());
+    }
+}
@@ -8,6 +8,7 @@ use styx_core::grpc::io::spi::{
     Empty, MasterChipSelectPacket, MasterPacket, PortRequest, SlaveChipSelectPacket, SlavePacket,
 };
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use thiserror::Error;
 use tokio::sync::broadcast;
 use tokio_stream::Stream;
@@ -79,6 +80,20 @@ pub trait SpiImpl: AsAny + Send {
     fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
         Ok(())
     }
+
+    /// Save the port's internal state for snapshots, memory mapped registers are saved with
+    /// memory. Stateless ports keep the default empty state.
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::default())
+    }
+
+    /// Restore state saved by [`SpiImpl::save_state()`].
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        if !state.is_empty() {
+            return Err(anyhow!("spi port does not support restoring state"));
+        }
+        Ok(())
+    }
 }
 
 /// Holds the clones of channels of a SpiPort so that an SpiService can do async comms.
@@ -236,6 +251,31 @@ impl Peripheral for SPIController {
         }
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let ports = self
+            .spi_ports
+            .iter()
+            .map(|port| Ok((port.port_id, port.inner.save_state()?)))
+            .collect::<Result<Vec<(u32, ComponentState)>, UnknownError>>()?;
+
+        Ok(ComponentState::new(&ports)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let ports: Vec<(u32, ComponentState)> = state.get()?;
+
+        for (id, state) in ports {
+            let port = self
+                .spi_ports
+                .iter_mut()
+                .find(|port| port.port_id == id)
+                .with_context(|| format!("no spi port {id} to restore"))?;
+            port.inner.restore_state(&state)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Error)]
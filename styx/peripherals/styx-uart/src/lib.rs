@@ -72,6 +72,7 @@ use styx_core::errors::UnknownError;
 use styx_core::grpc::io::uart::uart_port_server::{UartPort, UartPortServer};
 use styx_core::grpc::io::uart::{self, RxData, TxData};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_core::sync::sync::Arc;
 
 use as_any::{AsAny, Downcast};
@@ -168,6 +169,18 @@ pub trait UartImpl: AsAny + Send {
     ) -> Result<(), UnknownError> {
         Ok(())
     }
+    /// Serialize the interface state for a [`Snapshot`](styx_core::snapshot::Snapshot).
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::default())
+    }
+
+    /// Restore state saved by [`UartImpl::save_state()`].
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        if !state.is_empty() {
+            return Err(anyhow!("uart does not support restoring state"));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -363,6 +376,30 @@ impl Peripheral for UartController {
     fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
         Ok(())
     }
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let interfaces = self
+            .uart_interfaces
+            .iter()
+            .map(|i| Ok((i.interface_id.clone(), i.inner.save_state()?)))
+            .collect::<Result<Vec<(String, ComponentState)>, UnknownError>>()?;
+
+        Ok(ComponentState::new(&interfaces)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let interfaces: Vec<(String, ComponentState)> = state.get()?;
+
+        for (id, state) in interfaces {
+            let interface = self
+                .uart_interfaces
+                .iter_mut()
+                .find(|i| i.interface_id == id)
+                .with_context(|| format!("no uart interface '{id}' to restore"))?;
+            interface.inner.restore_state(&state)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
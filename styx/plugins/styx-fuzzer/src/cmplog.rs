@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Comparison logging for input-to-state mutations.
+//!
+//! Before the input-to-state stage, the [`CmpLogTracingStage`] runs the scheduled corpus entry
+//! unmutated while a compare hook records the operands of the integer comparisons it executes.
+//! They are handed to LibAFL as [`CmpValuesMetadata`], which the
+//! [`I2SRandReplace`](libafl::mutators::I2SRandReplace) mutator uses to replace operands found in
+//! the entry with the value they were compared against, getting past magic value checks. Other
+//! runs are not logged.
+//!
+//! Byte-wise comparison loops like `memcmp` compare one byte per iteration at the same
+//! instruction, consecutive byte comparisons of an instruction are joined into a single
+//! [`CmpValues::Bytes`] entry.
+//!
+//! Requires a cpu backend supporting [`StyxHook::compare()`], i.e. the pcode backend.
+use libafl::{
+    fuzzer::ExecutesInput,
+    inputs::UsesInput,
+    observers::cmp::{CmpValues, CmpValuesMetadata, CmplogBytes},
+    stages::Stage,
+    state::{HasCurrentTestcase, HasMetadata, UsesState},
+    Error,
+};
+use libafl_bolts::Named;
+use rustc_hash::FxHashMap;
+use std::{borrow::Cow, marker::PhantomData};
+use styx_core::{
+    hooks::CompareKind,
+    prelude::*,
+    sync::sync::{Arc, Mutex},
+};
+
+/// Most comparisons logged per run, counting each byte comparison loop once, later comparisons
+/// are dropped.
+const MAX_CMP_VALUES: usize = 1024;
+
+/// Most bytes joined into one [`CmpValues::Bytes`] entry.
+const MAX_CMP_BYTES: usize = 32;
+
+/// Comparisons logged during a traced run.
+#[derive(Debug, Default)]
+pub(crate) struct CmpLog {
+    /// Only set while the [`CmpLogTracingStage`] runs an input.
+    enabled: bool,
+    values: Vec<CmpValues>,
+    /// Operands of consecutive byte comparisons by pc.
+    bytes: FxHashMap<u64, (Vec<u8>, Vec<u8>)>,
+}
+
+impl CmpLog {
+    /// Log a comparison of `size` byte operands executed at `pc`.
+    pub(crate) fn record(&mut self, pc: u64, size: u32, left: u128, right: u128) {
+        if !self.enabled {
+            return;
+        }
+        let full = self.values.len() + self.bytes.len() >= MAX_CMP_VALUES;
+
+        let value = match size {
+            1 => {
+                // a full log still extends the byte comparisons it has
+                if full && !self.bytes.contains_key(&pc) {
+                    return;
+                }
+                let (l, r) = self.bytes.entry(pc).or_default();
+                if l.len() < MAX_CMP_BYTES {
+                    l.push(left as u8);
+                    r.push(right as u8);
+                }
+                return;
+            }
+            _ if full => return,
+            2 => CmpValues::U16((left as u16, right as u16)),
+            4 => CmpValues::U32((left as u32, right as u32)),
+            8 => CmpValues::U64((left as u64, right as u64)),
+            _ => return,
+        };
+        self.values.push(value);
+    }
+
+    /// Drop all logged comparisons and log the following ones.
+    pub(crate) fn start(&mut self) {
+        self.values.clear();
+        self.bytes.clear();
+        self.enabled = true;
+    }
+
+    /// Stop logging and take the logged comparisons.
+    pub(crate) fn stop(&mut self) -> Vec<CmpValues> {
+        self.enabled = false;
+        self.take()
+    }
+
+    /// Logged comparisons with byte comparisons joined.
+    pub(crate) fn take(&mut self) -> Vec<CmpValues> {
+        let mut values = std::mem::take(&mut self.values);
+        for (_, (left, right)) in self.bytes.drain() {
+            if left.len() == 1 {
+                values.push(CmpValues::U8((left[0], right[0])));
+            } else {
+                values.push(CmpValues::Bytes((
+                    cmplog_bytes(&left),
+                    cmplog_bytes(&right),
+                )));
+            }
+        }
+        values
+    }
+}
+
+fn cmplog_bytes(bytes: &[u8]) -> CmplogBytes {
+    let mut buf = [0; MAX_CMP_BYTES];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    CmplogBytes::from_buf_and_len(buf, bytes.len() as u8)
+}
+
+/// Adds a compare hook logging every comparison to `log`.
+pub(crate) fn install(
+    proc: &mut ProcessorCore,
+    log: Arc<Mutex<CmpLog>>,
+) -> Result<(), UnknownError> {
+    proc.cpu
+        .add_hook(StyxHook::compare(
+            ..,
+            move |mut proc: CoreHandle, _: CompareKind, size: u32, left: u128, right: u128| {
+                let pc = proc.pc()?;
+                log.lock().unwrap().record(pc, size, left, right);
+                Ok(())
+            },
+        ))
+        .with_context(|| "failed to add compare hook, cmplog requires the pcode backend")?;
+    Ok(())
+}
+
+/// Runs the current corpus entry unmutated with comparison logging, replacing the
+/// [`CmpValuesMetadata`] with its comparisons.
+#[derive(Debug)]
+pub(crate) struct CmpLogTracingStage<E, EM, Z> {
+    name: Cow<'static, str>,
+    log: Arc<Mutex<CmpLog>>,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, Z> CmpLogTracingStage<E, EM, Z> {
+    pub(crate) fn new(log: Arc<Mutex<CmpLog>>) -> Self {
+        Self {
+            name: Cow::Borrowed("cmplog tracing"),
+            log,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, Z> Named for CmpLogTracingStage<E, EM, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, Z> UsesState for CmpLogTracingStage<E, EM, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for CmpLogTracingStage<E, EM, Z>
+where
+    E: UsesState,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State> + ExecutesInput<E, EM>,
+    E::State: HasMetadata + HasCurrentTestcase<<E::State as UsesInput>::Input>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let input = state.current_input_cloned()?;
+
+        self.log.lock().unwrap().start();
+        let result = fuzzer.execute_input(state, executor, manager, &input);
+        let list = self.log.lock().unwrap().stop();
+        result?;
+
+        // replaced even if empty, older comparisons belong to another input
+        state.add_metadata(CmpValuesMetadata { list });
+        Ok(())
+    }
+
+    // tracing has no progress to resume, a restarted client traces the entry again
+    fn should_restart(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn clear_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut log = CmpLog::default();
+        log.start();
+        log.record(0x100, 4, 0x1234, 0xCAFE);
+        log.record(0x104, 2, 0x1, 0x2);
+        log.record(0x108, 16, 0x1, 0x2);
+
+        let values = log.stop();
+        assert_eq!(values.len(), 2);
+        assert!(matches!(values[0], CmpValues::U32((0x1234, 0xCAFE))));
+        assert!(matches!(values[1], CmpValues::U16((0x1, 0x2))));
+        assert!(log.take().is_empty());
+
+        // nothing is logged outside of tracing
+        log.record(0x100, 4, 0x1234, 0xCAFE);
+        assert!(log.take().is_empty());
+    }
+
+    #[test]
+    fn test_byte_loop() {
+        let mut log = CmpLog::default();
+        log.start();
+        for (l, r) in b"MAGX".iter().zip(b"MAGIC") {
+            log.record(0x200, 1, *l as u128, *r as u128);
+        }
+        log.record(0x300, 1, 0x10, 0x20);
+
+        let values = log.take();
+        assert_eq!(values.len(), 2);
+        for value in values {
+            match value {
+                CmpValues::Bytes((l, r)) => {
+                    assert_eq!(l.as_slice(), b"MAGX");
+                    assert_eq!(r.as_slice(), b"MAGI");
+                }
+                CmpValues::U8((l, r)) => assert_eq!((l, r), (0x10, 0x20)),
+                _ => panic!("unexpected {value:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_full() {
+        let mut log = CmpLog::default();
+        log.start();
+        for pc in 0..MAX_CMP_VALUES as u64 {
+            log.record(pc, 1, 0x1, 0x2);
+        }
+        // only the byte comparison loops already logged grow
+        log.record(0, 1, 0x3, 0x4);
+        log.record(0x1_0000, 1, 0x1, 0x2);
+        log.record(0x1_0000, 4, 0x1, 0x2);
+
+        let values = log.take();
+        assert_eq!(values.len(), MAX_CMP_VALUES);
+        assert!(values
+            .iter()
+            .all(|value| !matches!(value, CmpValues::U32(_))));
+        assert!(values.iter().any(|value| matches!(
+            value,
+            CmpValues::Bytes((l, _)) if l.as_slice() == [0x1, 0x3]
+        )));
+    }
+}
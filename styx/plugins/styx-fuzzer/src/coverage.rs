@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! In-process edge coverage.
+//!
+//! A block hook records coverage straight into the AFL map while the target runs, so no
+//! StyxTracePlugin, trace bus consumer or processing thread is needed and the map is complete as
+//! soon as the run returns. Block hooks are supported by both the unicorn and the pcode backends.
+//!
+//! Each basic block from the branches file is identified by its line in the file. Like AFL, the
+//! edge from the previous block to the current one increments the map entry at
+//! `(previous >> 1) ^ current`, the shift keeping `A -> B` and `B -> A` as well as tight loops
+//! `A -> A` apart. Blocks missing from the branches file are not recorded.
+use rustc_hash::FxHashMap;
+use styx_core::{
+    prelude::*,
+    sync::sync::{Arc, Mutex},
+};
+
+/// Records the edges taken by the target into the coverage map.
+#[derive(Debug)]
+pub(crate) struct EdgeCoverage {
+    map: &'static mut [u8],
+    /// Block ids by start address.
+    branches: FxHashMap<u32, usize>,
+    /// Shifted id of the previous block.
+    previous: usize,
+}
+
+impl EdgeCoverage {
+    pub(crate) fn new(map: &'static mut [u8], branches: FxHashMap<u32, usize>) -> Self {
+        Self {
+            map,
+            branches,
+            previous: 0,
+        }
+    }
+
+    /// Create an [`EdgeCoverage`] and add its block hook to the `proc`.
+    pub(crate) fn install(
+        proc: &mut ProcessorCore,
+        map: &'static mut [u8],
+        branches: FxHashMap<u32, usize>,
+    ) -> Result<Arc<Mutex<Self>>, UnknownError> {
+        let coverage = Arc::new(Mutex::new(Self::new(map, branches)));
+
+        let block_coverage = coverage.clone();
+        proc.cpu
+            .add_hook(StyxHook::block(
+                move |_: CoreHandle, address: u64, _: u32| {
+                    block_coverage.lock().unwrap().block(address);
+                    Ok(())
+                },
+            ))
+            .with_context(|| "failed to add coverage hook")?;
+
+        Ok(coverage)
+    }
+
+    /// Start a new run, the first block is not an edge from the last block of the previous run.
+    pub(crate) fn begin_run(&mut self) {
+        self.previous = 0;
+    }
+
+    fn block(&mut self, address: u64) {
+        let Some(&current) = self.branches.get(&(address as u32)) else {
+            return;
+        };
+
+        let index = (self.previous ^ current) % self.map.len();
+        // This is roughly whats happening in the counters in afl++
+        // See: https://github.com/AFLplusplus/AFLplusplus/blob/ea14f3fd40e32234989043a525e3853fcb33c1b6/instrumentation/afl-compiler-rt.o.c#L179
+        self.map[index] = self.map[index].wrapping_add(1);
+        self.previous = current >> 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverage() -> EdgeCoverage {
+        let branches = FxHashMap::from_iter([(0x100, 4), (0x200, 8)]);
+        EdgeCoverage::new(Box::leak(vec![0; 16].into_boxed_slice()), branches)
+    }
+
+    fn hit(coverage: &EdgeCoverage) -> Vec<usize> {
+        (0..coverage.map.len())
+            .filter(|i| coverage.map[*i] > 0)
+            .collect()
+    }
+
+    #[test]
+    fn test_edges() {
+        let mut forward = coverage();
+        forward.block(0x100);
+        forward.block(0x200);
+        // entry edge to 4, then (4 >> 1) ^ 8
+        assert_eq!(hit(&forward), vec![4, 10]);
+
+        let mut backward = coverage();
+        backward.block(0x200);
+        backward.block(0x100);
+        // entry edge to 8, then (8 >> 1) ^ 4
+        assert_eq!(hit(&backward), vec![0, 8]);
+    }
+
+    #[test]
+    fn test_loop_counts() {
+        let mut coverage = coverage();
+        for _ in 0..3 {
+            coverage.block(0x100);
+        }
+        // entry edge, then the (4 >> 1) ^ 4 loop edge twice
+        assert_eq!(coverage.map[4], 1);
+        assert_eq!(coverage.map[6], 2);
+    }
+
+    #[test]
+    fn test_unknown_blocks() {
+        let mut coverage = coverage();
+        coverage.block(0x300);
+        assert!(hit(&coverage).is_empty());
+
+        coverage.block(0x100);
+        coverage.begin_run();
+        coverage.block(0x100);
+        assert_eq!(coverage.map[4], 2);
+    }
+}
@@ -3,46 +3,58 @@ use derivative::Derivative;
 #[cfg(feature = "tui")]
 use libafl::monitors::tui::TuiMonitor;
 #[cfg(not(feature = "tui"))]
-use libafl::prelude::SimpleMonitor;
+use libafl::prelude::{MultiMonitor, SimpleMonitor};
 use libafl::{
-    corpus::{CachedOnDiskCorpus, OnDiskCorpus},
-    events::SimpleEventManager,
+    corpus::{CachedOnDiskCorpus, Corpus, OnDiskCorpus},
+    events::{launcher::Launcher, EventConfig, SimpleEventManager},
     executors::{ExitKind, InProcessExecutor},
     feedback_or,
     feedbacks::{CrashFeedback, MaxMapFeedback, TimeFeedback},
     generators::RandBytesGenerator,
     inputs::{BytesInput, HasMutatorBytes},
-    mutators::{havoc_mutations, StdScheduledMutator},
+    mutators::{havoc_mutations, I2SRandReplace, StdScheduledMutator},
     observers::{CanTrack, HitcountsMapObserver, StdMapObserver, TimeObserver},
     prelude::{AflMapFeedback, TimeoutFeedback},
     schedulers::QueueScheduler,
-    stages::StdMutationalStage,
-    state::StdState,
+    stages::{IfStage, StdMutationalStage},
+    state::{HasCorpus, StdState},
     Fuzzer, StdFuzzer,
 };
 
-use libafl_bolts::{current_nanos, rands::StdRand, tuples::tuple_list};
+use libafl_bolts::{
+    core_affinity::{CoreId, Cores},
+    current_nanos,
+    rands::StdRand,
+    shmem::{ShMemProvider, StdShMemProvider},
+    tuples::tuple_list,
+};
 use rustc_hash::FxHashMap;
-use std::{any::Any, thread, time::Instant};
+use std::{any::Any, time::Instant};
 use std::{fs, marker::PhantomData};
-use std::{path::PathBuf, time::Duration};
-use styx_core::plugins::Plugins;
-use styx_core::{
-    cpu::ExecutionReport,
-    tracebus::{
-        BaseTraceEvent, IPCTracer, TraceProvider, TracerReader, TracerReaderOptions, STRACE,
-    },
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
 };
+use styx_core::cpu::ExecutionReport;
+use styx_core::plugins::Plugins;
 use styx_core::{executor::ExecutorImpl, prelude::*};
 use styx_sync::{
     cell::UnsafeCell,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+    sync::{Arc, Mutex},
 };
 use thiserror::Error;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
+
+mod cmplog;
+use cmplog::{CmpLog, CmpLogTracingStage};
+mod coverage;
+use coverage::EdgeCoverage;
+mod mmio;
+use mmio::MmioFuzzer;
+pub use mmio::{MmioFuzzConfig, MmioModel, MmioModels, CORTEX_M_PERIPHERALS};
+mod triage;
+use triage::CrashTriage;
+pub use triage::{CrashReport, TriageConfig};
 
 #[derive(Debug, Error)]
 pub enum StyxFuzzerError {
@@ -55,16 +67,15 @@ pub enum StyxFuzzerError {
 ///
 /// # Safety
 /// In order for styx to give afl access to the coverage map data, and allow
-/// the harness post-exec hook to process and then update the coverage map
-/// based on the styx-trace output we need to have 2 `&mut` to the internal
-/// coverage map.
+/// the coverage block hook to update the coverage map while the target runs
+/// we need to have 2 `&mut` to the internal coverage map.
 ///
 /// The only valid ways to consume this data are to either immediately hand
-/// off the mutable ref to afl, or to pass it into the post-processing thread.
+/// off the mutable ref to afl, or to pass it into the [`EdgeCoverage`] hook.
 ///
-/// The latter *must* synchronize access to operating on the coverage map only
-/// while afl is not operating on it (eg. while a "running flag" is set and before
-/// you tell afl "im done processing this exec" is set)
+/// Both run on the fuzzing thread and never at the same time, the hook only
+/// runs while the harness executes the target, afl only reads and resets the
+/// map before and after each execution.
 #[derive(Debug)]
 struct CoverageMap<'a> {
     buffer: UnsafeCell<Vec<u8>>,
@@ -154,6 +165,40 @@ pub struct StyxFuzzerConfig {
     /// Function for restoring context
     #[derivative(Debug = "ignore")]
     pub context_restore: ContextRestoreCBType,
+    /// Also save physical memory after setup and restore it after every run, on top of
+    /// [`Self::context_save`] and [`Self::context_restore`].
+    ///
+    /// Use a processor with a [`PhysicalMemoryVariant::CowRegionStore`] to make the restore cost
+    /// scale with the number of dirtied pages instead of the size of memory. Defaults to `false`.
+    ///
+    /// [`PhysicalMemoryVariant::CowRegionStore`]:
+    ///     styx_core::memory::physical::PhysicalMemoryVariant::CowRegionStore
+    pub restore_memory: bool,
+    /// Serve reads from MMIO ranges without peripheral implementations from the fuzz input, see
+    /// [`MmioFuzzConfig`]. Defaults to [`None`], only using [`Self::input_hook`].
+    ///
+    /// The whole input is consumed by MMIO reads, the [`Self::input_hook`] is still called with it.
+    pub mmio: Option<MmioFuzzConfig>,
+    /// Rerun each scheduled corpus entry logging the operands of the comparisons it executes, and
+    /// add input-to-state mutations replacing them in the entry, getting past magic value checks.
+    /// Defaults to `false`.
+    ///
+    /// Requires a cpu backend supporting compare hooks, i.e. the pcode backend.
+    pub cmplog: bool,
+    /// Save crashes with a [`CrashReport`], bucketed by their last basic blocks, see
+    /// [`TriageConfig`]. Defaults to [`None`], crashes are only saved to [`Self::crashes_dir`].
+    pub triage: Option<TriageConfig>,
+    /// Replay the input at this path instead of fuzzing, defaults to [`None`].
+    ///
+    /// The processor is set up like for fuzzing, then the input is run once. If it crashes its
+    /// [`CrashReport`] is passed to [`Self::replay_report`] and the input is minimized to
+    /// `<path>.min`, keeping the crash in the same bucket, with the report in `<path>.min.yaml`.
+    /// Uses [`Self::triage`], or the default [`TriageConfig`] if not set, with backtraces.
+    pub replay: Option<PathBuf>,
+    /// Called with the [`CrashReport`] of the input replayed with [`Self::replay`] if it
+    /// crashed, e.g. to print it. Does nothing by default.
+    #[derivative(Debug = "ignore")]
+    pub replay_report: ReplayReportCallbackType,
     /// Directory to use for the discovered crashes,
     /// defaults to `./crashes`
     pub crashes_dir: PathBuf,
@@ -182,6 +227,16 @@ pub struct StyxFuzzerConfig {
     /// Maximum number of inputs fuzz cases and reasons
     /// to keep in an in-memory cache
     pub max_in_mem_corpus: u64,
+    /// Number of fuzzing clients to run, defaults to `1`.
+    ///
+    /// With more than one core the clients are launched in separate processes bound to cores
+    /// `0..cores` and share their corpus through a broker on [`Self::broker_port`].
+    ///
+    /// **NOTE**: Ignored when using a custom [`Self::fuzz_func`].
+    pub cores: usize,
+    /// TCP port of the broker connecting the fuzzing clients when [`Self::cores`] is more than
+    /// one, defaults to `1337`.
+    pub broker_port: u16,
     /// Optional custom fuzzer function to use instead of the default
     #[derivative(Debug = "ignore")]
     pub fuzz_func: Option<FuzzerFuncType>,
@@ -200,22 +255,39 @@ impl Default for StyxFuzzerConfig {
             setup: Box::new(|_| ()),
             context_save: Box::new(|_| Arc::new(())),
             context_restore: Box::new(|_, _| ()),
+            restore_memory: false,
+            mmio: None,
+            cmplog: false,
+            triage: None,
+            replay: None,
+            replay_report: Box::new(|_| ()),
             corpus_paths: Vec::new(),
             crashes_dir: PathBuf::from("./crashes"),
             generator: StyxFuzzerInputType::default(),
             execution_stride: 1000,
             max_in_mem_corpus: 100,
+            cores: 1,
+            broker_port: 1337,
             fuzz_func: None,
         }
     }
 }
 
+/// Fuzzing modes acting on every run of [`FuzzerExecutor::harness_fn`].
+#[derive(Debug, Default)]
+struct RunHooks {
+    coverage: Option<Arc<Mutex<EdgeCoverage>>>,
+    mmio: Option<Arc<Mutex<MmioFuzzer>>>,
+    triage: Option<Arc<Mutex<CrashTriage>>>,
+}
+
 // typedefs to appease clippy
 type AnyTpe = Arc<dyn Any + Send>;
 type InputCallbackType = Box<dyn Fn(&mut ProcessorCore, &[u8]) -> bool>;
 type SetupCallbackType = Box<dyn Fn(&mut ProcessorCore)>;
 type ContextSaveCallbackType = Box<dyn Fn(&mut ProcessorCore) -> AnyTpe>;
 type ContextRestoreCBType = Box<dyn Fn(&mut ProcessorCore, AnyTpe)>;
+type ReplayReportCallbackType = Box<dyn Fn(&CrashReport)>;
 type FuzzerFuncType =
     Box<dyn Fn(&mut FuzzerExecutor, &mut ProcessorCore) -> Result<(), UnknownError>>;
 
@@ -226,10 +298,10 @@ unsafe impl Sync for StyxFuzzerConfig {}
 ///
 /// In order to properly construct this plugin you must provide a
 /// size of the coverage map that the plugin can use to share with the
-/// AFL harness and the styx-trace consumers can update.
+/// AFL harness and the coverage block hook can update.
 ///
-/// The processor needs to have a StyxTracePlugin attached to it, with
-/// only block trace events enabled.
+/// Edge coverage is collected in-process by a block hook, the processor
+/// does not need any plugins.
 ///
 /// When run, the plugin will first call the user-provided `config.setup` function
 /// which is intended to be used to get emulation into the state where fuzzing can
@@ -247,7 +319,6 @@ unsafe impl Sync for StyxFuzzerConfig {}
 /// ```no_run
 /// # use styx_emulator::loader::RawLoader;
 /// # use styx_emulator::processors::arm::kinetis21::Kinetis21Builder;
-/// # use styx_emulator::plugins::styx_trace::StyxTracePlugin;
 /// # use styx_emulator::prelude::*;
 /// # use styx_emulator::sync::Arc;
 /// # use styx_emulator::arch::arm::ArmVariants;
@@ -255,7 +326,6 @@ unsafe impl Sync for StyxFuzzerConfig {}
 /// # use std::any::Any;
 /// # use styx_emulator::plugins::fuzzer::{FuzzerExecutor, StyxFuzzerConfig};
 ///
-/// // needs to be a power of 2
 /// const COVERAGE_MAP_SIZE: usize = 1024;
 ///
 /// const MAX_INPUT_LEN: usize = 5;
@@ -291,7 +361,6 @@ unsafe impl Sync for StyxFuzzerConfig {}
 ///             ..Default::default()
 ///         },
 ///     ))
-///     .add_plugin(StyxTracePlugin::new(false, false, false, true))
 ///     .with_loader(RawLoader)
 ///     .with_target_program(String::from("path_to_program"))
 ///     .build().unwrap();
@@ -306,7 +375,7 @@ pub struct FuzzerExecutor<'a> {
 
 impl FuzzerExecutor<'static> {
     /// Makes a new [`FuzzerExecutor`] provided a [`CoverageMap`] size
-    /// to use for IPC between the styx trace targeting and the AFL harness
+    /// to share between the coverage block hook and the AFL harness
     pub fn new(coverage_map_size: usize, config: StyxFuzzerConfig) -> Self {
         Self {
             config,
@@ -347,88 +416,27 @@ impl FuzzerExecutor<'static> {
         }
     }
 
-    /// This function first clears any existing events from the ring buffer,
-    /// then spawns a thread to process trace events and update the coverage map
-    /// `emulation_running`
-    /// - set to true upon entering the fuzzer harness function
-    /// - set to false once emulation finishes in the fuzzer harness function
-    ///   `done_processing`
-    /// - set to true if the ring buffer is empty (when we receive a None)
-    /// - set to false before receiving a trace event
-    /// - the fuzzer harness function waits to return until this is true
-    fn capture_trace_events(
-        &self,
-        emulation_running: Arc<AtomicBool>,
-        done_processing: Arc<AtomicBool>,
-        branches: FxHashMap<u32, usize>,
-    ) {
-        let opts = TracerReaderOptions::new(&STRACE.key());
-        let mut rx = IPCTracer::get_consumer(opts.clone()).unwrap();
-
-        // drain the trace bus because we want this to block the main thread so
-        // that we make sure the buffer is empty before starting any fuzzing runs
-        loop {
-            let evt = rx.zero_copy_context().try_recv::<BaseTraceEvent>().unwrap();
-            if evt.is_none() {
-                break;
-            }
-        }
-
-        // prep the variables for the trace processing + updating of the
-        // coverage map
-        done_processing.store(true, Ordering::Release);
-        let coverage_map = unsafe { self.coverage_map.get_coverage_map_mut() };
-
-        // start the worker thread to process trace events forever
-        thread::spawn(move || {
-            loop {
-                // if emulation is not running don't start processing
-                //
-                // - emulation must be running for there to be any events
-                // - we need to be done processing the events to start processing
-                if !emulation_running.load(Ordering::Acquire)
-                    && done_processing.load(Ordering::Acquire)
-                {
-                    continue;
-                }
-
-                // signal the other thread that we are beginning to process data
-                done_processing.store(false, Ordering::Release);
-                let evt = rx.zero_copy_context().try_recv::<BaseTraceEvent>().unwrap();
-
-                // all events have `pc` attached, so do a lookup for
-                // pc to see if it's in our branch map, and get the coverage
-                // map index to update if so
-                if let Some(e) = evt {
-                    if let Some(v) = branches.get(&e.pc) {
-                        // update the coverage index
-                        //
-                        // This is roughly whats happening in the counters in afl++
-                        // See: https://github.com/AFLplusplus/AFLplusplus/blob/ea14f3fd40e32234989043a525e3853fcb33c1b6/instrumentation/afl-compiler-rt.o.c#L179
-                        coverage_map[*v] = coverage_map[*v].wrapping_add(1);
-                    }
-                }
-
-                // done processing the event, so signal
-                done_processing.store(true, Ordering::Release);
-            }
-        });
-    }
-
     /// Takes an input, inserts the input in memory, runs the emulation,
-    /// converts the styx exit status into the libafl exit status,
-    /// waits for the background thread to finish processing trace events
+    /// converts the styx exit status into the libafl exit status
     #[inline]
     fn harness_fn(
         &self,
         proc: &mut ProcessorCore,
         input: &BytesInput,
-        running: Arc<AtomicBool>,
-        done_processing: Arc<AtomicBool>,
         saved_context: AnyTpe,
+        hooks: &RunHooks,
     ) -> ExitKind {
-        // signal that we're about to start executing the target
-        running.store(true, Ordering::Release);
+        if let Some(coverage) = &hooks.coverage {
+            coverage.lock().unwrap().begin_run();
+        }
+        if let Some(mmio) = &hooks.mmio {
+            mmio.lock().unwrap().begin_run(input.bytes());
+        }
+        if let Some(triage) = &hooks.triage {
+            triage.lock().unwrap().begin_run();
+            // runs start from the saved context, not where the last one stopped
+            proc.event_controller.call_stack.clear();
+        }
         if !(self.config.input_hook)(proc, input.bytes()) {
             warn!("insert input failed");
             return ExitKind::Ok;
@@ -498,19 +506,235 @@ impl FuzzerExecutor<'static> {
         };
 
         // target is done running
-        running.store(false, Ordering::Release);
+        if let Some(mmio) = &hooks.mmio {
+            mmio.lock().unwrap().end_run();
+        }
+        if exit_kind == ExitKind::Crash {
+            if let Some(triage) = &hooks.triage {
+                self.triage_crash(proc, &mut triage.lock().unwrap(), reason, input.bytes());
+            }
+        }
 
         // call the context restore now that the target is done running
         (self.config.context_restore)(proc, saved_context);
+        if self.config.restore_memory {
+            proc.mmu.context_restore().unwrap();
+        }
 
-        // wait until the processing task is done
-        loop {
-            if done_processing.load(Ordering::Acquire) {
-                break;
+        exit_kind
+    }
+
+    /// Reports the crash the run ended with, saving it unless replaying.
+    fn triage_crash(
+        &self,
+        proc: &mut ProcessorCore,
+        triage: &mut CrashTriage,
+        reason: &TargetExitReason,
+        input: &[u8],
+    ) {
+        let report = match triage.crashed(proc, reason) {
+            Ok(report) => report.clone(),
+            Err(e) => {
+                warn!("failed to report crash: {e:?}");
+                return;
+            }
+        };
+        if self.config.replay.is_none() {
+            if let Err(e) = triage.save(&report, input) {
+                warn!("failed to save crash: {e:?}");
             }
         }
+    }
 
-        exit_kind
+    /// Runs the input at `path` once, minimizing it if it crashes, see
+    /// [`StyxFuzzerConfig::replay`].
+    ///
+    /// Returns the report of the input, [`None`] if it did not crash.
+    fn replay(
+        &self,
+        proc: &mut ProcessorCore,
+        path: &Path,
+        saved_cpu_context: AnyTpe,
+        hooks: &RunHooks,
+    ) -> Result<Option<CrashReport>, UnknownError> {
+        let input =
+            fs::read(path).with_context(|| format!("could not read input {}", path.display()))?;
+        let triage = hooks
+            .triage
+            .as_ref()
+            .with_context(|| "triage is not set up")?;
+
+        let mut run = |input: &[u8]| {
+            let exit_kind = self.harness_fn(
+                proc,
+                &BytesInput::new(input.to_vec()),
+                saved_cpu_context.clone(),
+                hooks,
+            );
+            let report = triage.lock().unwrap().last_crash().cloned();
+            (exit_kind, report)
+        };
+
+        let (exit_kind, report) = run(&input);
+        let Some(report) = report else {
+            info!("{} did not crash: {exit_kind:?}", path.display());
+            return Ok(None);
+        };
+
+        let minimized = triage::minimize(&input, &report.bucket, |candidate| {
+            run(candidate).1.map(|r| r.bucket)
+        });
+        info!(
+            "minimized {} from {} to {} bytes",
+            path.display(),
+            input.len(),
+            minimized.len()
+        );
+
+        // rerun for the report of the minimized input
+        let (_, minimized_report) = run(&minimized);
+        let minimized_path = PathBuf::from(format!("{}.min", path.display()));
+        fs::write(&minimized_path, &minimized)
+            .with_context(|| format!("could not write {}", minimized_path.display()))?;
+        if let Some(report) = minimized_report {
+            report.save(&PathBuf::from(format!("{}.yaml", minimized_path.display())))?;
+        }
+
+        Ok(Some(report))
+    }
+
+    /// Fuzzes with [`StyxFuzzerConfig::cores`] clients, each in its own process, connected through
+    /// an LLMP broker on [`StyxFuzzerConfig::broker_port`].
+    ///
+    /// The clients are forked from this process after the setup so they all start from the saved
+    /// context. New corpus entries are broadcast through the broker to every client.
+    fn libafl_fuzz_multi(
+        &self,
+        proc: &mut ProcessorCore,
+        saved_cpu_context: AnyTpe,
+        hooks: RunHooks,
+        cmplog: Arc<Mutex<CmpLog>>,
+    ) -> Result<(), UnknownError> {
+        #[cfg(not(feature = "tui"))]
+        let monitor = MultiMonitor::new(|s| println!("{s}"));
+        #[cfg(feature = "tui")]
+        let monitor = TuiMonitor::builder()
+            .title("Styx-LibAFL Fuzzer")
+            .enhanced_graphics(true)
+            .build();
+
+        let shmem_provider =
+            StdShMemProvider::new().with_context(|| "failed to create shared memory provider")?;
+        let cores = Cores::from_cmdline(&format!("0-{}", self.config.cores - 1))
+            .with_context(|| "invalid core count")?;
+
+        // called in every client process after it is spawned, and again after a client restarts
+        // from a timeout
+        let mut run_client =
+            |state: Option<_>, mut event_mgr, core_id: CoreId| -> Result<(), libafl::Error> {
+                debug!("starting fuzzing client on core {core_id:?}");
+
+                let time_observer = TimeObserver::new("time");
+                let coverage_observer = unsafe {
+                    HitcountsMapObserver::new(StdMapObserver::new(
+                        "coverage map",
+                        self.coverage_map.get_coverage_map_mut(),
+                    ))
+                };
+                let coverage_observer = coverage_observer.track_indices().track_novelties();
+
+                let mut feedback = feedback_or!(
+                    MaxMapFeedback::new(&coverage_observer),
+                    TimeFeedback::new(&time_observer),
+                    AflMapFeedback::new(&coverage_observer),
+                );
+                let mut objective = feedback_or!(CrashFeedback::new(), TimeoutFeedback::new());
+
+                // restarted clients get their state back from the restarting manager
+                let mut state = match state {
+                    Some(state) => state,
+                    None => StdState::new(
+                        StdRand::with_seed(current_nanos()),
+                        CachedOnDiskCorpus::new(
+                            &self.config.crashes_dir,
+                            self.config.max_in_mem_corpus as usize,
+                        )?,
+                        OnDiskCorpus::new(&self.config.crashes_dir)?,
+                        &mut feedback,
+                        &mut objective,
+                    )?,
+                };
+
+                let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+
+                let mut harness = |input: &BytesInput| {
+                    self.harness_fn(proc, input, saved_cpu_context.clone(), &hooks)
+                };
+
+                let mut executor = InProcessExecutor::with_timeout(
+                    &mut harness,
+                    tuple_list!(time_observer, coverage_observer),
+                    &mut fuzzer,
+                    &mut state,
+                    &mut event_mgr,
+                    self.config.timeout,
+                )?;
+
+                if state.must_load_initial_inputs() {
+                    let mut input_paths = vec![self.config.crashes_dir.clone()];
+                    input_paths.extend(self.config.corpus_paths.iter().cloned());
+                    state.load_initial_inputs(
+                        &mut fuzzer,
+                        &mut executor,
+                        &mut event_mgr,
+                        &input_paths,
+                    )?;
+
+                    // only the first client seeds an empty corpus, the others receive it from the
+                    // broker
+                    if state.corpus().count() == 0 && core_id == cores.ids[0] {
+                        let mut generator = RandBytesGenerator::new(self.config.max_input_len);
+                        state.generate_initial_inputs(
+                            &mut fuzzer,
+                            &mut executor,
+                            &mut generator,
+                            &mut event_mgr,
+                            8,
+                        )?;
+                    }
+                }
+
+                let mutator = StdScheduledMutator::new(havoc_mutations());
+                let i2s = StdScheduledMutator::new(tuple_list!(I2SRandReplace::new()));
+                let cmplog_enabled = self.config.cmplog;
+                let mut stages = tuple_list!(
+                    StdMutationalStage::new(mutator),
+                    IfStage::new(
+                        move |_, _, _, _| Ok(cmplog_enabled),
+                        tuple_list!(
+                            CmpLogTracingStage::new(cmplog.clone()),
+                            StdMutationalStage::new(i2s)
+                        )
+                    )
+                );
+                fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut event_mgr)?;
+                Ok(())
+            };
+
+        let result = Launcher::builder()
+            .shmem_provider(shmem_provider)
+            .configuration(EventConfig::from_name("styx-fuzzer"))
+            .monitor(monitor)
+            .run_client(&mut run_client)
+            .cores(&cores)
+            .broker_port(self.config.broker_port)
+            .build()
+            .launch();
+
+        match result {
+            Ok(()) | Err(libafl::Error::ShuttingDown) => Ok(()),
+            Err(e) => Err(e).with_context(|| "Error in the fuzzing launcher"),
+        }
     }
 
     /// the main fuzzing function
@@ -523,7 +747,53 @@ impl FuzzerExecutor<'static> {
         // - save the context to restore to between emulation runs
         self.config.setup.as_ref()(proc);
         self.fuzzer_setup(proc);
+        let coverage = EdgeCoverage::install(
+            proc,
+            unsafe { self.coverage_map.get_coverage_map_mut() },
+            branches,
+        )?;
+        let mmio = self
+            .config
+            .mmio
+            .clone()
+            .map(|config| MmioFuzzer::install(proc, config))
+            .transpose()
+            .with_context(|| "failed to set up mmio fuzzing")?;
+        let cmplog = Arc::new(Mutex::new(CmpLog::default()));
+        if self.config.cmplog {
+            cmplog::install(proc, cmplog.clone())?;
+        }
+        let triage = self
+            .config
+            .triage
+            .clone()
+            .or_else(|| self.config.replay.as_ref().map(|_| TriageConfig::default()))
+            .map(|mut config| {
+                config.backtraces |= self.config.replay.is_some();
+                CrashTriage::install(proc, config)
+            })
+            .transpose()?;
+        let hooks = RunHooks {
+            coverage: Some(coverage),
+            mmio,
+            triage,
+        };
         let saved_cpu_context = (self.config.context_save)(proc);
+        if self.config.restore_memory {
+            proc.mmu
+                .context_save()
+                .with_context(|| "failed to save memory for restoring between runs")?;
+        }
+
+        if let Some(path) = &self.config.replay {
+            if let Some(report) = self.replay(proc, path, saved_cpu_context, &hooks)? {
+                (self.config.replay_report)(&report);
+            }
+            return Ok(());
+        }
+        if self.config.cores > 1 {
+            return self.libafl_fuzz_multi(proc, saved_cpu_context, hooks, cmplog);
+        }
 
         // initialize observers
         // - execution timing
@@ -579,28 +849,11 @@ impl FuzzerExecutor<'static> {
 
         let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
 
-        // these atomics control the capturing of trace events
-        let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
-        let done_processing: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
-        // spawns a thread to read trace events, atomics get shared between the fuzzer harness and this new thread
-        self.capture_trace_events(running.clone(), done_processing.clone(), branches);
-
-        // make sure that the trace buffer is empty before beginning,
-        // `self.capture_trace_events` should block on draining
-        // the trace buffer before we get here
-        assert!(done_processing.load(Ordering::Acquire));
-
         // this closure takes in an input and returns an ExitKind depending on the exit state of the emulation
-        // it also invokes the trace event reciever which updates the observed coverage map
+        // the observed coverage map is updated by the coverage block hook while the target runs
         let mut harness = |input: &BytesInput| {
             // call the harness function
-            self.harness_fn(
-                proc,
-                input,
-                running.clone(),
-                done_processing.clone(),
-                saved_cpu_context.clone(),
-            )
+            self.harness_fn(proc, input, saved_cpu_context.clone(), &hooks)
         };
 
         // prepare for actual fuzz case execution
@@ -637,7 +890,20 @@ impl FuzzerExecutor<'static> {
 
         // Setup a mutational stage with a basic bytes mutator
         let mutator = StdScheduledMutator::new(havoc_mutations());
-        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+        // and an input-to-state stage using the comparisons of the unmutated input, if they are
+        // logged
+        let i2s = StdScheduledMutator::new(tuple_list!(I2SRandReplace::new()));
+        let cmplog_enabled = self.config.cmplog;
+        let mut stages = tuple_list!(
+            StdMutationalStage::new(mutator),
+            IfStage::new(
+                move |_, _, _, _| Ok(cmplog_enabled),
+                tuple_list!(
+                    CmpLogTracingStage::new(cmplog),
+                    StdMutationalStage::new(i2s)
+                )
+            )
+        );
 
         // run the fuzzer loop
         fuzzer
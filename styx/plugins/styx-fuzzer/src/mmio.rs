@@ -0,0 +1,560 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Peripheral-model-free fuzzing of memory mapped IO.
+//!
+//! In the style of [Fuzzware](https://github.com/fuzzware-fuzzer/fuzzware) and
+//! [P2IM](https://github.com/RiS3-Lab/p2im), reads from MMIO ranges without a peripheral
+//! implementation are served from the fuzz input instead of the input being written into RAM.
+//! Pages of the ranges that are not mapped get mapped on first access instead of faulting.
+//!
+//! The ranges have to be given in [`MmioFuzzConfig::ranges`] and must leave out the peripherals the
+//! processor models, the fuzzer would otherwise take over their registers.
+//!
+//! Each read is attributed to an access site, the pc of the instruction and the MMIO address, and
+//! handled according to the site's [`MmioModel`]. Sites without a model consume as many bytes of
+//! input as the read is wide. Models are learned from the accesses seen while fuzzing:
+//! - sites that are only read after the firmware wrote the register become
+//!   [`MmioModel::Passthrough`], e.g. control registers
+//! - sites polled in a loop become a [`MmioModel::Set`] of the values that ended the loop, e.g.
+//!   status registers
+//!
+//! Learned models are persisted to [`MmioFuzzConfig::models_path`], which can also be edited by
+//! hand to add [`MmioModel::Constant`] models.
+//!
+//! A run ends when the input is exhausted.
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use styx_core::{
+    errors::anyhow::bail,
+    hooks::Resolution,
+    prelude::*,
+    sync::sync::{Arc, Mutex},
+};
+use tracing::{debug, info, warn};
+
+/// Peripheral region of the Cortex-M memory map.
+///
+/// This includes the peripherals the processor models, only fuzz all of it on processors without
+/// peripheral models.
+pub const CORTEX_M_PERIPHERALS: Range<u64> = 0x4000_0000..0x6000_0000;
+
+/// Granularity MMIO pages are mapped with.
+const MMIO_PAGE_SIZE: u64 = 0x1000;
+
+/// Most values a [`MmioModel::Set`] is learned with, sites ending polling loops with more distinct
+/// values are data registers.
+const MAX_SET_VALUES: usize = 16;
+
+/// Configuration of the MMIO fuzzing mode, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct MmioFuzzConfig {
+    /// MMIO ranges without peripheral implementations, must not be empty.
+    pub ranges: Vec<Range<u64>>,
+    /// File to load models from and save learned models to, models are not persisted if
+    /// [`None`].
+    pub models_path: Option<PathBuf>,
+    /// Consecutive reads of the same site after which it is considered polled, defaults to `8`.
+    pub poll_threshold: u32,
+    /// Runs a site has to be read in before a model is learned for it, defaults to `16`.
+    pub learn_after: u32,
+}
+
+impl Default for MmioFuzzConfig {
+    fn default() -> Self {
+        Self {
+            ranges: Vec::new(),
+            models_path: None,
+            poll_threshold: 8,
+            learn_after: 16,
+        }
+    }
+}
+
+/// How reads from an MMIO access site are served.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum MmioModel {
+    /// Each read consumes as many input bytes as it is wide.
+    Fuzzed,
+    /// Reads return the value last written to the register, or zero.
+    Passthrough,
+    /// Reads always return `value`.
+    Constant { value: u64 },
+    /// Each read consumes one input byte to choose one of `values`.
+    Set { values: Vec<u64> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ModelEntry {
+    pc: u64,
+    address: u64,
+    #[serde(flatten)]
+    model: MmioModel,
+}
+
+/// [`MmioModel`]s by access site.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MmioModels {
+    /// Keyed on (pc, address).
+    models: FxHashMap<(u64, u64), MmioModel>,
+}
+
+impl MmioModels {
+    /// Load models saved with [`MmioModels::save()`].
+    pub fn load(path: &Path) -> Result<Self, UnknownError> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read mmio models from {}", path.display()))?;
+        Self::from_yaml(&contents)
+    }
+
+    /// Save the models as yaml.
+    pub fn save(&self, path: &Path) -> Result<(), UnknownError> {
+        fs::write(path, self.to_yaml()?)
+            .with_context(|| format!("could not write mmio models to {}", path.display()))
+    }
+
+    fn from_yaml(contents: &str) -> Result<Self, UnknownError> {
+        let entries: Vec<ModelEntry> =
+            serde_yaml::from_str(contents).with_context(|| "invalid mmio models")?;
+        Ok(Self {
+            models: entries
+                .into_iter()
+                .map(|e| ((e.pc, e.address), e.model))
+                .collect(),
+        })
+    }
+
+    fn to_yaml(&self) -> Result<String, UnknownError> {
+        let mut entries: Vec<_> = self
+            .models
+            .iter()
+            .map(|(&(pc, address), model)| ModelEntry {
+                pc,
+                address,
+                model: model.clone(),
+            })
+            .collect();
+        entries.sort_by_key(|e| (e.address, e.pc));
+        Ok(serde_yaml::to_string(&entries)?)
+    }
+
+    /// Model of the site reading `address` at `pc`.
+    pub fn get(&self, pc: u64, address: u64) -> Option<&MmioModel> {
+        self.models.get(&(pc, address))
+    }
+
+    /// Set the model of the site reading `address` at `pc`.
+    pub fn insert(&mut self, pc: u64, address: u64, model: MmioModel) {
+        self.models.insert((pc, address), model);
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
+
+/// Observations of a site across runs.
+#[derive(Debug, Default)]
+struct SiteStats {
+    /// Runs the site was read in.
+    runs: u32,
+    /// Runs every read of the site followed a write to the register.
+    written_runs: u32,
+    /// Values that ended polling loops on the site.
+    escapes: BTreeSet<u64>,
+}
+
+/// Reads of a site in the current run.
+#[derive(Debug, Default)]
+struct RunSite {
+    /// Reads before the register was written.
+    unwritten_reads: u32,
+}
+
+/// Consecutive reads of one site.
+#[derive(Debug)]
+struct Streak {
+    site: (u64, u64),
+    count: u32,
+    value: u64,
+}
+
+/// Serves MMIO reads from the fuzz input and learns [`MmioModel`]s.
+#[derive(Debug)]
+pub(crate) struct MmioFuzzer {
+    config: MmioFuzzConfig,
+    models: MmioModels,
+    stats: FxHashMap<(u64, u64), SiteStats>,
+    input: Vec<u8>,
+    cursor: usize,
+    exhausted: bool,
+    /// Values written to registers this run.
+    written: FxHashMap<u64, u64>,
+    sites: FxHashMap<(u64, u64), RunSite>,
+    streak: Option<Streak>,
+    /// Polling loops ended this run.
+    escapes: Vec<((u64, u64), u64)>,
+}
+
+impl MmioFuzzer {
+    pub(crate) fn new(config: MmioFuzzConfig) -> Result<Self, UnknownError> {
+        if config.ranges.is_empty() {
+            bail!("no mmio ranges to fuzz, give the ranges without peripheral implementations");
+        }
+
+        let models = match &config.models_path {
+            Some(path) if path.exists() => MmioModels::load(path)?,
+            _ => MmioModels::default(),
+        };
+        info!("fuzzing mmio with {} models", models.len());
+
+        Ok(Self {
+            config,
+            models,
+            stats: FxHashMap::default(),
+            input: Vec::new(),
+            cursor: 0,
+            exhausted: false,
+            written: FxHashMap::default(),
+            sites: FxHashMap::default(),
+            streak: None,
+            escapes: Vec::new(),
+        })
+    }
+
+    /// Create a [`MmioFuzzer`] and add its hooks to the `proc`.
+    pub(crate) fn install(
+        proc: &mut ProcessorCore,
+        config: MmioFuzzConfig,
+    ) -> Result<Arc<Mutex<Self>>, UnknownError> {
+        let ranges = config.ranges.clone();
+        let fuzzer = Arc::new(Mutex::new(Self::new(config)?));
+
+        for range in ranges {
+            let read_fuzzer = fuzzer.clone();
+            proc.cpu.add_hook(StyxHook::memory_read(
+                range.clone(),
+                move |proc: CoreHandle<'_>, address: u64, _size: u32, data: &mut [u8]| {
+                    let pc = proc.cpu.pc()?;
+                    let endian = proc.cpu.endian();
+                    match read_fuzzer.lock().unwrap().read(pc, address, data.len()) {
+                        Some(value) => encode(value, endian, data),
+                        None => {
+                            // input exhausted, end the run
+                            data.fill(0);
+                            proc.cpu.stop();
+                        }
+                    }
+                    Ok(())
+                },
+            ))?;
+
+            let write_fuzzer = fuzzer.clone();
+            proc.cpu.add_hook(StyxHook::memory_write(
+                range.clone(),
+                move |proc: CoreHandle<'_>, address: u64, _size: u32, data: &[u8]| {
+                    let value = decode(proc.cpu.endian(), data);
+                    write_fuzzer.lock().unwrap().write(address, value);
+                    Ok(())
+                },
+            ))?;
+
+            let bounds = range.clone();
+            proc.cpu.add_hook(StyxHook::unmapped_fault(
+                range,
+                move |proc: CoreHandle<'_>, address: u64, _size: u32, _data: MemFaultData| {
+                    map_page(proc, &bounds, address)
+                },
+            ))?;
+        }
+
+        Ok(fuzzer)
+    }
+
+    /// Start a run consuming `input`.
+    pub(crate) fn begin_run(&mut self, input: &[u8]) {
+        self.input.clear();
+        self.input.extend_from_slice(input);
+        self.cursor = 0;
+        self.exhausted = false;
+        self.written.clear();
+        self.sites.clear();
+        self.streak = None;
+        self.escapes.clear();
+    }
+
+    /// Value of a `size` byte read of `address` at `pc`, [`None`] if the input is exhausted.
+    pub(crate) fn read(&mut self, pc: u64, address: u64, size: usize) -> Option<u64> {
+        let site = (pc, address);
+        let written = self.written.get(&address).copied();
+        let run_site = self.sites.entry(site).or_default();
+        if written.is_none() {
+            run_site.unwritten_reads += 1;
+        }
+
+        let value = match self.models.get(pc, address) {
+            Some(MmioModel::Passthrough) => written.unwrap_or(0),
+            Some(MmioModel::Constant { value }) => *value,
+            Some(MmioModel::Set { values }) if !values.is_empty() => {
+                let choice = self.take(1)?;
+                values[choice as usize % values.len()]
+            }
+            _ => self.take(size.min(8))?,
+        };
+
+        self.track_streak(site, value);
+        Some(value)
+    }
+
+    /// Record a write of `value` to `address`.
+    pub(crate) fn write(&mut self, address: u64, value: u64) {
+        self.written.insert(address, value);
+    }
+
+    /// Finish the run and learn models from it, returns true if models were learned.
+    ///
+    /// Learned models are saved to [`MmioFuzzConfig::models_path`].
+    pub(crate) fn end_run(&mut self) -> bool {
+        // a polling loop interrupted by the end of the input was not escaped
+        if let Some(streak) = self.streak.take() {
+            if !self.exhausted {
+                self.end_streak(streak);
+            }
+        }
+
+        for (site, run_site) in self.sites.drain() {
+            let stats = self.stats.entry(site).or_default();
+            stats.runs += 1;
+            if run_site.unwritten_reads == 0 {
+                stats.written_runs += 1;
+            }
+        }
+        for (site, value) in self.escapes.drain(..) {
+            self.stats.entry(site).or_default().escapes.insert(value);
+        }
+
+        let mut learned = false;
+        for (&(pc, address), stats) in self.stats.iter() {
+            if stats.runs < self.config.learn_after || self.models.get(pc, address).is_some() {
+                continue;
+            }
+
+            let model = if stats.written_runs == stats.runs {
+                MmioModel::Passthrough
+            } else if !stats.escapes.is_empty() && stats.escapes.len() <= MAX_SET_VALUES {
+                MmioModel::Set {
+                    values: stats.escapes.iter().copied().collect(),
+                }
+            } else {
+                continue;
+            };
+
+            debug!("learned {model:?} for 0x{address:X} read at 0x{pc:X}");
+            self.models.insert(pc, address, model);
+            learned = true;
+        }
+
+        if learned {
+            if let Some(path) = &self.config.models_path {
+                if let Err(e) = self.models.save(path) {
+                    warn!("could not save mmio models: {e:?}");
+                }
+            }
+        }
+        learned
+    }
+
+    /// Consume `size` bytes of input as a little endian value.
+    fn take(&mut self, size: usize) -> Option<u64> {
+        let Some(bytes) = self.input.get(self.cursor..self.cursor + size) else {
+            self.exhausted = true;
+            return None;
+        };
+        self.cursor += size;
+
+        let mut value = [0u8; 8];
+        value[..size].copy_from_slice(bytes);
+        Some(u64::from_le_bytes(value))
+    }
+
+    fn track_streak(&mut self, site: (u64, u64), value: u64) {
+        match &mut self.streak {
+            Some(streak) if streak.site == site => {
+                streak.count += 1;
+                streak.value = value;
+            }
+            _ => {
+                if let Some(streak) = self.streak.take() {
+                    self.end_streak(streak);
+                }
+                self.streak = Some(Streak {
+                    site,
+                    count: 1,
+                    value,
+                });
+            }
+        }
+    }
+
+    fn end_streak(&mut self, streak: Streak) {
+        if streak.count >= self.config.poll_threshold {
+            self.escapes.push((streak.site, streak.value));
+        }
+    }
+}
+
+/// Map the page of the MMIO `range` containing `address`.
+fn map_page(
+    proc: CoreHandle<'_>,
+    range: &Range<u64>,
+    address: u64,
+) -> Result<Resolution, UnknownError> {
+    let page = address - address % MMIO_PAGE_SIZE;
+    let base = page.max(range.start);
+    let end = (page + MMIO_PAGE_SIZE).min(range.end);
+
+    let mut region = MemoryRegion::new(base, end - base, MemoryPermissions::all())?;
+    // give the region a saved context so restoring memory between runs still works
+    unsafe { region.context_save()? };
+    match proc.mmu.add_memory_region(region) {
+        Ok(()) => {
+            debug!("mapped mmio page 0x{base:X}..0x{end:X}");
+            Ok(Resolution::Fixed)
+        }
+        Err(e) => {
+            warn!("could not map mmio page 0x{base:X}..0x{end:X}: {e}");
+            Ok(Resolution::NotFixed)
+        }
+    }
+}
+
+/// Write `value` into `data` in target endianness.
+fn encode(value: u64, endian: ArchEndian, data: &mut [u8]) {
+    let size = data.len().min(8);
+    match endian {
+        ArchEndian::LittleEndian => data[..size].copy_from_slice(&value.to_le_bytes()[..size]),
+        ArchEndian::BigEndian => data[..size].copy_from_slice(&value.to_be_bytes()[8 - size..]),
+    }
+}
+
+/// Read a value in target endianness from `data`.
+fn decode(endian: ArchEndian, data: &[u8]) -> u64 {
+    let size = data.len().min(8);
+    let mut value = [0u8; 8];
+    match endian {
+        ArchEndian::LittleEndian => {
+            value[..size].copy_from_slice(&data[..size]);
+            u64::from_le_bytes(value)
+        }
+        ArchEndian::BigEndian => {
+            value[8 - size..].copy_from_slice(&data[..size]);
+            u64::from_be_bytes(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fuzzer() -> MmioFuzzer {
+        MmioFuzzer::new(MmioFuzzConfig {
+            ranges: vec![CORTEX_M_PERIPHERALS],
+            poll_threshold: 3,
+            learn_after: 2,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ranges_required() {
+        assert!(MmioFuzzer::new(MmioFuzzConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_read_consumes_input() {
+        let mut fuzzer = fuzzer();
+        fuzzer.begin_run(&[0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(fuzzer.read(0x100, 0x4000_0000, 4), Some(0x4433_2211));
+        assert_eq!(fuzzer.read(0x104, 0x4000_0004, 1), Some(0x55));
+        assert_eq!(fuzzer.read(0x104, 0x4000_0004, 1), None);
+        assert!(!fuzzer.end_run());
+    }
+
+    #[test]
+    fn test_learn_passthrough() {
+        let mut fuzzer = fuzzer();
+        for _ in 0..2 {
+            fuzzer.begin_run(&[0xFF; 8]);
+            fuzzer.write(0x4000_0000, 0x5);
+            assert_eq!(fuzzer.read(0x100, 0x4000_0000, 4), Some(0xFFFF_FFFF));
+            fuzzer.end_run();
+        }
+        assert_eq!(
+            fuzzer.models.get(0x100, 0x4000_0000),
+            Some(&MmioModel::Passthrough)
+        );
+
+        fuzzer.begin_run(&[]);
+        fuzzer.write(0x4000_0000, 0x5);
+        assert_eq!(fuzzer.read(0x100, 0x4000_0000, 4), Some(0x5));
+    }
+
+    #[test]
+    fn test_learn_set() {
+        let mut fuzzer = fuzzer();
+        for escape in [0x80, 0x40] {
+            fuzzer.begin_run(&[0, 0, 0, escape, 1]);
+            for _ in 0..4 {
+                fuzzer.read(0x100, 0x4000_0010, 1).unwrap();
+            }
+            // leaving the loop
+            fuzzer.read(0x200, 0x4000_0014, 1).unwrap();
+            fuzzer.end_run();
+        }
+        assert_eq!(
+            fuzzer.models.get(0x100, 0x4000_0010),
+            Some(&MmioModel::Set {
+                values: vec![0x40, 0x80]
+            })
+        );
+        // never polled
+        assert_eq!(fuzzer.models.get(0x200, 0x4000_0014), None);
+
+        fuzzer.begin_run(&[1]);
+        assert_eq!(fuzzer.read(0x100, 0x4000_0010, 1), Some(0x80));
+    }
+
+    #[test]
+    fn test_models_yaml() {
+        let mut models = MmioModels::default();
+        models.insert(0x100, 0x4000_0000, MmioModel::Passthrough);
+        models.insert(0x104, 0x4000_0004, MmioModel::Constant { value: 1 });
+        models.insert(0x108, 0x4000_0004, MmioModel::Set { values: vec![1, 2] });
+        models.insert(0x10C, 0x4000_0008, MmioModel::Fuzzed);
+
+        let yaml = models.to_yaml().unwrap();
+        assert_eq!(MmioModels::from_yaml(&yaml).unwrap(), models);
+    }
+
+    #[test]
+    fn test_endian() {
+        let mut data = [0u8; 2];
+        encode(0x1234, ArchEndian::BigEndian, &mut data);
+        assert_eq!(data, [0x12, 0x34]);
+        assert_eq!(decode(ArchEndian::BigEndian, &data), 0x1234);
+        encode(0x1234, ArchEndian::LittleEndian, &mut data);
+        assert_eq!(data, [0x34, 0x12]);
+        assert_eq!(decode(ArchEndian::LittleEndian, &data), 0x1234);
+    }
+}
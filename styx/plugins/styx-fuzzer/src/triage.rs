@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Crash triage, deduplication and minimization.
+//!
+//! With triage enabled, a block hook keeps the last [`TriageConfig::last_blocks`] basic blocks of
+//! every run. Runs that crash produce a [`CrashReport`] with the exit reason, pc, registers and
+//! those blocks. With [`TriageConfig::backtraces`] set, on architectures supporting
+//! [call tracking](styx_core::core::ProcessorCore::track_calls()) the report also holds a
+//! backtrace and any returns that did not match the call stack, which usually means a smashed
+//! stack. Replaying an input always collects them. Crashes are bucketed by a hash of their last blocks, each bucket is a directory
+//! in [`TriageConfig::dir`] holding the crashing inputs and a `.yaml` report next to each one:
+//!
+//! ```text
+//! triage/
+//! └── 3c1f9a0e2b7d4c55/
+//!     ├── 0f2d6e3a9c1b8e47
+//!     └── 0f2d6e3a9c1b8e47.yaml
+//! ```
+//!
+//! Setting [`StyxFuzzerConfig::replay`](crate::StyxFuzzerConfig::replay) replays a single input
+//! instead of fuzzing, then minimizes it while keeping it in the same bucket, see
+//! [`minimize()`].
+use rustc_hash::FxHasher;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+use styx_core::{
+    prelude::*,
+    snapshot::CpuSnapshot,
+    sync::sync::{Arc, Mutex},
+};
+use tracing::{debug, info};
+
+/// Configuration of crash triage, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct TriageConfig {
+    /// Directory crash buckets are written to, defaults to `./triage`.
+    pub dir: PathBuf,
+    /// Number of basic blocks kept for reports and bucketing, defaults to `16`.
+    pub last_blocks: usize,
+    /// Track calls while fuzzing to add backtraces to the reports, defaults to `false`.
+    ///
+    /// Call tracking hooks every call and return, which slows down every run. Replaying an input
+    /// tracks calls regardless.
+    pub backtraces: bool,
+}
+
+impl Default for TriageConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("./triage"),
+            last_blocks: 16,
+            backtraces: false,
+        }
+    }
+}
+
+/// Context of a crashing run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// Hash of [`Self::last_blocks`] naming the crash's bucket.
+    pub bucket: String,
+    /// Debug representation of the [`TargetExitReason`].
+    pub exit_reason: String,
+    pub pc: u64,
+    /// Register values in hex, by name.
+    pub registers: BTreeMap<String, String>,
+    /// Start addresses of the last basic blocks executed, oldest first.
+    pub last_blocks: Vec<u64>,
+    /// Backtrace at the crash, innermost frame first. Empty without call tracking.
+    #[serde(default)]
+    pub backtrace: Vec<String>,
+    /// Returns that did not go back to their caller, oldest first.
+    #[serde(default)]
+    pub mismatched_returns: Vec<String>,
+}
+
+impl CrashReport {
+    /// Load a report saved next to a crashing input.
+    pub fn load(path: &Path) -> Result<Self, UnknownError> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read crash report {}", path.display()))?;
+        serde_yaml::from_str(&contents).with_context(|| "invalid crash report")
+    }
+
+    /// Save the report as yaml.
+    pub fn save(&self, path: &Path) -> Result<(), UnknownError> {
+        fs::write(path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("could not write crash report to {}", path.display()))
+    }
+}
+
+/// Formats the report as yaml.
+impl std::fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let yaml = serde_yaml::to_string(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(&yaml)
+    }
+}
+
+/// Bucket of a crash with the `last_blocks`.
+fn bucket(last_blocks: &[u64]) -> String {
+    let mut hasher = FxHasher::default();
+    last_blocks.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Name of a saved input.
+fn input_name(input: &[u8]) -> String {
+    let mut hasher = FxHasher::default();
+    input.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Records the last blocks of each run and reports crashes.
+#[derive(Debug)]
+pub(crate) struct CrashTriage {
+    config: TriageConfig,
+    blocks: VecDeque<u64>,
+    /// Report of the last run, if it crashed.
+    last_crash: Option<CrashReport>,
+}
+
+impl CrashTriage {
+    pub(crate) fn new(config: TriageConfig) -> Self {
+        Self {
+            blocks: VecDeque::with_capacity(config.last_blocks),
+            config,
+            last_crash: None,
+        }
+    }
+
+    /// Add the block hook recording the last blocks to the processor, and track calls for
+    /// [`TriageConfig::backtraces`] if the architecture supports it.
+    pub(crate) fn install(
+        proc: &mut ProcessorCore,
+        config: TriageConfig,
+    ) -> Result<Arc<Mutex<Self>>, UnknownError> {
+        let backtraces = config.backtraces;
+        let triage = Arc::new(Mutex::new(Self::new(config)));
+
+        let block_triage = triage.clone();
+        proc.cpu
+            .add_hook(StyxHook::block(
+                move |_: CoreHandle, address: u64, _: u32| {
+                    block_triage.lock().unwrap().block(address);
+                    Ok(())
+                },
+            ))
+            .with_context(|| "failed to add triage block hook")?;
+
+        if backtraces && !proc.call_stack().is_enabled() {
+            if let Err(e) = proc.track_calls() {
+                info!("crash reports will not have backtraces: {e}");
+            }
+        }
+
+        Ok(triage)
+    }
+
+    /// Start a new run.
+    pub(crate) fn begin_run(&mut self) {
+        self.blocks.clear();
+        self.last_crash = None;
+    }
+
+    fn block(&mut self, address: u64) {
+        if self.blocks.len() == self.config.last_blocks {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(address);
+    }
+
+    /// Report the crash the run ended with.
+    pub(crate) fn crashed(
+        &mut self,
+        proc: &mut ProcessorCore,
+        exit_reason: &TargetExitReason,
+    ) -> Result<&CrashReport, UnknownError> {
+        let cpu = CpuSnapshot::capture(proc.cpu.as_mut())?;
+        let last_blocks: Vec<u64> = self.blocks.iter().copied().collect();
+
+        let call_stack = proc.call_stack();
+        let backtrace = if call_stack.is_enabled() {
+            call_stack
+                .backtrace(cpu.pc)
+                .into_iter()
+                .map(|address| format!("0x{address:X}: {}", proc.symbols.describe(address)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mismatched_returns = call_stack
+            .mismatches()
+            .iter()
+            .map(|mismatch| {
+                format!(
+                    "return at 0x{:X} went to 0x{:X} instead of 0x{:X}",
+                    mismatch.return_site, mismatch.actual, mismatch.expected
+                )
+            })
+            .collect();
+
+        Ok(self.last_crash.insert(CrashReport {
+            bucket: bucket(&last_blocks),
+            exit_reason: format!("{exit_reason:?}"),
+            pc: cpu.pc,
+            registers: cpu
+                .registers
+                .into_iter()
+                .map(|r| (r.name, format!("0x{:X}", r.value)))
+                .collect(),
+            last_blocks,
+            backtrace,
+            mismatched_returns,
+        }))
+    }
+
+    /// Report of the last run, [`None`] if it did not crash.
+    pub(crate) fn last_crash(&self) -> Option<&CrashReport> {
+        self.last_crash.as_ref()
+    }
+
+    /// Save `input` and its `report` to the report's bucket.
+    ///
+    /// Returns the path of the saved input.
+    pub(crate) fn save(&self, report: &CrashReport, input: &[u8]) -> Result<PathBuf, UnknownError> {
+        let bucket_dir = self.config.dir.join(&report.bucket);
+        if !bucket_dir.exists() {
+            info!(
+                "new crash bucket {} at pc 0x{:X}: {}",
+                report.bucket, report.pc, report.exit_reason
+            );
+            fs::create_dir_all(&bucket_dir)
+                .with_context(|| format!("could not create {}", bucket_dir.display()))?;
+        }
+
+        let path = bucket_dir.join(input_name(input));
+        if !path.exists() {
+            fs::write(&path, input)
+                .with_context(|| format!("could not write crash to {}", path.display()))?;
+            report.save(&path.with_extension("yaml"))?;
+        }
+
+        Ok(path)
+    }
+}
+
+/// Minimize a crashing `input`, keeping it crashing in the same `bucket`.
+///
+/// `run` runs an input and returns the bucket of its crash, or [`None`] if it did not crash.
+/// Chunks of the input are removed while the bucket does not change, starting with halves down
+/// to single bytes, then remaining bytes are zeroed where possible.
+pub(crate) fn minimize(
+    input: &[u8],
+    bucket: &str,
+    mut run: impl FnMut(&[u8]) -> Option<String>,
+) -> Vec<u8> {
+    let mut input = input.to_vec();
+    let mut same_bucket = |candidate: &[u8]| run(candidate).as_deref() == Some(bucket);
+
+    let mut chunk = input.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < input.len() {
+            let end = (start + chunk).min(input.len());
+            let mut candidate = input[..start].to_vec();
+            candidate.extend_from_slice(&input[end..]);
+            if same_bucket(&candidate) {
+                debug!("removed [{start}, {end}), {} bytes left", candidate.len());
+                input = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk /= 2;
+    }
+
+    for i in 0..input.len() {
+        if input[i] == 0 {
+            continue;
+        }
+        let mut candidate = input.clone();
+        candidate[i] = 0;
+        if same_bucket(&candidate) {
+            input = candidate;
+        }
+    }
+
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_blocks() {
+        let mut triage = CrashTriage::new(TriageConfig {
+            last_blocks: 3,
+            ..Default::default()
+        });
+        for block in 0..5 {
+            triage.block(block * 0x10);
+        }
+        assert_eq!(triage.blocks, [0x20, 0x30, 0x40]);
+
+        triage.begin_run();
+        assert!(triage.blocks.is_empty());
+    }
+
+    #[test]
+    fn test_bucket() {
+        assert_eq!(bucket(&[0x100, 0x200]), bucket(&[0x100, 0x200]));
+        assert_ne!(bucket(&[0x100, 0x200]), bucket(&[0x200, 0x100]));
+    }
+
+    #[test]
+    fn test_minimize() {
+        // crashes in bucket "a" if the input contains "AB", in "b" if it only contains "A"
+        let run = |input: &[u8]| {
+            if input.windows(2).any(|w| w == b"AB") {
+                Some("a".to_owned())
+            } else if input.contains(&b'A') {
+                Some("b".to_owned())
+            } else {
+                None
+            }
+        };
+
+        assert_eq!(minimize(b"xxxxABxxxxxA", "a", run), b"AB");
+        assert_eq!(minimize(b"xxxxABxxxxxA", "b", run), b"A");
+        assert_eq!(minimize(b"xAy", "b", run), b"A");
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let report = CrashReport {
+            bucket: bucket(&[0x100]),
+            exit_reason: format!("{:?}", TargetExitReason::UnmappedMemoryRead),
+            pc: 0x104,
+            registers: BTreeMap::from([("r0".to_owned(), "0x1".to_owned())]),
+            last_blocks: vec![0x100],
+            backtrace: vec!["0x104: crash+0x4".to_owned(), "0x80: main+0x10".to_owned()],
+            mismatched_returns: Vec::new(),
+        };
+        let yaml = serde_yaml::to_string(&report).unwrap();
+        assert_eq!(serde_yaml::from_str::<CrashReport>(&yaml).unwrap(), report);
+        assert_eq!(report.to_string(), yaml);
+    }
+}
@@ -78,6 +78,8 @@ impl Ord for BpContainer {
 #[derive(Debug, Default)]
 pub struct BreakpointManager {
     paused: AtomicBool,
+    /// Breakpoints are ignored while replaying recorded execution
+    suspended: AtomicBool,
     paused_address: Arc<Mutex<u64>>,
     /// Addresses of breakpoints from the gdb client. These get reset on each
     /// emulation start/stop.
@@ -191,6 +193,18 @@ impl BreakpointManager {
         self.paused.load(Ordering::Acquire)
     }
 
+    /// Sets `self.suspended`, while suspended breakpoints don't stop the cpu
+    #[inline]
+    pub fn set_suspended(&self, suspended: bool) {
+        self.suspended.store(suspended, Ordering::Release);
+    }
+
+    /// Gets the current state of `self.suspended`
+    #[inline]
+    pub fn suspended(&self) -> bool {
+        self.suspended.load(Ordering::Acquire)
+    }
+
     pub fn add_breakpoint(&self, hook_token: HookToken, addr: u64) -> bool {
         // TODO: only 1 bp per address for now
         if self.contains_breakpoint(&addr) {
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use std::{fmt::Debug, net::SocketAddr};
 
-use super::{GdbExecutor, GdbPluginParams};
+use super::{GdbExecutor, GdbPluginParams, RecordConfig};
 
 use tap::Conv;
 
@@ -24,6 +24,12 @@ pub struct GdbConfig {
     pub arch: ArchVariant,
     #[serde(default)]
     pub verbose: bool,
+    /// Record execution to support reverse execution
+    #[serde(default)]
+    pub record: Option<RecordConfig>,
+    /// Track calls and returns for `monitor backtrace`, the client's `bt` still needs frame info
+    #[serde(default)]
+    pub call_tracking: bool,
 }
 
 /// Build a GDB executor with an [`ArchVariant`] and [`GdbPluginParams`].
@@ -52,6 +58,15 @@ pub fn build_gdb_config(config: GdbConfig) -> Result<Box<dyn ExecutorImpl>, Unkn
     } else {
         GdbPluginParams::uds(connection.to_owned().leak(), verbose)
     };
+    let gdb_params = match config.record {
+        Some(record) => gdb_params.with_record(record),
+        None => gdb_params,
+    };
+    let gdb_params = if config.call_tracking {
+        gdb_params.with_call_tracking()
+    } else {
+        gdb_params
+    };
 
     let gdb = build_gdb(config.arch, gdb_params).with_context(|| "could not build gdb plugin")?;
     Ok(gdb)
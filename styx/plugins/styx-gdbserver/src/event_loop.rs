@@ -4,12 +4,14 @@
 //! [`EmuGdbEventLoop`] implements
 //! [the blocking event loop trait](https://docs.rs/gdbstub/0.6.6/gdbstub/stub/run_blocking/trait.BlockingEventLoop.html)
 //! from gdbstub. It's created and used by the [GdbExecutor](crate::plugin::GdbExecutor).
+use crate::record::RecordConfig;
 use crate::target_impl::TargetImpl;
 use gdbstub::common::Signal;
 use gdbstub::conn::Connection;
 use gdbstub::conn::ConnectionExt;
 use gdbstub::stub::run_blocking;
 use gdbstub::stub::SingleThreadStopReason;
+use gdbstub::target::ext::base::reverse_exec::ReplayLogPosition;
 use gdbstub::target::ext::breakpoints::WatchKind;
 use gdbstub::target::Target;
 use num_traits::FromPrimitive;
@@ -40,6 +42,8 @@ pub enum Event {
     WatchRead(u64),
     /// the emulator hit a _write_ watch point (`gdb watch`)
     WatchWrite(u64),
+    /// reverse execution reached the start of the recorded execution
+    ReplayLogBegin,
 }
 
 /// Variants which allow matching some [Event] or incoming gdb client data
@@ -134,6 +138,10 @@ where
                         kind: WatchKind::Read,
                         addr: FromPrimitive::from_u64(addr).unwrap(),
                     },
+                    Event::ReplayLogBegin => SingleThreadStopReason::ReplayLog {
+                        tid: None,
+                        pos: ReplayLogPosition::Begin,
+                    },
                     Event::Exited(exit_reason) => {
                         // TODO: at some point it would be nice to propagate the
                         // target exit information, for now just send exit 0 or 1
@@ -285,6 +293,11 @@ pub struct GdbPluginParams {
     pub tcp: Option<TcpParameters>,
     pub uds: Option<UdsParameters>,
     pub port_in_use: Arc<Mutex<u16>>,
+    /// Record execution to support reverse execution, disabled if [`None`]
+    pub record: Option<RecordConfig>,
+    /// Track calls and returns so `monitor backtrace` works without frame info, the client's
+    /// `bt` is unaffected
+    pub call_tracking: bool,
 }
 
 impl WaitForConnection for GdbPluginParams {
@@ -329,6 +342,8 @@ impl GdbPluginParams {
             }),
             uds: None,
             port_in_use: Arc::new(Mutex::new(port)),
+            record: None,
+            call_tracking: false,
         }
     }
 
@@ -342,6 +357,22 @@ impl GdbPluginParams {
                 listener: Arc::new(Mutex::new(None)),
             }),
             port_in_use: Arc::new(Mutex::new(0)),
+            record: None,
+            call_tracking: false,
         }
     }
+
+    /// Emit self with execution recording enabled, allowing the gdb client to
+    /// `reverse-stepi`, `reverse-continue` and use watchpoints in reverse
+    pub fn with_record(mut self, config: RecordConfig) -> Self {
+        self.record = Some(config);
+        self
+    }
+
+    /// Emit self with call tracking enabled, see
+    /// [`ProcessorCore::track_calls()`](styx_core::core::ProcessorCore::track_calls())
+    pub fn with_call_tracking(mut self) -> Self {
+        self.call_tracking = true;
+        self
+    }
 }
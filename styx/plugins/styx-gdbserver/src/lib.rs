@@ -47,6 +47,20 @@
 //!   [gdbstub's BlockingEventLoop](https://docs.rs/gdbstub/0.6.6/gdbstub/stub/run_blocking/trait.BlockingEventLoop.html)
 //!   trait.
 //!
+//! ## Reverse execution
+//!
+//! Setting a [`RecordConfig`] with [`GdbPluginParams::with_record()`] records target execution so
+//! the gdb client can `reverse-stepi`, `reverse-continue` and hit watchpoints in reverse. See the
+//! [record] module for how execution is recorded and its limitations.
+//!
+//! ## Backtraces
+//!
+//! The gdb client's own `bt` unwinds with the frame info of the target's debug info, the stub
+//! cannot provide unwind info. Without it gdb falls back to prologue analysis, which usually stops
+//! after the first frame or shows bogus frames. Enabling call tracking
+//! with [`GdbPluginParams::with_call_tracking()`] makes `monitor backtrace` (or `monitor bt`)
+//! show the shadow call stack instead, which does not need frame info.
+//!
 #![allow(rustdoc::private_intra_doc_links)]
 pub(crate) mod breakpoint_manager;
 mod builder;
@@ -54,10 +68,12 @@ pub(crate) mod event_loop;
 pub(crate) mod mem_watch;
 pub(crate) mod monitor;
 pub(crate) mod plugin;
+pub(crate) mod record;
 pub(crate) mod target_impl;
 
 pub use builder::*;
 pub use event_loop::GdbPluginParams;
 pub use plugin::GdbExecutor;
+pub use record::RecordConfig;
 
 use styx_core::cpu::arch::GdbArchIdSupportTrait;
@@ -99,6 +99,11 @@ impl MemHookCache {
     pub(crate) fn pending_len(&self) -> usize {
         self.pending.lock().unwrap().len()
     }
+
+    /// Drop all callbacks not yet processed
+    pub(crate) fn clear_pending(&self) {
+        self.pending.lock().unwrap().clear();
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Hash)]
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use super::common::*;
+use styx_core::calls::FrameKind;
+
+/// Show the shadow call stack of the target.
+///
+/// Built by watching calls and returns, so it works without frame pointers or debug info. Needs
+/// call tracking to be enabled on the gdb executor.
+///
+/// The `bt` alias is only `monitor bt`, the client's own `bt` still unwinds with frame info and
+/// can't unwind without it.
+///
+/// Example:
+///
+/// ```console
+///     (gdb) monitor backtrace
+///     #0  0x722: delay+0x6 (gpio_led_output.c:64)
+///     #1  0x756: main+0xa (gpio_led_output.c:71), sp 0x20007ff0
+///     (gdb) monitor backtrace -m
+///     return at 0x6f0 went to 0x41414140 instead of 0x75a
+/// ```
+#[derive(Parser, Clone)]
+#[command(name = "backtrace", visible_alias = "bt", verbatim_doc_comment)]
+pub(super) struct BacktraceCommand {
+    /// Show returns that did not match the call stack instead.
+    #[arg(short, long)]
+    mismatches: bool,
+}
+
+impl SubcommandRunnable for BacktraceCommand {
+    fn run<GdbArchImpl>(
+        &self,
+        target: &mut TargetImpl<'_, GdbArchImpl>,
+        out: &mut ConsoleOutput<'_>,
+    ) -> Result<(), UnknownError>
+    where
+        GdbArchImpl: gdbstub::arch::Arch,
+        GdbArchImpl::Registers: GdbRegistersHelper,
+        GdbArchImpl::RegId: GdbArchIdSupportTrait,
+    {
+        let core = &mut *target.proc;
+        if !core.call_stack().is_enabled() {
+            outputln!(out, "call tracking is not enabled");
+            return Ok(());
+        }
+
+        if self.mismatches {
+            let mismatches = core.call_stack().mismatches();
+            if mismatches.is_empty() {
+                outputln!(out, "no mismatched returns");
+            }
+            for mismatch in mismatches {
+                outputln!(
+                    out,
+                    "return at {:#x} went to {:#x} instead of {:#x}",
+                    mismatch.return_site,
+                    mismatch.actual,
+                    mismatch.expected
+                );
+            }
+            return Ok(());
+        }
+
+        let pc = core.pc()?;
+        outputln!(out, "#0  {pc:#x}: {}", core.symbols.describe(pc));
+        for (idx, frame) in core.call_stack().frames().iter().rev().enumerate() {
+            let interrupt = match frame.kind {
+                FrameKind::Call => "",
+                FrameKind::Interrupt => " <interrupt>",
+            };
+            outputln!(
+                out,
+                "#{:<2} {:#x}: {}, sp {:#x}{interrupt}",
+                idx + 1,
+                frame.call_site,
+                core.symbols.describe(frame.call_site),
+                frame.stack_pointer
+            );
+        }
+        Ok(())
+    }
+}
@@ -11,8 +11,10 @@
 //! [`hooks`] as examples of adding custom commands.
 //!
 
+mod backtrace;
 mod events;
 mod hooks;
+mod symbols;
 
 use std::str::from_utf8;
 
@@ -92,8 +94,10 @@ where
     GdbArchImpl::RegId: super::GdbArchIdSupportTrait,
 {
     match &cmd.commands {
+        Commands::Backtrace(backtrace_command) => backtrace_command.run(target, out),
         Commands::Hooks(hooks_command) => hooks_command.run(target, out),
         Commands::Events(events_command) => events_command.run(target, out),
+        Commands::Symbol(symbol_command) => symbol_command.run(target, out),
     }
 }
 
@@ -115,8 +119,10 @@ struct MonitorCli {
 
 #[derive(Subcommand, Clone)]
 enum Commands {
+    Backtrace(backtrace::BacktraceCommand),
     Events(events::EventsCommand),
     Hooks(hooks::HooksCommand),
+    Symbol(symbols::SymbolCommand),
 }
 
 trait SubcommandRunnable {
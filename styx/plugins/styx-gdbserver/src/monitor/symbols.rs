@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use super::common::*;
+
+/// Look up symbols and source lines of the target program.
+///
+/// Example:
+///
+/// ```console
+///     (gdb) monitor symbol where
+///     pc: main+0x4 (gpio_led_output.c:70)
+///     (gdb) monitor symbol lookup 0x722
+///     0x722: delay+0x6 (gpio_led_output.c:64)
+///     (gdb) monitor symbol address main
+///     main: 0x74c, 100 bytes
+/// ```
+#[derive(Parser, Clone)]
+#[command(name = "symbol", verbatim_doc_comment)]
+pub(super) struct SymbolCommand {
+    #[command(subcommand)]
+    commands: SymbolSubcommands,
+}
+
+#[derive(Subcommand, Clone)]
+enum SymbolSubcommands {
+    /// Show the symbol and source line of the current pc.
+    Where,
+    /// Show the symbol and source line of an address.
+    Lookup {
+        /// Address to look up, hex if prefixed with `0x`.
+        #[arg(value_parser = parse_address)]
+        address: u64,
+    },
+    /// Show the address of a symbol.
+    Address {
+        /// Name of the symbol.
+        name: String,
+    },
+}
+
+impl SubcommandRunnable for SymbolCommand {
+    fn run<GdbArchImpl>(
+        &self,
+        target: &mut TargetImpl<'_, GdbArchImpl>,
+        out: &mut ConsoleOutput<'_>,
+    ) -> Result<(), UnknownError>
+    where
+        GdbArchImpl: gdbstub::arch::Arch,
+        GdbArchImpl::Registers: GdbRegistersHelper,
+        GdbArchImpl::RegId: GdbArchIdSupportTrait,
+    {
+        let core = &mut *target.proc;
+        if core.symbols.is_empty() {
+            outputln!(out, "target program has no symbols");
+            return Ok(());
+        }
+
+        match &self.commands {
+            SymbolSubcommands::Where => {
+                let pc = core.pc()?;
+                outputln!(out, "pc: {}", core.symbols.describe(pc));
+            }
+            SymbolSubcommands::Lookup { address } => {
+                outputln!(out, "{address:#x}: {}", core.symbols.describe(*address));
+            }
+            SymbolSubcommands::Address { name } => match core.symbols.get(name) {
+                Some(symbol) => {
+                    outputln!(out, "{name}: {:#x}, {} bytes", symbol.address, symbol.size)
+                }
+                None => outputln!(out, "no symbol named `{name}`"),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Parse a decimal or `0x` prefixed hex address.
+fn parse_address(address: &str) -> Result<u64, String> {
+    let parsed = match address.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => address.parse(),
+    };
+
+    parsed.map_err(|err| format!("invalid address `{address}`: {err}"))
+}
@@ -134,8 +134,14 @@ where
     pub fn run_gdb(&mut self, proc: &mut ProcessorCore) {
         let params = self.params.clone();
 
+        if params.call_tracking && !proc.call_stack().is_enabled() {
+            if let Err(e) = proc.track_calls() {
+                warn!("could not enable call tracking: {e}");
+            }
+        }
+
         // create a single handle to emulation
-        let mut emu = TargetImpl::<GdbArchImpl>::new(proc);
+        let mut emu = TargetImpl::<GdbArchImpl>::new(proc, params.record);
 
         // run loop, only exit's on error
         loop {
@@ -0,0 +1,460 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Records target execution to support reverse execution.
+//!
+//! Recording is built from two pieces:
+//! - periodic [`Snapshot`]s of the whole processor, called checkpoints
+//! - the nondeterministic inputs consumed while running, which are the peripheral and event
+//!   controller state after each [`EventController::tick()`] (data received over IPC, timers,
+//!   latched interrupts)
+//!
+//! Positions in the recording are counted in executed instructions. Any past position is reached
+//! by restoring the closest previous checkpoint and executing forward, applying the recorded
+//! inputs at the positions they were consumed instead of ticking the peripherals again.
+//!
+//! ## Limitations
+//! - Side effects of peripheral ticks on the cpu or memory (e.g. DMA) are not recorded.
+//! - Peripherals that consume IPC data from memory hooks rather than from
+//!   [`Peripheral::tick()`](styx_core::event_controller::Peripheral::tick) are not replayed
+//!   faithfully.
+//! - Backends must report how many instructions were executed when stopped early, execution
+//!   through the recording fails with an error otherwise.
+use std::collections::{BTreeMap, VecDeque};
+
+use styx_core::{
+    cpu::{ExecutionReport, TargetExitReason},
+    errors::{anyhow::anyhow, UnknownError},
+    event_controller::EventController,
+    executor::Delta,
+    prelude::ProcessorCore,
+    snapshot::{ComponentState, Snapshot},
+};
+use tracing::debug;
+
+/// Configures execution recording for reverse execution.
+///
+/// Recording is enabled by passing a [`RecordConfig`] to
+/// [`GdbPluginParams::with_record()`](crate::GdbPluginParams::with_record).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct RecordConfig {
+    /// Number of executed instructions between checkpoints.
+    ///
+    /// Reverse execution replays at most this many instructions per checkpoint.
+    pub snapshot_interval: u64,
+    /// Maximum number of checkpoints to keep, the oldest history is dropped first.
+    pub max_snapshots: usize,
+}
+
+impl Default for RecordConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_interval: 100_000,
+            max_snapshots: 64,
+        }
+    }
+}
+
+/// Peripheral and event controller state after ticking the event controller.
+#[derive(Debug)]
+struct RecordedInput {
+    event_controller: ComponentState,
+    peripherals: Vec<ComponentState>,
+}
+
+impl RecordedInput {
+    fn capture(event_controller: &EventController) -> Result<Self, UnknownError> {
+        Ok(Self {
+            event_controller: event_controller.inner.save_state()?,
+            peripherals: event_controller
+                .peripherals
+                .peripherals
+                .iter()
+                .map(|p| p.save_state())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn restore(&self, event_controller: &mut EventController) -> Result<(), UnknownError> {
+        event_controller
+            .inner
+            .restore_state(&self.event_controller)?;
+        for (peripheral, state) in event_controller
+            .peripherals
+            .peripherals
+            .iter_mut()
+            .zip(self.peripherals.iter())
+        {
+            peripheral.restore_state(state)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Checkpoint {
+    position: u64,
+    /// Inputs at `position` already consumed when the snapshot was taken.
+    applied: usize,
+    snapshot: Snapshot,
+}
+
+/// Recorded execution history of a [`ProcessorCore`].
+#[derive(Debug)]
+pub(crate) struct ExecutionRecord {
+    config: RecordConfig,
+    /// Instructions executed since the recording started.
+    position: u64,
+    /// Inputs at `position` already consumed.
+    applied: usize,
+    /// Furthest position reached, execution before this is replayed.
+    head: u64,
+    /// Was the target modified at `position` by the debugger.
+    modified: bool,
+    /// Sorted by position.
+    checkpoints: VecDeque<Checkpoint>,
+    inputs: BTreeMap<u64, Vec<RecordedInput>>,
+}
+
+impl ExecutionRecord {
+    pub(crate) fn new(config: RecordConfig) -> Self {
+        Self {
+            config,
+            position: 0,
+            applied: 0,
+            head: 0,
+            modified: false,
+            checkpoints: VecDeque::new(),
+            inputs: BTreeMap::new(),
+        }
+    }
+
+    /// Current position in the recording.
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Earliest position that can be reached.
+    pub(crate) fn begin(&self) -> u64 {
+        self.checkpoints
+            .front()
+            .map(|c| c.position)
+            .unwrap_or(self.position)
+    }
+
+    /// Is execution currently replaying history?
+    pub(crate) fn replaying(&self) -> bool {
+        self.position < self.head
+    }
+
+    /// Positions of all checkpoints, oldest first.
+    pub(crate) fn checkpoint_positions(&self) -> Vec<u64> {
+        self.checkpoints.iter().map(|c| c.position).collect()
+    }
+
+    /// Start recording if not yet started.
+    pub(crate) fn start(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        if self.checkpoints.is_empty() {
+            self.checkpoint(core)?;
+        }
+        Ok(())
+    }
+
+    /// Notify the recording the debugger modified registers or memory.
+    ///
+    /// Execution after the current position would diverge from the recording so it is dropped
+    /// before running again.
+    pub(crate) fn modified(&mut self) {
+        self.modified = true;
+    }
+
+    /// Bump the event controller, replacing
+    /// [`EventController::next()`]/[`EventController::tick()`].
+    ///
+    /// While replaying this does nothing, the recorded inputs are applied by
+    /// [`ExecutionRecord::execute()`] at the positions they were consumed.
+    pub(crate) fn tick(
+        &mut self,
+        core: &mut ProcessorCore,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        self.sync(core)?;
+        if self.replaying() {
+            return Ok(());
+        }
+
+        self.apply_pending(core)?;
+        _ = core.event_controller.next(core.cpu.as_mut(), &mut core.mmu);
+        core.event_controller
+            .tick(core.cpu.as_mut(), &mut core.mmu, delta)?;
+
+        let input = RecordedInput::capture(&core.event_controller)?;
+        self.inputs.entry(self.position).or_default().push(input);
+        self.applied += 1;
+        Ok(())
+    }
+
+    /// Execute up to `count` instructions, applying recorded inputs while replaying.
+    pub(crate) fn execute(
+        &mut self,
+        core: &mut ProcessorCore,
+        count: u64,
+    ) -> Result<ExecutionReport, UnknownError> {
+        self.sync(core)?;
+        let mut done = 0;
+        while done < count {
+            self.apply_pending(core)?;
+
+            // stop at the next recorded input, the end of the replay, or the next checkpoint
+            let mut chunk = count - done;
+            if self.replaying() {
+                chunk = chunk.min(self.head - self.position);
+                if let Some((next, _)) = self.inputs.range(self.position + 1..).next() {
+                    chunk = chunk.min(next - self.position);
+                }
+            } else {
+                let last = self.checkpoints.back().map(|c| c.position).unwrap_or(0);
+                let next = last + self.config.snapshot_interval.max(1);
+                chunk = chunk.min(next.saturating_sub(self.position).max(1));
+            }
+
+            let report = core
+                .cpu
+                .execute(&mut core.mmu, &mut core.event_controller, chunk)?;
+            let executed = match report.instructions_executed {
+                Some(executed) => executed,
+                None if report.exit_reason == TargetExitReason::InstructionCountComplete => chunk,
+                None => {
+                    // the position is lost, start over from here so the error is recoverable
+                    self.reset(core)?;
+                    return Err(anyhow!(
+                        "backend did not report how many instructions it executed before stopping \
+                         ({}), its execution can't be recorded",
+                        report.exit_reason
+                    ));
+                }
+            };
+            self.advance(executed);
+            done += executed;
+
+            if !self.replaying() {
+                self.maybe_checkpoint(core)?;
+            }
+            if executed < chunk || report.exit_reason != TargetExitReason::InstructionCountComplete
+            {
+                return Ok(ExecutionReport::new(report.exit_reason, done));
+            }
+        }
+
+        Ok(ExecutionReport::new(
+            TargetExitReason::InstructionCountComplete,
+            done,
+        ))
+    }
+
+    /// Move to `target`, which must be between [`ExecutionRecord::begin()`] and the current
+    /// position.
+    ///
+    /// Inputs consumed at `target` are not applied.
+    pub(crate) fn seek(
+        &mut self,
+        core: &mut ProcessorCore,
+        target: u64,
+    ) -> Result<(), UnknownError> {
+        self.sync(core)?;
+        let checkpoint = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|c| c.position <= target)
+            .ok_or(anyhow!(
+                "position {target} is before the start of the recording"
+            ))?;
+        debug!(
+            "seeking to {target} from checkpoint at {}",
+            checkpoint.position
+        );
+
+        core.restore_snapshot(&checkpoint.snapshot)?;
+        self.position = checkpoint.position;
+        self.applied = checkpoint.applied;
+
+        while self.position < target {
+            let before = self.position;
+            self.execute(core, target - self.position)?;
+            if self.position == before {
+                return Err(anyhow!(
+                    "replay made no progress at position {before}, target stopped while replaying"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Start recording and handle modifications by the debugger.
+    fn sync(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        if std::mem::take(&mut self.modified) {
+            self.diverge(core)
+        } else {
+            self.start(core)
+        }
+    }
+
+    /// Drop history after the current position and checkpoint the modified target.
+    fn diverge(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        debug!(
+            "target modified at {}, dropping later history",
+            self.position
+        );
+        let position = self.position;
+        let applied = self.applied;
+        self.inputs.retain(|p, _| *p <= position);
+        if let Some(inputs) = self.inputs.get_mut(&position) {
+            inputs.truncate(applied);
+        }
+        self.checkpoints
+            .retain(|c| c.position < position || (c.position == position && c.applied < applied));
+        self.head = position;
+        self.checkpoint(core)
+    }
+
+    fn apply_pending(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        let Some(inputs) = self.inputs.get(&self.position) else {
+            return Ok(());
+        };
+
+        for input in &inputs[self.applied.min(inputs.len())..] {
+            _ = core.event_controller.next(core.cpu.as_mut(), &mut core.mmu);
+            input.restore(&mut core.event_controller)?;
+        }
+        self.applied = inputs.len();
+        Ok(())
+    }
+
+    fn advance(&mut self, executed: u64) {
+        if executed > 0 {
+            self.position += executed;
+            self.applied = 0;
+            self.head = self.head.max(self.position);
+        }
+    }
+
+    fn maybe_checkpoint(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        let last = self.checkpoints.back().map(|c| c.position).unwrap_or(0);
+        if self.position - last >= self.config.snapshot_interval {
+            self.checkpoint(core)?;
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        debug!("taking checkpoint at {}", self.position);
+        let snapshot = core.snapshot()?;
+        self.checkpoints.push_back(Checkpoint {
+            position: self.position,
+            applied: self.applied,
+            snapshot,
+        });
+
+        while self.checkpoints.len() > self.config.max_snapshots.max(1) {
+            self.checkpoints.pop_front();
+        }
+        let begin = self.begin();
+        self.inputs.retain(|p, _| *p >= begin);
+        Ok(())
+    }
+
+    fn reset(&mut self, core: &mut ProcessorCore) -> Result<(), UnknownError> {
+        self.checkpoints.clear();
+        self.inputs.clear();
+        self.position = 0;
+        self.applied = 0;
+        self.head = 0;
+        self.modified = false;
+        self.checkpoint(core)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use styx_core::{
+        arch::ppc32::{Ppc32Register, Ppc32Variants},
+        cpu::{ArchEndian, PcodeBackend},
+        memory::{helpers::WriteExt, MemoryPermissions, Mmu},
+        prelude::{Arch, CpuBackend, CpuBackendExt},
+    };
+
+    /// `addi r3, r3, 1` repeated, r3 counts executed instructions.
+    fn counting_core() -> ProcessorCore {
+        let cpu =
+            PcodeBackend::new_engine(Arch::Ppc32, Ppc32Variants::Ppc405, ArchEndian::BigEndian);
+        let mut core = ProcessorCore {
+            cpu: Box::new(cpu),
+            mmu: Mmu::default_region_store(),
+            event_controller: EventController::default(),
+            symbols: Default::default(),
+        };
+        core.mmu
+            .memory_map(0x1000, 0x1000, MemoryPermissions::all())
+            .unwrap();
+        let code: Vec<u8> = [0x38, 0x63, 0x00, 0x01].repeat(0x400);
+        core.mmu.code().write(0x1000).bytes(&code).unwrap();
+        core.cpu.set_pc(0x1000).unwrap();
+        core
+    }
+
+    fn r3(core: &mut ProcessorCore) -> u32 {
+        core.cpu.read_register::<u32>(Ppc32Register::R3).unwrap()
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut core = counting_core();
+        let mut record = ExecutionRecord::new(RecordConfig {
+            snapshot_interval: 10,
+            max_snapshots: 4,
+        });
+
+        record.execute(&mut core, 25).unwrap();
+        assert_eq!(record.position(), 25);
+        assert_eq!(r3(&mut core), 25);
+        assert_eq!(record.checkpoint_positions(), vec![0, 10, 20]);
+
+        record.seek(&mut core, 13).unwrap();
+        assert!(record.replaying());
+        assert_eq!(r3(&mut core), 13);
+        assert_eq!(core.cpu.pc().unwrap(), 0x1000 + 13 * 4);
+
+        // running forward replays then continues live
+        record.execute(&mut core, 20).unwrap();
+        assert!(!record.replaying());
+        assert_eq!(r3(&mut core), 33);
+
+        // oldest checkpoints are dropped
+        record.execute(&mut core, 20).unwrap();
+        assert_eq!(record.checkpoint_positions(), vec![20, 30, 40, 50]);
+        assert_eq!(record.begin(), 20);
+        assert!(record.seek(&mut core, 10).is_err());
+    }
+
+    #[test]
+    fn test_diverge() {
+        let mut core = counting_core();
+        let mut record = ExecutionRecord::new(RecordConfig {
+            snapshot_interval: 10,
+            max_snapshots: 4,
+        });
+        record.start(&mut core).unwrap();
+        record.execute(&mut core, 25).unwrap();
+
+        record.seek(&mut core, 15).unwrap();
+        core.cpu.write_register(Ppc32Register::R3, 100u32).unwrap();
+        record.modified();
+
+        record.execute(&mut core, 5).unwrap();
+        assert!(!record.replaying());
+        assert_eq!(record.checkpoint_positions(), vec![0, 10, 15]);
+        record.seek(&mut core, 17).unwrap();
+        assert_eq!(r3(&mut core), 102);
+    }
+}
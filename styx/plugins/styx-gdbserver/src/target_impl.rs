@@ -17,6 +17,7 @@
 use crate::{
     event_loop::{self, RunEvent},
     mem_watch::{Access, MemHookCache},
+    record::{ExecutionRecord, RecordConfig},
 };
 use gdbstub::{
     common::Signal,
@@ -31,7 +32,7 @@ use std::marker::PhantomData;
 use styx_core::{
     cpu::{
         arch::{CpuRegister, GdbRegistersHelper},
-        ArchEndian, TargetExitReason,
+        ArchEndian, ExecutionReport, TargetExitReason,
     },
     executor::Delta,
     hooks::CodeHook,
@@ -65,6 +66,11 @@ impl CodeHook for GdbBreakpointHook {
     fn call(&mut self, proc: CoreHandle) -> Result<(), UnknownError> {
         // let bp_state: Arc<BreakpointManager> = userdata.downcast().unwrap();
 
+        // breakpoints are checked by the `TargetImpl` while replaying
+        if self.0.suspended() {
+            return Ok(());
+        }
+
         let pc = proc.cpu.pc().unwrap();
         // check if pc is in our breakpoints, if not then bail
         if !self.0.contains_active(&pc) {
@@ -109,6 +115,10 @@ pub enum ExecMode {
     RangeStep(u64, u64),
     /// Resume cpu for 1 step
     Step,
+    /// Go back 1 step in the recorded execution
+    ReverseStep,
+    /// Go back in the recorded execution until a breakpoint or watchpoint
+    ReverseContinue,
 }
 
 /// Holds the state of the target emulation session
@@ -146,6 +156,8 @@ where
     /// Tracks `styx_core::cpu::hooks::HookType::MEM_WRITE` hooks
     /// TODO: make sure this really does
     pub(crate) mem_hook_cache: Arc<MemHookCache>,
+    /// Recorded execution for reverse execution, [`None`] if not recording
+    record: Option<ExecutionRecord>,
     _unused: PhantomData<GdbArchImpl>,
 }
 
@@ -169,7 +181,9 @@ where
     /// Construct a new [TargetImpl] from the [ProcessorCore]
     /// Assumes that processor and cpu adhere to
     /// [Using _GdbExecutor_](super::plugin::GdbExecutor).
-    pub(crate) fn new(mach: &'a mut ProcessorCore) -> Self {
+    ///
+    /// Execution is recorded to support reverse execution if `record` is set.
+    pub(crate) fn new(mach: &'a mut ProcessorCore, record: Option<RecordConfig>) -> Self {
         trace!("Creating TargetImpl");
         let reg_size = mach.cpu.architecture().core_register_size();
 
@@ -180,6 +194,7 @@ where
             reg_size,
             breakpoint_state: Arc::new(BreakpointManager::default()),
             mem_hook_cache: Arc::new(MemHookCache::new()),
+            record: record.map(ExecutionRecord::new),
             _unused: PhantomData::<GdbArchImpl> {},
         }
     }
@@ -188,6 +203,48 @@ where
         self.proc.cpu.as_mut()
     }
 
+    /// Execute `count` instructions, through the [`ExecutionRecord`] if recording
+    fn execute(&mut self, count: u64) -> Result<ExecutionReport, UnknownError> {
+        match self.record.as_mut() {
+            Some(record) => record.execute(self.proc, count),
+            None => {
+                self.proc
+                    .cpu
+                    .execute(&mut self.proc.mmu, &mut self.proc.event_controller, count)
+            }
+        }
+    }
+
+    /// Latch the next interrupt and tick the peripherals for an epoch,
+    /// through the [`ExecutionRecord`] if recording
+    fn tick_event_controller(&mut self) {
+        let delta = Delta::from_instructions(CPU_EPOCH_SIZE);
+
+        match self.record.as_mut() {
+            Some(record) => record.tick(self.proc, &delta).unwrap(),
+            None => {
+                // insert interrupt
+                _ = self
+                    .proc
+                    .event_controller
+                    .next(self.proc.cpu.as_mut(), &mut self.proc.mmu);
+
+                self.proc
+                    .event_controller
+                    .tick(self.proc.cpu.as_mut(), &mut self.proc.mmu, &delta)
+                    .unwrap();
+            }
+        }
+    }
+
+    /// The gdb client modified registers or memory, the recorded execution
+    /// after this point is no longer valid
+    fn target_modified(&mut self) {
+        if let Some(record) = self.record.as_mut() {
+            record.modified();
+        }
+    }
+
     // pub fn get_target_xml(&mut self) {
     //     if let Some(xml_string) = self.target_cpu().architecture().target_xml(annex) {
     //         trace!("{}", xml_string);
@@ -234,10 +291,7 @@ where
     fn step(&mut self) -> Option<event_loop::Event> {
         // Step 1 instruction
         //  Bail if there is an event generated while running (target error)
-        let cpu_exit_condition =
-            self.proc
-                .cpu
-                .execute(&mut self.proc.mmu, &mut self.proc.event_controller, 1);
+        let cpu_exit_condition = self.execute(1);
         if let Some(event) =
             self.handle_cpu_exit_code(cpu_exit_condition.map(|report| report.exit_reason))
         {
@@ -297,20 +351,7 @@ where
                         // for new data from the `gdb` client
                         if cycles % CPU_EPOCH_SIZE == 0 {
                             // insert interrupt
-                            _ = self
-                                .proc
-                                .event_controller
-                                .next(self.proc.cpu.as_mut(), &mut self.proc.mmu);
-
-                            // assume 1 ns per instruction
-                            let delta = Delta {
-                                time: std::time::Duration::from_nanos(CPU_EPOCH_SIZE),
-                                count: CPU_EPOCH_SIZE,
-                            };
-                            self.proc
-                                .event_controller
-                                .tick(self.proc.cpu.as_mut(), &mut self.proc.mmu, &delta)
-                                .unwrap();
+                            self.tick_event_controller();
 
                             // poll for incoming data
                             if poll_incoming_data() {
@@ -340,27 +381,10 @@ where
                         }
 
                         // bump the event controller
-                        _ = self
-                            .proc
-                            .event_controller
-                            .next(self.proc.cpu.as_mut(), &mut self.proc.mmu);
-
-                        // assume 1 ns per instruction
-                        let delta = Delta {
-                            time: std::time::Duration::from_nanos(CPU_EPOCH_SIZE),
-                            count: CPU_EPOCH_SIZE,
-                        };
-                        self.proc
-                            .event_controller
-                            .tick(self.proc.cpu.as_mut(), &mut self.proc.mmu, &delta)
-                            .unwrap();
+                        self.tick_event_controller();
 
                         // run the CPU epoch
-                        let cpu_exit_condition = self.proc.cpu.execute(
-                            &mut self.proc.mmu,
-                            &mut self.proc.event_controller,
-                            CPU_EPOCH_SIZE,
-                        );
+                        let cpu_exit_condition = self.execute(CPU_EPOCH_SIZE);
                         if let Some(event) = self.handle_cpu_exit_code(
                             cpu_exit_condition.map(|report| report.exit_reason),
                         ) {
@@ -407,9 +431,111 @@ where
                     }
                 }
             }
+
+            // reverse execution replays the recording from a checkpoint, see
+            // [`ExecutionRecord`]
+            ExecMode::ReverseStep => RunEvent::Event(self.reverse(Self::run_reverse_step)),
+            ExecMode::ReverseContinue => RunEvent::Event(self.reverse(Self::run_reverse_continue)),
         }
     }
 
+    /// Runs a reverse execution `op` with breakpoint hooks suspended, the
+    /// replay checks for breakpoints itself.
+    fn reverse(
+        &mut self,
+        op: impl FnOnce(&mut Self) -> Result<event_loop::Event, UnknownError>,
+    ) -> event_loop::Event {
+        self.breakpoint_state.set_suspended(true);
+        let result = op(self);
+        self.breakpoint_state.set_suspended(false);
+        self.mem_hook_cache.clear_pending();
+
+        // like stopping at a breakpoint going forward, resuming from a
+        // breakpoint must not immediately stop at it again
+        let pc = self.target_cpu().pc().unwrap();
+        if self.breakpoint_state.contains_active(&pc) {
+            self.breakpoint_state.pause(pc);
+        } else {
+            self.breakpoint_state.unpause();
+        }
+
+        result.unwrap_or_else(|err| {
+            error!("Reverse execution failed: {}", err);
+            event_loop::Event::Exited(Err(TargetExitReason::GeneralFault(err.to_string())))
+        })
+    }
+
+    /// Go back 1 instruction.
+    fn run_reverse_step(&mut self) -> Result<event_loop::Event, UnknownError> {
+        let record = self
+            .record
+            .as_mut()
+            .ok_or(anyhow!("execution is not being recorded"))?;
+
+        let position = record.position();
+        if position <= record.begin() {
+            return Ok(event_loop::Event::ReplayLogBegin);
+        }
+        record.seek(self.proc, position - 1)?;
+        Ok(event_loop::Event::DoneStep)
+    }
+
+    /// Go back to the last breakpoint hit or watchpoint write, checking one
+    /// checkpoint interval at a time from the most recent.
+    ///
+    /// A watchpoint stops before the instruction that wrote to it.
+    fn run_reverse_continue(&mut self) -> Result<event_loop::Event, UnknownError> {
+        let record = self
+            .record
+            .as_mut()
+            .ok_or(anyhow!("execution is not being recorded"))?;
+
+        let end = record.position();
+        let mut checkpoints = record.checkpoint_positions();
+        checkpoints.retain(|p| *p < end);
+        checkpoints.dedup();
+
+        let mut stop = end;
+        for start in checkpoints.into_iter().rev() {
+            record.seek(self.proc, start)?;
+            self.mem_hook_cache.clear_pending();
+
+            let mut hit = None;
+            while record.position() < stop {
+                let position = record.position();
+                let pc = self.proc.cpu.pc()?;
+                if self.breakpoint_state.contains_active(&pc) {
+                    hit = Some((position, event_loop::Event::Break));
+                }
+
+                let report = record.execute(self.proc, 1)?;
+                if record.position() == position {
+                    return Err(anyhow!(
+                        "target stopped while replaying: {}",
+                        report.exit_reason
+                    ));
+                }
+
+                for w in self.watchpoints.iter() {
+                    if let Some(hit_addr) = self.mem_hook_cache.take(*w) {
+                        hit = Some((position, event_loop::Event::WatchWrite(hit_addr)));
+                    }
+                }
+                self.mem_hook_cache.clear_pending();
+            }
+
+            if let Some((position, event)) = hit {
+                record.seek(self.proc, position)?;
+                return Ok(event);
+            }
+            stop = start;
+        }
+
+        let begin = record.begin();
+        record.seek(self.proc, begin)?;
+        Ok(event_loop::Event::ReplayLogBegin)
+    }
+
     /// Logs the output of the cpu exit code, and determines if the
     /// targeted has exited, errored etc.
     ///
@@ -666,6 +792,7 @@ where
     /// corresponding register value
     fn write_registers(&mut self, regs: &GdbArchImpl::Registers) -> TargetResult<(), Self> {
         trace!("GdbExecutor::write_registers");
+        self.target_modified();
 
         // `regs` has a list of register values to set, so do so
         for (reg, value) in regs.register_tank().iter() {
@@ -719,6 +846,7 @@ where
         data: &[u8],
     ) -> TargetResult<(), Self> {
         let addr: u64 = num_traits::ToPrimitive::to_u64(&start_addr).unwrap();
+        self.target_modified();
 
         match self.proc.mmu.write_data(addr, data) {
             Err(e) => {
@@ -768,20 +896,58 @@ where
         Some(self)
     }
 
-    /// Reverse continue/step not supported
+    /// Reverse continue is supported when recording execution
     #[inline(always)]
     fn support_reverse_cont(
         &mut self,
     ) -> Option<target::ext::base::reverse_exec::ReverseContOps<'_, (), Self>> {
-        None
+        if self.record.is_some() {
+            Some(self)
+        } else {
+            None
+        }
     }
 
-    /// Reverse continue/step not supported
+    /// Reverse step is supported when recording execution
     #[inline(always)]
     fn support_reverse_step(
         &mut self,
     ) -> Option<target::ext::base::reverse_exec::ReverseStepOps<'_, (), Self>> {
-        None
+        if self.record.is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, GdbArchImpl> target::ext::base::reverse_exec::ReverseCont<()>
+    for TargetImpl<'a, GdbArchImpl>
+where
+    GdbArchImpl: gdbstub::arch::Arch,
+    GdbArchImpl::Registers: styx_core::cpu::arch::GdbRegistersHelper,
+    GdbArchImpl::RegId: super::GdbArchIdSupportTrait,
+{
+    /// By setting [ExecMode], this gets injected into the event loop
+    fn reverse_cont(&mut self) -> Result<(), Self::Error> {
+        trace!("Setting self.exec_mode to ExecMode::ReverseContinue");
+        self.exec_mode = ExecMode::ReverseContinue;
+        Ok(())
+    }
+}
+
+impl<'a, GdbArchImpl> target::ext::base::reverse_exec::ReverseStep<()>
+    for TargetImpl<'a, GdbArchImpl>
+where
+    GdbArchImpl: gdbstub::arch::Arch,
+    GdbArchImpl::Registers: styx_core::cpu::arch::GdbRegistersHelper,
+    GdbArchImpl::RegId: super::GdbArchIdSupportTrait,
+{
+    /// By setting [ExecMode], this gets injected into the event loop
+    fn reverse_step(&mut self, _tid: ()) -> Result<(), Self::Error> {
+        trace!("Setting self.exec_mode to ExecMode::ReverseStep");
+        self.exec_mode = ExecMode::ReverseStep;
+        Ok(())
     }
 }
 
@@ -868,6 +1034,7 @@ where
         val: &[u8],
     ) -> TargetResult<(), Self> {
         trace!("GDB: write_register: {:?}", reg_id);
+        self.target_modified();
 
         // Write is received in target endianness so we have to account for
         // endian to get value
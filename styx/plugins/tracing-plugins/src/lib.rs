@@ -242,6 +242,17 @@ fn pc_trace_hook(proc: CoreHandle) -> Result<(), UnknownError> {
     Ok(())
 }
 
+fn symbolic_pc_trace_hook(proc: CoreHandle, symbols: &SymbolTable) -> Result<(), UnknownError> {
+    let pc = proc.cpu.pc()?;
+    match symbols.lookup(pc) {
+        Some(location) => {
+            trace!(target: "pc-trace","{{\"type\": \"pc\", \"value\": \"{pc:#x}\", \"symbol\": \"{location}\"}}")
+        }
+        None => trace!(target: "pc-trace","{{\"type\": \"pc\", \"value\": \"{pc:#x}\"}}"),
+    }
+    Ok(())
+}
+
 /// Logs every single executed instruction address to the console in a
 /// JSON compatible message.
 ///
@@ -255,6 +266,16 @@ fn pc_trace_hook(proc: CoreHandle) -> Result<(), UnknownError> {
 /// }
 /// ```
 ///
+/// If the target program has symbols the containing symbol is added when one is found:
+///
+/// ```json
+/// {
+///     "type": "pc",
+///     "value": 41414141,
+///     "symbol": "main+0x10",
+/// }
+/// ```
+///
 /// NOTE: The default tracing plugin, [`ProcessorTracingPlugin`], MUST
 /// be enabled in order for other trace plugins to function.
 #[derive(Debug, Default)]
@@ -271,8 +292,17 @@ impl UninitPlugin for JsonPcTracePlugin {
         self: Box<Self>,
         proc: &mut BuildingProcessor,
     ) -> Result<Box<dyn Plugin>, UnknownError> {
-        // add event hook
-        proc.core.cpu.add_hook(StyxHook::code(.., pc_trace_hook))?;
+        // add event hook, only pay for symbol lookups if there are symbols
+        let symbols = proc.core.symbols.clone();
+        if symbols.is_empty() {
+            proc.core.cpu.add_hook(StyxHook::code(.., pc_trace_hook))?;
+        } else {
+            proc.core
+                .cpu
+                .add_hook(StyxHook::code(.., move |proc: CoreHandle| {
+                    symbolic_pc_trace_hook(proc, &symbols)
+                }))?;
+        }
 
         // enable the logging
         TRACING_LAYERS.push(Box::new(
@@ -1,61 +1,152 @@
 // SPDX-License-Identifier: BSD-2-Clause
+//! AArch64 Processor
+//!
+//! Models a single core of the QEMU "virt" machine, enough to boot bare-metal and small RTOS
+//! images. Has support for the [GICv3](Gicv3), the [generic timer](timer) and the
+//! [PL011 UART](pl011).
+//!
+//! Memory map:
+//!
+//!  Start      | Size   | Description
+//! ----------------------------------------------------------
+//!  0x00000000 | 128MiB | flash, execution starts at its base
+//!  0x08000000 | 64KiB  | GIC distributor
+//!  0x08010000 | 8KiB   | GIC CPU interface
+//!  0x080A0000 | 128KiB | GIC redistributor
+//!  0x09000000 | 4KiB   | PL011 UART
+//!  0x40000000 | 128MiB | RAM
+//!
+//! The core starts at EL1 using `SP_EL1` with all exceptions masked. Exceptions are taken to EL1
+//! through the vector table at `VBAR_EL1`, see [Gicv3] for the supported exceptions.
+pub mod pl011;
+pub mod timer;
+
 use styx_core::{
-    arch::aarch64::Aarch64Variants,
-    core::{
-        builder::{BuildProcessorImplArgs, ProcessorImpl},
-        ProcessorBundle,
-    },
-    cpu::{Backend, PcodeBackend},
-    event_controller::DummyEventController,
-    loader::LoaderHints,
-    memory::Mmu,
-    prelude::CpuBackend,
+    arch::aarch64::{Aarch64Register, Aarch64Variants},
+    core::builder::{BuildProcessorImplArgs, ProcessorImpl},
+    cpu::PcodeBackend,
+    prelude::*,
 };
+use styx_gic::Gicv3;
+use styx_peripherals::uart::UartController;
+use timer::GenericTimer;
+
+pub use pl011::{pl011_interface, PL011_BASE, PL011_IRQN};
+pub use timer::{PHYSICAL_TIMER_IRQN, VIRTUAL_TIMER_IRQN};
+
+const GIC_DISTRIBUTOR_BASE: u64 = 0x0800_0000;
+const GIC_CPU_INTERFACE_BASE: u64 = 0x0801_0000;
+const GIC_REDISTRIBUTOR_BASE: u64 = 0x080A_0000;
 
-/// A processor with no peripherals or event controller, purely instruction emulation.
+/// PSTATE at reset, EL1h with `D`, `A`, `I` and `F` set.
+const RESET_PSTATE: u32 = 0x3C5;
+
+/// The "virt" machine, see the [crate documentation](crate).
 #[derive(Default)]
 pub struct Aarch64Processor {}
 
+impl Aarch64Processor {
+    fn setup_address_space(&self, mmu: &mut Mmu) -> Result<(), UnknownError> {
+        let mut regions = Vec::new();
+
+        let flash_start = 0x0000_0000;
+        let flash_size = 0x800_0000;
+        regions.push(MemoryRegion::new(
+            flash_start,
+            flash_size,
+            MemoryPermissions::all(),
+        )?);
+
+        let gic_start = GIC_DISTRIBUTOR_BASE;
+        let gic_size = 0x10_0000;
+        regions.push(MemoryRegion::new(
+            gic_start,
+            gic_size,
+            MemoryPermissions::RW,
+        )?);
+
+        let uart_start = PL011_BASE;
+        let uart_size = 0x1000;
+        regions.push(MemoryRegion::new(
+            uart_start,
+            uart_size,
+            MemoryPermissions::RW,
+        )?);
+
+        let ram_start = 0x4000_0000;
+        let ram_size = 0x800_0000;
+        regions.push(MemoryRegion::new(
+            ram_start,
+            ram_size,
+            MemoryPermissions::all(),
+        )?);
+
+        for region in regions {
+            mmu.add_memory_region(region)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl ProcessorImpl for Aarch64Processor {
-    fn build(
-        &self,
-        args: &BuildProcessorImplArgs,
-    ) -> Result<styx_core::prelude::ProcessorBundle, styx_core::prelude::UnknownError> {
+    fn build(&self, args: &BuildProcessorImplArgs) -> Result<ProcessorBundle, UnknownError> {
         let cpu: Box<dyn CpuBackend> = match args.backend {
-            Backend::Pcode => Box::new(PcodeBackend::new_engine(
-                styx_core::cpu::Arch::Aarch64,
+            Backend::Pcode => Box::new(PcodeBackend::new_engine_config(
+                Aarch64Variants::Generic,
+                ArchEndian::LittleEndian,
+                &args.into(),
+            )),
+            #[cfg(feature = "unicorn-backend")]
+            Backend::Unicorn => Box::new(styx_core::cpu::UnicornBackend::new_engine_exception(
+                Arch::Aarch64,
                 Aarch64Variants::Generic,
-                styx_core::cpu::ArchEndian::LittleEndian,
+                ArchEndian::LittleEndian,
+                args.exception,
             )),
-            _ => unimplemented!("not supported"),
+            _ => return Err(BackendNotSupported(args.backend).into()),
         };
 
-        let mut hints = LoaderHints::new();
-        hints.insert(
-            "arch".to_string().into_boxed_str(),
-            Box::new(styx_core::cpu::Arch::Aarch64),
+        let mut mmu = Mmu::default_region_store();
+
+        self.setup_address_space(&mut mmu)?;
+
+        let gic = Gicv3::new(
+            GIC_DISTRIBUTOR_BASE,
+            GIC_REDISTRIBUTOR_BASE,
+            GIC_CPU_INTERFACE_BASE,
         );
 
+        let peripherals: Vec<Box<dyn Peripheral>> = vec![
+            Box::new(GenericTimer::new(args.backend)),
+            Box::new(UartController::new(pl011::get_uarts())),
+        ];
+
+        let mut hints = LoaderHints::new();
+        hints.insert("arch".to_string().into_boxed_str(), Box::new(Arch::Aarch64));
+
         Ok(ProcessorBundle {
             cpu,
-            mmu: Mmu::default(),
-            event_controller: Box::new(DummyEventController::default()),
-            peripherals: vec![],
+            mmu,
+            event_controller: Box::new(gic),
+            peripherals,
             loader_hints: hints,
         })
     }
+
+    fn init(&self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        proc.core
+            .cpu
+            .write_register(Aarch64Register::Cpsr, RESET_PSTATE)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use styx_core::{
-        errors::UnknownError,
-        hooks::{CoreHandle, StyxHook},
-        prelude::{Forever, ProcessorBuilder},
-        util::resolve_test_bin,
-    };
+    use styx_core::util::resolve_test_bin;
     use test_case::test_case;
 
     #[test_case("adds.bin")]
@@ -121,4 +212,108 @@ mod tests {
             panic!("test failed");
         }
     }
+
+    /// Processor on `backend` with the instruction words of `blocks` at their addresses, starting
+    /// at the first block.
+    fn processor(backend: Backend, blocks: &[(u64, &[u32])]) -> Processor {
+        let mut proc = ProcessorBuilder::default()
+            .with_builder(Aarch64Processor::default())
+            .with_backend(backend)
+            .build()
+            .unwrap();
+
+        let mmu = &mut proc.core.mmu;
+        for (address, words) in blocks {
+            let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+            mmu.write_code(*address, &bytes).unwrap();
+        }
+        proc.core.set_pc(blocks[0].0).unwrap();
+        proc
+    }
+
+    /// Takes a supervisor call to the handler at `VBAR_EL1 + 0x200` and returns with `eret`.
+    #[test_case(Backend::Pcode ; "pcode")]
+    #[cfg_attr(feature = "unicorn-backend", test_case(Backend::Unicorn ; "unicorn"))]
+    #[cfg_attr(miri, ignore)]
+    fn test_supervisor_call(backend: Backend) {
+        let program: &[u32] = &[
+            0xD2840000, // mov x0, #0x2000
+            0xD518C000, // msr vbar_el1, x0
+            0xD2800020, // mov x0, #1
+            0xD4000841, // svc #0x42
+            0xD2800022, // mov x2, #1
+            0x14000000, // b .
+        ];
+        let handler: &[u32] = &[
+            0xD5385201, // mrs x1, esr_el1
+            0xD69F03E0, // eret
+        ];
+
+        let mut proc = processor(backend, &[(0x1000, program), (0x2200, handler)]);
+        proc.run(10).unwrap();
+
+        let cpu = proc.core.cpu.as_mut();
+        // EC 0x15, IL and the immediate
+        assert_eq!(
+            0x5600_0042,
+            cpu.read_register::<u64>(Aarch64Register::X1).unwrap()
+        );
+        assert_eq!(1, cpu.read_register::<u64>(Aarch64Register::X2).unwrap());
+        assert_eq!(
+            0x1010,
+            cpu.read_register::<u64>(Aarch64Register::ElrEl1).unwrap()
+        );
+        assert_eq!(
+            RESET_PSTATE,
+            cpu.read_register::<u32>(Aarch64Register::Cpsr).unwrap()
+        );
+        assert_eq!(0x1014, proc.core.pc().unwrap());
+    }
+
+    /// Arms the physical timer and takes its interrupt through the GIC to `VBAR_EL1 + 0x280`.
+    #[test_case(Backend::Pcode ; "pcode")]
+    #[cfg_attr(feature = "unicorn-backend", test_case(Backend::Unicorn ; "unicorn"))]
+    #[cfg_attr(miri, ignore)]
+    fn test_timer_interrupt(backend: Backend) {
+        let program: &[u32] = &[
+            0xD2A10001, // mov x1, #0x08000000
+            0x52800022, // mov w2, #1
+            0xB9000022, // str w2, [x1], GICD_CTLR
+            0xD2A10161, // mov x1, #0x080B0000
+            0x52A80003, // mov w3, #0x40000000
+            0xB9010023, // str w3, [x1, #0x100], GICR_ISENABLER0 bit 30
+            0xD2A10021, // mov x1, #0x08010000
+            0xB9000022, // str w2, [x1], GICC_CTLR
+            0x52801FE3, // mov w3, #0xff
+            0xB9000423, // str w3, [x1, #4], GICC_PMR
+            0xD2840000, // mov x0, #0x2000
+            0xD518C000, // msr vbar_el1, x0
+            0xD2800C80, // mov x0, #100
+            0xD51BE200, // msr cntp_tval_el0, x0
+            0xD51BE222, // msr cntp_ctl_el0, x2
+            0xD50342FF, // msr daifclr, #2
+            0x14000000, // b .
+        ];
+        let handler: &[u32] = &[
+            0xD53BE224, // mrs x4, cntp_ctl_el0
+            0xD2800025, // mov x5, #1
+            0x14000000, // b .
+        ];
+
+        let mut proc = processor(backend, &[(0x1000, program), (0x2280, handler)]);
+        proc.run(100_000).unwrap();
+
+        let cpu = proc.core.cpu.as_mut();
+        assert_eq!(1, cpu.read_register::<u64>(Aarch64Register::X5).unwrap());
+        // ENABLE and ISTATUS
+        assert_eq!(
+            0b101,
+            cpu.read_register::<u64>(Aarch64Register::X4).unwrap()
+        );
+        assert_eq!(
+            0x1040,
+            cpu.read_register::<u64>(Aarch64Register::ElrEl1).unwrap()
+        );
+        assert_eq!(0x2288, proc.core.pc().unwrap());
+    }
 }
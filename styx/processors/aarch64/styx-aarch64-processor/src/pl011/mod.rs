@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! ARM PrimeCell UART (PL011)
+//!
+//! The UART is exposed through a [UartController] so clients can talk to it over the `UartPort`
+//! gRPC service with the interface id `"0"`. Its registers are at [`PL011_BASE`] and it requests
+//! [`PL011_IRQN`].
+//!
+//! Other machines can place PL011s anywhere with [`pl011_interface()`].
+//!
+//! The interrupt is level sensitive, it is latched on every tick while `UARTMIS` is not 0.
+//!
+//! See [registers] for the modeled register behavior.
+use std::collections::VecDeque;
+
+use styx_core::hooks::{MemoryReadHook, MemoryWriteHook};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use styx_peripherals::uart::{IntoUartImpl, UartController, UartImpl, UartInterface};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+mod registers;
+
+use registers::{Pl011Registers, LAST_OFFSET};
+
+/// Base address of the UART registers.
+pub const PL011_BASE: u64 = 0x0900_0000;
+/// The UART interrupt, SPI 1.
+pub const PL011_IRQN: ExceptionNumber = 33;
+
+const INTERFACE_ID: &str = "0";
+
+/// Creates the interface for the UART to be added to a [UartController].
+pub(crate) fn get_uarts() -> Vec<UartInterface> {
+    vec![pl011_interface(INTERFACE_ID, PL011_BASE, PL011_IRQN)]
+}
+
+/// Creates the interface for a PL011 with registers at `base` requesting `irqn`, to be added to a
+/// [UartController].
+pub fn pl011_interface(
+    interface_id: impl Into<String>,
+    base: u64,
+    irqn: ExceptionNumber,
+) -> UartInterface {
+    UartInterface::new(interface_id.into(), Pl011Builder { base, irqn })
+}
+
+struct Pl011Builder {
+    base: u64,
+    irqn: ExceptionNumber,
+}
+
+impl IntoUartImpl for Pl011Builder {
+    fn new(
+        self,
+        mosi_rx: broadcast::Receiver<u8>,
+        miso_tx: broadcast::Sender<u8>,
+        interface_id: String,
+    ) -> Result<Box<dyn UartImpl>, UnknownError> {
+        Ok(Box::new(Pl011 {
+            base: self.base,
+            irqn: self.irqn,
+            interface_id,
+            registers: Pl011Registers::default(),
+            buffer: VecDeque::new(),
+            miso_stream: miso_tx,
+            mosi_stream: mosi_rx,
+        }))
+    }
+}
+
+/// Connects the [Pl011Registers] to the uart streams.
+pub(crate) struct Pl011 {
+    base: u64,
+    irqn: ExceptionNumber,
+    interface_id: String,
+    registers: Pl011Registers,
+    /// uart bytes that have come in from master but not received yet.
+    buffer: VecDeque<u8>,
+    miso_stream: broadcast::Sender<u8>,
+    mosi_stream: broadcast::Receiver<u8>,
+}
+
+impl Pl011 {
+    /// checks uart mosi for bytes and gives to buffer
+    fn grab_bytes(&mut self) {
+        loop {
+            match self.mosi_stream.try_recv() {
+                Ok(data) => self.buffer.push_back(data),
+                Err(broadcast::error::TryRecvError::Empty) => break,
+                Err(broadcast::error::TryRecvError::Closed) => {
+                    warn!("uart mosi stream closed??");
+                    break;
+                }
+                Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                    warn!("uart mosi stream lagged {n} items");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn hook(&self) -> Pl011Hook {
+        Pl011Hook {
+            base: self.base,
+            interface_id: self.interface_id.clone(),
+        }
+    }
+
+    fn guest_transmit_data(&mut self, data: u8) {
+        debug!("pl011 transmit data {data:#x}");
+        // an error means no one is listening, that's fine
+        let _ = self.miso_stream.send(data);
+    }
+
+    /// Move waiting bytes into the receive FIFO while it has room.
+    fn fill_fifo(&mut self) {
+        while self.registers.receive_ready() {
+            let Some(data) = self.buffer.pop_front() else {
+                break;
+            };
+            self.registers.receive(data);
+        }
+    }
+
+    fn read_register(&mut self, offset: u64) -> u32 {
+        self.registers.read(offset)
+    }
+
+    fn write_register(&mut self, offset: u64, value: u32, mask: u32) {
+        if let Some(data) = self.registers.write(offset, value, mask) {
+            self.guest_transmit_data(data);
+        }
+    }
+}
+
+impl UartImpl for Pl011 {
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let end = self.base + LAST_OFFSET;
+        proc.core
+            .cpu
+            .mem_read_hook(self.base, end, Box::new(self.hook()))?;
+        proc.core
+            .cpu
+            .mem_write_hook(self.base, end, Box::new(self.hook()))?;
+        Ok(())
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        vec![self.irqn]
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+    ) -> Result<(), UnknownError> {
+        self.grab_bytes();
+        self.fill_fifo();
+        if self.registers.masked_interrupts() != 0 {
+            event_controller.latch(self.irqn)?;
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(&self.registers, &self.buffer))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        (self.registers, self.buffer) = state.get()?;
+        Ok(())
+    }
+}
+
+/// Register hook of the UART, registers are accessed through the [UartController].
+struct Pl011Hook {
+    base: u64,
+    interface_id: String,
+}
+
+impl Pl011Hook {
+    fn uart<'a>(&self, proc: &'a mut CoreHandle) -> Result<&'a mut Pl011, UnknownError> {
+        proc.event_controller
+            .peripherals
+            .get_expect::<UartController>()?
+            .try_get::<Pl011>(&self.interface_id)
+    }
+}
+
+impl MemoryReadHook for Pl011Hook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        size: u32,
+        data: &mut [u8],
+    ) -> Result<(), UnknownError> {
+        let uart = self.uart(&mut proc)?;
+        let offset = address - self.base;
+        let word = offset & !0x3;
+        let value = uart.read_register(word);
+        let shift = (offset - word) as u32 * 8;
+        // bytes past the word register read as 0
+        for (index, byte) in data[..size as usize].iter_mut().enumerate() {
+            *byte = value.checked_shr(shift + 8 * index as u32).unwrap_or(0) as u8;
+        }
+        Ok(())
+    }
+}
+
+impl MemoryWriteHook for Pl011Hook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        size: u32,
+        data: &[u8],
+    ) -> Result<(), UnknownError> {
+        let uart = self.uart(&mut proc)?;
+        let offset = address - self.base;
+        let word = offset & !0x3;
+        let shift = (offset - word) as u32 * 8;
+        let (mut value, mut mask) = (0u32, 0u32);
+        // bytes past the word register are dropped
+        for (index, byte) in data[..size as usize].iter().enumerate() {
+            let shift = shift + 8 * index as u32;
+            value |= (*byte as u32).checked_shl(shift).unwrap_or(0);
+            mask |= 0xFFu32.checked_shl(shift).unwrap_or(0);
+        }
+        uart.write_register(word, value, mask);
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! PL011 register state.
+//!
+//! Transmission is instant, so the transmit FIFO is always empty and the transmit interrupt is
+//! raised whenever it is unmasked. The raw interrupt status follows the receive FIFO instead of
+//! being latched, `UARTICR` has nothing to clear. The receive timeout and the modem and error
+//! interrupts are never raised. Like QEMU, data is transmitted and received whatever the enable
+//! bits in `UARTCR`.
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// Data Register
+const UARTDR: u64 = 0x000;
+/// Receive Status Register / Error Clear Register
+const UARTRSR: u64 = 0x004;
+/// Flag Register
+const UARTFR: u64 = 0x018;
+/// IrDA Low-Power Counter Register
+const UARTILPR: u64 = 0x020;
+/// Integer Baud Rate Register
+const UARTIBRD: u64 = 0x024;
+/// Fractional Baud Rate Register
+const UARTFBRD: u64 = 0x028;
+/// Line Control Register
+const UARTLCR_H: u64 = 0x02C;
+/// Control Register
+const UARTCR: u64 = 0x030;
+/// Interrupt FIFO Level Select Register
+const UARTIFLS: u64 = 0x034;
+/// Interrupt Mask Set/Clear Register
+const UARTIMSC: u64 = 0x038;
+/// Raw Interrupt Status Register
+const UARTRIS: u64 = 0x03C;
+/// Masked Interrupt Status Register
+const UARTMIS: u64 = 0x040;
+/// Interrupt Clear Register
+const UARTICR: u64 = 0x044;
+/// DMA Control Register
+const UARTDMACR: u64 = 0x048;
+/// Peripheral and PrimeCell identification registers.
+const UARTPERIPHID0: u64 = 0xFE0;
+const ID_VALUES: [u32; 8] = [0x11, 0x10, 0x14, 0x00, 0x0D, 0xF0, 0x05, 0xB1];
+
+/// Offset of the last byte of the registers.
+pub(super) const LAST_OFFSET: u64 = 0xFFF;
+
+const FR_TXFE: u32 = 1 << 7;
+const FR_RXFF: u32 = 1 << 6;
+const FR_RXFE: u32 = 1 << 4;
+
+/// `UARTLCR_H[FEN]`, the FIFOs hold 32 bytes instead of 1.
+const LCR_H_FEN: u32 = 1 << 4;
+
+pub(super) const INT_RX: u32 = 1 << 4;
+pub(super) const INT_TX: u32 = 1 << 5;
+/// Every interrupt of `UARTIMSC`.
+const INT_ALL: u32 = 0x7FF;
+
+/// `UARTCR` at reset, `TXE` and `RXE` set.
+const CR_RESET: u32 = 0x300;
+/// `UARTIFLS` at reset, both FIFOs at half full.
+const IFLS_RESET: u32 = 0x12;
+
+const FIFO_SIZE: usize = 32;
+
+/// State of the PL011 registers.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Pl011Registers {
+    ilpr: u32,
+    ibrd: u32,
+    fbrd: u32,
+    lcr_h: u32,
+    cr: u32,
+    ifls: u32,
+    imsc: u32,
+    dmacr: u32,
+    /// Receive FIFO, read through `UARTDR`.
+    rx: VecDeque<u8>,
+}
+
+impl Default for Pl011Registers {
+    fn default() -> Self {
+        Self {
+            ilpr: 0,
+            ibrd: 0,
+            fbrd: 0,
+            lcr_h: 0,
+            cr: CR_RESET,
+            ifls: IFLS_RESET,
+            imsc: 0,
+            dmacr: 0,
+            rx: VecDeque::new(),
+        }
+    }
+}
+
+impl Pl011Registers {
+    fn fifo_depth(&self) -> usize {
+        if self.lcr_h & LCR_H_FEN != 0 {
+            FIFO_SIZE
+        } else {
+            1
+        }
+    }
+
+    /// The receive FIFO has room.
+    pub(super) fn receive_ready(&self) -> bool {
+        self.rx.len() < self.fifo_depth()
+    }
+
+    /// Place a received byte in the receive FIFO.
+    pub(super) fn receive(&mut self, data: u8) {
+        trace!("pl011 received {data:#x}");
+        self.rx.push_back(data);
+    }
+
+    fn flags(&self) -> u32 {
+        let mut flags = FR_TXFE;
+        if self.rx.is_empty() {
+            flags |= FR_RXFE;
+        }
+        if !self.receive_ready() {
+            flags |= FR_RXFF;
+        }
+        flags
+    }
+
+    fn raw_interrupts(&self) -> u32 {
+        if self.rx.is_empty() {
+            INT_TX
+        } else {
+            INT_TX | INT_RX
+        }
+    }
+
+    /// `UARTMIS`, the interrupt line is asserted while it is not 0.
+    pub(super) fn masked_interrupts(&self) -> u32 {
+        self.raw_interrupts() & self.imsc
+    }
+
+    /// Read the word register at `offset`, reading `UARTDR` pops the receive FIFO.
+    pub(super) fn read(&mut self, offset: u64) -> u32 {
+        match offset {
+            UARTDR => self.rx.pop_front().unwrap_or_default() as u32,
+            // no receive errors
+            UARTRSR => 0,
+            UARTFR => self.flags(),
+            UARTILPR => self.ilpr,
+            UARTIBRD => self.ibrd,
+            UARTFBRD => self.fbrd,
+            UARTLCR_H => self.lcr_h,
+            UARTCR => self.cr,
+            UARTIFLS => self.ifls,
+            UARTIMSC => self.imsc,
+            UARTRIS => self.raw_interrupts(),
+            UARTMIS => self.masked_interrupts(),
+            UARTDMACR => self.dmacr,
+            UARTPERIPHID0..=LAST_OFFSET => ID_VALUES[((offset - UARTPERIPHID0) / 4) as usize],
+            _ => {
+                debug!("read of unimplemented pl011 register {offset:#x}");
+                0
+            }
+        }
+    }
+
+    /// Write the `mask`ed bits of `value` to the word register at `offset`.
+    ///
+    /// Returns the byte to transmit if `UARTDR` was written.
+    pub(super) fn write(&mut self, offset: u64, value: u32, mask: u32) -> Option<u8> {
+        let merge = |old: u32| (old & !mask) | (value & mask);
+        match offset {
+            UARTDR => return Some(value as u8),
+            UARTILPR => self.ilpr = merge(self.ilpr) & 0xFF,
+            UARTIBRD => self.ibrd = merge(self.ibrd) & 0xFFFF,
+            UARTFBRD => self.fbrd = merge(self.fbrd) & 0x3F,
+            UARTLCR_H => {
+                self.lcr_h = merge(self.lcr_h) & 0xFF;
+                // the FIFOs are flushed when disabled
+                if self.lcr_h & LCR_H_FEN == 0 {
+                    self.rx.truncate(1);
+                }
+            }
+            UARTCR => self.cr = merge(self.cr) & 0xFF87,
+            UARTIFLS => self.ifls = merge(self.ifls) & 0x3F,
+            UARTIMSC => self.imsc = merge(self.imsc) & INT_ALL,
+            UARTDMACR => self.dmacr = merge(self.dmacr) & 0x7,
+            // errors and interrupt status are derived from the FIFOs
+            UARTRSR | UARTICR => trace!("ignored pl011 clear of {offset:#x}: {value:#x}"),
+            _ => debug!("write to read only pl011 register {offset:#x}: {value:#x}"),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: u32 = 0xFFFF_FFFF;
+
+    #[test]
+    fn test_receive_fifo() {
+        let mut uart = Pl011Registers::default();
+        assert_eq!(FR_TXFE | FR_RXFE, uart.read(UARTFR));
+
+        // without the FIFOs only one byte is held
+        uart.receive(b'a');
+        assert!(!uart.receive_ready());
+        assert_eq!(FR_TXFE | FR_RXFF, uart.read(UARTFR));
+
+        uart.write(UARTLCR_H, LCR_H_FEN, ALL);
+        for data in b"bcd" {
+            uart.receive(*data);
+        }
+        assert!(uart.receive_ready());
+        assert_eq!(b'a' as u32, uart.read(UARTDR));
+        assert_eq!(b'b' as u32, uart.read(UARTDR));
+
+        // disabling the FIFOs keeps the oldest byte
+        uart.write(UARTLCR_H, 0, ALL);
+        assert_eq!(b'c' as u32, uart.read(UARTDR));
+        assert_eq!(FR_TXFE | FR_RXFE, uart.read(UARTFR));
+    }
+
+    #[test]
+    fn test_interrupts() {
+        let mut uart = Pl011Registers::default();
+        assert_eq!(INT_TX, uart.read(UARTRIS));
+        assert_eq!(0, uart.masked_interrupts());
+
+        uart.write(UARTIMSC, INT_RX, ALL);
+        assert_eq!(0, uart.masked_interrupts());
+        uart.receive(b'a');
+        assert_eq!(INT_RX, uart.read(UARTMIS));
+        uart.read(UARTDR);
+        assert_eq!(0, uart.masked_interrupts());
+
+        assert_eq!(Some(b'x'), uart.write(UARTDR, b'x' as u32, 0xFF));
+        assert_eq!(0x11, uart.read(UARTPERIPHID0));
+        assert_eq!(0xB1, uart.read(0xFFC));
+    }
+}
@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! ARM generic timer
+//!
+//! The system counter counts one tick per executed instruction and is reported at
+//! [`COUNTER_FREQUENCY`] through `CNTFRQ_EL0`. `CNTPCT_EL0` and `CNTVCT_EL0` both read the counter,
+//! the virtual offset is always 0.
+//!
+//! Unicorn implements the counter itself, it counts host time at 62.5MHz and `CNTPCT_EL0` and
+//! `CNTVCT_EL0` are read only. With that backend the timers compare against Unicorn's counter,
+//! which also keeps `TVAL` and `ISTATUS` up to date, see [GenericTimer::new()].
+//!
+//! Timers:
+//!
+//!  Name     | Registers                                  | Interrupt
+//! ----------------------------------------------------------
+//!  physical | `CNTP_CTL_EL0`, `CNTP_CVAL_EL0`, `CNTP_TVAL_EL0` | [`PHYSICAL_TIMER_IRQN`]
+//!  virtual  | `CNTV_CTL_EL0`, `CNTV_CVAL_EL0`, `CNTV_TVAL_EL0` | [`VIRTUAL_TIMER_IRQN`]
+//!
+//! The timer registers are system registers of the backend, they are updated on every tick:
+//! `ISTATUS` is set while the timer is enabled and the counter reached `CVAL` and `TVAL` counts
+//! down to `CVAL`. A `TVAL` that differs from the value written on the last tick was written by
+//! software and sets `CVAL` to the counter plus `TVAL`.
+//!
+//! The interrupts are level sensitive, they are latched when `ISTATUS` is set with `IMASK` clear
+//! and again after each completion while the condition holds.
+use serde::{Deserialize, Serialize};
+use styx_core::arch::aarch64::Aarch64Register;
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::trace;
+
+/// `CNTFRQ_EL0`, 62.5MHz like the QEMU virt machine.
+pub const COUNTER_FREQUENCY: u64 = 62_500_000;
+
+/// Non-secure EL1 physical timer PPI.
+pub const PHYSICAL_TIMER_IRQN: ExceptionNumber = 30;
+/// Virtual timer PPI.
+pub const VIRTUAL_TIMER_IRQN: ExceptionNumber = 27;
+
+const CTL_ENABLE: u64 = 1 << 0;
+const CTL_IMASK: u64 = 1 << 1;
+const CTL_ISTATUS: u64 = 1 << 2;
+
+/// System registers and interrupt of a timer.
+struct TimerRegisters {
+    ctl: Aarch64Register,
+    cval: Aarch64Register,
+    tval: Aarch64Register,
+    irqn: ExceptionNumber,
+}
+
+const TIMERS: [TimerRegisters; 2] = [
+    TimerRegisters {
+        ctl: Aarch64Register::CntpCtlEl0,
+        cval: Aarch64Register::CntpCvalEl0,
+        tval: Aarch64Register::CntpTvalEl0,
+        irqn: PHYSICAL_TIMER_IRQN,
+    },
+    TimerRegisters {
+        ctl: Aarch64Register::CntvCtlEl0,
+        cval: Aarch64Register::CntvCvalEl0,
+        tval: Aarch64Register::CntvTvalEl0,
+        irqn: VIRTUAL_TIMER_IRQN,
+    },
+];
+
+/// Values of the timer registers in the backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TimerValues {
+    ctl: u64,
+    cval: u64,
+    tval: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Timer {
+    /// `TVAL` written on the last update.
+    tval: u32,
+    /// The interrupt is asserted.
+    asserted: bool,
+    /// Counts until the interrupt is asserted as of the last update, only with our counter.
+    #[serde(skip)]
+    deadline: Option<u64>,
+}
+
+impl Timer {
+    /// Update the register `values` for the counter at `count`.
+    ///
+    /// Returns `true` if the interrupt was asserted by this update.
+    fn update(&mut self, count: u64, values: &mut TimerValues) -> bool {
+        let tval = values.tval as u32;
+        if tval != self.tval {
+            // TVAL is a signed offset from the counter
+            values.cval = count.wrapping_add(tval as i32 as u64);
+            trace!("timer compare value set to {:#x}", values.cval);
+        }
+
+        let fired = values.ctl & CTL_ENABLE != 0 && count >= values.cval;
+        values.ctl &= CTL_ENABLE | CTL_IMASK;
+        if fired {
+            values.ctl |= CTL_ISTATUS;
+        }
+        self.tval = values.cval.wrapping_sub(count) as u32;
+        values.tval = self.tval as u64;
+        self.deadline = (values.ctl & (CTL_ENABLE | CTL_IMASK) == CTL_ENABLE && !fired)
+            .then(|| values.cval - count);
+
+        self.set_asserted(fired && values.ctl & CTL_IMASK == 0)
+    }
+
+    /// Track the interrupt for `values` kept up to date by the backend.
+    ///
+    /// Returns `true` if the interrupt was asserted by this update.
+    fn update_interrupt(&mut self, count: u64, values: &TimerValues) -> bool {
+        let fired = values.ctl & CTL_ENABLE != 0 && count >= values.cval;
+        self.set_asserted(fired && values.ctl & CTL_IMASK == 0)
+    }
+
+    fn set_asserted(&mut self, asserted: bool) -> bool {
+        let rising = asserted && !self.asserted;
+        self.asserted = asserted;
+        rising
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct GenericTimerState {
+    count: u64,
+    timers: [Timer; 2],
+}
+
+/// The system counter and the physical and virtual EL1 timers, see the
+/// [module documentation](self).
+#[derive(Default)]
+pub struct GenericTimer {
+    /// The backend implements the counter, see [GenericTimer::new()].
+    backend_counter: bool,
+    state: GenericTimerState,
+}
+
+impl GenericTimer {
+    /// Timer for a processor with `backend`.
+    ///
+    /// The counter counts instructions on the pcode backend. Other backends implement the counter
+    /// and the timer registers themselves, the timer only raises the interrupts for them.
+    pub fn new(backend: Backend) -> Self {
+        Self {
+            backend_counter: backend != Backend::Pcode,
+            state: Default::default(),
+        }
+    }
+
+    fn write_counter(&self, cpu: &mut dyn CpuBackend) -> Result<(), UnknownError> {
+        if self.backend_counter {
+            return Ok(());
+        }
+        cpu.write_register(Aarch64Register::CntpctEl0, self.state.count)?;
+        cpu.write_register(Aarch64Register::CntvctEl0, self.state.count)?;
+        Ok(())
+    }
+}
+
+impl Peripheral for GenericTimer {
+    fn name(&self) -> &str {
+        "generic timer"
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        TIMERS.iter().map(|timer| timer.irqn).collect()
+    }
+
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let cpu = proc.core.cpu.as_mut();
+        cpu.write_register(Aarch64Register::CntfrqEl0, COUNTER_FREQUENCY)?;
+        self.write_counter(cpu)
+    }
+
+    fn reset(&mut self, cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.state = Default::default();
+        self.write_counter(cpu)
+    }
+
+    /// Unicorn's counter counts host time, only timers on our counter have a deadline.
+    fn next_deadline(&self) -> Option<u64> {
+        self.state
+            .timers
+            .iter()
+            .filter_map(|timer| timer.deadline)
+            .min()
+    }
+
+    fn post_event_hook(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+        irqn: ExceptionNumber,
+    ) -> Result<(), UnknownError> {
+        for (timer, registers) in self.state.timers.iter().zip(TIMERS.iter()) {
+            if registers.irqn == irqn && timer.asserted {
+                event_controller.latch(irqn)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn tick(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        let count = if self.backend_counter {
+            cpu.read_register::<u64>(Aarch64Register::CntpctEl0)?
+        } else {
+            self.state.count = self.state.count.wrapping_add(delta.count);
+            self.write_counter(cpu)?;
+            self.state.count
+        };
+
+        for (timer, registers) in self.state.timers.iter_mut().zip(TIMERS.iter()) {
+            let mut values = TimerValues {
+                ctl: cpu.read_register::<u64>(registers.ctl)?,
+                cval: cpu.read_register::<u64>(registers.cval)?,
+                tval: cpu.read_register::<u64>(registers.tval)?,
+            };
+            let rising = if self.backend_counter {
+                // writing CVAL makes the backend recompute ISTATUS for its current count
+                cpu.write_register(registers.cval, values.cval)?;
+                timer.update_interrupt(count, &values)
+            } else {
+                let rising = timer.update(count, &mut values);
+                cpu.write_register(registers.ctl, values.ctl)?;
+                cpu.write_register(registers.cval, values.cval)?;
+                cpu.write_register(registers.tval, values.tval)?;
+                rising
+            };
+
+            if rising {
+                trace!("timer interrupt {}", registers.irqn);
+                event_controller.latch(registers.irqn)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.state)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.state = state.get()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_value() {
+        let mut timer = Timer::default();
+        let mut values = TimerValues {
+            ctl: CTL_ENABLE,
+            cval: 0,
+            tval: 100,
+        };
+
+        // software wrote TVAL
+        assert!(!timer.update(1000, &mut values));
+        assert_eq!(1100, values.cval);
+        assert_eq!(100, values.tval);
+        assert_eq!(CTL_ENABLE, values.ctl);
+        assert_eq!(Some(100), timer.deadline);
+
+        assert!(!timer.update(1050, &mut values));
+        assert_eq!(50, values.tval);
+        assert_eq!(Some(50), timer.deadline);
+
+        assert!(timer.update(1100, &mut values));
+        assert_eq!(CTL_ENABLE | CTL_ISTATUS, values.ctl);
+        assert_eq!(None, timer.deadline);
+        // still asserted
+        assert!(!timer.update(1200, &mut values));
+        assert!(timer.asserted);
+        assert_eq!(-100i32 as u32 as u64, values.tval);
+    }
+
+    #[test]
+    fn test_timer_mask() {
+        let mut timer = Timer::default();
+        let mut values = TimerValues {
+            ctl: CTL_ENABLE | CTL_IMASK | CTL_ISTATUS,
+            cval: 10,
+            tval: 0,
+        };
+        assert!(!timer.update(5, &mut values));
+        // ISTATUS is read only
+        assert_eq!(CTL_ENABLE | CTL_IMASK, values.ctl);
+        assert_eq!(None, timer.deadline);
+
+        assert!(!timer.update(10, &mut values));
+        assert_eq!(CTL_ENABLE | CTL_IMASK | CTL_ISTATUS, values.ctl);
+        assert!(!timer.asserted);
+
+        values.ctl = CTL_ENABLE;
+        assert!(timer.update(11, &mut values));
+
+        values.ctl = 0;
+        assert!(!timer.update(12, &mut values));
+        assert!(!timer.asserted);
+    }
+
+    #[test]
+    fn test_timer_backend_values() {
+        let mut timer = Timer::default();
+        let values = TimerValues {
+            ctl: CTL_ENABLE,
+            cval: 100,
+            tval: 0,
+        };
+        assert!(!timer.update_interrupt(99, &values));
+        assert!(timer.update_interrupt(100, &values));
+        assert!(!timer.update_interrupt(101, &values));
+
+        let masked = TimerValues {
+            ctl: CTL_ENABLE | CTL_IMASK,
+            ..values
+        };
+        assert!(!timer.update_interrupt(102, &masked));
+        assert!(!timer.asserted);
+    }
+}
@@ -2,6 +2,7 @@
 //! Emulates the Clock Manager for the Cyclone V HPS.
 use std::mem::size_of;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_cyclone_v_hps_sys::{clkmgr, generic::FromBytes, Clkmgr};
 
 const CLKMGR_REG_BLOCK_SIZE: usize = size_of::<clkmgr::RegisterBlock>();
@@ -120,4 +121,19 @@ impl Peripheral for ClockManager {
     fn name(&self) -> &str {
         "Clock Manager"
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(
+            &self.inner_hal.registers.as_bytes_ref(),
+        )?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let registers: Vec<u8> = state.get()?;
+        // # Safety
+        // The bytes were saved from a register block of the same type.
+        self.inner_hal.registers = unsafe { clkmgr::RegisterBlock::from_bytes(&registers) }
+            .context("saved clock manager registers have the wrong size")?;
+        Ok(())
+    }
 }
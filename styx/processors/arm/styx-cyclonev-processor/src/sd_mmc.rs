@@ -2,6 +2,7 @@
 use log::log_enabled;
 use std::mem::size_of;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 
 use super::sd_mmc_hooks;
 use styx_cyclone_v_hps_sys::{generic::FromBytes, sdmmc, Sdmmc};
@@ -132,6 +133,16 @@ impl Peripheral for CycloneVSDMMC {
     fn name(&self) -> &str {
         "SD MMC"
     }
+
+    /// The registers live in memory, only the paused flag is saved.
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.paused)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.paused = state.get()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -2,6 +2,7 @@
 //! Emulates Uart controller for the Cyclone V HPS.
 use hooks::UartMMRHook;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_cyclone_v_hps_sys::{uart0, Uart0, Uart1};
 use tokio::sync::broadcast;
 use tracing::{debug, trace, warn};
@@ -143,6 +144,14 @@ impl UartImpl for UartPortInner {
 
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        self.inner_hal.save_state()
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.inner_hal.restore_state(state)
+    }
 }
 
 impl UartPortInner {
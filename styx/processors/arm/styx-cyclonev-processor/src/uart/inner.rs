@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! Emulates Uart controller for K21
+use serde::{Deserialize, Serialize};
 use std::{collections::VecDeque, mem::size_of};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_cyclone_v_hps_sys::{generic::FromBytes, uart0};
 
 pub enum UartPortNumber {
@@ -14,6 +16,7 @@ pub enum UartPortNumber {
 /// This is also the value in the cpr register's fifo_mode.
 pub const TX_RX_FIFO_BUFFERS_SIZE: usize = 128;
 
+#[derive(Serialize, Deserialize)]
 pub struct UartFifo {
     pub enabled: bool,
     pub tx_empty_threshold: usize,
@@ -88,7 +91,7 @@ impl UartFifo {
 /// processors state. One view is active, and thus being accessed during writes and reads, while
 /// the others are hidden.  These "shadow registers" allow us to preserve all views and present
 /// them to the guest at the appropriate times.
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct UartShadowRegs {
     pub dll_val: u32,
     pub dlh_val: u32,
@@ -101,7 +104,7 @@ pub struct UartShadowRegs {
     pub fcr_val: u32,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct UartInterrupt {
     interrupt: bool,
     interrupt_enabled: bool,
@@ -146,7 +149,7 @@ impl UartInterrupt {
 }
 
 /// UART Interrupt Types describe in Table 22-4 of the Cyclone V Hard Processor System TRM.
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct UartInterruptControl {
     /// Transmit Hold Register Empty Interrupt (THRE) Interrupt (IER bit 7)
     /// This seems to actually be a mode rather than an interrupt, though the documentation is
@@ -248,6 +251,39 @@ impl UartHalLayer {
         }
     }
 
+    /// Registers, fifos and interrupt state, the base address is fixed by the port.
+    pub fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(
+            self.registers.as_bytes_ref(),
+            &self.shadow_regs,
+            self.dlab_state,
+            self.request_to_send,
+            self.data_terminal_ready,
+            &self.fifo,
+            &self.interrupt_control,
+            self.client_connected,
+        ))?)
+    }
+
+    pub fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let registers: Vec<u8>;
+        (
+            registers,
+            self.shadow_regs,
+            self.dlab_state,
+            self.request_to_send,
+            self.data_terminal_ready,
+            self.fifo,
+            self.interrupt_control,
+            self.client_connected,
+        ) = state.get()?;
+        // # Safety
+        // The bytes were saved from a register block of the same type.
+        self.registers = unsafe { uart0::RegisterBlock::from_bytes(&registers) }
+            .context("saved uart registers have the wrong size")?;
+        Ok(())
+    }
+
     fn reset_uart_regs(&mut self) {
         // # Safety
         // This unsafe block is performing hardware initialization,
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: BSD-2-Clause
+use serde::{Deserialize, Serialize};
 use std::mem::offset_of;
 use styx_core::errors::anyhow::anyhow;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use tracing::trace;
 
 // base FTM type
@@ -15,6 +17,15 @@ use super::mk21f12_sys::{FTM0_BASE, FTM1_BASE, FTM2_BASE, FTM3_BASE};
 
 const FLEXIBLE_TIMER_DURATION: u64 = 10000;
 
+/// Saved state of a [`FlexibleTimer`].
+#[derive(Debug, Serialize, Deserialize)]
+struct FlexibleTimerState {
+    running: bool,
+    guest_enabled: bool,
+    internal_counter: u64,
+    interrupt_raised: bool,
+}
+
 pub struct FlexibleTimer {
     num: u32,
     base_address: u32,
@@ -92,10 +103,10 @@ impl Peripheral for FlexibleTimer {
     ) -> Result<(), UnknownError> {
         // if the guest has enabled us
         if self.guest_enabled {
-            let _ = self.internal_counter.saturating_add(delta.count);
+            self.internal_counter = self.internal_counter.saturating_add(delta.count);
 
             if self.internal_counter >= self.timer_duration {
-                self.internal_counter = 0;
+                self.internal_counter %= self.timer_duration;
                 self.interrupt_raised = true;
                 event_controller.latch(self.irqn)?;
             }
@@ -104,6 +115,11 @@ impl Peripheral for FlexibleTimer {
         Ok(())
     }
 
+    fn next_deadline(&self) -> Option<u64> {
+        self.guest_enabled
+            .then(|| self.timer_duration - self.internal_counter)
+    }
+
     fn init(&mut self, _proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
         Ok(())
     }
@@ -119,6 +135,24 @@ impl Peripheral for FlexibleTimer {
         self.interrupt_raised = false;
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&FlexibleTimerState {
+            running: self.running,
+            guest_enabled: self.guest_enabled,
+            internal_counter: self.internal_counter,
+            interrupt_raised: self.interrupt_raised,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: FlexibleTimerState = state.get()?;
+        self.running = state.running;
+        self.guest_enabled = state.guest_enabled;
+        self.internal_counter = state.internal_counter;
+        self.interrupt_raised = state.interrupt_raised;
+        Ok(())
+    }
 }
 
 pub struct FtmController {
@@ -197,6 +231,13 @@ impl Peripheral for FtmController {
         Ok(())
     }
 
+    fn next_deadline(&self) -> Option<u64> {
+        self.timers
+            .iter()
+            .filter_map(|timer| timer.next_deadline())
+            .min()
+    }
+
     fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
         for timer in &mut self.timers {
             timer.register_hooks(proc.core.cpu.as_mut())?;
@@ -216,6 +257,30 @@ impl Peripheral for FtmController {
 
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let timers = self
+            .timers
+            .iter()
+            .map(|timer| timer.save_state())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ComponentState::new(&timers)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let timers: Vec<ComponentState> = state.get()?;
+        if timers.len() != self.timers.len() {
+            return Err(anyhow!(
+                "snapshot holds {} flexible timers, expected {}",
+                timers.len(),
+                self.timers.len()
+            ));
+        }
+        for (timer, state) in self.timers.iter_mut().zip(timers.iter()) {
+            timer.restore_state(state)?;
+        }
+        Ok(())
+    }
 }
 
 #[allow(dead_code)]
@@ -35,6 +35,7 @@
 //!                                                 └──────┘
 //!
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use tracing::trace;
 
 mod constants;
@@ -42,6 +43,7 @@ mod pin;
 mod port;
 
 use constants::{GPIO_BASE, GPIO_END, GPIO_PORTS};
+use pin::Pin;
 use port::GPIOPort;
 
 /// Notional example of a GPIO peripheral for `kinetis_21`.
@@ -98,6 +100,19 @@ impl Peripheral for Gpio {
     fn name(&self) -> &str {
         "GPIO"
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let pins: Vec<&[Pin]> = self.ports.iter().map(|port| port.pins()).collect();
+        Ok(ComponentState::new(&pins)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let pins: Vec<Vec<Pin>> = state.get()?;
+        for (port, pins) in self.ports.iter_mut().zip(pins) {
+            port.set_pins(pins)?;
+        }
+        Ok(())
+    }
 }
 
 /// Callback function for writes to GPIO memory-mapped registers.
@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! The ['Pin'] abstraction represents an individual pin within a GPIO port on a target device.
 //! Pins can be individually set, cleared, toggled and configured.
+use serde::{Deserialize, Serialize};
 
 /// Represents an individual pin within a GPIO port on a target device.
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub struct Pin {
     /// pin number
     pno: u32,
@@ -70,7 +71,7 @@ impl Pin {
 }
 
 /// Represents the configured mode of a [`Pin`].
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 enum PinMode {
     Input = 0,
     Output = 1,
@@ -7,6 +7,7 @@ use super::{
     pin::Pin,
 };
 use std::{collections::BTreeMap, ops::RangeInclusive};
+use styx_core::errors::anyhow::anyhow;
 use styx_core::prelude::*;
 use tracing::{trace, warn};
 
@@ -64,6 +65,24 @@ impl GPIOPort {
     }
 
     /// Set initial register state.
+    /// The pins, the only state not held in the memory mapped registers.
+    pub fn pins(&self) -> &[Pin] {
+        &self.pins
+    }
+
+    pub fn set_pins(&mut self, pins: Vec<Pin>) -> Result<(), UnknownError> {
+        if pins.len() != self.pins.len() {
+            return Err(anyhow!(
+                "GPIO{} has {} pins, got {}",
+                self.name,
+                self.pins.len(),
+                pins.len()
+            ));
+        }
+        self.pins = pins;
+        Ok(())
+    }
+
     pub fn reset_state(&self, mmu: &mut Mmu) -> Result<(), UnknownError> {
         // Initialize registers to `reset` state.
         self.regs.clone().into_iter().for_each(|(_, r)| {
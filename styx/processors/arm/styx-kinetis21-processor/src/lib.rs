@@ -43,7 +43,7 @@ use styx_core::errors::anyhow::anyhow;
 use styx_core::memory::memory_region::MemoryRegion;
 use styx_core::prelude::*;
 use styx_mk21f12_sys as mk21f12_sys;
-use styx_nvic::Nvic;
+use styx_nvic::{Nvic, SysTickTimer};
 use thiserror::Error;
 
 // this import is only used when building documentation. When the
@@ -55,7 +55,6 @@ use embed_doc_image::embed_doc_image;
 
 use self::ftm::FtmController;
 use self::mcg::Mcg;
-use self::systick::SysTickTimer;
 use self::uart::get_uarts;
 
 use styx_peripherals::uart::UartController;
@@ -64,7 +63,6 @@ mod bit_banding;
 mod ftm;
 pub mod gpio;
 mod mcg;
-mod systick;
 mod uart;
 
 use gpio::Gpio;
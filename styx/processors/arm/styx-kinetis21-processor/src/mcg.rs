@@ -3,6 +3,10 @@ use derivative::Derivative;
 use styx_core::prelude::*;
 use tracing::debug;
 
+/// Multipurpose Clock Generator.
+///
+/// All of its state is in the memory mapped registers, which are saved with the memory in
+/// snapshots, so it saves no state of its own.
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct Mcg;
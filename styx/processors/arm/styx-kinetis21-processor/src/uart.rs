@@ -7,6 +7,7 @@ use std::mem::offset_of;
 use styx_core::errors::UnknownError;
 use styx_core::memory::Mmu;
 use styx_core::prelude::{CpuBackend, ExceptionNumber};
+use styx_core::snapshot::ComponentState;
 
 use tokio::sync::broadcast;
 use tracing::{debug, trace, warn};
@@ -283,6 +284,20 @@ impl UartImpl for UartPortInner {
 
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(
+            self.inner_hal.to_bytes(),
+            &self.rx_fifo,
+        ))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let (registers, rx_fifo): ([u8; 18], VecDeque<u8>) = state.get()?;
+        self.inner_hal = UartHalLayer::from_bytes(&registers);
+        self.rx_fifo = rx_fifo;
+        Ok(())
+    }
 }
 
 /*
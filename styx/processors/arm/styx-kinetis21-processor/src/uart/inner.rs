@@ -296,7 +296,29 @@ pub struct UartHalLayer {
 }
 
 impl UartHalLayer {
-    #[allow(dead_code)]
+    pub fn to_bytes(&self) -> [u8; 18] {
+        [
+            self.bdh.clone().into(),
+            self.bdl.clone().into(),
+            self.c1.clone().into(),
+            self.c2.clone().into(),
+            self.s1.clone().into(),
+            self.s2.clone().into(),
+            self.c3.clone().into(),
+            self.d.clone().into(),
+            self.ma1.clone().into(),
+            self.ma2.clone().into(),
+            self.c4.clone().into(),
+            self.c5.clone().into(),
+            self.ed.clone().into(),
+            self.modem.clone().into(),
+            self.ir.clone().into(),
+            self.pfifo.clone().into(),
+            self.cfifo.clone().into(),
+            self.sfifo.clone().into(),
+        ]
+    }
+
     pub fn from_bytes(data: &[u8; 18]) -> Self {
         UartHalLayer {
             bdh: BDH::from(data[0]),
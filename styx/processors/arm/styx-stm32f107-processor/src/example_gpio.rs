@@ -10,9 +10,11 @@
 //! STM32F107xx advanced Arm®-based 32-bit MCUs.
 //! # Resources:
 //! - [Technical Reference Manual](https://www.st.com/resource/en/reference_manual/rm0008-stm32f101xx-stm32f102xx-stm32f103xx-stm32f105xx-and-stm32f107xx-advanced-armbased-32bit-mcus-stmicroelectronics.pdf)
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 
 pub mod gpio_constants {
     /// Address constants for GPIO ports.
@@ -262,9 +264,10 @@ pub mod gpio_constants {
 
 pub mod pin {
     use super::gpio_constants::pindefs;
+    use serde::{Deserialize, Serialize};
     use styx_core::util::{bit_range, high_low_u32};
 
-    #[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+    #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
     pub struct Pin {
         // pin number
         pub pno: usize,
@@ -415,7 +418,7 @@ pub mod pin {
         }
     }
 
-    #[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(Default, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
     pub enum Speed {
         NotSet = 0, // Input mode
         #[default]
@@ -450,7 +453,7 @@ pub mod pin {
         }
     }
 
-    #[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+    #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
     pub enum Mode {
         /// "Analog"
         Analog = 0x0,
@@ -796,6 +799,13 @@ use pin::Pin;
 use port::Port;
 use reg::Reg;
 
+/// Saved state of a [Port].
+#[derive(Serialize, Deserialize)]
+struct PortState {
+    pins: Vec<Pin>,
+    registers: Vec<u32>,
+}
+
 /// Notional example of a GPIO peripheral for `stm32f107`.
 pub struct Gpio {
     pub a: Port,
@@ -901,6 +911,39 @@ impl Peripheral for Gpio {
     fn name(&self) -> &str {
         "Stm32 Gpio"
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let ports: Vec<PortState> = self
+            .ports()
+            .into_iter()
+            .map(|port| PortState {
+                pins: port.get_state(),
+                registers: port.regs.iter().map(|reg| reg.reg_value).collect(),
+            })
+            .collect();
+        Ok(ComponentState::new(&ports)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let ports: Vec<PortState> = state.get()?;
+        if ports.len() != portdefs::NUMPORTS {
+            return Err(anyhow!(
+                "saved {} gpio ports, expected {}",
+                ports.len(),
+                portdefs::NUMPORTS
+            ));
+        }
+        for (port, saved) in self.ports_mut().into_iter().zip(ports) {
+            if saved.pins.len() != port.pins.len() || saved.registers.len() != port.regs.len() {
+                return Err(anyhow!("saved gpio port {} has the wrong shape", port.name));
+            }
+            port.pins = saved.pins;
+            for (reg, value) in port.regs.iter_mut().zip(saved.registers) {
+                reg.reg_value = value;
+            }
+        }
+        Ok(())
+    }
 }
 
 ///////////////////////////////////// CRATE  /////////////////////////////////////////////////////////
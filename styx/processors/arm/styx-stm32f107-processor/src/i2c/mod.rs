@@ -44,10 +44,12 @@
 use bilge::prelude::*;
 use derivative::Derivative;
 use getset::Getters;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::pin::Pin;
 use std::sync::Arc;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_core::{errors::anyhow::anyhow, grpc::io, grpc::io::i2c::i2c_port_server::I2cPortServer};
 use thiserror::Error;
 use tokio::sync::{broadcast, mpsc};
@@ -65,7 +67,7 @@ type I2CInnerRecv = mpsc::Receiver<MessageType>;
 type RegisteredDevices = Arc<Mutex<HashSet<u32>>>;
 
 /// Defines the valid states for the I2C bus
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum I2CBusState {
     /// master is addressing a device
     Address,
@@ -77,6 +79,16 @@ pub enum I2CBusState {
     Write,
 }
 
+/// Saved state of an [I2CPortInner].
+#[derive(Serialize, Deserialize)]
+struct I2CPortState {
+    registers: [u16; 9],
+    i2c_bus_state: I2CBusState,
+    itevten: bool,
+    iterren: bool,
+    itbufen: bool,
+}
+
 #[derive(Derivative)]
 pub struct I2CPortInner {
     /// port number identifier
@@ -314,6 +326,24 @@ impl I2CPortInner {
         vec![self.event_interrupt, self.error_interrupt]
     }
 
+    fn save_state(&self) -> I2CPortState {
+        I2CPortState {
+            registers: self.inner_hal.lock().unwrap().to_words(),
+            i2c_bus_state: self.i2c_bus_state.lock().unwrap().clone(),
+            itevten: self.itevten,
+            iterren: self.iterren,
+            itbufen: self.itbufen,
+        }
+    }
+
+    fn restore_state(&mut self, state: I2CPortState) {
+        *self.inner_hal.lock().unwrap() = I2CHal::from_words(&state.registers);
+        *self.i2c_bus_state.lock().unwrap() = state.i2c_bus_state;
+        self.itevten = state.itevten;
+        self.iterren = state.iterren;
+        self.itbufen = state.itbufen;
+    }
+
     fn tick(
         &mut self,
         _cpu: &mut dyn CpuBackend,
@@ -415,6 +445,26 @@ impl Peripheral for I2CController {
     fn name(&self) -> &str {
         "stm32 i2c controller"
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let ports: Vec<I2CPortState> = self.i2cs.iter().map(|i2c| i2c.save_state()).collect();
+        Ok(ComponentState::new(&ports)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let ports: Vec<I2CPortState> = state.get()?;
+        if ports.len() != self.i2cs.len() {
+            return Err(anyhow!(
+                "saved {} i2c ports, controller has {}",
+                ports.len(),
+                self.i2cs.len()
+            ));
+        }
+        for (i2c, port) in self.i2cs.iter_mut().zip(ports) {
+            i2c.restore_state(port);
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Error)]
@@ -635,6 +685,36 @@ impl I2CHal {
         self.trise = TRISE::from(0);
         self.trise.set_trise(u6::new(0x2));
     }
+
+    /// Register values in memory map order.
+    pub fn to_words(&self) -> [u16; 9] {
+        [
+            self.cr1.clone().into(),
+            self.cr2.clone().into(),
+            self.oar1.clone().into(),
+            self.oar2.clone().into(),
+            self.dr.clone().into(),
+            self.sr1.clone().into(),
+            self.sr2.clone().into(),
+            self.ccr.clone().into(),
+            self.trise.clone().into(),
+        ]
+    }
+
+    /// Registers from values saved with [I2CHal::to_words()].
+    pub fn from_words(words: &[u16; 9]) -> Self {
+        Self {
+            cr1: CR1::from(words[0]),
+            cr2: CR2::from(words[1]),
+            oar1: OAR1::from(words[2]),
+            oar2: OAR2::from(words[3]),
+            dr: DR::from(words[4]),
+            sr1: SR1::from(words[5]),
+            sr2: SR2::from(words[6]),
+            ccr: CCR::from(words[7]),
+            trise: TRISE::from(words[8]),
+        }
+    }
 }
 
 #[bitsize(16)]
@@ -9,10 +9,12 @@
 use bilge::prelude::*;
 use derivative::Derivative;
 use getset::Getters;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use styx_core::grpc::io;
 use styx_core::grpc::io::spi::{MasterChipSelectPacket, MasterPacket};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_spi::{IntoSpiImp, SpiImpl};
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::error::TryRecvError;
@@ -80,6 +82,18 @@ impl IntoSpiImp for StmSpiPrecursor {
     }
 }
 
+/// Saved state of an [SPIPortInner].
+#[derive(Serialize, Deserialize)]
+struct SpiPortState {
+    registers: [u16; 7],
+    byte_frame_size: bool,
+    rx_fifo: VecDeque<u8>,
+    txeie: bool,
+    rxneie: bool,
+    errie: bool,
+    selected: bool,
+}
+
 #[derive(Derivative)]
 pub struct SPIPortInner {
     /// port number identifier
@@ -184,6 +198,30 @@ impl SpiImpl for SPIPortInner {
     ) -> Result<(), UnknownError> {
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&SpiPortState {
+            registers: self.inner_hal.to_words(),
+            byte_frame_size: self.byte_frame_size,
+            rx_fifo: self.rx_fifo.clone(),
+            txeie: self.txeie,
+            rxneie: self.rxneie,
+            errie: self.errie,
+            selected: self.selected,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: SpiPortState = state.get()?;
+        self.inner_hal = SPIHal::from_words(&state.registers);
+        self.byte_frame_size = state.byte_frame_size;
+        self.rx_fifo = state.rx_fifo;
+        self.txeie = state.txeie;
+        self.rxneie = state.rxneie;
+        self.errie = state.errie;
+        self.selected = state.selected;
+        Ok(())
+    }
 }
 
 impl SPIPortInner {
@@ -304,6 +342,32 @@ impl SPIHal {
         self.rxcrcr = RXCRCR::from(0);
         self.txcrcr = TXCRCR::from(0);
     }
+
+    /// Register values in memory map order.
+    pub fn to_words(&self) -> [u16; 7] {
+        [
+            self.cr1.clone().into(),
+            self.cr2.clone().into(),
+            self.sr.clone().into(),
+            self.dr.clone().into(),
+            self.crcpr.clone().into(),
+            self.rxcrcr.clone().into(),
+            self.txcrcr.clone().into(),
+        ]
+    }
+
+    /// Registers from values saved with [SPIHal::to_words()].
+    pub fn from_words(words: &[u16; 7]) -> Self {
+        Self {
+            cr1: CR1::from(words[0]),
+            cr2: CR2::from(words[1]),
+            sr: SR::from(words[2]),
+            dr: DR::from(words[3]),
+            crcpr: CRCPR::from(words[4]),
+            rxcrcr: RXCRCR::from(words[5]),
+            txcrcr: TXCRCR::from(words[6]),
+        }
+    }
 }
 
 #[bitsize(16)]
@@ -2,8 +2,10 @@
 //! Emulation of UART/USART controller for STM32F405
 use derivative::Derivative;
 use num_derive::ToPrimitive;
+use serde::{Deserialize, Serialize};
 use std::{collections::VecDeque, mem::size_of};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_peripherals::uart::{IntoUartImpl, UartImpl};
 use styx_stm32f405_sys::interrupt::Interrupt;
 use tokio::sync::broadcast;
@@ -67,6 +69,7 @@ pub enum RegisterBlocks {
     Usart6(usart6::RegisterBlock),
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct DataTerminals {
     // note: baud rate and noise handling are abstracted over as data
     // transfer is emulated atomically and noiselessly
@@ -206,7 +209,7 @@ impl DataTerminals {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct UsartInterrupt {
     interrupt: bool,
     interrupt_enabled: bool,
@@ -260,7 +263,7 @@ impl UsartInterrupt {
 
 /// USART Interrupt Types. See **[STM32F405xx: USART Interrupts](https://www.st.com/resource/en/reference_manual/dm00031020-stm32f405-415-stm32f407-417-stm32f427-437-and-stm32f429-439-advanced-arm-based-32-bit-mcus-stmicroelectronics.pdf#page=1009)** for
 /// details.
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct UsartInterruptControl {
     /// Transmit Data Register Empty
     pub int_txe: UsartInterrupt,
@@ -648,4 +651,26 @@ impl UartImpl for UartPortInner {
         }
         Ok(())
     }
+
+    /// The registers live in memory, only the emulated terminals and interrupts are saved.
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(
+            &self.inner_hal.data_terminals,
+            &self.inner_hal.interrupt_control,
+            self.inner_hal.client_connected,
+            self.inner_hal.enabled,
+            &self.rx_fifo,
+        ))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        (
+            self.inner_hal.data_terminals,
+            self.inner_hal.interrupt_control,
+            self.inner_hal.client_connected,
+            self.inner_hal.enabled,
+            self.rx_fifo,
+        ) = state.get()?;
+        Ok(())
+    }
 }
@@ -152,6 +152,39 @@ impl EventsContainer {
         }
     }
 
+    /// Snapshot of `(written_enabled, pending, latched)` for each event, in [Event] order.
+    pub fn save_state(&self) -> Vec<(bool, bool, bool)> {
+        self.exceptions
+            .values()
+            .map(|exception| {
+                let exception = exception.lock().unwrap();
+                (
+                    exception.written_enabled,
+                    exception.pending,
+                    exception.latched,
+                )
+            })
+            .collect()
+    }
+
+    /// Restore state from [EventsContainer::save_state()].
+    pub fn restore_state(&self, state: &[(bool, bool, bool)]) -> Result<(), UnknownError> {
+        if state.len() != self.exceptions.len() {
+            return Err(anyhow!("saved event state has the wrong number of events"));
+        }
+
+        for (exception, (written_enabled, pending, latched)) in
+            self.exceptions.values().zip(state.iter().copied())
+        {
+            let mut exception = exception.lock().unwrap();
+            exception.written_enabled = written_enabled;
+            exception.pending = pending;
+            exception.latched = latched;
+        }
+
+        Ok(())
+    }
+
     /// Performs latch bit clearing as described in the ILAT documentation.
     ///
     /// Writes to ILAT are used to clear bits only (in Supervisor mode). To clear bit N from ILAT,
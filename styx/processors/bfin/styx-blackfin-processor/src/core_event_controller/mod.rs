@@ -6,8 +6,10 @@ mod system_interrupts;
 
 use event::*;
 use exception::*;
+use serde::{Deserialize, Serialize};
 use styx_blackfin_sys::bf512 as sys;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_core::{
     cpu::arch::blackfin::BlackfinRegister,
     event_controller::{ActivateIRQnError, InterruptExecuted, Peripherals},
@@ -25,6 +27,15 @@ pub struct CoreEventController {
     current_exceptions: Mutex<Vec<ExecutingEvent>>,
 }
 
+/// Serialized [`CoreEventController`] state.
+#[derive(Serialize, Deserialize)]
+struct CoreEventControllerState {
+    exceptions: Vec<(bool, bool, bool)>,
+    system: Vec<(bool, bool, bool, Option<u8>)>,
+    /// `(event, reti)` for each executing event
+    current_exceptions: Vec<(u8, u32)>,
+}
+
 struct ExecutingEvent {
     event: Event,
     reti: u32,
@@ -176,6 +187,41 @@ impl EventControllerImpl for CoreEventController {
         register_hooks(cpu)?;
         Ok(())
     }
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let current_exceptions = self
+            .current_exceptions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| (u8::from(e.event), e.reti))
+            .collect();
+
+        Ok(ComponentState::new(&CoreEventControllerState {
+            exceptions: self.exceptions.save_state(),
+            system: self.system.lock().unwrap().save_state(),
+            current_exceptions,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: CoreEventControllerState = state.get()?;
+
+        self.exceptions.restore_state(&state.exceptions)?;
+        self.system.lock().unwrap().restore_state(&state.system)?;
+        *self.current_exceptions.lock().unwrap() = state
+            .current_exceptions
+            .into_iter()
+            .map(|(event, reti)| {
+                Ok(ExecutingEvent {
+                    event: Event::try_from(event)
+                        .map_err(|_| anyhow!("invalid saved executing event"))?,
+                    reti,
+                })
+            })
+            .collect::<Result<_, UnknownError>>()?;
+
+        Ok(())
+    }
 }
 
 fn register_hooks(cpu: &mut dyn CpuBackend) -> Result<(), UnknownError> {
@@ -394,6 +394,41 @@ impl PeripheralsContainer {
         Self { interrupts }
     }
 
+    /// Snapshot of `(enabled, status, wakeup, assignment)` for each peripheral, in
+    /// [PeripheralId] order.
+    pub fn save_state(&self) -> Vec<(bool, bool, bool, Option<u8>)> {
+        self.interrupts
+            .values()
+            .map(|i| (i.enabled, i.status, i.wakeup, i.assignment.map(u8::from)))
+            .collect()
+    }
+
+    /// Restore state from [PeripheralsContainer::save_state()].
+    pub fn restore_state(
+        &mut self,
+        state: &[(bool, bool, bool, Option<u8>)],
+    ) -> Result<(), UnknownError> {
+        if state.len() != self.interrupts.len() {
+            return Err(anyhow!(
+                "saved system interrupt state has the wrong number of peripherals"
+            ));
+        }
+
+        for (interrupt, (enabled, status, wakeup, assignment)) in
+            self.interrupts.values_mut().zip(state.iter().copied())
+        {
+            interrupt.enabled = enabled;
+            interrupt.status = status;
+            interrupt.wakeup = wakeup;
+            interrupt.assignment = assignment
+                .map(Event::try_from)
+                .transpose()
+                .map_err(|_| anyhow!("invalid saved event assignment"))?;
+        }
+
+        Ok(())
+    }
+
     /// Iterate over peripherals that are in single [RoutingBank].
     fn bank_iter(
         &self,
@@ -7,7 +7,7 @@ use bilge::prelude::*;
 
 /// `DMAx_CONFIG` register.
 #[bitsize(15)]
-#[derive(TryFromBits, DefaultBits, DebugBits)]
+#[derive(TryFromBits, DefaultBits, DebugBits, Clone, Copy)]
 pub struct DmaConfig {
     pub enable: bool,
     pub direction: DmaDirection,
@@ -1,7 +1,11 @@
 // SPDX-License-Identifier: BSD-2-Clause
 use styx_core::prelude::*;
 
-use super::{id::DmaId, state::DmaState, DmaPeripheralMapping};
+use super::{
+    id::DmaId,
+    state::{DmaChannelState, DmaState},
+    DmaPeripheralMapping,
+};
 use enum_map::EnumMap;
 
 use crate::core_event_controller::SicHandle;
@@ -30,6 +34,44 @@ impl DmaContainer {
         state.pipe_new_data(mmu, ev, data)
     }
 
+    /// Take the next byte to send to a peripheral from its dma channel, if it is transmitting.
+    pub(super) fn pull_data(
+        &mut self,
+        mmu: &mut Mmu,
+        ev: &mut dyn EventControllerImpl,
+        peripheral: DmaPeripheralMapping,
+    ) -> Option<u8> {
+        self.state_from_peripheral_mapping(peripheral)
+            .pull_data(mmu, ev)
+    }
+
+    /// Is the dma channel mapped to `peripheral` enabled?
+    pub(super) fn is_enabled(&mut self, peripheral: DmaPeripheralMapping) -> bool {
+        self.state_from_peripheral_mapping(peripheral).enabled()
+    }
+
+    /// State of every channel, in [DmaId] order.
+    pub(super) fn save_state(&self) -> Vec<DmaChannelState> {
+        self.dma.values().map(DmaState::save_state).collect()
+    }
+
+    pub(super) fn restore_state(
+        &mut self,
+        states: Vec<DmaChannelState>,
+    ) -> Result<(), UnknownError> {
+        if states.len() != self.dma.len() {
+            return Err(anyhow!(
+                "saved {} dma channels, expected {}",
+                states.len(),
+                self.dma.len()
+            ));
+        }
+        for (channel, state) in self.dma.values_mut().zip(states) {
+            channel.restore_state(state)?;
+        }
+        Ok(())
+    }
+
     fn state_from_peripheral_mapping(&mut self, p: DmaPeripheralMapping) -> &mut DmaState {
         self.dma
             .values_mut()
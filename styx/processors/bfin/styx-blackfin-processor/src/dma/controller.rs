@@ -11,6 +11,7 @@ use derivative::Derivative;
 use futures::stream::BoxStream;
 use futures::FutureExt;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use tokio_stream::{StreamExt, StreamMap};
 use tracing::warn;
 
@@ -21,7 +22,7 @@ use styx_blackfin_sys::bf512 as sys;
 /// Handles the hooking of registers and passes on state management to a locked [DmaContainer].
 #[derive(Derivative)]
 pub(crate) struct DmaController {
-    dma: Mutex<DmaContainer>,
+    dma: Arc<Mutex<DmaContainer>>,
 
     /// Stream sources that must be polled to pass on to DMA channels.
     mapping: DmaSources,
@@ -30,10 +31,56 @@ pub(crate) struct DmaController {
 impl DmaController {
     pub fn new(system: SicHandle, mapping_sources: DmaSources) -> Self {
         Self {
-            dma: Mutex::new(DmaContainer::new(system)),
+            dma: Arc::new(Mutex::new(DmaContainer::new(system))),
             mapping: mapping_sources,
         }
     }
+
+    /// Get a handle for peripherals to move their data through their dma channels.
+    pub fn get_handle(&self) -> DmaHandle {
+        DmaHandle {
+            internal: self.dma.clone(),
+        }
+    }
+}
+
+/// Clonable handle to the dma channels for peripherals that drive their dma channel directly
+/// instead of through [DmaSources], e.g. because they need dma -> peripheral data as well.
+#[derive(Clone)]
+pub(crate) struct DmaHandle {
+    internal: Arc<Mutex<DmaContainer>>,
+}
+
+impl DmaHandle {
+    /// Is the dma channel mapped to `peripheral` enabled?
+    pub(crate) fn is_enabled(&self, peripheral: DmaPeripheralMapping) -> bool {
+        self.internal.lock().unwrap().is_enabled(peripheral)
+    }
+
+    /// Pass a byte received by `peripheral` to its dma channel.
+    pub(crate) fn receive(
+        &self,
+        mmu: &mut Mmu,
+        ev: &mut dyn EventControllerImpl,
+        peripheral: DmaPeripheralMapping,
+        data: u8,
+    ) {
+        self.internal
+            .lock()
+            .unwrap()
+            .pipe_new_data(mmu, ev, peripheral, data)
+    }
+
+    /// Take the next byte for `peripheral` to transmit from its dma channel, [None] if the
+    /// channel is not transmitting.
+    pub(crate) fn transmit(
+        &self,
+        mmu: &mut Mmu,
+        ev: &mut dyn EventControllerImpl,
+        peripheral: DmaPeripheralMapping,
+    ) -> Option<u8> {
+        self.internal.lock().unwrap().pull_data(mmu, ev, peripheral)
+    }
 }
 
 pub type DmaStream = BoxStream<'static, u8>;
@@ -86,6 +133,14 @@ impl Peripheral for DmaController {
     fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
         Ok(())
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.dma.lock().unwrap().save_state())?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.dma.lock().unwrap().restore_state(state.get()?)
+    }
 }
 
 fn get_data_u16(data: &[u8]) -> u16 {
@@ -130,7 +185,7 @@ fn dma_register_write_hook(
     let mut dma_container = controller.dma.lock().unwrap();
     let dma_channel = &mut dma_container.dma[dma_id];
     match mmr_offset {
-        mmr_offsets::CONFIG_OFFSET => dma_channel.set_config(get_data_u16(data)),
+        mmr_offsets::CONFIG_OFFSET => dma_channel.set_config(proc.mmu, get_data_u16(data)),
         mmr_offsets::X_COUNT_OFFSET => dma_channel.set_x_count(proc.mmu, get_data_u16(data)),
         mmr_offsets::Y_COUNT_OFFSET => dma_channel.set_y_count(proc.mmu, get_data_u16(data)),
         mmr_offsets::X_MODIFY_OFFSET => dma_channel.set_x_modify(get_data_u16(data)),
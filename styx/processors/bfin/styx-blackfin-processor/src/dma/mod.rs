@@ -4,22 +4,22 @@
 //! - manages peripheral DMA channels, including receiving data from mapped async sources and
 //!   register based configuration
 //! - interrupt on row/total completion as well as proper irq status
-//! - 2d mode support and updated DMAx_X_CURR and DMAx_Y_CURR registers.
+//! - linear and 2d mode support and updated DMAx_X_CURR and DMAx_Y_CURR registers.
+//! - 8, 16 and 32 bit words
+//! - peripheral transmit (memory read) through a [DmaHandle]
 //!
 //! # Interface
 //!
 //! The processor will only have to interact with the [DmaController] and the [DmaSources]. The
-//! [DmaSources] is used to map async [Stream](futures::stream::Stream)s to DMA channels. Currently
-//! this only supports the peripheral -> dma stream but in the future would contain a sender to
-//! facilitate dma -> peripheral communication.
+//! [DmaSources] is used to map async [Stream](futures::stream::Stream)s to DMA channels.
+//! Peripherals that also need dma -> peripheral communication (e.g. UART transmit) use a
+//! [DmaHandle] from [DmaController::get_handle()] instead.
 //!
 //! # Missing features
 //!
 //! - memory DMA
-//! - DMA transmit
 //! - many configuration values
 //! - memory descriptors
-//! - DMA run bit while running
 //!
 //! # Layout
 //!
@@ -42,5 +42,5 @@ mod state;
 
 use styx_blackfin_sys::bf512 as sys;
 
-pub(crate) use controller::{DmaController, DmaSources, DmaStream};
+pub(crate) use controller::{DmaController, DmaHandle, DmaSources, DmaStream};
 pub(crate) use peripheral_mapping::DmaPeripheralMapping;
@@ -2,6 +2,7 @@
 use std::fmt::Debug;
 
 use arbitrary_int::{u15, u4};
+use serde::{Deserialize, Serialize};
 use styx_core::prelude::*;
 use tap::Conv;
 use tracing::{trace, warn};
@@ -10,6 +11,21 @@ use crate::core_event_controller::SicHandle;
 
 use super::{config, id::DmaId, DmaPeripheralMapping};
 
+/// Saved registers and buffered data of a [DmaState].
+#[derive(Serialize, Deserialize)]
+pub(super) struct DmaChannelState {
+    status: u8,
+    config: u16,
+    x_modify: u16,
+    x_count: u16,
+    x_current: u16,
+    start_address: u32,
+    y_modify: u16,
+    y_count: u16,
+    y_current: u16,
+    internal_buffer: Vec<u8>,
+}
+
 /// Runtime state of a single DMA channel.
 ///
 /// DMA data and DMA register writes come to this struct which facilitates register
@@ -28,10 +44,8 @@ pub(super) struct DmaState {
     y_count: u16,
     y_current: u16,
     mapping: DmaPeripheralMapping,
-    /// Buffers incoming DMA bytes until we have a full word to write to memory.
-    ///
-    /// At the moment this could be an [Option] but we will have to support 4 byte words in the
-    /// future which will require a vec or similar data structure.
+    /// Buffers DMA bytes until we have a full word to write to memory, or the bytes of the last
+    /// word read from memory that have not been sent to the peripheral yet.
     internal_buffer: Vec<u8>,
 }
 
@@ -105,12 +119,24 @@ impl DmaState {
     /// Dynamically calculated and should be correct as long as current and count values are
     /// correct.
     fn current_address(&self) -> u32 {
-        if matches!(self.config.mode(), config::Mode::TwoDimensional) {
-            self.start_address
-                + (self.actual_x_current() * self.x_modify as u32)
-                + (self.actual_y_current() * self.y_modify as u32)
-        } else {
-            unimplemented!("Linear mode not implemented")
+        match self.config.mode() {
+            config::Mode::Linear => {
+                self.start_address + (self.actual_x_current() * self.x_modify as u32)
+            }
+            config::Mode::TwoDimensional => {
+                self.start_address
+                    + (self.actual_x_current() * self.x_modify as u32)
+                    + (self.actual_y_current() * self.y_modify as u32)
+            }
+        }
+    }
+
+    /// Number of bytes in a DMA word.
+    fn word_size_bytes(&self) -> usize {
+        match self.config.word_size() {
+            config::WordSize::EightBit => 1,
+            config::WordSize::SixteenBit => 2,
+            config::WordSize::ThirtyTwoBit => 4,
         }
     }
 
@@ -126,14 +152,13 @@ impl DmaState {
     /// This will decrement current counts by 1 and reset them to their reset count if they hit
     /// zero. Also triggers interrupt if a row/complete transfer is completed.
     fn decrement_counts(&mut self, mmu: &mut Mmu, ev: &mut dyn EventControllerImpl) {
-        assert_eq!(
-            self.config.mode(),
-            config::Mode::TwoDimensional,
-            "Linear mode not implemented."
-        );
         self.set_x_current(mmu, self.x_current - 1);
         if self.x_current == 0 {
             self.x_current = self.x_count;
+            if self.config.mode() == config::Mode::Linear {
+                self.completed_transfer(mmu, ev);
+                return;
+            }
             self.set_y_current(mmu, self.y_current - 1);
             self.completed_row(mmu, ev);
             if self.y_current == 0 {
@@ -147,6 +172,11 @@ impl DmaState {
     fn completed_transfer(&mut self, mmu: &mut Mmu, ev: &mut dyn EventControllerImpl) {
         trace!("dma {:?} completed transfer", self.id);
 
+        if let config::NextOperationFlow::Stop = self.config.flow() {
+            self.config.set_enable(false);
+            self.set_status_run(mmu, false);
+        }
+
         match self.config.mode() {
             config::Mode::Linear => self.trigger_interrupt(mmu, ev),
             config::Mode::TwoDimensional => match self.config.interrupt_timing() {
@@ -170,7 +200,7 @@ impl DmaState {
 
     /// Pass incoming data from the mapped peripheral to be handled by this dma channel.
     ///
-    /// Note: Only supports 16-bit word length.
+    /// Bytes are buffered until a full word is received and written to memory.
     pub(super) fn pipe_new_data(
         &mut self,
         mmu: &mut Mmu,
@@ -178,35 +208,77 @@ impl DmaState {
         data: u8,
     ) {
         // ignore new data if we're not enabled
-        if self.config.enable() {
-            if self.internal_buffer.is_empty() {
-                // no buffered data, save this byte for now
-                self.internal_buffer.push(data);
-            } else if self.internal_buffer.len() == 1 {
-                // we already have a byte! write and decrement current count.
-                self.internal_buffer.push(data);
-
-                mmu.data()
-                    .write(self.current_address() as u64)
-                    .bytes(&self.internal_buffer)
-                    .unwrap();
-
-                self.internal_buffer.clear();
-                self.decrement_counts(mmu, ev);
-            } else {
-                panic!("err");
-            }
+        if !self.config.enable() {
+            return;
+        }
+        if !matches!(self.config.direction(), config::DmaDirection::MemoryWrite) {
+            warn!("dma {:?} received data but is a memory read", self.id);
+            return;
+        }
+
+        self.internal_buffer.push(data);
+        if self.internal_buffer.len() == self.word_size_bytes() {
+            mmu.data()
+                .write(self.current_address() as u64)
+                .bytes(&self.internal_buffer)
+                .unwrap();
+
+            self.internal_buffer.clear();
+            self.decrement_counts(mmu, ev);
         }
     }
 
+    /// Take the next byte from memory to be sent to the mapped peripheral.
+    ///
+    /// A full word is read from memory at a time and its bytes are handed out lowest first.
+    /// Returns [None] if the channel is not enabled as a memory read.
+    pub(super) fn pull_data(
+        &mut self,
+        mmu: &mut Mmu,
+        ev: &mut dyn EventControllerImpl,
+    ) -> Option<u8> {
+        if !self.config.enable()
+            || !matches!(self.config.direction(), config::DmaDirection::MemoryRead)
+        {
+            return None;
+        }
+
+        if self.internal_buffer.is_empty() {
+            let mut word = vec![0u8; self.word_size_bytes()];
+            mmu.data()
+                .read(self.current_address() as u64)
+                .bytes(&mut word)
+                .unwrap();
+            // stored in reverse so bytes can be popped off in order
+            word.reverse();
+            self.internal_buffer = word;
+            self.decrement_counts(mmu, ev);
+        }
+
+        self.internal_buffer.pop()
+    }
+
+    /// Is this channel enabled in `DMAx_CONFIG`?
+    pub(super) fn enabled(&self) -> bool {
+        self.config.enable()
+    }
+
     /// A write to the config register.
-    pub(super) fn set_config(&mut self, config: u16) {
+    pub(super) fn set_config(&mut self, mmu: &mut Mmu, config: u16) {
         let config = u15::new(config);
         let dma_config = config::DmaConfig::try_from(config).expect("config is invalid!");
 
         self.debug_print_config("config", &dma_config);
 
+        let enable = dma_config.enable();
         self.config = dma_config;
+        self.internal_buffer.clear();
+        self.set_status_run(mmu, enable);
+    }
+
+    fn set_status_run(&mut self, mmu: &mut Mmu, run: bool) {
+        self.status.set_run(run);
+        self.update_status(mmu);
     }
 
     pub(super) fn set_x_count(&mut self, mmu: &mut Mmu, x_count: u16) {
@@ -241,6 +313,37 @@ impl DmaState {
         self.mapping
     }
 
+    pub(super) fn save_state(&self) -> DmaChannelState {
+        DmaChannelState {
+            status: self.status.conv::<u4>().value(),
+            config: self.config.conv::<u15>().value(),
+            x_modify: self.x_modify,
+            x_count: self.x_count,
+            x_current: self.x_current,
+            start_address: self.start_address,
+            y_modify: self.y_modify,
+            y_count: self.y_count,
+            y_current: self.y_current,
+            internal_buffer: self.internal_buffer.clone(),
+        }
+    }
+
+    /// Restore the channel, the memory mapped registers are restored with memory.
+    pub(super) fn restore_state(&mut self, state: DmaChannelState) -> Result<(), UnknownError> {
+        self.status = config::IrqStatus::from(u4::new(state.status));
+        self.config = config::DmaConfig::try_from(u15::new(state.config))
+            .map_err(|_| anyhow!("invalid saved dma {:?} config", self.id))?;
+        self.x_modify = state.x_modify;
+        self.x_count = state.x_count;
+        self.x_current = state.x_current;
+        self.start_address = state.start_address;
+        self.y_modify = state.y_modify;
+        self.y_count = state.y_count;
+        self.y_current = state.y_current;
+        self.internal_buffer = state.internal_buffer;
+        Ok(())
+    }
+
     fn debug_print_config(&self, parameter_name: &str, value: &impl Debug) {
         trace!("dma {:?} {parameter_name} set to {value:?}", self.id);
     }
@@ -2,11 +2,12 @@
 //! Blackfin processor.
 //!
 //! Currently has decent support for core and peripheral interrupts. Also has support for [DMA](dma),
-//! [SPORT](sport), and [timers] with some features missing.
+//! [SPORT](sport), [timers] and [UART](uart) with some features missing.
 mod core_event_controller;
 mod dma;
 mod sport;
 mod timers;
+mod uart;
 
 use dma::DmaController;
 use dma::{DmaPeripheralMapping, DmaSources};
@@ -18,6 +19,7 @@ use styx_core::cpu::PcodeBackend;
 use styx_core::memory::memory_region::MemoryRegion;
 use styx_core::memory::MemoryPermissions;
 use styx_core::prelude::*;
+use styx_peripherals::uart::UartController;
 
 use core_event_controller::CoreEventController;
 use timers::Timers;
@@ -86,7 +88,12 @@ impl ProcessorImpl for BlackfinBuilder {
             sin2,
         );
         let dma = Box::new(DmaController::new(cec.get_sic(), mapping));
+        let uarts = Box::new(UartController::new(uart::get_uarts(
+            cec.get_sic(),
+            dma.get_handle(),
+        )));
         peripherals.push(dma);
+        peripherals.push(uarts);
 
         let mut hints = LoaderHints::new();
         hints.insert(
@@ -1,47 +1,25 @@
 // SPDX-License-Identifier: BSD-2-Clause
 mod timer;
 
-use std::{
-    sync::atomic::{AtomicBool, Ordering},
-    time::Duration,
-};
-
 use derivative::Derivative;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use timer::*;
-use tokio::time;
 use tracing::{debug, warn};
 
 use styx_blackfin_sys::bf512 as sys;
 
 use crate::core_event_controller::SicHandle;
 
-#[derive(Clone, Default)]
-pub struct TimerLoopStatus {
-    triggered: Arc<AtomicBool>,
-}
-
 #[derive(Derivative)]
 pub struct Timers {
     timers: TimerContainer,
-    loop_status: TimerLoopStatus,
-    running: bool,
-}
-async fn timer_loop(status: TimerLoopStatus) {
-    let mut interval = time::interval(Duration::from_millis(100));
-    loop {
-        // sleep for our sleep time
-        interval.tick().await;
-        status.triggered.store(true, Ordering::Relaxed);
-    }
 }
 
 impl Timers {
     pub fn new(system: SicHandle) -> Self {
         Self {
             timers: TimerContainer::new(system),
-            loop_status: TimerLoopStatus::default(),
-            running: false,
         }
     }
 }
@@ -50,14 +28,6 @@ impl Peripheral for Timers {
     fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
         debug!("Timers init");
 
-        // let clock = self.weak_ref.upgrade().unwrap();
-
-        let status = self.loop_status.clone();
-        // start our stuff
-        proc.runtime
-            .handle()
-            .spawn(async move { timer_loop(status).await });
-
         proc.core.cpu.mem_write_hook(
             sys::TIMER0_CONFIG as u64,
             sys::TIMER_STATUS as u64,
@@ -76,17 +46,26 @@ impl Peripheral for Timers {
         _cpu: &mut dyn CpuBackend,
         mmu: &mut Mmu,
         ev: &mut dyn EventControllerImpl,
-        _delta: &styx_core::prelude::Delta,
+        delta: &styx_core::prelude::Delta,
     ) -> Result<(), UnknownError> {
-        if self.running && self.loop_status.triggered.load(Ordering::Relaxed) {
-            self.loop_status.triggered.store(false, Ordering::Relaxed);
-            for enabled_timer in self.timers.enabled_timers() {
-                // timer_went_off will latch proper peripheral
-                self.timers.timer_went_off(mmu, ev, enabled_timer);
-            }
+        for timer in self.timers.advance(delta.count) {
+            // timer_went_off will latch proper peripheral
+            self.timers.timer_went_off(mmu, ev, timer);
         }
         Ok(())
     }
+
+    fn next_deadline(&self) -> Option<u64> {
+        self.timers.next_deadline()
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.timers.save_state())?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.timers.restore_state(state.get()?)
+    }
 }
 
 fn timer_register_write_hook(
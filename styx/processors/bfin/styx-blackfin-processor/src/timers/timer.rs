@@ -3,12 +3,16 @@ use styx_core::prelude::*;
 
 use enum_map::{Enum, EnumMap};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
 use tracing::{debug, trace};
 
 use crate::core_event_controller::{PeripheralId, SicHandle};
 
 /// Timer configuration and runtime state.
 ///
+/// Enabled timers count executed instructions as `SCLK` cycles and go off every `period`
+/// cycles.
+///
 /// FIXME unimplemented features:
 /// - Errors (also in `TIMER_STATUS`)
 /// - `TIMER_COUNTER` is not updated nothing
@@ -26,6 +30,26 @@ struct TimerState {
     width: u32,
     // Timer interrupt status, `TIMILx` bit sourced from TIMER_STATUS.
     interrupt_status: bool,
+    // Cycles counted since the timer was enabled or last went off.
+    counter: u64,
+}
+
+impl TimerState {
+    /// Cycles until the timer goes off, `None` if it is stopped.
+    fn remaining(&self) -> Option<u64> {
+        (self.enabled && self.period > 0).then(|| (self.period as u64).saturating_sub(self.counter))
+    }
+}
+
+/// Saved [TimerState], the config is kept as the raw `TIMERx_CONFIG` value.
+#[derive(Serialize, Deserialize)]
+pub struct SavedTimer {
+    enabled: bool,
+    config: u16,
+    period: u32,
+    width: u32,
+    interrupt_status: bool,
+    counter: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, IntoPrimitive, TryFromPrimitive)]
@@ -70,7 +94,11 @@ impl TimerContainer {
         for (id, timer) in self.timers.iter() {
             let is_enabled = id.mask(timer_enabled_register);
             if is_enabled {
-                timer.lock().unwrap().enabled = true;
+                let mut timer = timer.lock().unwrap();
+                if !timer.enabled {
+                    timer.counter = 0;
+                }
+                timer.enabled = true;
                 debug!("timer {id:?} enabled");
             }
         }
@@ -87,14 +115,29 @@ impl TimerContainer {
         }
     }
 
-    /// Iterator over enabled timers.
-    pub fn enabled_timers(&self) -> impl Iterator<Item = TimerId> + use<> {
-        let a: Vec<_> = self
-            .timers
+    /// Count `cycles` on all enabled timers, returns the timers that went off.
+    pub fn advance(&self, cycles: u64) -> Vec<TimerId> {
+        self.timers
             .iter()
-            .filter_map(|(id, per)| per.lock().unwrap().enabled.then_some(id))
-            .collect();
-        a.into_iter()
+            .filter_map(|(id, timer)| {
+                let mut timer = timer.lock().unwrap();
+                timer.remaining()?;
+                timer.counter += cycles;
+                let period = timer.period as u64;
+                (timer.counter >= period).then(|| {
+                    timer.counter %= period;
+                    id
+                })
+            })
+            .collect()
+    }
+
+    /// Cycles until the next timer goes off, `None` if none are running.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.timers
+            .values()
+            .filter_map(|timer| timer.lock().unwrap().remaining())
+            .min()
     }
 
     /// Set configuration for timer from `TIMERx_CONFIG` register.
@@ -159,6 +202,45 @@ impl TimerContainer {
             timers: Default::default(),
         }
     }
+
+    /// State of every timer, in [TimerId] order.
+    pub fn save_state(&self) -> Vec<SavedTimer> {
+        self.timers
+            .values()
+            .map(|timer| {
+                let timer = timer.lock().unwrap();
+                SavedTimer {
+                    enabled: timer.enabled,
+                    config: timer.config.value(),
+                    period: timer.period,
+                    width: timer.width,
+                    interrupt_status: timer.interrupt_status,
+                    counter: timer.counter,
+                }
+            })
+            .collect()
+    }
+
+    pub fn restore_state(&self, saved: Vec<SavedTimer>) -> Result<(), UnknownError> {
+        if saved.len() != self.timers.len() {
+            return Err(anyhow!(
+                "saved {} timers, expected {}",
+                saved.len(),
+                self.timers.len()
+            ));
+        }
+        for (timer, saved) in self.timers.values().zip(saved) {
+            *timer.lock().unwrap() = TimerState {
+                enabled: saved.enabled,
+                config: timer_config::Config::from_config(saved.config),
+                period: saved.period,
+                width: saved.width,
+                interrupt_status: saved.interrupt_status,
+                counter: saved.counter,
+            };
+        }
+        Ok(())
+    }
 }
 
 mod timer_config {
@@ -315,6 +397,8 @@ mod timer_config {
 
     #[derive(Debug)]
     pub struct Config {
+        /// Raw `TIMERx_CONFIG` value the fields were parsed from.
+        value: u16,
         mode: Mode,
         pulse_hi: PulseHi,
         period_count: PeriodCount,
@@ -325,6 +409,7 @@ mod timer_config {
     impl Config {
         pub fn from_config(config: u16) -> Self {
             Self {
+                value: config,
                 mode: ConfigParameter::from_config(config),
                 pulse_hi: ConfigParameter::from_config(config),
                 period_count: ConfigParameter::from_config(config),
@@ -332,6 +417,10 @@ mod timer_config {
                 output_pad_disabled: ConfigParameter::from_config(config),
             }
         }
+
+        pub fn value(&self) -> u16 {
+            self.value
+        }
     }
 
     impl Default for Config {
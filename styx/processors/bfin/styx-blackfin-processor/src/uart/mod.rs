@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! UART emulation for the blackfin processor.
+//!
+//! Both UARTs are exposed through a [UartController] so clients can talk to them over the
+//! `UartPort` gRPC service, interface ids are `"0"` and `"1"`.
+//!
+//! The receive and transmit interrupts are the same system interrupts as the UART's DMA channels
+//! (e.g. `DMA8` and `DMA9` for UART0). When a UART's DMA channel is enabled `IER[ERBFI]` and
+//! `IER[ETBEI]` request DMA transfers instead and the system interrupt comes from the DMA channel.
+//!
+//! See [registers] for the modeled register behavior.
+use std::collections::VecDeque;
+
+use styx_core::hooks::{MemoryReadHook, MemoryWriteHook};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use styx_peripherals::uart::{IntoUartImpl, UartController, UartImpl, UartInterface};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use styx_blackfin_sys::bf512 as sys;
+
+use crate::core_event_controller::{PeripheralId, SicHandle};
+use crate::dma::{DmaHandle, DmaPeripheralMapping};
+
+mod registers;
+
+use registers::{UartRegisters, GCTL_OFFSET, IER_ERBFI, IER_ETBEI};
+
+/// Creates the interfaces for UART0 and UART1 to be added to a [UartController].
+pub(crate) fn get_uarts(sic: SicHandle, dma: DmaHandle) -> Vec<UartInterface> {
+    [UartPort::Zero, UartPort::One]
+        .into_iter()
+        .map(|port| {
+            UartInterface::new(
+                port.interface_id().to_owned(),
+                BlackfinUartBuilder {
+                    port,
+                    sic: sic.clone(),
+                    dma: dma.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Static info of a UART.
+#[derive(Debug, Clone, Copy)]
+enum UartPort {
+    Zero,
+    One,
+}
+
+impl UartPort {
+    fn interface_id(self) -> &'static str {
+        match self {
+            UartPort::Zero => "0",
+            UartPort::One => "1",
+        }
+    }
+
+    fn base_address(self) -> u64 {
+        match self {
+            UartPort::Zero => sys::UART0_THR as u64,
+            UartPort::One => sys::UART1_THR as u64,
+        }
+    }
+
+    fn receive_interrupt(self) -> PeripheralId {
+        match self {
+            UartPort::Zero => PeripheralId::DMA8,
+            UartPort::One => PeripheralId::DMA10,
+        }
+    }
+
+    fn transmit_interrupt(self) -> PeripheralId {
+        match self {
+            UartPort::Zero => PeripheralId::DMA9,
+            UartPort::One => PeripheralId::DMA11,
+        }
+    }
+
+    fn receive_dma(self) -> DmaPeripheralMapping {
+        match self {
+            UartPort::Zero => DmaPeripheralMapping::Uart0Receive,
+            UartPort::One => DmaPeripheralMapping::Uart1Receive,
+        }
+    }
+
+    fn transmit_dma(self) -> DmaPeripheralMapping {
+        match self {
+            UartPort::Zero => DmaPeripheralMapping::Uart0Transmit,
+            UartPort::One => DmaPeripheralMapping::Uart1Transmit,
+        }
+    }
+}
+
+struct BlackfinUartBuilder {
+    port: UartPort,
+    sic: SicHandle,
+    dma: DmaHandle,
+}
+
+impl IntoUartImpl for BlackfinUartBuilder {
+    fn new(
+        self,
+        mosi_rx: broadcast::Receiver<u8>,
+        miso_tx: broadcast::Sender<u8>,
+        _interface_id: String,
+    ) -> Result<Box<dyn UartImpl>, UnknownError> {
+        Ok(Box::new(BlackfinUart {
+            port: self.port,
+            registers: UartRegisters::default(),
+            buffer: VecDeque::new(),
+            receive_latched: false,
+            transmit_latched: false,
+            sic: self.sic,
+            dma: self.dma,
+            miso_stream: miso_tx,
+            mosi_stream: mosi_rx,
+        }))
+    }
+}
+
+/// A blackfin UART, connects the [UartRegisters] to the uart streams, the system interrupt
+/// controller and the UART's DMA channels.
+pub(crate) struct BlackfinUart {
+    port: UartPort,
+    registers: UartRegisters,
+    /// uart bytes that have come in from master but not received yet.
+    buffer: VecDeque<u8>,
+    /// Receive system interrupt is latched.
+    receive_latched: bool,
+    /// Transmit system interrupt is latched.
+    transmit_latched: bool,
+    sic: SicHandle,
+    dma: DmaHandle,
+    miso_stream: broadcast::Sender<u8>,
+    mosi_stream: broadcast::Receiver<u8>,
+}
+
+impl BlackfinUart {
+    /// checks uart mosi for bytes and gives to buffer
+    fn grab_bytes(&mut self) {
+        loop {
+            match self.mosi_stream.try_recv() {
+                Ok(data) => self.buffer.push_back(data),
+                Err(broadcast::error::TryRecvError::Empty) => break,
+                Err(broadcast::error::TryRecvError::Closed) => {
+                    warn!("uart mosi stream closed??");
+                    break;
+                }
+                Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                    warn!("uart mosi stream lagged {n} items");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn guest_transmit_data(&mut self, data: u8) {
+        debug!("uart{} transmit data {data:#x}", self.port.interface_id());
+        // an error means no one is listening, that's fine
+        let _ = self.miso_stream.send(data);
+    }
+
+    /// Receiving through DMA, `IER[ERBFI]` is set and the receive DMA channel is enabled.
+    fn receive_dma(&self) -> bool {
+        self.registers.interrupt_enabled(IER_ERBFI) && self.dma.is_enabled(self.port.receive_dma())
+    }
+
+    /// Transmitting through DMA, `IER[ETBEI]` is set and the transmit DMA channel is enabled.
+    fn transmit_dma(&self) -> bool {
+        self.registers.interrupt_enabled(IER_ETBEI) && self.dma.is_enabled(self.port.transmit_dma())
+    }
+
+    fn read_register(&mut self, offset: u32) -> u16 {
+        let value = self.registers.read(offset);
+        self.unlatch_inactive();
+        value
+    }
+
+    fn write_register(&mut self, offset: u32, value: u16) {
+        if let Some(data) = self.registers.write(offset, value) {
+            self.guest_transmit_data(data);
+        }
+        self.unlatch_inactive();
+    }
+
+    /// Unlatch system interrupts the UART no longer requests.
+    ///
+    /// Register hooks can only unlatch, new requests are latched on the next tick.
+    fn unlatch_inactive(&mut self) {
+        if self.receive_latched && !self.registers.receive_request() {
+            self.sic.unlatch_peripheral(self.port.receive_interrupt());
+            self.receive_latched = false;
+        }
+        if self.transmit_latched && !self.registers.transmit_request() {
+            self.sic.unlatch_peripheral(self.port.transmit_interrupt());
+            self.transmit_latched = false;
+        }
+    }
+
+    /// Latch system interrupts for new requests and unlatch the inactive ones.
+    fn update_interrupts(&mut self, mmu: &mut Mmu, ev: &mut dyn EventControllerImpl) {
+        // in DMA mode the DMA channel raises the system interrupt
+        let receive = self.registers.receive_request() && !self.receive_dma();
+        if receive != self.receive_latched {
+            let peripheral = self.port.receive_interrupt();
+            if receive {
+                self.sic.latch_peripheral(mmu, ev, peripheral);
+            } else {
+                self.sic.unlatch_peripheral(peripheral);
+            }
+            self.receive_latched = receive;
+        }
+
+        let transmit = self.registers.transmit_request() && !self.transmit_dma();
+        if transmit != self.transmit_latched {
+            let peripheral = self.port.transmit_interrupt();
+            if transmit {
+                self.sic.latch_peripheral(mmu, ev, peripheral);
+            } else {
+                self.sic.unlatch_peripheral(peripheral);
+            }
+            self.transmit_latched = transmit;
+        }
+    }
+}
+
+impl UartImpl for BlackfinUart {
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let base = self.port.base_address();
+        // registers are 16 bit
+        let end = base + GCTL_OFFSET as u64 + 1;
+        let hook = || UartHook {
+            interface_id: self.port.interface_id(),
+            base,
+        };
+        proc.core.cpu.mem_read_hook(base, end, Box::new(hook()))?;
+        proc.core.cpu.mem_write_hook(base, end, Box::new(hook()))?;
+        Ok(())
+    }
+
+    fn post_event_hook(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+    ) -> Result<(), UnknownError> {
+        self.update_interrupts(mmu, event_controller);
+        Ok(())
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+    ) -> Result<(), UnknownError> {
+        self.grab_bytes();
+
+        if self.registers.enabled() {
+            if self.receive_dma() {
+                for data in self.buffer.drain(..) {
+                    self.dma
+                        .receive(mmu, event_controller, self.port.receive_dma(), data);
+                }
+            } else if !self.registers.data_ready() {
+                if let Some(data) = self.buffer.pop_front() {
+                    self.registers.receive(data);
+                }
+            }
+
+            // one character per tick
+            if self.transmit_dma() {
+                if let Some(data) =
+                    self.dma
+                        .transmit(mmu, event_controller, self.port.transmit_dma())
+                {
+                    self.guest_transmit_data(data);
+                }
+            }
+        }
+
+        self.update_interrupts(mmu, event_controller);
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(
+            &self.registers,
+            &self.buffer,
+            self.receive_latched,
+            self.transmit_latched,
+        ))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        (
+            self.registers,
+            self.buffer,
+            self.receive_latched,
+            self.transmit_latched,
+        ) = state.get()?;
+        Ok(())
+    }
+}
+
+/// Register hook for one UART, registers are accessed through the [UartController].
+struct UartHook {
+    interface_id: &'static str,
+    base: u64,
+}
+
+impl UartHook {
+    fn uart<'a>(&self, proc: &'a mut CoreHandle) -> Result<&'a mut BlackfinUart, UnknownError> {
+        proc.event_controller
+            .peripherals
+            .get_expect::<UartController>()?
+            .try_get::<BlackfinUart>(self.interface_id)
+    }
+
+    /// Register offset and the bit offset of `address` in that register.
+    fn register_offset(&self, address: u64) -> (u32, u32) {
+        let offset = (address - self.base) as u32;
+        let register = offset & !0x3;
+        (register, (offset - register) * 8)
+    }
+}
+
+impl MemoryReadHook for UartHook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        _size: u32,
+        data: &mut [u8],
+    ) -> Result<(), UnknownError> {
+        let (register, shift) = self.register_offset(address);
+        let value = (self.uart(&mut proc)?.read_register(register) as u32) >> shift;
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = value.checked_shr(i as u32 * 8).unwrap_or_default() as u8;
+        }
+        Ok(())
+    }
+}
+
+impl MemoryWriteHook for UartHook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        _size: u32,
+        data: &[u8],
+    ) -> Result<(), UnknownError> {
+        let (register, shift) = self.register_offset(address);
+        let value = data.iter().enumerate().fold(0u32, |value, (i, byte)| {
+            value
+                | (*byte as u32)
+                    .checked_shl(shift + i as u32 * 8)
+                    .unwrap_or_default()
+        });
+        self.uart(&mut proc)?.write_register(register, value as u16);
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! UART register state, a 16450 compatible UART without FIFOs.
+//!
+//! Registers are 16 bits wide and spaced 4 bytes apart. `THR`, `RBR` and `DLL` share an address,
+//! as do `IER` and `DLH`, `LCR[DLAB]` selects the divisor latch.
+//!
+//! Transmission is instant so `LSR[THRE]` and `LSR[TEMT]` are always set. Line errors never
+//! happen, so the line status interrupt is never raised.
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+use styx_blackfin_sys::bf512 as sys;
+
+pub(super) const THR_RBR_DLL_OFFSET: u32 = 0;
+pub(super) const IER_DLH_OFFSET: u32 = sys::UART0_IER - sys::UART0_THR;
+pub(super) const IIR_OFFSET: u32 = sys::UART0_IIR - sys::UART0_THR;
+pub(super) const LCR_OFFSET: u32 = sys::UART0_LCR - sys::UART0_THR;
+pub(super) const MCR_OFFSET: u32 = sys::UART0_MCR - sys::UART0_THR;
+pub(super) const LSR_OFFSET: u32 = sys::UART0_LSR - sys::UART0_THR;
+pub(super) const SCR_OFFSET: u32 = sys::UART0_SCR - sys::UART0_THR;
+pub(super) const GCTL_OFFSET: u32 = sys::UART0_GCTL - sys::UART0_THR;
+
+/// Divisor latch access
+const LCR_DLAB: u16 = 0x80;
+
+/// Data ready
+const LSR_DR: u16 = 0x01;
+/// `THR` empty
+const LSR_THRE: u16 = 0x20;
+/// `THR` and transmit shift register empty
+const LSR_TEMT: u16 = 0x40;
+
+/// Receive buffer full interrupt (or DMA request) enable
+pub(super) const IER_ERBFI: u16 = 0x01;
+/// `THR` empty interrupt (or DMA request) enable
+pub(super) const IER_ETBEI: u16 = 0x02;
+/// Line status interrupt enable
+const IER_ELSI: u16 = 0x04;
+
+/// No interrupt pending
+const IIR_NINT: u16 = 0x01;
+const IIR_STATUS_RECEIVE: u16 = 0b10 << 1;
+const IIR_STATUS_TRANSMIT: u16 = 0b01 << 1;
+
+/// UART clock enable
+const GCTL_UCEN: u16 = 0x01;
+
+/// State of the UART registers.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct UartRegisters {
+    /// `DLH:DLL`
+    divisor: u16,
+    ier: u16,
+    lcr: u16,
+    mcr: u16,
+    scr: u16,
+    gctl: u16,
+    /// Character in `RBR`, `LSR[DR]` is set while there is one.
+    rbr: Option<u8>,
+    /// `THR` empty interrupt pending, cleared by writing `THR` or reading `IIR`.
+    thre_interrupt: bool,
+}
+
+impl UartRegisters {
+    /// `GCTL[UCEN]`, the UART ignores the line while its clock is disabled.
+    pub(super) fn enabled(&self) -> bool {
+        self.gctl & GCTL_UCEN != 0
+    }
+
+    fn dlab(&self) -> bool {
+        self.lcr & LCR_DLAB != 0
+    }
+
+    /// Is `bit` set in `IER`? Also the DMA request enables in DMA mode.
+    pub(super) fn interrupt_enabled(&self, bit: u16) -> bool {
+        self.ier & bit != 0
+    }
+
+    /// `LSR[DR]`
+    pub(super) fn data_ready(&self) -> bool {
+        self.rbr.is_some()
+    }
+
+    /// Place a received character in `RBR`.
+    pub(super) fn receive(&mut self, data: u8) {
+        trace!("uart received {data:#x}");
+        self.rbr = Some(data);
+    }
+
+    /// Receive interrupt request, data is ready and `IER[ERBFI]` is set.
+    pub(super) fn receive_request(&self) -> bool {
+        self.interrupt_enabled(IER_ERBFI) && self.data_ready()
+    }
+
+    /// Transmit interrupt request, `THR` is empty and `IER[ETBEI]` is set.
+    pub(super) fn transmit_request(&self) -> bool {
+        self.interrupt_enabled(IER_ETBEI) && self.thre_interrupt
+    }
+
+    fn line_status(&self) -> u16 {
+        let data_ready = if self.data_ready() { LSR_DR } else { 0 };
+        data_ready | LSR_THRE | LSR_TEMT
+    }
+
+    /// `IIR`, the highest priority pending interrupt.
+    fn interrupt_identification(&self) -> u16 {
+        if self.receive_request() {
+            IIR_STATUS_RECEIVE
+        } else if self.transmit_request() {
+            IIR_STATUS_TRANSMIT
+        } else {
+            IIR_NINT
+        }
+    }
+
+    /// Read the register at `offset` from the UART base.
+    ///
+    /// Reading `RBR` takes the received character and reading `IIR` clears a `THR` empty
+    /// interrupt it reports.
+    pub(super) fn read(&mut self, offset: u32) -> u16 {
+        match offset {
+            THR_RBR_DLL_OFFSET if self.dlab() => self.divisor & 0xFF,
+            THR_RBR_DLL_OFFSET => self.rbr.take().unwrap_or_default() as u16,
+            IER_DLH_OFFSET if self.dlab() => self.divisor >> 8,
+            IER_DLH_OFFSET => self.ier,
+            IIR_OFFSET => {
+                let iir = self.interrupt_identification();
+                if iir == IIR_STATUS_TRANSMIT {
+                    self.thre_interrupt = false;
+                }
+                iir
+            }
+            LCR_OFFSET => self.lcr,
+            MCR_OFFSET => self.mcr,
+            LSR_OFFSET => self.line_status(),
+            SCR_OFFSET => self.scr,
+            GCTL_OFFSET => self.gctl,
+            _ => {
+                trace!("read of reserved uart register {offset:#x}");
+                0
+            }
+        }
+    }
+
+    /// Write the register at `offset` from the UART base.
+    ///
+    /// Returns the character to transmit if `THR` was written.
+    pub(super) fn write(&mut self, offset: u32, value: u16) -> Option<u8> {
+        match offset {
+            THR_RBR_DLL_OFFSET if self.dlab() => {
+                self.divisor = (self.divisor & 0xFF00) | (value & 0xFF)
+            }
+            THR_RBR_DLL_OFFSET => {
+                if !self.enabled() {
+                    debug!("uart write to THR with UCEN clear: {value:#x}");
+                    return None;
+                }
+                // sent instantly, so THR is empty again
+                self.thre_interrupt = true;
+                return Some(value as u8);
+            }
+            IER_DLH_OFFSET if self.dlab() => {
+                self.divisor = (self.divisor & 0x00FF) | ((value & 0xFF) << 8)
+            }
+            IER_DLH_OFFSET => {
+                // enabling the THR empty interrupt while THR is empty raises it
+                if value & IER_ETBEI != 0 && !self.interrupt_enabled(IER_ETBEI) {
+                    self.thre_interrupt = true;
+                }
+                self.ier = value & (IER_ERBFI | IER_ETBEI | IER_ELSI);
+            }
+            LCR_OFFSET => self.lcr = value & 0xFF,
+            MCR_OFFSET => self.mcr = value & 0xFF,
+            SCR_OFFSET => self.scr = value & 0xFF,
+            GCTL_OFFSET => self.gctl = value,
+            _ => debug!("write to read only uart register {offset:#x}: {value:#x}"),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_uart() -> UartRegisters {
+        let mut uart = UartRegisters::default();
+        uart.write(GCTL_OFFSET, GCTL_UCEN);
+        uart
+    }
+
+    #[test]
+    fn test_divisor_latch() {
+        let mut uart = enabled_uart();
+        uart.write(LCR_OFFSET, LCR_DLAB | 0x3);
+        uart.write(THR_RBR_DLL_OFFSET, 0x34);
+        uart.write(IER_DLH_OFFSET, 0x12);
+        assert_eq!(0x34, uart.read(THR_RBR_DLL_OFFSET));
+        assert_eq!(0x12, uart.read(IER_DLH_OFFSET));
+        assert_eq!(0, uart.read(IER_DLH_OFFSET) & IER_ERBFI);
+
+        uart.write(LCR_OFFSET, 0x3);
+        assert_eq!(0, uart.read(IER_DLH_OFFSET));
+        assert_eq!(Some(b'a'), uart.write(THR_RBR_DLL_OFFSET, b'a' as u16));
+        assert_eq!(0x1234, uart.divisor);
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut uart = enabled_uart();
+        uart.write(IER_DLH_OFFSET, IER_ERBFI);
+        assert_eq!(LSR_THRE | LSR_TEMT, uart.read(LSR_OFFSET));
+        assert_eq!(IIR_NINT, uart.read(IIR_OFFSET));
+
+        uart.receive(b'x');
+        assert_eq!(LSR_DR, uart.read(LSR_OFFSET) & LSR_DR);
+        assert!(uart.receive_request());
+        assert_eq!(IIR_STATUS_RECEIVE, uart.read(IIR_OFFSET));
+
+        assert_eq!(b'x' as u16, uart.read(THR_RBR_DLL_OFFSET));
+        assert!(!uart.data_ready());
+        assert!(!uart.receive_request());
+        assert_eq!(IIR_NINT, uart.read(IIR_OFFSET));
+    }
+
+    #[test]
+    fn test_transmit_interrupt() {
+        let mut uart = UartRegisters::default();
+        // clock disabled, nothing is sent
+        assert_eq!(None, uart.write(THR_RBR_DLL_OFFSET, b'a' as u16));
+        uart.write(GCTL_OFFSET, GCTL_UCEN);
+
+        uart.write(IER_DLH_OFFSET, IER_ETBEI);
+        assert!(uart.transmit_request());
+        // received data is reported first
+        uart.receive(b'x');
+        uart.write(IER_DLH_OFFSET, IER_ETBEI | IER_ERBFI);
+        assert_eq!(IIR_STATUS_RECEIVE, uart.read(IIR_OFFSET));
+        uart.read(THR_RBR_DLL_OFFSET);
+
+        // reading IIR clears the THR empty interrupt
+        assert_eq!(IIR_STATUS_TRANSMIT, uart.read(IIR_OFFSET));
+        assert!(!uart.transmit_request());
+        assert_eq!(IIR_NINT, uart.read(IIR_OFFSET));
+
+        assert_eq!(Some(b'b'), uart.write(THR_RBR_DLL_OFFSET, b'b' as u16));
+        assert!(uart.transmit_request());
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Selecting the [Arch] and [ArchVariant] of a `cpu` node from its `compatible`.
+use styx_core::{
+    arch::{aarch64::Aarch64Variants, arm::ArmVariants, mips32::Mips32Variants},
+    prelude::*,
+};
+
+use crate::DeviceNode;
+
+/// The emulated core of a `cpu` node, see [CpuModel::from_node()].
+#[derive(Debug, Clone, Copy)]
+pub struct CpuModel {
+    pub arch: Arch,
+    pub variant: ArchVariant,
+    pub endian: ArchEndian,
+    /// Is the core an Armv6-M/Armv7-M/Armv8-M core? These fetch their initial stack pointer and
+    /// pc from the vector table.
+    pub m_profile: bool,
+}
+
+impl CpuModel {
+    /// Model for the first supported entry of the node's `compatible`.
+    ///
+    /// The core is little endian unless the node has a `big-endian` property, MIPS cores are big
+    /// endian unless it has a `little-endian` property.
+    pub fn from_node(cpu: &DeviceNode) -> Result<Self, UnknownError> {
+        let (arch, variant, m_profile) = cpu
+            .compatible()
+            .into_iter()
+            .find_map(variant)
+            .with_context(|| format!("{}: unsupported cpu {:?}", cpu.path(), cpu.compatible()))?;
+
+        let big_endian = if arch == Arch::Mips32 {
+            cpu.property("little-endian").is_none()
+        } else {
+            cpu.property("big-endian").is_some()
+        };
+        let endian = if big_endian {
+            ArchEndian::BigEndian
+        } else {
+            ArchEndian::LittleEndian
+        };
+
+        Ok(Self {
+            arch,
+            variant,
+            endian,
+            m_profile,
+        })
+    }
+}
+
+/// Arch, variant and M-profile-ness of a cpu compatible string.
+fn variant(compatible: &str) -> Option<(Arch, ArchVariant, bool)> {
+    let arm = |variant: ArmVariants| Some((Arch::Arm, variant.into(), false));
+    let arm_m = |variant: ArmVariants| Some((Arch::Arm, variant.into(), true));
+    let mips = |variant: Mips32Variants| Some((Arch::Mips32, variant.into(), false));
+
+    match compatible {
+        "arm,cortex-m0" | "arm,cortex-m0+" | "arm,cortex-m1" => arm_m(ArmVariants::ArmCortexM0),
+        "arm,cortex-m3" => arm_m(ArmVariants::ArmCortexM3),
+        "arm,cortex-m4" | "arm,cortex-m4f" => arm_m(ArmVariants::ArmCortexM4),
+        "arm,cortex-m7" => arm_m(ArmVariants::ArmCortexM7),
+        "arm,cortex-m33" | "arm,cortex-m33f" => arm_m(ArmVariants::ArmCortexM33),
+        "arm,cortex-r5" => arm(ArmVariants::ArmCortexR5),
+        "arm,cortex-r5f" => arm(ArmVariants::ArmCortexR5F),
+        "arm,cortex-a7" => arm(ArmVariants::ArmCortexA7),
+        "arm,cortex-a8" => arm(ArmVariants::ArmCortexA8),
+        "arm,cortex-a9" => arm(ArmVariants::ArmCortexA9),
+        "arm,cortex-a15" => arm(ArmVariants::ArmCortexA15),
+        "arm,arm926ej-s" => arm(ArmVariants::Arm926),
+        "arm,arm1176jzf-s" => arm(ArmVariants::Arm1176),
+        "arm,cortex-a53" | "arm,cortex-a55" | "arm,cortex-a57" | "arm,cortex-a72" | "arm,armv8" => {
+            Some((Arch::Aarch64, Aarch64Variants::Generic.into(), false))
+        }
+        "mti,mips4Kc" => mips(Mips32Variants::Mips324kc),
+        "mti,mips4KEc" => mips(Mips32Variants::Mips324kec),
+        "mti,mips24Kc" => mips(Mips32Variants::Mips3224kc),
+        "mti,mips74Kc" => mips(Mips32Variants::Mips3274kc),
+        _ => None,
+    }
+}
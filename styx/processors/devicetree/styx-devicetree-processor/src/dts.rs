@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Compiling device tree sources, the same way as `dt-stats` and the Zephyr build do.
+use std::{
+    ffi::OsStr,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use styx_core::prelude::*;
+use tracing::debug;
+
+/// Compile the device tree source at `path` to a blob.
+///
+/// The source is run through the C preprocessor first with every directory in `include_dirs` as a
+/// system include directory, Zephyr sources need `include`, `dts/common`, `dts/<arch>` and
+/// `dts` of the zephyr tree. Requires `cpp` and `dtc` on the `PATH`.
+pub fn compile_dts(
+    path: impl AsRef<Path>,
+    include_dirs: &[impl AsRef<Path>],
+) -> Result<Vec<u8>, UnknownError> {
+    let path = path.as_ref();
+
+    let mut preprocessor = Command::new("cpp")
+        .args(["-xassembler-with-cpp", "-nostdinc", "-undef", "-D__DTS__"])
+        .args(
+            include_dirs
+                .iter()
+                .flat_map(|dir| [OsStr::new("-isystem"), dir.as_ref().as_os_str()]),
+        )
+        .arg("-E")
+        .arg(path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| "failed to run cpp")?;
+
+    let preprocessed = preprocessor.stdout.take().unwrap();
+    let compiled = Command::new("dtc")
+        .args(["-Idts", "-Odtb"])
+        .current_dir(path.parent().unwrap_or(Path::new(".")))
+        .stdin(preprocessed)
+        .output()
+        .with_context(|| "failed to run dtc")?;
+
+    let preprocessor = preprocessor
+        .wait_with_output()
+        .with_context(|| "failed to run cpp")?;
+    if !preprocessor.status.success() {
+        return Err(anyhow!(
+            "cpp failed on {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&preprocessor.stderr)
+        ));
+    }
+    if !compiled.status.success() {
+        return Err(anyhow!(
+            "dtc failed on {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&compiled.stderr)
+        ));
+    }
+
+    if !compiled.stderr.is_empty() {
+        debug!(
+            "dtc warnings for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+    }
+    Ok(compiled.stdout)
+}
@@ -0,0 +1,556 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Device Tree Processor
+//!
+//! Builds a processor from a device tree instead of a hand written memory map, enough to stand up
+//! Zephyr boards from their DTS files without writing a processor crate:
+//!
+//! - The [core](CpuModel) is selected by the `compatible` of the first `cpu` node, or the one
+//!   picked with [DeviceTreeProcessor::with_cpu()].
+//! - Memories, the nodes with `device_type = "memory"` or compatible with `"mmio-sram"` or
+//!   `"soc-nv-flash"`, are mapped with all permissions.
+//! - The `reg` of every other node is mapped read/write, rounded out to 4KiB pages. Disabled
+//!   nodes are mapped too, firmware commonly touches their registers during clock setup.
+//! - The event controller comes from the first interrupt controller with a
+//!   [registered driver](registry), falling back to a [DummyEventController].
+//! - Enabled nodes with a registered [PeripheralDriver] get their peripherals, all others are
+//!   left as plain memory.
+//!
+//! M-profile cores get the private peripheral bus mapped and a [SysTickTimer]. If nothing is mapped at address 0 the
+//! flash in `/chosen/zephyr,flash` is aliased there, like the boot alias of most parts. The initial
+//! stack pointer and pc are then read from the vector table at 0. Other cores start at the
+//! loader's entry point.
+//!
+//! ```no_run
+//! use styx_core::prelude::*;
+//! use styx_devicetree_processor::DeviceTreeProcessor;
+//!
+//! let board = DeviceTreeProcessor::from_dts(
+//!     "zephyr/boards/st/nucleo_f401re/nucleo_f401re.dts",
+//!     &["zephyr/include", "zephyr/dts/arm", "zephyr/dts/common", "zephyr/dts"],
+//! )?;
+//! let proc = ProcessorBuilder::default()
+//!     .with_builder(board)
+//!     .with_target_program("zephyr.elf")
+//!     .build()?;
+//! # Ok::<(), UnknownError>(())
+//! ```
+mod cpu;
+mod dts;
+pub mod registry;
+mod tree;
+
+use std::path::{Path, PathBuf};
+
+use styx_core::{
+    arch::arm::ArmRegister,
+    core::builder::{BuildProcessorImplArgs, ProcessorImpl},
+    cpu::PcodeBackend,
+    event_controller::DummyEventController,
+    prelude::*,
+};
+use styx_nvic::SysTickTimer;
+use tracing::{debug, warn};
+
+pub use cpu::CpuModel;
+pub use dts::compile_dts;
+pub use registry::{
+    DeviceTreePeripherals, InterruptControllerDriver, PeripheralBuildFn, PeripheralDriver,
+};
+pub use tree::{DeviceNode, DeviceTree, InterruptSpecifier, Reg};
+
+/// MMIO windows are rounded out to this size.
+const PAGE_SIZE: u64 = 0x1000;
+
+/// The Armv6-M/Armv7-M/Armv8-M private peripheral bus, home of the NVIC and SysTick.
+const PRIVATE_PERIPHERAL_BUS: Reg = Reg {
+    address: 0xE000_0000,
+    size: 0x10_0000,
+};
+
+/// Compatible strings of memory nodes, besides `device_type = "memory"`.
+const MEMORY_COMPATIBLES: &[&str] = &["mmio-sram", "soc-nv-flash"];
+
+/// A processor described by a device tree, see the [crate documentation](crate).
+pub struct DeviceTreeProcessor {
+    tree: DeviceTree,
+    /// Index of the emulated core in [DeviceTree::cpus()].
+    cpu: usize,
+    /// Drivers added with [Self::with_peripheral()].
+    peripherals: Vec<(String, PeripheralBuildFn)>,
+}
+
+/// Config to build a [DeviceTreeProcessor] from a device tree file.
+#[derive(serde::Deserialize)]
+pub struct DeviceTreeConfig {
+    /// Device tree source, or blob if it ends in `.dtb`.
+    pub path: PathBuf,
+    /// Include directories for device tree sources.
+    #[serde(default)]
+    pub include_dirs: Vec<PathBuf>,
+    /// Index of the emulated core.
+    #[serde(default)]
+    pub cpu: usize,
+}
+
+impl DeviceTreeProcessor {
+    pub fn new(tree: DeviceTree) -> Self {
+        Self {
+            tree,
+            cpu: 0,
+            peripherals: Vec::new(),
+        }
+    }
+
+    /// Processor for a flattened device tree blob.
+    pub fn from_dtb(dtb: &[u8]) -> Result<Self, UnknownError> {
+        Ok(Self::new(DeviceTree::from_dtb(dtb)?))
+    }
+
+    /// Processor for a device tree source, see [compile_dts()].
+    pub fn from_dts(
+        path: impl AsRef<Path>,
+        include_dirs: &[impl AsRef<Path>],
+    ) -> Result<Self, UnknownError> {
+        Ok(Self::new(DeviceTree::from_dts(path, include_dirs)?))
+    }
+
+    /// Processor for a [DeviceTreeConfig].
+    pub fn from_config(config: DeviceTreeConfig) -> Result<Self, UnknownError> {
+        let processor = if config.path.extension().is_some_and(|ext| ext == "dtb") {
+            let dtb = std::fs::read(&config.path)
+                .with_context(|| format!("could not read {}", config.path.display()))?;
+            Self::from_dtb(&dtb)?
+        } else {
+            Self::from_dts(&config.path, &config.include_dirs)?
+        };
+        Ok(processor.with_cpu(config.cpu))
+    }
+
+    /// Emulate the `index`th `cpu` node instead of the first, e.g. to build every core of a board
+    /// for a [CoSimulation].
+    pub fn with_cpu(mut self, index: usize) -> Self {
+        self.cpu = index;
+        self
+    }
+
+    /// Build nodes compatible with `compatible` using `build`, before any registered
+    /// [PeripheralDriver].
+    pub fn with_peripheral(
+        mut self,
+        compatible: impl Into<String>,
+        build: PeripheralBuildFn,
+    ) -> Self {
+        self.peripherals.push((compatible.into(), build));
+        self
+    }
+
+    pub fn tree(&self) -> &DeviceTree {
+        &self.tree
+    }
+
+    /// Model of the emulated core.
+    pub fn cpu_model(&self) -> Result<CpuModel, UnknownError> {
+        let cpus = self.tree.cpus();
+        let cpu = cpus
+            .get(self.cpu)
+            .with_context(|| format!("device tree has no cpu {}", self.cpu))?;
+        CpuModel::from_node(cpu)
+    }
+
+    /// Memory node entries, merged where they overlap.
+    pub fn memories(&self) -> Vec<Reg> {
+        let memories = self
+            .tree
+            .nodes()
+            .filter(|node| is_memory(node) && node.enabled())
+            .flat_map(|node| memory_reg(node).to_vec())
+            .collect();
+        merge(memories, 0)
+    }
+
+    /// Register windows of all non memory nodes, rounded out to pages and merged.
+    ///
+    /// Windows overlapping memory are left out.
+    pub fn mmio_windows(&self) -> Result<Vec<Reg>, UnknownError> {
+        let mut windows: Vec<Reg> = self
+            .tree
+            .nodes()
+            .filter(|node| !is_memory(node))
+            .flat_map(|node| node.reg().to_vec())
+            .collect();
+        if self.cpu_model()?.m_profile {
+            windows.push(PRIVATE_PERIPHERAL_BUS);
+        }
+
+        let memories = self.memories();
+        let windows = merge(windows, PAGE_SIZE)
+            .into_iter()
+            .filter(|window| {
+                let overlaps = memories.iter().any(|memory| memory.overlaps(window));
+                if overlaps {
+                    warn!(
+                        "not mapping registers {:#x}..{:#x}, they overlap memory",
+                        window.address,
+                        window.end()
+                    );
+                }
+                !overlaps
+            })
+            .collect();
+        Ok(windows)
+    }
+
+    fn setup_address_space(&self, mmu: &mut Mmu, model: &CpuModel) -> Result<(), UnknownError> {
+        let mut regions = Vec::new();
+
+        for memory in self.memories() {
+            debug!("memory {:#x}..{:#x}", memory.address, memory.end());
+            regions.push(MemoryRegion::new(
+                memory.address,
+                memory.size,
+                MemoryPermissions::all(),
+            )?);
+        }
+
+        for window in self.mmio_windows()? {
+            debug!("registers {:#x}..{:#x}", window.address, window.end());
+            regions.push(MemoryRegion::new(
+                window.address,
+                window.size,
+                MemoryPermissions::RW,
+            )?);
+        }
+
+        if model.m_profile {
+            let flash = self
+                .tree
+                .chosen("zephyr,flash")
+                .and_then(|flash| memory_reg(flash).first().copied())
+                .and_then(|flash| regions.iter().find(|region| region.contains(flash.address)));
+            // the alias must not cover anything else
+            if let Some(flash) =
+                flash.filter(|flash| regions.iter().all(|region| region.base() >= flash.size()))
+            {
+                debug!("aliasing flash {:#x} at 0", flash.base());
+                let alias = flash.new_alias(0);
+                regions.push(alias);
+            }
+        }
+
+        for region in regions {
+            mmu.add_memory_region(region)?;
+        }
+
+        Ok(())
+    }
+
+    /// The event controller and the interrupt controller node it was built for.
+    fn event_controller(
+        &self,
+        backend: Backend,
+    ) -> Result<(Box<dyn EventControllerImpl>, DeviceTreePeripherals), UnknownError> {
+        let controller = self
+            .tree
+            .nodes()
+            .filter(|node| node.is_interrupt_controller() && node.enabled())
+            .find_map(|node| Some((node, InterruptControllerDriver::find(node)?)));
+
+        match controller {
+            Some((node, driver)) => {
+                debug!("event controller {}", node.path());
+                let event_controller = (driver.build)(node)
+                    .with_context(|| format!("could not build {}", node.path()))?;
+                let peripherals = DeviceTreePeripherals::new(
+                    backend,
+                    Some(node.path().to_owned()),
+                    driver.decode,
+                );
+                Ok((event_controller, peripherals))
+            }
+            None => {
+                warn!("no supported interrupt controller, interrupts are ignored");
+                let event_controller: Box<dyn EventControllerImpl> =
+                    Box::new(DummyEventController::default());
+                Ok((
+                    event_controller,
+                    DeviceTreePeripherals::new(backend, None, |_| None),
+                ))
+            }
+        }
+    }
+
+    /// Driver for the first of the node's `compatible` with one.
+    fn peripheral_driver(&self, node: &DeviceNode) -> Option<PeripheralBuildFn> {
+        node.compatible().into_iter().find_map(|compatible| {
+            self.peripherals
+                .iter()
+                .find(|(custom, _)| custom == compatible)
+                .map(|(_, build)| *build)
+                .or_else(|| PeripheralDriver::find(compatible).map(|driver| driver.build))
+        })
+    }
+}
+
+impl ProcessorImpl for DeviceTreeProcessor {
+    fn build(&self, args: &BuildProcessorImplArgs) -> Result<ProcessorBundle, UnknownError> {
+        let model = self.cpu_model()?;
+        debug!("device tree cpu {model:?}");
+
+        let cpu: Box<dyn CpuBackend> = match args.backend {
+            Backend::Pcode => Box::new(PcodeBackend::new_engine_config(
+                model.variant,
+                model.endian,
+                &args.into(),
+            )),
+            #[cfg(feature = "unicorn-backend")]
+            Backend::Unicorn => Box::new(styx_core::cpu::UnicornBackend::new_engine_exception(
+                model.arch,
+                model.variant,
+                model.endian,
+                args.exception,
+            )),
+            _ => return Err(BackendNotSupported(args.backend).into()),
+        };
+
+        let mut mmu = Mmu::default_region_store();
+
+        self.setup_address_space(&mut mmu, &model)?;
+
+        let (event_controller, mut peripherals) = self.event_controller(args.backend)?;
+        for node in self.tree.nodes().filter(|node| node.enabled()) {
+            match self.peripheral_driver(node) {
+                Some(build) => {
+                    debug!("peripheral {}", node.path());
+                    build(node, &mut peripherals)
+                        .with_context(|| format!("could not build {}", node.path()))?;
+                }
+                None if !node.compatible().is_empty() => {
+                    debug!("no driver for {} {:?}", node.path(), node.compatible());
+                }
+                None => (),
+            }
+        }
+        // the SysTick is part of the core, it has no node in most trees
+        if model.m_profile {
+            peripherals.add_peripheral(Box::new(SysTickTimer::new()));
+        }
+
+        let mut hints = LoaderHints::new();
+        hints.insert("arch".to_string().into_boxed_str(), Box::new(model.arch));
+
+        Ok(ProcessorBundle {
+            cpu,
+            mmu,
+            event_controller,
+            peripherals: peripherals.into_peripherals(),
+            loader_hints: hints,
+        })
+    }
+
+    fn init(&self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        if !self.cpu_model()?.m_profile {
+            return Ok(());
+        }
+
+        // an empty vector table keeps the loader's entry point
+        let sp = proc.core.mmu.read_u32_le_phys_data(0);
+        let pc = proc.core.mmu.read_u32_le_phys_data(4);
+        if let (Ok(sp), Ok(pc)) = (sp, pc) {
+            if pc != 0 {
+                proc.core.cpu.write_register(ArmRegister::Sp, sp)?;
+                proc.core.cpu.write_register(ArmRegister::Pc, pc)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_memory(node: &DeviceNode) -> bool {
+    node.property_str("device_type") == Some("memory")
+        || MEMORY_COMPATIBLES
+            .iter()
+            .any(|compatible| node.is_compatible(compatible))
+}
+
+/// Entries of a memory node.
+///
+/// Zephyr places flash under its flash controller, which has no `ranges`, so the untranslated
+/// entries are used for those.
+fn memory_reg(node: &DeviceNode) -> &[Reg] {
+    if node.reg().is_empty() && node.is_compatible("soc-nv-flash") {
+        node.raw_reg()
+    } else {
+        node.reg()
+    }
+}
+
+/// Sort and merge overlapping entries after rounding them out to `align`, 0 to leave them as is.
+fn merge(mut entries: Vec<Reg>, align: u64) -> Vec<Reg> {
+    if align != 0 {
+        for entry in entries.iter_mut() {
+            let start = entry.address & !(align - 1);
+            // saturates for a window ending in the last page
+            let end = entry
+                .end()
+                .checked_next_multiple_of(align)
+                .unwrap_or(u64::MAX);
+            *entry = Reg {
+                address: start,
+                size: end - start,
+            };
+        }
+    }
+    entries.retain(|entry| entry.size != 0);
+    entries.sort();
+
+    let mut merged: Vec<Reg> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match merged.last_mut() {
+            Some(last) if entry.address <= last.end() && (align != 0 || entry.overlaps(last)) => {
+                last.size = last.end().max(entry.end()) - last.address;
+            }
+            _ => merged.push(entry),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        sync::atomic::{AtomicI32, Ordering},
+    };
+
+    use super::*;
+
+    const NVIC: &str = "/soc/interrupt-controller@e000e100";
+
+    fn board() -> DeviceTreeProcessor {
+        let dtb = std::fs::read(resolve_test_bin("devicetree/board.dtb")).unwrap();
+        DeviceTreeProcessor::from_dtb(&dtb).unwrap()
+    }
+
+    fn reg(address: u64, size: u64) -> Reg {
+        Reg { address, size }
+    }
+
+    #[test]
+    fn test_tree() {
+        let board = board();
+        let tree = board.tree();
+
+        let cpus = tree.cpus();
+        assert_eq!(cpus.len(), 1);
+        assert!(cpus[0].is_compatible("arm,cortex-m4f"));
+
+        let uart = tree.chosen("zephyr,console").unwrap();
+        assert_eq!(uart.path(), "/soc/serial@40013800");
+        assert_eq!(tree.find("console").unwrap().path(), uart.path());
+        assert_eq!(uart.reg(), &[reg(0x4001_3800, 0x1000)]);
+        assert_eq!(
+            uart.interrupts(),
+            &[InterruptSpecifier {
+                controller: NVIC.to_owned(),
+                cells: vec![37, 0],
+            }]
+        );
+        assert!(uart.enabled());
+        assert!(!tree.find("/soc/serial@40004400").unwrap().enabled());
+
+        let i2c = tree.find("/soc/i2c@40005400").unwrap();
+        assert_eq!(i2c.interrupts().len(), 2);
+        assert_eq!(i2c.interrupts()[1].cells, vec![32, 0]);
+
+        // behind a bus with ranges
+        let timer = tree.find("/soc/bus@50000000/timer@1000").unwrap();
+        assert_eq!(timer.reg(), &[reg(0x5000_1000, 0x100)]);
+        assert_eq!(timer.raw_reg(), &[reg(0x1000, 0x100)]);
+
+        // not memory mapped
+        let sensor = tree.find("/soc/i2c@40005400/sensor@48").unwrap();
+        assert!(sensor.reg().is_empty());
+        assert_eq!(sensor.raw_reg(), &[reg(0x48, 0)]);
+        let partition = tree
+            .nodes()
+            .find(|node| node.name() == "partition@0")
+            .unwrap();
+        assert!(partition.reg().is_empty());
+
+        let flash = tree.chosen("zephyr,flash").unwrap();
+        assert!(flash.reg().is_empty());
+        assert_eq!(flash.raw_reg(), &[reg(0x0800_0000, 0x4_0000)]);
+    }
+
+    #[test]
+    fn test_memory_map() {
+        let board = board();
+
+        let model = board.cpu_model().unwrap();
+        assert_eq!(model.arch, Arch::Arm);
+        assert_eq!(model.endian, ArchEndian::LittleEndian);
+        assert!(model.m_profile);
+
+        assert_eq!(
+            board.memories(),
+            vec![reg(0x0800_0000, 0x4_0000), reg(0x2000_0000, 0x1_0000)]
+        );
+        assert_eq!(
+            board.mmio_windows().unwrap(),
+            vec![
+                // both the disabled uart and the i2c
+                reg(0x4000_4000, 0x2000),
+                reg(0x4001_3000, 0x2000),
+                reg(0x4002_2000, 0x1000),
+                reg(0x5000_1000, 0x1000),
+                // the nvic
+                PRIVATE_PERIPHERAL_BUS,
+            ]
+        );
+    }
+
+    static TIMER_IRQ: AtomicI32 = AtomicI32::new(-1);
+
+    fn build_test_timer(
+        node: &DeviceNode,
+        peripherals: &mut DeviceTreePeripherals,
+    ) -> Result<(), UnknownError> {
+        TIMER_IRQ.store(peripherals.irq(node)?, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Builds the board with a vector table at the start of flash, aliased at 0.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_build() {
+        #[rustfmt::skip]
+        let vector_table: &[u8] = &[
+            0x00, 0x10, 0x00, 0x20, // initial SP `0x2000_1000`
+            0x09, 0x00, 0x00, 0x08, // reset handler `0x0800_0008`
+        ];
+
+        let mut proc = ProcessorBuilder::default()
+            .with_builder(board().with_peripheral("styx,test-timer", build_test_timer))
+            .with_backend(Backend::Pcode)
+            .with_loader(RawLoader)
+            .with_input_bytes(Cow::Borrowed(vector_table))
+            .build()
+            .unwrap();
+
+        assert_eq!(TIMER_IRQ.load(Ordering::SeqCst), 40);
+        assert!(proc
+            .core
+            .event_controller
+            .peripherals
+            .get::<SysTickTimer>()
+            .is_some());
+        assert_eq!(
+            proc.core.mmu.read_u32_le_phys_data(0x0800_0004).unwrap(),
+            0x0800_0009
+        );
+        assert_eq!(
+            proc.core.cpu.read_register::<u32>(ArmRegister::Sp).unwrap(),
+            0x2000_1000
+        );
+    }
+}
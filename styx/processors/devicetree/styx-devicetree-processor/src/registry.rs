@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Drivers that turn device tree nodes into event controllers and [Peripheral]s.
+//!
+//! Drivers are matched on the node's `compatible` strings and registered at compile time with
+//! [`inventory::submit!()`], so any crate linked into the final binary can add boards' devices:
+//!
+//! ```ignore
+//! use styx_devicetree_processor::{DeviceNode, DeviceTreePeripherals, PeripheralDriver};
+//!
+//! fn build_my_uart(
+//!     node: &DeviceNode,
+//!     peripherals: &mut DeviceTreePeripherals,
+//! ) -> Result<(), UnknownError> {
+//!     let irqn = peripherals.irq(node)?;
+//!     peripherals.add_peripheral(Box::new(MyUart::new(node.reg()[0].address, irqn)));
+//!     Ok(())
+//! }
+//!
+//! inventory::submit! {
+//!     PeripheralDriver { compatible: &["vendor,my-uart"], build: build_my_uart }
+//! }
+//! ```
+//!
+//! Drivers for one processor only can be added with
+//! [DeviceTreeProcessor::with_peripheral()](crate::DeviceTreeProcessor::with_peripheral), these
+//! take precedence over registered ones.
+//!
+//! Built in are the NVIC, GICv2, GICv3, PL011 and the Armv8 generic timer.
+use styx_aarch64_processor::{pl011_interface, timer::GenericTimer};
+use styx_core::prelude::*;
+use styx_gic::{Gic, Gicv3};
+use styx_nvic::Nvic;
+use styx_peripherals::uart::{UartController, UartInterface};
+
+use crate::{DeviceNode, Reg};
+
+/// Creates the event controller of an interrupt controller node.
+pub type InterruptControllerBuildFn =
+    fn(&DeviceNode) -> Result<Box<dyn EventControllerImpl>, UnknownError>;
+
+/// Converts the cells of an interrupt specifier to the [ExceptionNumber] of the event controller,
+/// [None] if the specifier is invalid.
+pub type InterruptDecodeFn = fn(&[u32]) -> Option<ExceptionNumber>;
+
+/// Creates the peripherals of a node and adds them to the [DeviceTreePeripherals].
+pub type PeripheralBuildFn =
+    fn(&DeviceNode, &mut DeviceTreePeripherals) -> Result<(), UnknownError>;
+
+/// Driver for interrupt controller nodes.
+pub struct InterruptControllerDriver {
+    pub compatible: &'static [&'static str],
+    pub build: InterruptControllerBuildFn,
+    pub decode: InterruptDecodeFn,
+}
+
+inventory::collect!(InterruptControllerDriver);
+
+impl InterruptControllerDriver {
+    /// The registered driver for the first matching `compatible` of `node`.
+    pub fn find(node: &DeviceNode) -> Option<&'static Self> {
+        node.compatible().into_iter().find_map(|compatible| {
+            inventory::iter::<Self>().find(|driver| driver.compatible.contains(&compatible))
+        })
+    }
+}
+
+/// Driver for peripheral nodes.
+pub struct PeripheralDriver {
+    pub compatible: &'static [&'static str],
+    pub build: PeripheralBuildFn,
+}
+
+inventory::collect!(PeripheralDriver);
+
+impl PeripheralDriver {
+    /// The registered driver for `compatible`.
+    pub fn find(compatible: &str) -> Option<&'static Self> {
+        inventory::iter::<Self>().find(|driver| driver.compatible.contains(&compatible))
+    }
+}
+
+/// Peripherals created by the [PeripheralDriver]s.
+pub struct DeviceTreePeripherals {
+    backend: Backend,
+    /// Path of the interrupt controller backing the event controller.
+    controller: Option<String>,
+    decode: InterruptDecodeFn,
+    peripherals: Vec<Box<dyn Peripheral>>,
+    /// UARTs are collected into one [UartController], peripherals are looked up by type.
+    uarts: Vec<UartInterface>,
+}
+
+impl DeviceTreePeripherals {
+    pub(crate) fn new(
+        backend: Backend,
+        controller: Option<String>,
+        decode: InterruptDecodeFn,
+    ) -> Self {
+        Self {
+            backend,
+            controller,
+            decode,
+            peripherals: Vec::new(),
+            uarts: Vec::new(),
+        }
+    }
+
+    /// Backend of the processor being built.
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Exception numbers of the node's interrupts on the event controller.
+    ///
+    /// Interrupts routed to other controllers, e.g. GPIO controllers, are left out.
+    pub fn irqs(&self, node: &DeviceNode) -> Vec<ExceptionNumber> {
+        node.interrupts()
+            .iter()
+            .filter(|interrupt| Some(&interrupt.controller) == self.controller.as_ref())
+            .filter_map(|interrupt| (self.decode)(&interrupt.cells))
+            .collect()
+    }
+
+    /// Exception number of the node's first interrupt, see [Self::irqs()].
+    pub fn irq(&self, node: &DeviceNode) -> Result<ExceptionNumber, UnknownError> {
+        self.irqs(node)
+            .first()
+            .copied()
+            .with_context(|| format!("{}: no interrupt on the event controller", node.path()))
+    }
+
+    pub fn add_peripheral(&mut self, peripheral: Box<dyn Peripheral>) {
+        self.peripherals.push(peripheral);
+    }
+
+    /// Interface id for the next UART, UARTs are numbered from `"0"` in tree order.
+    pub fn next_uart_id(&self) -> String {
+        self.uarts.len().to_string()
+    }
+
+    pub fn add_uart(&mut self, uart: UartInterface) {
+        self.uarts.push(uart);
+    }
+
+    pub(crate) fn into_peripherals(mut self) -> Vec<Box<dyn Peripheral>> {
+        if !self.uarts.is_empty() {
+            self.peripherals
+                .push(Box::new(UartController::new(self.uarts)));
+        }
+        self.peripherals
+    }
+}
+
+/// First `reg` entry of the node.
+fn base(node: &DeviceNode) -> Result<u64, UnknownError> {
+    node.reg()
+        .first()
+        .map(|reg| reg.address)
+        .with_context(|| format!("{}: no memory mapped registers", node.path()))
+}
+
+fn build_nvic(_node: &DeviceNode) -> Result<Box<dyn EventControllerImpl>, UnknownError> {
+    Ok(Box::new(Nvic::default()))
+}
+
+/// `<irq priority>` or `<irq>`, external interrupt n is exception number n.
+fn decode_nvic(cells: &[u32]) -> Option<ExceptionNumber> {
+    cells.first().map(|&irq| irq as ExceptionNumber)
+}
+
+inventory::submit! {
+    InterruptControllerDriver {
+        compatible: &["arm,v6m-nvic", "arm,v7m-nvic", "arm,v8m-nvic", "arm,v8.1m-nvic"],
+        build: build_nvic,
+        decode: decode_nvic,
+    }
+}
+
+/// The distributor, redistributor and the memory mapped CPU interface are the first three `reg`
+/// entries.
+fn build_gicv3(node: &DeviceNode) -> Result<Box<dyn EventControllerImpl>, UnknownError> {
+    let [distributor, redistributor, cpu_interface, ..] = node.reg() else {
+        return Err(anyhow!(
+            "{}: GICv3 needs the distributor, redistributor and CPU interface in reg",
+            node.path()
+        ));
+    };
+    Ok(Box::new(Gicv3::new(
+        distributor.address,
+        redistributor.address,
+        cpu_interface.address,
+    )))
+}
+
+/// `<type number flags>`, the exception number is the INTID: 32 + n for SPI n and 16 + n for
+/// PPI n.
+fn decode_gic(cells: &[u32]) -> Option<ExceptionNumber> {
+    let number = *cells.get(1)? as ExceptionNumber;
+    match cells.first()? {
+        0 => Some(32 + number),
+        1 => Some(16 + number),
+        _ => None,
+    }
+}
+
+inventory::submit! {
+    InterruptControllerDriver {
+        compatible: &["arm,gic-v3"],
+        build: build_gicv3,
+        decode: decode_gic,
+    }
+}
+
+/// The distributor and the CPU interface are the first two `reg` entries. Exceptions are taken
+/// through the vector table at 0.
+fn build_gic(node: &DeviceNode) -> Result<Box<dyn EventControllerImpl>, UnknownError> {
+    let [distributor, cpu_interface, ..] = node.reg() else {
+        return Err(anyhow!(
+            "{}: GIC needs the distributor and CPU interface in reg",
+            node.path()
+        ));
+    };
+    let address = |reg: &Reg| {
+        u32::try_from(reg.address)
+            .with_context(|| format!("{}: GIC above 4GiB at {:#x}", node.path(), reg.address))
+    };
+
+    let gic = Gic::default();
+    gic.initialize_at(0, address(distributor)?, address(cpu_interface)?)?;
+    Ok(Box::new(gic))
+}
+
+inventory::submit! {
+    InterruptControllerDriver {
+        compatible: &["arm,cortex-a9-gic", "arm,cortex-a15-gic", "arm,gic-400"],
+        build: build_gic,
+        decode: decode_gic,
+    }
+}
+
+fn build_pl011(
+    node: &DeviceNode,
+    peripherals: &mut DeviceTreePeripherals,
+) -> Result<(), UnknownError> {
+    let base = base(node)?;
+    let irqn = peripherals.irq(node)?;
+    let id = peripherals.next_uart_id();
+    peripherals.add_uart(pl011_interface(id, base, irqn));
+    Ok(())
+}
+
+inventory::submit! {
+    PeripheralDriver { compatible: &["arm,pl011"], build: build_pl011 }
+}
+
+/// The generic timer uses the architectural PPIs, the node's interrupts are ignored.
+fn build_armv8_timer(
+    _node: &DeviceNode,
+    peripherals: &mut DeviceTreePeripherals,
+) -> Result<(), UnknownError> {
+    peripherals.add_peripheral(Box::new(GenericTimer::new(peripherals.backend())));
+    Ok(())
+}
+
+inventory::submit! {
+    PeripheralDriver { compatible: &["arm,armv8-timer"], build: build_armv8_timer }
+}
@@ -0,0 +1,582 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Owned view of a flattened device tree with the `reg` and `interrupts` properties resolved.
+//!
+//! Addresses in `reg` are translated to CPU addresses through the `ranges` of every ancestor bus.
+//! An empty `ranges` is an identity mapping and a missing one means the bus is not memory
+//! mapped (e.g. I2C or SPI), see [DeviceNode::reg()].
+//!
+//! Interrupt specifiers are split by the `#interrupt-cells` of their controller, which is found by
+//! following `interrupt-parent` or the node parents like Linux does. `interrupts-extended` is
+//! supported as well.
+use std::{collections::HashMap, path::Path};
+
+use fdt::{node::FdtNode, Fdt};
+use styx_core::prelude::*;
+use tracing::warn;
+
+/// Default `#address-cells` of a node without the property.
+const DEFAULT_ADDRESS_CELLS: u32 = 2;
+/// Default `#size-cells` of a node without the property.
+const DEFAULT_SIZE_CELLS: u32 = 1;
+
+/// One `(address, size)` entry of a `reg` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Reg {
+    pub address: u64,
+    pub size: u64,
+}
+
+impl Reg {
+    /// First address after the entry, saturated at the top of the address space.
+    pub fn end(&self) -> u64 {
+        self.address.saturating_add(self.size)
+    }
+
+    /// Do the two entries share any address?
+    pub fn overlaps(&self, other: &Reg) -> bool {
+        self.address < other.end() && other.address < self.end()
+    }
+}
+
+/// One interrupt of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterruptSpecifier {
+    /// Path of the interrupt controller.
+    pub controller: String,
+    /// The `#interrupt-cells` cells, their meaning depends on the controller.
+    pub cells: Vec<u32>,
+}
+
+/// One node of a [DeviceTree].
+#[derive(Debug, Clone)]
+pub struct DeviceNode {
+    path: String,
+    name: String,
+    parent: Option<usize>,
+    properties: Vec<(String, Vec<u8>)>,
+    raw_reg: Vec<Reg>,
+    reg: Vec<Reg>,
+    interrupts: Vec<InterruptSpecifier>,
+}
+
+impl DeviceNode {
+    /// Full path of the node, e.g. `/soc/serial@40013800`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Node name including the unit address, empty for the root.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Raw value of the property `name`.
+    pub fn property(&self, name: &str) -> Option<&[u8]> {
+        self.properties
+            .iter()
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// First cell of the property `name`.
+    pub fn property_u32(&self, name: &str) -> Option<u32> {
+        cells(self.property(name)?).first().copied()
+    }
+
+    /// The property `name` as a string, the first one for string lists.
+    pub fn property_str(&self, name: &str) -> Option<&str> {
+        strings(self.property(name)?).next()
+    }
+
+    /// Entries of `compatible`, most specific first.
+    pub fn compatible(&self) -> Vec<&str> {
+        self.property("compatible")
+            .map(|value| strings(value).collect())
+            .unwrap_or_default()
+    }
+
+    /// Is `compatible` one of the node's compatible strings?
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible().contains(&compatible)
+    }
+
+    /// Is the node enabled, `status` is missing, `"okay"` or `"ok"`.
+    pub fn enabled(&self) -> bool {
+        matches!(
+            self.property_str("status"),
+            None | Some("okay") | Some("ok")
+        )
+    }
+
+    /// Is the node an interrupt controller?
+    pub fn is_interrupt_controller(&self) -> bool {
+        self.property("interrupt-controller").is_some()
+    }
+
+    /// `reg` entries translated to CPU addresses.
+    ///
+    /// Entries that are not memory mapped, i.e. an ancestor bus has no `ranges` or they don't
+    /// cover the entry, are left out, as are entries with a size of 0.
+    pub fn reg(&self) -> &[Reg] {
+        &self.reg
+    }
+
+    /// `reg` entries in the address space of the parent bus.
+    pub fn raw_reg(&self) -> &[Reg] {
+        &self.raw_reg
+    }
+
+    /// Interrupts of the node, from `interrupts-extended` or `interrupts`.
+    pub fn interrupts(&self) -> &[InterruptSpecifier] {
+        &self.interrupts
+    }
+}
+
+/// A parsed device tree, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct DeviceTree {
+    /// Nodes in depth first order, the root is first.
+    nodes: Vec<DeviceNode>,
+    phandles: HashMap<u32, usize>,
+}
+
+impl DeviceTree {
+    /// Parse a flattened device tree blob.
+    pub fn from_dtb(dtb: &[u8]) -> Result<Self, UnknownError> {
+        let fdt = Fdt::new(dtb).map_err(|e| anyhow!("invalid device tree blob: {e}"))?;
+        let root = fdt
+            .find_node("/")
+            .with_context(|| "device tree has no root node")?;
+
+        let mut tree = DeviceTree {
+            nodes: Vec::new(),
+            phandles: HashMap::new(),
+        };
+        tree.add_node(root, None);
+
+        for (index, node) in tree.nodes.iter().enumerate() {
+            if let Some(phandle) = node
+                .property_u32("phandle")
+                .or_else(|| node.property_u32("linux,phandle"))
+            {
+                tree.phandles.insert(phandle, index);
+            }
+        }
+
+        for index in 0..tree.nodes.len() {
+            let raw_reg = tree.resolve_raw_reg(index);
+            let reg = raw_reg
+                .iter()
+                .filter(|reg| reg.size != 0)
+                .filter_map(|reg| {
+                    let address = tree.translate(index, reg.address)?;
+                    if address.checked_add(reg.size).is_none() {
+                        warn!(
+                            "{}: reg {address:#x} + {:#x} wraps the address space",
+                            tree.nodes[index].path, reg.size
+                        );
+                        return None;
+                    }
+                    Some(Reg {
+                        address,
+                        size: reg.size,
+                    })
+                })
+                .collect();
+            let interrupts = tree.resolve_interrupts(index);
+
+            let node = &mut tree.nodes[index];
+            node.raw_reg = raw_reg;
+            node.reg = reg;
+            node.interrupts = interrupts;
+        }
+
+        Ok(tree)
+    }
+
+    /// Compile a device tree source with `cpp` and `dtc` and parse it, see
+    /// [compile_dts()](crate::compile_dts).
+    pub fn from_dts(
+        path: impl AsRef<Path>,
+        include_dirs: &[impl AsRef<Path>],
+    ) -> Result<Self, UnknownError> {
+        let dtb = crate::compile_dts(path, include_dirs)?;
+        Self::from_dtb(&dtb)
+    }
+
+    /// All nodes in depth first order, the root is first.
+    pub fn nodes(&self) -> impl Iterator<Item = &DeviceNode> {
+        self.nodes.iter()
+    }
+
+    /// The node at `path`, or the node of the alias `path` when it does not start with `/`.
+    pub fn find(&self, path: &str) -> Option<&DeviceNode> {
+        let path = if path.starts_with('/') {
+            path
+        } else {
+            self.find("/aliases")?.property_str(path)?
+        };
+        self.nodes.iter().find(|node| node.path == path)
+    }
+
+    /// The node referenced by the property `name` of `/chosen`, e.g. `"zephyr,flash"`.
+    pub fn chosen(&self, name: &str) -> Option<&DeviceNode> {
+        let path = self.find("/chosen")?.property_str(name)?;
+        // linux adds arguments after a ':' to "stdout-path"
+        self.find(path.split(':').next().unwrap_or(path))
+    }
+
+    /// The `cpu` nodes under `/cpus`, in tree order.
+    pub fn cpus(&self) -> Vec<&DeviceNode> {
+        self.nodes
+            .iter()
+            .filter(|node| {
+                node.property_str("device_type") == Some("cpu")
+                    && node
+                        .parent
+                        .is_some_and(|parent| self.nodes[parent].path == "/cpus")
+            })
+            .collect()
+    }
+
+    /// The node with the given `phandle`.
+    pub fn phandle(&self, phandle: u32) -> Option<&DeviceNode> {
+        self.phandles.get(&phandle).map(|&index| &self.nodes[index])
+    }
+
+    fn add_node(&mut self, node: FdtNode, parent: Option<usize>) {
+        let path = match parent {
+            None => "/".to_owned(),
+            Some(0) => format!("/{}", node.name),
+            Some(parent) => format!("{}/{}", self.nodes[parent].path, node.name),
+        };
+        let name = if parent.is_some() {
+            node.name.to_owned()
+        } else {
+            String::new()
+        };
+        let properties = node
+            .properties()
+            .map(|property| (property.name.to_owned(), property.value.to_owned()))
+            .collect();
+
+        let index = self.nodes.len();
+        self.nodes.push(DeviceNode {
+            path,
+            name,
+            parent,
+            properties,
+            raw_reg: Vec::new(),
+            reg: Vec::new(),
+            interrupts: Vec::new(),
+        });
+
+        for child in node.children() {
+            self.add_node(child, Some(index));
+        }
+    }
+
+    fn address_cells(&self, index: usize) -> u32 {
+        self.nodes[index]
+            .property_u32("#address-cells")
+            .unwrap_or(DEFAULT_ADDRESS_CELLS)
+    }
+
+    fn size_cells(&self, index: usize) -> u32 {
+        self.nodes[index]
+            .property_u32("#size-cells")
+            .unwrap_or(DEFAULT_SIZE_CELLS)
+    }
+
+    /// `reg` of the node split with the cell counts of its parent.
+    fn resolve_raw_reg(&self, index: usize) -> Vec<Reg> {
+        let node = &self.nodes[index];
+        let (Some(parent), Some(reg)) = (node.parent, node.property("reg")) else {
+            return Vec::new();
+        };
+        let address_cells = self.address_cells(parent) as usize;
+        let size_cells = self.size_cells(parent) as usize;
+        if address_cells == 0 || address_cells > 2 || size_cells > 2 {
+            warn!(
+                "{}: unsupported reg with {address_cells} address and {size_cells} size cells",
+                node.path
+            );
+            return Vec::new();
+        }
+
+        cells(reg)
+            .chunks_exact(address_cells + size_cells)
+            .filter_map(|entry| {
+                let (address, size) = entry.split_at(address_cells);
+                let (address, size) = (join_cells(address), join_cells(size));
+                if address.checked_add(size).is_none() {
+                    warn!(
+                        "{}: reg {address:#x} + {size:#x} wraps the address space",
+                        node.path
+                    );
+                    return None;
+                }
+                Some(Reg { address, size })
+            })
+            .collect()
+    }
+
+    /// Translate `address` on the bus of node `index` to a CPU address.
+    fn translate(&self, index: usize, mut address: u64) -> Option<u64> {
+        let mut bus = self.nodes[index].parent?;
+        // the root's address space is the CPU's
+        while let Some(parent) = self.nodes[bus].parent {
+            let ranges = self.nodes[bus].property("ranges")?;
+            if !ranges.is_empty() {
+                let child_cells = self.address_cells(bus) as usize;
+                let parent_cells = self.address_cells(parent) as usize;
+                let size_cells = self.size_cells(bus) as usize;
+                let entry_cells = child_cells + parent_cells + size_cells;
+                if child_cells > 2 || parent_cells > 2 || size_cells > 2 || entry_cells == 0 {
+                    warn!(
+                        "{}: unsupported ranges with {child_cells} child, {parent_cells} parent \
+                         and {size_cells} size cells",
+                        self.nodes[bus].path
+                    );
+                    return None;
+                }
+
+                address = cells(ranges).chunks_exact(entry_cells).find_map(|range| {
+                    let (child, rest) = range.split_at(child_cells);
+                    let (parent, size) = rest.split_at(parent_cells);
+                    let child = join_cells(child);
+                    let offset = address.checked_sub(child)?;
+                    if offset >= join_cells(size) {
+                        return None;
+                    }
+                    let translated = join_cells(parent).checked_add(offset);
+                    if translated.is_none() {
+                        warn!(
+                            "{}: range at {:#x} + {offset:#x} wraps the address space",
+                            self.nodes[bus].path,
+                            join_cells(parent)
+                        );
+                    }
+                    translated
+                })?;
+            }
+            bus = parent;
+        }
+        Some(address)
+    }
+
+    /// The interrupt controller of node `index`.
+    fn interrupt_parent(&self, index: usize) -> Option<usize> {
+        let mut current = index;
+        // bounded in case of a phandle cycle
+        for _ in 0..self.nodes.len() {
+            let node = &self.nodes[current];
+            let next = match node.property_u32("interrupt-parent") {
+                Some(phandle) => *self.phandles.get(&phandle)?,
+                None => node.parent?,
+            };
+            if self.nodes[next].property("#interrupt-cells").is_some() {
+                return Some(next);
+            }
+            current = next;
+        }
+        None
+    }
+
+    fn resolve_interrupts(&self, index: usize) -> Vec<InterruptSpecifier> {
+        let node = &self.nodes[index];
+        let mut interrupts = Vec::new();
+
+        if let Some(extended) = node.property("interrupts-extended") {
+            let mut extended = cells(extended).into_iter();
+            while let Some(phandle) = extended.next() {
+                let Some(&controller) = self.phandles.get(&phandle) else {
+                    warn!("{}: unknown interrupt controller {phandle:#x}", node.path);
+                    break;
+                };
+                let count = self.nodes[controller]
+                    .property_u32("#interrupt-cells")
+                    .unwrap_or(1);
+                interrupts.push(InterruptSpecifier {
+                    controller: self.nodes[controller].path.clone(),
+                    cells: extended.by_ref().take(count as usize).collect(),
+                });
+            }
+        } else if let Some(cells_value) = node.property("interrupts") {
+            let Some(controller) = self.interrupt_parent(index) else {
+                warn!("{}: no interrupt controller", node.path);
+                return interrupts;
+            };
+            let count = self.nodes[controller]
+                .property_u32("#interrupt-cells")
+                .unwrap_or(1)
+                .max(1);
+            for specifier in cells(cells_value).chunks_exact(count as usize) {
+                interrupts.push(InterruptSpecifier {
+                    controller: self.nodes[controller].path.clone(),
+                    cells: specifier.to_vec(),
+                });
+            }
+        }
+
+        interrupts
+    }
+}
+
+/// Big endian cells of a property.
+fn cells(value: &[u8]) -> Vec<u32> {
+    value
+        .chunks_exact(4)
+        .map(|cell| u32::from_be_bytes(cell.try_into().unwrap()))
+        .collect()
+}
+
+/// Number made of one or two cells, most significant first.
+fn join_cells(cells: &[u32]) -> u64 {
+    cells
+        .iter()
+        .fold(0, |value, &cell| (value << 32) | cell as u64)
+}
+
+/// Null terminated strings of a property.
+fn strings(value: &[u8]) -> impl Iterator<Item = &str> {
+    value
+        .split(|&byte| byte == 0)
+        .filter(|string| !string.is_empty())
+        .filter_map(|string| std::str::from_utf8(string).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Node of a blob built by [dtb()].
+    struct Node(&'static str, Vec<(&'static str, Vec<u32>)>, Vec<Node>);
+
+    /// Flattened device tree blob of `root`, property values are cells.
+    fn dtb(root: &Node) -> Vec<u8> {
+        fn push_str(out: &mut Vec<u8>, string: &str) {
+            out.extend_from_slice(string.as_bytes());
+            out.push(0);
+            out.resize(out.len().next_multiple_of(4), 0);
+        }
+        fn add(node: &Node, structure: &mut Vec<u8>, strings: &mut Vec<u8>) {
+            structure.extend_from_slice(&1u32.to_be_bytes());
+            push_str(structure, node.0);
+            for (name, value) in &node.1 {
+                let offset = strings.len() as u32;
+                strings.extend_from_slice(name.as_bytes());
+                strings.push(0);
+                structure.extend_from_slice(&3u32.to_be_bytes());
+                structure.extend_from_slice(&(value.len() as u32 * 4).to_be_bytes());
+                structure.extend_from_slice(&offset.to_be_bytes());
+                for cell in value {
+                    structure.extend_from_slice(&cell.to_be_bytes());
+                }
+            }
+            for child in &node.2 {
+                add(child, structure, strings);
+            }
+            structure.extend_from_slice(&2u32.to_be_bytes());
+        }
+
+        let mut structure = Vec::new();
+        let mut strings = Vec::new();
+        add(root, &mut structure, &mut strings);
+        structure.extend_from_slice(&9u32.to_be_bytes());
+
+        // header, then an empty memory reservation block
+        let reservations = 40;
+        let structure_offset = reservations + 16;
+        let strings_offset = structure_offset + structure.len();
+        let total = strings_offset + strings.len();
+        let mut blob = Vec::with_capacity(total);
+        for field in [
+            0xd00d_feed,
+            total,
+            structure_offset,
+            strings_offset,
+            reservations,
+            17,
+            16,
+            0,
+            strings.len(),
+            structure.len(),
+        ] {
+            blob.extend_from_slice(&(field as u32).to_be_bytes());
+        }
+        blob.extend_from_slice(&[0; 16]);
+        blob.extend_from_slice(&structure);
+        blob.extend_from_slice(&strings);
+        blob
+    }
+
+    /// Entries wrapping the address space and zero cell `ranges` are skipped.
+    #[test]
+    fn test_malformed_reg() {
+        let root = Node(
+            "",
+            vec![("#address-cells", vec![2]), ("#size-cells", vec![2])],
+            vec![
+                Node(
+                    "huge",
+                    vec![("reg", vec![0xffff_ffff, 0xffff_ffff, 0, 0x10])],
+                    vec![],
+                ),
+                Node(
+                    "wrap",
+                    vec![
+                        ("#address-cells", vec![1]),
+                        ("#size-cells", vec![1]),
+                        ("ranges", vec![0, 0xffff_ffff, 0xffff_fff0, 0x100]),
+                    ],
+                    vec![Node("dev@10", vec![("reg", vec![0x10, 0x4])], vec![])],
+                ),
+                Node(
+                    "outer",
+                    vec![
+                        ("#address-cells", vec![0]),
+                        ("#size-cells", vec![0]),
+                        ("ranges", vec![]),
+                    ],
+                    vec![Node(
+                        "inner",
+                        vec![
+                            ("#address-cells", vec![0]),
+                            ("#size-cells", vec![0]),
+                            ("ranges", vec![1]),
+                        ],
+                        vec![Node(
+                            "bus",
+                            vec![
+                                ("#address-cells", vec![1]),
+                                ("#size-cells", vec![1]),
+                                ("ranges", vec![0, 0x100]),
+                            ],
+                            vec![Node("dev@0", vec![("reg", vec![0, 0x4])], vec![])],
+                        )],
+                    )],
+                ),
+            ],
+        );
+        let tree = DeviceTree::from_dtb(&dtb(&root)).unwrap();
+
+        let huge = tree.find("/huge").unwrap();
+        assert!(huge.raw_reg().is_empty());
+        assert!(huge.reg().is_empty());
+
+        let wrapped = tree.find("/wrap/dev@10").unwrap();
+        assert_eq!(
+            wrapped.raw_reg(),
+            &[Reg {
+                address: 0x10,
+                size: 0x4
+            }]
+        );
+        assert!(wrapped.reg().is_empty());
+
+        let nested = tree.find("/outer/inner/bus/dev@0").unwrap();
+        assert_eq!(nested.raw_reg().len(), 1);
+        assert!(nested.reg().is_empty());
+    }
+}
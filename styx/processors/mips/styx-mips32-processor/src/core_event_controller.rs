@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! MIPS32 coprocessor 0 exception handling.
+//!
+//! Exceptions are delivered like the processor does in compatibility mode: the interrupted pc is
+//! saved to `EPC`, `Cause[ExcCode]` is set, `Status[EXL]` is set and execution continues at the
+//! general exception vector. The vector base is `0xBFC00200` while `Status[BEV]` is set and
+//! `EBase` otherwise, interrupts use offset `0x200` when `Cause[IV]` is set and every other
+//! exception uses offset `0x180`. `eret` clears `Status[EXL]` and returns to `EPC` in the pcode.
+//!
+//! Exception numbers:
+//!
+//!  Number          | Exception
+//! ----------------------------------------------------------
+//!  0-1             | software interrupts `IP0`, `IP1`
+//!  2-7             | hardware interrupts `IP2`-`IP7`
+//!  [SYSCALL_INTNO] | `syscall`
+//!  [BREAK_INTNO]   | `break`
+//!  [TRAP_INTNO]    | `teq`, `tge`, ...
+//!
+//! Hardware interrupts are level sensitive. Peripherals latch their line on every tick while it
+//! is asserted and the latched lines are copied to `Cause[IP]` before each check, the timer
+//! interrupt `Cause[TI]` is routed to `IP7`. Synchronous exceptions are raised by the backend
+//! through the interrupt hook with the pc at the faulting instruction, their numbers match the
+//! QEMU exception numbers so both backends agree.
+//!
+//! Vectored and external interrupt controller modes, branch delay slots (`Cause[BD]`) and the
+//! TLB are not modeled.
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use styx_core::arch::mips32::Mips32Register;
+use styx_core::event_controller::{
+    ActivateIRQnError, Exception, InterruptExecuted, OptionalFeatureError, Peripherals,
+};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::{debug, trace};
+
+/// `syscall` instruction, QEMU's `EXCP_SYSCALL`.
+pub const SYSCALL_INTNO: ExceptionNumber = 17;
+/// `break` instruction, QEMU's `EXCP_BREAK`.
+pub const BREAK_INTNO: ExceptionNumber = 18;
+/// Conditional trap instructions, QEMU's `EXCP_TRAP`.
+pub const TRAP_INTNO: ExceptionNumber = 22;
+
+/// Hardware interrupt raised by `Cause[TI]`.
+pub const TIMER_IRQN: ExceptionNumber = 7;
+
+/// `Status[IE]`, interrupts are enabled.
+pub(crate) const STATUS_IE: u32 = 1 << 0;
+/// `Status[EXL]`, an exception is being handled.
+pub(crate) const STATUS_EXL: u32 = 1 << 1;
+/// `Status[ERL]`, an error is being handled.
+const STATUS_ERL: u32 = 1 << 2;
+/// `Status[IM]`, the interrupt mask.
+const STATUS_IM_SHIFT: u32 = 8;
+/// `Status[BEV]`, exceptions use the boot vectors.
+pub(crate) const STATUS_BEV: u32 = 1 << 22;
+
+/// `Cause[ExcCode]`
+const CAUSE_EXC_CODE_SHIFT: u32 = 2;
+const CAUSE_EXC_CODE_MASK: u32 = 0x1F << CAUSE_EXC_CODE_SHIFT;
+/// `Cause[IP]`, the pending interrupts.
+const CAUSE_IP_SHIFT: u32 = 8;
+/// `Cause[IP7..IP2]`, driven by the hardware lines.
+const CAUSE_HARDWARE_IP_MASK: u32 = 0xFC << CAUSE_IP_SHIFT;
+/// `Cause[IV]`, interrupts use the special interrupt vector.
+const CAUSE_IV: u32 = 1 << 23;
+/// `Cause[TI]`, the count/compare timer interrupt is pending.
+pub(crate) const CAUSE_TI: u32 = 1 << 30;
+
+/// Boot exception vector base while `Status[BEV]` is set.
+const BOOT_VECTOR_BASE: u64 = 0xBFC0_0200;
+/// `EBase[Exception Base]`
+const EBASE_MASK: u32 = 0xFFFF_F000;
+const GENERAL_VECTOR_OFFSET: u64 = 0x180;
+const INTERRUPT_VECTOR_OFFSET: u64 = 0x200;
+
+/// `Cause[ExcCode]` values.
+const EXC_CODE_INT: u32 = 0;
+const EXC_CODE_SYS: u32 = 8;
+const EXC_CODE_BP: u32 = 9;
+const EXC_CODE_TR: u32 = 13;
+
+/// Interrupts selectable by `Status[IM]`.
+const NUM_INTERRUPTS: ExceptionNumber = 8;
+
+/// Name and `Cause[ExcCode]` of the synchronous exceptions.
+const SYNCHRONOUS_EXCEPTIONS: [(ExceptionNumber, &str, u32); 3] = [
+    (SYSCALL_INTNO, "Sys", EXC_CODE_SYS),
+    (BREAK_INTNO, "Bp", EXC_CODE_BP),
+    (TRAP_INTNO, "Tr", EXC_CODE_TR),
+];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CoreEventController {
+    /// Bitmask of hardware lines latched since the last tick.
+    lines: u8,
+    /// Exception being handled, until `Status[EXL]` is cleared.
+    current: Option<ExceptionNumber>,
+    /// `Cause[IP]` had a pending interrupt at the last check, masked or not.
+    pending: bool,
+}
+
+impl CoreEventController {
+    /// Highest pending interrupt that `status` allows in `cause`.
+    fn next_pending(status: u32, cause: u32) -> Option<ExceptionNumber> {
+        if status & STATUS_IE == 0 || status & (STATUS_EXL | STATUS_ERL) != 0 {
+            return None;
+        }
+
+        let pending = ((status >> STATUS_IM_SHIFT) & (cause >> CAUSE_IP_SHIFT)) as u8;
+        (pending != 0).then(|| 7 - pending.leading_zeros() as ExceptionNumber)
+    }
+
+    /// `Cause` with `IP7..IP2` set from the latched lines and `Cause[TI]`.
+    fn update_cause(&self, cause: u32) -> u32 {
+        let mut lines = self.lines as u32;
+        if cause & CAUSE_TI != 0 {
+            lines |= 1 << TIMER_IRQN;
+        }
+        (cause & !CAUSE_HARDWARE_IP_MASK) | ((lines << CAUSE_IP_SHIFT) & CAUSE_HARDWARE_IP_MASK)
+    }
+
+    /// Take exception `number` with `exc_code` to the vector at `offset`.
+    fn enter_exception(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        number: ExceptionNumber,
+        exc_code: u32,
+        offset: u64,
+    ) -> Result<(), UnknownError> {
+        let status = cpu.read_register::<u32>(Mips32Register::Status)?;
+        let cause = cpu.read_register::<u32>(Mips32Register::Cause)?;
+        let old_pc = cpu.pc()?;
+
+        // nested exceptions keep the original return address
+        if status & STATUS_EXL == 0 {
+            cpu.write_register(Mips32Register::Epc, old_pc as u32)?;
+        }
+        let cause = (cause & !CAUSE_EXC_CODE_MASK) | (exc_code << CAUSE_EXC_CODE_SHIFT);
+        cpu.write_register(Mips32Register::Cause, cause)?;
+        cpu.write_register(Mips32Register::Status, status | STATUS_EXL)?;
+
+        let base = if status & STATUS_BEV != 0 {
+            BOOT_VECTOR_BASE
+        } else {
+            (cpu.read_register::<u32>(Mips32Register::EBase)? & EBASE_MASK) as u64
+        };
+        let new_pc = base + offset;
+        trace!("Setting PC to {new_pc:#08X}, (exception {number}, ExcCode {exc_code})");
+
+        // emit `styx_trace` interrupt ISR entry event
+        strace!(InterruptEvent {
+            etype: TraceEventType::INTERRUPT,
+            old_pc: old_pc as u32,
+            new_pc: new_pc as u32,
+            interrupt_num: number,
+            interrupt_type: InterruptType::IsrEntry,
+            ..Default::default()
+        });
+
+        cpu.set_pc(new_pc)?;
+        self.current = Some(number);
+        Ok(())
+    }
+
+    /// Take interrupt `irqn` whatever the masks.
+    fn take_interrupt(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        irqn: ExceptionNumber,
+    ) -> Result<(), UnknownError> {
+        let cause = cpu.read_register::<u32>(Mips32Register::Cause)?;
+        let offset = if cause & CAUSE_IV != 0 {
+            INTERRUPT_VECTOR_OFFSET
+        } else {
+            GENERAL_VECTOR_OFFSET
+        };
+        self.enter_exception(cpu, irqn, EXC_CODE_INT, offset)
+    }
+}
+
+impl EventControllerImpl for CoreEventController {
+    fn next(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        let status = cpu.read_register::<u32>(Mips32Register::Status)?;
+        if status & STATUS_EXL == 0 {
+            self.current = None;
+        }
+
+        let cause = self.update_cause(cpu.read_register::<u32>(Mips32Register::Cause)?);
+        cpu.write_register(Mips32Register::Cause, cause)?;
+        self.pending = (cause >> CAUSE_IP_SHIFT) as u8 != 0;
+
+        let Some(irqn) = Self::next_pending(status, cause) else {
+            return Ok(InterruptExecuted::NotExecuted);
+        };
+        debug!("taking interrupt {irqn}");
+        self.take_interrupt(cpu, irqn)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+        if !(2..NUM_INTERRUPTS).contains(&event) {
+            return Err(ActivateIRQnError::InvalidIRQn(event));
+        }
+
+        self.lines |= 1 << event;
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        irq: ExceptionNumber,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Result<InterruptExecuted, ActivateIRQnError> {
+        if (0..NUM_INTERRUPTS).contains(&irq) {
+            self.take_interrupt(cpu, irq)?;
+            return Ok(InterruptExecuted::Executed);
+        }
+
+        let Some((_, _, exc_code)) = SYNCHRONOUS_EXCEPTIONS
+            .iter()
+            .find(|(number, _, _)| *number == irq)
+        else {
+            return Err(ActivateIRQnError::InvalidIRQn(irq));
+        };
+        self.enter_exception(cpu, irq, *exc_code, GENERAL_VECTOR_OFFSET)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    /// Clears the latched lines, peripherals latch them again while they are asserted.
+    fn tick(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.lines = 0;
+        Ok(())
+    }
+
+    fn finish_interrupt(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Option<ExceptionNumber> {
+        // eret is handled entirely by the pcode
+        self.current.take()
+    }
+
+    /// `wait` ends on any pending interrupt, even a masked one.
+    fn has_pending_events(&self) -> bool {
+        self.pending
+    }
+
+    fn init(&mut self, cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        cpu.intr_hook(Box::new(interrupt_hook))?;
+        Ok(())
+    }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.lines = 0;
+        self.current = None;
+        self.pending = false;
+        Ok(())
+    }
+
+    fn current_exception(&mut self) -> Result<Option<Exception>, OptionalFeatureError> {
+        Ok(self.current.map(|number| Exception {
+            name: exception_name(number),
+            number,
+        }))
+    }
+
+    fn available_exceptions(&mut self) -> Result<Cow<'_, [Exception]>, OptionalFeatureError> {
+        Ok((0..NUM_INTERRUPTS)
+            .chain(SYNCHRONOUS_EXCEPTIONS.iter().map(|(number, _, _)| *number))
+            .map(|number| Exception {
+                name: exception_name(number),
+                number,
+            })
+            .collect())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(self)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        *self = state.get()?;
+        Ok(())
+    }
+}
+
+fn exception_name(number: ExceptionNumber) -> Cow<'static, str> {
+    match SYNCHRONOUS_EXCEPTIONS
+        .iter()
+        .find(|(synchronous, _, _)| *synchronous == number)
+    {
+        Some((_, name, _)) => Cow::Borrowed(name),
+        None => format!("IP{number}").into(),
+    }
+}
+
+/// Catches exceptions from the cpu backend and sends them to the event controller.
+fn interrupt_hook(proc: CoreHandle, intno: ExceptionNumber) -> Result<(), UnknownError> {
+    debug!("caught exception: {intno}, pc: {:#x}", proc.cpu.pc()?);
+
+    proc.event_controller.execute(intno, proc.cpu, proc.mmu)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENABLED: u32 = STATUS_IE | (0xFF << STATUS_IM_SHIFT);
+
+    #[test]
+    fn test_priority() {
+        let mut controller = CoreEventController::default();
+        let cause = controller.update_cause(0);
+        assert_eq!(None, CoreEventController::next_pending(ENABLED, cause));
+
+        controller.latch(3).unwrap();
+        controller.latch(5).unwrap();
+        let cause = controller.update_cause(0);
+        assert_eq!(Some(5), CoreEventController::next_pending(ENABLED, cause));
+        // masked by IM5 and by EXL
+        let status = ENABLED & !(1 << (STATUS_IM_SHIFT + 5));
+        assert_eq!(Some(3), CoreEventController::next_pending(status, cause));
+        assert_eq!(
+            None,
+            CoreEventController::next_pending(ENABLED | STATUS_EXL, cause)
+        );
+
+        // the timer is IP7 and software interrupts are kept
+        let cause = controller.update_cause(CAUSE_TI | (1 << CAUSE_IP_SHIFT));
+        assert_eq!(
+            CAUSE_TI | (0xAA << CAUSE_IP_SHIFT),
+            cause & (CAUSE_TI | (0xFF << CAUSE_IP_SHIFT))
+        );
+        assert_eq!(
+            Some(TIMER_IRQN),
+            CoreEventController::next_pending(ENABLED, cause)
+        );
+
+        // lines that are not latched again are cleared
+        controller.lines = 0;
+        assert_eq!(0, controller.update_cause(0xFC << CAUSE_IP_SHIFT));
+
+        assert!(controller.latch(1).is_err());
+        assert!(controller.latch(8).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! MIPS32 Processor
+//!
+//! Models a single M4K/24K class core on the QEMU "mipssim" machine, enough to boot bare-metal
+//! and small RTOS images. Has support for [coprocessor 0 exceptions](core_event_controller), the
+//! [count/compare timer](timer) and a [16550 UART](uart16550).
+//!
+//! Memory map:
+//!
+//!  Start      | Size   | Description
+//! ----------------------------------------------------------
+//!  0x00000000 | 128MiB | RAM
+//!  0x1FC00000 | 1MiB   | boot ROM, the reset vector is at its base
+//!  0x1FD00000 | 64KiB  | ISA I/O ports, the UART is at port 0x3F8
+//!
+//! There is no TLB, the unmapped segments are aliases of the physical addresses: kseg0
+//! (`0x80000000`) for RAM and the boot ROM and kseg1 (`0xA0000000`) for all of them.
+//!
+//! The core comes out of reset with `Status[BEV]` and `Status[ERL]` set. Execution starts at the
+//! reset vector (`0xBFC00000`) when a boot ROM image was loaded, otherwise at the pc set by the
+//! loader.
+//!
+//! Coprocessor 0 exceptions, interrupts and the count/compare timer are pcode only. Unicorn only
+//! exposes the `Status` coprocessor 0 register, with it the processor has a
+//! [DummyEventController] and no timer, the UART can only be polled.
+pub mod core_event_controller;
+pub mod timer;
+pub mod uart16550;
+
+use core_event_controller::{CoreEventController, STATUS_BEV};
+use styx_core::{
+    arch::mips32::{Mips32Register, Mips32Variants},
+    core::builder::{BuildProcessorImplArgs, ProcessorImpl},
+    cpu::PcodeBackend,
+    event_controller::DummyEventController,
+    prelude::*,
+};
+use styx_peripherals::uart::UartController;
+use timer::CountCompareTimer;
+use tracing::debug;
+
+pub use core_event_controller::{BREAK_INTNO, SYSCALL_INTNO, TIMER_IRQN, TRAP_INTNO};
+pub use uart16550::{UART_BASE, UART_IRQN, UART_KSEG1_BASE};
+
+/// Base of the unmapped cached segment.
+const KSEG0_BASE: u64 = 0x8000_0000;
+/// Base of the unmapped uncached segment.
+const KSEG1_BASE: u64 = 0xA000_0000;
+
+const BOOT_ROM_BASE: u64 = 0x1FC0_0000;
+/// Physical address of the ISA I/O ports.
+const ISA_IO_BASE: u64 = 0x1FD0_0000;
+/// Address of the ISA I/O ports in kseg1.
+const ISA_IO_KSEG1_BASE: u64 = KSEG1_BASE + ISA_IO_BASE;
+
+/// The reset vector, the base of the boot ROM in kseg1.
+const RESET_VECTOR: u64 = KSEG1_BASE + BOOT_ROM_BASE;
+
+/// `Status[ERL]`, set at reset.
+const STATUS_ERL: u32 = 1 << 2;
+/// `EBase` at reset, the base of kseg0.
+const EBASE_RESET: u32 = KSEG0_BASE as u32;
+
+/// The "mipssim" machine, see the [crate documentation](crate).
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct Mips32Builder {
+    pub variant: Mips32Variants,
+    pub endian: ArchEndian,
+}
+
+impl Default for Mips32Builder {
+    fn default() -> Self {
+        Self {
+            variant: Mips32Variants::Mips3224kf,
+            endian: ArchEndian::BigEndian,
+        }
+    }
+}
+
+impl Mips32Builder {
+    fn setup_address_space(&self, mmu: &mut Mmu) -> Result<(), UnknownError> {
+        let mut regions = Vec::new();
+
+        let ram_start = 0x0000_0000;
+        let ram_size = 0x800_0000;
+        let ram = MemoryRegion::new(ram_start, ram_size, MemoryPermissions::all())?;
+        regions.push(ram.new_alias(KSEG0_BASE + ram_start));
+        regions.push(ram.new_alias(KSEG1_BASE + ram_start));
+        regions.push(ram);
+
+        let boot_rom_start = BOOT_ROM_BASE;
+        let boot_rom_size = 0x10_0000;
+        let boot_rom = MemoryRegion::new(boot_rom_start, boot_rom_size, MemoryPermissions::all())?;
+        regions.push(boot_rom.new_alias(KSEG0_BASE + boot_rom_start));
+        regions.push(boot_rom.new_alias(KSEG1_BASE + boot_rom_start));
+        regions.push(boot_rom);
+
+        let isa_io_start = ISA_IO_BASE;
+        let isa_io_size = 0x1_0000;
+        let isa_io = MemoryRegion::new(isa_io_start, isa_io_size, MemoryPermissions::RW)?;
+        regions.push(isa_io.new_alias(ISA_IO_KSEG1_BASE));
+        regions.push(isa_io);
+
+        for region in regions {
+            mmu.add_memory_region(region)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ProcessorImpl for Mips32Builder {
+    fn build(&self, args: &BuildProcessorImplArgs) -> Result<ProcessorBundle, UnknownError> {
+        let cpu: Box<dyn CpuBackend> = match args.backend {
+            Backend::Pcode => Box::new(PcodeBackend::new_engine_config(
+                self.variant,
+                self.endian,
+                &args.into(),
+            )),
+            #[cfg(feature = "unicorn-backend")]
+            Backend::Unicorn => Box::new(styx_core::cpu::UnicornBackend::new_engine_exception(
+                Arch::Mips32,
+                self.variant,
+                self.endian,
+                args.exception,
+            )),
+            _ => return Err(BackendNotSupported(args.backend).into()),
+        };
+
+        let mut mmu = Mmu::default_region_store();
+
+        self.setup_address_space(&mut mmu)?;
+
+        let mut peripherals: Vec<Box<dyn Peripheral>> =
+            vec![Box::new(UartController::new(uart16550::get_uarts()))];
+        // unicorn does not expose Cause, EPC, Count, Compare etc.
+        let event_controller: Box<dyn EventControllerImpl> = if args.backend == Backend::Pcode {
+            peripherals.push(Box::new(CountCompareTimer::default()));
+            Box::new(CoreEventController::default())
+        } else {
+            Box::new(DummyEventController::default())
+        };
+
+        let mut hints = LoaderHints::new();
+        hints.insert("arch".to_string().into_boxed_str(), Box::new(Arch::Mips32));
+
+        Ok(ProcessorBundle {
+            cpu,
+            mmu,
+            event_controller,
+            peripherals,
+            loader_hints: hints,
+        })
+    }
+
+    fn init(&self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let cp0 = proc
+            .core
+            .event_controller
+            .get_impl::<CoreEventController>()
+            .is_ok();
+        let cpu = proc.core.cpu.as_mut();
+        cpu.write_register(Mips32Register::Status, STATUS_BEV | STATUS_ERL)?;
+        if cp0 {
+            cpu.write_register(Mips32Register::EBase, EBASE_RESET)?;
+        }
+
+        // a loaded boot ROM takes over from the loader entry point
+        let reset = proc.core.mmu.code().read(RESET_VECTOR).le().u32()?;
+        if reset != 0 {
+            debug!("starting at reset vector {RESET_VECTOR:#X}");
+            proc.core.cpu.set_pc(RESET_VECTOR)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core_event_controller::STATUS_EXL;
+
+    /// Build the processor with `program` at `0x1000` and `handler` at the general exception
+    /// vector in kseg0.
+    fn processor(program: &[u32], handler: &[u32]) -> Processor {
+        let mut proc = ProcessorBuilder::default()
+            .with_builder(Mips32Builder::default())
+            .with_backend(Backend::Pcode)
+            .build()
+            .unwrap();
+
+        let mmu = &mut proc.core.mmu;
+        for (address, words) in [(0x1000, program), (0x8000_0180, handler)] {
+            let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+            mmu.write_code(address, &bytes).unwrap();
+        }
+        proc.core.set_pc(0x1000).unwrap();
+        proc
+    }
+
+    /// Takes a system call to the handler at `EBase + 0x180` and returns after it with `eret`.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_syscall() {
+        let program: &[u32] = &[
+            0x40806000, // mtc0 zero, Status
+            0x0000000C, // syscall
+            0x34090001, // ori t1, zero, 1
+            0x1000FFFF, // b .
+            0x00000000, // nop
+        ];
+        let handler: &[u32] = &[
+            0x40087000, // mfc0 t0, EPC
+            0x25080004, // addiu t0, t0, 4
+            0x40887000, // mtc0 t0, EPC
+            0x42000018, // eret
+        ];
+        let mut proc = processor(program, handler);
+
+        proc.run(10).unwrap();
+
+        let cpu = proc.core.cpu.as_mut();
+        assert_eq!(1, cpu.read_register::<u32>(Mips32Register::T1).unwrap());
+        assert_eq!(
+            0x1008,
+            cpu.read_register::<u32>(Mips32Register::Epc).unwrap()
+        );
+        // ExcCode Sys
+        let cause = cpu.read_register::<u32>(Mips32Register::Cause).unwrap();
+        assert_eq!(8, (cause >> 2) & 0x1F);
+        let status = cpu.read_register::<u32>(Mips32Register::Status).unwrap();
+        assert_eq!(0, status & STATUS_EXL);
+    }
+
+    /// The timer interrupt is taken once `Count` reaches `Compare`.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_timer_interrupt() {
+        let program: &[u32] = &[
+            0x34080010, // ori t0, zero, 0x10
+            0x40885800, // mtc0 t0, Compare
+            0x34088001, // ori t0, zero, 0x8001
+            0x40886000, // mtc0 t0, Status
+            0x1000FFFF, // b .
+            0x00000000, // nop
+        ];
+        let handler: &[u32] = &[
+            0x34090001, // ori t1, zero, 1
+            0x40805800, // mtc0 zero, Compare
+            0x1000FFFF, // b .
+            0x00000000, // nop
+        ];
+        let mut proc = processor(program, handler);
+
+        proc.run(3000).unwrap();
+
+        let cpu = proc.core.cpu.as_mut();
+        assert_eq!(1, cpu.read_register::<u32>(Mips32Register::T1).unwrap());
+        // ExcCode Int, TI cleared by the Compare write
+        let cause = cpu.read_register::<u32>(Mips32Register::Cause).unwrap();
+        assert_eq!(0, (cause >> 2) & 0x1F);
+        assert_eq!(0, cause & core_event_controller::CAUSE_TI);
+        let epc = cpu.read_register::<u32>(Mips32Register::Epc).unwrap();
+        assert!((0x1010..=0x1014).contains(&epc));
+        assert!(cpu.read_register::<u32>(Mips32Register::Count).unwrap() >= 0x10);
+        let status = cpu.read_register::<u32>(Mips32Register::Status).unwrap();
+        assert_eq!(STATUS_EXL, status & STATUS_EXL);
+    }
+
+    /// `wait` skips ahead to the timer interrupt instead of falling through.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_wait_for_timer_interrupt() {
+        let program: &[u32] = &[
+            0x34081000, // ori t0, zero, 0x1000
+            0x40885800, // mtc0 t0, Compare
+            0x34088001, // ori t0, zero, 0x8001
+            0x40886000, // mtc0 t0, Status
+            0x42000020, // wait
+            0x340A0002, // ori t2, zero, 2
+            0x1000FFFF, // b .
+            0x00000000, // nop
+        ];
+        let handler: &[u32] = &[
+            0x34090001, // ori t1, zero, 1
+            0x40805800, // mtc0 zero, Compare
+            0x1000FFFF, // b .
+            0x00000000, // nop
+        ];
+        let mut proc = processor(program, handler);
+
+        proc.run(10000).unwrap();
+
+        let cpu = proc.core.cpu.as_mut();
+        assert_eq!(1, cpu.read_register::<u32>(Mips32Register::T1).unwrap());
+        // the interrupt was taken right after the wait
+        assert_eq!(0, cpu.read_register::<u32>(Mips32Register::T2).unwrap());
+        assert_eq!(
+            0x1014,
+            cpu.read_register::<u32>(Mips32Register::Epc).unwrap()
+        );
+        assert!(cpu.read_register::<u32>(Mips32Register::Count).unwrap() >= 0x1000);
+    }
+
+    /// Unicorn runs plain code, with a dummy event controller and no timer.
+    #[test]
+    #[cfg(feature = "unicorn-backend")]
+    #[cfg_attr(miri, ignore)]
+    fn test_unicorn_backend() {
+        let mut proc = ProcessorBuilder::default()
+            .with_builder(Mips32Builder::default())
+            .with_backend(Backend::Unicorn)
+            .build()
+            .unwrap();
+        assert!(proc
+            .core
+            .event_controller
+            .get_impl::<DummyEventController>()
+            .is_ok());
+
+        let program: &[u32] = &[
+            0x34090001, // ori t1, zero, 1
+            0x1000FFFF, // b .
+            0x00000000, // nop
+        ];
+        let bytes: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+        proc.core.mmu.write_code(0x1000, &bytes).unwrap();
+        proc.core.set_pc(0x1000).unwrap();
+
+        proc.run(10).unwrap();
+
+        let cpu = proc.core.cpu.as_mut();
+        assert_eq!(1, cpu.read_register::<u32>(Mips32Register::T1).unwrap());
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Coprocessor 0 count/compare timer
+//!
+//! `Count` increments once every two executed instructions unless `Cause[DC]` is set. When it
+//! reaches `Compare` the timer interrupt is flagged in `Cause[TI]` and raised on
+//! [`TIMER_IRQN`](crate::TIMER_IRQN) by the [event controller](crate::core_event_controller).
+//! Writing `Compare` clears `Cause[TI]`.
+//!
+//! Both registers are coprocessor 0 registers of the backend, `Count` is read back on every tick
+//! so software can write it.
+use serde::{Deserialize, Serialize};
+use styx_core::arch::mips32::Mips32Register;
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::trace;
+
+use crate::core_event_controller::{CAUSE_TI, TIMER_IRQN};
+
+/// `Cause[DC]`, the count register is disabled.
+const CAUSE_DC: u32 = 1 << 27;
+
+/// Executed instructions per `Count` increment.
+const INSTRUCTIONS_PER_COUNT: u64 = 2;
+
+/// Advance `count` by `increments`.
+///
+/// Returns the new count and if it reached `compare` on the way.
+fn advance(count: u32, compare: u32, increments: u64) -> (u32, bool) {
+    let distance = compare.wrapping_sub(count) as u64;
+    let reached = distance != 0 && distance <= increments;
+    (count.wrapping_add(increments as u32), reached)
+}
+
+/// Instructions until `count` reaches `compare`, `remainder` instructions into an increment.
+fn instructions_until(count: u32, compare: u32, remainder: u64) -> u64 {
+    let distance = match compare.wrapping_sub(count) {
+        // already equal, the next match is a full wrap away
+        0 => 1 << 32,
+        distance => distance as u64,
+    };
+    distance * INSTRUCTIONS_PER_COUNT - remainder
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct CountCompareState {
+    /// Instructions executed since the last increment.
+    remainder: u64,
+}
+
+/// The `Count` and `Compare` registers, see the [module documentation](self).
+#[derive(Default)]
+pub struct CountCompareTimer {
+    state: CountCompareState,
+    /// Instructions until `Count` reaches `Compare` as of the last tick, `None` while disabled.
+    deadline: Option<u64>,
+}
+
+impl Peripheral for CountCompareTimer {
+    fn name(&self) -> &str {
+        "count/compare timer"
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        vec![TIMER_IRQN]
+    }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.state = Default::default();
+        self.deadline = None;
+        Ok(())
+    }
+
+    fn tick(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        let cause = cpu.read_register::<u32>(Mips32Register::Cause)?;
+        if cause & CAUSE_DC != 0 {
+            self.deadline = None;
+            return Ok(());
+        }
+
+        let instructions = self.state.remainder + delta.count;
+        self.state.remainder = instructions % INSTRUCTIONS_PER_COUNT;
+        let increments = instructions / INSTRUCTIONS_PER_COUNT;
+
+        let mut count = cpu.read_register::<u32>(Mips32Register::Count)?;
+        let compare = cpu.read_register::<u32>(Mips32Register::Compare)?;
+        if increments > 0 {
+            let reached;
+            (count, reached) = advance(count, compare, increments);
+            cpu.write_register(Mips32Register::Count, count)?;
+
+            if reached {
+                trace!("count reached compare {compare:#x}");
+                cpu.write_register(Mips32Register::Cause, cause | CAUSE_TI)?;
+            }
+        }
+
+        self.deadline = Some(instructions_until(count, compare, self.state.remainder));
+        Ok(())
+    }
+
+    /// Computed from `Count` and `Compare` on the last tick, software writing either register
+    /// is only seen on the next one.
+    fn next_deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.state)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.state = state.get()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        assert_eq!((100, false), advance(90, 101, 10));
+        assert_eq!((110, true), advance(100, 101, 10));
+        // already equal before the increment
+        assert_eq!((111, false), advance(110, 110, 1));
+        assert_eq!((0x10, true), advance(0xFFFF_FFF0, 0x5, 0x20));
+        assert_eq!((0x10, false), advance(0xFFFF_FFF0, 0x11, 0x20));
+    }
+
+    #[test]
+    fn test_instructions_until() {
+        assert_eq!(20, instructions_until(90, 100, 0));
+        assert_eq!(19, instructions_until(90, 100, 1));
+        assert_eq!(0x22, instructions_until(0xFFFF_FFF0, 0x1, 0));
+        assert_eq!(2 << 32, instructions_until(110, 110, 0));
+    }
+}
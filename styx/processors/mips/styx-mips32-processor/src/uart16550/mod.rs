@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! 16550 UART
+//!
+//! The UART is exposed through a [UartController] so clients can talk to it over the `UartPort`
+//! gRPC service with the interface id `"0"`. Its registers are the ISA I/O ports at
+//! [`UART_PORT`], reachable at [`UART_BASE`] and through kseg1 at [`UART_KSEG1_BASE`], and it
+//! requests [`UART_IRQN`].
+//!
+//! The interrupt is level sensitive, it is latched on every tick while `IIR` reports one.
+//!
+//! See [registers] for the modeled register behavior.
+use std::collections::VecDeque;
+
+use styx_core::hooks::{MemoryReadHook, MemoryWriteHook};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use styx_peripherals::uart::{IntoUartImpl, UartController, UartImpl, UartInterface};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+mod registers;
+
+use registers::{Uart16550Registers, LAST_OFFSET};
+
+use crate::{ISA_IO_BASE, ISA_IO_KSEG1_BASE};
+
+/// ISA I/O port of the registers, `COM1`.
+pub const UART_PORT: u64 = 0x3F8;
+/// Physical address of the UART registers.
+pub const UART_BASE: u64 = ISA_IO_BASE + UART_PORT;
+/// Address of the UART registers in kseg1.
+pub const UART_KSEG1_BASE: u64 = ISA_IO_KSEG1_BASE + UART_PORT;
+/// The UART interrupt, `IP4`.
+pub const UART_IRQN: ExceptionNumber = 4;
+
+const INTERFACE_ID: &str = "0";
+
+/// Creates the interface for the UART to be added to a [UartController].
+pub(crate) fn get_uarts() -> Vec<UartInterface> {
+    vec![UartInterface::new(
+        INTERFACE_ID.to_owned(),
+        Uart16550Builder,
+    )]
+}
+
+struct Uart16550Builder;
+
+impl IntoUartImpl for Uart16550Builder {
+    fn new(
+        self,
+        mosi_rx: broadcast::Receiver<u8>,
+        miso_tx: broadcast::Sender<u8>,
+        _interface_id: String,
+    ) -> Result<Box<dyn UartImpl>, UnknownError> {
+        Ok(Box::new(Uart16550 {
+            registers: Uart16550Registers::default(),
+            buffer: VecDeque::new(),
+            miso_stream: miso_tx,
+            mosi_stream: mosi_rx,
+        }))
+    }
+}
+
+/// Connects the [Uart16550Registers] to the uart streams.
+pub(crate) struct Uart16550 {
+    registers: Uart16550Registers,
+    /// uart bytes that have come in from master but not received yet.
+    buffer: VecDeque<u8>,
+    miso_stream: broadcast::Sender<u8>,
+    mosi_stream: broadcast::Receiver<u8>,
+}
+
+impl Uart16550 {
+    /// checks uart mosi for bytes and gives to buffer
+    fn grab_bytes(&mut self) {
+        loop {
+            match self.mosi_stream.try_recv() {
+                Ok(data) => self.buffer.push_back(data),
+                Err(broadcast::error::TryRecvError::Empty) => break,
+                Err(broadcast::error::TryRecvError::Closed) => {
+                    warn!("uart mosi stream closed??");
+                    break;
+                }
+                Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                    warn!("uart mosi stream lagged {n} items");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn guest_transmit_data(&mut self, data: u8) {
+        debug!("16550 transmit data {data:#x}");
+        // an error means no one is listening, that's fine
+        let _ = self.miso_stream.send(data);
+    }
+
+    /// Move waiting bytes into the receive FIFO while it has room.
+    fn fill_fifo(&mut self) {
+        while self.registers.receive_ready() {
+            let Some(data) = self.buffer.pop_front() else {
+                break;
+            };
+            self.registers.receive(data);
+        }
+    }
+
+    fn read_register(&mut self, offset: u64) -> u8 {
+        self.registers.read(offset)
+    }
+
+    fn write_register(&mut self, offset: u64, value: u8) {
+        if let Some(data) = self.registers.write(offset, value) {
+            self.guest_transmit_data(data);
+        }
+    }
+}
+
+impl UartImpl for Uart16550 {
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        for base in [UART_BASE, UART_KSEG1_BASE] {
+            let end = base + LAST_OFFSET;
+            proc.core
+                .cpu
+                .mem_read_hook(base, end, Box::new(Uart16550Hook { base }))?;
+            proc.core
+                .cpu
+                .mem_write_hook(base, end, Box::new(Uart16550Hook { base }))?;
+        }
+        Ok(())
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        vec![UART_IRQN]
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+    ) -> Result<(), UnknownError> {
+        self.grab_bytes();
+        self.fill_fifo();
+        if self.registers.interrupt() {
+            event_controller.latch(UART_IRQN)?;
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(&self.registers, &self.buffer))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        (self.registers, self.buffer) = state.get()?;
+        Ok(())
+    }
+}
+
+/// Register hook of the UART at `base`, registers are accessed through the [UartController].
+struct Uart16550Hook {
+    base: u64,
+}
+
+impl Uart16550Hook {
+    fn uart<'a>(&self, proc: &'a mut CoreHandle) -> Result<&'a mut Uart16550, UnknownError> {
+        proc.event_controller
+            .peripherals
+            .get_expect::<UartController>()?
+            .try_get::<Uart16550>(INTERFACE_ID)
+    }
+}
+
+impl MemoryReadHook for Uart16550Hook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        size: u32,
+        data: &mut [u8],
+    ) -> Result<(), UnknownError> {
+        let offset = address - self.base;
+        let uart = self.uart(&mut proc)?;
+        // the registers are bytes, wider accesses read consecutive registers
+        for (index, byte) in data[..size as usize].iter_mut().enumerate() {
+            *byte = uart.read_register(offset + index as u64);
+        }
+        Ok(())
+    }
+}
+
+impl MemoryWriteHook for Uart16550Hook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        size: u32,
+        data: &[u8],
+    ) -> Result<(), UnknownError> {
+        let offset = address - self.base;
+        let uart = self.uart(&mut proc)?;
+        for (index, byte) in data[..size as usize].iter().enumerate() {
+            uart.write_register(offset + index as u64, *byte);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! 16550 register state.
+//!
+//! Transmission is instant, so the transmitter is always empty and the transmitter empty
+//! interrupt is raised again after every write to `THR`. It is cleared by reading `IIR` while it
+//! is the reported interrupt, like the hardware. The receiver line status and modem status
+//! interrupts are never raised and the receive FIFO trigger level is ignored, any received byte
+//! raises the data available interrupt. Loopback mode is not modeled.
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// Receiver Buffer Register (read) / Transmitter Holding Register (write), Divisor Latch LSB with
+/// `LCR[DLAB]`
+const RBR: u64 = 0;
+/// Interrupt Enable Register, Divisor Latch MSB with `LCR[DLAB]`
+const IER: u64 = 1;
+/// Interrupt Identification Register (read) / FIFO Control Register (write)
+const IIR: u64 = 2;
+/// Line Control Register
+const LCR: u64 = 3;
+/// Modem Control Register
+const MCR: u64 = 4;
+/// Line Status Register
+const LSR: u64 = 5;
+/// Modem Status Register
+const MSR: u64 = 6;
+/// Scratch Register
+const SCR: u64 = 7;
+
+/// Offset of the last register.
+pub(super) const LAST_OFFSET: u64 = SCR;
+
+/// `IER[ERBFI]`, received data available interrupt.
+const IER_RDA: u8 = 1 << 0;
+/// `IER[ETBEI]`, transmitter holding register empty interrupt.
+const IER_THRE: u8 = 1 << 1;
+
+/// `IIR` without a pending interrupt.
+const IIR_NONE: u8 = 0x01;
+const IIR_THRE: u8 = 0x02;
+const IIR_RDA: u8 = 0x04;
+/// `IIR[7:6]`, the FIFOs are enabled.
+const IIR_FIFO: u8 = 0xC0;
+
+/// `FCR[FIFO Enable]`
+const FCR_FE: u8 = 1 << 0;
+/// `FCR[RCVR FIFO Reset]`
+const FCR_RX_RESET: u8 = 1 << 1;
+
+/// `LCR[DLAB]`, divisor latch access.
+const LCR_DLAB: u8 = 1 << 7;
+
+const LSR_DR: u8 = 1 << 0;
+const LSR_THRE: u8 = 1 << 5;
+const LSR_TEMT: u8 = 1 << 6;
+
+/// `MSR` with `CTS`, `DSR` and `DCD` asserted.
+const MSR_VALUE: u8 = 0xB0;
+
+const FIFO_SIZE: usize = 16;
+
+/// State of the 16550 registers.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct Uart16550Registers {
+    ier: u8,
+    /// `FCR[FIFO Enable]` and the trigger level.
+    fcr: u8,
+    lcr: u8,
+    mcr: u8,
+    scr: u8,
+    divisor: u16,
+    /// The transmitter empty interrupt is pending.
+    thre_pending: bool,
+    /// Receive FIFO, read through `RBR`.
+    rx: VecDeque<u8>,
+}
+
+impl Uart16550Registers {
+    fn fifo_depth(&self) -> usize {
+        if self.fcr & FCR_FE != 0 {
+            FIFO_SIZE
+        } else {
+            1
+        }
+    }
+
+    /// The receive FIFO has room.
+    pub(super) fn receive_ready(&self) -> bool {
+        self.rx.len() < self.fifo_depth()
+    }
+
+    /// Place a received byte in the receive FIFO.
+    pub(super) fn receive(&mut self, data: u8) {
+        trace!("16550 received {data:#x}");
+        self.rx.push_back(data);
+    }
+
+    /// Highest priority pending interrupt as reported by `IIR[3:0]`.
+    fn pending_interrupt(&self) -> u8 {
+        if self.ier & IER_RDA != 0 && !self.rx.is_empty() {
+            IIR_RDA
+        } else if self.ier & IER_THRE != 0 && self.thre_pending {
+            IIR_THRE
+        } else {
+            IIR_NONE
+        }
+    }
+
+    /// The interrupt line is asserted.
+    pub(super) fn interrupt(&self) -> bool {
+        self.pending_interrupt() != IIR_NONE
+    }
+
+    fn line_status(&self) -> u8 {
+        let mut status = LSR_THRE | LSR_TEMT;
+        if !self.rx.is_empty() {
+            status |= LSR_DR;
+        }
+        status
+    }
+
+    fn dlab(&self) -> bool {
+        self.lcr & LCR_DLAB != 0
+    }
+
+    /// Read the register at `offset`, reading `RBR` pops the receive FIFO.
+    pub(super) fn read(&mut self, offset: u64) -> u8 {
+        match offset {
+            RBR if self.dlab() => self.divisor as u8,
+            RBR => self.rx.pop_front().unwrap_or_default(),
+            IER if self.dlab() => (self.divisor >> 8) as u8,
+            IER => self.ier,
+            IIR => {
+                let pending = self.pending_interrupt();
+                // reporting the transmitter empty interrupt clears it
+                if pending == IIR_THRE {
+                    self.thre_pending = false;
+                }
+                if self.fcr & FCR_FE != 0 {
+                    pending | IIR_FIFO
+                } else {
+                    pending
+                }
+            }
+            LCR => self.lcr,
+            MCR => self.mcr,
+            LSR => self.line_status(),
+            MSR => MSR_VALUE,
+            SCR => self.scr,
+            _ => {
+                debug!("read of unimplemented 16550 register {offset:#x}");
+                0
+            }
+        }
+    }
+
+    /// Write `value` to the register at `offset`.
+    ///
+    /// Returns the byte to transmit if `THR` was written.
+    pub(super) fn write(&mut self, offset: u64, value: u8) -> Option<u8> {
+        match offset {
+            RBR if self.dlab() => self.divisor = (self.divisor & 0xFF00) | value as u16,
+            RBR => {
+                self.thre_pending = true;
+                return Some(value);
+            }
+            IER if self.dlab() => {
+                self.divisor = (self.divisor & 0x00FF) | ((value as u16) << 8);
+            }
+            IER => {
+                let value = value & 0x0F;
+                // enabling the interrupt with an empty transmitter raises it
+                if value & IER_THRE != 0 && self.ier & IER_THRE == 0 {
+                    self.thre_pending = true;
+                }
+                self.ier = value;
+            }
+            IIR => {
+                // changing the FIFO mode clears the FIFOs
+                if (value ^ self.fcr) & FCR_FE != 0 || value & FCR_RX_RESET != 0 {
+                    self.rx.clear();
+                }
+                self.fcr = value & (FCR_FE | 0xC0);
+            }
+            LCR => self.lcr = value,
+            MCR => self.mcr = value & 0x1F,
+            SCR => self.scr = value,
+            _ => debug!("write to read only 16550 register {offset:#x}: {value:#x}"),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receive_fifo() {
+        let mut uart = Uart16550Registers::default();
+        assert_eq!(LSR_THRE | LSR_TEMT, uart.read(LSR));
+
+        // without the FIFOs only one byte is held
+        uart.receive(b'a');
+        assert!(!uart.receive_ready());
+        assert_eq!(LSR_THRE | LSR_TEMT | LSR_DR, uart.read(LSR));
+        assert_eq!(b'a', uart.read(RBR));
+
+        uart.write(IIR, FCR_FE);
+        for data in b"bcd" {
+            uart.receive(*data);
+        }
+        assert!(uart.receive_ready());
+        assert_eq!(b'b', uart.read(RBR));
+
+        // resetting the receive FIFO drops the rest
+        uart.write(IIR, FCR_FE | FCR_RX_RESET);
+        assert_eq!(LSR_THRE | LSR_TEMT, uart.read(LSR));
+    }
+
+    #[test]
+    fn test_interrupts() {
+        let mut uart = Uart16550Registers::default();
+        assert_eq!(IIR_NONE, uart.read(IIR));
+
+        uart.write(IER, IER_RDA | IER_THRE);
+        assert!(uart.interrupt());
+        uart.receive(b'a');
+        // received data has priority
+        assert_eq!(IIR_RDA, uart.read(IIR));
+        uart.read(RBR);
+        // reading IIR clears the transmitter empty interrupt
+        assert_eq!(IIR_THRE, uart.read(IIR));
+        assert!(!uart.interrupt());
+
+        assert_eq!(Some(b'x'), uart.write(RBR, b'x'));
+        assert!(uart.interrupt());
+    }
+
+    #[test]
+    fn test_divisor_latch() {
+        let mut uart = Uart16550Registers::default();
+        uart.write(LCR, LCR_DLAB | 0x03);
+        assert_eq!(None, uart.write(RBR, 0x0C));
+        uart.write(IER, 0x01);
+        assert_eq!(0x010C, uart.divisor);
+        uart.write(LCR, 0x03);
+        assert_eq!(0, uart.read(IER));
+        assert_eq!(Some(b'y'), uart.write(RBR, b'y'));
+    }
+}
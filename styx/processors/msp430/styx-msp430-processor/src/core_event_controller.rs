@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! MSP430 interrupt handling.
+//!
+//! Interrupts are identified by their vector, interrupt `n` jumps to the address stored at
+//! `0xFFE0 + 2 * n` and higher vectors have higher priority. The reset vector (15) is not latched,
+//! it is loaded by [`EventControllerImpl::reset()`].
+//!
+//! Taking an interrupt pushes the pc then the status register, clears the status register except
+//! `SCG0` and jumps to the vector. `RETI` pops both again in the pcode so the controller is not
+//! told when an interrupt finishes. Maskable interrupts stay latched until `GIE` is set.
+use styx_core::cpu::arch::msp430::Msp430Register;
+use styx_core::event_controller::{ActivateIRQnError, InterruptExecuted};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::debug;
+
+/// Base address of the interrupt vector table.
+const VECTOR_TABLE: u64 = 0xFFE0;
+/// The reset vector.
+pub const RESET_VECTOR: ExceptionNumber = 15;
+/// The non-maskable interrupt vector, taken regardless of `GIE`.
+pub const NMI_VECTOR: ExceptionNumber = 14;
+
+/// General interrupt enable bit in the status register.
+pub(crate) const SR_GIE: u16 = 1 << 3;
+/// System clock generator 0 bit in the status register, kept when taking an interrupt.
+const SR_SCG0: u16 = 1 << 6;
+
+#[derive(Debug, Default)]
+pub struct CoreEventController {
+    /// Bitmask of latched vectors.
+    pending: u16,
+}
+
+impl CoreEventController {
+    /// Address of the handler for `vector`.
+    fn handler(mmu: &mut Mmu, vector: ExceptionNumber) -> Result<u16, UnknownError> {
+        Ok(mmu
+            .data()
+            .read(VECTOR_TABLE + 2 * vector as u64)
+            .le()
+            .u16()?)
+    }
+
+    /// Highest priority latched vector that can be taken with `sr`.
+    fn next_pending(&self, sr: u16) -> Option<ExceptionNumber> {
+        let pending = if sr & SR_GIE > 0 {
+            self.pending
+        } else {
+            self.pending & (1 << NMI_VECTOR)
+        };
+
+        (pending != 0).then(|| 15 - pending.leading_zeros() as ExceptionNumber)
+    }
+
+    /// Push the pc and status register and jump to the handler of `vector`.
+    fn take_interrupt(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        vector: ExceptionNumber,
+    ) -> Result<(), UnknownError> {
+        let pc = cpu.pc()? as u16;
+        let sr = cpu.read_register::<u16>(Msp430Register::Sr)?;
+        let sp = cpu.read_register::<u16>(Msp430Register::Sp)?;
+
+        let sp = sp.wrapping_sub(2);
+        mmu.data().write(sp as u64).le().value(pc)?;
+        let sp = sp.wrapping_sub(2);
+        mmu.data().write(sp as u64).le().value(sr)?;
+
+        let handler = Self::handler(mmu, vector)?;
+        debug!("taking interrupt {vector} at 0x{pc:X}, handler 0x{handler:X}");
+
+        cpu.write_register(Msp430Register::Sp, sp)?;
+        cpu.write_register(Msp430Register::Sr, sr & SR_SCG0)?;
+        cpu.set_pc(handler as u64)?;
+        Ok(())
+    }
+}
+
+impl EventControllerImpl for CoreEventController {
+    fn next(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        _peripherals: &mut styx_core::event_controller::Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        let sr = cpu.read_register::<u16>(Msp430Register::Sr)?;
+        let Some(vector) = self.next_pending(sr) else {
+            return Ok(InterruptExecuted::NotExecuted);
+        };
+
+        self.pending &= !(1 << vector);
+        self.take_interrupt(cpu, mmu, vector)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+        if !(0..RESET_VECTOR).contains(&event) {
+            return Err(ActivateIRQnError::InvalidIRQn(event));
+        }
+
+        self.pending |= 1 << event;
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        irq: ExceptionNumber,
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+    ) -> Result<InterruptExecuted, ActivateIRQnError> {
+        if !(0..RESET_VECTOR).contains(&irq) {
+            return Err(ActivateIRQnError::InvalidIRQn(irq));
+        }
+
+        self.take_interrupt(cpu, mmu, irq)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    fn finish_interrupt(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Option<ExceptionNumber> {
+        // RETI is handled entirely by the pcode
+        None
+    }
+
+    fn has_pending_events(&self) -> bool {
+        self.pending != 0
+    }
+
+    fn init(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        Ok(())
+    }
+
+    fn reset(&mut self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.pending = 0;
+        cpu.write_register(Msp430Register::Sr, 0u16)?;
+        let handler = Self::handler(mmu, RESET_VECTOR)?;
+        cpu.set_pc(handler as u64)?;
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.pending)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.pending = state.get()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority() {
+        let mut controller = CoreEventController::default();
+        assert_eq!(controller.next_pending(SR_GIE), None);
+
+        controller.latch(6).unwrap();
+        controller.latch(9).unwrap();
+        assert_eq!(controller.next_pending(SR_GIE), Some(9));
+        // masked without GIE
+        assert_eq!(controller.next_pending(0), None);
+
+        controller.latch(NMI_VECTOR).unwrap();
+        assert_eq!(controller.next_pending(0), Some(NMI_VECTOR));
+
+        assert!(controller.latch(RESET_VECTOR).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Generic MSP430 processor.
+//!
+//! Models the memory map shared by the MSP430x1xx family with 2KiB of RAM and a full 60KiB
+//! flash. Has support for [interrupts](core_event_controller), [Timer_A](timer_a) and
+//! [USART0](usart) in UART mode, the remaining peripheral registers are plain memory.
+//!
+//! Execution starts at the address in the reset vector (`0xFFFE`) after the target program is
+//! loaded.
+mod core_event_controller;
+mod timer_a;
+mod usart;
+
+use core_event_controller::CoreEventController;
+use styx_core::core::builder::{BuildProcessorImplArgs, ProcessorImpl};
+use styx_core::cpu::arch::msp430::Msp430Variants;
+use styx_core::cpu::PcodeBackend;
+use styx_core::memory::memory_region::MemoryRegion;
+use styx_core::memory::MemoryPermissions;
+use styx_core::prelude::*;
+use styx_peripherals::uart::{UartController, UartInterface};
+use timer_a::TimerA;
+use tracing::debug;
+use usart::NewUsartPortInner;
+
+pub use core_event_controller::{NMI_VECTOR, RESET_VECTOR};
+pub use timer_a::{TIMER_A0_VECTOR, TIMER_A1_VECTOR};
+pub use usart::{USART0_RX_VECTOR, USART0_TX_VECTOR};
+
+/// Address of the reset vector.
+const RESET_VECTOR_ADDRESS: u64 = 0xFFFE;
+
+#[derive(serde::Deserialize)]
+pub struct Msp430Builder {
+    pub variant: Msp430Variants,
+}
+
+impl Default for Msp430Builder {
+    fn default() -> Self {
+        Self {
+            variant: Msp430Variants::Msp430x31x,
+        }
+    }
+}
+
+impl ProcessorImpl for Msp430Builder {
+    fn build(&self, args: &BuildProcessorImplArgs) -> Result<ProcessorBundle, UnknownError> {
+        let cpu = if let Backend::Pcode = args.backend {
+            Box::new(PcodeBackend::new_engine_config(
+                self.variant,
+                ArchEndian::LittleEndian,
+                &args.into(),
+            ))
+        } else {
+            return Err(anyhow!("msp430 processor only supports pcode backend"));
+        };
+
+        let mut mmu = Mmu::default_region_store();
+
+        self.setup_address_space(&mut mmu)?;
+
+        let cec = Box::new(CoreEventController::default());
+
+        let mut peripherals: Vec<Box<dyn Peripheral>> = Vec::new();
+        peripherals.push(Box::new(TimerA::new()));
+        let uart = UartController::new(vec![UartInterface::new("0".into(), NewUsartPortInner)]);
+        peripherals.push(Box::new(uart));
+
+        let mut hints = LoaderHints::new();
+        hints.insert("arch".to_string().into_boxed_str(), Box::new(Arch::Msp430));
+
+        Ok(ProcessorBundle {
+            cpu,
+            mmu,
+            event_controller: cec,
+            peripherals,
+            loader_hints: hints,
+        })
+    }
+
+    fn init(&self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        // the loader may have left the pc somewhere else, the hardware always starts at the
+        // reset vector
+        let reset = proc.core.mmu.data().read(RESET_VECTOR_ADDRESS).le().u16()?;
+        if reset != 0 {
+            debug!("starting at reset vector 0x{reset:X}");
+            proc.core.cpu.set_pc(reset as u64)?;
+        }
+        Ok(())
+    }
+}
+
+impl Msp430Builder {
+    fn setup_address_space(&self, mmu: &mut Mmu) -> Result<(), UnknownError> {
+        let mut regions = Vec::new();
+
+        // special function, 8 bit and 16 bit peripheral registers
+        let peripherals_start = 0x0000;
+        let peripherals_size = 0x200;
+        regions.push(MemoryRegion::new(
+            peripherals_start,
+            peripherals_size,
+            MemoryPermissions::RW,
+        )?);
+
+        let ram_start = 0x0200;
+        let ram_size = 0x800;
+        regions.push(MemoryRegion::new(
+            ram_start,
+            ram_size,
+            MemoryPermissions::all(),
+        )?);
+
+        let boot_memory_start = 0x0C00;
+        let boot_memory_size = 0x400;
+        regions.push(MemoryRegion::new(
+            boot_memory_start,
+            boot_memory_size,
+            MemoryPermissions::READ | MemoryPermissions::EXEC,
+        )?);
+
+        let information_memory_start = 0x1000;
+        let information_memory_size = 0x100;
+        regions.push(MemoryRegion::new(
+            information_memory_start,
+            information_memory_size,
+            MemoryPermissions::RW,
+        )?);
+
+        // includes the interrupt vector table at the top
+        let flash_start = 0x1100;
+        let flash_size = 0xEF00;
+        regions.push(MemoryRegion::new(
+            flash_start,
+            flash_size,
+            MemoryPermissions::READ | MemoryPermissions::EXEC,
+        )?);
+
+        for region in regions {
+            mmu.add_memory_region(region)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use styx_core::cpu::arch::msp430::Msp430Register;
+
+    const PROGRAM_START: u64 = 0xC000;
+    const HANDLER_START: u64 = 0xC100;
+
+    /// Enables the Timer_A compare interrupt in up mode and checks the handler runs.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_timer_interrupt() {
+        let program: &[u8] = &[
+            0x31, 0x40, 0x00, 0x0A, // mov #0x0A00, sp
+            0xB2, 0x40, 0x0A, 0x00, 0x72, 0x01, // mov #10, &TACCR0
+            0xB2, 0x40, 0x10, 0x00, 0x62, 0x01, // mov #CCIE, &TACCTL0
+            0xB2, 0x40, 0x10, 0x02, 0x60, 0x01, // mov #TASSEL_2 | MC_1, &TACTL
+            0x32, 0xD2, // eint
+            0xFF, 0x3F, // jmp $
+        ];
+        let handler: &[u8] = &[
+            0x14, 0x53, // inc r4
+            0x00, 0x13, // reti
+        ];
+
+        let mut proc = ProcessorBuilder::default()
+            .with_builder(Msp430Builder::default())
+            .build()
+            .unwrap();
+
+        let vector = 0xFFE0 + 2 * TIMER_A0_VECTOR as u64;
+        // flash is not writable
+        let mmu = &mut proc.core.mmu;
+        mmu.sudo_code().write(PROGRAM_START).bytes(program).unwrap();
+        mmu.sudo_code().write(HANDLER_START).bytes(handler).unwrap();
+        mmu.sudo_code()
+            .write(vector)
+            .le()
+            .value(HANDLER_START as u16)
+            .unwrap();
+        proc.core.cpu.set_pc(PROGRAM_START).unwrap();
+
+        proc.run(3500).unwrap();
+
+        let interrupts = proc
+            .core
+            .cpu
+            .read_register::<u16>(Msp430Register::R4)
+            .unwrap();
+        assert!(interrupts > 0, "timer interrupt never taken");
+        // back in the idle loop with interrupts still enabled
+        let sr = proc
+            .core
+            .cpu
+            .read_register::<u16>(Msp430Register::Sr)
+            .unwrap();
+        assert!(sr & core_event_controller::SR_GIE > 0);
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Timer_A
+//!
+//! Generic Timer_A found on most MSP430 devices, only the counter and capture/compare block 0 are
+//! emulated. The registers live in memory and are updated on every tick, the timer counts one
+//! clock per executed instruction regardless of the selected clock source (`TASSEL`).
+//!
+//! Registers:
+//!
+//!  Address | Name    | Description
+//! ----------------------------------------------------------
+//!  0x0160  | TACTL   | control
+//!  0x0162  | TACCTL0 | capture/compare control 0
+//!  0x0170  | TAR     | counter
+//!  0x0172  | TACCR0  | capture/compare 0
+//!
+//! Supported modes (`MC`) are stop, up and continuous, up/down is treated as up. In up mode the
+//! counter wraps after reaching `TACCR0`, setting `CCIFG` in `TACCTL0` and raising
+//! [`TIMER_A0_VECTOR`] if `CCIE` is set. In continuous mode the counter wraps after `0xFFFF`,
+//! setting `TAIFG` in `TACTL` and raising [`TIMER_A1_VECTOR`] if `TAIE` is set.
+use serde::{Deserialize, Serialize};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::trace;
+
+const TACTL: u64 = 0x0160;
+const TACCTL0: u64 = 0x0162;
+const TAR: u64 = 0x0170;
+const TACCR0: u64 = 0x0172;
+
+const TACTL_TAIFG: u16 = 1 << 0;
+const TACTL_TAIE: u16 = 1 << 1;
+const TACTL_TACLR: u16 = 1 << 2;
+const TACTL_MC_SHIFT: u16 = 4;
+const TACTL_ID_SHIFT: u16 = 6;
+
+const TACCTL_CCIFG: u16 = 1 << 0;
+const TACCTL_CCIE: u16 = 1 << 4;
+
+/// Timer_A capture/compare 0 interrupt.
+pub const TIMER_A0_VECTOR: ExceptionNumber = 6;
+/// Timer_A overflow and capture/compare 1-2 interrupt.
+pub const TIMER_A1_VECTOR: ExceptionNumber = 5;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TimerA {
+    /// Instructions not yet counted because of the input divider.
+    remainder: u64,
+}
+
+/// Events of advancing the counter.
+#[derive(Debug, PartialEq, Eq)]
+struct Advance {
+    counter: u16,
+    /// Wrapped at `TACCR0` in up mode.
+    compare: bool,
+    /// Wrapped at `0xFFFF` in continuous mode.
+    overflow: bool,
+}
+
+/// Advance `counter` by `counts` in `mode`.
+fn advance(mode: u16, counter: u16, compare: u16, counts: u64) -> Advance {
+    match mode {
+        // stop
+        0 => Advance {
+            counter,
+            compare: false,
+            overflow: false,
+        },
+        // continuous
+        2 => {
+            let next = counter as u64 + counts;
+            Advance {
+                counter: next as u16,
+                compare: false,
+                overflow: next > u16::MAX as u64,
+            }
+        }
+        // up, up/down
+        _ => {
+            let period = compare as u64 + 1;
+            let next = counter as u64 + counts;
+            Advance {
+                counter: (next % period) as u16,
+                compare: next >= period,
+                overflow: false,
+            }
+        }
+    }
+}
+
+impl TimerA {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(
+        &mut self,
+        mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+        instructions: u64,
+    ) -> Result<(), UnknownError> {
+        let mut control = mmu.data().read(TACTL).le().u16()?;
+        let mut counter = mmu.data().read(TAR).le().u16()?;
+
+        if control & TACTL_TACLR > 0 {
+            control &= !TACTL_TACLR;
+            counter = 0;
+            self.remainder = 0;
+        }
+
+        let divider = 1 << ((control >> TACTL_ID_SHIFT) & 0b11);
+        let instructions = self.remainder + instructions;
+        self.remainder = instructions % divider;
+
+        let mode = (control >> TACTL_MC_SHIFT) & 0b11;
+        let compare = mmu.data().read(TACCR0).le().u16()?;
+        let advance = advance(mode, counter, compare, instructions / divider);
+
+        if advance.compare {
+            let cctl = mmu.data().read(TACCTL0).le().u16()? | TACCTL_CCIFG;
+            mmu.data().write(TACCTL0).le().value(cctl)?;
+            if cctl & TACCTL_CCIE > 0 {
+                trace!("timer_a ccr0 interrupt");
+                event_controller.latch(TIMER_A0_VECTOR)?;
+            }
+        }
+        if advance.overflow {
+            control |= TACTL_TAIFG;
+            if control & TACTL_TAIE > 0 {
+                trace!("timer_a overflow interrupt");
+                event_controller.latch(TIMER_A1_VECTOR)?;
+            }
+        }
+
+        mmu.data().write(TACTL).le().value(control)?;
+        mmu.data().write(TAR).le().value(advance.counter)?;
+        Ok(())
+    }
+}
+
+impl Peripheral for TimerA {
+    fn name(&self) -> &str {
+        "Timer_A"
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        vec![TIMER_A0_VECTOR, TIMER_A1_VECTOR]
+    }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.remainder = 0;
+        for register in [TACTL, TACCTL0, TAR, TACCR0] {
+            mmu.data().write(register).le().value(0u16)?;
+        }
+        Ok(())
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        self.update(mmu, event_controller, delta.count)
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(self)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        *self = state.get()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        // stopped
+        assert_eq!(advance(0, 5, 10, 100).counter, 5);
+
+        // up mode counts 0..=10
+        let up = advance(1, 5, 10, 5);
+        assert_eq!((up.counter, up.compare), (10, false));
+        let up = advance(1, 5, 10, 6);
+        assert_eq!((up.counter, up.compare), (0, true));
+        let up = advance(1, 5, 10, 30);
+        assert_eq!((up.counter, up.compare), (2, true));
+
+        // continuous mode wraps at 0xFFFF
+        let continuous = advance(2, 0xFFF0, 10, 0x20);
+        assert_eq!(continuous.counter, 0x10);
+        assert!(continuous.overflow);
+    }
+}
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! USART0 in UART mode
+//!
+//! Transmitting and receiving through `U0TXBUF` and `U0RXBUF` are emulated, the control and baud
+//! rate registers are plain memory and have no effect. Transmits complete immediately so
+//! `UTXIFG0` is always set.
+//!
+//! Registers:
+//!
+//!  Address | Name    | Description
+//! ----------------------------------------------------------
+//!  0x0000  | IE1     | `URXIE0` (bit 6) and `UTXIE0` (bit 7) interrupt enables
+//!  0x0002  | IFG1    | `URXIFG0` (bit 6) and `UTXIFG0` (bit 7) interrupt flags
+//!  0x0076  | U0RXBUF | receive buffer, reading pops a received byte
+//!  0x0077  | U0TXBUF | transmit buffer, writing transmits a byte
+//!
+//! `URXIFG0` is set while received bytes are waiting and raises [`USART0_RX_VECTOR`] on each
+//! tick if `URXIE0` is set. Writing `U0TXBUF` raises [`USART0_TX_VECTOR`] if `UTXIE0` is set.
+use std::collections::VecDeque;
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use styx_peripherals::uart::{IntoUartImpl, UartController, UartImpl};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+const IE1: u64 = 0x0000;
+const IFG1: u64 = 0x0002;
+const U0RXBUF: u64 = 0x0076;
+const U0TXBUF: u64 = 0x0077;
+
+const URX0: u8 = 1 << 6;
+const UTX0: u8 = 1 << 7;
+
+/// USART0 receive interrupt.
+pub const USART0_RX_VECTOR: ExceptionNumber = 9;
+/// USART0 transmit interrupt.
+pub const USART0_TX_VECTOR: ExceptionNumber = 8;
+
+/// Set or clear `bits` of the 8 bit register at `address`.
+fn set_bits(mmu: &mut Mmu, address: u64, bits: u8, set: bool) -> Result<(), UnknownError> {
+    let value = mmu.data().read(address).u8()?;
+    let value = if set { value | bits } else { value & !bits };
+    mmu.data().write(address).bytes(&[value])?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct UsartPortInner {
+    interface_id: String,
+    /// Bytes received from the master but not read yet.
+    buffer: VecDeque<u8>,
+    miso_stream: broadcast::Sender<u8>,
+    mosi_stream: broadcast::Receiver<u8>,
+}
+
+pub struct NewUsartPortInner;
+impl IntoUartImpl for NewUsartPortInner {
+    fn new(
+        self,
+        mosi: broadcast::Receiver<u8>,
+        miso: broadcast::Sender<u8>,
+        interface_id: String,
+    ) -> Result<Box<dyn UartImpl>, UnknownError> {
+        Ok(Box::new(UsartPortInner {
+            interface_id,
+            buffer: Default::default(),
+            miso_stream: miso,
+            mosi_stream: mosi,
+        }))
+    }
+}
+
+impl UsartPortInner {
+    /// Checks the mosi stream for bytes and gives them to the buffer.
+    fn grab_bytes(&mut self) {
+        loop {
+            match self.mosi_stream.try_recv() {
+                Ok(data) => self.buffer.push_back(data),
+                Err(broadcast::error::TryRecvError::Empty) => break,
+                Err(broadcast::error::TryRecvError::Closed) => {
+                    warn!("uart mosi stream closed??");
+                    break;
+                }
+                Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                    warn!("uart mosi stream lagged {n} items");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Called from the `U0RXBUF` read hook.
+    fn guest_receive_data(&mut self) -> u8 {
+        self.grab_bytes();
+        self.buffer.pop_front().unwrap_or(0)
+    }
+
+    /// Called from the `U0TXBUF` write hook.
+    fn guest_transmit_data(&mut self, value: u8) {
+        debug!("guest transmit data {value:#X}");
+        // okay if no one is listening
+        let _ = self.miso_stream.send(value);
+    }
+}
+
+impl UartImpl for UsartPortInner {
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let interface_id = self.interface_id.clone();
+        proc.core.cpu.add_hook(StyxHook::memory_read(
+            U0RXBUF,
+            move |proc: CoreHandle, _address: u64, _size: u32, data: &mut [u8]| {
+                let port = proc
+                    .event_controller
+                    .peripherals
+                    .get_expect::<UartController>()?
+                    .try_get::<UsartPortInner>(&interface_id)?;
+                data[0] = port.guest_receive_data();
+                let waiting = !port.buffer.is_empty();
+                set_bits(proc.mmu, IFG1, URX0, waiting)
+            },
+        ))?;
+
+        let interface_id = self.interface_id.clone();
+        proc.core.cpu.add_hook(StyxHook::memory_write(
+            U0TXBUF,
+            move |proc: CoreHandle, _address: u64, _size: u32, data: &[u8]| {
+                proc.event_controller
+                    .peripherals
+                    .get_expect::<UartController>()?
+                    .try_get::<UsartPortInner>(&interface_id)?
+                    .guest_transmit_data(data[0]);
+
+                set_bits(proc.mmu, IFG1, UTX0, true)?;
+                if proc.mmu.data().read(IE1).u8()? & UTX0 > 0 {
+                    proc.event_controller.latch(USART0_TX_VECTOR)?;
+                }
+                Ok(())
+            },
+        ))?;
+
+        // the transmit buffer starts out empty
+        set_bits(&mut proc.core.mmu, IFG1, UTX0, true)
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        vec![USART0_RX_VECTOR, USART0_TX_VECTOR]
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+    ) -> Result<(), UnknownError> {
+        self.grab_bytes();
+
+        if !self.buffer.is_empty() {
+            set_bits(mmu, IFG1, URX0, true)?;
+            if mmu.data().read(IE1).u8()? & URX0 > 0 {
+                event_controller.latch(USART0_RX_VECTOR)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.buffer)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.buffer = state.get()?;
+        Ok(())
+    }
+}
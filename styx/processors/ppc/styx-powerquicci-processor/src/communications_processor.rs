@@ -258,6 +258,13 @@ doc = ::embed_doc_image::embed_image!("pram_reloc", "assets/pram_reloc.png"),))]
 //! 4) Restore Registers in (1) from stack.
 //! 5) Execute rfi instruction to return from supervisor-state interrupt handler.
 //!
+//! ### CPM Interrupt Controller
+//!
+//! CPM sources are raised with [`request_interrupt`], they become pending in `CIPR` and the
+//! CPM interrupt controller (emulated in [`styx_mpc866m::Mpc866mController`]) requests the
+//! SIU level programmed in `CICR[IRL]`. The handler reads the source from `CIVR` after writing
+//! `CIVR[IACK]`, and clears it by writing a 1 to its bit in `CIPR` and `CISR`.
+//!
 //! # CPM to Peripherals
 //!
 //! The CP uses the peripheral bus to communicate with the peripherals. The serial communications
@@ -304,7 +311,8 @@ use super::Mpc8xxVariants;
 use derive_more::Display;
 use enum_dispatch::enum_dispatch;
 use styx_core::errors::StyxMachineError;
-use styx_core::prelude::Peripheral;
+use styx_core::event_controller::ActivateIRQnError;
+use styx_core::prelude::{EventControllerImpl, Peripheral};
 use styx_core::sync::sync::{Arc, Weak};
 use thiserror::Error;
 use tracing::trace;
@@ -312,6 +320,7 @@ use tracing::trace;
 use super::peripherals::clocks::{PllClock, SystemControlClock};
 mod cpm_inner;
 pub use cpm_inner::*;
+pub use styx_mpc866m::CpmInterrupt;
 
 #[allow(unused_imports)]
 #[cfg(feature = "docimages")]
@@ -327,6 +336,15 @@ pub enum CpmError {
     PeripheralEventInit(&'static str, &'static str),
 }
 
+/// Mark a CPM interrupt source pending in the CPM interrupt controller.
+pub fn request_interrupt(
+    event_controller: &mut dyn EventControllerImpl,
+    source: CpmInterrupt,
+) -> Result<(), ActivateIRQnError> {
+    trace!("CPM interrupt request: {source}");
+    event_controller.latch(source.exception_number())
+}
+
 /// Events from shared memory with the emulated Host processor,
 /// this trait is monomorphized into the [`CpmEventType`] enum,
 /// and all implementors should also implement a pub method with
@@ -71,6 +71,8 @@ impl CpmGpTimerEvent {
 /// struct as it implements the actual inner state machine of the
 /// communications processor
 ///
+/// None of the subsystems are modeled yet, so it has no state to save in snapshots.
+///
 /// To obtain this struct you can do something like:
 ///
 /// ```rust
@@ -5,6 +5,9 @@ use super::Mpc8xxVariants;
 use styx_core::prelude::Peripheral;
 use tracing::trace;
 
+/// Placeholder for the FEC.
+///
+/// The controller is not modeled yet, so it has no state of its own to save in snapshots.
 #[derive(Debug, Default)]
 pub struct FastEthernetController;
 
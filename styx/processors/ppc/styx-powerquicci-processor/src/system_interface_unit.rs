@@ -5,24 +5,38 @@ use super::communications_processor::CommunicationsProcessorModule;
 use super::immr;
 use super::Mpc8xxVariants;
 
+use serde::{Deserialize, Serialize};
 use styx_core::errors::UnknownError;
 use styx_core::hooks::CoreHandle;
 use styx_core::hooks::HookToken;
-use styx_core::prelude::CpuBackend;
-use styx_core::prelude::Peripheral;
+use styx_core::prelude::{
+    CpuBackend, Delta, EventControllerImpl, ExceptionNumber, Mmu, Peripheral,
+};
+use styx_core::snapshot::ComponentState;
 use styx_core::sync::sync::Arc;
+use styx_mpc866m::{Mpc866mController, Mpc866mIRQn};
 use tracing::{debug, error, trace, warn};
 
 mod mtspr_manager;
 
 use mtspr_manager::*;
 
+/// Saved state of the [SystemInterfaceUnit].
+#[derive(Serialize, Deserialize)]
+struct SiuState {
+    immr_base_address: u64,
+    /// Timebase and decrementer.
+    mtspr_mgr: MtsprStateManager,
+}
+
 #[derive(Debug)]
 pub struct SystemInterfaceUnit {
     _family_variant: Mpc8xxVariants,
     mtspr_mgr: MtsprStateManager,
     immr_proxy_hooks: Vec<HookToken>,
     immr_base_address: u64,
+    /// IMMR base of a restored state, the hooks are moved there on the next tick.
+    restored_immr_base_address: Option<u64>,
     _cpm: Arc<CommunicationsProcessorModule>,
 }
 
@@ -32,14 +46,24 @@ fn mem_hook_read_proxy(
     size: u32,
     value: &mut [u8],
 ) -> Result<(), UnknownError> {
-    match core
+    let offset = match core
         .event_controller
         .peripherals
         .get::<SystemInterfaceUnit>()
     {
-        Some(siu) => siu.read_memory_hook(address, size, value),
-        None => error!("SIU not found"),
-    }
+        Some(siu) => {
+            siu.read_memory_hook(address, size, value);
+            siu.immr_offset(address)
+        }
+        None => {
+            error!("SIU not found");
+            return Ok(());
+        }
+    };
+
+    // the interrupt controllers live in the event controller
+    let cec = core.event_controller.get_impl::<Mpc866mController>()?;
+    cec.read_immr(offset, &mut value[..size as usize]);
     Ok(())
 }
 
@@ -49,14 +73,23 @@ fn mem_hook_write_proxy(
     size: u32,
     value: &[u8],
 ) -> Result<(), UnknownError> {
-    match core
+    let offset = match core
         .event_controller
         .peripherals
         .get::<SystemInterfaceUnit>()
     {
-        Some(siu) => siu.write_memory_hook(address, size, value),
-        None => error!("SIU not found"),
-    }
+        Some(siu) => {
+            siu.write_memory_hook(address, size, value);
+            siu.immr_offset(address)
+        }
+        None => {
+            error!("SIU not found");
+            return Ok(());
+        }
+    };
+
+    let cec = core.event_controller.get_impl::<Mpc866mController>()?;
+    cec.write_immr(offset, &value[..size as usize]);
     Ok(())
 }
 
@@ -67,6 +100,7 @@ impl SystemInterfaceUnit {
             mtspr_mgr: MtsprStateManager::new(),
             immr_proxy_hooks: Default::default(),
             immr_base_address: 0,
+            restored_immr_base_address: None,
             _cpm: CommunicationsProcessorModule::new_arc(variant).expect("Bad MPC8xx variant"),
         }
     }
@@ -113,19 +147,21 @@ impl SystemInterfaceUnit {
         Ok(())
     }
 
+    fn immr_offset(&self, address: u64) -> u32 {
+        (address - self.immr_base_address) as u32
+    }
+
     fn read_memory_hook(&self, address: u64, size: u32, value: &[u8]) {
-        let offset = address - self.immr_base_address;
+        let offset = self.immr_offset(address);
 
         // get the range being read from
-        if let Ok(register) = immr::register_search(offset as u32, size) {
+        if let Ok(register) = immr::register_search(offset, size) {
             trace!(
                 " [READ] ImmrRegister{{name: `{}`, value: `{:?}`, address: {:#x}}}",
                 register.abbreviation(),
                 &value[..size as usize],
                 address,
             );
-
-            // now send the data to the proper peripherals
         } else {
             warn!(
                 "Failed to find IMMR register at {:#x}, size: {:#x}",
@@ -135,10 +171,10 @@ impl SystemInterfaceUnit {
     }
 
     fn write_memory_hook(&self, address: u64, size: u32, value: &[u8]) {
-        let offset = address - self.immr_base_address;
+        let offset = self.immr_offset(address);
 
         // get the range being written to
-        if let Ok(register) = immr::register_search(offset as u32, size) {
+        if let Ok(register) = immr::register_search(offset, size) {
             if register.name() == "DPRAM" {
                 trace!(
                     "[WRITE] ImmrRegister{{name: `{}`, value: `{:?}`, address: {:#x}}}",
@@ -153,8 +189,6 @@ impl SystemInterfaceUnit {
                     &value[..size as usize],
                 );
             }
-
-            // now send the data to the proper peripherals
         } else {
             warn!(
                 "Failed to find IMMR register at {:#x}, size: {:#x}",
@@ -187,4 +221,47 @@ impl Peripheral for SystemInterfaceUnit {
     fn name(&self) -> &str {
         "System Interface Unit"
     }
+
+    /// Counts the decrementer down, one tick per instruction.
+    fn tick(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        if let Some(base_address) = self.restored_immr_base_address.take() {
+            if base_address != self.immr_base_address {
+                self.set_immr_hooks(cpu, base_address)?;
+            }
+        }
+
+        if self.mtspr_mgr.decrement(delta.count) {
+            trace!("decrementer expired");
+            event_controller.latch(Mpc866mIRQn::Decrementer as ExceptionNumber)?;
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<u64> {
+        self.mtspr_mgr.ticks_until_exception()
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&SiuState {
+            immr_base_address: self
+                .restored_immr_base_address
+                .unwrap_or(self.immr_base_address),
+            mtspr_mgr: self.mtspr_mgr.clone(),
+        })?)
+    }
+
+    /// The IMMR hooks can only be moved with the cpu, so a relocated IMMR is applied on the
+    /// next tick.
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: SiuState = state.get()?;
+        self.restored_immr_base_address = Some(state.immr_base_address);
+        self.mtspr_mgr = state.mtspr_mgr;
+        Ok(())
+    }
 }
@@ -3,23 +3,26 @@
 use derive_more::Display;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
 use styx_core::errors::UnknownError;
 use styx_core::{
     cpu::arch::ppc32::Ppc32Register,
     hooks::CoreHandle,
     prelude::{CpuBackend, CpuBackendExt},
 };
-use tracing::{error, info, trace, warn};
+use tracing::{info, trace, warn};
 
 use crate::system_interface_unit::SystemInterfaceUnit;
 
 /// Manages the state if the internal effects / status of
 /// internal MTSPR SPR's
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MtsprStateManager {
     tblw: u32,
     tbuw: u32,
     dec: u32,
+    /// The decrementer only counts once software wrote it.
+    dec_enabled: bool,
 }
 
 impl MtsprStateManager {
@@ -28,8 +31,26 @@ impl MtsprStateManager {
             tblw: 0,
             tbuw: 0,
             dec: 0,
+            dec_enabled: false,
         }
     }
+
+    /// Count the decrementer down by `ticks`, returns true if it passed zero, which raises a
+    /// decrementer exception.
+    pub fn decrement(&mut self, ticks: u64) -> bool {
+        if !self.dec_enabled {
+            return false;
+        }
+        let before = self.dec;
+        self.dec = before.wrapping_sub(ticks as u32);
+        // the exception is raised when the msb goes from 0 to 1
+        (before as i32) >= 0 && ticks > before as u64
+    }
+
+    /// Ticks until [`MtsprStateManager::decrement()`] raises the next decrementer exception.
+    pub fn ticks_until_exception(&self) -> Option<u64> {
+        (self.dec_enabled && (self.dec as i32) >= 0).then(|| self.dec as u64 + 1)
+    }
 }
 
 const MTSPR_INSN_MASK: u32 = 0xfc_00_03_ff;
@@ -38,6 +59,8 @@ const MTSPR_REG_OPERAND_MASK: u32 = 0x03e0_0000;
 const MTSPR_REG_OPERAND_SHIFT: u8 = 21;
 const MTSPR_SPR_OPERAND_MASK: u32 = 0x001f_f800;
 const MTSPR_SPR_OPERAND_SHIFT: u8 = 11;
+const MFSPR_INSN_MASK: u32 = 0xfc_00_07_fe;
+const MFSPR_INSN_BITS: u32 = 0x7c_00_02_a6;
 
 #[inline]
 fn is_mtspr(b: u32) -> bool {
@@ -50,6 +73,12 @@ fn is_mtspr(b: u32) -> bool {
     b & MTSPR_INSN_MASK == MTSPR_INSN_BITS
 }
 
+/// `mfspr` shares the register and SPR operand layout of `mtspr`
+#[inline]
+fn is_mfspr(b: u32) -> bool {
+    b & MFSPR_INSN_MASK == MFSPR_INSN_BITS
+}
+
 #[allow(non_snake_case)]
 const fn FLIP_SPR(bits: u16) -> u16 {
     let lower = bits & 0x1fu16;
@@ -281,8 +310,8 @@ pub(crate) fn mtspr_proxy(proc: CoreHandle) -> Result<(), UnknownError> {
             }
             SprEnum::DEC => {
                 trace!("executing {:?} impl", mtspr);
-                error!("DEC = {:#x}", mtspr.value);
                 siu.mtspr_mgr.dec = mtspr.value;
+                siu.mtspr_mgr.dec_enabled = true;
                 skip_cpu_insn(proc.cpu);
             }
             SprEnum::CTR | SprEnum::LR => {
@@ -301,7 +330,70 @@ pub(crate) fn mtspr_proxy(proc: CoreHandle) -> Result<(), UnknownError> {
                 );
             }
         }
+    } else if is_mfspr(insn_bytes) && MtsprInstruction::parse_spr(insn_bytes) == Some(SprEnum::DEC)
+    {
+        // the backend does not know the decrementer value
+        let siu = proc
+            .event_controller
+            .peripherals
+            .get::<SystemInterfaceUnit>()
+            .unwrap();
+        let register = MtsprInstruction::parse_register(insn_bytes).unwrap();
+        trace!("MFSPR @ {:#x}: {:?} = DEC", pc, register);
+        proc.cpu.write_register(register, siu.mtspr_mgr.dec)?;
+        skip_cpu_insn(proc.cpu);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use styx_core::snapshot::ComponentState;
+
+    #[test]
+    fn test_decrementer() {
+        let mut mgr = MtsprStateManager::new();
+        assert!(!mgr.decrement(100));
+
+        assert_eq!(None, mgr.ticks_until_exception());
+
+        mgr.dec = 10;
+        mgr.dec_enabled = true;
+        assert_eq!(Some(11), mgr.ticks_until_exception());
+        assert!(!mgr.decrement(10));
+        assert_eq!(0, mgr.dec);
+        assert_eq!(Some(1), mgr.ticks_until_exception());
+        assert!(mgr.decrement(1));
+        // keeps counting without raising again
+        assert!(!mgr.decrement(1));
+        assert_eq!(0xFFFF_FFFE, mgr.dec);
+        assert_eq!(None, mgr.ticks_until_exception());
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let mgr = MtsprStateManager {
+            tblw: 1,
+            tbuw: 2,
+            dec: 3,
+            dec_enabled: true,
+        };
+        let state = ComponentState::new(&mgr).unwrap();
+        assert_eq!(mgr, state.get().unwrap());
+    }
+
+    #[test]
+    fn test_mfspr_dec() {
+        // mfspr r3, DEC
+        let insn = 0x7c76_02a6;
+        assert!(is_mfspr(insn));
+        assert!(!is_mtspr(insn));
+        assert_eq!(Some(SprEnum::DEC), MtsprInstruction::parse_spr(insn));
+        assert_eq!(
+            Some(Ppc32Register::R3),
+            MtsprInstruction::parse_register(insn)
+        );
+    }
+}
@@ -2,6 +2,9 @@
 
 use bitfield_struct::bitfield;
 use enum_map::EnumMap;
+use serde::{Deserialize, Serialize};
+use styx_core::errors::anyhow::anyhow;
+use styx_core::errors::UnknownError;
 use styx_core::{cpu::arch::ppc32::Ppc32Register, prelude::CpuBackend};
 use tracing::{debug, trace};
 
@@ -43,6 +46,14 @@ pub struct EventsContainer {
     msr: Register,
 }
 
+/// Serializable runtime state of an [EventsContainer].
+#[derive(Serialize, Deserialize)]
+pub struct EventsContainerState {
+    /// `(enabled, latched)` for each event, in [Event] order.
+    events: Vec<(bool, bool)>,
+    msr_prev_value: u32,
+}
+
 #[derive(Debug)]
 pub enum LatchError {
     /// Event is already latched.
@@ -109,6 +120,31 @@ impl EventsContainer {
         self.exceptions[event].enabled = enabled;
     }
 
+    pub fn save_state(&self) -> EventsContainerState {
+        EventsContainerState {
+            events: self
+                .exceptions
+                .values()
+                .map(|state| (state.enabled, state.latched))
+                .collect(),
+            msr_prev_value: self.msr.prev_value(),
+        }
+    }
+
+    pub fn restore_state(&mut self, state: EventsContainerState) -> Result<(), UnknownError> {
+        if state.events.len() != self.exceptions.len() {
+            return Err(anyhow!("saved event state has the wrong number of events"));
+        }
+
+        for (exception, (enabled, latched)) in self.exceptions.values_mut().zip(state.events) {
+            exception.enabled = enabled;
+            exception.latched = latched;
+        }
+        self.msr.set_prev_value(state.msr_prev_value);
+
+        Ok(())
+    }
+
     pub fn code_hook(&mut self, cpu: &mut dyn CpuBackend) {
         self.msr = self.msr.clone().update_clone(cpu, |cpu, value| {
             let msr = MachineStateRegisterBitfield::from_bits(value);
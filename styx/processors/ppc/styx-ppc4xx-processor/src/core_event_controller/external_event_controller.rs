@@ -88,6 +88,8 @@ use styx_core::{
 
 use super::{Event, UnknownError};
 
+use serde::{Deserialize, Serialize};
+
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use tracing::debug;
@@ -108,6 +110,15 @@ const MER_HARDWARE_ENABLE: u32 = 0x2;
 const EVENT_UART: u32 = 1 << 6;
 const EVENT_ETHERNET: u32 = 1 << 5;
 
+/// Serializable state of the [ExternalEventController] registers.
+#[derive(Serialize, Deserialize)]
+pub struct ExternalEventControllerState {
+    master_enable: bool,
+    hardware_enable: bool,
+    ier: u32,
+    isr: u32,
+}
+
 pub struct ExternalEventController {
     self_ref: Weak<ExternalEventController>,
     // ME bit from the MER
@@ -179,6 +190,24 @@ impl ExternalEventController {
         Ok(())
     }
 
+    pub fn save_state(&self) -> ExternalEventControllerState {
+        ExternalEventControllerState {
+            master_enable: self.master_enable.load(Ordering::Acquire),
+            hardware_enable: self.hardware_enable.load(Ordering::Acquire),
+            ier: self.ier.load(Ordering::Acquire),
+            isr: self.isr.load(Ordering::Acquire),
+        }
+    }
+
+    pub fn restore_state(&self, state: &ExternalEventControllerState) {
+        self.master_enable
+            .store(state.master_enable, Ordering::Release);
+        self.hardware_enable
+            .store(state.hardware_enable, Ordering::Release);
+        self.ier.store(state.ier, Ordering::Release);
+        self.isr.store(state.isr, Ordering::Release);
+    }
+
     pub fn reset(&self, mmu: &mut Mmu) -> Result<(), UnknownError> {
         // clear the memory mapped registers
         mmu.data().write(ISR_OFFSET).bytes(&[0; 0x20])?;
@@ -8,9 +8,11 @@ use std::sync::Arc;
 
 pub use event::*;
 use exception::*;
-use external_event_controller::ExternalEventController;
+use external_event_controller::{ExternalEventController, ExternalEventControllerState};
+use serde::{Deserialize, Serialize};
 use styx_core::event_controller::{ActivateIRQnError, Exception, OptionalFeatureError};
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_core::{cpu::arch::ppc32::Ppc32Register, event_controller::InterruptExecuted};
 
 use tokio::runtime::Handle;
@@ -31,6 +33,16 @@ impl Register {
         }
     }
 
+    /// Last value seen in the register.
+    pub fn prev_value(&self) -> u32 {
+        self.prev_value
+    }
+
+    /// Overwrite the last seen value, used when restoring a snapshot.
+    pub fn set_prev_value(&mut self, value: u32) {
+        self.prev_value = value;
+    }
+
     pub fn update(
         &mut self,
         cpu: &mut dyn CpuBackend,
@@ -55,6 +67,14 @@ impl Register {
     }
 }
 
+/// Serialized [`CoreEventController`] state.
+#[derive(Serialize, Deserialize)]
+struct CoreEventControllerState {
+    exceptions: EventsContainerState,
+    external_controller: ExternalEventControllerState,
+    interrupt_stack: Vec<ExceptionNumber>,
+}
+
 pub struct CoreEventController {
     exceptions: EventsContainer,
     /// reference to the external interrupt controller that handles events from peripherals
@@ -229,6 +249,25 @@ impl EventControllerImpl for CoreEventController {
         Ok(())
     }
 
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&CoreEventControllerState {
+            exceptions: self.exceptions.save_state(),
+            external_controller: self.external_controller.save_state(),
+            interrupt_stack: self.interrupt_stack.clone(),
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: CoreEventControllerState = state.get()?;
+
+        self.exceptions.restore_state(state.exceptions)?;
+        self.external_controller
+            .restore_state(&state.external_controller);
+        self.interrupt_stack = state.interrupt_stack;
+
+        Ok(())
+    }
+
     fn current_exception(&mut self) -> Result<Option<Exception>, OptionalFeatureError> {
         let item = self.interrupt_stack.first();
         Ok(match item {
@@ -25,11 +25,13 @@
 //!
 mod service;
 
+use serde::{Deserialize, Serialize};
 use service::EthernetControllerService;
 use styx_core::errors::UnknownError;
 use styx_core::grpc::io::ethernet::ethernet_port_server::EthernetPortServer;
 use styx_core::grpc::io::ethernet::EthernetPacket;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 
 use crate::core_event_controller::Event;
 use derivative::Derivative;
@@ -96,6 +98,17 @@ impl std::fmt::Display for Mac {
     }
 }
 
+/// Saved state of the [EthernetController], received packets are kept as `(frame, crc)`.
+#[derive(Serialize, Deserialize)]
+struct EthernetState {
+    rx_fifo: Vec<(Vec<u8>, u32)>,
+    mac_addr: [u8; 6],
+    global_interrupts_enabled: bool,
+    tx_interrupts_enabled: bool,
+    rx_interrupts_enabled: bool,
+    tx_len: usize,
+}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct EthernetController {
@@ -276,6 +289,36 @@ impl Peripheral for EthernetController {
     fn reset(&mut self, _cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
         self.reset_state(mmu)
     }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&EthernetState {
+            rx_fifo: self
+                .rx_fifo
+                .iter()
+                .map(|packet| (packet.frame.clone(), packet.crc))
+                .collect(),
+            mac_addr: self.mac_addr.0,
+            global_interrupts_enabled: self.global_interrupts_enabled,
+            tx_interrupts_enabled: self.tx_interrupts_enabled,
+            rx_interrupts_enabled: self.rx_interrupts_enabled,
+            tx_len: self.tx_len,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: EthernetState = state.get()?;
+        self.rx_fifo = state
+            .rx_fifo
+            .into_iter()
+            .map(|(frame, crc)| EthernetPacket { frame, crc })
+            .collect();
+        self.mac_addr = state.mac_addr.into();
+        self.global_interrupts_enabled = state.global_interrupts_enabled;
+        self.tx_interrupts_enabled = state.tx_interrupts_enabled;
+        self.rx_interrupts_enabled = state.rx_interrupts_enabled;
+        self.tx_len = state.tx_len;
+        Ok(())
+    }
 }
 
 fn verify_packet(our_mac: &Mac, packet: &EthernetPacket) -> bool {
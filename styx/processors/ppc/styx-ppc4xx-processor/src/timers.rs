@@ -16,13 +16,12 @@
 //! All timer facilities run of off the same base clock which is different than
 //! the processor clock rate. The emulated base clock is defined in
 //! [TIMER_BASE_CLOCK_HZ]. Clocking is emulated in the [TimersInner::update()]
-//! method which is called at intervals by the [timer_cpu_loop_hook()].
+//! method which is called on every peripheral tick with the number of timer
+//! clocks that passed during the executed instructions, see [TimerClock].
 //!
-//! [TimersInner::update()] is not called at base clock frequency, instead it is
-//! called at a fractional frequency (defined in [timer_cpu_loop_hook()]) and
-//! incrementors/decrementors are modified by the inverse fraction of that
-//! frequency. This way timers react with a fixed frequency that doesn't bog
-//! down emulator performance.
+//! The PIT schedules a deadline at the instruction it reaches zero, so its
+//! interrupt is raised at the same instruction on every run. Writes to the PIT
+//! or TCR are picked up on the next tick.
 //!
 //! ## Timer Base (register)
 //!
@@ -33,52 +32,112 @@
 //! The PIT is documented in *Section 6.2* and in [ProgrammableInterruptTimer].
 //!
 use bitfield_struct::bitfield;
-use std::sync::{Arc, Mutex};
 use styx_core::cpu::arch::ppc32::Ppc32Register;
 use styx_core::errors::UnknownError;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 
 use crate::core_event_controller::{Event, Register};
+use serde::{Deserialize, Serialize};
 
 const CPU_CLOCK_HZ: f64 = 400_000_000.;
 
 /// Timer clock speed in hertz.
 const TIMER_BASE_CLOCK_HZ: f64 = 1_666_666.;
 
+/// Update timers after N instructions. Improves performance by
+/// reducing register writes.
+const UPDATE_INSTRUCTIONS: u64 = 1_000;
+/// Ratio of the Timer Base Clock to the CPU Clock.
+///
+/// This will be used to ensure accurate timers based on how fast the CPU is
+/// executing.
+const TIMER_CLOCK_RATIO: f64 = TIMER_BASE_CLOCK_HZ / CPU_CLOCK_HZ;
+/// Scale up how fast the timer base clock increments.
+///
+/// This makes the emulation innaccruate but ramps up PIT/FIT to normal
+/// speeds given slow emulation
+const SCALE: f64 = 1500.0;
+/// Timer clocks per [UPDATE_INSTRUCTIONS] executed instructions.
+const TIMER_INCREMENT: u64 = (UPDATE_INSTRUCTIONS as f64 * TIMER_CLOCK_RATIO * SCALE) as u64;
+
+/// Converts executed instructions to timer base clocks.
+///
+/// Timers advance [TIMER_INCREMENT] clocks every [UPDATE_INSTRUCTIONS]
+/// instructions, the fraction of a clock left over is carried to the next
+/// conversion.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+struct TimerClock {
+    /// Timer clocks times [UPDATE_INSTRUCTIONS] not yet passed to the timers.
+    remainder: u64,
+}
+
+impl TimerClock {
+    /// Timer clocks that passed during `instructions`.
+    fn advance(&mut self, instructions: u64) -> u32 {
+        let scaled = instructions * TIMER_INCREMENT + self.remainder;
+        self.remainder = scaled % UPDATE_INSTRUCTIONS;
+        (scaled / UPDATE_INSTRUCTIONS) as u32
+    }
+
+    /// Instructions until at least `clocks` timer clocks passed.
+    fn instructions_until(&self, clocks: u32) -> u64 {
+        (clocks as u64 * UPDATE_INSTRUCTIONS)
+            .saturating_sub(self.remainder)
+            .div_ceil(TIMER_INCREMENT)
+    }
+}
+
 pub struct Timers {
-    inner: Arc<Mutex<TimersInner>>,
+    inner: TimersInner,
 }
 
 impl Timers {
     pub fn new(cpu: &mut dyn CpuBackend) -> Self {
-        let inner = Arc::new(Mutex::new(TimersInner::new(cpu)));
-        Self { inner }
+        Self {
+            inner: TimersInner::new(cpu),
+        }
     }
 }
 
+/// Serialized [`Timers`] state.
+#[derive(Serialize, Deserialize)]
+struct TimersState {
+    control: u32,
+    status: u32,
+    pit_auto_reload: u32,
+    pit_last_value: u32,
+    pit_enabled: bool,
+    clock: TimerClock,
+}
+
 struct TimersInner {
     control: Register,
     _status: Register,
     pit: ProgrammableInterruptTimer,
+    clock: TimerClock,
 }
 
 impl Peripheral for Timers {
-    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
-        let timers = self.inner.clone();
-        let mut current_instruction = 0;
-
-        log::debug!("timers initialized");
-        proc.core.cpu.code_hook(
-            u64::MIN,
-            u64::MAX,
-            Box::new(move |proc: CoreHandle| {
-                timer_cpu_loop_hook(proc, &mut current_instruction, timers.as_ref());
-                Ok(())
-            }),
-        )?;
+    fn tick(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        let timer_clocks = self.inner.clock.advance(delta.count);
+        self.inner.update(cpu, event_controller, timer_clocks);
         Ok(())
     }
 
+    fn next_deadline(&self) -> Option<u64> {
+        self.inner
+            .pit
+            .clocks_until_interrupt()
+            .map(|clocks| self.inner.clock.instructions_until(clocks))
+    }
+
     fn irqs(&self) -> Vec<ExceptionNumber> {
         [
             Event::ProgrammableInterruptTimer,
@@ -91,6 +150,29 @@ impl Peripheral for Timers {
     fn name(&self) -> &str {
         "Timers"
     }
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        let inner = &self.inner;
+        Ok(ComponentState::new(&TimersState {
+            control: inner.control.prev_value(),
+            status: inner._status.prev_value(),
+            pit_auto_reload: inner.pit.auto_reload,
+            pit_last_value: inner.pit.last_value,
+            pit_enabled: inner.pit.enabled,
+            clock: inner.clock,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: TimersState = state.get()?;
+        let inner = &mut self.inner;
+        inner.control.set_prev_value(state.control);
+        inner._status.set_prev_value(state.status);
+        inner.pit.auto_reload = state.pit_auto_reload;
+        inner.pit.last_value = state.pit_last_value;
+        inner.pit.enabled = state.pit_enabled;
+        inner.clock = state.clock;
+        Ok(())
+    }
 }
 
 impl TimersInner {
@@ -99,11 +181,12 @@ impl TimersInner {
             control: Register::new(Ppc32Register::Tcr, cpu),
             _status: Register::new(Ppc32Register::Tsr, cpu),
             pit: ProgrammableInterruptTimer::new(cpu),
+            clock: TimerClock::default(),
         }
     }
 
-    fn code_hook(&mut self, proc: &mut CoreHandle) {
-        self.control.update(proc.cpu, |cpu, value| {
+    fn update_control(&mut self, cpu: &mut dyn CpuBackend) {
+        self.control.update(cpu, |cpu, value| {
             let pc = cpu.pc().unwrap();
             log::debug!("TCR new value! {value:x} @ 0x{pc:X}");
             let tcr = TimerControlRegisterBitfield::from_bits(value);
@@ -114,10 +197,15 @@ impl TimersInner {
         })
     }
 
-    fn update(&mut self, proc: &mut CoreHandle, timer_clocks: u32) {
-        self.code_hook(proc);
-        self.pit.update(proc, timer_clocks);
-        update_timer_base(proc.cpu, timer_clocks);
+    fn update(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        event_controller: &mut dyn EventControllerImpl,
+        timer_clocks: u32,
+    ) {
+        self.update_control(cpu);
+        self.pit.update(cpu, event_controller, timer_clocks);
+        update_timer_base(cpu, timer_clocks);
     }
 }
 
@@ -126,7 +214,7 @@ impl TimersInner {
 /// Documented in section 6.2, the PIT decrements at the same rate as the time
 /// base ([update_timer_base()]).
 ///
-/// A PIT value of 1 (i.e. after decrementing) triggers the exception if MSR's
+/// The PIT reaching 0 (i.e. decrementing from 1) triggers the exception if MSR's
 /// External Exceptions is enabled and TCR's PIT Interrupt Enable is enabled.
 ///
 /// SHORTCOMING: Currently the msr and tcr will set the enable of the pit
@@ -134,7 +222,7 @@ impl TimersInner {
 /// instead check the condition when checking for enabled interrupts instead of
 /// updating when registers are update.
 ///
-/// After hitting 1, the behavior depends on the value of the tcr's auto reload
+/// After hitting 0, the behavior depends on the value of the tcr's auto reload
 /// enable bit. If it's set, the pit should reset back to the last value that
 /// was written to it. If auto reload is disabled, the pit should sit at 0.
 struct ProgrammableInterruptTimer {
@@ -155,8 +243,18 @@ impl ProgrammableInterruptTimer {
         }
     }
 
-    fn update(&mut self, proc: &mut CoreHandle, timer_clocks: u32) {
-        let new_pit = proc.cpu.read_register::<u32>(Ppc32Register::Pit).unwrap();
+    /// Timer clocks until the PIT reaches 0, `None` if it is stopped.
+    fn clocks_until_interrupt(&self) -> Option<u32> {
+        (self.enabled && self.last_value != 0).then_some(self.last_value)
+    }
+
+    fn update(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        event_controller: &mut dyn EventControllerImpl,
+        timer_clocks: u32,
+    ) {
+        let new_pit = cpu.read_register::<u32>(Ppc32Register::Pit).unwrap();
         if new_pit != self.last_value {
             log::debug!("pit set to {new_pit}");
             // new write
@@ -164,18 +262,16 @@ impl ProgrammableInterruptTimer {
             self.last_value = new_pit;
         }
 
-        // info!("HERHEHR: enabled: {} new_pit: {}", self.enabled, new_pit);
         // will not decrement if 0
         if self.enabled && new_pit != 0 {
             let final_pit = {
-                let pit_subtract_option = new_pit.checked_sub(timer_clocks);
-                if let Some(new_pit) = pit_subtract_option {
-                    new_pit
+                if timer_clocks < new_pit {
+                    new_pit - timer_clocks
                 } else {
-                    proc.event_controller
+                    event_controller
                         .latch(Event::ProgrammableInterruptTimer.event_number() as i32)
                         .unwrap();
-                    let tcr = TimerControlRegisterBitfield::load(proc.cpu);
+                    let tcr = TimerControlRegisterBitfield::load(cpu);
                     if tcr.auto_reload_enable() {
                         self.auto_reload
                     } else {
@@ -184,47 +280,12 @@ impl ProgrammableInterruptTimer {
                 }
             };
 
-            proc.cpu
-                .write_register(Ppc32Register::Pit, final_pit)
-                .unwrap();
+            cpu.write_register(Ppc32Register::Pit, final_pit).unwrap();
             self.last_value = final_pit;
         }
     }
 }
 
-/// Code hook to increment Timer Base Clock and update all other timers.
-fn timer_cpu_loop_hook(
-    mut proc: CoreHandle,
-    current_instruction: &mut u64,
-    timers: &Mutex<TimersInner>,
-) {
-    /// Update timer_increment after N instructions. Improves performance by
-    /// reducing timers locks and register writes.
-    const UPDATE_INSTRUCTIONS: f64 = 1_000.;
-    /// Ratio of the Timer Base Clock to the CPU Clock.
-    ///
-    /// This will be used to ensure accurate timers based on how fast the CPU is
-    /// executing.
-    const TIMER_CLOCK_RATIO: f64 = TIMER_BASE_CLOCK_HZ / CPU_CLOCK_HZ;
-    /// Scale up how fast the timer base clock increments.
-    ///
-    /// This makes the emulation innaccruate but ramps up PIT/FIT to normal
-    /// speeds given slow emulation
-    const SCALE: f64 = 1500.0;
-
-    /// Amount to increment timers by to maintain the same clock speed.
-    ///
-    /// Timers incremented by `TIMER_INCREMENT` every `UPDATE_INSTRUCTIONS` instructions.
-    const TIMER_INCREMENT: u32 = (UPDATE_INSTRUCTIONS * TIMER_CLOCK_RATIO * SCALE) as u32;
-    *current_instruction += 1;
-    if *current_instruction > UPDATE_INSTRUCTIONS as u64 {
-        *current_instruction = 0;
-
-        let mut timers = timers.lock().unwrap();
-        timers.update(&mut proc, TIMER_INCREMENT);
-    }
-}
-
 /// Updates timer base after timers update.
 ///
 /// Documented in **Section 6.1**, the Timer Base is a 64 bit int that is
@@ -265,3 +326,25 @@ impl TimerControlRegisterBitfield {
         Self::from_bits(cpu.read_register::<u32>(Ppc32Register::Tcr).unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_clock() {
+        let mut clock = TimerClock::default();
+        assert_eq!(TIMER_INCREMENT as u32, clock.advance(UPDATE_INSTRUCTIONS));
+
+        // fractions of a clock are carried over
+        let clocks: u32 = (0..UPDATE_INSTRUCTIONS).map(|_| clock.advance(1)).sum();
+        assert_eq!(TIMER_INCREMENT as u32, clocks);
+
+        // the deadline is the first instruction reaching the clock count
+        clock.advance(3);
+        let instructions = clock.instructions_until(100);
+        let mut before = clock;
+        assert!(before.advance(instructions - 1) < 100);
+        assert!(clock.advance(instructions) >= 100);
+    }
+}
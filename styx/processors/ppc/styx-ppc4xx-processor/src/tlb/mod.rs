@@ -71,6 +71,9 @@ use styx_core::memory::{
 };
 use styx_core::prelude::log::warn;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+
+use serde::{Deserialize, Serialize};
 
 #[cfg(all(
     target_arch = "x86_64",
@@ -97,6 +100,16 @@ const PPC405_TLB_LO: u32 = 1;
 const PPC405_TLB_L1: u32 = 0b1;
 const PPC405_TLB_L2: u32 = 0b10;
 
+/// Serialized [`Ppc405Tlb`] state.
+#[derive(Serialize, Deserialize)]
+struct Ppc405TlbState {
+    inst_relocate_enabled: bool,
+    data_relocate_enabled: bool,
+    current_pid: u8,
+    /// `(pid, raw_hi, raw_lo)` for each unified tlb record
+    records: Vec<(u8, u32, u32)>,
+}
+
 pub struct Ppc405Tlb {
     /// is address translation for code enabled
     inst_relocate_enabled: bool,
@@ -331,6 +344,41 @@ impl TlbImpl for Ppc405Tlb {
         Ok(())
     }
 
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        // only the unified tlb is saved, the shadow tlbs are refilled from it on demand
+        let records = self.tlb_data[..UNIFIED_TLB_CAPACITY]
+            .iter()
+            .map(|record| (record.pid, record.raw_hi, record.raw_lo))
+            .collect();
+
+        Ok(ComponentState::new(&Ppc405TlbState {
+            inst_relocate_enabled: self.inst_relocate_enabled,
+            data_relocate_enabled: self.data_relocate_enabled,
+            current_pid: self.current_pid,
+            records,
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: Ppc405TlbState = state.get()?;
+        if state.records.len() != UNIFIED_TLB_CAPACITY {
+            return Err(anyhow!("saved tlb state has the wrong number of records"));
+        }
+
+        *self = Self::new();
+        for (idx, (pid, raw_hi, raw_lo)) in state.records.into_iter().enumerate() {
+            self.current_pid = pid;
+            self.tlbwe_high(idx, raw_hi)?;
+            self.tlbwe_low(idx, raw_lo)?;
+        }
+
+        self.inst_relocate_enabled = state.inst_relocate_enabled;
+        self.data_relocate_enabled = state.data_relocate_enabled;
+        self.current_pid = state.current_pid;
+
+        Ok(())
+    }
+
     fn translate_va(
         &mut self,
         v_address: u64,
@@ -70,6 +70,7 @@
 //!
 use styx_core::errors::UnknownError;
 use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
 use styx_peripherals::uart::{IntoUartImpl, UartImpl};
 use tokio::sync::broadcast;
 use tracing::{debug, trace, warn};
@@ -253,4 +254,12 @@ impl UartImpl for UartPortInner {
 
         Ok(())
     }
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(self.intr_enabled, &self.buffer))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        (self.intr_enabled, self.buffer) = state.get()?;
+        Ok(())
+    }
 }
@@ -36,7 +36,22 @@ pub mod superh {
     pub use styx_superh2a_processor as superh2a;
 }
 
+pub mod msp430 {
+    pub use styx_msp430_processor as msp430;
+}
+
+pub mod mips {
+    pub use styx_mips32_processor as mips32;
+}
+
+pub mod devicetree {
+    pub use styx_devicetree_processor as devicetree;
+}
+
 mod uconf {
+    use crate::devicetree::devicetree::{DeviceTreeConfig, DeviceTreeProcessor};
+    use styx_core::{core::builder::ProcessorImpl, prelude::UnknownError};
+
     styx_uconf::register_component!(register processor: id = ppc_4xx, component = crate::ppc::ppc4xx::PowerPC405Builder::new());
     // todo, broke because ArchMetaVariant
     // styx_uconf::register_component_is_config!(register processor: id = ppc_mpc8xx, config = crate::ppc::powerquicci::Mpc8xxBuilder);
@@ -51,6 +66,16 @@ mod uconf {
     styx_uconf::register_component_config!(register processor: id = bfin, component = crate::bfin::blackfin::BlackfinBuilder);
 
     styx_uconf::register_component!(register processor: id = superh, component = crate::superh::superh2a::SuperH2aBuilder);
+
+    styx_uconf::register_component_config!(register processor: id = msp430, component = crate::msp430::msp430::Msp430Builder);
+
+    styx_uconf::register_component_config!(register processor: id = mips32, component = crate::mips::mips32::Mips32Builder);
+
+    styx_uconf::register_component_config_fn!(register processor: id = devicetree, component_fn = build_devicetree, config = DeviceTreeConfig);
+
+    fn build_devicetree(config: DeviceTreeConfig) -> Result<Box<dyn ProcessorImpl>, UnknownError> {
+        Ok(Box::new(DeviceTreeProcessor::from_config(config)?))
+    }
 }
 
 /// A processor with no peripherals or event controller, purely instruction emulation.
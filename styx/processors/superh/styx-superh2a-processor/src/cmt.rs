@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Compare match timer (CMT)
+//!
+//! Two 16 bit up counters clocked from `Pφ` divided by 8, 32, 128 or 512 (`CMCSR[CKS]`), one
+//! `Pφ` clock is counted per executed instruction.
+//!
+//! Registers:
+//!
+//!  Address    | Name   | Description
+//! ----------------------------------------------------------
+//!  0xFFFEC000 | CMSTR  | `STR0` (bit 0) and `STR1` (bit 1) start the channels
+//!  0xFFFEC002 | CMCSR0 | `CMF` (bit 7), `CMIE` (bit 6) and `CKS` (bits 1-0)
+//!  0xFFFEC004 | CMCNT0 | counter
+//!  0xFFFEC006 | CMCOR0 | compare match constant
+//!  0xFFFEC008 | CMCSR1 |
+//!  0xFFFEC00A | CMCNT1 |
+//!  0xFFFEC00C | CMCOR1 |
+//!
+//! A counter is cleared on the count after it matches `CMCOR`, setting `CMF`. The channel requests
+//! [`CMI0_VECTOR`]/[`CMI1_VECTOR`] while `CMF` and `CMIE` are set, `CMF` is cleared by writing 0.
+use serde::{Deserialize, Serialize};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::trace;
+
+use crate::intc::{IntcHandle, CMI0_VECTOR, CMI1_VECTOR};
+use crate::mmio::{self, merge, Mapped};
+use crate::timer;
+
+const CMSTR: u64 = 0xFFFE_C000;
+/// `CMCSR0`, channel 1 registers follow channel 0 at `+6`.
+const CMCSR0: u64 = 0xFFFE_C002;
+const CHANNEL_STRIDE: u64 = 6;
+const CMCNT_OFFSET: u64 = 2;
+const CMCOR_OFFSET: u64 = 4;
+/// Last byte of `CMCOR1`.
+const CMT_END: u64 = CMCSR0 + CHANNEL_STRIDE + CMCOR_OFFSET + 1;
+
+const CMCSR_CMF: u16 = 1 << 7;
+const CMCSR_CMIE: u16 = 1 << 6;
+const CMCSR_CKS: u16 = 0b11;
+
+const VECTORS: [ExceptionNumber; 2] = [CMI0_VECTOR, CMI1_VECTOR];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmtRegister {
+    Cmstr,
+    Cmcsr(usize),
+    Cmcnt(usize),
+    Cmcor(usize),
+}
+
+fn lookup(address: u64) -> Option<Mapped<CmtRegister>> {
+    let register = address & !1;
+    let register = match register {
+        CMSTR => CmtRegister::Cmstr,
+        CMCSR0..=CMT_END => {
+            let offset = register - CMCSR0;
+            let channel = (offset / CHANNEL_STRIDE) as usize;
+            match offset % CHANNEL_STRIDE {
+                0 => CmtRegister::Cmcsr(channel),
+                CMCNT_OFFSET => CmtRegister::Cmcnt(channel),
+                _ => CmtRegister::Cmcor(channel),
+            }
+        }
+        _ => return None,
+    };
+    Some(Mapped::new(register, address & !1, 2))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CmtChannel {
+    cmcsr: u16,
+    cmcnt: u16,
+    cmcor: u16,
+    /// `Pφ` clocks not yet counted because of the divider.
+    remainder: u64,
+}
+
+impl Default for CmtChannel {
+    fn default() -> Self {
+        Self {
+            cmcsr: 0,
+            cmcnt: 0,
+            cmcor: 0xFFFF,
+            remainder: 0,
+        }
+    }
+}
+
+impl CmtChannel {
+    fn divider(&self) -> u64 {
+        8 << (2 * (self.cmcsr & CMCSR_CKS))
+    }
+
+    /// Count `clocks` `Pφ` clocks.
+    fn advance(&mut self, clocks: u64) {
+        let clocks = self.remainder + clocks;
+        let divider = self.divider();
+        self.remainder = clocks % divider;
+        let counts = clocks / divider;
+
+        // the match with CMCOR clears the counter on the next count, setting CMF
+        let period = self.cmcor as u32 + 1;
+        if timer::wraps(self.cmcnt, counts, period) {
+            trace!("cmt compare match");
+            self.cmcsr |= CMCSR_CMF;
+        }
+        self.cmcnt = timer::advance(self.cmcnt, counts, period);
+    }
+
+    /// `Pφ` clocks until the next compare match requests an interrupt.
+    fn next_request(&self) -> Option<u64> {
+        if self.cmcsr & CMCSR_CMIE == 0 {
+            return None;
+        }
+        let counts = timer::counts_until_wrap(self.cmcnt, self.cmcor as u32 + 1);
+        Some(timer::clocks_until(counts, self.divider(), self.remainder))
+    }
+
+    fn request(&self) -> bool {
+        self.cmcsr & CMCSR_CMF != 0 && self.cmcsr & CMCSR_CMIE != 0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CmtRegisters {
+    cmstr: u16,
+    channels: [CmtChannel; 2],
+}
+
+pub struct Cmt {
+    registers: CmtRegisters,
+    intc: IntcHandle,
+}
+
+impl Cmt {
+    pub(crate) fn new(intc: IntcHandle) -> Self {
+        Self {
+            registers: Default::default(),
+            intc,
+        }
+    }
+
+    fn started(&self, channel: usize) -> bool {
+        self.registers.cmstr & (1 << channel) != 0
+    }
+
+    /// Count `clocks` `Pφ` clocks on the started channels.
+    fn advance(&mut self, clocks: u64) {
+        for channel in 0..2 {
+            if self.started(channel) {
+                self.registers.channels[channel].advance(clocks);
+            }
+        }
+        self.update_requests();
+    }
+
+    fn update_requests(&self) {
+        for (channel, vector) in self.registers.channels.iter().zip(VECTORS) {
+            self.intc.set_request(vector, channel.request());
+        }
+    }
+
+    fn read(&self, register: CmtRegister) -> u32 {
+        let registers = &self.registers;
+        let value = match register {
+            CmtRegister::Cmstr => registers.cmstr,
+            CmtRegister::Cmcsr(n) => registers.channels[n].cmcsr,
+            CmtRegister::Cmcnt(n) => registers.channels[n].cmcnt,
+            CmtRegister::Cmcor(n) => registers.channels[n].cmcor,
+        };
+        value as u32
+    }
+
+    fn write(&mut self, register: CmtRegister, value: u32, mask: u32) {
+        let merge = |old: u16| merge(old as u32, value, mask) as u16;
+        let registers = &mut self.registers;
+        match register {
+            CmtRegister::Cmstr => registers.cmstr = merge(registers.cmstr) & 0b11,
+            CmtRegister::Cmcsr(n) => {
+                let channel = &mut registers.channels[n];
+                // CMF can only be cleared
+                let cmf = channel.cmcsr & merge(channel.cmcsr) & CMCSR_CMF;
+                channel.cmcsr = cmf | (merge(channel.cmcsr) & (CMCSR_CMIE | CMCSR_CKS));
+            }
+            CmtRegister::Cmcnt(n) => {
+                registers.channels[n].cmcnt = merge(registers.channels[n].cmcnt)
+            }
+            CmtRegister::Cmcor(n) => {
+                registers.channels[n].cmcor = merge(registers.channels[n].cmcor)
+            }
+        }
+        self.update_requests();
+    }
+}
+
+impl Peripheral for Cmt {
+    fn name(&self) -> &str {
+        "CMT"
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        VECTORS.to_vec()
+    }
+
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let cpu = &mut proc.core.cpu;
+        cpu.add_hook(StyxHook::memory_read(CMSTR..=CMT_END, register_read_hook))?;
+        cpu.add_hook(StyxHook::memory_write(CMSTR..=CMT_END, register_write_hook))?;
+        Ok(())
+    }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.registers = Default::default();
+        self.update_requests();
+        Ok(())
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        self.advance(delta.count);
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<u64> {
+        (0..2)
+            .filter(|channel| self.started(*channel))
+            .filter_map(|channel| self.registers.channels[channel].next_request())
+            .min()
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.registers)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.registers = state.get()?;
+        self.update_requests();
+        Ok(())
+    }
+}
+
+fn register_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    _size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let cmt = proc.event_controller.peripherals.get_expect::<Cmt>()?;
+    mmio::read(address, data, lookup, |register| cmt.read(register));
+    Ok(())
+}
+
+fn register_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    _size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let cmt = proc.event_controller.peripherals.get_expect::<Cmt>()?;
+    mmio::write(address, data, lookup, |register, value, mask| {
+        cmt.write(register, value, mask)
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: u32 = 0xFFFF;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            Some(CmtRegister::Cmstr),
+            lookup(CMSTR + 1).map(|m| m.register)
+        );
+        assert_eq!(
+            Some(CmtRegister::Cmcor(0)),
+            lookup(0xFFFE_C006).map(|m| m.register)
+        );
+        assert_eq!(
+            Some(CmtRegister::Cmcsr(1)),
+            lookup(0xFFFE_C008).map(|m| m.register)
+        );
+        assert_eq!(
+            Some(CmtRegister::Cmcnt(1)),
+            lookup(0xFFFE_C00B).map(|m| m.register)
+        );
+        assert_eq!(None, lookup(CMT_END + 1));
+    }
+
+    #[test]
+    fn test_compare_match() {
+        let intc = IntcHandle::default();
+        let mut cmt = Cmt::new(intc.clone());
+        cmt.write(CmtRegister::Cmcor(0), 9, ALL);
+        // Pφ/32
+        cmt.write(CmtRegister::Cmcsr(0), (CMCSR_CMIE | 1) as u32, ALL);
+
+        // stopped
+        cmt.advance(1000);
+        assert_eq!(0, cmt.read(CmtRegister::Cmcnt(0)));
+        assert_eq!(None, cmt.next_deadline());
+
+        cmt.write(CmtRegister::Cmstr, 1, ALL);
+        assert_eq!(Some(10 * 32), cmt.next_deadline());
+        cmt.advance(9 * 32 + 31);
+        assert_eq!(9, cmt.read(CmtRegister::Cmcnt(0)));
+        assert_eq!(Some(1), cmt.next_deadline());
+        assert!(!intc.is_requested(CMI0_VECTOR));
+
+        cmt.advance(1);
+        assert_eq!(0, cmt.read(CmtRegister::Cmcnt(0)));
+        assert!(cmt.read(CmtRegister::Cmcsr(0)) & CMCSR_CMF as u32 != 0);
+        assert!(intc.is_requested(CMI0_VECTOR));
+
+        // writing 1 keeps CMF, writing 0 clears it
+        cmt.write(CmtRegister::Cmcsr(0), (CMCSR_CMF | CMCSR_CMIE) as u32, ALL);
+        assert!(intc.is_requested(CMI0_VECTOR));
+        cmt.write(CmtRegister::Cmcsr(0), CMCSR_CMIE as u32, ALL);
+        assert!(!intc.is_requested(CMI0_VECTOR));
+        assert!(!intc.is_requested(CMI1_VECTOR));
+    }
+}
@@ -0,0 +1,421 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! SH-2A interrupt controller (INTC).
+//!
+//! Interrupts are identified by their vector number, vector `n` jumps to the address stored at
+//! `VBR + 4 * n`. Maskable sources get their priority level from the `IPR` registers and are taken
+//! when their level is above `SR[I]`, the [`NMI`](NMI_VECTOR) always has level 16.
+//!
+//! Peripherals hold an [`IntcHandle`] and assert their request while its flag and enable bits are
+//! set, like the level sensitive request lines of the real chip. Latching a vector instead makes a
+//! one shot request, latching an `IRQn` vector sets its flag in `IRQRR`.
+//!
+//! Taking an interrupt pushes `SR` then `PC`, sets `SR[I]` to the level of the interrupt and saves
+//! `R0`-`R14`, `GBR`, `MACH`, `MACL` and `PR` to a register bank if banks are enabled for that
+//! level. `RESBANK` restores the most recent bank, the pcode treats it as a `NOP` so it is
+//! emulated from a code hook. `RTE` is handled by the pcode, the hook only tells the controller
+//! the interrupt finished.
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use styx_core::cpu::arch::superh::SuperHRegister;
+use styx_core::event_controller::{ActivateIRQnError, InterruptExecuted, Peripherals};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::{debug, trace, warn};
+
+use crate::mmio;
+use registers::{IntcRegisters, INTC_REGISTERS_HIGH, INTC_REGISTERS_LOW};
+pub use vectors::*;
+
+mod registers;
+mod vectors;
+
+/// Number of register banks.
+const BANK_COUNT: usize = 15;
+
+/// `SR[I]`
+const SR_I_SHIFT: u32 = 4;
+const SR_I_MASK: u32 = 0xF << SR_I_SHIFT;
+/// `SR[BO]`, set when an interrupt found all register banks used.
+const SR_BO: u32 = 1 << 14;
+
+/// Level of the NMI, above any `SR[I]` mask.
+const NMI_LEVEL: u8 = 16;
+
+/// `RTE`
+const RTE_INSN: u16 = 0x002B;
+/// `RESBANK`
+const RESBANK_INSN: u16 = 0x005B;
+
+/// Registers saved to a register bank.
+const BANKED_REGISTERS: [SuperHRegister; 19] = [
+    SuperHRegister::R0,
+    SuperHRegister::R1,
+    SuperHRegister::R2,
+    SuperHRegister::R3,
+    SuperHRegister::R4,
+    SuperHRegister::R5,
+    SuperHRegister::R6,
+    SuperHRegister::R7,
+    SuperHRegister::R8,
+    SuperHRegister::R9,
+    SuperHRegister::R10,
+    SuperHRegister::R11,
+    SuperHRegister::R12,
+    SuperHRegister::R13,
+    SuperHRegister::R14,
+    SuperHRegister::Gbr,
+    SuperHRegister::Mach,
+    SuperHRegister::Macl,
+    SuperHRegister::Pr,
+];
+
+/// Saved registers of one register bank.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegisterBank {
+    /// Values of [`BANKED_REGISTERS`].
+    registers: Vec<u32>,
+    /// `VTO`, the vector of the interrupt that saved the bank.
+    vector: ExceptionNumber,
+}
+
+/// An interrupt being handled, finished by the `RTE` that pops its stack frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ActiveInterrupt {
+    vector: ExceptionNumber,
+    /// `R15` after pushing `SR` and `PC`.
+    sp: u32,
+}
+
+/// Requests asserted by peripherals through an [`IntcHandle`].
+type Requests = Arc<Mutex<BTreeSet<ExceptionNumber>>>;
+
+/// Lets peripherals assert and deassert their interrupt requests.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IntcHandle {
+    requests: Requests,
+}
+
+impl IntcHandle {
+    /// Assert or deassert the request for `vector`.
+    pub(crate) fn set_request(&self, vector: ExceptionNumber, asserted: bool) {
+        let mut requests = self.requests.lock().unwrap();
+        if asserted {
+            requests.insert(vector);
+        } else {
+            requests.remove(&vector);
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_requested(&self, vector: ExceptionNumber) -> bool {
+        self.requests.lock().unwrap().contains(&vector)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct IntcState {
+    registers: IntcRegisters,
+    requests: BTreeSet<ExceptionNumber>,
+    pending: BTreeSet<ExceptionNumber>,
+    nmi: bool,
+    exception: Option<ExceptionNumber>,
+    banks: Vec<RegisterBank>,
+    active: Vec<ActiveInterrupt>,
+}
+
+#[derive(Debug, Default)]
+pub struct Intc {
+    registers: IntcRegisters,
+    requests: Requests,
+    /// One shot requests from [`EventControllerImpl::latch()`].
+    pending: BTreeSet<ExceptionNumber>,
+    nmi: bool,
+    /// Exception raised by the controller itself, taken before anything else.
+    exception: Option<ExceptionNumber>,
+    banks: Vec<RegisterBank>,
+    active: Vec<ActiveInterrupt>,
+}
+
+impl Intc {
+    pub(crate) fn get_handle(&self) -> IntcHandle {
+        IntcHandle {
+            requests: self.requests.clone(),
+        }
+    }
+
+    /// Highest priority maskable request above `mask` with its level.
+    fn next_pending(&self, mask: u8) -> Option<(ExceptionNumber, u8)> {
+        let requests = self.requests.lock().unwrap();
+        let all = requests
+            .iter()
+            .chain(self.pending.iter())
+            .copied()
+            .chain(self.registers.irq_requests());
+        self.registers.select(all, mask)
+    }
+
+    /// Address of the handler for `vector`.
+    fn handler(
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        vector: ExceptionNumber,
+    ) -> Result<u32, UnknownError> {
+        let vbr = cpu.read_register::<u32>(SuperHRegister::Vbr)?;
+        Ok(mmu
+            .data()
+            .read(vbr.wrapping_add(4 * vector as u32) as u64)
+            .be()
+            .u32()?)
+    }
+
+    /// Save the banked registers, returns false if all banks are used.
+    fn save_bank(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        vector: ExceptionNumber,
+    ) -> Result<bool, UnknownError> {
+        if self.banks.len() == BANK_COUNT {
+            return Ok(false);
+        }
+        let registers = BANKED_REGISTERS
+            .iter()
+            .map(|register| cpu.read_register::<u32>(*register))
+            .collect::<Result<_, _>>()?;
+        self.banks.push(RegisterBank { registers, vector });
+        Ok(true)
+    }
+
+    /// `RESBANK`, restore the most recently saved bank.
+    fn restore_bank(&mut self, cpu: &mut dyn CpuBackend) -> Result<(), UnknownError> {
+        let Some(bank) = self.banks.pop() else {
+            warn!("RESBANK with no register bank saved");
+            self.exception = Some(BANK_UNDERFLOW_VECTOR);
+            return Ok(());
+        };
+        trace!("restoring register bank of vector {}", bank.vector);
+        for (register, value) in BANKED_REGISTERS.iter().zip(bank.registers) {
+            cpu.write_register(*register, value)?;
+        }
+        Ok(())
+    }
+
+    /// Take `vector`, setting `SR[I]` to `level` if it is an interrupt.
+    ///
+    /// `bank` saves the banked registers, if all banks are used `SR[BO]` is set or the bank
+    /// overflow exception is taken instead, depending on `IBNR[BOVE]`.
+    fn take_interrupt(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        mut vector: ExceptionNumber,
+        level: Option<u8>,
+        bank: bool,
+    ) -> Result<(), UnknownError> {
+        let pc = cpu.pc()? as u32;
+        let mut sr = cpu.read_register::<u32>(SuperHRegister::Sr)?;
+        let sp = cpu.read_register::<u32>(SuperHRegister::R15)?;
+
+        let sp = sp.wrapping_sub(4);
+        mmu.data().write(sp as u64).be().value(sr)?;
+        let sp = sp.wrapping_sub(4);
+        mmu.data().write(sp as u64).be().value(pc)?;
+
+        if let Some(level) = level {
+            let level = level.min(0xF) as u32;
+            sr = (sr & !SR_I_MASK) | (level << SR_I_SHIFT);
+        }
+        if bank && !self.save_bank(cpu, vector)? {
+            if self.registers.bank_overflow_exception() {
+                debug!("register bank overflow taking vector {vector}");
+                vector = BANK_OVERFLOW_VECTOR;
+            } else {
+                sr |= SR_BO;
+            }
+        }
+
+        let handler = Self::handler(cpu, mmu, vector)?;
+        debug!("taking vector {vector} at {pc:#x}, handler {handler:#x}");
+
+        cpu.write_register(SuperHRegister::R15, sp)?;
+        cpu.write_register(SuperHRegister::Sr, sr)?;
+        cpu.set_pc(handler as u64)?;
+        self.active.push(ActiveInterrupt { vector, sp });
+        Ok(())
+    }
+}
+
+impl EventControllerImpl for Intc {
+    fn next(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+        _peripherals: &mut Peripherals,
+    ) -> Result<InterruptExecuted, UnknownError> {
+        if let Some(vector) = self.exception.take() {
+            self.take_interrupt(cpu, mmu, vector, None, false)?;
+            return Ok(InterruptExecuted::Executed);
+        }
+        if self.nmi {
+            self.nmi = false;
+            self.take_interrupt(cpu, mmu, NMI_VECTOR, Some(NMI_LEVEL), false)?;
+            return Ok(InterruptExecuted::Executed);
+        }
+
+        let sr = cpu.read_register::<u32>(SuperHRegister::Sr)?;
+        let mask = ((sr & SR_I_MASK) >> SR_I_SHIFT) as u8;
+        let Some((vector, level)) = self.next_pending(mask) else {
+            return Ok(InterruptExecuted::NotExecuted);
+        };
+
+        self.pending.remove(&vector);
+        let bank = self.registers.uses_bank(level);
+        self.take_interrupt(cpu, mmu, vector, Some(level), bank)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    fn latch(&mut self, event: ExceptionNumber) -> Result<(), ActivateIRQnError> {
+        match event {
+            NMI_VECTOR => self.nmi = true,
+            _ if (IRQ0_VECTOR..IRQ0_VECTOR + IRQ_COUNT as ExceptionNumber).contains(&event) => {
+                self.registers.latch_irq((event - IRQ0_VECTOR) as usize)
+            }
+            _ if priority_field(event).is_some() => {
+                self.pending.insert(event);
+            }
+            _ => return Err(ActivateIRQnError::InvalidIRQn(event)),
+        }
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        irq: ExceptionNumber,
+        cpu: &mut dyn CpuBackend,
+        mmu: &mut Mmu,
+    ) -> Result<InterruptExecuted, ActivateIRQnError> {
+        if !(0..=255).contains(&irq) {
+            return Err(ActivateIRQnError::InvalidIRQn(irq));
+        }
+
+        self.take_interrupt(cpu, mmu, irq, None, false)?;
+        Ok(InterruptExecuted::Executed)
+    }
+
+    fn finish_interrupt(
+        &mut self,
+        cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+    ) -> Option<ExceptionNumber> {
+        let sp = cpu.read_register::<u32>(SuperHRegister::R15).ok()?;
+        // an RTE returning from something else, e.g. a TRAPA
+        if self.active.last()?.sp != sp {
+            return None;
+        }
+        let finished = self.active.pop()?;
+        trace!("finished vector {}", finished.vector);
+        Some(finished.vector)
+    }
+
+    /// Requests with priority level 0 are never accepted and don't count.
+    fn has_pending_events(&self) -> bool {
+        self.exception.is_some() || self.nmi || self.next_pending(0).is_some()
+    }
+
+    fn init(&mut self, cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        cpu.add_hook(StyxHook::code(.., instruction_hook))?;
+        for (start, end) in [INTC_REGISTERS_LOW, INTC_REGISTERS_HIGH] {
+            cpu.add_hook(StyxHook::memory_read(start..=end, register_read_hook))?;
+            cpu.add_hook(StyxHook::memory_write(start..=end, register_write_hook))?;
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self, cpu: &mut dyn CpuBackend, mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.registers = Default::default();
+        self.requests.lock().unwrap().clear();
+        self.pending.clear();
+        self.nmi = false;
+        self.exception = None;
+        self.banks.clear();
+        self.active.clear();
+
+        cpu.write_register(SuperHRegister::Sr, SR_I_MASK)?;
+        cpu.write_register(SuperHRegister::Vbr, 0u32)?;
+        let pc = mmu.data().read(0).be().u32()?;
+        let sp = mmu.data().read(4).be().u32()?;
+        cpu.write_register(SuperHRegister::R15, sp)?;
+        cpu.set_pc(pc as u64)?;
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&IntcState {
+            registers: self.registers.clone(),
+            requests: self.requests.lock().unwrap().clone(),
+            pending: self.pending.clone(),
+            nmi: self.nmi,
+            exception: self.exception,
+            banks: self.banks.clone(),
+            active: self.active.clone(),
+        })?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        let state: IntcState = state.get()?;
+        self.registers = state.registers;
+        *self.requests.lock().unwrap() = state.requests;
+        self.pending = state.pending;
+        self.nmi = state.nmi;
+        self.exception = state.exception;
+        self.banks = state.banks;
+        self.active = state.active;
+        Ok(())
+    }
+}
+
+/// Called on every instruction, emulates `RESBANK` and finishes interrupts on `RTE`.
+fn instruction_hook(proc: CoreHandle) -> Result<(), UnknownError> {
+    let pc = proc.cpu.pc()?;
+    match proc.mmu.read_u16_be_phys_code(pc) {
+        Ok(RTE_INSN) => {
+            trace!("rte at {pc:#x}");
+            proc.event_controller.finish_interrupt(proc.cpu, proc.mmu);
+        }
+        Ok(RESBANK_INSN) => {
+            trace!("resbank at {pc:#x}");
+            proc.event_controller
+                .get_impl::<Intc>()?
+                .restore_bank(proc.cpu)?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn register_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    _size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let intc = proc.event_controller.get_impl::<Intc>()?;
+    let banks = intc.banks.len();
+    mmio::read(address, data, registers::lookup, |register| {
+        intc.registers.read(register, banks)
+    });
+    Ok(())
+}
+
+fn register_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    _size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let intc = proc.event_controller.get_impl::<Intc>()?;
+    mmio::write(address, data, registers::lookup, |register, value, mask| {
+        intc.registers.write(register, value, mask)
+    });
+    Ok(())
+}
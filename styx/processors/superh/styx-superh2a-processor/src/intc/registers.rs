@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! INTC register state and interrupt prioritization.
+//!
+//! - `ICR0`, `ICR1` select the `NMI` and `IRQ` pin sense, they have no effect since pins are
+//!   driven by latching their vector.
+//! - `IRQRR` holds the `IRQn` request flags, a flag is cleared by writing 0 to it.
+//! - `IBCR` and `IBNR` control the register banks, `IBNR[BN]` is the number of used banks.
+//! - `IPRxx` hold the 4 bit priority level of each source, level 0 masks the source.
+use serde::{Deserialize, Serialize};
+use styx_core::prelude::ExceptionNumber;
+use tracing::{debug, trace};
+
+use super::vectors::{priority_field, IRQ0_VECTOR, IRQ_COUNT};
+use crate::mmio::{merge, Mapped};
+
+const ICR0: u64 = 0xFFFE_0800;
+const ICR1: u64 = 0xFFFE_0802;
+const IRQRR: u64 = 0xFFFE_0806;
+const IBCR: u64 = 0xFFFE_080C;
+const IBNR: u64 = 0xFFFE_080E;
+const IPR01: u64 = 0xFFFE_0818;
+const IPR02: u64 = 0xFFFE_081A;
+const IPR05: u64 = 0xFFFE_0820;
+const IPR06: u64 = 0xFFFE_0C00;
+const IPR15: u64 = 0xFFFE_0C12;
+
+/// First range of INTC registers, `ICR0` to `IPR05`.
+pub(crate) const INTC_REGISTERS_LOW: (u64, u64) = (ICR0, IPR05 + 1);
+/// Second range of INTC registers, `IPR06` to `IPR15`.
+pub(crate) const INTC_REGISTERS_HIGH: (u64, u64) = (IPR06, IPR15 + 1);
+
+/// Number of the highest `IPR` register.
+const IPR_COUNT: usize = 15;
+
+/// `IBNR[BE]`
+const IBNR_BE_SHIFT: u16 = 14;
+/// `IBNR[BOVE]`
+const IBNR_BOVE: u16 = 1 << 13;
+const IBNR_WRITABLE: u16 = (0b11 << IBNR_BE_SHIFT) | IBNR_BOVE;
+
+/// Banks used for all interrupts except `NMI` and `UBC`.
+const BE_ALL: u16 = 0b00;
+/// Banks used for the levels enabled in `IBCR`.
+const BE_IBCR: u16 = 0b11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IntcRegister {
+    Icr0,
+    Icr1,
+    Irqrr,
+    Ibcr,
+    Ibnr,
+    Ipr(usize),
+}
+
+/// Address of `IPRn`, if it exists.
+fn ipr_address(n: usize) -> Option<u64> {
+    match n {
+        1 => Some(IPR01),
+        2 => Some(IPR02),
+        5 => Some(IPR05),
+        6..=IPR_COUNT => Some(IPR06 + 2 * (n as u64 - 6)),
+        _ => None,
+    }
+}
+
+/// INTC register containing `address`.
+pub(crate) fn lookup(address: u64) -> Option<Mapped<IntcRegister>> {
+    let register = address & !1;
+    let register = match register {
+        ICR0 => IntcRegister::Icr0,
+        ICR1 => IntcRegister::Icr1,
+        IRQRR => IntcRegister::Irqrr,
+        IBCR => IntcRegister::Ibcr,
+        IBNR => IntcRegister::Ibnr,
+        _ => IntcRegister::Ipr((1..=IPR_COUNT).find(|n| ipr_address(*n) == Some(register))?),
+    };
+    Some(Mapped::new(register, address & !1, 2))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct IntcRegisters {
+    icr0: u16,
+    icr1: u16,
+    irqrr: u16,
+    ibcr: u16,
+    ibnr: u16,
+    /// Indexed by `IPR` number, unused numbers stay 0.
+    ipr: [u16; IPR_COUNT + 1],
+}
+
+impl IntcRegisters {
+    /// `IRQn` pin was asserted.
+    pub(crate) fn latch_irq(&mut self, irq: usize) {
+        self.irqrr |= 1 << irq;
+    }
+
+    /// Vectors of the `IRQn` pins with their flag set in `IRQRR`.
+    pub(crate) fn irq_requests(&self) -> impl Iterator<Item = ExceptionNumber> + '_ {
+        (0..IRQ_COUNT)
+            .filter(|irq| self.irqrr & (1 << irq) != 0)
+            .map(|irq| IRQ0_VECTOR + irq as ExceptionNumber)
+    }
+
+    /// Priority level of `vector`, 0 if it is masked or not a maskable source.
+    pub(crate) fn level(&self, vector: ExceptionNumber) -> u8 {
+        priority_field(vector)
+            .map(|(ipr, field)| ((self.ipr[ipr] >> (4 * field)) & 0xF) as u8)
+            .unwrap_or_default()
+    }
+
+    /// Highest priority request above the `SR[I]` `mask`, returns the vector and its level.
+    ///
+    /// Higher levels win, on a tie the lower vector wins.
+    pub(crate) fn select(
+        &self,
+        requests: impl Iterator<Item = ExceptionNumber>,
+        mask: u8,
+    ) -> Option<(ExceptionNumber, u8)> {
+        requests
+            .map(|vector| (vector, self.level(vector)))
+            .filter(|(_, level)| *level > mask)
+            .min_by_key(|(vector, level)| (u8::MAX - level, *vector))
+    }
+
+    /// Are registers saved to a bank when taking an interrupt at `level`?
+    pub(crate) fn uses_bank(&self, level: u8) -> bool {
+        match self.ibnr >> IBNR_BE_SHIFT {
+            BE_ALL => true,
+            BE_IBCR => self.ibcr & (1 << level) != 0,
+            _ => false,
+        }
+    }
+
+    /// `IBNR[BOVE]`, take a bank overflow exception instead of dropping the registers.
+    pub(crate) fn bank_overflow_exception(&self) -> bool {
+        self.ibnr & IBNR_BOVE != 0
+    }
+
+    /// Read `register`, `banks` is the number of used register banks.
+    pub(crate) fn read(&self, register: IntcRegister, banks: usize) -> u32 {
+        let value = match register {
+            IntcRegister::Icr0 => self.icr0,
+            IntcRegister::Icr1 => self.icr1,
+            IntcRegister::Irqrr => self.irqrr,
+            IntcRegister::Ibcr => self.ibcr,
+            IntcRegister::Ibnr => self.ibnr | banks as u16,
+            IntcRegister::Ipr(n) => self.ipr[n],
+        };
+        value as u32
+    }
+
+    /// Write the `mask`ed bits of `value` to `register`.
+    pub(crate) fn write(&mut self, register: IntcRegister, value: u32, mask: u32) {
+        trace!("{register:?} <- {value:#x} (mask {mask:#x})");
+        let merge = |old: u16| merge(old as u32, value, mask) as u16;
+        match register {
+            // only NMIE is writable
+            IntcRegister::Icr0 => self.icr0 = merge(self.icr0) & 0x0100,
+            IntcRegister::Icr1 => self.icr1 = merge(self.icr1),
+            // flags are cleared by writing 0
+            IntcRegister::Irqrr => self.irqrr &= (value | !mask) as u16,
+            // E0 is reserved
+            IntcRegister::Ibcr => self.ibcr = merge(self.ibcr) & !1,
+            IntcRegister::Ibnr => self.ibnr = merge(self.ibnr) & IBNR_WRITABLE,
+            IntcRegister::Ipr(n) => self.ipr[n] = merge(self.ipr[n]),
+        }
+        if register == IntcRegister::Ibnr && self.ibnr >> IBNR_BE_SHIFT == 0b10 {
+            debug!("reserved IBNR[BE] setting, register banks disabled");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intc::vectors::{CMI0_VECTOR, CMI1_VECTOR};
+
+    const ALL: u32 = 0xFFFF;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            Some(Mapped::new(IntcRegister::Ipr(2), IPR02, 2)),
+            lookup(IPR02 + 1)
+        );
+        assert_eq!(
+            Some(Mapped::new(IntcRegister::Ipr(8), 0xFFFE_0C04, 2)),
+            lookup(0xFFFE_0C04)
+        );
+        assert_eq!(None, lookup(0xFFFE_081C));
+        assert_eq!(None, lookup(IPR15 + 2));
+    }
+
+    #[test]
+    fn test_select() {
+        let mut registers = IntcRegisters::default();
+        let requests = [CMI1_VECTOR, CMI0_VECTOR, IRQ0_VECTOR];
+        // everything masked at level 0
+        assert_eq!(None, registers.select(requests.into_iter(), 0));
+
+        registers.write(IntcRegister::Ipr(8), 0x5500, ALL);
+        assert_eq!(
+            Some((CMI0_VECTOR, 5)),
+            registers.select(requests.into_iter(), 0)
+        );
+        assert_eq!(None, registers.select(requests.into_iter(), 5));
+
+        registers.write(IntcRegister::Ipr(8), 0x0600, 0x0F00);
+        registers.write(IntcRegister::Ipr(1), 0x6000, ALL);
+        assert_eq!(
+            Some((IRQ0_VECTOR, 6)),
+            registers.select(requests.into_iter(), 5)
+        );
+    }
+
+    #[test]
+    fn test_irq_flags_and_banks() {
+        let mut registers = IntcRegisters::default();
+        registers.latch_irq(2);
+        registers.latch_irq(5);
+        assert_eq!(
+            vec![IRQ0_VECTOR + 2, IRQ0_VECTOR + 5],
+            registers.irq_requests().collect::<Vec<_>>()
+        );
+        registers.write(IntcRegister::Irqrr, !(1 << 2), ALL);
+        assert_eq!(0x20, registers.read(IntcRegister::Irqrr, 0));
+
+        assert!(registers.uses_bank(3));
+        registers.write(IntcRegister::Ibnr, 0xC000 | IBNR_BOVE as u32, ALL);
+        assert!(!registers.uses_bank(3));
+        registers.write(IntcRegister::Ibcr, 1 << 3, ALL);
+        assert!(registers.uses_bank(3));
+        assert!(registers.bank_overflow_exception());
+        assert_eq!(0xE002, registers.read(IntcRegister::Ibnr, 2));
+    }
+}
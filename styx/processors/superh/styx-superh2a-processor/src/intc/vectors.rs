@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Vector numbers of the interrupt sources and their priority fields in the `IPR` registers.
+//!
+//! Vector numbers and `IPR` fields follow the SH7211 layout, each `IPR` register holds four 4 bit
+//! levels, field 3 is bits 15-12 and field 0 is bits 3-0.
+//!
+//! | Register | Field 3 | Field 2 | Field 1 | Field 0 |
+//! |----------|---------|---------|---------|---------|
+//! | `IPR01` | `IRQ0` | `IRQ1` | `IRQ2` | `IRQ3` |
+//! | `IPR02` | `IRQ4` | `IRQ5` | `IRQ6` | `IRQ7` |
+//! | `IPR08` | `CMT0` | `CMT1` | | |
+//! | `IPR09` | `MTU2_0` `TGI` | `MTU2_0` `TCI` | `MTU2_1` `TGI` | `MTU2_1` `TCI` |
+//! | `IPR10` | `MTU2_2` `TGI` | `MTU2_2` `TCI` | `MTU2_3` `TGI` | `MTU2_3` `TCI` |
+//! | `IPR11` | `MTU2_4` `TGI` | `MTU2_4` `TCI` | | |
+//! | `IPR14` | `SCIF0` | `SCIF1` | `SCIF2` | `SCIF3` |
+use styx_core::prelude::ExceptionNumber;
+
+/// Register bank underflow, `RESBANK` with no bank saved.
+pub const BANK_UNDERFLOW_VECTOR: ExceptionNumber = 9;
+/// Register bank overflow, taken instead of an interrupt that finds all banks used while
+/// `IBNR[BOVE]` is set.
+pub const BANK_OVERFLOW_VECTOR: ExceptionNumber = 10;
+/// Non-maskable interrupt.
+pub const NMI_VECTOR: ExceptionNumber = 11;
+/// External interrupt `IRQ0`, `IRQ1` to `IRQ7` follow.
+pub const IRQ0_VECTOR: ExceptionNumber = 64;
+/// Number of `IRQ` pins.
+pub const IRQ_COUNT: usize = 8;
+/// CMT channel 0 compare match.
+pub const CMI0_VECTOR: ExceptionNumber = 140;
+/// CMT channel 1 compare match.
+pub const CMI1_VECTOR: ExceptionNumber = 144;
+
+/// First vector of each MTU2 channel, compare matches `TGIA` to `TGID` are `+0..=+3` and overflow
+/// `TCIV` is `+4`.
+const MTU2_VECTORS: [ExceptionNumber; 5] = [156, 164, 172, 180, 188];
+
+/// Compare match interrupt `TGIx` of MTU2 `channel`, `compare` 0 is `TGIA`.
+pub const fn tgi_vector(channel: usize, compare: usize) -> ExceptionNumber {
+    MTU2_VECTORS[channel] + compare as ExceptionNumber
+}
+
+/// Overflow interrupt `TCIV` of MTU2 `channel`.
+pub const fn tciv_vector(channel: usize) -> ExceptionNumber {
+    MTU2_VECTORS[channel] + 4
+}
+
+/// Receive error interrupt `ERI` of SCIF channel 0, `RXI`, `BRI` and `TXI` follow. Each channel
+/// has four vectors.
+pub const SCIF0_ERI_VECTOR: ExceptionNumber = 240;
+
+/// Receive FIFO data full interrupt `RXI` of SCIF `channel`.
+pub const fn rxi_vector(channel: usize) -> ExceptionNumber {
+    SCIF0_ERI_VECTOR + 4 * channel as ExceptionNumber + 1
+}
+
+/// Transmit FIFO data empty interrupt `TXI` of SCIF `channel`.
+pub const fn txi_vector(channel: usize) -> ExceptionNumber {
+    SCIF0_ERI_VECTOR + 4 * channel as ExceptionNumber + 3
+}
+
+/// `(first vector, last vector, IPR number, field)`
+const PRIORITY_FIELDS: [(ExceptionNumber, ExceptionNumber, usize, u32); 24] = [
+    (64, 64, 1, 3),
+    (65, 65, 1, 2),
+    (66, 66, 1, 1),
+    (67, 67, 1, 0),
+    (68, 68, 2, 3),
+    (69, 69, 2, 2),
+    (70, 70, 2, 1),
+    (71, 71, 2, 0),
+    (CMI0_VECTOR, CMI0_VECTOR, 8, 3),
+    (CMI1_VECTOR, CMI1_VECTOR, 8, 2),
+    (156, 159, 9, 3),
+    (160, 160, 9, 2),
+    (164, 165, 9, 1),
+    (168, 168, 9, 0),
+    (172, 173, 10, 3),
+    (176, 176, 10, 2),
+    (180, 183, 10, 1),
+    (184, 184, 10, 0),
+    (188, 191, 11, 3),
+    (192, 192, 11, 2),
+    (240, 243, 14, 3),
+    (244, 247, 14, 2),
+    (248, 251, 14, 1),
+    (252, 255, 14, 0),
+];
+
+/// `IPR` number and field holding the priority level of `vector`, [None] if it is not a
+/// maskable interrupt source.
+pub(crate) fn priority_field(vector: ExceptionNumber) -> Option<(usize, u32)> {
+    PRIORITY_FIELDS
+        .iter()
+        .find(|(first, last, ..)| (*first..=*last).contains(&vector))
+        .map(|(.., ipr, field)| (*ipr, *field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(158, tgi_vector(0, 2));
+        assert_eq!(Some((9, 3)), priority_field(tgi_vector(0, 2)));
+        assert_eq!(Some((10, 0)), priority_field(tciv_vector(3)));
+        assert_eq!(Some((14, 2)), priority_field(rxi_vector(1)));
+        assert_eq!(255, txi_vector(3));
+        assert_eq!(Some((1, 0)), priority_field(IRQ0_VECTOR + 3));
+        assert_eq!(None, priority_field(NMI_VECTOR));
+        assert_eq!(None, priority_field(166));
+    }
+}
@@ -1,25 +1,92 @@
 // SPDX-License-Identifier: BSD-2-Clause
 //! SuperH2a Processor
+//!
+//! Models an SH7201/SH7211 class SH-2A microcontroller. Has support for the
+//! [interrupt controller](intc), the [compare match timer](cmt), the
+//! [multi-function timer pulse unit 2](mtu2) and the four [SCIF](scif) serial channels, the
+//! remaining peripheral registers are plain memory.
+//!
+//! Memory map:
+//!
+//!  Start      | Size   | Description
+//! ----------------------------------------------------------
+//!  0x00000000 | 512KiB | on-chip ROM, the vector table is at its start
+//!  0x0C000000 | 64MiB  | SDRAM on `CS3`
+//!  0xFFF80000 | 32KiB  | on-chip RAM
+//!  0xFFFC0000 | 256KiB | on-chip peripheral registers
+//!
+//! Execution starts at the address in the power-on reset vector (vector 0) with the stack pointer
+//! from vector 1 after the target program is loaded.
+mod cmt;
+mod intc;
+mod mmio;
+mod mtu2;
+mod scif;
+mod timer;
+
+use cmt::Cmt;
+use intc::Intc;
+use mtu2::Mtu2;
 use styx_core::core::builder::{BuildProcessorImplArgs, ProcessorImpl};
-use styx_core::cpu::arch::superh::SuperHVariants;
+use styx_core::cpu::arch::superh::{SuperHRegister, SuperHVariants};
 use styx_core::cpu::arch::ArchEndian;
 use styx_core::cpu::PcodeBackend;
-use styx_core::event_controller::DummyEventController;
+use styx_core::memory::memory_region::MemoryRegion;
 use styx_core::memory::MemoryPermissions;
 use styx_core::prelude::*;
+use styx_peripherals::uart::UartController;
+use tracing::debug;
+
+pub use intc::{
+    rxi_vector, tciv_vector, tgi_vector, txi_vector, BANK_OVERFLOW_VECTOR, BANK_UNDERFLOW_VECTOR,
+    CMI0_VECTOR, CMI1_VECTOR, IRQ0_VECTOR, NMI_VECTOR,
+};
+
+/// Address of the power-on reset vector, the initial stack pointer follows.
+const RESET_VECTOR_ADDRESS: u64 = 0x0;
 
-/// Partial Implementation of a SuperH2a processor
-///
-/// Not implemented:
-/// - no event controller
-/// - no peripherals get attached
-/// - no default memory maps setup besides [`0x0` -> `0xffffffff`]
 #[derive(Default)]
 pub struct SuperH2aBuilder;
 impl SuperH2aBuilder {
-    /// Note: This is the bare minimum and needs to be revisited
     fn setup_address_space(&self, mmu: &mut Mmu) -> Result<(), UnknownError> {
-        mmu.memory_map(0, u32::MAX as u64, MemoryPermissions::all())?;
+        let mut regions = Vec::new();
+
+        let rom_start = 0x0000_0000;
+        let rom_size = 0x8_0000;
+        regions.push(MemoryRegion::new(
+            rom_start,
+            rom_size,
+            MemoryPermissions::READ | MemoryPermissions::EXEC,
+        )?);
+
+        let sdram_start = 0x0C00_0000;
+        let sdram_size = 0x400_0000;
+        regions.push(MemoryRegion::new(
+            sdram_start,
+            sdram_size,
+            MemoryPermissions::all(),
+        )?);
+
+        let ram_start = 0xFFF8_0000;
+        let ram_size = 0x8000;
+        regions.push(MemoryRegion::new(
+            ram_start,
+            ram_size,
+            MemoryPermissions::all(),
+        )?);
+
+        let peripherals_start = 0xFFFC_0000;
+        let peripherals_size = 0x4_0000;
+        regions.push(MemoryRegion::new(
+            peripherals_start,
+            peripherals_size,
+            MemoryPermissions::RW,
+        )?);
+
+        for region in regions {
+            mmu.add_memory_region(region)?;
+        }
+
         Ok(())
     }
 }
@@ -40,15 +107,119 @@ impl ProcessorImpl for SuperH2aBuilder {
 
         self.setup_address_space(&mut mmu)?;
 
+        let intc = Intc::default();
+        let handle = intc.get_handle();
+
+        let mut peripherals: Vec<Box<dyn Peripheral>> = Vec::new();
+        peripherals.push(Box::new(Cmt::new(handle.clone())));
+        peripherals.push(Box::new(Mtu2::new(handle.clone())));
+        peripherals.push(Box::new(UartController::new(scif::get_scifs(handle))));
+
         let mut hints = LoaderHints::new();
         hints.insert("arch".to_owned().into_boxed_str(), Box::new(Arch::SuperH));
 
         Ok(ProcessorBundle {
             cpu,
             mmu,
-            event_controller: Box::new(DummyEventController::default()),
-            peripherals: Vec::new(),
+            event_controller: Box::new(intc),
+            peripherals,
             loader_hints: hints,
         })
     }
+
+    fn init(&self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        // the hardware starts from the power-on reset vector, unless the program has none
+        let mmu = &mut proc.core.mmu;
+        let reset = mmu.data().read(RESET_VECTOR_ADDRESS).be().u32()?;
+        let stack = mmu.data().read(RESET_VECTOR_ADDRESS + 4).be().u32()?;
+        if reset != 0 {
+            debug!("starting at reset vector {reset:#x}");
+            proc.core.cpu.set_pc(reset as u64)?;
+        }
+        if stack != 0 {
+            proc.core.cpu.write_register(SuperHRegister::R15, stack)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM_START: u64 = 0x1000;
+    const HANDLER_START: u64 = 0x1040;
+    /// Interrupt counter in on-chip RAM.
+    const COUNTER: u64 = 0xFFF8_0000;
+
+    /// Starts CMT channel 0 with its interrupt at level 5 and checks the handler runs more than
+    /// once, so `RTE` restored `SR` and `RESBANK` restored the interrupted registers.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_cmt_interrupt() {
+        let program: &[u8] = &[
+            0xDF, 0x08, // mov.l @(0x1024), r15
+            0xD1, 0x09, // mov.l @(0x1028), r1
+            0xD0, 0x09, // mov.l @(0x102C), r0
+            0x21, 0x01, // mov.w r0, @r1 (IPR08 = 0x5000)
+            0xD1, 0x09, // mov.l @(0x1030), r1
+            0xE0, 0x63, // mov #99, r0
+            0x21, 0x01, // mov.w r0, @r1 (CMCOR0 = 99)
+            0xD1, 0x09, // mov.l @(0x1034), r1
+            0xE0, 0x40, // mov #0x40, r0
+            0x21, 0x01, // mov.w r0, @r1 (CMCSR0 = CMIE, Pφ/8)
+            0xD1, 0x08, // mov.l @(0x1038), r1
+            0xE0, 0x01, // mov #1, r0
+            0x21, 0x01, // mov.w r0, @r1 (CMSTR = STR0)
+            0xE0, 0x00, // mov #0, r0
+            0x40, 0x0E, // ldc r0, sr
+            0xAF, 0xFE, // bra $
+            0x00, 0x09, // nop
+            0x00, 0x09, // nop
+            0xFF, 0xF8, 0x80, 0x00, // stack top
+            0xFF, 0xFE, 0x0C, 0x04, // IPR08
+            0x00, 0x00, 0x50, 0x00, // CMT0 level 5
+            0xFF, 0xFE, 0xC0, 0x06, // CMCOR0
+            0xFF, 0xFE, 0xC0, 0x02, // CMCSR0
+            0xFF, 0xFE, 0xC0, 0x00, // CMSTR
+        ];
+        let handler: &[u8] = &[
+            0xD2, 0x05, // mov.l @(0x1058), r2
+            0x63, 0x22, // mov.l @r2, r3
+            0x73, 0x01, // add #1, r3
+            0x22, 0x32, // mov.l r3, @r2
+            0xD1, 0x04, // mov.l @(0x105C), r1
+            0x60, 0x11, // mov.w @r1, r0
+            0xE0, 0x40, // mov #0x40, r0
+            0x21, 0x01, // mov.w r0, @r1 (clear CMF)
+            0x00, 0x5B, // resbank
+            0x00, 0x2B, // rte
+            0x00, 0x09, // nop
+            0x00, 0x09, // nop
+            0xFF, 0xF8, 0x00, 0x00, // counter
+            0xFF, 0xFE, 0xC0, 0x02, // CMCSR0
+        ];
+
+        let mut proc = ProcessorBuilder::default()
+            .with_builder(SuperH2aBuilder)
+            .build()
+            .unwrap();
+
+        // the rom is not writable
+        let mmu = &mut proc.core.mmu;
+        mmu.sudo_code().write(PROGRAM_START).bytes(program).unwrap();
+        mmu.sudo_code().write(HANDLER_START).bytes(handler).unwrap();
+        mmu.sudo_code()
+            .write(4 * CMI0_VECTOR as u64)
+            .be()
+            .value(HANDLER_START as u32)
+            .unwrap();
+        proc.core.cpu.set_pc(PROGRAM_START).unwrap();
+
+        proc.run(5000).unwrap();
+
+        let interrupts = proc.core.mmu.data().read(COUNTER).be().u32().unwrap();
+        // a second interrupt at the same level needs SR restored by the first RTE
+        assert!(interrupts > 1, "timer interrupt taken {interrupts} times");
+    }
 }
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Helpers for the memory mapped register hooks of the on-chip peripherals.
+//!
+//! Registers are 8, 16 or 32 bits wide and big endian. A register hook looks up the registers an
+//! access touches, each touched register is read or written once so access side effects (e.g.
+//! popping a receive FIFO) happen once per access regardless of its size.
+
+/// Location of a memory mapped register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Mapped<R> {
+    pub register: R,
+    pub address: u64,
+    /// Width in bytes.
+    pub size: u64,
+}
+
+impl<R> Mapped<R> {
+    pub(crate) fn new(register: R, address: u64, size: u64) -> Self {
+        Self {
+            register,
+            address,
+            size,
+        }
+    }
+
+    /// Shift of the (big endian) byte at `address` in the register value.
+    fn byte_shift(&self, address: u64) -> u32 {
+        8 * (self.address + self.size - 1 - address) as u32
+    }
+}
+
+/// Registers touched by an access of `len` bytes at `address`, with the value of the access
+/// shifted into place and the mask of the accessed bytes.
+fn touched<R: Copy + PartialEq>(
+    address: u64,
+    data: &[u8],
+    lookup: impl Fn(u64) -> Option<Mapped<R>>,
+) -> Vec<(Mapped<R>, u32, u32)> {
+    let mut touched: Vec<(Mapped<R>, u32, u32)> = Vec::new();
+    for (address, byte) in (address..).zip(data.iter()) {
+        let Some(mapped) = lookup(address) else {
+            continue;
+        };
+        let shift = mapped.byte_shift(address);
+        let (value, mask) = ((*byte as u32) << shift, 0xFF << shift);
+        match touched
+            .iter_mut()
+            .find(|(seen, ..)| seen.register == mapped.register)
+        {
+            Some((_, seen_value, seen_mask)) => {
+                *seen_value |= value;
+                *seen_mask |= mask;
+            }
+            None => touched.push((mapped, value, mask)),
+        }
+    }
+    touched
+}
+
+/// Fill `data` read at `address` from the registers found by `lookup`, bytes not belonging to a
+/// register are left untouched.
+pub(crate) fn read<R: Copy + PartialEq>(
+    address: u64,
+    data: &mut [u8],
+    lookup: impl Fn(u64) -> Option<Mapped<R>>,
+    mut read: impl FnMut(R) -> u32,
+) {
+    for (mapped, ..) in touched(address, data, &lookup) {
+        let value = read(mapped.register);
+        for (address, byte) in (address..).zip(data.iter_mut()) {
+            if lookup(address).is_some_and(|other| other.register == mapped.register) {
+                *byte = (value >> mapped.byte_shift(address)) as u8;
+            }
+        }
+    }
+}
+
+/// Write `data` at `address` to the registers found by `lookup`.
+///
+/// `write` gets the register, the written value and the mask of the written bytes, partial writes
+/// should only change the masked bits.
+pub(crate) fn write<R: Copy + PartialEq>(
+    address: u64,
+    data: &[u8],
+    lookup: impl Fn(u64) -> Option<Mapped<R>>,
+    mut write: impl FnMut(R, u32, u32),
+) {
+    for (mapped, value, mask) in touched(address, data, lookup) {
+        write(mapped.register, value, mask);
+    }
+}
+
+/// Merge the `mask`ed bits of `value` into `old`.
+pub(crate) fn merge(old: u32, value: u32, mask: u32) -> u32 {
+    (old & !mask) | (value & mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Register {
+        Byte,
+        Half,
+    }
+
+    fn lookup(address: u64) -> Option<Mapped<Register>> {
+        match address {
+            0x100 => Some(Mapped::new(Register::Byte, 0x100, 1)),
+            0x102..=0x103 => Some(Mapped::new(Register::Half, 0x102, 2)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let mut reads = Vec::new();
+        let mut data = [0xAA; 4];
+        read(0x100, &mut data, lookup, |register| {
+            reads.push(register);
+            match register {
+                Register::Byte => 0x12,
+                Register::Half => 0x3456,
+            }
+        });
+        assert_eq!([0x12, 0xAA, 0x34, 0x56], data);
+        assert_eq!(vec![Register::Byte, Register::Half], reads);
+
+        let mut data = [0];
+        read(0x103, &mut data, lookup, |_| 0x3456);
+        assert_eq!([0x56], data);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut writes = Vec::new();
+        write(0x102, &[0x12, 0x34], lookup, |register, value, mask| {
+            writes.push((register, value, mask))
+        });
+        write(0x102, &[0x56], lookup, |register, value, mask| {
+            writes.push((register, value, mask))
+        });
+        assert_eq!(
+            vec![
+                (Register::Half, 0x1234, 0xFFFF),
+                (Register::Half, 0x5600, 0xFF00)
+            ],
+            writes
+        );
+        assert_eq!(0x5634, merge(0x1234, 0x5600, 0xFF00));
+    }
+}
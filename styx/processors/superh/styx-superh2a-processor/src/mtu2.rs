@@ -0,0 +1,534 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Multi-function timer pulse unit 2 (MTU2)
+//!
+//! Five 16 bit timer channels running in normal mode, counting internal `Pφ` clocks selected by
+//! `TCR[TPSC]`. One `Pφ` clock is counted per executed instruction, external clocks and cascaded
+//! counting never count. `TMDR`, `TIOR` and the pin functions are stored but have no effect.
+//!
+//! Registers:
+//!
+//!  Channel | Base       | Layout
+//! ----------------------------------------------------------
+//!  0       | 0xFFFE4300 | `TCR`, `TMDR`, `TIORH`, `TIORL`, `TIER`, `TSR`, `TCNT`, `TGRA`-`TGRD`
+//!  1       | 0xFFFE4380 | `TCR`, `TMDR`, `TIOR`, `TIER`, `TSR`, `TCNT`, `TGRA`, `TGRB`
+//!  2       | 0xFFFE4000 | same as channel 1
+//!  3, 4    | 0xFFFE4200 | interleaved, see [`REGISTERS`]
+//!  shared  | 0xFFFE4280 | `TSTR`, `CST0`-`CST2` (bits 0-2), `CST3` (bit 6), `CST4` (bit 7)
+//!
+//! A match of `TCNT` with a `TGR` sets its `TGF` flag in `TSR` and clears the counter if it is
+//! selected by `TCR[CCLR]`, an overflow of a free running counter sets `TCFV`. A channel requests
+//! [`tgi_vector()`] and [`tciv_vector()`] while a flag and its `TIER` enable are set, flags are
+//! cleared by writing 0.
+use serde::{Deserialize, Serialize};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use tracing::trace;
+
+use crate::intc::{tciv_vector, tgi_vector, IntcHandle};
+use crate::mmio::{self, merge, Mapped};
+use crate::timer::{self, FREE_RUNNING};
+
+/// Number of channels.
+const CHANNELS: usize = 5;
+
+/// First and last byte of the MTU2 registers.
+const MTU2_START: u64 = 0xFFFE_4000;
+const MTU2_END: u64 = 0xFFFE_438F;
+
+const TSTR: u64 = 0xFFFE_4280;
+
+/// `TSR` bits that always read 1.
+const TSR_RESERVED: u8 = 0xC0;
+/// `TSR[TCFV]`, also `TIER[TCIEV]`.
+const OVERFLOW: u8 = 1 << 4;
+
+/// `TCR[CCLR]`
+const TCR_CCLR_SHIFT: u8 = 5;
+
+/// Input dividers of `TCR[TPSC]` for each channel, [None] selects an external clock.
+const PRESCALERS: [[Option<u64>; 8]; CHANNELS] = [
+    [Some(1), Some(4), Some(16), Some(64), None, None, None, None],
+    [
+        Some(1),
+        Some(4),
+        Some(16),
+        Some(64),
+        None,
+        None,
+        Some(256),
+        None,
+    ],
+    [
+        Some(1),
+        Some(4),
+        Some(16),
+        Some(64),
+        None,
+        None,
+        None,
+        Some(1024),
+    ],
+    [
+        Some(1),
+        Some(4),
+        Some(16),
+        Some(64),
+        Some(256),
+        Some(1024),
+        None,
+        None,
+    ],
+    [
+        Some(1),
+        Some(4),
+        Some(16),
+        Some(64),
+        Some(256),
+        Some(1024),
+        None,
+        None,
+    ],
+];
+
+/// Number of `TGR` registers of each channel.
+const TGR_COUNT: [usize; CHANNELS] = [4, 2, 2, 4, 4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mtu2Register {
+    Tstr,
+    Tcr(usize),
+    Tmdr(usize),
+    Tiorh(usize),
+    Tiorl(usize),
+    Tier(usize),
+    Tsr(usize),
+    Tcnt(usize),
+    Tgr(usize, usize),
+}
+
+/// `(address, register, size)` of every MTU2 register.
+const REGISTERS: [(u64, Mtu2Register, u64); 50] = [
+    (TSTR, Mtu2Register::Tstr, 1),
+    // channel 0
+    (0xFFFE_4300, Mtu2Register::Tcr(0), 1),
+    (0xFFFE_4301, Mtu2Register::Tmdr(0), 1),
+    (0xFFFE_4302, Mtu2Register::Tiorh(0), 1),
+    (0xFFFE_4303, Mtu2Register::Tiorl(0), 1),
+    (0xFFFE_4304, Mtu2Register::Tier(0), 1),
+    (0xFFFE_4305, Mtu2Register::Tsr(0), 1),
+    (0xFFFE_4306, Mtu2Register::Tcnt(0), 2),
+    (0xFFFE_4308, Mtu2Register::Tgr(0, 0), 2),
+    (0xFFFE_430A, Mtu2Register::Tgr(0, 1), 2),
+    (0xFFFE_430C, Mtu2Register::Tgr(0, 2), 2),
+    (0xFFFE_430E, Mtu2Register::Tgr(0, 3), 2),
+    // channel 1
+    (0xFFFE_4380, Mtu2Register::Tcr(1), 1),
+    (0xFFFE_4381, Mtu2Register::Tmdr(1), 1),
+    (0xFFFE_4382, Mtu2Register::Tiorh(1), 1),
+    (0xFFFE_4384, Mtu2Register::Tier(1), 1),
+    (0xFFFE_4385, Mtu2Register::Tsr(1), 1),
+    (0xFFFE_4386, Mtu2Register::Tcnt(1), 2),
+    (0xFFFE_4388, Mtu2Register::Tgr(1, 0), 2),
+    (0xFFFE_438A, Mtu2Register::Tgr(1, 1), 2),
+    // channel 2
+    (0xFFFE_4000, Mtu2Register::Tcr(2), 1),
+    (0xFFFE_4001, Mtu2Register::Tmdr(2), 1),
+    (0xFFFE_4002, Mtu2Register::Tiorh(2), 1),
+    (0xFFFE_4004, Mtu2Register::Tier(2), 1),
+    (0xFFFE_4005, Mtu2Register::Tsr(2), 1),
+    (0xFFFE_4006, Mtu2Register::Tcnt(2), 2),
+    (0xFFFE_4008, Mtu2Register::Tgr(2, 0), 2),
+    (0xFFFE_400A, Mtu2Register::Tgr(2, 1), 2),
+    // channels 3 and 4
+    (0xFFFE_4200, Mtu2Register::Tcr(3), 1),
+    (0xFFFE_4201, Mtu2Register::Tcr(4), 1),
+    (0xFFFE_4202, Mtu2Register::Tmdr(3), 1),
+    (0xFFFE_4203, Mtu2Register::Tmdr(4), 1),
+    (0xFFFE_4204, Mtu2Register::Tiorh(3), 1),
+    (0xFFFE_4205, Mtu2Register::Tiorl(3), 1),
+    (0xFFFE_4206, Mtu2Register::Tiorh(4), 1),
+    (0xFFFE_4207, Mtu2Register::Tiorl(4), 1),
+    (0xFFFE_4208, Mtu2Register::Tier(3), 1),
+    (0xFFFE_4209, Mtu2Register::Tier(4), 1),
+    (0xFFFE_4210, Mtu2Register::Tcnt(3), 2),
+    (0xFFFE_4212, Mtu2Register::Tcnt(4), 2),
+    (0xFFFE_4218, Mtu2Register::Tgr(3, 0), 2),
+    (0xFFFE_421A, Mtu2Register::Tgr(3, 1), 2),
+    (0xFFFE_421C, Mtu2Register::Tgr(4, 0), 2),
+    (0xFFFE_421E, Mtu2Register::Tgr(4, 1), 2),
+    (0xFFFE_4224, Mtu2Register::Tgr(3, 2), 2),
+    (0xFFFE_4226, Mtu2Register::Tgr(3, 3), 2),
+    (0xFFFE_4228, Mtu2Register::Tgr(4, 2), 2),
+    (0xFFFE_422A, Mtu2Register::Tgr(4, 3), 2),
+    (0xFFFE_422C, Mtu2Register::Tsr(3), 1),
+    (0xFFFE_422D, Mtu2Register::Tsr(4), 1),
+];
+
+fn lookup(address: u64) -> Option<Mapped<Mtu2Register>> {
+    REGISTERS
+        .iter()
+        .copied()
+        .find(|(start, _, size)| (*start..*start + size).contains(&address))
+        .map(|(start, register, size)| Mapped::new(register, start, size))
+}
+
+/// `TSTR[CSTn]`
+fn counter_start_bit(channel: usize) -> u8 {
+    match channel {
+        0..=2 => 1 << channel,
+        _ => 1 << (channel + 3),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Mtu2Channel {
+    tcr: u8,
+    tmdr: u8,
+    tiorh: u8,
+    tiorl: u8,
+    tier: u8,
+    tsr: u8,
+    tcnt: u16,
+    tgr: [u16; 4],
+    /// `Pφ` clocks not yet counted because of the prescaler.
+    remainder: u64,
+}
+
+impl Default for Mtu2Channel {
+    fn default() -> Self {
+        Self {
+            tcr: 0,
+            tmdr: 0,
+            tiorh: 0,
+            tiorl: 0,
+            tier: 0,
+            tsr: TSR_RESERVED,
+            tcnt: 0,
+            tgr: [0xFFFF; 4],
+            remainder: 0,
+        }
+    }
+}
+
+impl Mtu2Channel {
+    /// `TGR` cleared on, selected by `TCR[CCLR]`.
+    fn clearing_tgr(&self, channel: usize) -> Option<usize> {
+        let tgr = match self.tcr >> TCR_CCLR_SHIFT {
+            0b001 => 0,
+            0b010 => 1,
+            0b101 => 2,
+            0b110 => 3,
+            // disabled or synchronous clearing
+            _ => return None,
+        };
+        (tgr < TGR_COUNT[channel]).then_some(tgr)
+    }
+
+    /// Count `clocks` `Pφ` clocks.
+    fn advance(&mut self, channel: usize, clocks: u64) {
+        let Some(divider) = PRESCALERS[channel][(self.tcr & 0b111) as usize] else {
+            return;
+        };
+        let clocks = self.remainder + clocks;
+        self.remainder = clocks % divider;
+        let counts = clocks / divider;
+
+        let clearing = self.clearing_tgr(channel);
+        let period = clearing.map_or(FREE_RUNNING, |tgr| self.tgr[tgr] as u32 + 1);
+        for (tgr, value) in self.tgr.iter().take(TGR_COUNT[channel]).enumerate() {
+            if timer::reaches(self.tcnt, counts, *value, period) {
+                trace!("mtu2 channel {channel} TGR{tgr} compare match");
+                self.tsr |= 1 << tgr;
+            }
+        }
+        if clearing.is_none() && timer::wraps(self.tcnt, counts, period) {
+            trace!("mtu2 channel {channel} overflow");
+            self.tsr |= OVERFLOW;
+        }
+        self.tcnt = timer::advance(self.tcnt, counts, period);
+    }
+
+    /// `Pφ` clocks until the next compare match or overflow with its interrupt enabled.
+    fn next_request(&self, channel: usize) -> Option<u64> {
+        let divider = PRESCALERS[channel][(self.tcr & 0b111) as usize]?;
+        let clearing = self.clearing_tgr(channel);
+        let period = clearing.map_or(FREE_RUNNING, |tgr| self.tgr[tgr] as u32 + 1);
+
+        let matches = self
+            .tgr
+            .iter()
+            .take(TGR_COUNT[channel])
+            .enumerate()
+            .filter(|(tgr, _)| self.tier & (1 << tgr) != 0)
+            .filter_map(|(_, value)| timer::counts_until(self.tcnt, *value, period));
+        let overflow = (clearing.is_none() && self.tier & OVERFLOW != 0)
+            .then(|| timer::counts_until_wrap(self.tcnt, period));
+        let counts = matches.chain(overflow).min()?;
+        Some(timer::clocks_until(counts, divider, self.remainder))
+    }
+
+    /// Requested interrupts, the flags with their enable set.
+    fn requests(&self) -> u8 {
+        self.tsr & self.tier & 0x1F
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Mtu2Registers {
+    tstr: u8,
+    channels: [Mtu2Channel; CHANNELS],
+}
+
+pub struct Mtu2 {
+    registers: Mtu2Registers,
+    intc: IntcHandle,
+}
+
+impl Mtu2 {
+    pub(crate) fn new(intc: IntcHandle) -> Self {
+        Self {
+            registers: Default::default(),
+            intc,
+        }
+    }
+
+    /// Count `clocks` `Pφ` clocks on the started channels.
+    fn advance(&mut self, clocks: u64) {
+        for (n, channel) in self.registers.channels.iter_mut().enumerate() {
+            if self.registers.tstr & counter_start_bit(n) != 0 {
+                channel.advance(n, clocks);
+            }
+        }
+        self.update_requests();
+    }
+
+    fn update_requests(&self) {
+        for (n, channel) in self.registers.channels.iter().enumerate() {
+            let requests = channel.requests();
+            for tgr in 0..TGR_COUNT[n] {
+                self.intc
+                    .set_request(tgi_vector(n, tgr), requests & (1 << tgr) != 0);
+            }
+            self.intc
+                .set_request(tciv_vector(n), requests & OVERFLOW != 0);
+        }
+    }
+
+    fn read(&self, register: Mtu2Register) -> u32 {
+        let channels = &self.registers.channels;
+        match register {
+            Mtu2Register::Tstr => self.registers.tstr as u32,
+            Mtu2Register::Tcr(n) => channels[n].tcr as u32,
+            Mtu2Register::Tmdr(n) => channels[n].tmdr as u32,
+            Mtu2Register::Tiorh(n) => channels[n].tiorh as u32,
+            Mtu2Register::Tiorl(n) => channels[n].tiorl as u32,
+            Mtu2Register::Tier(n) => channels[n].tier as u32,
+            Mtu2Register::Tsr(n) => channels[n].tsr as u32,
+            Mtu2Register::Tcnt(n) => channels[n].tcnt as u32,
+            Mtu2Register::Tgr(n, tgr) => channels[n].tgr[tgr] as u32,
+        }
+    }
+
+    fn write(&mut self, register: Mtu2Register, value: u32, mask: u32) {
+        let merge8 = |old: u8| merge(old as u32, value, mask) as u8;
+        let merge16 = |old: u16| merge(old as u32, value, mask) as u16;
+        let registers = &mut self.registers;
+        match register {
+            Mtu2Register::Tstr => registers.tstr = merge8(registers.tstr) & 0xC7,
+            Mtu2Register::Tcr(n) => registers.channels[n].tcr = merge8(registers.channels[n].tcr),
+            Mtu2Register::Tmdr(n) => {
+                registers.channels[n].tmdr = merge8(registers.channels[n].tmdr)
+            }
+            Mtu2Register::Tiorh(n) => {
+                registers.channels[n].tiorh = merge8(registers.channels[n].tiorh)
+            }
+            Mtu2Register::Tiorl(n) => {
+                registers.channels[n].tiorl = merge8(registers.channels[n].tiorl)
+            }
+            Mtu2Register::Tier(n) => {
+                registers.channels[n].tier = merge8(registers.channels[n].tier)
+            }
+            // flags can only be cleared
+            Mtu2Register::Tsr(n) => {
+                let channel = &mut registers.channels[n];
+                channel.tsr = (channel.tsr & merge8(channel.tsr)) | TSR_RESERVED;
+            }
+            Mtu2Register::Tcnt(n) => {
+                registers.channels[n].tcnt = merge16(registers.channels[n].tcnt)
+            }
+            Mtu2Register::Tgr(n, tgr) => {
+                registers.channels[n].tgr[tgr] = merge16(registers.channels[n].tgr[tgr])
+            }
+        }
+        self.update_requests();
+    }
+}
+
+impl Peripheral for Mtu2 {
+    fn name(&self) -> &str {
+        "MTU2"
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        (0..CHANNELS)
+            .flat_map(|n| {
+                (0..TGR_COUNT[n])
+                    .map(move |tgr| tgi_vector(n, tgr))
+                    .chain([tciv_vector(n)])
+            })
+            .collect()
+    }
+
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let cpu = &mut proc.core.cpu;
+        cpu.add_hook(StyxHook::memory_read(
+            MTU2_START..=MTU2_END,
+            register_read_hook,
+        ))?;
+        cpu.add_hook(StyxHook::memory_write(
+            MTU2_START..=MTU2_END,
+            register_write_hook,
+        ))?;
+        Ok(())
+    }
+
+    fn reset(&mut self, _cpu: &mut dyn CpuBackend, _mmu: &mut Mmu) -> Result<(), UnknownError> {
+        self.registers = Default::default();
+        self.update_requests();
+        Ok(())
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _event_controller: &mut dyn EventControllerImpl,
+        delta: &Delta,
+    ) -> Result<(), UnknownError> {
+        self.advance(delta.count);
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<u64> {
+        self.registers
+            .channels
+            .iter()
+            .enumerate()
+            .filter(|(n, _)| self.registers.tstr & counter_start_bit(*n) != 0)
+            .filter_map(|(n, channel)| channel.next_request(n))
+            .min()
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&self.registers)?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        self.registers = state.get()?;
+        self.update_requests();
+        Ok(())
+    }
+}
+
+fn register_read_hook(
+    proc: CoreHandle,
+    address: u64,
+    _size: u32,
+    data: &mut [u8],
+) -> Result<(), UnknownError> {
+    let mtu2 = proc.event_controller.peripherals.get_expect::<Mtu2>()?;
+    mmio::read(address, data, lookup, |register| mtu2.read(register));
+    Ok(())
+}
+
+fn register_write_hook(
+    proc: CoreHandle,
+    address: u64,
+    _size: u32,
+    data: &[u8],
+) -> Result<(), UnknownError> {
+    let mtu2 = proc.event_controller.peripherals.get_expect::<Mtu2>()?;
+    mmio::write(address, data, lookup, |register, value, mask| {
+        mtu2.write(register, value, mask)
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: u32 = 0xFFFF;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            Some(Mapped::new(Mtu2Register::Tcnt(4), 0xFFFE_4212, 2)),
+            lookup(0xFFFE_4213)
+        );
+        assert_eq!(
+            Some(Mtu2Register::Tgr(3, 3)),
+            lookup(0xFFFE_4226).map(|m| m.register)
+        );
+        assert_eq!(
+            Some(Mtu2Register::Tsr(1)),
+            lookup(0xFFFE_4385).map(|m| m.register)
+        );
+        // TIORL only exists on channels 0, 3 and 4
+        assert_eq!(None, lookup(0xFFFE_4383));
+        assert_eq!(None, lookup(0xFFFE_420A));
+    }
+
+    #[test]
+    fn test_compare_match_clear() {
+        let intc = IntcHandle::default();
+        let mut mtu2 = Mtu2::new(intc.clone());
+        // Pφ/4, cleared by TGRA
+        mtu2.write(Mtu2Register::Tcr(3), 0x21, ALL);
+        mtu2.write(Mtu2Register::Tgr(3, 0), 99, ALL);
+        mtu2.write(Mtu2Register::Tgr(3, 1), 50, ALL);
+        mtu2.write(Mtu2Register::Tier(3), 0x11, ALL);
+        mtu2.write(Mtu2Register::Tstr, counter_start_bit(3) as u32, ALL);
+        // TGRB is not enabled
+        assert_eq!(Some(4 * 99), mtu2.next_deadline());
+
+        mtu2.advance(4 * 50);
+        assert_eq!(50, mtu2.read(Mtu2Register::Tcnt(3)));
+        assert_eq!(Some(4 * 49), mtu2.next_deadline());
+        // TGRB matched but its interrupt is disabled
+        assert_eq!(0xC2, mtu2.read(Mtu2Register::Tsr(3)));
+        assert!(!intc.is_requested(tgi_vector(3, 1)));
+
+        mtu2.advance(4 * 50);
+        assert_eq!(0, mtu2.read(Mtu2Register::Tcnt(3)));
+        assert_eq!(0xC3, mtu2.read(Mtu2Register::Tsr(3)));
+        assert!(intc.is_requested(tgi_vector(3, 0)));
+        // cleared before overflowing
+        assert!(!intc.is_requested(tciv_vector(3)));
+
+        mtu2.write(Mtu2Register::Tsr(3), 0xFE, 0xFF);
+        assert_eq!(0xC2, mtu2.read(Mtu2Register::Tsr(3)));
+        assert!(!intc.is_requested(tgi_vector(3, 0)));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let intc = IntcHandle::default();
+        let mut mtu2 = Mtu2::new(intc.clone());
+        mtu2.write(Mtu2Register::Tier(1), OVERFLOW as u32, ALL);
+        mtu2.write(Mtu2Register::Tcnt(1), 0xFFF0, ALL);
+        // external clock, never counts
+        mtu2.write(Mtu2Register::Tcr(1), 0x04, ALL);
+        mtu2.write(Mtu2Register::Tstr, counter_start_bit(1) as u32, ALL);
+        mtu2.advance(0x100);
+        assert_eq!(0xFFF0, mtu2.read(Mtu2Register::Tcnt(1)));
+        assert_eq!(None, mtu2.next_deadline());
+
+        mtu2.write(Mtu2Register::Tcr(1), 0, ALL);
+        assert_eq!(Some(0x10), mtu2.next_deadline());
+        mtu2.advance(0x20);
+        assert_eq!(0x10, mtu2.read(Mtu2Register::Tcnt(1)));
+        assert!(intc.is_requested(tciv_vector(1)));
+        assert!(!intc.is_requested(tciv_vector(0)));
+    }
+}
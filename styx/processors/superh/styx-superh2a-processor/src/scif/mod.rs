@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Serial communication interface with FIFO (SCIF)
+//!
+//! The four channels are exposed through a [UartController] so clients can talk to them over the
+//! `UartPort` gRPC service, interface ids are `"0"` to `"3"`. Channel `n` has its registers at
+//! `0xFFFE8000 + 0x800 * n` and requests [`rxi_vector()`] and [`txi_vector()`].
+//!
+//! See [registers] for the modeled register behavior.
+use std::collections::VecDeque;
+
+use styx_core::hooks::{MemoryReadHook, MemoryWriteHook};
+use styx_core::prelude::*;
+use styx_core::snapshot::ComponentState;
+use styx_peripherals::uart::{IntoUartImpl, UartController, UartImpl, UartInterface};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::intc::{rxi_vector, txi_vector, IntcHandle};
+use crate::mmio;
+
+mod registers;
+
+use registers::{ScifRegister, ScifRegisters, LAST_OFFSET};
+
+/// Number of SCIF channels.
+const CHANNELS: usize = 4;
+
+/// Base address of channel 0, the other channels follow every `0x800` bytes.
+const SCIF0_BASE: u64 = 0xFFFE_8000;
+const CHANNEL_STRIDE: u64 = 0x800;
+
+const INTERFACE_IDS: [&str; CHANNELS] = ["0", "1", "2", "3"];
+
+/// Creates the interfaces for the SCIF channels to be added to a [UartController].
+pub(crate) fn get_scifs(intc: IntcHandle) -> Vec<UartInterface> {
+    (0..CHANNELS)
+        .map(|channel| {
+            UartInterface::new(
+                INTERFACE_IDS[channel].to_owned(),
+                ScifBuilder {
+                    channel,
+                    intc: intc.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+fn base_address(channel: usize) -> u64 {
+    SCIF0_BASE + CHANNEL_STRIDE * channel as u64
+}
+
+struct ScifBuilder {
+    channel: usize,
+    intc: IntcHandle,
+}
+
+impl IntoUartImpl for ScifBuilder {
+    fn new(
+        self,
+        mosi_rx: broadcast::Receiver<u8>,
+        miso_tx: broadcast::Sender<u8>,
+        _interface_id: String,
+    ) -> Result<Box<dyn UartImpl>, UnknownError> {
+        Ok(Box::new(Scif {
+            channel: self.channel,
+            registers: ScifRegisters::default(),
+            buffer: VecDeque::new(),
+            intc: self.intc,
+            miso_stream: miso_tx,
+            mosi_stream: mosi_rx,
+        }))
+    }
+}
+
+/// A SCIF channel, connects the [ScifRegisters] to the uart streams and the INTC.
+pub(crate) struct Scif {
+    channel: usize,
+    registers: ScifRegisters,
+    /// uart bytes that have come in from master but not received yet.
+    buffer: VecDeque<u8>,
+    intc: IntcHandle,
+    miso_stream: broadcast::Sender<u8>,
+    mosi_stream: broadcast::Receiver<u8>,
+}
+
+impl Scif {
+    /// checks uart mosi for bytes and gives to buffer
+    fn grab_bytes(&mut self) {
+        loop {
+            match self.mosi_stream.try_recv() {
+                Ok(data) => self.buffer.push_back(data),
+                Err(broadcast::error::TryRecvError::Empty) => break,
+                Err(broadcast::error::TryRecvError::Closed) => {
+                    warn!("uart mosi stream closed??");
+                    break;
+                }
+                Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                    warn!("uart mosi stream lagged {n} items");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn guest_transmit_data(&mut self, data: u8) {
+        debug!("scif{} transmit data {data:#x}", self.channel);
+        // an error means no one is listening, that's fine
+        let _ = self.miso_stream.send(data);
+    }
+
+    /// Move waiting bytes into the receive FIFO while it has room.
+    fn fill_fifo(&mut self) {
+        while self.registers.receive_ready() {
+            let Some(data) = self.buffer.pop_front() else {
+                break;
+            };
+            self.registers.receive(data);
+        }
+    }
+
+    fn update_requests(&self) {
+        self.intc
+            .set_request(rxi_vector(self.channel), self.registers.receive_request());
+        self.intc
+            .set_request(txi_vector(self.channel), self.registers.transmit_request());
+    }
+
+    fn read_register(&mut self, register: ScifRegister) -> u32 {
+        let value = self.registers.read(register);
+        self.update_requests();
+        value
+    }
+
+    fn write_register(&mut self, register: ScifRegister, value: u32, mask: u32) {
+        if let Some(data) = self.registers.write(register, value, mask) {
+            self.guest_transmit_data(data);
+        }
+        self.update_requests();
+    }
+}
+
+impl UartImpl for Scif {
+    fn init(&mut self, proc: &mut BuildingProcessor) -> Result<(), UnknownError> {
+        let base = base_address(self.channel);
+        let end = base + LAST_OFFSET;
+        let hook = || ScifHook {
+            interface_id: INTERFACE_IDS[self.channel],
+            base,
+        };
+        proc.core.cpu.mem_read_hook(base, end, Box::new(hook()))?;
+        proc.core.cpu.mem_write_hook(base, end, Box::new(hook()))?;
+        Ok(())
+    }
+
+    fn irqs(&self) -> Vec<ExceptionNumber> {
+        vec![rxi_vector(self.channel), txi_vector(self.channel)]
+    }
+
+    fn tick(
+        &mut self,
+        _cpu: &mut dyn CpuBackend,
+        _mmu: &mut Mmu,
+        _event_controller: &mut dyn EventControllerImpl,
+    ) -> Result<(), UnknownError> {
+        self.grab_bytes();
+        self.fill_fifo();
+        self.update_requests();
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<ComponentState, UnknownError> {
+        Ok(ComponentState::new(&(&self.registers, &self.buffer))?)
+    }
+
+    fn restore_state(&mut self, state: &ComponentState) -> Result<(), UnknownError> {
+        (self.registers, self.buffer) = state.get()?;
+        self.update_requests();
+        Ok(())
+    }
+}
+
+/// Register hook for one SCIF channel, registers are accessed through the [UartController].
+struct ScifHook {
+    interface_id: &'static str,
+    base: u64,
+}
+
+impl ScifHook {
+    fn scif<'a>(&self, proc: &'a mut CoreHandle) -> Result<&'a mut Scif, UnknownError> {
+        proc.event_controller
+            .peripherals
+            .get_expect::<UartController>()?
+            .try_get::<Scif>(self.interface_id)
+    }
+}
+
+impl MemoryReadHook for ScifHook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        _size: u32,
+        data: &mut [u8],
+    ) -> Result<(), UnknownError> {
+        let base = self.base;
+        let scif = self.scif(&mut proc)?;
+        mmio::read(
+            address,
+            data,
+            |address| registers::lookup(base, address),
+            |register| scif.read_register(register),
+        );
+        Ok(())
+    }
+}
+
+impl MemoryWriteHook for ScifHook {
+    fn call(
+        &mut self,
+        mut proc: CoreHandle,
+        address: u64,
+        _size: u32,
+        data: &[u8],
+    ) -> Result<(), UnknownError> {
+        let base = self.base;
+        let scif = self.scif(&mut proc)?;
+        mmio::write(
+            address,
+            data,
+            |address| registers::lookup(base, address),
+            |register, value, mask| scif.write_register(register, value, mask),
+        );
+        Ok(())
+    }
+}
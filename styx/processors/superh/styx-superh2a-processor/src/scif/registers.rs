@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! SCIF register state, an asynchronous serial channel with a 16 byte receive FIFO.
+//!
+//! Transmission is instant, so the transmit FIFO is always empty and `SCFSR[TEND]` and
+//! `SCFSR[TDFE]` are always set. `SCFSR[RDF]` and `SCFSR[DR]` follow the receive FIFO instead of
+//! being latched, `RDF` is set while the FIFO holds at least the `SCFCR[RTRG]` trigger count and
+//! `DR` while it holds fewer bytes. Line errors never happen.
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+use crate::mmio::{merge, Mapped};
+
+/// Size of the receive FIFO.
+const FIFO_SIZE: usize = 16;
+
+/// Offset of the last byte of the channel registers.
+pub(super) const LAST_OFFSET: u64 = 0x29;
+
+pub(super) const SCSCR_TIE: u16 = 0x80;
+pub(super) const SCSCR_RIE: u16 = 0x40;
+pub(super) const SCSCR_TE: u16 = 0x20;
+pub(super) const SCSCR_RE: u16 = 0x10;
+const SCSCR_REIE: u16 = 0x08;
+const SCSCR_CKE: u16 = 0x03;
+
+const SCFSR_TEND: u16 = 0x40;
+const SCFSR_TDFE: u16 = 0x20;
+const SCFSR_RDF: u16 = 0x02;
+const SCFSR_DR: u16 = 0x01;
+
+/// `SCFCR[RTRG]`
+const SCFCR_RTRG_SHIFT: u16 = 6;
+const SCFCR_RFRST: u16 = 0x02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ScifRegister {
+    Scsmr,
+    Scbrr,
+    Scscr,
+    Scftdr,
+    Scfsr,
+    Scfrdr,
+    Scfcr,
+    Scfdr,
+    Scsptr,
+    Sclsr,
+    Scemr,
+}
+
+/// SCIF register containing `address`, for the channel at `base`.
+pub(super) fn lookup(base: u64, address: u64) -> Option<Mapped<ScifRegister>> {
+    let offset = address.checked_sub(base)?;
+    let (register, size) = match offset & !1 {
+        0x00 => (ScifRegister::Scsmr, 2),
+        0x08 => (ScifRegister::Scscr, 2),
+        0x10 => (ScifRegister::Scfsr, 2),
+        0x18 => (ScifRegister::Scfcr, 2),
+        0x1C => (ScifRegister::Scfdr, 2),
+        0x20 => (ScifRegister::Scsptr, 2),
+        0x24 => (ScifRegister::Sclsr, 2),
+        0x28 => (ScifRegister::Scemr, 2),
+        // 8 bit registers
+        _ => match offset {
+            0x04 => (ScifRegister::Scbrr, 1),
+            0x0C => (ScifRegister::Scftdr, 1),
+            0x14 => (ScifRegister::Scfrdr, 1),
+            _ => return None,
+        },
+    };
+    Some(Mapped::new(register, address & !(size - 1), size))
+}
+
+/// State of the SCIF registers.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ScifRegisters {
+    scsmr: u16,
+    scbrr: u8,
+    scscr: u16,
+    scfcr: u16,
+    scsptr: u16,
+    sclsr: u16,
+    scemr: u16,
+    /// Receive FIFO, read through `SCFRDR`.
+    rx: VecDeque<u8>,
+}
+
+impl Default for ScifRegisters {
+    fn default() -> Self {
+        Self {
+            scsmr: 0,
+            scbrr: 0xFF,
+            scscr: 0,
+            scfcr: 0,
+            scsptr: 0,
+            sclsr: 0,
+            scemr: 0,
+            rx: VecDeque::new(),
+        }
+    }
+}
+
+impl ScifRegisters {
+    fn enabled(&self, bit: u16) -> bool {
+        self.scscr & bit != 0
+    }
+
+    /// Receiving is enabled and the receive FIFO has room and is not held in reset.
+    pub(super) fn receive_ready(&self) -> bool {
+        self.enabled(SCSCR_RE) && self.scfcr & SCFCR_RFRST == 0 && self.rx.len() < FIFO_SIZE
+    }
+
+    /// Place a received byte in the receive FIFO.
+    pub(super) fn receive(&mut self, data: u8) {
+        trace!("scif received {data:#x}");
+        self.rx.push_back(data);
+    }
+
+    /// `SCFCR[RTRG]`, receive FIFO count setting `SCFSR[RDF]`.
+    fn receive_trigger(&self) -> usize {
+        [1, 4, 8, 14][((self.scfcr >> SCFCR_RTRG_SHIFT) & 0b11) as usize]
+    }
+
+    fn status(&self) -> u16 {
+        let count = self.rx.len();
+        let receive = if count >= self.receive_trigger() {
+            SCFSR_RDF
+        } else if count > 0 {
+            SCFSR_DR
+        } else {
+            0
+        };
+        SCFSR_TEND | SCFSR_TDFE | receive
+    }
+
+    /// Receive interrupt request, `SCFSR[RDF]` or `SCFSR[DR]` with `SCSCR[RIE]` set.
+    pub(super) fn receive_request(&self) -> bool {
+        self.enabled(SCSCR_RIE) && self.status() & (SCFSR_RDF | SCFSR_DR) != 0
+    }
+
+    /// Transmit interrupt request, the transmit FIFO is always empty so `SCSCR[TIE]` with
+    /// transmission enabled.
+    pub(super) fn transmit_request(&self) -> bool {
+        self.enabled(SCSCR_TIE) && self.enabled(SCSCR_TE)
+    }
+
+    /// Read `register`, reading `SCFRDR` pops the receive FIFO.
+    pub(super) fn read(&mut self, register: ScifRegister) -> u32 {
+        let value = match register {
+            ScifRegister::Scsmr => self.scsmr,
+            ScifRegister::Scbrr => self.scbrr as u16,
+            ScifRegister::Scscr => self.scscr,
+            ScifRegister::Scftdr => 0,
+            ScifRegister::Scfsr => self.status(),
+            ScifRegister::Scfrdr => self.rx.pop_front().unwrap_or_default() as u16,
+            ScifRegister::Scfcr => self.scfcr,
+            // the transmit count in bits 12-8 is always 0
+            ScifRegister::Scfdr => self.rx.len() as u16,
+            ScifRegister::Scsptr => self.scsptr,
+            ScifRegister::Sclsr => self.sclsr,
+            ScifRegister::Scemr => self.scemr,
+        };
+        value as u32
+    }
+
+    /// Write the `mask`ed bits of `value` to `register`.
+    ///
+    /// Returns the byte to transmit if `SCFTDR` was written with transmission enabled.
+    pub(super) fn write(&mut self, register: ScifRegister, value: u32, mask: u32) -> Option<u8> {
+        let merge = |old: u16| merge(old as u32, value, mask) as u16;
+        match register {
+            ScifRegister::Scsmr => self.scsmr = merge(self.scsmr),
+            ScifRegister::Scbrr => self.scbrr = value as u8,
+            ScifRegister::Scscr => {
+                self.scscr = merge(self.scscr)
+                    & (SCSCR_TIE | SCSCR_RIE | SCSCR_TE | SCSCR_RE | SCSCR_REIE | SCSCR_CKE)
+            }
+            ScifRegister::Scftdr => {
+                if !self.enabled(SCSCR_TE) {
+                    debug!("scif write to SCFTDR with TE clear: {value:#x}");
+                    return None;
+                }
+                return Some(value as u8);
+            }
+            ScifRegister::Scfcr => {
+                self.scfcr = merge(self.scfcr) & 0x07FF;
+                // TFRST has nothing to do, the transmit FIFO is always empty
+                if self.scfcr & SCFCR_RFRST != 0 {
+                    self.rx.clear();
+                }
+            }
+            ScifRegister::Scsptr => self.scsptr = merge(self.scsptr),
+            ScifRegister::Sclsr => self.sclsr &= (value | !mask) as u16,
+            ScifRegister::Scemr => self.scemr = merge(self.scemr),
+            // flags are derived from the FIFOs, SCFDR is read only
+            ScifRegister::Scfsr | ScifRegister::Scfrdr | ScifRegister::Scfdr => {
+                trace!("ignored scif write to {register:?}: {value:#x}")
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: u64 = 0xFFFE_8000;
+    const ALL: u32 = 0xFFFF;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            Some(Mapped::new(ScifRegister::Scfsr, BASE + 0x10, 2)),
+            lookup(BASE, BASE + 0x11)
+        );
+        assert_eq!(
+            Some(Mapped::new(ScifRegister::Scfrdr, BASE + 0x14, 1)),
+            lookup(BASE, BASE + 0x14)
+        );
+        assert_eq!(None, lookup(BASE, BASE + 0x15));
+        assert_eq!(None, lookup(BASE, BASE + 0x2A));
+    }
+
+    #[test]
+    fn test_receive_fifo() {
+        let mut scif = ScifRegisters::default();
+        assert!(!scif.receive_ready());
+        scif.write(ScifRegister::Scscr, (SCSCR_RE | SCSCR_RIE) as u32, ALL);
+        // trigger at 4 bytes
+        scif.write(ScifRegister::Scfcr, 1 << SCFCR_RTRG_SHIFT, ALL);
+        assert_eq!(
+            (SCFSR_TEND | SCFSR_TDFE) as u32,
+            scif.read(ScifRegister::Scfsr)
+        );
+
+        for data in b"abc" {
+            scif.receive(*data);
+        }
+        assert_eq!(SCFSR_DR as u32, scif.read(ScifRegister::Scfsr) & 0x3);
+        assert!(scif.receive_request());
+        scif.receive(b'd');
+        assert_eq!(SCFSR_RDF as u32, scif.read(ScifRegister::Scfsr) & 0x3);
+        assert_eq!(4, scif.read(ScifRegister::Scfdr));
+
+        assert_eq!(b'a' as u32, scif.read(ScifRegister::Scfrdr));
+        assert_eq!(3, scif.read(ScifRegister::Scfdr));
+
+        scif.write(ScifRegister::Scfcr, SCFCR_RFRST as u32, ALL);
+        assert_eq!(0, scif.read(ScifRegister::Scfdr));
+        assert!(!scif.receive_request());
+        assert!(!scif.receive_ready());
+        scif.write(ScifRegister::Scfcr, 0, ALL);
+
+        for data in 0..16 {
+            scif.receive(data);
+        }
+        assert!(!scif.receive_ready());
+    }
+
+    #[test]
+    fn test_transmit() {
+        let mut scif = ScifRegisters::default();
+        assert_eq!(None, scif.write(ScifRegister::Scftdr, b'a' as u32, 0xFF));
+        scif.write(ScifRegister::Scscr, SCSCR_TIE as u32, ALL);
+        assert!(!scif.transmit_request());
+
+        scif.write(ScifRegister::Scscr, (SCSCR_TE | SCSCR_TIE) as u32, ALL);
+        assert!(scif.transmit_request());
+        assert_eq!(
+            Some(b'b'),
+            scif.write(ScifRegister::Scftdr, b'b' as u32, 0xFF)
+        );
+    }
+}
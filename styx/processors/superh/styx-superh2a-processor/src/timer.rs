@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: BSD-2-Clause
+//! Counter arithmetic shared by the [CMT](crate::cmt) and [MTU2](crate::mtu2).
+//!
+//! Counters are advanced a whole tick at a time instead of one clock at a time. A counter cleared
+//! by a compare match counts `0..=compare`, so its period is `compare + 1`, a free running 16 bit
+//! counter has a period of `0x10000`.
+
+/// Period of a free running 16 bit counter.
+pub(crate) const FREE_RUNNING: u32 = 0x10000;
+
+/// Counter value after advancing `counter` by `counts` with `period`.
+pub(crate) fn advance(counter: u16, counts: u64, period: u32) -> u16 {
+    ((counter as u64 + counts) % period as u64) as u16
+}
+
+/// Counts until `counter` with `period` is next equal to `value`.
+///
+/// Values outside of the period are never reached.
+pub(crate) fn counts_until(counter: u16, value: u16, period: u32) -> Option<u64> {
+    if value as u32 >= period {
+        return None;
+    }
+    let period = period as u64;
+    let distance = (value as u64 + period - counter as u64 % period) % period;
+    Some(if distance == 0 { period } else { distance })
+}
+
+/// Counts until `counter` with `period` wraps around.
+pub(crate) fn counts_until_wrap(counter: u16, period: u32) -> u64 {
+    period as u64 - counter as u64 % period as u64
+}
+
+/// `Pφ` clocks until `counts` counts with `divider`, `remainder` clocks into the next count.
+pub(crate) fn clocks_until(counts: u64, divider: u64, remainder: u64) -> u64 {
+    counts * divider - remainder
+}
+
+/// Does advancing `counter` by `counts` with `period` reach `value`?
+pub(crate) fn reaches(counter: u16, counts: u64, value: u16, period: u32) -> bool {
+    counts_until(counter, value, period).is_some_and(|distance| counts >= distance)
+}
+
+/// Does advancing `counter` by `counts` with `period` wrap around?
+pub(crate) fn wraps(counter: u16, counts: u64, period: u32) -> bool {
+    counts >= counts_until_wrap(counter, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_match_clear() {
+        // counts 0..=9
+        assert_eq!(9, advance(5, 4, 10));
+        assert!(reaches(5, 4, 9, 10));
+        assert!(!reaches(5, 3, 9, 10));
+        assert!(!wraps(5, 4, 10));
+
+        assert_eq!(0, advance(5, 5, 10));
+        assert!(wraps(5, 5, 10));
+        // reaching the same value again takes a whole period
+        assert!(!reaches(9, 9, 9, 10));
+        assert!(reaches(9, 10, 9, 10));
+        // never reached when cleared earlier
+        assert!(!reaches(0, 100, 20, 10));
+    }
+
+    #[test]
+    fn test_free_running() {
+        assert_eq!(0x0010, advance(0xFFF0, 0x20, FREE_RUNNING));
+        assert!(wraps(0xFFF0, 0x20, FREE_RUNNING));
+        assert!(reaches(0xFFF0, 0x20, 0x0005, FREE_RUNNING));
+        assert!(!reaches(0xFFF0, 0x20, 0x0020, FREE_RUNNING));
+    }
+
+    #[test]
+    fn test_counts_until() {
+        assert_eq!(Some(4), counts_until(5, 9, 10));
+        assert_eq!(Some(10), counts_until(9, 9, 10));
+        assert_eq!(None, counts_until(0, 20, 10));
+        assert_eq!(5, counts_until_wrap(5, 10));
+        assert_eq!(0x10, counts_until_wrap(0xFFF0, FREE_RUNNING));
+        assert_eq!(37, clocks_until(5, 8, 3));
+    }
+}
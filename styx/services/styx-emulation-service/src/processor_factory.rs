@@ -15,7 +15,9 @@ use styx_processors::arm::cyclonev::CycloneVBuilder;
 use styx_processors::arm::kinetis21::Kinetis21Builder;
 use styx_processors::arm::stm32f107::Stm32f107Builder;
 use styx_processors::bfin::blackfin::BlackfinBuilder;
+use styx_processors::mips::mips32::Mips32Builder;
 use styx_processors::ppc::powerquicci::Mpc8xxBuilder;
+use styx_processors::superh::superh2a::SuperH2aBuilder;
 
 /// Fallback peripheral IPC port to use when not set with the `Processor` builder.
 ///
@@ -172,6 +174,30 @@ impl ProcessorFactory {
 
                 Ok(proc)
             }
+
+            Target::Mips32 => {
+                let proc = ProcessorBuilder::default()
+                    .with_builder(Mips32Builder::default())
+                    .add_plugin(trace_plugin)
+                    .with_executor(executor)
+                    .with_target_program(firmware_path.to_string())
+                    .with_ipc_port(ipc_port)
+                    .build()?;
+
+                Ok(proc)
+            }
+
+            Target::SuperH2a => {
+                let proc = ProcessorBuilder::default()
+                    .with_builder(SuperH2aBuilder)
+                    .add_plugin(trace_plugin)
+                    .with_executor(executor)
+                    .with_target_program(firmware_path.to_string())
+                    .with_ipc_port(ipc_port)
+                    .build()?;
+
+                Ok(proc)
+            }
         }
     }
 }
@@ -7,7 +7,9 @@ use styx_core::cpu::arch::arm::gdb_targets::{
     ArmCoreDescription, ArmMProfileDescription, Armv7emDescription,
 };
 use styx_core::cpu::arch::blackfin::gdb_targets::BlackfinDescription;
+use styx_core::cpu::arch::mips32::gdb_targets::Mips32CpuTargetDescription;
 use styx_core::cpu::arch::ppc32::gdb_targets::Mpc8xxTargetDescription;
+use styx_core::cpu::arch::superh::gdb_targets::Sh2ADescription;
 use styx_core::executor::DefaultExecutor;
 use styx_core::grpc::args::Target;
 use styx_core::prelude::Forever;
@@ -64,6 +66,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 &args,
                 GdbExecutor::<BlackfinDescription>::new(params)?,
             )?,
+            Target::Mips32 => ProcessorFactory::create_processor_no_svc(
+                &args,
+                GdbExecutor::<Mips32CpuTargetDescription>::new(params)?,
+            )?,
+            Target::SuperH2a => ProcessorFactory::create_processor_no_svc(
+                &args,
+                GdbExecutor::<Sh2ADescription>::new(params)?,
+            )?,
         }
     } else {
         ProcessorFactory::create_processor_no_svc(&args, DefaultExecutor)?